@@ -48,8 +48,8 @@ fn example_basic_inlining() -> Result<(), Box<dyn std::error::Error>> {
     // Main code: 5 double double 1 +
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("double".to_string()),
-        Instruction::Call("double".to_string()),
+        Instruction::call("double"),
+        Instruction::call("double"),
         Instruction::Literal(1),
         Instruction::Add,
     ];
@@ -87,13 +87,13 @@ fn example_multi_level_inlining() -> Result<(), Box<dyn std::error::Error>> {
     // : level2 level3 ;  (1 instruction, expands to 1)
     let level2 = WordDef::new(
         "level2".to_string(),
-        vec![Instruction::Call("level3".to_string())],
+        vec![Instruction::call("level3")],
     );
 
     // : level1 level2 ;  (1 instruction, expands to 1)
     let level1 = WordDef::new(
         "level1".to_string(),
-        vec![Instruction::Call("level2".to_string())],
+        vec![Instruction::call("level2")],
     );
 
     ir.add_word(level3);
@@ -103,7 +103,7 @@ fn example_multi_level_inlining() -> Result<(), Box<dyn std::error::Error>> {
     // Main: 5 level1
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("level1".to_string()),
+        Instruction::call("level1"),
     ];
 
     println!("Original IR: 1 main call, 3 word definitions");
@@ -135,14 +135,14 @@ fn example_cycle_detection() -> Result<(), Box<dyn std::error::Error>> {
             Instruction::Dup,
             Instruction::Literal(1),
             Instruction::Gt,
-            Instruction::Call("factorial".to_string()), // Recursive call
+            Instruction::call("factorial"), // Recursive call
         ],
     );
 
     ir.add_word(factorial);
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("factorial".to_string()),
+        Instruction::call("factorial"),
     ];
 
     // Build call graph to detect cycles
@@ -174,8 +174,8 @@ fn example_statistics() -> Result<(), Box<dyn std::error::Error>> {
     let quad = WordDef::new(
         "quad".to_string(),
         vec![
-            Instruction::Call("mul2".to_string()),
-            Instruction::Call("mul2".to_string()),
+            Instruction::call("mul2"),
+            Instruction::call("mul2"),
         ],
     );
 
@@ -185,9 +185,9 @@ fn example_statistics() -> Result<(), Box<dyn std::error::Error>> {
 
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("quad".to_string()),
-        Instruction::Call("add1".to_string()),
-        Instruction::Call("add1".to_string()),
+        Instruction::call("quad"),
+        Instruction::call("add1"),
+        Instruction::call("add1"),
     ];
 
     let original_size = ir.instruction_count();
@@ -230,9 +230,9 @@ fn example_optimization_levels() -> Result<(), Box<dyn std::error::Error>> {
     // Call all three
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("tiny".to_string()),
-        Instruction::Call("small".to_string()),
-        Instruction::Call("medium".to_string()),
+        Instruction::call("tiny"),
+        Instruction::call("small"),
+        Instruction::call("medium"),
     ];
 
     println!("Original main calls: 3");
@@ -297,7 +297,7 @@ fn pattern_analyze_call_graph() -> Result<(), Box<dyn std::error::Error>> {
 
     // Build your IR...
     let a = WordDef::new("a".to_string(), vec![Instruction::Dup]);
-    let b = WordDef::new("b".to_string(), vec![Instruction::Call("a".to_string())]);
+    let b = WordDef::new("b".to_string(), vec![Instruction::call("a")]);
     ir.add_word(a);
     ir.add_word(b);
 
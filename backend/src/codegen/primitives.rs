@@ -34,6 +34,8 @@ impl<'ctx> PrimitiveCodegen<'ctx> {
             BinaryOperator::Mul => self.gen_mul(builder, lhs, rhs),
             BinaryOperator::Div => self.gen_div(builder, lhs, rhs),
             BinaryOperator::Mod => self.gen_mod(builder, lhs, rhs),
+            BinaryOperator::Min => self.gen_min_max(builder, IntPredicate::SLT, lhs, rhs),
+            BinaryOperator::Max => self.gen_min_max(builder, IntPredicate::SGT, lhs, rhs),
 
             // Comparison operations
             BinaryOperator::Lt => self.gen_lt(builder, lhs, rhs),
@@ -42,10 +44,14 @@ impl<'ctx> PrimitiveCodegen<'ctx> {
             BinaryOperator::Ge => self.gen_ge(builder, lhs, rhs),
             BinaryOperator::Eq => self.gen_eq(builder, lhs, rhs),
             BinaryOperator::Ne => self.gen_ne(builder, lhs, rhs),
+            BinaryOperator::ULt => self.gen_unsigned_compare(builder, IntPredicate::ULT, lhs, rhs),
+            BinaryOperator::UGt => self.gen_unsigned_compare(builder, IntPredicate::UGT, lhs, rhs),
 
             // Logical operations
             BinaryOperator::And => self.gen_and(builder, lhs, rhs),
             BinaryOperator::Or => self.gen_or(builder, lhs, rhs),
+            BinaryOperator::Xor => self.gen_xor(builder, lhs, rhs),
+            BinaryOperator::Shl | BinaryOperator::Shr | BinaryOperator::Sar => self.gen_shift(builder, op, lhs, rhs),
         }
     }
 
@@ -56,10 +62,19 @@ impl<'ctx> PrimitiveCodegen<'ctx> {
         op: UnaryOperator,
         operand: BasicValueEnum<'ctx>,
     ) -> Result<BasicValueEnum<'ctx>> {
+        let zero: BasicValueEnum<'ctx> = self.context.i64_type().const_zero().into();
+        let one: BasicValueEnum<'ctx> = self.context.i64_type().const_int(1, false).into();
         match op {
             UnaryOperator::Negate => self.gen_negate(builder, operand),
             UnaryOperator::Not => self.gen_not(builder, operand),
             UnaryOperator::Abs => self.gen_abs(builder, operand),
+            UnaryOperator::ZeroEq => self.gen_eq(builder, operand, zero),
+            UnaryOperator::ZeroLt => self.gen_lt(builder, operand, zero),
+            UnaryOperator::ZeroGt => self.gen_gt(builder, operand, zero),
+            UnaryOperator::IncOne => self.gen_add(builder, operand, one),
+            UnaryOperator::DecOne => self.gen_sub(builder, operand, one),
+            UnaryOperator::MulTwo => self.gen_shift(builder, BinaryOperator::Shl, operand, one),
+            UnaryOperator::DivTwo => self.gen_shift(builder, BinaryOperator::Sar, operand, one),
         }
     }
 
@@ -369,6 +384,53 @@ impl<'ctx> PrimitiveCodegen<'ctx> {
         }
     }
 
+    fn gen_min_max(
+        &self,
+        builder: &Builder<'ctx>,
+        predicate: IntPredicate,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        if lhs.is_int_value() && rhs.is_int_value() {
+            let keep_left = builder.build_int_compare(
+                predicate,
+                lhs.into_int_value(),
+                rhs.into_int_value(),
+                "keep_left"
+            ).map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            let result = builder.build_select(keep_left, lhs, rhs, "minmax")
+                .map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            Ok(result)
+        } else {
+            Err(BackendError::CodeGenError("Min/max requires integer operands".to_string()))
+        }
+    }
+
+    fn gen_unsigned_compare(
+        &self,
+        builder: &Builder<'ctx>,
+        predicate: IntPredicate,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        if lhs.is_int_value() && rhs.is_int_value() {
+            let result = builder.build_int_compare(
+                predicate,
+                lhs.into_int_value(),
+                rhs.into_int_value(),
+                "ucmp"
+            ).map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            let extended = builder.build_int_z_extend(
+                result,
+                self.context.i64_type(),
+                "ucmp_ext"
+            ).map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            Ok(extended.into())
+        } else {
+            Err(BackendError::CodeGenError("Unsigned comparison requires integer operands".to_string()))
+        }
+    }
+
     // Logical operations
 
     fn gen_and(
@@ -407,6 +469,48 @@ impl<'ctx> PrimitiveCodegen<'ctx> {
         }
     }
 
+    fn gen_xor(
+        &self,
+        builder: &Builder<'ctx>,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        if lhs.is_int_value() && rhs.is_int_value() {
+            let result = builder.build_xor(
+                lhs.into_int_value(),
+                rhs.into_int_value(),
+                "xor"
+            ).map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            Ok(result.into())
+        } else {
+            Err(BackendError::CodeGenError("Xor operation requires integer operands".to_string()))
+        }
+    }
+
+    fn gen_shift(
+        &self,
+        builder: &Builder<'ctx>,
+        op: BinaryOperator,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>> {
+        if lhs.is_int_value() && rhs.is_int_value() {
+            let value = lhs.into_int_value();
+            // LLVM shifts are poison for counts >= 64, so mask like Cranelift does
+            let mask = value.get_type().const_int(63, false);
+            let count = builder.build_and(rhs.into_int_value(), mask, "shift_count")
+                .map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            let result = match op {
+                BinaryOperator::Shl => builder.build_left_shift(value, count, "shl"),
+                BinaryOperator::Sar => builder.build_right_shift(value, count, true, "sar"),
+                _ => builder.build_right_shift(value, count, false, "shr"),
+            }.map_err(|e| BackendError::CodeGenError(e.to_string()))?;
+            Ok(result.into())
+        } else {
+            Err(BackendError::CodeGenError("Shift operation requires integer operands".to_string()))
+        }
+    }
+
     // Unary operations
 
     fn gen_negate(
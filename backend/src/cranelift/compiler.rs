@@ -3,8 +3,15 @@
 //! Fast compilation backend using Cranelift code generator.
 
 use crate::error::{BackendError, Result};
-use crate::cranelift::{CraneliftSettings, SSATranslator, FFIRegistry};
-use fastforth_frontend::ssa::SSAFunction;
+use crate::cranelift::{CraneliftSettings, SSATranslator, TranslatorImports, FFIRegistry};
+use crate::cranelift::mangle::mangle_word;
+use crate::cranelift::ffi::{
+    fastforth_cr, fastforth_delete_file, fastforth_div_by_zero, fastforth_emit, fastforth_fault_pending,
+    fastforth_file_status, fastforth_invalid_xt, fastforth_overflow, fastforth_print_int, fastforth_print_int_right, fastforth_print_uint,
+    fastforth_system, fastforth_type, CR_HOOK, DELETE_FILE_HOOK, DIV_BY_ZERO_HOOK, EMIT_HOOK, FAULT_PENDING_HOOK,
+    FILE_STATUS_HOOK, INVALID_XT_HOOK, OVERFLOW_HOOK, PRINT_INT_HOOK, PRINT_INT_RIGHT_HOOK, PRINT_UINT_HOOK, SYSTEM_HOOK, TYPE_HOOK,
+};
+use fastforth_frontend::ssa::{SSAFunction, SSAInstruction};
 
 use cranelift_codegen::ir::types;
 
@@ -15,12 +22,15 @@ use cranelift_codegen::Context;
 use cranelift_codegen::isa::TargetIsa;
 use cranelift_frontend::FunctionBuilderContext;
 use cranelift_jit::{JITBuilder, JITModule};
-use cranelift_module::{DataDescription, FuncId, Linkage, Module};
-use target_lexicon::Triple;
+use cranelift_module::{DataDescription, DataId, FuncId, Linkage, Module};
+use target_lexicon::{Architecture, Triple};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+/// Compiled function that takes no arguments and returns the top of its stack
+pub type EntryPoint = unsafe extern "C" fn() -> i64;
+
 /// Cranelift backend for Fast Forth
 pub struct CraneliftBackend {
     module: JITModule,
@@ -34,6 +44,16 @@ pub struct CraneliftBackend {
     ffi_registry: FFIRegistry,
     /// Target ISA for verification
     isa: Arc<dyn TargetIsa>,
+    /// Whether to record disassembly while compiling
+    keep_disassembly: bool,
+    /// Recorded disassembly per compiled function
+    disassembly: HashMap<String, String>,
+    /// Storage cells for Forth VARIABLEs, shared by all functions
+    variables: HashMap<String, DataId>,
+    /// Functions defined since the last `finalize_all`
+    pending: Vec<FuncId>,
+    /// Functions whose code is finalized and callable
+    finalized: HashSet<FuncId>,
 }
 
 impl CraneliftBackend {
@@ -48,6 +68,14 @@ impl CraneliftBackend {
             Triple::host()
         };
 
+        // Cranelift compiles from WebAssembly, but has no code generator for it
+        if matches!(triple.architecture, Architecture::Wasm32 | Architecture::Wasm64) {
+            return Err(BackendError::Initialization(format!(
+                "Cranelift cannot generate code for {}",
+                triple
+            )));
+        }
+
         // Create Cranelift settings
         let mut flag_builder = settings::builder();
 
@@ -81,12 +109,26 @@ impl CraneliftBackend {
             .map_err(|e| BackendError::Initialization(format!("ISA creation failed: {}", e)))?;
 
         // Create JIT module (JITBuilder::with_isa takes Arc<dyn TargetIsa>)
-        let builder = JITBuilder::with_isa(isa.clone(), cranelift_module::default_libcall_names());
+        let mut builder = JITBuilder::with_isa(isa.clone(), cranelift_module::default_libcall_names());
+        builder.symbol(DIV_BY_ZERO_HOOK, fastforth_div_by_zero as *const u8);
+        builder.symbol(OVERFLOW_HOOK, fastforth_overflow as *const u8);
+        builder.symbol(INVALID_XT_HOOK, fastforth_invalid_xt as *const u8);
+        builder.symbol(FAULT_PENDING_HOOK, fastforth_fault_pending as *const u8);
+        builder.symbol(PRINT_INT_HOOK, fastforth_print_int as *const u8);
+        builder.symbol(PRINT_UINT_HOOK, fastforth_print_uint as *const u8);
+        builder.symbol(PRINT_INT_RIGHT_HOOK, fastforth_print_int_right as *const u8);
+        builder.symbol(EMIT_HOOK, fastforth_emit as *const u8);
+        builder.symbol(CR_HOOK, fastforth_cr as *const u8);
+        builder.symbol(TYPE_HOOK, fastforth_type as *const u8);
+        builder.symbol(DELETE_FILE_HOOK, fastforth_delete_file as *const u8);
+        builder.symbol(FILE_STATUS_HOOK, fastforth_file_status as *const u8);
+        builder.symbol(SYSTEM_HOOK, fastforth_system as *const u8);
         let mut module = JITModule::new(builder);
 
-        // Initialize FFI registry and register libc functions
+        // Initialize FFI registry and register libc functions and runtime hooks
         let mut ffi_registry = FFIRegistry::new();
         ffi_registry.register_libc_functions(&mut module)?;
+        ffi_registry.register_runtime_hooks(&mut module)?;
 
         Ok(Self {
             module,
@@ -97,6 +139,11 @@ impl CraneliftBackend {
             func_refs: HashMap::new(),
             ffi_registry,
             isa,
+            keep_disassembly: false,
+            disassembly: HashMap::new(),
+            variables: HashMap::new(),
+            pending: Vec::new(),
+            finalized: HashSet::new(),
         })
     }
 
@@ -109,7 +156,7 @@ impl CraneliftBackend {
             let sig = self.create_signature(param_count, return_count);
 
             let func_id = self.module
-                .declare_function(name, Linkage::Export, &sig)
+                .declare_function(&mangle_word(name), Linkage::Export, &sig)
                 .map_err(|e| BackendError::CodeGeneration(format!("Failed to declare function '{}': {}", name, e)))?;
             self.functions.insert(name.clone(), func_id);
         }
@@ -123,6 +170,7 @@ impl CraneliftBackend {
         let func_id = self.functions.get(name)
             .copied()
             .ok_or_else(|| BackendError::CodeGeneration(format!("Function '{}' not declared", name)))?;
+        self.check_cell_width(ssa_func, name)?;
 
         // Create function signature based on SSA function's parameters and return count
         let param_count = ssa_func.parameters.len();
@@ -147,6 +195,20 @@ impl CraneliftBackend {
             }
         }
 
+        // Import the storage of every variable this function refers to
+        let mut variable_refs = HashMap::new();
+        for block in &ssa_func.blocks {
+            for inst in &block.instructions {
+                if let SSAInstruction::VariableAddr { name: var, cells, .. } = inst {
+                    if !variable_refs.contains_key(var) {
+                        let data_id = self.variable_storage(var, *cells)?;
+                        let gv = self.module.declare_data_in_func(data_id, &mut self.ctx.func);
+                        variable_refs.insert(var.clone(), gv);
+                    }
+                }
+            }
+        }
+
         // Clone func_refs to avoid borrow checker issues
         let func_refs_copy = self.func_refs.clone();
 
@@ -154,17 +216,36 @@ impl CraneliftBackend {
         let translator = SSATranslator::new(
             &mut self.ctx.func,
             &mut self.builder_ctx,
-            &func_refs_copy,
-            &ffi_refs,
+            TranslatorImports {
+                func_refs: &func_refs_copy,
+                ffi_refs: &ffi_refs,
+                variable_refs: &variable_refs,
+            },
             &self.isa,
-            self.settings.enable_verification,
+            &self.settings,
         );
         translator.translate(ssa_func)?;
 
+        let clif = if self.keep_disassembly {
+            self.ctx.set_disasm(true);
+            Some(self.ctx.func.display().to_string())
+        } else {
+            None
+        };
+
         // Define function (but don't finalize yet - allows recursion)
         self.module
             .define_function(func_id, &mut self.ctx)
             .map_err(|e| BackendError::CodeGeneration(format!("Failed to define function '{}': {}", name, e)))?;
+        self.pending.push(func_id);
+
+        if let Some(clif) = clif {
+            let mut text = format!("; Cranelift IR\n{}", clif);
+            if let Some(asm) = self.ctx.compiled_code().and_then(|code| code.vcode.as_ref()) {
+                text.push_str(&format!("\n; {} assembly\n{}", self.isa.triple().architecture, asm));
+            }
+            self.disassembly.insert(name.to_string(), text);
+        }
 
         // Clear context for next function
         self.module.clear_context(&mut self.ctx);
@@ -172,10 +253,66 @@ impl CraneliftBackend {
         Ok(())
     }
 
+    /// Reject literals that do not fit in a cell, and addresses when the
+    /// target's pointers are wider than a cell
+    fn check_cell_width(&self, ssa_func: &SSAFunction, name: &str) -> Result<()> {
+        let width = self.settings.cell_width;
+        let pointers_fit = self.isa.pointer_bits() as u32 <= width.bits();
+
+        for inst in ssa_func.blocks.iter().flat_map(|block| &block.instructions) {
+            let address_of = match inst {
+                SSAInstruction::LoadInt { value, .. } if !width.contains(*value) => {
+                    return Err(BackendError::CodeGeneration(format!(
+                        "Literal {} in '{}' does not fit in a {}-bit cell",
+                        value, name, width.bits()
+                    )));
+                }
+                SSAInstruction::VariableAddr { name: var, .. } | SSAInstruction::ExecutionToken { word: var, .. }
+                    if !pointers_fit =>
+                {
+                    format!("'{}'", var)
+                }
+                SSAInstruction::LoadString { .. } if !pointers_fit => "a string literal".to_string(),
+                _ => continue,
+            };
+            return Err(BackendError::UnsupportedFeature(format!(
+                "The address of {} in '{}' does not fit in a {}-bit cell on {}",
+                address_of, name, width.bits(), self.isa.triple()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Zero-initialized storage backing a VARIABLE, VALUE or CREATEd word, created on first use
+    fn variable_storage(&mut self, name: &str, cells: usize) -> Result<DataId> {
+        if let Some(&data_id) = self.variables.get(name) {
+            return Ok(data_id);
+        }
+
+        // Prefixed so variables never clash with words or libc symbols
+        let data_id = self.module
+            .declare_data(&format!("forth_var_{}", name), Linkage::Local, true, false)
+            .map_err(|e| BackendError::CodeGeneration(format!("Failed to declare variable '{}': {}", name, e)))?;
+
+        // SSA conversion lays fields out 8 bytes apart, which leaves room
+        // for either cell width
+        let mut description = DataDescription::new();
+        description.define_zeroinit(8 * cells.max(1));
+        description.set_align(8);
+        self.module
+            .define_data(data_id, &description)
+            .map_err(|e| BackendError::CodeGeneration(format!("Failed to define variable '{}': {}", name, e)))?;
+
+        self.variables.insert(name.to_string(), data_id);
+        Ok(data_id)
+    }
+
     /// Finalize all compiled functions (call after compiling all functions)
     pub fn finalize_all(&mut self) -> Result<()> {
         self.module.finalize_definitions()
             .map_err(|e| BackendError::CodeGeneration(format!("Failed to finalize: {}", e)))?;
+        self.finalized.extend(self.pending.drain(..));
         Ok(())
     }
 
@@ -198,10 +335,69 @@ impl CraneliftBackend {
     }
 
     /// Get pointer to compiled function by name
+    ///
+    /// Returns `None` unless the function was compiled and `finalize_all`
+    /// has run since.
     pub fn get_function(&self, name: &str) -> Option<*const u8> {
-        self.functions.get(name).map(|&func_id| {
-            self.module.get_finalized_function(func_id)
-        })
+        self.functions
+            .get(name)
+            .filter(|func_id| self.finalized.contains(func_id))
+            .map(|&func_id| self.module.get_finalized_function(func_id))
+    }
+
+    /// Callable entry point of a finalized function that takes no arguments,
+    /// such as [`MAIN_FUNCTION`](fastforth_frontend::MAIN_FUNCTION)
+    ///
+    /// Compiled code keeps no state between calls, so the entry point can be
+    /// called any number of times, each call starting from an empty stack.
+    /// It stays valid until the backend's memory is freed. Returns `None` if
+    /// the function is not finalized or takes parameters.
+    pub fn get_entry_point(&self, name: &str) -> Option<EntryPoint> {
+        let func_id = *self.functions.get(name)?;
+        let decl = self.module.declarations().get_function_decl(func_id);
+        if !decl.signature.params.is_empty() {
+            return None;
+        }
+
+        let ptr = self.get_function(name)?;
+        // SAFETY: the function was declared with no parameters and one i64 return
+        Some(unsafe { std::mem::transmute::<*const u8, EntryPoint>(ptr) })
+    }
+
+    /// Record Cranelift IR and target assembly for functions compiled from now on
+    pub fn set_keep_disassembly(&mut self, enabled: bool) {
+        self.keep_disassembly = enabled;
+    }
+
+    /// Textual Cranelift IR and, where the target supports it, assembly for a
+    /// compiled function
+    ///
+    /// The text reflects the SSA that was handed to the backend, so any
+    /// inlining done before code generation is already applied.
+    pub fn disassemble(&self, name: &str) -> Result<String> {
+        if let Some(text) = self.disassembly.get(name) {
+            return Ok(text.clone());
+        }
+
+        if !self.functions.contains_key(name) {
+            Err(BackendError::CodeGeneration(format!("Function '{}' not declared", name)))
+        } else if !self.keep_disassembly {
+            Err(BackendError::CodeGeneration(
+                "Disassembly was not enabled before compilation".to_string(),
+            ))
+        } else {
+            Err(BackendError::CodeGeneration(format!("Function '{}' has not been compiled yet", name)))
+        }
+    }
+
+    /// Release the executable memory owned by this backend
+    ///
+    /// # Safety
+    ///
+    /// No pointer obtained from [`get_function`](Self::get_function) may be
+    /// called after this returns.
+    pub unsafe fn free_memory(self) {
+        self.module.free_memory();
     }
 }
 
@@ -244,6 +440,7 @@ impl Default for CraneliftCompiler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cranelift::CellWidth;
 
     #[test]
     fn test_create_compiler() {
@@ -257,4 +454,173 @@ mod tests {
         let compiler = CraneliftCompiler::with_settings(settings);
         assert!(compiler.is_ok());
     }
+
+    #[test]
+    fn test_wasm_target_rejected() {
+        let settings = CraneliftSettings {
+            target_triple: Some("wasm32-unknown-unknown"),
+            ..CraneliftSettings::default()
+        };
+        match CraneliftBackend::new(settings) {
+            Err(BackendError::Initialization(msg)) => assert!(msg.contains("wasm32"), "{}", msg),
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("wasm32 target should be rejected"),
+        }
+    }
+
+    fn compile_source(source: &str, keep_disassembly: bool) -> CraneliftBackend {
+        let program = fastforth_frontend::parse_program(source).unwrap();
+        let functions = fastforth_frontend::convert_to_ssa(&program).unwrap();
+        let named: Vec<(String, &SSAFunction)> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+
+        let mut backend = CraneliftBackend::new(CraneliftSettings::default()).unwrap();
+        backend.set_keep_disassembly(keep_disassembly);
+        backend.declare_all_functions(&named).unwrap();
+        for (name, func) in &named {
+            backend.compile_function(func, name).unwrap();
+        }
+        backend.finalize_all().unwrap();
+        backend
+    }
+
+    #[test]
+    fn test_disassemble_word() {
+        let backend = compile_source(": inc 1 + ;", true);
+        let text = backend.disassemble("inc").unwrap();
+        assert!(text.contains("iadd"));
+    }
+
+    #[test]
+    fn test_32_bit_cells() {
+        let program = fastforth_frontend::parse_program(": inc ( n -- n ) 1 + ;").unwrap();
+        let functions = fastforth_frontend::convert_to_ssa(&program).unwrap();
+        let settings = CraneliftSettings { cell_width: CellWidth::Bits32, ..CraneliftSettings::default() };
+
+        let mut backend = CraneliftBackend::new(settings).unwrap();
+        backend.set_keep_disassembly(true);
+        backend.declare_all_functions(&[("inc".to_string(), &functions[0])]).unwrap();
+        backend.compile_function(&functions[0], "inc").unwrap();
+        backend.finalize_all().unwrap();
+
+        // Added as i32, passed as a sign-extended i64
+        assert!(backend.disassemble("inc").unwrap().contains("ireduce.i32"));
+        let inc: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(backend.get_function("inc").unwrap()) };
+        assert_eq!(inc(41), 42);
+        assert_eq!(inc(i32::MAX as i64), i32::MIN as i64);
+    }
+
+    #[test]
+    fn test_disassemble_errors() {
+        let backend = compile_source(": inc 1 + ;", false);
+        assert!(backend.disassemble("inc").is_err());
+        assert!(backend.disassemble("missing").is_err());
+    }
+
+    #[test]
+    fn test_words_use_natural_signatures() {
+        let backend = compile_source(": inc ( n -- n ) 1 + ; : mix ( a b -- c ) 10 * + ;", false);
+
+        let inc: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(backend.get_function("inc").unwrap()) };
+        let mix: extern "C" fn(i64, i64) -> i64 = unsafe { std::mem::transmute(backend.get_function("mix").unwrap()) };
+        assert_eq!(inc(41), 42);
+        assert_eq!(mix(1, 2), 21);
+    }
+
+    #[test]
+    fn test_entry_point_is_reusable() {
+        let backend = compile_source(": inc ( n -- n ) 1 + ; 41 inc", false);
+
+        let main = backend.get_entry_point(fastforth_frontend::MAIN_FUNCTION).unwrap();
+        for _ in 0..3 {
+            assert_eq!(unsafe { main() }, 42);
+        }
+        // Words with parameters have no argument-free entry point
+        assert!(backend.get_entry_point("inc").is_none());
+        assert!(backend.get_entry_point("missing").is_none());
+    }
+
+    #[test]
+    fn test_calls_between_words() {
+        let backend = compile_source(": a 1 ; : b a a + ; : countdown ( n -- n ) dup 0 > if 1 - countdown else 1 + then ;", false);
+
+        let b: extern "C" fn() -> i64 = unsafe { std::mem::transmute(backend.get_function("b").unwrap()) };
+        let countdown: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(backend.get_function("countdown").unwrap()) };
+        assert_eq!(b(), 2);
+        assert_eq!(countdown(5), 1);
+    }
+
+    #[test]
+    fn test_functions_callable_only_after_finalize() {
+        let program = fastforth_frontend::parse_program(": even? ( n -- f ) dup 0 = if drop -1 else 1 - odd? then ; \
+             : odd? ( n -- f ) dup 0 = if drop 0 else 1 - even? then ;").unwrap();
+        let functions = fastforth_frontend::convert_to_ssa(&program).unwrap();
+        let named: Vec<(String, &SSAFunction)> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+
+        let mut backend = CraneliftBackend::new(CraneliftSettings::default()).unwrap();
+        backend.declare_all_functions(&named).unwrap();
+        // Declaring again is harmless
+        backend.declare_all_functions(&named).unwrap();
+        for (name, func) in &named {
+            backend.compile_function(func, name).unwrap();
+        }
+        assert!(backend.get_function("even?").is_none());
+
+        backend.finalize_all().unwrap();
+        let even: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(backend.get_function("even?").unwrap()) };
+        assert_eq!(even(10), -1);
+        assert_eq!(even(7), 0);
+    }
+
+    #[test]
+    fn test_call_to_undeclared_word() {
+        let program = fastforth_frontend::parse_program(": a 1 ; : b a a + ;").unwrap();
+        let functions = fastforth_frontend::convert_to_ssa(&program).unwrap();
+
+        // Only `b` is declared, so its call to `a` cannot be resolved
+        let mut backend = CraneliftBackend::new(CraneliftSettings::default()).unwrap();
+        backend.declare_all_functions(&[("b".to_string(), &functions[1])]).unwrap();
+        match backend.compile_function(&functions[1], "b") {
+            Err(BackendError::CodeGeneration(msg)) => assert!(msg.contains("'a'"), "{}", msg),
+            other => panic!("expected an unresolved call, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_symbolic_and_clashing_word_names() {
+        let program = fastforth_frontend::parse_program(": double ( n -- n ) 2 * ; : fopen ( -- n ) 7 ;").unwrap();
+        let mut functions = fastforth_frontend::convert_to_ssa(&program).unwrap();
+        // `2*` is a builtin, so it can only be named after parsing
+        functions[0].name = "2*".to_string();
+        let named: Vec<(String, &SSAFunction)> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+
+        let mut backend = CraneliftBackend::new(CraneliftSettings::default()).unwrap();
+        backend.declare_all_functions(&named).unwrap();
+        for (name, func) in &named {
+            backend.compile_function(func, name).unwrap();
+        }
+        backend.finalize_all().unwrap();
+
+        let double: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(backend.get_function("2*").unwrap()) };
+        let fopen: extern "C" fn() -> i64 = unsafe { std::mem::transmute(backend.get_function("fopen").unwrap()) };
+        assert_eq!(double(21), 42);
+        assert_eq!(fopen(), 7);
+    }
+
+    #[test]
+    fn test_variables_have_distinct_storage() {
+        let backend = compile_source(
+            "variable a variable b : set-a 7 a ! ; : get-a a @ ; : addrs a b - ;",
+            false,
+        );
+
+        let set_a: extern "C" fn() -> i64 = unsafe { std::mem::transmute(backend.get_function("set-a").unwrap()) };
+        let get_a: extern "C" fn() -> i64 = unsafe { std::mem::transmute(backend.get_function("get-a").unwrap()) };
+        let addrs: extern "C" fn() -> i64 = unsafe { std::mem::transmute(backend.get_function("addrs").unwrap()) };
+
+        // Read before any store sees the zero-initialized cell
+        assert_eq!(get_a(), 0);
+        set_a();
+        assert_eq!(get_a(), 7);
+        assert_ne!(addrs(), 0);
+    }
 }
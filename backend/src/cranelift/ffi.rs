@@ -7,7 +7,347 @@ use crate::error::{BackendError, Result};
 use cranelift_codegen::ir::{types, AbiParam, ExternalName, Signature};
 use cranelift_codegen::isa::CallConv;
 use cranelift_module::{FuncId, Linkage, Module};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+
+/// Symbol name of the runtime hook invoked by guarded division
+pub const DIV_BY_ZERO_HOOK: &str = "fastforth_div_by_zero";
+
+/// Symbol name of the runtime hook invoked by checked arithmetic on overflow
+pub const OVERFLOW_HOOK: &str = "fastforth_overflow";
+
+/// Symbol name of the runtime hook invoked when EXECUTE gets a value that is
+/// not an execution token
+pub const INVALID_XT_HOOK: &str = "fastforth_invalid_xt";
+
+/// Symbol name of the runtime hook telling compiled code whether a word it
+/// called raised a fault
+pub const FAULT_PENDING_HOOK: &str = "fastforth_fault_pending";
+
+/// Symbol name of the runtime hook behind `.`
+pub const PRINT_INT_HOOK: &str = "fastforth_print_int";
+
+/// Symbol name of the runtime hook behind `u.`
+pub const PRINT_UINT_HOOK: &str = "fastforth_print_uint";
+
+/// Symbol name of the runtime hook behind `.r`
+pub const PRINT_INT_RIGHT_HOOK: &str = "fastforth_print_int_right";
+
+/// Symbol name of the runtime hook behind `emit`
+pub const EMIT_HOOK: &str = "fastforth_emit";
+
+/// Symbol name of the runtime hook behind `cr`
+pub const CR_HOOK: &str = "fastforth_cr";
+
+/// Symbol name of the runtime hook behind `type`
+pub const TYPE_HOOK: &str = "fastforth_type";
+
+/// Symbol name of the runtime hook behind `delete-file`
+pub const DELETE_FILE_HOOK: &str = "fastforth_delete_file";
+
+/// Symbol name of the runtime hook behind `file-status`
+pub const FILE_STATUS_HOOK: &str = "fastforth_file_status";
+
+/// Symbol name of the runtime hook behind `system`
+pub const SYSTEM_HOOK: &str = "fastforth_system";
+
+/// Output words and the runtime hooks implementing them, with their argument counts
+const OUTPUT_HOOKS: &[(&str, &str, usize)] = &[
+    (".", PRINT_INT_HOOK, 1),
+    ("u.", PRINT_UINT_HOOK, 1),
+    (".r", PRINT_INT_RIGHT_HOOK, 2),
+    ("emit", EMIT_HOOK, 1),
+    ("cr", CR_HOOK, 0),
+    ("type", TYPE_HOOK, 2),
+];
+
+/// Runtime hook implementing the output word `word`, if it is one
+pub fn output_hook(word: &str) -> Option<&'static str> {
+    OUTPUT_HOOKS
+        .iter()
+        .find(|(name, _, _)| *name == word)
+        .map(|(_, hook, _)| *hook)
+}
+
+thread_local! {
+    /// Last runtime fault raised by JIT-compiled code on this thread
+    static RUNTIME_FAULT: Cell<Option<&'static str>> = const { Cell::new(None) };
+
+    /// Sink receiving output on this thread, or `None` for stdout
+    static OUTPUT_SINK: RefCell<Option<Box<dyn OutputSink>>> = const { RefCell::new(None) };
+}
+
+/// Destination for the text printed by output words (`.`, `emit`, `cr`, ...)
+///
+/// Sinks are installed per thread with [`replace_output_sink`], so a sink is
+/// only ever called from the thread that runs the compiled code and needs no
+/// locking.
+pub trait OutputSink {
+    /// Write a single byte, as `emit` does
+    fn write_byte(&mut self, byte: u8);
+
+    /// Write a string, as the number printing words do
+    fn write_str(&mut self, s: &str) {
+        for byte in s.bytes() {
+            self.write_byte(byte);
+        }
+    }
+
+    /// Flush buffered output; called after each output word
+    fn flush(&mut self) {}
+}
+
+/// Default sink: the process's stdout
+///
+/// Stdout is flushed after every word so that output from compiled code
+/// interleaves correctly with the host's own output. Output words have no
+/// way to report an error, so write failures are dropped.
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write_byte(&mut self, byte: u8) {
+        let _ = std::io::stdout().lock().write_all(&[byte]);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        let _ = std::io::stdout().lock().write_all(s.as_bytes());
+    }
+
+    fn flush(&mut self) {
+        let _ = std::io::stdout().lock().flush();
+    }
+}
+
+/// In-memory sink; keep a clone to read what was written
+impl OutputSink for Rc<RefCell<Vec<u8>>> {
+    fn write_byte(&mut self, byte: u8) {
+        self.borrow_mut().push(byte);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.borrow_mut().extend_from_slice(s.as_bytes());
+    }
+}
+
+/// Install `sink` for output on this thread, returning the previous one
+///
+/// `None` restores the default [`StdoutSink`].
+pub fn replace_output_sink(sink: Option<Box<dyn OutputSink>>) -> Option<Box<dyn OutputSink>> {
+    OUTPUT_SINK.with(|current| current.replace(sink))
+}
+
+/// Runtime hook called by JIT code when a division or modulo sees a zero divisor
+///
+/// Records the fault so the host can report it after the compiled code returns.
+pub extern "C" fn fastforth_div_by_zero() -> i64 {
+    RUNTIME_FAULT.with(|fault| fault.set(Some("Division by zero")));
+    0
+}
+
+/// Runtime hook called by JIT code when checked arithmetic overflows
+pub extern "C" fn fastforth_overflow() -> i64 {
+    RUNTIME_FAULT.with(|fault| fault.set(Some("Integer overflow")));
+    0
+}
+
+/// Runtime hook called by JIT code when EXECUTE gets something other than
+/// the execution token of a word taking the parameters it passes
+pub extern "C" fn fastforth_invalid_xt() -> i64 {
+    RUNTIME_FAULT.with(|fault| fault.set(Some("Invalid execution token")));
+    0
+}
+
+/// Runtime hook called by JIT code after each call to another compiled word:
+/// nonzero when that word raised a fault, so the caller stops as well
+pub extern "C" fn fastforth_fault_pending() -> i64 {
+    RUNTIME_FAULT.with(|fault| fault.get().is_some() as i64)
+}
+
+/// Take (and clear) the runtime fault raised by JIT code on this thread, if any
+pub fn take_runtime_fault() -> Option<&'static str> {
+    RUNTIME_FAULT.with(|fault| fault.take())
+}
+
+/// Runtime hook behind `.`: print a signed cell followed by a space
+///
+/// Numbers are always printed in decimal; there is no `BASE` variable yet.
+pub extern "C" fn fastforth_print_int(n: i64) -> i64 {
+    write_output(|sink| sink.write_str(&format!("{} ", n)));
+    0
+}
+
+/// Runtime hook behind `u.`: print a cell as unsigned followed by a space
+pub extern "C" fn fastforth_print_uint(n: i64) -> i64 {
+    write_output(|sink| sink.write_str(&format!("{} ", n as u64)));
+    0
+}
+
+/// Runtime hook behind `.r`: print a signed cell right-justified in `width`
+/// characters, without a trailing space
+///
+/// A number wider than the field is printed in full.
+pub extern "C" fn fastforth_print_int_right(n: i64, width: i64) -> i64 {
+    let width = usize::try_from(width).unwrap_or(0);
+    write_output(|sink| sink.write_str(&format!("{:>width$}", n, width = width)));
+    0
+}
+
+/// Runtime hook behind `emit`: print the low byte of a cell
+pub extern "C" fn fastforth_emit(c: i64) -> i64 {
+    write_output(|sink| sink.write_byte(c as u8));
+    0
+}
+
+/// Runtime hook behind `cr`: print a newline
+pub extern "C" fn fastforth_cr() -> i64 {
+    write_output(|sink| sink.write_byte(b'\n'));
+    0
+}
+
+/// Runtime hook behind `type`: print the `len` bytes at `addr` unchanged
+///
+/// Prints nothing for a null address or a length that is not positive.
+/// Like `@`, it trusts the address; reading past the end of the string is
+/// the caller's error.
+pub extern "C" fn fastforth_type(addr: i64, len: i64) -> i64 {
+    let Ok(len) = usize::try_from(len) else {
+        return 0;
+    };
+    if addr == 0 || len == 0 {
+        return 0;
+    }
+    // SAFETY: compiled code passes a string it owns, `len` bytes long
+    let bytes = unsafe { std::slice::from_raw_parts(addr as *const u8, len) };
+    write_output(|sink| {
+        for &byte in bytes {
+            sink.write_byte(byte);
+        }
+    });
+    0
+}
+
+/// String made of the `len` bytes at `addr`, which need not be NUL-terminated
+///
+/// Returns `None` for a null address, a negative length or invalid UTF-8.
+fn string_arg(addr: i64, len: i64) -> Option<String> {
+    let len = usize::try_from(len).ok()?;
+    if addr == 0 {
+        return None;
+    }
+    // SAFETY: compiled code passes a string it owns, `len` bytes long
+    let bytes = unsafe { std::slice::from_raw_parts(addr as *const u8, len) };
+    std::str::from_utf8(bytes).ok().map(str::to_string)
+}
+
+/// Path named by `addr len`; relative paths are resolved against the
+/// current directory by the OS
+fn path_arg(addr: i64, len: i64) -> Option<std::path::PathBuf> {
+    string_arg(addr, len).map(std::path::PathBuf::from)
+}
+
+/// Nonzero `ior` for an I/O error: the negated OS error code, or -1
+fn io_error_code(err: &std::io::Error) -> i64 {
+    err.raw_os_error().map_or(-1, |code| -(code as i64))
+}
+
+/// Runtime hook behind `delete-file`: remove the file named by `addr len`
+///
+/// Returns the `ior`: 0 on success, nonzero if the file could not be
+/// removed (missing, a directory, or not permitted).
+pub extern "C" fn fastforth_delete_file(addr: i64, len: i64) -> i64 {
+    match path_arg(addr, len) {
+        Some(path) => std::fs::remove_file(path).map_or_else(|err| io_error_code(&err), |_| 0),
+        None => -1,
+    }
+}
+
+/// Runtime hook behind `file-status`: look up the file named by `addr len`
+///
+/// Returns its permission bits (`st_mode & 0o7777` on Unix; 0o444 or 0o666
+/// elsewhere, depending on the read-only flag) if it exists, or a negative
+/// `ior` if it does not or cannot be examined.
+pub extern "C" fn fastforth_file_status(addr: i64, len: i64) -> i64 {
+    let Some(path) = path_arg(addr, len) else {
+        return -1;
+    };
+    match std::fs::metadata(path) {
+        #[cfg(unix)]
+        Ok(metadata) => {
+            use std::os::unix::fs::PermissionsExt;
+            i64::from(metadata.permissions().mode() & 0o7777)
+        }
+        #[cfg(not(unix))]
+        Ok(metadata) => if metadata.permissions().readonly() { 0o444 } else { 0o666 },
+        Err(err) => io_error_code(&err),
+    }
+}
+
+/// Runtime hook behind `system`: run the command named by `addr len` with `sh -c`
+///
+/// The command's standard output goes to this thread's output sink, after
+/// it exits; standard error is inherited. Returns its exit status, 128 plus
+/// the signal number if a signal killed it, or -1 if it could not be run
+/// (including commands containing a NUL byte, which no shell can receive).
+pub extern "C" fn fastforth_system(addr: i64, len: i64) -> i64 {
+    let Some(command) = string_arg(addr, len).filter(|command| !command.contains('\0')) else {
+        return -1;
+    };
+
+    let output = match std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .stdin(std::process::Stdio::inherit())
+        .stderr(std::process::Stdio::inherit())
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return -1,
+    };
+
+    write_output(|sink| {
+        for &byte in &output.stdout {
+            sink.write_byte(byte);
+        }
+    });
+
+    match output.status.code() {
+        Some(code) => i64::from(code),
+        #[cfg(unix)]
+        None => {
+            use std::os::unix::process::ExitStatusExt;
+            output.status.signal().map_or(-1, |signal| 128 + i64::from(signal))
+        }
+        #[cfg(not(unix))]
+        None => -1,
+    }
+}
+
+/// Send program output to this thread's sink, flushing after each word
+fn write_output(write: impl FnOnce(&mut dyn OutputSink)) {
+    OUTPUT_SINK.with(|current| match current.borrow_mut().as_deref_mut() {
+        Some(sink) => {
+            write(sink);
+            sink.flush();
+        }
+        None => {
+            write(&mut StdoutSink);
+            StdoutSink.flush();
+        }
+    });
+}
+
+/// Run `f`, collecting everything the output hooks print on this thread
+/// instead of sending it to the current sink
+pub fn capture_output<R>(f: impl FnOnce() -> R) -> (R, String) {
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    let previous = replace_output_sink(Some(Box::new(buffer.clone())));
+    let result = f();
+    replace_output_sink(previous);
+    let output = buffer.take();
+    (result, String::from_utf8_lossy(&output).into_owned())
+}
 
 /// FFI function metadata
 #[derive(Debug, Clone)]
@@ -167,6 +507,55 @@ impl FFIRegistry {
         Ok(())
     }
 
+    /// Register FastForth runtime hooks (symbols must be provided to the JIT builder)
+    pub fn register_runtime_hooks<M: Module>(&mut self, module: &mut M) -> Result<()> {
+        // i64 fastforth_div_by_zero(void)
+        self.register_function(
+            module,
+            FFISignature::new(DIV_BY_ZERO_HOOK)
+                .returns(types::I64), // dummy result
+        )?;
+
+        // i64 fastforth_overflow(void)
+        self.register_function(
+            module,
+            FFISignature::new(OVERFLOW_HOOK)
+                .returns(types::I64), // dummy result
+        )?;
+
+        // i64 fastforth_invalid_xt(void)
+        self.register_function(
+            module,
+            FFISignature::new(INVALID_XT_HOOK)
+                .returns(types::I64), // dummy result
+        )?;
+
+        // i64 fastforth_fault_pending(void) -> nonzero after a fault
+        self.register_function(
+            module,
+            FFISignature::new(FAULT_PENDING_HOOK)
+                .returns(types::I64),
+        )?;
+
+        // i64 fastforth_delete_file(i64 addr, i64 len) -> ior
+        // i64 fastforth_file_status(i64 addr, i64 len) -> mode or negative ior
+        // i64 fastforth_system(i64 addr, i64 len) -> exit status
+        for hook in [DELETE_FILE_HOOK, FILE_STATUS_HOOK, SYSTEM_HOOK] {
+            self.register_function(
+                module,
+                FFISignature::new(hook).param(types::I64).param(types::I64).returns(types::I64),
+            )?;
+        }
+
+        // i64 <output hook>(i64...) - one cell per argument
+        for &(_, hook, arity) in OUTPUT_HOOKS {
+            let sig = (0..arity).fold(FFISignature::new(hook), |sig, _| sig.param(types::I64));
+            self.register_function(module, sig.returns(types::I64))?;
+        }
+
+        Ok(())
+    }
+
     /// Register a single external function
     fn register_function<M: Module>(
         &mut self,
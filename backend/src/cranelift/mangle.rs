@@ -0,0 +1,131 @@
+//! Symbol names for Forth words
+//!
+//! Forth words may be named with any non-space characters (`2*`, `>r`,
+//! `fopen`), but their symbols share a module with libc imports and the
+//! runtime hooks. Every word is therefore declared under a mangled name:
+//!
+//! - the prefix `forth_word_`, which no import or hook uses;
+//! - ASCII letters and digits as they are;
+//! - `_` doubled to `__`;
+//! - common punctuation as `_name_`, e.g. `+` as `_plus_`;
+//! - any other character as `_u<hex>_` of its code point.
+//!
+//! So `2*` becomes `forth_word_2_star_`. Because a lone `_` always opens an
+//! escape, distinct words never share a symbol and [`demangle_word`] can
+//! recover the original name. The scheme is fixed, so symbols are stable
+//! across runs.
+
+/// Prefix of every word symbol
+const WORD_PREFIX: &str = "forth_word_";
+
+/// Punctuation with a readable escape
+const ESCAPES: &[(char, &str)] = &[
+    ('+', "plus"),
+    ('-', "minus"),
+    ('*', "star"),
+    ('/', "slash"),
+    ('@', "fetch"),
+    ('!', "store"),
+    ('<', "lt"),
+    ('>', "gt"),
+    ('=', "eq"),
+    ('.', "dot"),
+    (',', "comma"),
+    (':', "colon"),
+    (';', "semi"),
+    ('?', "query"),
+    ('\'', "tick"),
+    ('"', "quote"),
+    ('#', "hash"),
+    ('$', "dollar"),
+    ('%', "percent"),
+    ('&', "amp"),
+    ('|', "bar"),
+    ('^', "caret"),
+    ('~', "tilde"),
+    ('(', "lparen"),
+    (')', "rparen"),
+    ('[', "lbracket"),
+    (']', "rbracket"),
+    ('{', "lbrace"),
+    ('}', "rbrace"),
+    ('\\', "backslash"),
+];
+
+/// Symbol a word is declared under
+pub fn mangle_word(name: &str) -> String {
+    let mut symbol = String::from(WORD_PREFIX);
+    for c in name.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => symbol.push(c),
+            '_' => symbol.push_str("__"),
+            _ => match ESCAPES.iter().find(|(escaped, _)| *escaped == c) {
+                Some((_, escape)) => symbol.push_str(&format!("_{}_", escape)),
+                None => symbol.push_str(&format!("_u{:x}_", c as u32)),
+            },
+        }
+    }
+    symbol
+}
+
+/// Word a symbol made by [`mangle_word`] was declared for, for diagnostics
+///
+/// Returns `None` for symbols that are not word symbols.
+pub fn demangle_word(symbol: &str) -> Option<String> {
+    let mut rest = symbol.strip_prefix(WORD_PREFIX)?;
+    let mut name = String::new();
+
+    while let Some(c) = rest.chars().next() {
+        if c != '_' {
+            name.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("__") {
+            name.push('_');
+            rest = after;
+            continue;
+        }
+
+        let (escape, after) = rest[1..].split_once('_')?;
+        let decoded = match ESCAPES.iter().find(|(_, name)| *name == escape) {
+            Some((c, _)) => *c,
+            None => {
+                let code = u32::from_str_radix(escape.strip_prefix('u')?, 16).ok()?;
+                char::from_u32(code)?
+            }
+        };
+        name.push(decoded);
+        rest = after;
+    }
+
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mangle_word() {
+        assert_eq!(mangle_word("square"), "forth_word_square");
+        assert_eq!(mangle_word("2*"), "forth_word_2_star_");
+        assert_eq!(mangle_word(">r"), "forth_word__gt_r");
+        assert_eq!(mangle_word("a_b"), "forth_word_a__b");
+        assert_eq!(mangle_word("λ"), "forth_word__u3bb_");
+    }
+
+    #[test]
+    fn test_mangling_round_trips_without_collisions() {
+        let names = ["+", "_plus_", "a_b", "a__b", "2dup", ">r", ":main", "λx", "forth_word_x", "x_u2b_"];
+        let symbols: Vec<String> = names.iter().map(|name| mangle_word(name)).collect();
+
+        for (name, symbol) in names.iter().zip(&symbols) {
+            assert_eq!(demangle_word(symbol).as_deref(), Some(*name));
+        }
+        let distinct: std::collections::HashSet<&String> = symbols.iter().collect();
+        assert_eq!(distinct.len(), names.len());
+
+        assert_eq!(demangle_word("fopen"), None);
+    }
+}
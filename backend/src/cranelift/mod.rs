@@ -10,13 +10,71 @@
 mod compiler;
 mod translator;
 pub mod ffi;
+pub mod mangle;
 
-pub use compiler::{CraneliftBackend, CraneliftCompiler};
-pub use translator::SSATranslator;
-pub use ffi::{FFIRegistry, FFISignature};
+pub use compiler::{CraneliftBackend, CraneliftCompiler, EntryPoint};
+pub use translator::{SSATranslator, TranslatorImports};
+pub use mangle::{demangle_word, mangle_word};
+pub use ffi::{FFIRegistry, FFISignature, OutputSink, StdoutSink, capture_output, replace_output_sink, take_runtime_fault};
 
 use crate::error::{BackendError, Result};
 use fastforth_frontend::ssa::{SSAFunction, SSAInstruction, Register, BlockId};
+use cranelift_codegen::ir::{types, Type};
+
+/// Width of a Forth cell in compiled code
+///
+/// Arithmetic and memory cells (`@ !`) use this width. A 32-bit cell is held
+/// sign-extended in a 64-bit register, so word signatures and runtime hooks
+/// are the same for both widths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellWidth {
+    /// 32-bit cells, for embedded targets
+    Bits32,
+    /// 64-bit cells
+    #[default]
+    Bits64,
+}
+
+impl CellWidth {
+    /// Cranelift integer type cell arithmetic is done in
+    pub fn ir_type(self) -> Type {
+        match self {
+            CellWidth::Bits32 => types::I32,
+            CellWidth::Bits64 => types::I64,
+        }
+    }
+
+    /// Number of bits in a cell
+    pub fn bits(self) -> u32 {
+        self.ir_type().bits()
+    }
+
+    /// Number of bytes in a cell
+    pub fn bytes(self) -> u32 {
+        self.ir_type().bytes()
+    }
+
+    /// Smallest signed value a cell holds
+    pub fn min(self) -> i64 {
+        match self {
+            CellWidth::Bits32 => i32::MIN as i64,
+            CellWidth::Bits64 => i64::MIN,
+        }
+    }
+
+    /// Largest signed value a cell holds
+    pub fn max(self) -> i64 {
+        match self {
+            CellWidth::Bits32 => i32::MAX as i64,
+            CellWidth::Bits64 => i64::MAX,
+        }
+    }
+
+    /// Whether `value` fits in a cell as a signed number
+    pub fn contains(self, value: i64) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+}
 
 /// Compilation settings for Cranelift
 #[derive(Debug, Clone, Copy)]
@@ -29,6 +87,13 @@ pub struct CraneliftSettings {
     pub target_triple: Option<&'static str>,
     /// Enable IR verification (disabled in release builds for performance)
     pub enable_verification: bool,
+    /// Report signed overflow in `+ - * negate abs` as a runtime fault instead of wrapping
+    pub checked_arithmetic: bool,
+    /// Let programs run shell commands with `system`; compiling a program
+    /// that uses it fails while this is off
+    pub allow_system: bool,
+    /// Width of a cell
+    pub cell_width: CellWidth,
 }
 
 impl Default for CraneliftSettings {
@@ -39,6 +104,9 @@ impl Default for CraneliftSettings {
             target_triple: None,
             // Enable verification in debug builds, disable in release builds
             enable_verification: cfg!(debug_assertions),
+            checked_arithmetic: false,
+            allow_system: false,
+            cell_width: CellWidth::Bits64,
         }
     }
 }
@@ -51,6 +119,9 @@ impl CraneliftSettings {
             debug_info: true,
             target_triple: None,
             enable_verification: true,
+            checked_arithmetic: false,
+            allow_system: false,
+            cell_width: CellWidth::Bits64,
         }
     }
 
@@ -61,6 +132,9 @@ impl CraneliftSettings {
             debug_info: true,
             target_triple: None,
             enable_verification: true,
+            checked_arithmetic: false,
+            allow_system: false,
+            cell_width: CellWidth::Bits64,
         }
     }
 
@@ -71,6 +145,9 @@ impl CraneliftSettings {
             debug_info: false,
             target_triple: None,
             enable_verification: false, // Disable for maximum performance
+            checked_arithmetic: false,
+            allow_system: false,
+            cell_width: CellWidth::Bits64,
         }
     }
 }
@@ -92,4 +169,13 @@ mod tests {
         assert_eq!(settings.opt_level, 0);
         assert!(settings.debug_info);
     }
+
+    #[test]
+    fn test_cell_width_range() {
+        assert_eq!(CellWidth::default(), CellWidth::Bits64);
+        assert_eq!(CellWidth::Bits32.bytes(), 4);
+        assert!(CellWidth::Bits32.contains(i32::MIN as i64));
+        assert!(!CellWidth::Bits32.contains(i32::MAX as i64 + 1));
+        assert!(CellWidth::Bits64.contains(i64::MIN) && CellWidth::Bits64.contains(i64::MAX));
+    }
 }
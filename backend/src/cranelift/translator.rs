@@ -2,19 +2,20 @@
 //!
 //! Translates Fast Forth SSA representation to Cranelift IR for compilation.
 
+use crate::cranelift::{CellWidth, CraneliftSettings};
 use crate::error::{BackendError, Result};
 use fastforth_frontend::ssa::{
-    SSAFunction, SSAInstruction, Register, BlockId, BinaryOperator, UnaryOperator, BasicBlock,
+    SSAFunction, SSAInstruction, Register, BlockId, BinaryOperator, UnaryOperator, BasicBlock, MAIN_FUNCTION,
 };
 use fastforth_frontend::ast::StackType;
 
 use cranelift_codegen::ir::{
-    types, AbiParam, Block, Function, FuncRef, InstBuilder, Value,
+    types, AbiParam, Block, Function, FuncRef, GlobalValue, InstBuilder, Value,
 };
 use cranelift_codegen::isa::TargetIsa;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// Information about Phi nodes for a block
@@ -26,6 +27,16 @@ struct PhiInfo {
     incoming: Vec<(BlockId, Register)>,
 }
 
+/// What a translated function can refer to, imported into it beforehand
+pub struct TranslatorImports<'a> {
+    /// Map of function names to FuncRefs
+    pub func_refs: &'a HashMap<String, FuncRef>,
+    /// Map of FFI function names to FuncRefs
+    pub ffi_refs: &'a HashMap<String, FuncRef>,
+    /// Map of variable names to their storage
+    pub variable_refs: &'a HashMap<String, GlobalValue>,
+}
+
 /// Translator from Fast Forth SSA to Cranelift IR
 pub struct SSATranslator<'a> {
     builder: FunctionBuilder<'a>,
@@ -41,13 +52,25 @@ pub struct SSATranslator<'a> {
     func_refs: &'a HashMap<String, FuncRef>,
     /// Map of FFI function names to FuncRefs (pre-imported)
     ffi_refs: &'a HashMap<String, FuncRef>,
+    /// Map of variable names to their storage (pre-imported)
+    variable_refs: &'a HashMap<String, GlobalValue>,
     /// Actual control flow graph: tracks which blocks jump to which blocks
     /// This is built during translation and may differ from SSA Phi predecessors
     block_predecessors: HashMap<BlockId, Vec<BlockId>>,
+    /// Self-calls in tail position, keyed by (block, instruction index)
+    tail_calls: HashSet<(BlockId, usize)>,
+    /// Block that self tail calls jump back to (the SSA entry block)
+    loop_entry: Option<Block>,
     /// Target ISA for verification
     isa: &'a Arc<dyn TargetIsa>,
     /// Whether to enable IR verification
     enable_verification: bool,
+    /// Whether `+ - * negate abs` report signed overflow instead of wrapping
+    checked_arithmetic: bool,
+    /// Whether `system` may be compiled
+    allow_system: bool,
+    /// Width arithmetic and memory cells are computed at
+    cell_width: CellWidth,
 }
 
 impl<'a> SSATranslator<'a> {
@@ -55,10 +78,9 @@ impl<'a> SSATranslator<'a> {
     pub fn new(
         func: &'a mut Function,
         builder_ctx: &'a mut FunctionBuilderContext,
-        func_refs: &'a HashMap<String, FuncRef>,
-        ffi_refs: &'a HashMap<String, FuncRef>,
+        imports: TranslatorImports<'a>,
         isa: &'a Arc<dyn TargetIsa>,
-        enable_verification: bool,
+        settings: &CraneliftSettings,
     ) -> Self {
         let builder = FunctionBuilder::new(func, builder_ctx);
 
@@ -68,11 +90,17 @@ impl<'a> SSATranslator<'a> {
             block_map: HashMap::new(),
             phi_nodes: HashMap::new(),
             current_block: None,
-            func_refs,
-            ffi_refs,
+            func_refs: imports.func_refs,
+            ffi_refs: imports.ffi_refs,
+            variable_refs: imports.variable_refs,
             block_predecessors: HashMap::new(),
+            tail_calls: HashSet::new(),
+            loop_entry: None,
             isa,
-            enable_verification,
+            enable_verification: settings.enable_verification,
+            checked_arithmetic: settings.checked_arithmetic,
+            allow_system: settings.allow_system,
+            cell_width: settings.cell_width,
         }
     }
 
@@ -93,10 +121,69 @@ impl<'a> SSATranslator<'a> {
         }
     }
 
+    /// Find self-calls in tail position
+    ///
+    /// A call is in tail position when its result is returned directly, either by
+    /// an immediately following `Return` or by a jump to a block that only merges
+    /// the result through a Phi node and returns it.
+    fn find_self_tail_calls(ssa_func: &SSAFunction) -> HashSet<(BlockId, usize)> {
+        let mut tail_calls = HashSet::new();
+
+        for block in &ssa_func.blocks {
+            for (i, inst) in block.instructions.iter().enumerate() {
+                let (dest, args) = match inst {
+                    SSAInstruction::Call { dest, name, args } if *name == ssa_func.name => (dest, args),
+                    _ => continue,
+                };
+                // The stack effect must match the entry so parameter registers can be reused
+                if args.len() != ssa_func.parameters.len() || dest.len() != 1 {
+                    continue;
+                }
+
+                let is_tail = match &block.instructions[i + 1..] {
+                    [SSAInstruction::Return { values }] => values == dest,
+                    [SSAInstruction::Jump { target }] => ssa_func.blocks
+                        .iter()
+                        .find(|b| b.id == *target)
+                        .is_some_and(|merge| Self::returns_merged_value(merge, block.id, dest[0])),
+                    _ => false,
+                };
+
+                if is_tail {
+                    tail_calls.insert((block.id, i));
+                }
+            }
+        }
+
+        tail_calls
+    }
+
+    /// Check whether `merge` only returns the value `reg` flowing in from `from`
+    fn returns_merged_value(merge: &BasicBlock, from: BlockId, reg: Register) -> bool {
+        let (last, phis) = match merge.instructions.split_last() {
+            Some(split) => split,
+            None => return false,
+        };
+
+        let returned = match last {
+            SSAInstruction::Return { values } if values.len() == 1 => values[0],
+            _ => return false,
+        };
+
+        phis.iter().all(|inst| matches!(inst, SSAInstruction::Phi { .. }))
+            && phis.iter().any(|inst| matches!(
+                inst,
+                SSAInstruction::Phi { dest, incoming }
+                    if *dest == returned && incoming.contains(&(from, reg))
+            ))
+    }
+
     /// Translate entire SSA function to Cranelift IR
     pub fn translate(mut self, ssa_func: &SSAFunction) -> Result<()> {
         // First pass: analyze Phi nodes to determine block parameters
         self.analyze_phi_nodes(ssa_func);
+        self.tail_calls = Self::find_self_tail_calls(ssa_func);
+        let has_tail_calls = !self.tail_calls.is_empty();
 
         // Create Cranelift blocks for all SSA blocks
         for block in &ssa_func.blocks {
@@ -105,7 +192,14 @@ impl<'a> SSATranslator<'a> {
 
             // First block is entry block - add parameters
             if block.id == ssa_func.entry_block {
-                self.builder.append_block_params_for_function_params(cl_block);
+                if has_tail_calls {
+                    // Tail calls jump back here, so parameters arrive as block parameters
+                    for _ in &ssa_func.parameters {
+                        self.builder.append_block_param(cl_block, types::I64);
+                    }
+                } else {
+                    self.builder.append_block_params_for_function_params(cl_block);
+                }
             } else if let Some(phi_infos) = self.phi_nodes.get(&block.id) {
                 // Add block parameters for Phi nodes
                 for _ in phi_infos {
@@ -114,8 +208,21 @@ impl<'a> SSATranslator<'a> {
             }
         }
 
-        // Switch to entry block
         let entry_block = self.block_map[&ssa_func.entry_block];
+
+        // Cranelift's entry block cannot be a branch target, so loop through a
+        // separate entry when self tail calls are present
+        if has_tail_calls {
+            let function_entry = self.builder.create_block();
+            self.builder.append_block_params_for_function_params(function_entry);
+            self.builder.switch_to_block(function_entry);
+            let params = self.builder.block_params(function_entry).to_vec();
+            self.builder.ins().jump(entry_block, &params);
+            self.builder.seal_block(function_entry);
+            self.loop_entry = Some(entry_block);
+        }
+
+        // Switch to entry block
         self.builder.switch_to_block(entry_block);
 
         // Map function parameters to registers
@@ -180,7 +287,21 @@ impl<'a> SSATranslator<'a> {
             }
         }
 
-        for inst in &block.instructions {
+        for (i, inst) in block.instructions.iter().enumerate() {
+            if self.tail_calls.contains(&(block.id, i)) {
+                // Self tail call: rebind the parameters and loop back to the entry
+                if let SSAInstruction::Call { args, .. } = inst {
+                    let arg_values: Vec<Value> = args
+                        .iter()
+                        .map(|&reg| self.get_register(reg))
+                        .collect::<Result<Vec<_>>>()?;
+                    let entry_block = self.loop_entry.ok_or_else(|| BackendError::CodeGeneration(
+                        "Tail call without a loop entry block".to_string()
+                    ))?;
+                    self.builder.ins().jump(entry_block, &arg_values);
+                }
+                break;
+            }
             self.translate_instruction(inst)?;
         }
 
@@ -204,12 +325,56 @@ impl<'a> SSATranslator<'a> {
                 let left_val = self.get_register(*left)?;
                 let right_val = self.get_register(*right)?;
 
+                // Operations that can leave the cell range run at the cell width
+                let narrow = self.cell_width != CellWidth::Bits64
+                    && matches!(
+                        op,
+                        BinaryOperator::Add
+                            | BinaryOperator::Sub
+                            | BinaryOperator::Mul
+                            | BinaryOperator::Div
+                            | BinaryOperator::Mod
+                            | BinaryOperator::Shl
+                            | BinaryOperator::Shr
+                            | BinaryOperator::Sar
+                    );
+                let (left_val, right_val) = if narrow {
+                    (self.narrow_to_cell(left_val), self.narrow_to_cell(right_val))
+                } else {
+                    (left_val, right_val)
+                };
+
                 let result = match op {
+                    BinaryOperator::Add if self.checked_arithmetic => {
+                        let (sum, overflow) = self.builder.ins().sadd_overflow(left_val, right_val);
+                        self.guard_runtime_fault(crate::cranelift::ffi::OVERFLOW_HOOK, overflow)?;
+                        sum
+                    }
+                    BinaryOperator::Sub if self.checked_arithmetic => {
+                        let (difference, overflow) = self.builder.ins().ssub_overflow(left_val, right_val);
+                        self.guard_runtime_fault(crate::cranelift::ffi::OVERFLOW_HOOK, overflow)?;
+                        difference
+                    }
+                    BinaryOperator::Mul if self.checked_arithmetic => {
+                        let (product, overflow) = self.builder.ins().smul_overflow(left_val, right_val);
+                        self.guard_runtime_fault(crate::cranelift::ffi::OVERFLOW_HOOK, overflow)?;
+                        product
+                    }
                     BinaryOperator::Add => self.builder.ins().iadd(left_val, right_val),
                     BinaryOperator::Sub => self.builder.ins().isub(left_val, right_val),
                     BinaryOperator::Mul => self.builder.ins().imul(left_val, right_val),
-                    BinaryOperator::Div => self.builder.ins().sdiv(left_val, right_val),
-                    BinaryOperator::Mod => self.builder.ins().srem(left_val, right_val),
+                    BinaryOperator::Div | BinaryOperator::Mod => {
+                        self.translate_guarded_division(*op, left_val, right_val)?
+                    }
+                    BinaryOperator::Min | BinaryOperator::Max => {
+                        let cc = if *op == BinaryOperator::Min {
+                            cranelift_codegen::ir::condcodes::IntCC::SignedLessThan
+                        } else {
+                            cranelift_codegen::ir::condcodes::IntCC::SignedGreaterThan
+                        };
+                        let keep_left = self.builder.ins().icmp(cc, left_val, right_val);
+                        self.builder.ins().select(keep_left, left_val, right_val)
+                    }
                     BinaryOperator::Lt => {
                         let cmp = self.builder.ins().icmp(
                             cranelift_codegen::ir::condcodes::IntCC::SignedLessThan,
@@ -258,9 +423,31 @@ impl<'a> SSATranslator<'a> {
                         );
                         self.builder.ins().uextend(types::I64, cmp)
                     }
+                    BinaryOperator::ULt => {
+                        let cmp = self.builder.ins().icmp(
+                            cranelift_codegen::ir::condcodes::IntCC::UnsignedLessThan,
+                            left_val,
+                            right_val,
+                        );
+                        self.builder.ins().uextend(types::I64, cmp)
+                    }
+                    BinaryOperator::UGt => {
+                        let cmp = self.builder.ins().icmp(
+                            cranelift_codegen::ir::condcodes::IntCC::UnsignedGreaterThan,
+                            left_val,
+                            right_val,
+                        );
+                        self.builder.ins().uextend(types::I64, cmp)
+                    }
                     BinaryOperator::And => self.builder.ins().band(left_val, right_val),
                     BinaryOperator::Or => self.builder.ins().bor(left_val, right_val),
+                    BinaryOperator::Xor => self.builder.ins().bxor(left_val, right_val),
+                    // Cranelift masks the shift count to the operand width
+                    BinaryOperator::Shl => self.builder.ins().ishl(left_val, right_val),
+                    BinaryOperator::Shr => self.builder.ins().ushr(left_val, right_val),
+                    BinaryOperator::Sar => self.builder.ins().sshr(left_val, right_val),
                 };
+                let result = if narrow { self.widen_from_cell(result) } else { result };
 
                 self.register_values.insert(*dest, result);
             }
@@ -268,9 +455,33 @@ impl<'a> SSATranslator<'a> {
             SSAInstruction::UnaryOp { dest, op, operand } => {
                 let operand_val = self.get_register(*operand)?;
 
+                // Operations that can leave the cell range run at the cell width
+                let narrow = self.cell_width != CellWidth::Bits64
+                    && !matches!(
+                        op,
+                        UnaryOperator::Not | UnaryOperator::ZeroEq | UnaryOperator::ZeroLt | UnaryOperator::ZeroGt
+                    );
+                let operand_val = if narrow { self.narrow_to_cell(operand_val) } else { operand_val };
+                let ty = self.builder.func.dfg.value_type(operand_val);
+
+                // Each overflowing unary operation overflows on exactly one input
+                let overflowing_input = match op {
+                    UnaryOperator::Negate | UnaryOperator::Abs | UnaryOperator::DecOne => Some(self.cell_width.min()),
+                    UnaryOperator::IncOne => Some(self.cell_width.max()),
+                    _ => None,
+                };
+                if let Some(input) = overflowing_input.filter(|_| self.checked_arithmetic) {
+                    let overflow = self.builder.ins().icmp_imm(
+                        cranelift_codegen::ir::condcodes::IntCC::Equal,
+                        operand_val,
+                        input,
+                    );
+                    self.guard_runtime_fault(crate::cranelift::ffi::OVERFLOW_HOOK, overflow)?;
+                }
+
                 let result = match op {
                     UnaryOperator::Negate => {
-                        let zero = self.builder.ins().iconst(types::I64, 0);
+                        let zero = self.builder.ins().iconst(ty, 0);
                         self.builder.ins().isub(zero, operand_val)
                     }
                     UnaryOperator::Not => {
@@ -279,7 +490,7 @@ impl<'a> SSATranslator<'a> {
                     }
                     UnaryOperator::Abs => {
                         // abs(x) = (x < 0) ? -x : x
-                        let zero = self.builder.ins().iconst(types::I64, 0);
+                        let zero = self.builder.ins().iconst(ty, 0);
                         let is_neg = self.builder.ins().icmp(
                             cranelift_codegen::ir::condcodes::IntCC::SignedLessThan,
                             operand_val,
@@ -288,25 +499,60 @@ impl<'a> SSATranslator<'a> {
                         let negated = self.builder.ins().isub(zero, operand_val);
                         self.builder.ins().select(is_neg, negated, operand_val)
                     }
+                    UnaryOperator::ZeroEq | UnaryOperator::ZeroLt | UnaryOperator::ZeroGt => {
+                        let cc = match op {
+                            UnaryOperator::ZeroEq => cranelift_codegen::ir::condcodes::IntCC::Equal,
+                            UnaryOperator::ZeroLt => cranelift_codegen::ir::condcodes::IntCC::SignedLessThan,
+                            _ => cranelift_codegen::ir::condcodes::IntCC::SignedGreaterThan,
+                        };
+                        let zero = self.builder.ins().iconst(types::I64, 0);
+                        let cmp = self.builder.ins().icmp(cc, operand_val, zero);
+                        self.builder.ins().uextend(types::I64, cmp)
+                    }
+                    UnaryOperator::IncOne => self.builder.ins().iadd_imm(operand_val, 1),
+                    UnaryOperator::DecOne => self.builder.ins().iadd_imm(operand_val, -1),
+                    UnaryOperator::MulTwo => self.builder.ins().ishl_imm(operand_val, 1),
+                    UnaryOperator::DivTwo => self.builder.ins().sshr_imm(operand_val, 1),
                 };
+                let result = if narrow { self.widen_from_cell(result) } else { result };
 
                 self.register_values.insert(*dest, result);
             }
 
+            SSAInstruction::VariableAddr { dest, name, .. } => {
+                let gv = self.variable_refs.get(name)
+                    .copied()
+                    .ok_or_else(|| BackendError::CodeGeneration(format!("Variable '{}' has no storage", name)))?;
+                let addr = self.builder.ins().global_value(types::I64, gv);
+                self.register_values.insert(*dest, addr);
+            }
+
+            SSAInstruction::ExecutionToken { dest, word } => {
+                let func_ref = self.func_refs.get(word)
+                    .copied()
+                    .ok_or_else(|| BackendError::CodeGeneration(format!("Word '{}' has no execution token", word)))?;
+                let xt = self.builder.ins().func_addr(types::I64, func_ref);
+                self.register_values.insert(*dest, xt);
+            }
+
             SSAInstruction::Load { dest, address, ty } => {
                 let addr_val = self.get_register(*address)?;
 
                 use cranelift_codegen::ir::MemFlags;
 
                 let result = match ty {
+                    StackType::Int | StackType::Addr if self.cell_width == CellWidth::Bits32 => {
+                        self.builder.ins().sload32(MemFlags::new(), addr_val, 0)
+                    }
                     StackType::Int | StackType::Addr => {
                         self.builder.ins().load(types::I64, MemFlags::new(), addr_val, 0)
                     }
                     StackType::Float => {
                         self.builder.ins().load(types::F64, MemFlags::new(), addr_val, 0)
                     }
+                    // Bytes are zero-extended to a full cell
                     StackType::Bool | StackType::Char => {
-                        self.builder.ins().load(types::I8, MemFlags::new(), addr_val, 0)
+                        self.builder.ins().uload8(types::I64, MemFlags::new(), addr_val, 0)
                     }
                     StackType::String | StackType::Var(_) | StackType::Unknown => {
                         // For unknown or complex types, default to I64
@@ -323,7 +569,11 @@ impl<'a> SSATranslator<'a> {
                 let addr_val = self.get_register(*address)?;
                 let val = self.get_register(*value)?;
 
-                self.builder.ins().store(MemFlags::new(), val, addr_val, 0);
+                if matches!(ty, StackType::Int | StackType::Addr) && self.cell_width == CellWidth::Bits32 {
+                    self.builder.ins().istore32(MemFlags::new(), val, addr_val, 0);
+                } else {
+                    self.builder.ins().store(MemFlags::new(), val, addr_val, 0);
+                }
             }
 
             SSAInstruction::Branch { condition, true_block, false_block } => {
@@ -377,10 +627,18 @@ impl<'a> SSATranslator<'a> {
                 self.builder.ins().return_(&return_vals);
             }
 
+            SSAInstruction::Call { dest, name, args } if name == "execute" && !self.func_refs.contains_key(name) => {
+                self.translate_execute(dest, args)?;
+            }
+
             SSAInstruction::Call { dest, name, args } => {
-                // Look up the pre-imported function reference
-                let func_ref = self.func_refs.get(name)
-                    .copied()
+                // Output words go to their runtime hooks unless the program
+                // redefines them; anything else must be a pre-imported
+                // function reference
+                let func_ref = match crate::cranelift::ffi::output_hook(name) {
+                    Some(hook) if !self.func_refs.contains_key(name.as_str()) => self.ffi_refs.get(hook).copied(),
+                    _ => self.func_refs.get(name).copied(),
+                }
                     .ok_or_else(|| BackendError::CodeGeneration(
                         format!("Function '{}' not declared/imported", name)
                     ))?;
@@ -396,6 +654,9 @@ impl<'a> SSATranslator<'a> {
 
                 // Get the return values and convert to Vec to avoid borrow conflicts
                 let results: Vec<Value> = self.builder.inst_results(call).to_vec();
+                if self.func_refs.contains_key(name.as_str()) {
+                    self.propagate_callee_fault()?;
+                }
 
                 // Map return values to destination registers
                 for (i, &dest_reg) in dest.iter().enumerate() {
@@ -624,25 +885,41 @@ impl<'a> SSATranslator<'a> {
             }
 
             SSAInstruction::FileDelete { dest_ior, path_addr, path_len } => {
-                // Get remove FFI function reference
-                let remove_ref = self.ffi_refs.get("remove")
-                    .copied()
-                    .ok_or_else(|| BackendError::CodeGeneration(
-                        "FFI function 'remove' not registered".to_string()
-                    ))?;
+                // The runtime hook takes the counted string as is, with no
+                // NUL terminator, and returns the ior directly
+                let delete_ref = self.runtime_hook(crate::cranelift::ffi::DELETE_FILE_HOOK)?;
 
-                // Get path pointer
                 let path_ptr = self.get_register(*path_addr)?;
+                let path_len_val = self.get_register(*path_len)?;
 
-                // Call remove(path)
-                let call = self.builder.ins().call(remove_ref, &[path_ptr]);
+                let call = self.builder.ins().call(delete_ref, &[path_ptr, path_len_val]);
                 let result = self.builder.inst_results(call)[0];
 
-                // Convert i32 result to i64
-                let result_i64 = self.builder.ins().sextend(types::I64, result);
+                self.register_values.insert(*dest_ior, result);
+            }
 
-                // remove returns 0 on success, non-zero on error
-                self.register_values.insert(*dest_ior, result_i64);
+            SSAInstruction::FileStatus { dest_status, dest_ior, path_addr, path_len } => {
+                // The hook returns the file's mode bits, or a negative ior
+                let status_ref = self.runtime_hook(crate::cranelift::ffi::FILE_STATUS_HOOK)?;
+
+                let path_ptr = self.get_register(*path_addr)?;
+                let path_len_val = self.get_register(*path_len)?;
+
+                let call = self.builder.ins().call(status_ref, &[path_ptr, path_len_val]);
+                let result = self.builder.inst_results(call)[0];
+
+                // Split into ( x ior ): x is 0 whenever ior is nonzero
+                let zero = self.builder.ins().iconst(types::I64, 0);
+                let failed = self.builder.ins().icmp_imm(
+                    cranelift_codegen::ir::condcodes::IntCC::SignedLessThan,
+                    result,
+                    0,
+                );
+                let status = self.builder.ins().select(failed, zero, result);
+                let ior = self.builder.ins().select(failed, result, zero);
+
+                self.register_values.insert(*dest_status, status);
+                self.register_values.insert(*dest_ior, ior);
             }
 
             SSAInstruction::FileCreate { dest_fileid, dest_ior, path_addr, path_len, mode } => {
@@ -685,26 +962,22 @@ impl<'a> SSATranslator<'a> {
             }
 
             SSAInstruction::SystemCall { dest, command_addr, command_len } => {
-                // Get system FFI function reference
-                let system_ref = self.ffi_refs.get("system")
-                    .copied()
-                    .ok_or_else(|| BackendError::CodeGeneration(
-                        "FFI function 'system' not registered".to_string()
-                    ))?;
+                if !self.allow_system {
+                    return Err(BackendError::UnsupportedFeature(
+                        "`system` runs shell commands and is disabled; enable CraneliftSettings::allow_system".to_string()
+                    ));
+                }
+
+                let system_ref = self.runtime_hook(crate::cranelift::ffi::SYSTEM_HOOK)?;
 
-                // Get command pointer
                 let command_ptr = self.get_register(*command_addr)?;
+                let command_len_val = self.get_register(*command_len)?;
 
-                // Call system(command)
-                let call = self.builder.ins().call(system_ref, &[command_ptr]);
+                // The hook returns the command's exit status
+                let call = self.builder.ins().call(system_ref, &[command_ptr, command_len_val]);
                 let result = self.builder.inst_results(call)[0];
 
-                // Convert i32 result to i64
-                let result_i64 = self.builder.ins().sextend(types::I64, result);
-
-                // system returns exit code of command
-                // Store in destination register
-                self.register_values.insert(*dest, result_i64);
+                self.register_values.insert(*dest, result);
             }
         }
 
@@ -720,6 +993,158 @@ impl<'a> SSATranslator<'a> {
             ))
     }
 
+    /// Narrow a register value to the cell type
+    fn narrow_to_cell(&mut self, value: Value) -> Value {
+        self.builder.ins().ireduce(self.cell_width.ir_type(), value)
+    }
+
+    /// Sign-extend a cell-typed value back to register width
+    fn widen_from_cell(&mut self, value: Value) -> Value {
+        self.builder.ins().sextend(types::I64, value)
+    }
+
+    /// Call the word whose execution token is the last of `args`, passing it the rest
+    ///
+    /// Tokens are word addresses, so this is an indirect call. Only the
+    /// token of a word taking that many parameters is valid; anything else
+    /// calls the runtime hook and returns zeros, like a zero divisor.
+    fn translate_execute(&mut self, dest: &[Register], args: &[Register]) -> Result<()> {
+        let (&token, params) = args.split_last()
+            .ok_or_else(|| BackendError::CodeGeneration("EXECUTE without a token".to_string()))?;
+        let xt = self.get_register(token)?;
+        let param_values: Vec<Value> = params
+            .iter()
+            .map(|&reg| self.get_register(reg))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Every word with the right signature, in a fixed order
+        let mut candidates: Vec<(&String, FuncRef)> = self.func_refs
+            .iter()
+            .filter(|(name, &func_ref)| {
+                let signature = self.builder.func.dfg.ext_funcs[func_ref].signature;
+                let param_count = self.builder.func.dfg.signatures[signature].params.len();
+                name.as_str() != MAIN_FUNCTION && param_count == params.len()
+            })
+            .map(|(name, &func_ref)| (name, func_ref))
+            .collect();
+        candidates.sort();
+
+        let mut valid = self.builder.ins().iconst(types::I8, 0);
+        for &(_, func_ref) in &candidates {
+            let addr = self.builder.ins().func_addr(types::I64, func_ref);
+            let matches = self.builder.ins().icmp(cranelift_codegen::ir::condcodes::IntCC::Equal, xt, addr);
+            valid = self.builder.ins().bor(valid, matches);
+        }
+        let invalid = self.builder.ins().icmp_imm(cranelift_codegen::ir::condcodes::IntCC::Equal, valid, 0);
+        self.guard_runtime_fault(crate::cranelift::ffi::INVALID_XT_HOOK, invalid)?;
+
+        let result = match candidates.first() {
+            Some(&(_, func_ref)) => {
+                let signature = self.builder.func.dfg.ext_funcs[func_ref].signature;
+                let call = self.builder.ins().call_indirect(signature, xt, &param_values);
+                let result = self.builder.inst_results(call)[0];
+                self.propagate_callee_fault()?;
+                result
+            }
+            // No token is valid, so this is never reached
+            None => self.builder.ins().iconst(types::I64, 0),
+        };
+        if let Some(&dest) = dest.first() {
+            self.register_values.insert(dest, result);
+        }
+        Ok(())
+    }
+
+    /// Emit a division/modulo guarded against a zero divisor and i64::MIN / -1
+    ///
+    /// A zero divisor calls the runtime hook and returns zeros from the function
+    /// instead of raising a hardware trap. A -1 divisor is computed as negation
+    /// (wrapping) so i64::MIN / -1 cannot overflow.
+    fn translate_guarded_division(&mut self, op: BinaryOperator, left: Value, right: Value) -> Result<Value> {
+        use cranelift_codegen::ir::condcodes::IntCC;
+
+        let is_zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+        self.guard_runtime_fault(crate::cranelift::ffi::DIV_BY_ZERO_HOOK, is_zero)?;
+
+        let ty = self.builder.func.dfg.value_type(right);
+        let is_neg_one = self.builder.ins().icmp_imm(IntCC::Equal, right, -1);
+        let one = self.builder.ins().iconst(ty, 1);
+        let safe_divisor = self.builder.ins().select(is_neg_one, one, right);
+
+        let result = match op {
+            BinaryOperator::Div => {
+                let quotient = self.builder.ins().sdiv(left, safe_divisor);
+                let negated = self.builder.ins().ineg(left);
+                self.builder.ins().select(is_neg_one, negated, quotient)
+            }
+            _ => {
+                let remainder = self.builder.ins().srem(left, safe_divisor);
+                let zero = self.builder.ins().iconst(ty, 0);
+                self.builder.ins().select(is_neg_one, zero, remainder)
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// Function reference for the runtime hook `hook`
+    fn runtime_hook(&self, hook: &str) -> Result<FuncRef> {
+        self.ffi_refs.get(hook)
+            .copied()
+            .ok_or_else(|| BackendError::CodeGeneration(
+                format!("Runtime hook '{}' not registered", hook)
+            ))
+    }
+
+    /// Report a fault through the runtime hook `hook` and return zeros when
+    /// `condition` holds
+    fn guard_runtime_fault(&mut self, hook: &str, condition: Value) -> Result<()> {
+        let hook_ref = self.runtime_hook(hook)?;
+        self.return_on_fault(condition, Some(hook_ref));
+        Ok(())
+    }
+
+    /// Return zeros straight after a call whose callee raised a fault, so
+    /// execution stops in every caller up to the host
+    fn propagate_callee_fault(&mut self) -> Result<()> {
+        let pending_ref = self.runtime_hook(crate::cranelift::ffi::FAULT_PENDING_HOOK)?;
+        let call = self.builder.ins().call(pending_ref, &[]);
+        let pending = self.builder.inst_results(call)[0];
+        let faulted = self.builder.ins().icmp_imm(cranelift_codegen::ir::condcodes::IntCC::NotEqual, pending, 0);
+        self.return_on_fault(faulted, None);
+        Ok(())
+    }
+
+    /// Branch to a cold block that calls `hook`, if any, and returns zeros
+    /// when `condition` holds; translation continues on the normal path
+    fn return_on_fault(&mut self, condition: Value, hook: Option<FuncRef>) {
+        let trap_block = self.builder.create_block();
+        let cont_block = self.builder.create_block();
+
+        self.builder.ins().brif(condition, trap_block, &[], cont_block, &[]);
+        self.builder.seal_block(trap_block);
+        self.builder.seal_block(cont_block);
+
+        // Trap path: report the fault and bail out of the function
+        self.builder.switch_to_block(trap_block);
+        self.builder.set_cold_block(trap_block);
+        if let Some(hook_ref) = hook {
+            self.builder.ins().call(hook_ref, &[]);
+        }
+        let return_types: Vec<_> = self.builder.func.signature.returns
+            .iter()
+            .map(|param| param.value_type)
+            .collect();
+        let zeros: Vec<Value> = return_types
+            .into_iter()
+            .map(|ty| self.builder.ins().iconst(ty, 0))
+            .collect();
+        self.builder.ins().return_(&zeros);
+
+        // Normal path
+        self.builder.switch_to_block(cont_block);
+    }
+
     /// Collect arguments for a branch based on target block's Phi nodes
     fn collect_branch_args(&self, target_block: BlockId, from_block: &BlockId) -> Result<Vec<Value>> {
         if let Some(phi_infos) = self.phi_nodes.get(&target_block) {
@@ -2,11 +2,19 @@
 
 use anyhow::{Context, Result};
 use backend::cranelift::{CraneliftBackend, CraneliftSettings};
-use fastforth_frontend::{parse_program, convert_to_ssa};
+use fastforth_frontend::ast::SourceLocation;
+use fastforth_frontend::ssa::SSAInstruction;
+use fastforth_frontend::{parse_program, convert_to_ssa, SSAFunction, Word, MAIN_FUNCTION};
 use std::path::Path;
+use std::time::Instant;
 
 /// Execute a Forth program with JIT compilation
-pub fn execute_program(source: &str, verbose: bool) -> Result<i64> {
+///
+/// Returns the value on top of the stack after the top-level code runs, or
+/// `None` if there is no top-level code to run (an empty program, only
+/// comments, or only definitions). Top-level code that leaves the stack
+/// empty still runs and returns 0, the JIT's value for an empty stack.
+pub fn execute_program(source: &str, verbose: bool) -> Result<Option<i64>> {
     // Phase 1: Parse
     if verbose {
         println!("  Parsing...");
@@ -30,6 +38,109 @@ pub fn execute_program(source: &str, verbose: bool) -> Result<i64> {
     }
 
     // Phase 3: JIT compile with Cranelift
+    let backend = jit_compile(&ssa_functions, verbose)?;
+
+    // Phase 4: Execute
+    if verbose {
+        println!("  Executing...");
+    }
+
+    // Only top-level code runs; definitions are compiled but never called
+    let has_main = program.top_level_code.iter().any(|word| !matches!(word, Word::Comment(_)));
+    if !has_main {
+        if verbose {
+            println!("  No top-level code to execute");
+        }
+        return Ok(None);
+    }
+
+    let return_count = 1; // All Forth functions return 1 value
+
+    let main_func_ptr = backend.get_function(MAIN_FUNCTION)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get compiled function"))?;
+
+    // Call function based on its return count
+    let result = match return_count {
+        0 => {
+            // Function returns nothing
+            type ForthFn = unsafe extern "C" fn();
+            let forth_fn: ForthFn = unsafe { std::mem::transmute(main_func_ptr) };
+            unsafe { forth_fn() };
+            0
+        }
+        1 => {
+            // Function returns one i64 value
+            type ForthFn = unsafe extern "C" fn() -> i64;
+            let forth_fn: ForthFn = unsafe { std::mem::transmute(main_func_ptr) };
+            unsafe { forth_fn() }
+        }
+        _ => {
+            return Err(anyhow::anyhow!("Functions with multiple return values not yet supported in execute harness"));
+        }
+    };
+
+    Ok(Some(result))
+}
+
+/// Execute a Forth program and return the whole data stack it leaves,
+/// bottom first
+///
+/// Compiled code returns a single value, so the top-level code is extended
+/// to store each remaining item into a buffer and return the depth instead.
+/// An empty program, or one that empties the stack, returns an empty vec.
+pub fn execute_program_full(source: &str) -> Result<Vec<i64>> {
+    let mut program = parse_program(source)
+        .map_err(|e| anyhow::anyhow!("Failed to parse: {}", e))?;
+
+    // :main returns 0 for an empty stack, so measure the depth with one
+    // extra value pushed and discount it
+    let mut probe = program.clone();
+    probe.top_level_code.push(Word::IntLiteral(0));
+    let depth = convert_to_ssa(&probe)
+        .map_err(|e| anyhow::anyhow!("Failed to convert to SSA: {}", e))?
+        .iter()
+        .find(|func| func.name == MAIN_FUNCTION)
+        .and_then(|main| {
+            main.blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+                .filter_map(|inst| match inst {
+                    SSAInstruction::Return { values } => Some(values.len()),
+                    _ => None,
+                })
+                .max()
+        })
+        .map_or(0, |returned| returned.saturating_sub(1));
+
+    let mut results = vec![0i64; depth];
+    let base = results.as_mut_ptr() as i64;
+    for slot in (0..depth).rev() {
+        program.top_level_code.push(Word::IntLiteral(base + (slot * 8) as i64));
+        program.top_level_code.push(Word::WordRef {
+            name: "!".to_string(),
+            location: SourceLocation::default(),
+        });
+    }
+    program.top_level_code.push(Word::IntLiteral(depth as i64));
+
+    let ssa_functions = convert_to_ssa(&program)
+        .map_err(|e| anyhow::anyhow!("Failed to convert to SSA: {}", e))?;
+    let backend = jit_compile(&ssa_functions, false)?;
+
+    let main_func_ptr = backend.get_function(MAIN_FUNCTION)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get compiled function"))?;
+    type ForthFn = unsafe extern "C" fn() -> i64;
+    let forth_fn: ForthFn = unsafe { std::mem::transmute(main_func_ptr) };
+    let returned = unsafe { forth_fn() };
+
+    if returned != depth as i64 {
+        return Err(anyhow::anyhow!("Expected {} stack items, program reported {}", depth, returned));
+    }
+    Ok(results)
+}
+
+/// JIT-compile every function, ready to be called
+fn jit_compile(ssa_functions: &[SSAFunction], verbose: bool) -> Result<CraneliftBackend> {
     if verbose {
         println!("  JIT compiling...");
     }
@@ -38,6 +149,7 @@ pub fn execute_program(source: &str, verbose: bool) -> Result<i64> {
         opt_level: 1,
         debug_info: false,
         target_triple: None,
+        ..CraneliftSettings::default()
     };
 
     let mut backend = CraneliftBackend::new(settings)
@@ -68,48 +180,139 @@ pub fn execute_program(source: &str, verbose: bool) -> Result<i64> {
         println!("  Compiled {} functions", ssa_functions.len());
     }
 
-    // Phase 4: Execute
-    if verbose {
-        println!("  Executing...");
+    Ok(backend)
+}
+
+/// Per-call timings from [`bench_program`]
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    /// Word that was timed, or `:main` for the top-level code
+    pub word: String,
+    /// Calls measured, after warm-up
+    pub iterations: usize,
+    /// Calls per timed sample; fast words are timed in batches so the
+    /// timer's resolution does not dominate
+    pub batch: usize,
+    /// Fastest sample, in nanoseconds per call
+    pub min_ns: f64,
+    /// Median sample, in nanoseconds per call
+    pub median_ns: f64,
+    /// Mean over all samples, in nanoseconds per call
+    pub mean_ns: f64,
+    /// Slowest sample, in nanoseconds per call
+    pub max_ns: f64,
+    /// Top of the stack after the last call
+    pub result: i64,
+}
+
+impl BenchReport {
+    /// Calls per second at the mean call time
+    pub fn throughput(&self) -> f64 {
+        if self.mean_ns > 0.0 {
+            1e9 / self.mean_ns
+        } else {
+            f64::INFINITY
+        }
     }
+}
 
-    // Get the last compiled function (which will be :main if top-level code exists)
-    if ssa_functions.is_empty() {
-        return Ok(0);
+/// Shortest span a timed sample should cover
+const MIN_SAMPLE_NS: u128 = 10_000;
+
+/// JIT-compile a program once and time `iterations` calls of one word
+///
+/// `word` defaults to the top-level code if there is any, otherwise the last
+/// definition. A word is called with `args` on the stack (deepest first), or
+/// with a zero for each input it takes if `args` is empty. Every call starts
+/// from an empty stack, but side effects such as output repeat on each call,
+/// including the `warmup` calls made before measuring.
+pub fn bench_program(
+    source: &str,
+    word: Option<&str>,
+    args: &[i64],
+    iterations: usize,
+    warmup: usize,
+) -> Result<BenchReport> {
+    if iterations == 0 {
+        return Err(anyhow::anyhow!("Benchmark needs at least one iteration"));
     }
 
-    // Execute the last function (usually :main if top-level code exists)
-    let func_name = &ssa_functions.last().unwrap().name;
-    let return_count = 1; // All Forth functions return 1 value
+    let mut program = parse_program(source)
+        .map_err(|e| anyhow::anyhow!("Failed to parse: {}", e))?;
+    let has_main = program.top_level_code.iter().any(|word| !matches!(word, Word::Comment(_)));
+    let word = match word {
+        Some(word) => Some(word.to_string()),
+        None if has_main => None,
+        None => Some(
+            program.definitions.last()
+                .map(|def| def.name.clone())
+                .ok_or_else(|| anyhow::anyhow!("Nothing to benchmark: no definitions or top-level code"))?,
+        ),
+    };
+
+    // Time a word through top-level code that pushes its arguments and calls it
+    if let Some(name) = &word {
+        let args = if args.is_empty() {
+            let inputs = convert_to_ssa(&program)
+                .map_err(|e| anyhow::anyhow!("Failed to convert to SSA: {}", e))?
+                .iter()
+                .find(|func| &func.name == name)
+                .map(|func| func.parameters.len())
+                .ok_or_else(|| anyhow::anyhow!("Unknown word '{}'", name))?;
+            vec![0; inputs]
+        } else {
+            args.to_vec()
+        };
+
+        program.top_level_code = args.into_iter().map(Word::IntLiteral).collect();
+        program.top_level_code.push(Word::WordRef {
+            name: name.clone(),
+            location: SourceLocation::default(),
+        });
+    }
 
-    let main_func_ptr = backend.get_function(func_name)
+    let ssa_functions = convert_to_ssa(&program)
+        .map_err(|e| anyhow::anyhow!("Failed to convert to SSA: {}", e))?;
+    let backend = jit_compile(&ssa_functions, false)?;
+    let entry = backend.get_entry_point(MAIN_FUNCTION)
         .ok_or_else(|| anyhow::anyhow!("Failed to get compiled function"))?;
 
-    // Call function based on its return count
-    let result = match return_count {
-        0 => {
-            // Function returns nothing
-            type ForthFn = unsafe extern "C" fn();
-            let forth_fn: ForthFn = unsafe { std::mem::transmute(main_func_ptr) };
-            unsafe { forth_fn() };
-            0
-        }
-        1 => {
-            // Function returns one i64 value
-            type ForthFn = unsafe extern "C" fn() -> i64;
-            let forth_fn: ForthFn = unsafe { std::mem::transmute(main_func_ptr) };
-            unsafe { forth_fn() }
-        }
-        _ => {
-            return Err(anyhow::anyhow!("Functions with multiple return values not yet supported in execute harness"));
+    let mut result = 0;
+    let warm_start = Instant::now();
+    for _ in 0..warmup.max(1) {
+        result = unsafe { entry() };
+    }
+    let call_ns = warm_start.elapsed().as_nanos() / warmup.max(1) as u128;
+    let batch = (MIN_SAMPLE_NS / call_ns.max(1)).clamp(1, iterations as u128) as usize;
+
+    let mut samples = Vec::with_capacity(iterations / batch + 1);
+    let mut remaining = iterations;
+    while remaining > 0 {
+        let calls = batch.min(remaining);
+        let start = Instant::now();
+        for _ in 0..calls {
+            result = unsafe { entry() };
         }
-    };
+        samples.push(start.elapsed().as_nanos() as f64 / calls as f64);
+        remaining -= calls;
+    }
 
-    Ok(result)
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let mean_ns = samples.iter().sum::<f64>() / samples.len() as f64;
+    Ok(BenchReport {
+        word: word.unwrap_or_else(|| MAIN_FUNCTION.to_string()),
+        iterations,
+        batch,
+        min_ns: samples[0],
+        median_ns: samples[samples.len() / 2],
+        mean_ns,
+        max_ns: samples[samples.len() - 1],
+        result,
+    })
 }
 
 /// Execute a Forth file
-pub fn execute_file(path: &Path, verbose: bool) -> Result<i64> {
+pub fn execute_file(path: &Path, verbose: bool) -> Result<Option<i64>> {
     let source = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read {}", path.display()))?;
 
@@ -124,21 +327,67 @@ mod tests {
     fn test_execute_simple() {
         let result = execute_program(": double 2 * ; 5 double", false);
         assert!(result.is_ok(), "Failed to execute: {:?}", result);
-        assert_eq!(result.unwrap(), 10, "Expected 5 * 2 = 10");
+        assert_eq!(result.unwrap(), Some(10), "Expected 5 * 2 = 10");
     }
 
     #[test]
     fn test_execute_toplevel_constant() {
         let result = execute_program("42", true);
         assert!(result.is_ok(), "Failed to execute top-level constant: {:?}", result);
-        assert_eq!(result.unwrap(), 42, "Top-level constant should return 42");
+        assert_eq!(result.unwrap(), Some(42), "Top-level constant should return 42");
     }
 
     #[test]
     fn test_execute_definition_only() {
         let result = execute_program(": answer 42 ;", true);
         assert!(result.is_ok(), "Failed to compile definition: {:?}", result);
-        // Definition only, no execution, should return 0
-        assert_eq!(result.unwrap(), 0);
+        // Definition only: nothing is executed, so there is no result
+        assert_eq!(result.unwrap(), None);
+
+        // Top-level code before the final definition still runs
+        assert_eq!(execute_program("7 : answer 42 ;", false).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn test_execute_empty_program() {
+        assert_eq!(execute_program("", false).unwrap(), None);
+        assert_eq!(execute_program("\\ just a comment\n( and another )", false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_execute_toplevel_leaving_empty_stack() {
+        assert_eq!(execute_program("1 2 + drop", false).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_execute_full_stack() {
+        assert_eq!(execute_program_full("1 2 3").unwrap(), vec![1, 2, 3]);
+        assert_eq!(execute_program_full(": sq dup * ; 2 3 sq").unwrap(), vec![2, 9]);
+        assert_eq!(execute_program_full("").unwrap(), Vec::<i64>::new());
+        assert_eq!(execute_program_full("1 2 + drop").unwrap(), Vec::<i64>::new());
+
+        let deep = execute_program_full(&"7 ".repeat(300)).unwrap();
+        assert_eq!(deep, vec![7; 300]);
+    }
+
+    #[test]
+    fn test_bench_program() {
+        let report = bench_program(": sq dup * ;", None, &[], 100, 10).unwrap();
+        assert_eq!(report.word, "sq");
+        assert_eq!(report.iterations, 100);
+        assert!(report.min_ns <= report.median_ns && report.median_ns <= report.max_ns);
+        assert!(report.throughput() > 0.0);
+
+        let report = bench_program(": sq dup * ;", Some("sq"), &[7], 10, 0).unwrap();
+        assert_eq!(report.result, 49);
+        assert_eq!(bench_program("2 3 +", None, &[], 1, 0).unwrap().result, 5);
+        assert!(bench_program(": sq dup * ;", Some("cube"), &[], 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_execute_deep_stack() {
+        // Stack items live in registers and spill slots, so depth is not capped
+        let source = format!("{}{}", "1 ".repeat(300), "+ ".repeat(299));
+        assert_eq!(execute_program(&source, false).unwrap(), Some(300));
     }
 }
@@ -143,14 +143,27 @@ enum Commands {
         output: Option<PathBuf>,
     },
 
-    /// Benchmark performance
-    Benchmark {
+    /// Time repeated JIT execution of a word
+    #[command(alias = "benchmark")]
+    Bench {
         /// Input file
         input: PathBuf,
 
-        /// Number of iterations
-        #[arg(short, long, default_value = "100")]
+        /// Word to time (default: the top-level code, or else the last definition)
+        #[arg(long)]
+        word: Option<String>,
+
+        /// Stack arguments for the word, deepest first (default: a zero per input)
+        #[arg(long, num_args = 1.., allow_hyphen_values = true)]
+        args: Vec<i64>,
+
+        /// Number of timed calls
+        #[arg(short, long, default_value = "1000")]
         iterations: usize,
+
+        /// Untimed calls made first
+        #[arg(long, default_value = "100")]
+        warmup: usize,
     },
 
     /// Generate documentation
@@ -228,7 +241,7 @@ fn main() {
         Some(Commands::Profile { .. }) => {
             run_profile(&cli)
         }
-        Some(Commands::Benchmark { .. }) => {
+        Some(Commands::Bench { .. }) => {
             run_benchmark(&cli)
         }
         Some(Commands::Doc { .. }) => {
@@ -406,7 +419,10 @@ fn run_execute(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
                 Ok(result) => {
                     if !cli.quiet {
                         println!();
-                        println!("Result: {}", result);
+                        match result {
+                            Some(result) => println!("Result: {}", result),
+                            None => println!("ok"),
+                        }
                     }
                 }
                 Err(e) => {
@@ -425,7 +441,10 @@ fn run_execute(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
                 Ok(result) => {
                     if !cli.quiet {
                         println!();
-                        println!("Result: {}", result);
+                        match result {
+                            Some(result) => println!("Result: {}", result),
+                            None => println!("ok"),
+                        }
                     }
                 }
                 Err(e) => {
@@ -552,12 +571,34 @@ fn run_profile(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn run_benchmark(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(Commands::Benchmark { input, iterations }) = &cli.command {
-        println!("Benchmarking {:?}", input);
-        println!("  Iterations: {}", iterations);
-
-        // Placeholder benchmarking
-        println!("\n✓ Benchmark complete");
+    if let Some(Commands::Bench { input, word, args, iterations, warmup }) = &cli.command {
+        let source = std::fs::read_to_string(input)?;
+        let report = execute::bench_program(&source, word.as_deref(), args, *iterations, *warmup)?;
+
+        if cli.json {
+            let json = serde_json::json!({
+                "word": report.word,
+                "iterations": report.iterations,
+                "batch": report.batch,
+                "min_ns": report.min_ns,
+                "median_ns": report.median_ns,
+                "mean_ns": report.mean_ns,
+                "max_ns": report.max_ns,
+                "calls_per_sec": report.throughput(),
+                "result": report.result,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        } else if !cli.quiet {
+            println!("Benchmarking {} in {}", report.word, input.display());
+            println!("  Iterations: {} (batches of {})", report.iterations, report.batch);
+            println!("  Min:        {:.1} ns", report.min_ns);
+            println!("  Median:     {:.1} ns", report.median_ns);
+            println!("  Mean:       {:.1} ns", report.mean_ns);
+            println!("  Max:        {:.1} ns", report.max_ns);
+            println!("  Throughput: {:.0} calls/s", report.throughput());
+            println!("  Result:     {}", report.result);
+            println!("\n✓ Benchmark complete");
+        }
     }
 
     Ok(())
@@ -122,9 +122,9 @@ fn demo_hot_loop_optimization() {
     // Main: call sieve-inner repeatedly
     ir.main = vec![
         Instruction::Literal(2),
-        Instruction::Call("sieve-inner".to_string()),
-        Instruction::Call("sieve-inner".to_string()),
-        Instruction::Call("sieve-inner".to_string()),
+        Instruction::call("sieve-inner"),
+        Instruction::call("sieve-inner"),
+        Instruction::call("sieve-inner"),
     ];
 
     let mut pgo = PGOOptimizer::new();
@@ -185,11 +185,11 @@ fn demo_fibonacci_pgo() {
     ir.main = vec![
         Instruction::Literal(1),
         Instruction::Literal(1),
-        Instruction::Call("fib-step".to_string()),
-        Instruction::Call("fib-step".to_string()),
-        Instruction::Call("fib-step".to_string()),
-        Instruction::Call("fib-step".to_string()),
-        Instruction::Call("fib-step".to_string()),
+        Instruction::call("fib-step"),
+        Instruction::call("fib-step"),
+        Instruction::call("fib-step"),
+        Instruction::call("fib-step"),
+        Instruction::call("fib-step"),
     ];
 
     let mut pgo = PGOOptimizer::new();
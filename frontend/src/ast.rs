@@ -2,6 +2,8 @@
 
 use std::fmt;
 
+pub use crate::effect::{StackEffect, StackType, TypeVar};
+
 /// A complete Forth program
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
@@ -18,6 +20,22 @@ impl Program {
     }
 }
 
+impl Program {
+    /// Canonical Forth source for the program, which parses back to an
+    /// equal program apart from source locations
+    ///
+    /// Each definition is printed on its own line, followed by the top-level
+    /// code on one line; the program does not record how the two were
+    /// interleaved, nor comments other than stack comments.
+    pub fn to_source(&self) -> String {
+        let mut lines: Vec<String> = self.definitions.iter().map(Definition::to_source).collect();
+        if !self.top_level_code.is_empty() {
+            lines.push(words_to_source(&self.top_level_code));
+        }
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
 impl Default for Program {
     fn default() -> Self {
         Self::new()
@@ -31,103 +49,94 @@ pub struct Definition {
     pub body: Vec<Word>,
     pub immediate: bool,
     pub stack_effect: Option<StackEffect>,
+    /// The stack comment as written, e.g. `( n -- n*n )`, with its spacing
+    /// normalized; `stack_effect` keeps only the types it names
+    pub stack_comment: Option<String>,
+    /// Locals declared at the start of the body (`{ a b | temp -- }`)
+    pub locals: Option<Locals>,
     pub location: SourceLocation,
 }
 
-/// Source code location for error reporting
-#[derive(Debug, Clone, PartialEq, Default)]
-pub struct SourceLocation {
-    pub line: usize,
-    pub column: usize,
-}
-
-/// Stack effect declaration ( in1 in2 -- out1 )
-#[derive(Debug, Clone, PartialEq)]
-pub struct StackEffect {
-    pub inputs: Vec<StackType>,
-    pub outputs: Vec<StackType>,
+impl Definition {
+    /// Canonical source for the definition, on one line
+    pub fn to_source(&self) -> String {
+        let mut parts = vec![":".to_string(), self.name.clone()];
+        match (&self.stack_comment, &self.stack_effect) {
+            (Some(comment), _) => parts.push(comment.clone()),
+            (None, Some(effect)) => parts.push(stack_comment_for(effect)),
+            (None, None) => {}
+        }
+        if let Some(locals) = &self.locals {
+            let mut declaration = vec!["{".to_string()];
+            declaration.extend(locals.params.iter().cloned());
+            if !locals.uninitialized.is_empty() {
+                declaration.push("|".to_string());
+                declaration.extend(locals.uninitialized.iter().cloned());
+            }
+            declaration.push("}".to_string());
+            parts.push(declaration.join(" "));
+        }
+        if !self.body.is_empty() {
+            parts.push(words_to_source(&self.body));
+        }
+        parts.push(";".to_string());
+        if self.immediate {
+            parts.push("immediate".to_string());
+        }
+        parts.join(" ")
+    }
 }
 
-impl StackEffect {
-    pub fn new(inputs: Vec<StackType>, outputs: Vec<StackType>) -> Self {
-        Self { inputs, outputs }
+/// Stack comment naming the types of `effect` the way the parser reads them
+fn stack_comment_for(effect: &StackEffect) -> String {
+    let name = |ty: &StackType| match ty {
+        StackType::Addr => "addr".to_string(),
+        StackType::Bool => "flag".to_string(),
+        other => other.to_string(),
+    };
+    let mut comment = String::from("(");
+    for ty in &effect.inputs {
+        comment = comment + " " + &name(ty);
     }
-
-    pub fn net_effect(&self) -> i32 {
-        self.outputs.len() as i32 - self.inputs.len() as i32
+    comment.push_str(" --");
+    for ty in &effect.outputs {
+        comment = comment + " " + &name(ty);
     }
+    comment + " )"
 }
 
-impl fmt::Display for StackEffect {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "( ")?;
-        for (i, input) in self.inputs.iter().enumerate() {
-            if i > 0 {
-                write!(f, " ")?;
-            }
-            write!(f, "{}", input)?;
-        }
-        write!(f, " -- ")?;
-        for (i, output) in self.outputs.iter().enumerate() {
-            if i > 0 {
-                write!(f, " ")?;
-            }
-            write!(f, "{}", output)?;
-        }
-        write!(f, " )")
-    }
+/// Source for a sequence of words, separated by single spaces
+fn words_to_source(words: &[Word]) -> String {
+    words.iter().map(Word::to_source).collect::<Vec<_>>().join(" ")
 }
 
-/// Stack value types
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum StackType {
-    /// Integer (cell size)
-    Int,
-    /// Floating point
-    Float,
-    /// Address/pointer
-    Addr,
-    /// Boolean
-    Bool,
-    /// Character
-    Char,
-    /// String
-    String,
-    /// Polymorphic type variable (for type inference)
-    Var(TypeVar),
-    /// Unknown type (to be inferred)
-    Unknown,
+/// Locals declaration `{ a b | temp -- }`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Locals {
+    /// Initialized from the stack on entry; the last name takes the top item
+    pub params: Vec<String>,
+    /// Declared after `|`; these start out as zero
+    pub uninitialized: Vec<String>,
 }
 
-impl fmt::Display for StackType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            StackType::Int => write!(f, "int"),
-            StackType::Float => write!(f, "float"),
-            StackType::Addr => write!(f, "addr"),
-            StackType::Bool => write!(f, "bool"),
-            StackType::Char => write!(f, "char"),
-            StackType::String => write!(f, "string"),
-            StackType::Var(v) => write!(f, "{}", v),
-            StackType::Unknown => write!(f, "?"),
-        }
+impl Locals {
+    /// All local names, in declaration order
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.params.iter().chain(self.uninitialized.iter())
     }
 }
 
-/// Type variable for polymorphic types
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct TypeVar {
-    pub id: usize,
-    pub name: Option<String>,
+/// Source code location for error reporting
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
 }
 
-impl fmt::Display for TypeVar {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(name) = &self.name {
-            write!(f, "'{}", name)
-        } else {
-            write!(f, "'t{}", self.id)
-        }
+impl SourceLocation {
+    /// Line number, or `None` for code built without a source (line 0)
+    pub fn known_line(&self) -> Option<usize> {
+        (self.line > 0).then_some(self.line)
     }
 }
 
@@ -183,10 +192,104 @@ pub enum Word {
         value: i64,
     },
 
+    /// Value definition (`42 VALUE name`); unlike a constant it can be
+    /// updated with TO
+    Value {
+        name: String,
+        value: i64,
+    },
+
+    /// Store into a value (`TO name`)
+    To {
+        name: String,
+    },
+
+    /// Push the execution token of a word (`' name` or `['] name`)
+    Tick {
+        name: String,
+        location: SourceLocation,
+    },
+
+    /// Start a new data field. At top level the name follows (`CREATE buf`);
+    /// inside a defining word it is supplied by the word's caller
+    Create {
+        name: Option<String>,
+    },
+
+    /// Runtime behavior of words made by a defining word (`DOES> ...`),
+    /// run with the word's data field address on the stack
+    Does {
+        body: Vec<Word>,
+    },
+
+    /// Use of a defining word to make a new word (`42 const answer`)
+    Instantiate {
+        defining_word: String,
+        name: String,
+    },
+
     /// Comment (preserved for documentation)
     Comment(String),
 }
 
+impl Word {
+    /// Canonical source for the word; control structures are printed with
+    /// their bodies, on one line
+    pub fn to_source(&self) -> String {
+        // Bodies that may be empty, with the space before them
+        let body = |words: &[Word]| {
+            if words.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", words_to_source(words))
+            }
+        };
+
+        match self {
+            Word::IntLiteral(value) => value.to_string(),
+            // Debug formatting keeps a `.0` on whole numbers, so they lex as floats
+            Word::FloatLiteral(value) => format!("{:?}", value),
+            Word::StringLiteral(value) => {
+                let mut literal = String::from("\"");
+                for ch in value.chars() {
+                    match ch {
+                        '"' => literal.push_str("\\\""),
+                        '\\' => literal.push_str("\\\\"),
+                        '\n' => literal.push_str("\\n"),
+                        '\t' => literal.push_str("\\t"),
+                        '\r' => literal.push_str("\\r"),
+                        ch => literal.push(ch),
+                    }
+                }
+                literal.push('"');
+                literal
+            }
+            Word::WordRef { name, .. } => name.clone(),
+            Word::If { then_branch, else_branch: None } => format!("if{} then", body(then_branch)),
+            Word::If { then_branch, else_branch: Some(else_branch) } => {
+                format!("if{} else{} then", body(then_branch), body(else_branch))
+            }
+            Word::BeginUntil { body: words } => format!("begin{} until", body(words)),
+            Word::BeginWhileRepeat { condition, body: words } => {
+                format!("begin{} while{} repeat", body(condition), body(words))
+            }
+            // The parser records every loop with an increment of 1
+            Word::DoLoop { body: words, increment: 1 } => format!("do{} loop", body(words)),
+            Word::DoLoop { body: words, .. } => format!("do{} +loop", body(words)),
+            Word::Variable { name } => format!("variable {}", name),
+            Word::Constant { name, value } => format!("{} constant {}", value, name),
+            Word::Value { name, value } => format!("{} value {}", value, name),
+            Word::To { name } => format!("to {}", name),
+            Word::Tick { name, .. } => format!("' {}", name),
+            Word::Create { name: Some(name) } => format!("create {}", name),
+            Word::Create { name: None } => "create".to_string(),
+            Word::Does { body: words } => format!("does>{}", body(words)),
+            Word::Instantiate { defining_word, name } => format!("{} {}", defining_word, name),
+            Word::Comment(text) => format!("( {} )", text),
+        }
+    }
+}
+
 /// Compilation mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompilationMode {
@@ -241,6 +344,14 @@ pub enum Token {
     Variable,
     /// CONSTANT keyword
     Constant,
+    /// VALUE keyword
+    Value,
+    /// TO keyword
+    To,
+    /// CREATE keyword
+    Create,
+    /// DOES> keyword
+    Does,
     /// IMMEDIATE keyword
     Immediate,
     /// End of file
@@ -271,8 +382,115 @@ impl fmt::Display for Token {
             Token::Repeat => write!(f, "REPEAT"),
             Token::Variable => write!(f, "VARIABLE"),
             Token::Constant => write!(f, "CONSTANT"),
+            Token::Value => write!(f, "VALUE"),
+            Token::To => write!(f, "TO"),
+            Token::Create => write!(f, "CREATE"),
+            Token::Does => write!(f, "DOES>"),
             Token::Immediate => write!(f, "IMMEDIATE"),
             Token::Eof => write!(f, "<EOF>"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_program;
+
+    /// `words` with every word reference's location cleared
+    fn strip_locations(words: &[Word]) -> Vec<Word> {
+        words
+            .iter()
+            .map(|word| match word {
+                Word::WordRef { name, .. } => Word::WordRef {
+                    name: name.clone(),
+                    location: SourceLocation::default(),
+                },
+                Word::If { then_branch, else_branch } => Word::If {
+                    then_branch: strip_locations(then_branch),
+                    else_branch: else_branch.as_deref().map(strip_locations),
+                },
+                Word::BeginUntil { body } => Word::BeginUntil { body: strip_locations(body) },
+                Word::BeginWhileRepeat { condition, body } => Word::BeginWhileRepeat {
+                    condition: strip_locations(condition),
+                    body: strip_locations(body),
+                },
+                Word::DoLoop { body, increment } => Word::DoLoop {
+                    body: strip_locations(body),
+                    increment: *increment,
+                },
+                Word::Does { body } => Word::Does { body: strip_locations(body) },
+                other => other.clone(),
+            })
+            .collect()
+    }
+
+    fn parse_without_locations(source: &str) -> Program {
+        let program = parse_program(source).unwrap_or_else(|e| panic!("{}: {}", source, e));
+        Program {
+            definitions: program
+                .definitions
+                .into_iter()
+                .map(|def| Definition {
+                    body: strip_locations(&def.body),
+                    location: SourceLocation::default(),
+                    ..def
+                })
+                .collect(),
+            top_level_code: strip_locations(&program.top_level_code),
+        }
+    }
+
+    #[test]
+    fn test_to_source_round_trips() {
+        let corpus = [
+            ": square ( n -- n*n ) dup * ;",
+            ": square ( n -- n*n )\n    dup *\n;\n7 square .",
+            ": sign ( n -- n ) dup 0 < if drop -1 else 0 > if 1 else 0 then then ;",
+            ": countdown ( n -- ) begin dup . 1 - dup 0 = until drop ;",
+            ": halve ( n -- n ) begin dup 1 > while 2 / repeat ;",
+            ": sum ( n -- n ) 0 swap 0 do i + loop ;",
+            ": steps 10 0 do i . 2 +loop ;",
+            ": greet ( -- ) \"say \\\"hi\\\"\\n\\tand \\\\ bye\" type ;",
+            ": half ( f -- f ) 0.5 f* 2.0 f+ 1e10 f* ;",
+            ": swap-add { a b | tmp -- n } b a + ;",
+            ": lit [ 2 3 + ] literal ; immediate",
+            ": const ( n -- ) create , does> @ ;\n42 const answer answer .",
+            "variable total 5 constant five 0 value speed 10 to speed create buf",
+            ": flagged ( flag c addr s x -- ) drop ;",
+            "-3 abs .",
+        ];
+
+        for source in corpus {
+            let parsed = parse_without_locations(source);
+            let printed = parsed.to_source();
+            assert_eq!(parse_without_locations(&printed), parsed, "{}\nprinted as\n{}", source, printed);
+            // Printing is canonical: the printed source prints as itself
+            assert_eq!(parse_program(&printed).unwrap().to_source(), printed);
+        }
+    }
+
+    #[test]
+    fn test_to_source_text() {
+        let program = parse_program(": square ( n --   n*n )\n  dup   * ;\n7 square").unwrap();
+        assert_eq!(program.to_source(), ": square ( n -- n*n ) dup * ;\n7 square\n");
+
+        let program = parse_program(": abs ( n -- n ) dup 0 < IF negate THEN ;").unwrap();
+        assert_eq!(program.to_source(), ": abs ( n -- n ) dup 0 < if negate then ;\n");
+
+        assert_eq!(Word::StringLiteral("a \"b\"\n".to_string()).to_source(), r#""a \"b\"\n""#);
+        assert_eq!(Word::FloatLiteral(2.0).to_source(), "2.0");
+
+        // Built without a comment: the types are spelled as the parser reads them
+        let def = Definition {
+            name: "ok?".to_string(),
+            body: vec![],
+            immediate: false,
+            stack_effect: Some(StackEffect::new(vec![StackType::Addr], vec![StackType::Bool])),
+            stack_comment: None,
+            locals: None,
+            location: SourceLocation::default(),
+        };
+        assert_eq!(def.to_source(), ": ok? ( addr -- flag ) ;");
+    }
+}
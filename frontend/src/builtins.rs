@@ -0,0 +1,163 @@
+//! Words built into the compiler
+//!
+//! [`BUILTINS`] lists every word SSA conversion translates directly rather
+//! than as a call to a definition, together with its stack effect.
+
+use crate::ast::{StackEffect, StackType};
+
+/// A word built into the compiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtin {
+    pub name: &'static str,
+    /// Items taken from and left on the stack, or `None` when they depend
+    /// on an operand (`pick`, `roll`, `?dup`)
+    pub effect: Option<(usize, usize)>,
+}
+
+impl Builtin {
+    const fn new(name: &'static str, inputs: usize, outputs: usize) -> Self {
+        Self { name, effect: Some((inputs, outputs)) }
+    }
+
+    const fn variadic(name: &'static str) -> Self {
+        Self { name, effect: None }
+    }
+
+    /// Stack effect with every item typed as a cell
+    pub fn stack_effect(&self) -> Option<StackEffect> {
+        self.effect.map(|(inputs, outputs)| {
+            StackEffect::new(vec![StackType::Int; inputs], vec![StackType::Int; outputs])
+        })
+    }
+}
+
+/// Every built-in word, grouped by kind
+pub const BUILTINS: &[Builtin] = &[
+    // Arithmetic
+    Builtin::new("+", 2, 1),
+    Builtin::new("-", 2, 1),
+    Builtin::new("*", 2, 1),
+    Builtin::new("/", 2, 1),
+    Builtin::new("mod", 2, 1),
+    Builtin::new("min", 2, 1),
+    Builtin::new("max", 2, 1),
+    Builtin::new("negate", 1, 1),
+    Builtin::new("abs", 1, 1),
+    Builtin::new("1+", 1, 1),
+    Builtin::new("1-", 1, 1),
+    Builtin::new("2*", 1, 1),
+    Builtin::new("2/", 1, 1),
+    // Comparison
+    Builtin::new("<", 2, 1),
+    Builtin::new(">", 2, 1),
+    Builtin::new("<=", 2, 1),
+    Builtin::new(">=", 2, 1),
+    Builtin::new("=", 2, 1),
+    Builtin::new("<>", 2, 1),
+    Builtin::new("u<", 2, 1),
+    Builtin::new("u>", 2, 1),
+    Builtin::new("0=", 1, 1),
+    Builtin::new("0<", 1, 1),
+    Builtin::new("0>", 1, 1),
+    // Logical
+    Builtin::new("and", 2, 1),
+    Builtin::new("or", 2, 1),
+    Builtin::new("xor", 2, 1),
+    Builtin::new("lshift", 2, 1),
+    Builtin::new("rshift", 2, 1),
+    Builtin::new("arshift", 2, 1),
+    Builtin::new("not", 1, 1),
+    // Stack manipulation
+    Builtin::new("dup", 1, 2),
+    Builtin::new("drop", 1, 0),
+    Builtin::new("swap", 2, 2),
+    Builtin::new("over", 2, 3),
+    Builtin::new("rot", 3, 3),
+    Builtin::new("2dup", 2, 4),
+    Builtin::new("2drop", 2, 0),
+    Builtin::new("2swap", 4, 4),
+    Builtin::new("2over", 4, 6),
+    Builtin::variadic("pick"),
+    Builtin::variadic("roll"),
+    Builtin::variadic("?dup"),
+    // Memory
+    Builtin::new("@", 1, 1),
+    Builtin::new("!", 2, 0),
+    Builtin::new(",", 1, 0),
+    Builtin::new("allot", 1, 0),
+    Builtin::new("here", 0, 1),
+    // Return stack
+    Builtin::new(">r", 1, 0),
+    Builtin::new("r>", 0, 1),
+    Builtin::new("r@", 0, 1),
+    // Output
+    Builtin::new(".", 1, 0),
+    Builtin::new("u.", 1, 0),
+    Builtin::new(".r", 2, 0),
+    Builtin::new("emit", 1, 0),
+    Builtin::new("cr", 0, 0),
+    Builtin::new("type", 2, 0),
+    Builtin::new("count", 1, 2),
+    // File access
+    Builtin::new("r/o", 0, 2),
+    Builtin::new("w/o", 0, 2),
+    Builtin::new("r/w", 0, 2),
+    Builtin::new("create-file", 4, 2),
+    Builtin::new("open-file", 4, 2),
+    Builtin::new("read-file", 3, 2),
+    Builtin::new("write-file", 3, 1),
+    Builtin::new("close-file", 1, 1),
+    Builtin::new("delete-file", 2, 1),
+    Builtin::new("file-status", 2, 2),
+    Builtin::new("system", 2, 1),
+    // Loop indices
+    Builtin::new("i", 0, 1),
+    Builtin::new("j", 0, 1),
+    // Other
+    Builtin::new("execute", 1, 1),
+    Builtin::new("char", 0, 1),
+];
+
+/// Look up a built-in word by name
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Names of all built-in words, in alphabetical order
+pub fn names() -> Vec<&'static str> {
+    let mut names: Vec<&str> = BUILTINS.iter().map(|builtin| builtin.name).collect();
+    names.sort_unstable();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ForthError;
+    use crate::parser::parse_program;
+    use crate::ssa::convert_to_ssa;
+
+    #[test]
+    fn test_builtin_names() {
+        let names = names();
+        for name in ["dup", "+", "swap"] {
+            assert!(names.contains(&name), "missing {}", name);
+        }
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]), "names must be sorted and unique");
+        assert_eq!(lookup("over").and_then(Builtin::stack_effect).unwrap().to_string(), "( n n -- n n n )");
+        assert_eq!(lookup("pick").unwrap().effect, None);
+        assert!(lookup("square").is_none());
+    }
+
+    #[test]
+    fn test_every_builtin_is_converted() {
+        for builtin in BUILTINS {
+            let inputs = builtin.effect.map_or(3, |(inputs, _)| inputs);
+            let source = format!("{}{}", "1 ".repeat(inputs), builtin.name);
+            let program = parse_program(&source).unwrap();
+            if let Err(ForthError::UndefinedWord { word, .. }) = convert_to_ssa(&program) {
+                panic!("built-in '{}' is not converted", word);
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Compile-time evaluation of constant expressions
+//!
+//! Runs the code between `[` and `]` inside a definition, so that
+//! `[ ... ] LITERAL` can embed its result as a literal. Only integers,
+//! constants and pure built-in words are allowed, and they compute what the
+//! compiled code would: comparisons give 1 for true, arithmetic wraps and
+//! division truncates.
+
+use crate::builtins;
+use std::collections::HashMap;
+
+/// Run one word of a bracketed expression against `stack`
+///
+/// `constants` holds the values of the constants declared so far. Returns a
+/// message describing why the word cannot run at compile time, if it cannot.
+pub fn evaluate_word(
+    name: &str,
+    constants: &HashMap<String, i64>,
+    stack: &mut Vec<i64>,
+) -> Result<(), String> {
+    if let Some(&value) = constants.get(name) {
+        stack.push(value);
+        return Ok(());
+    }
+
+    let inputs = match builtins::lookup(name).and_then(|builtin| builtin.effect) {
+        Some((inputs, _)) if is_pure(name) => inputs,
+        _ => return Err(format!("'{}' cannot be evaluated at compile time", name)),
+    };
+    if stack.len() < inputs {
+        return Err(format!(
+            "Stack underflow in '{}' at compile time: expected {} items, found {}",
+            name,
+            inputs,
+            stack.len()
+        ));
+    }
+
+    let operands = stack.split_off(stack.len() - inputs);
+    match (name, operands.as_slice()) {
+        ("/" | "mod", [_, 0]) => return Err("Division by zero at compile time".to_string()),
+        ("+", [a, b]) => stack.push(a.wrapping_add(*b)),
+        ("-", [a, b]) => stack.push(a.wrapping_sub(*b)),
+        ("*", [a, b]) => stack.push(a.wrapping_mul(*b)),
+        ("/", [a, b]) => stack.push(a.wrapping_div(*b)),
+        ("mod", [a, b]) => stack.push(a.wrapping_rem(*b)),
+        ("min", [a, b]) => stack.push(*a.min(b)),
+        ("max", [a, b]) => stack.push(*a.max(b)),
+        ("negate", [a]) => stack.push(a.wrapping_neg()),
+        ("abs", [a]) => stack.push(a.wrapping_abs()),
+        ("1+", [a]) => stack.push(a.wrapping_add(1)),
+        ("1-", [a]) => stack.push(a.wrapping_sub(1)),
+        ("2*", [a]) => stack.push(a.wrapping_shl(1)),
+        ("2/", [a]) => stack.push(a >> 1),
+        ("<", [a, b]) => stack.push((a < b) as i64),
+        (">", [a, b]) => stack.push((a > b) as i64),
+        ("<=", [a, b]) => stack.push((a <= b) as i64),
+        (">=", [a, b]) => stack.push((a >= b) as i64),
+        ("=", [a, b]) => stack.push((a == b) as i64),
+        ("<>", [a, b]) => stack.push((a != b) as i64),
+        ("u<", [a, b]) => stack.push(((*a as u64) < (*b as u64)) as i64),
+        ("u>", [a, b]) => stack.push(((*a as u64) > (*b as u64)) as i64),
+        ("0=", [a]) => stack.push((*a == 0) as i64),
+        ("0<", [a]) => stack.push((*a < 0) as i64),
+        ("0>", [a]) => stack.push((*a > 0) as i64),
+        ("and", [a, b]) => stack.push(a & b),
+        ("or", [a, b]) => stack.push(a | b),
+        ("xor", [a, b]) => stack.push(a ^ b),
+        ("not", [a]) => stack.push(!a),
+        // Shift counts are masked to the cell width, as in compiled code
+        ("lshift", [a, b]) => stack.push(a.wrapping_shl(*b as u32)),
+        ("rshift", [a, b]) => stack.push((*a as u64).wrapping_shr(*b as u32) as i64),
+        ("arshift", [a, b]) => stack.push(a.wrapping_shr(*b as u32)),
+        ("dup", [a]) => stack.extend([*a, *a]),
+        ("drop", [_]) => {}
+        ("swap", [a, b]) => stack.extend([*b, *a]),
+        ("over", [a, b]) => stack.extend([*a, *b, *a]),
+        ("rot", [a, b, c]) => stack.extend([*b, *c, *a]),
+        ("2dup", [a, b]) => stack.extend([*a, *b, *a, *b]),
+        ("2drop", [_, _]) => {}
+        ("2swap", [a, b, c, d]) => stack.extend([*c, *d, *a, *b]),
+        ("2over", [a, b, c, d]) => stack.extend([*a, *b, *c, *d, *a, *b]),
+        _ => unreachable!("pure built-in '{}' has no evaluation rule", name),
+    }
+    Ok(())
+}
+
+/// Whether a built-in only computes on the stack, without memory or I/O
+fn is_pure(name: &str) -> bool {
+    matches!(
+        name,
+        "+" | "-" | "*" | "/" | "mod" | "min" | "max" | "negate" | "abs" | "1+" | "1-" | "2*" | "2/"
+            | "<" | ">" | "<=" | ">=" | "=" | "<>" | "u<" | "u>" | "0=" | "0<" | "0>"
+            | "and" | "or" | "xor" | "not" | "lshift" | "rshift" | "arshift"
+            | "dup" | "drop" | "swap" | "over" | "rot" | "2dup" | "2drop" | "2swap" | "2over"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(code: &[&str]) -> Result<Vec<i64>, String> {
+        let constants = HashMap::from([("ten".to_string(), 10)]);
+        let mut stack = Vec::new();
+        for word in code {
+            match word.parse() {
+                Ok(value) => stack.push(value),
+                Err(_) => evaluate_word(word, &constants, &mut stack)?,
+            }
+        }
+        Ok(stack)
+    }
+
+    #[test]
+    fn test_evaluate_expressions() {
+        assert_eq!(evaluate(&["2", "3", "+"]), Ok(vec![5]));
+        assert_eq!(evaluate(&["ten", "dup", "*", "7", "mod"]), Ok(vec![2]));
+        assert_eq!(evaluate(&["1", "2", "3", "rot"]), Ok(vec![2, 3, 1]));
+        assert_eq!(evaluate(&["-7", "2", "/", "3", "4", "<"]), Ok(vec![-3, 1]));
+        assert_eq!(evaluate(&["-1", "60", "rshift"]), Ok(vec![15]));
+        assert_eq!(evaluate(&["5", "1+", "5", "1-", "-3", "2/", "-3", "2*"]), Ok(vec![6, 4, -2, -6]));
+        assert_eq!(evaluate(&["0", "0=", "1", "0=", "-5", "0<", "-5", "0>"]), Ok(vec![1, 0, 1, 0]));
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert!(evaluate(&["1", "0", "/"]).unwrap_err().contains("Division by zero"));
+        assert!(evaluate(&["1", "+"]).unwrap_err().contains("underflow"));
+        assert!(evaluate(&["1", "."]).unwrap_err().contains("'.' cannot be evaluated"));
+        assert!(evaluate(&["square"]).is_err());
+    }
+}
@@ -0,0 +1,517 @@
+//! Stack effects shared by the parser, the semantic checks and the root
+//! crate's inference engine
+//!
+//! A `StackEffect` is written `( inputs -- outputs )`, optionally followed by
+//! a return-stack part `( R: inputs -- outputs )`. Type variables make an
+//! effect polymorphic: `swap` is `( a b -- b a )`. The optimizer tracks only
+//! how many cells an instruction takes and leaves, and keeps its own
+//! cell-count `fastforth_optimizer::StackEffect` for that.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Stack value types
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StackType {
+    /// Integer (cell size)
+    Int,
+    /// Floating point
+    Float,
+    /// Address/pointer
+    Addr,
+    /// Boolean
+    Bool,
+    /// Character
+    Char,
+    /// String
+    String,
+    /// Polymorphic type variable (for type inference)
+    Var(TypeVar),
+    /// Unknown type (to be inferred)
+    Unknown,
+}
+
+impl StackType {
+    /// Type variable called `name`
+    pub fn var(name: impl Into<String>) -> Self {
+        StackType::Var(TypeVar::named(name))
+    }
+}
+
+impl fmt::Display for StackType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackType::Int => write!(f, "n"),
+            StackType::Float => write!(f, "f"),
+            StackType::Addr => write!(f, "a"),
+            StackType::Bool => write!(f, "b"),
+            StackType::Char => write!(f, "c"),
+            StackType::String => write!(f, "s"),
+            StackType::Var(v) => write!(f, "{}", v),
+            StackType::Unknown => write!(f, "x"),
+        }
+    }
+}
+
+/// Type variable for polymorphic types
+///
+/// Variables made during inference are told apart by `id`; variables read
+/// from a stack comment are told apart by `name` and all have id 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeVar {
+    pub id: usize,
+    pub name: Option<String>,
+}
+
+impl TypeVar {
+    /// Variable identified by its name
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            name: Some(name.into()),
+        }
+    }
+}
+
+impl fmt::Display for TypeVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{}", name)
+        } else {
+            write!(f, "t{}", self.id)
+        }
+    }
+}
+
+// Written as the name it displays as, so effects serialize as they did when
+// variables were plain strings; an unnamed variable comes back named `tN`
+impl Serialize for TypeVar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TypeVar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(TypeVar::named)
+    }
+}
+
+/// Stack effect (inputs -- outputs), with an optional return-stack part
+/// written `( inputs -- outputs )( R: inputs -- outputs )`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackEffect {
+    pub inputs: Vec<StackType>,
+    pub outputs: Vec<StackType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub return_inputs: Vec<StackType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub return_outputs: Vec<StackType>,
+}
+
+impl StackEffect {
+    pub fn new(inputs: Vec<StackType>, outputs: Vec<StackType>) -> Self {
+        Self {
+            inputs,
+            outputs,
+            return_inputs: Vec::new(),
+            return_outputs: Vec::new(),
+        }
+    }
+
+    /// Attach a return-stack effect
+    pub fn with_return(mut self, inputs: Vec<StackType>, outputs: Vec<StackType>) -> Self {
+        self.return_inputs = inputs;
+        self.return_outputs = outputs;
+        self
+    }
+
+    /// Whether the return stack is left as it was found
+    pub fn return_balanced(&self) -> bool {
+        self.return_inputs.is_empty() && self.return_outputs.is_empty()
+    }
+
+    /// Identity effect (no change)
+    pub fn identity() -> Self {
+        Self::new(vec![], vec![])
+    }
+
+    /// Stack depth change
+    pub fn depth_delta(&self) -> i32 {
+        self.outputs.len() as i32 - self.inputs.len() as i32
+    }
+
+    /// Same as [`depth_delta`](Self::depth_delta)
+    pub fn net_effect(&self) -> i32 {
+        self.depth_delta()
+    }
+
+    /// Whether both stacks are as deep afterwards as before
+    pub fn is_balanced(&self) -> bool {
+        self.depth_delta() == 0 && self.return_inputs.len() == self.return_outputs.len()
+    }
+
+    /// Compose two stack effects
+    ///
+    /// Outputs of `self` are unified with the inputs of `other`; type
+    /// variables are renamed apart first so the two effects never share one.
+    ///
+    /// The data and return stacks are composed independently, but share one
+    /// substitution since values move between them.
+    pub fn compose(&self, other: &StackEffect) -> Result<StackEffect, String> {
+        let other = other.renamed_apart(&self.variables());
+        let mut subst = Substitution::default();
+
+        let (inputs, outputs) = Self::compose_stack(
+            (&self.inputs, &self.outputs),
+            (&other.inputs, &other.outputs),
+            &mut subst,
+        )?;
+        let (return_inputs, return_outputs) = Self::compose_stack(
+            (&self.return_inputs, &self.return_outputs),
+            (&other.return_inputs, &other.return_outputs),
+            &mut subst,
+        )?;
+
+        Ok(StackEffect::new(subst.apply(&inputs), subst.apply(&outputs))
+            .with_return(subst.apply(&return_inputs), subst.apply(&return_outputs))
+            .normalized())
+    }
+
+    /// Compose the `(inputs, outputs)` of one stack, unifying the values
+    /// passed from the first effect to the second
+    fn compose_stack(
+        first: (&[StackType], &[StackType]),
+        second: (&[StackType], &[StackType]),
+        subst: &mut Substitution,
+    ) -> Result<(Vec<StackType>, Vec<StackType>), String> {
+        let (first_inputs, first_outputs) = first;
+        let (second_inputs, second_outputs) = second;
+
+        // If we don't have enough outputs to satisfy other's inputs,
+        // those inputs must come from our caller
+        let shortfall = second_inputs.len().saturating_sub(first_outputs.len());
+        let consumed_from_self = second_inputs.len() - shortfall;
+        let remaining_outputs = first_outputs.len() - consumed_from_self;
+
+        for (produced, expected) in first_outputs[remaining_outputs..]
+            .iter()
+            .zip(&second_inputs[shortfall..])
+        {
+            subst.unify(produced, expected)?;
+        }
+
+        // The shortfall sits below our own inputs on the caller's stack
+        let mut inputs = second_inputs[..shortfall].to_vec();
+        inputs.extend(first_inputs.iter().cloned());
+
+        let mut outputs = first_outputs[..remaining_outputs].to_vec();
+        outputs.extend(second_outputs.iter().cloned());
+
+        Ok((inputs, outputs))
+    }
+
+    /// All types in the effect, data stack first, in reading order
+    fn types(&self) -> impl Iterator<Item = &StackType> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .chain(&self.return_inputs)
+            .chain(&self.return_outputs)
+    }
+
+    /// All type variables mentioned in this effect
+    pub fn variables(&self) -> HashSet<TypeVar> {
+        self.types()
+            .filter_map(|ty| match ty {
+                StackType::Var(var) => Some(var.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Rename variables so none collides with `taken`
+    fn renamed_apart(&self, taken: &HashSet<TypeVar>) -> StackEffect {
+        let taken_names: HashSet<String> = taken.iter().map(ToString::to_string).collect();
+        let own = self.variables();
+        let own_names: HashSet<String> = own.iter().map(ToString::to_string).collect();
+        let mut renames = HashMap::new();
+
+        for var in &own {
+            if taken_names.contains(&var.to_string()) {
+                let mut fresh = format!("{}'", var);
+                while taken_names.contains(&fresh) || own_names.contains(&fresh) {
+                    fresh.push('\'');
+                }
+                renames.insert(var.clone(), TypeVar::named(fresh));
+            }
+        }
+
+        self.rename_variables(&renames)
+    }
+
+    /// Rename variables to `a`, `b`, `c`, ... in order of first appearance
+    pub fn normalized(&self) -> StackEffect {
+        let mut renames = HashMap::new();
+
+        for ty in self.types() {
+            if let StackType::Var(var) = ty {
+                if !renames.contains_key(var) {
+                    let next = renames.len();
+                    let fresh = if next < 26 {
+                        ((b'a' + next as u8) as char).to_string()
+                    } else {
+                        format!("t{}", next)
+                    };
+                    renames.insert(var.clone(), TypeVar::named(fresh));
+                }
+            }
+        }
+
+        self.rename_variables(&renames)
+    }
+
+    /// Apply a simultaneous renaming of type variables
+    fn rename_variables(&self, renames: &HashMap<TypeVar, TypeVar>) -> StackEffect {
+        let rename = |types: &[StackType]| {
+            types
+                .iter()
+                .map(|ty| match ty {
+                    StackType::Var(var) => StackType::Var(renames.get(var).unwrap_or(var).clone()),
+                    other => other.clone(),
+                })
+                .collect()
+        };
+
+        StackEffect::new(rename(&self.inputs), rename(&self.outputs))
+            .with_return(rename(&self.return_inputs), rename(&self.return_outputs))
+    }
+
+    /// Check if this effect is compatible with another: both take and leave
+    /// as many items on each stack
+    pub fn compatible_with(&self, other: &StackEffect) -> bool {
+        self.inputs.len() == other.inputs.len()
+            && self.outputs.len() == other.outputs.len()
+            && self.return_inputs.len() == other.return_inputs.len()
+            && self.return_outputs.len() == other.return_outputs.len()
+    }
+}
+
+impl fmt::Display for StackEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_stack_part(f, "", &self.inputs, &self.outputs)?;
+
+        if !self.return_balanced() {
+            write_stack_part(f, " R:", &self.return_inputs, &self.return_outputs)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write one `( inputs -- outputs )` group, e.g. `( n -- )`
+fn write_stack_part(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    inputs: &[StackType],
+    outputs: &[StackType],
+) -> fmt::Result {
+    write!(f, "({}", prefix)?;
+    for ty in inputs {
+        write!(f, " {}", ty)?;
+    }
+    write!(f, " --")?;
+    for ty in outputs {
+        write!(f, " {}", ty)?;
+    }
+    write!(f, " )")
+}
+
+/// Mapping from type variables to the types they were unified with
+#[derive(Debug, Clone, Default)]
+pub struct Substitution {
+    bindings: HashMap<TypeVar, StackType>,
+}
+
+impl Substitution {
+    /// Follow variable bindings until reaching an unbound variable or a concrete type
+    pub fn resolve(&self, ty: &StackType) -> StackType {
+        let mut current = ty.clone();
+        while let StackType::Var(var) = &current {
+            match self.bindings.get(var) {
+                Some(bound) => current = bound.clone(),
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Unify a produced type with the type a consumer expects
+    ///
+    /// All cell-sized types (`n`, `b`, `c`, `a`, `s`) are interchangeable, as
+    /// in Forth itself; only floats and cells are kept apart.
+    pub fn unify(&mut self, produced: &StackType, expected: &StackType) -> Result<(), String> {
+        let produced = self.resolve(produced);
+        let expected = self.resolve(expected);
+
+        match (&produced, &expected) {
+            _ if produced == expected => Ok(()),
+            (StackType::Var(var), ty) | (ty, StackType::Var(var)) => self.bind(var, ty),
+            (StackType::Unknown, _) | (_, StackType::Unknown) => Ok(()),
+            (StackType::Float, _) | (_, StackType::Float) => Err(format!(
+                "Type mismatch: expected {}, found {}",
+                expected, produced
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Bind a variable, refusing bindings that would make a type refer to itself
+    fn bind(&mut self, var: &TypeVar, ty: &StackType) -> Result<(), String> {
+        // Types are flat, so a variable can only occur in a type by being it
+        if let StackType::Var(bound) = self.resolve(ty) {
+            if &bound == var {
+                return Ok(());
+            }
+        }
+        self.bindings.insert(var.clone(), ty.clone());
+        Ok(())
+    }
+
+    /// Apply the substitution to a list of types
+    pub fn apply(&self, types: &[StackType]) -> Vec<StackType> {
+        types.iter().map(|ty| self.resolve(ty)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_delta() {
+        let effect = StackEffect::new(
+            vec![StackType::Int],
+            vec![StackType::Int, StackType::Int],
+        );
+        assert_eq!(effect.depth_delta(), 1);
+    }
+
+    #[test]
+    fn test_compose() {
+        let dup = StackEffect::new(
+            vec![StackType::Int],
+            vec![StackType::Int, StackType::Int],
+        );
+        let add = StackEffect::new(
+            vec![StackType::Int, StackType::Int],
+            vec![StackType::Int],
+        );
+
+        let composed = dup.compose(&add).unwrap();
+        assert_eq!(composed.inputs.len(), 1);
+        assert_eq!(composed.outputs.len(), 1);
+        assert!(composed.is_balanced());
+        assert_eq!(composed.depth_delta(), 0);
+        assert!(composed.compatible_with(&StackEffect::new(vec![StackType::Int], vec![StackType::Int])));
+        assert!(!composed.compatible_with(&add));
+        assert!(!dup.is_balanced());
+
+        // A return-stack push is not balanced even if the data stack is
+        let to_r = StackEffect::identity().with_return(vec![], vec![StackType::Int]);
+        assert!(!to_r.is_balanced());
+    }
+
+    #[test]
+    fn test_display() {
+        let effect = StackEffect::new(
+            vec![StackType::Int, StackType::Int],
+            vec![StackType::Int],
+        );
+        assert_eq!(format!("{}", effect), "( n n -- n )");
+    }
+
+    fn var(name: &str) -> StackType {
+        StackType::var(name)
+    }
+
+    fn dup() -> StackEffect {
+        StackEffect::new(vec![var("a")], vec![var("a"), var("a")])
+    }
+
+    fn swap() -> StackEffect {
+        StackEffect::new(vec![var("a"), var("b")], vec![var("b"), var("a")])
+    }
+
+    #[test]
+    fn test_compose_polymorphic() {
+        let effect = dup().compose(&swap()).unwrap();
+        assert_eq!(effect.to_string(), "( a -- a a )");
+
+        let effect = swap().compose(&swap()).unwrap();
+        assert_eq!(effect.to_string(), "( a b -- a b )");
+    }
+
+    #[test]
+    fn test_compose_numbered_variables() {
+        // Variables made during inference have no name, only an id
+        let fresh = |id| StackType::Var(TypeVar { id, name: None });
+        let over = StackEffect::new(vec![fresh(0), fresh(1)], vec![fresh(0), fresh(1), fresh(0)]);
+        let effect = over.compose(&swap()).unwrap();
+        assert_eq!(effect.to_string(), "( a b -- a a b )");
+    }
+
+    #[test]
+    fn test_unify_variable_with_int() {
+        let inc = StackEffect::new(vec![StackType::Int], vec![StackType::Int]);
+        let effect = dup().compose(&inc).unwrap();
+        assert_eq!(effect.to_string(), "( n -- n n )");
+    }
+
+    #[test]
+    fn test_float_int_mismatch() {
+        let float = StackEffect::new(vec![], vec![StackType::Float]);
+        let inc = StackEffect::new(vec![StackType::Int], vec![StackType::Int]);
+        let err = float.compose(&inc).unwrap_err();
+        assert!(err.contains("expected n, found f"), "{}", err);
+
+        // Flowing through a variable still reports the mismatch
+        let err = float.compose(&dup()).unwrap().compose(&inc).unwrap_err();
+        assert!(err.contains("mismatch"));
+    }
+
+    #[test]
+    fn test_occurs_check_self_binding() {
+        let mut subst = Substitution::default();
+        subst.unify(&var("a"), &var("b")).unwrap();
+        // b is already a; binding it back must not create a cycle
+        subst.unify(&var("b"), &var("a")).unwrap();
+        assert_eq!(subst.resolve(&var("a")), var("b"));
+        assert_eq!(subst.resolve(&var("b")), var("b"));
+    }
+
+    #[test]
+    fn test_compose_return_stack() {
+        let to_r = StackEffect::new(vec![var("a")], vec![]).with_return(vec![], vec![var("a")]);
+        let r_from = StackEffect::new(vec![], vec![var("a")]).with_return(vec![var("a")], vec![]);
+
+        let effect = to_r.compose(&r_from).unwrap();
+        assert_eq!(effect.to_string(), "( a -- a )");
+        assert!(effect.return_balanced());
+
+        assert_eq!(to_r.to_string(), "( a -- )( R: -- a )");
+    }
+
+    #[test]
+    fn test_serialized_form() {
+        let effect = swap().with_return(vec![], vec![StackType::Int]);
+        let json = serde_json::to_string(&effect).unwrap();
+        assert_eq!(
+            json,
+            r#"{"inputs":[{"Var":"a"},{"Var":"b"}],"outputs":[{"Var":"b"},{"Var":"a"}],"return_outputs":["Int"]}"#
+        );
+        let back: StackEffect = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, effect);
+    }
+}
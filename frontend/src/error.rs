@@ -1,9 +1,13 @@
 //! Error types for the Fast Forth compiler
 
+use crate::ast::SourceLocation;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, ForthError>;
 
+/// Most errors the parser or semantic analysis report for one program
+pub const MAX_REPORTED_ERRORS: usize = 20;
+
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum ForthError {
     #[error("Parse error at line {line}, column {column}: {message}")]
@@ -13,16 +17,33 @@ pub enum ForthError {
         message: String,
     },
 
+    /// The input ends inside a definition, control structure, comment or
+    /// string, so more input could still complete it
+    #[error("Incomplete input: {message}")]
+    IncompleteInput {
+        message: String,
+    },
+
     #[error("Lexical error at position {position}: {message}")]
     LexError {
         position: usize,
         message: String,
     },
 
-    #[error("Undefined word: {word}")]
+    #[error(
+        "Undefined word: {word}{}",
+        .location.as_ref().and_then(|l| l.known_line()).map(|line| format!(" at line {}", line)).unwrap_or_default()
+    )]
     UndefinedWord {
         word: String,
-        line: Option<usize>,
+        location: Option<SourceLocation>,
+    },
+
+    /// A token made of digits that is not a number in the current base
+    #[error("Invalid number '{token}' in base {radix}")]
+    InvalidNumber {
+        token: String,
+        radix: u32,
     },
 
     #[error("Stack underflow in word '{word}': expected {expected} items, found {found}")]
@@ -57,6 +78,17 @@ pub enum ForthError {
         declaration: String,
     },
 
+    /// A definition's body does not match its declared stack effect
+    #[error("Stack effect mismatch in '{word}': {trace}")]
+    StackEffectMismatch {
+        word: String,
+        /// Source text of the word where the body diverged, if one was found
+        offending: Option<String>,
+        /// The declared and inferred effects, then the rendered
+        /// [`StackTrace`](crate::stack_effects::StackTrace)
+        trace: String,
+    },
+
     #[error("Redefinition of word: {word}")]
     RedefinitionError {
         word: String,
@@ -82,6 +114,16 @@ pub enum ForthError {
     InternalError {
         message: String,
     },
+
+    /// Several errors found in one pass, in source order
+    #[error(
+        "{} errors:{}",
+        .errors.len(),
+        .errors.iter().map(|error| format!("\n  {}", error)).collect::<String>()
+    )]
+    Multiple {
+        errors: Vec<ForthError>,
+    },
 }
 
 impl ForthError {
@@ -93,10 +135,65 @@ impl ForthError {
         }
     }
 
+    pub fn incomplete_input(message: impl Into<String>) -> Self {
+        ForthError::IncompleteInput {
+            message: message.into(),
+        }
+    }
+
+    /// Whether the error only means the input stopped too early
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, ForthError::IncompleteInput { .. })
+    }
+
     pub fn undefined_word(word: impl Into<String>) -> Self {
         ForthError::UndefinedWord {
             word: word.into(),
-            line: None,
+            location: None,
+        }
+    }
+
+    /// `None` for no errors, the error itself for one, or
+    /// [`Multiple`](Self::Multiple) for several, keeping at most
+    /// [`MAX_REPORTED_ERRORS`]
+    pub fn combine(errors: Vec<ForthError>) -> Option<ForthError> {
+        let mut errors: Vec<ForthError> = errors.into_iter().flat_map(ForthError::into_errors).collect();
+        errors.truncate(MAX_REPORTED_ERRORS);
+        match errors.len() {
+            0 => None,
+            1 => errors.pop(),
+            _ => Some(ForthError::Multiple { errors }),
+        }
+    }
+
+    /// The individual errors: those of a [`Multiple`](Self::Multiple), or
+    /// the error itself
+    pub fn errors(&self) -> &[ForthError] {
+        match self {
+            ForthError::Multiple { errors } => errors,
+            error => std::slice::from_ref(error),
+        }
+    }
+
+    fn into_errors(self) -> Vec<ForthError> {
+        match self {
+            ForthError::Multiple { errors } => errors,
+            error => vec![error],
+        }
+    }
+
+    /// Where in the source the error was found, if known; the first
+    /// error's location for [`Multiple`](Self::Multiple)
+    pub fn span(&self) -> Option<SourceLocation> {
+        match self {
+            ForthError::Multiple { errors } => errors.first().and_then(ForthError::span),
+            ForthError::ParseError { line, column, .. } if *line > 0 => {
+                Some(SourceLocation { line: *line, column: *column })
+            }
+            ForthError::UndefinedWord { location, .. } => {
+                location.clone().filter(|location| location.known_line().is_some())
+            }
+            _ => None,
         }
     }
 
@@ -0,0 +1,282 @@
+//! Execution tokens and static resolution of EXECUTE
+//!
+//! `' name` and `['] name` push the execution token of a word, and `EXECUTE`
+//! calls the word behind the token on top of the stack. Most tokens are used
+//! right where they are made, so before analysis [`resolve_execute`] turns
+//! every `EXECUTE` of a known token into a direct call of the word, inlining
+//! words such as `: apply execute ;` into call sites that pass them a known
+//! token. What is left are indirect calls, compiled as a call through the
+//! token that traps on anything that is not one.
+
+use crate::ast::{Definition, Program, Word};
+use crate::builtins;
+use crate::stack_effects::definition_effects_with;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// How deep words taking a token are inlined into each other
+const MAX_INLINE_DEPTH: usize = 8;
+
+/// `program` with every `EXECUTE` whose token is known at compile time
+/// replaced by a call of the ticked word
+///
+/// A token is known when it is made by a `'` in the same straight-line code
+/// and nothing between consumes or moves it. A call of a definition that
+/// executes the token on top of its inputs is inlined when that token is
+/// known, so the token reaches its `EXECUTE`. The tick of a resolved token is
+/// removed; other ticks are kept.
+pub fn resolve_execute(program: &Program) -> Cow<'_, Program> {
+    if !program.definitions.iter().any(|def| has_tick(&def.body)) && !has_tick(&program.top_level_code) {
+        return Cow::Borrowed(program);
+    }
+
+    let resolver = Resolver::new(program);
+    let mut resolved = program.clone();
+    for def in &mut resolved.definitions {
+        // Locals could shadow the words the resolver tracks
+        if def.locals.is_none() {
+            def.body = resolver.resolve(&def.body, 0);
+        }
+    }
+    resolved.top_level_code = resolver.resolve(&program.top_level_code, 0);
+    Cow::Owned(resolved)
+}
+
+/// Names of the words ticked anywhere in `words`, in order of appearance
+pub fn ticked_words(words: &[Word]) -> Vec<&str> {
+    let mut ticked = Vec::new();
+    for word in words {
+        match word {
+            Word::Tick { name, .. } => ticked.push(name.as_str()),
+            Word::If { then_branch, else_branch } => {
+                ticked.extend(ticked_words(then_branch));
+                ticked.extend(ticked_words(else_branch.as_deref().unwrap_or_default()));
+            }
+            Word::BeginWhileRepeat { condition, body } => {
+                ticked.extend(ticked_words(condition));
+                ticked.extend(ticked_words(body));
+            }
+            Word::BeginUntil { body } | Word::DoLoop { body, .. } | Word::Does { body } => {
+                ticked.extend(ticked_words(body));
+            }
+            _ => {}
+        }
+    }
+    ticked
+}
+
+fn has_tick(words: &[Word]) -> bool {
+    !ticked_words(words).is_empty()
+}
+
+/// A token made by a tick that is still on the stack
+struct Pending {
+    /// Position of the tick in the resolved code
+    tick: usize,
+    name: String,
+    /// Items on the stack above the token
+    above: usize,
+}
+
+struct Resolver<'a> {
+    definitions: HashMap<&'a str, &'a Definition>,
+    /// Items taken and left by the definitions whose effect is known
+    effects: HashMap<&'a str, (usize, usize)>,
+    /// Variables, constants and values, which push one item
+    data_words: HashSet<&'a str>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(program: &'a Program) -> Self {
+        let definitions = program.definitions.iter().map(|def| (def.name.as_str(), def)).collect();
+        let effects = program
+            .definitions
+            .iter()
+            .zip(definition_effects_with(program, None))
+            .filter_map(|(def, effect)| {
+                effect.map(|effect| (def.name.as_str(), (effect.inputs.len(), effect.outputs.len())))
+            })
+            .collect();
+        let data_words = program
+            .top_level_code
+            .iter()
+            .filter_map(|word| match word {
+                Word::Variable { name } | Word::Constant { name, .. } | Word::Value { name, .. } => {
+                    Some(name.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+
+        Self { definitions, effects, data_words }
+    }
+
+    /// Items taken and left by `word`, if they are known
+    fn effect(&self, word: &Word) -> Option<(usize, usize)> {
+        match word {
+            Word::IntLiteral(_) | Word::FloatLiteral(_) | Word::Tick { .. } => Some((0, 1)),
+            Word::StringLiteral(_) => Some((0, 2)),
+            Word::Comment(_) => Some((0, 0)),
+            Word::To { .. } => Some((1, 0)),
+            Word::WordRef { name, .. } => self.word_effect(name),
+            _ => None,
+        }
+    }
+
+    fn word_effect(&self, name: &str) -> Option<(usize, usize)> {
+        if self.definitions.contains_key(name) {
+            return self.effects.get(name).copied();
+        }
+        if self.data_words.contains(name) {
+            return Some((0, 1));
+        }
+        builtins::lookup(name).and_then(|builtin| builtin.effect)
+    }
+
+    /// Whether `name` is the built-in EXECUTE rather than a definition shadowing it
+    fn is_execute(&self, name: &str) -> bool {
+        name == "execute" && !self.definitions.contains_key(name)
+    }
+
+    /// The body of `name`, if it is a definition to inline where the token
+    /// on top of the stack is known: one that executes a token, directly or
+    /// through another such word, without calling itself or leaving early
+    fn executor_body(&self, name: &str) -> Option<&'a [Word]> {
+        let def = self.definitions.get(name)?;
+        let inlinable = def.locals.is_none() && !calls_any(&def.body, &[name, "exit", "recurse"]);
+        (inlinable && self.executes(&def.body, &mut HashSet::new())).then_some(def.body.as_slice())
+    }
+
+    /// Whether straight-line code in `words` runs EXECUTE, directly or
+    /// through the definitions it calls (`seen` guards against cycles)
+    fn executes(&self, words: &'a [Word], seen: &mut HashSet<&'a str>) -> bool {
+        words.iter().any(|word| match word {
+            Word::WordRef { name, .. } if self.is_execute(name) => true,
+            Word::WordRef { name, .. } => match self.definitions.get(name.as_str()) {
+                Some(def) if seen.insert(def.name.as_str()) => self.executes(&def.body, seen),
+                _ => false,
+            },
+            _ => false,
+        })
+    }
+
+    fn resolve(&self, words: &[Word], depth: usize) -> Vec<Word> {
+        let mut input: VecDeque<(Word, usize)> = words.iter().map(|word| (word.clone(), depth)).collect();
+        let mut resolved: Vec<Option<Word>> = Vec::with_capacity(words.len());
+        let mut pending: Vec<Pending> = Vec::new();
+
+        while let Some((word, depth)) = input.pop_front() {
+            let token_on_top = pending.last().filter(|token| token.above == 0);
+
+            let word = match word {
+                Word::WordRef { name, location } if self.is_execute(&name) && token_on_top.is_some() => {
+                    let token = pending.pop().expect("token on top");
+                    resolved[token.tick] = None;
+                    // The token is gone; the word takes its place
+                    for below in &mut pending {
+                        below.above -= 1;
+                    }
+                    Word::WordRef { name: token.name, location }
+                }
+                Word::WordRef { ref name, .. } if token_on_top.is_some() && depth < MAX_INLINE_DEPTH => {
+                    if let Some(body) = self.executor_body(name) {
+                        for inlined in body.iter().rev() {
+                            input.push_front((inlined.clone(), depth + 1));
+                        }
+                        continue;
+                    }
+                    word
+                }
+                Word::If { then_branch, else_branch } => Word::If {
+                    then_branch: self.resolve(&then_branch, depth),
+                    else_branch: else_branch.map(|words| self.resolve(&words, depth)),
+                },
+                Word::BeginUntil { body } => Word::BeginUntil { body: self.resolve(&body, depth) },
+                Word::BeginWhileRepeat { condition, body } => Word::BeginWhileRepeat {
+                    condition: self.resolve(&condition, depth),
+                    body: self.resolve(&body, depth),
+                },
+                Word::DoLoop { body, increment } => Word::DoLoop { body: self.resolve(&body, depth), increment },
+                Word::Does { body } => Word::Does { body: self.resolve(&body, depth) },
+                word => word,
+            };
+
+            match self.effect(&word) {
+                // Tokens the word consumes can no longer be followed
+                Some((inputs, outputs)) => pending.retain_mut(|token| {
+                    let kept = token.above >= inputs;
+                    if kept {
+                        token.above = token.above - inputs + outputs;
+                    }
+                    kept
+                }),
+                None => pending.clear(),
+            }
+            if let Word::Tick { name, .. } = &word {
+                pending.push(Pending { tick: resolved.len(), name: name.clone(), above: 0 });
+            }
+            resolved.push(Some(word));
+        }
+
+        resolved.into_iter().flatten().collect()
+    }
+}
+
+/// Whether `words` call any of `names`, at any depth
+fn calls_any(words: &[Word], names: &[&str]) -> bool {
+    words.iter().any(|word| match word {
+        Word::WordRef { name, .. } => names.contains(&name.as_str()),
+        Word::If { then_branch, else_branch } => {
+            calls_any(then_branch, names) || calls_any(else_branch.as_deref().unwrap_or_default(), names)
+        }
+        Word::BeginWhileRepeat { condition, body } => calls_any(condition, names) || calls_any(body, names),
+        Word::BeginUntil { body } | Word::DoLoop { body, .. } | Word::Does { body } => calls_any(body, names),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_program;
+
+    fn resolved(source: &str) -> String {
+        let program = parse_program(source).unwrap();
+        resolve_execute(&program).to_source()
+    }
+
+    #[test]
+    fn test_known_token_becomes_a_call() {
+        assert_eq!(resolved(": f 5 ['] 1+ execute ;"), ": f 5 1+ ;\n");
+        // Code that leaves the token where it is doesn't hide it
+        assert_eq!(resolved("3 ' negate 1 drop execute"), "3 1 drop negate\n");
+    }
+
+    #[test]
+    fn test_words_taking_a_token_are_inlined() {
+        assert_eq!(resolved(": apply execute ;\n' dup apply"), ": apply execute ;\ndup\n");
+        assert_eq!(
+            resolved(": apply execute ;\n: apply-later 1 drop apply ;\n3 ' negate apply-later"),
+            ": apply execute ;\n: apply-later 1 drop apply ;\n3 1 drop negate\n"
+        );
+    }
+
+    #[test]
+    fn test_unknown_tokens_are_kept() {
+        // The token is moved, stored or passed through a branch
+        for source in [
+            ": f ['] dup swap execute ;",
+            "variable v ' dup v ! v @ execute",
+            ": f ['] dup 0 if then execute ;",
+        ] {
+            let program = parse_program(source).unwrap();
+            assert_eq!(*resolve_execute(&program), program, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_recursive_words_are_not_inlined() {
+        let program = parse_program(": forever execute forever ;\n' dup forever").unwrap();
+        assert_eq!(*resolve_execute(&program), program);
+    }
+}
@@ -0,0 +1,449 @@
+//! Source formatter
+//!
+//! Lays Forth source out in one canonical style:
+//!
+//! ```text
+//! \ Factorial
+//! : factorial ( n -- n! )
+//!   dup 1 <=
+//!   if
+//!     drop 1
+//!   else
+//!     dup 1 - factorial *
+//!   then
+//! ;
+//! ```
+//!
+//! Definitions without control structures or line comments that fit in
+//! [`MAX_WIDTH`] columns go on one line. Longer ones keep their line breaks,
+//! and control-structure keywords get lines of their own, indented by
+//! nesting depth. Each definition is set off by a blank line. Words are
+//! separated by single spaces, but comments and strings are copied
+//! unchanged, and nothing is reordered.
+//!
+//! Comments and the order of definitions are not in the AST, so the
+//! formatter works on the source text. It checks that the result lexes to
+//! the same tokens as the input before returning it.
+
+use crate::error::{ForthError, Result};
+use crate::lexer::Lexer;
+
+/// Spaces per nesting level inside a definition
+pub const INDENT: usize = 2;
+
+/// Longest definition kept on a single line
+pub const MAX_WIDTH: usize = 80;
+
+/// Keywords that open a nested body
+const OPENERS: [&str; 3] = ["IF", "BEGIN", "DO"];
+/// Keywords between two bodies of one structure
+const MIDDLES: [&str; 2] = ["ELSE", "WHILE"];
+/// Keywords that close a nested body
+const CLOSERS: [&str; 5] = ["THEN", "UNTIL", "REPEAT", "LOOP", "+LOOP"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Word,
+    /// A string, with its opening word if any, copied as written
+    Text,
+    /// `( ... )`
+    Comment,
+    /// `\ ...` up to the end of the line
+    LineComment,
+}
+
+/// A token of the source, as written
+#[derive(Debug, Clone, Copy)]
+struct Item<'a> {
+    kind: ItemKind,
+    text: &'a str,
+    /// Line breaks between the previous item and this one
+    newlines_before: usize,
+}
+
+impl Item<'_> {
+    fn is_word(&self, word: &str) -> bool {
+        self.kind == ItemKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    fn is_any_of(&self, words: &[&str]) -> bool {
+        words.iter().any(|word| self.is_word(word))
+    }
+
+    fn is_control(&self) -> bool {
+        self.is_any_of(&OPENERS) || self.is_any_of(&MIDDLES) || self.is_any_of(&CLOSERS)
+    }
+}
+
+/// Split `source` into items, splitting words where the lexer does
+fn scan(source: &str) -> Vec<Item<'_>> {
+    let mut items = Vec::new();
+    let mut newlines = 0;
+    let mut pos = 0;
+
+    while let Some(ch) = source[pos..].chars().next() {
+        if ch.is_whitespace() {
+            newlines += usize::from(ch == '\n');
+            pos += ch.len_utf8();
+            continue;
+        }
+
+        let (kind, end) = match ch {
+            '\\' => (ItemKind::LineComment, source[pos..].find('\n').map_or(source.len(), |n| pos + n)),
+            '(' => (ItemKind::Comment, paren_end(source, pos)),
+            '"' => (ItemKind::Text, closing(source, pos + 1, '"')),
+            ')' => (ItemKind::Word, pos + 1),
+            _ => {
+                let end = source[pos..]
+                    .find(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')')
+                    .map_or(source.len(), |n| pos + n);
+                match &*source[pos..end].to_ascii_lowercase() {
+                    ".\"" | "s\"" | "c\"" | "abort\"" => (ItemKind::Text, closing(source, end, '"')),
+                    "." if source[end..].starts_with('(') => (ItemKind::Text, closing(source, end, ')')),
+                    _ => (ItemKind::Word, end),
+                }
+            }
+        };
+
+        items.push(Item {
+            kind,
+            text: source[pos..end].trim_end(),
+            newlines_before: newlines,
+        });
+        newlines = 0;
+        pos = end;
+    }
+
+    items
+}
+
+/// End of the `( ... )` comment starting at `start`; comments nest
+fn paren_end(source: &str, start: usize) -> usize {
+    let mut depth = 0;
+    for (offset, ch) in source[start..].char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return start + offset + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// End of a string that closes with `close`, searching from `from`;
+/// backslash escapes are skipped as the lexer does
+fn closing(source: &str, from: usize, close: char) -> usize {
+    let mut chars = source[from..].char_indices();
+    while let Some((offset, ch)) = chars.next() {
+        if ch == '\\' && close == '"' {
+            chars.next();
+        } else if ch == close {
+            return from + offset + ch.len_utf8();
+        }
+    }
+    source.len()
+}
+
+/// A line or group of lines of formatted output
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    Blank,
+    /// A line comment on a line of its own
+    Comment(String),
+    Code(String),
+    Definition(Vec<String>),
+}
+
+/// Format `source`, failing if it does not lex
+pub fn format_source(source: &str) -> Result<String> {
+    let expected = Lexer::new(source).tokenize()?;
+    let items = scan(source);
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut i = 0;
+    while i < items.len() {
+        let item = items[i];
+        if item.newlines_before > 0 || item.is_word(":") {
+            flush(&mut line, &mut lines);
+            if item.newlines_before > 1 {
+                lines.push(Line::Blank);
+            }
+        }
+
+        if item.is_word(":") {
+            let (definition, next) = format_definition(&items, i);
+            lines.push(definition);
+            i = next;
+            continue;
+        }
+
+        if item.kind == ItemKind::LineComment && line.is_empty() {
+            lines.push(Line::Comment(item.text.to_string()));
+        } else {
+            append(&mut line, item.text);
+        }
+        i += 1;
+    }
+    flush(&mut line, &mut lines);
+
+    let formatted = render(&lines);
+    let actual = Lexer::new(&formatted).tokenize()?;
+    if actual != expected {
+        return Err(ForthError::InternalError {
+            message: "formatting changed the program's tokens".to_string(),
+        });
+    }
+    Ok(formatted)
+}
+
+/// Whether `source` is already formatted
+pub fn is_formatted(source: &str) -> Result<bool> {
+    Ok(format_source(source)? == source)
+}
+
+/// Add `text` to `line`, after a space unless the line is empty
+fn append(line: &mut String, text: &str) {
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(text);
+}
+
+fn flush(line: &mut String, lines: &mut Vec<Line>) {
+    if !line.is_empty() {
+        lines.push(Line::Code(std::mem::take(line)));
+    }
+}
+
+/// Format the definition whose `:` is `items[start]`, returning it and the
+/// index of the first item after it
+fn format_definition(items: &[Item<'_>], start: usize) -> (Line, usize) {
+    let mut i = start + 1;
+    let mut header = String::from(":");
+
+    if let Some(name) = items.get(i) {
+        append(&mut header, name.text);
+        i += 1;
+    }
+    if let Some(comment) = items.get(i).filter(|item| item.kind == ItemKind::Comment) {
+        append(&mut header, comment.text);
+        i += 1;
+    }
+    // A comment on the header's line stays there
+    let header_comment = items
+        .get(i)
+        .filter(|item| item.kind == ItemKind::LineComment && item.newlines_before == 0);
+    if let Some(comment) = header_comment {
+        append(&mut header, comment.text);
+        i += 1;
+    }
+
+    let body_start = i;
+    while i < items.len() && !items[i].is_word(";") {
+        i += 1;
+    }
+    let body = &items[body_start..i];
+
+    let mut end = String::new();
+    if let Some(semicolon) = items.get(i) {
+        end.push_str(semicolon.text);
+        i += 1;
+    }
+    if let Some(immediate) = items.get(i).filter(|item| item.is_word("immediate")) {
+        append(&mut end, immediate.text);
+        i += 1;
+    }
+    let trailing = items
+        .get(i)
+        .filter(|item| item.kind == ItemKind::LineComment && item.newlines_before == 0);
+    if let Some(comment) = trailing {
+        append(&mut end, comment.text);
+        i += 1;
+    }
+
+    let simple = header_comment.is_none()
+        && body
+            .iter()
+            .all(|item| item.kind != ItemKind::LineComment && !item.is_control());
+    if simple {
+        let mut line = header.clone();
+        for item in body {
+            append(&mut line, item.text);
+        }
+        let width = line.len() + 1 + end.len();
+        append(&mut line, &end);
+        if width <= MAX_WIDTH {
+            return (Line::Definition(vec![line]), i);
+        }
+    }
+
+    let mut lines = vec![header];
+    let mut depth = 1;
+    let mut line = String::new();
+    let indent = |depth: usize| " ".repeat(depth * INDENT);
+    for item in body {
+        if item.newlines_before > 0 || item.is_control() || item.is_word("does>") {
+            lines.extend(take_line(&mut line));
+        }
+
+        if item.is_any_of(&OPENERS) {
+            lines.push(indent(depth) + item.text);
+            depth += 1;
+        } else if item.is_any_of(&MIDDLES) {
+            lines.push(indent(depth.saturating_sub(1)) + item.text);
+        } else if item.is_any_of(&CLOSERS) {
+            depth = depth.saturating_sub(1);
+            lines.push(indent(depth) + item.text);
+        } else if item.is_word("does>") {
+            lines.push(indent(depth) + item.text);
+        } else {
+            if line.is_empty() {
+                line = indent(depth);
+            } else {
+                line.push(' ');
+            }
+            line.push_str(item.text);
+            if item.kind == ItemKind::LineComment {
+                lines.extend(take_line(&mut line));
+            }
+        }
+    }
+    lines.extend(take_line(&mut line));
+    lines.push(end);
+
+    (Line::Definition(lines), i)
+}
+
+fn take_line(line: &mut String) -> Option<String> {
+    (!line.is_empty()).then(|| std::mem::take(line))
+}
+
+/// Join the formatted lines, with a blank line between each definition and
+/// what surrounds it; a comment directly above a definition stays with it
+fn render(lines: &[Line]) -> String {
+    let mut out = String::new();
+    let mut previous: Option<&Line> = None;
+    let mut blank = false;
+
+    for line in lines {
+        if *line == Line::Blank {
+            blank = previous.is_some();
+            continue;
+        }
+
+        let separate = blank
+            || matches!(previous, Some(Line::Definition(_)))
+            || (matches!(line, Line::Definition(_)) && matches!(previous, Some(Line::Code(_))));
+        if separate {
+            out.push('\n');
+        }
+
+        match line {
+            Line::Comment(text) | Line::Code(text) => {
+                out.push_str(text);
+                out.push('\n');
+            }
+            Line::Definition(text) => {
+                for text in text {
+                    out.push_str(text);
+                    out.push('\n');
+                }
+            }
+            Line::Blank => unreachable!(),
+        }
+        previous = Some(line);
+        blank = false;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_ugly_source() {
+        let ugly = "\\ Factorial\n:   factorial ( n -- n! )   dup 1 <= if drop 1\n else dup 1 - factorial * then ;\n\n\n\n5   factorial .   \\ prints 120\n: square  ( n -- n*n )\n  dup   * ; 7 square .\n";
+        let expected = "\
+\\ Factorial
+: factorial ( n -- n! )
+  dup 1 <=
+  if
+    drop 1
+  else
+    dup 1 - factorial *
+  then
+;
+
+5 factorial . \\ prints 120
+
+: square ( n -- n*n ) dup * ;
+
+7 square .
+";
+        let formatted = format_source(ugly).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+        assert!(is_formatted(&formatted).unwrap());
+        assert!(!is_formatted(ugly).unwrap());
+    }
+
+    #[test]
+    fn test_format_nested_and_comments() {
+        let source = ": sign ( n -- n ) \\ -1, 0 or 1\n dup 0 < if drop -1 else 0 > if 1 ( positive ) else 0 then then ;\n: countdown begin dup . 1 - dup 0 = until drop ; immediate \\ done\n";
+        let expected = "\
+: sign ( n -- n ) \\ -1, 0 or 1
+  dup 0 <
+  if
+    drop -1
+  else
+    0 >
+    if
+      1 ( positive )
+    else
+      0
+    then
+  then
+;
+
+: countdown
+  begin
+    dup . 1 - dup 0 =
+  until
+  drop
+; immediate \\ done
+";
+        let formatted = format_source(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_leaves_strings_alone() {
+        let source = ": greet   .\"  hello,   world\"   s\" a  b\" type  \"x  \\\" y\" ;";
+        let formatted = format_source(source).unwrap();
+        assert_eq!(formatted, ": greet .\"  hello,   world\" s\" a  b\" type \"x  \\\" y\" ;\n");
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_long_definition_keeps_line_breaks() {
+        let words = vec!["word"; 20].join(" ");
+        let source = format!(": long\n  {}\n  {} ;", words, words);
+        let formatted = format_source(&source).unwrap();
+        assert_eq!(formatted, format!(": long\n  {}\n  {}\n;\n", words, words));
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_rejects_unlexable_source() {
+        assert!(format_source(": broken \"unterminated ;").is_err());
+    }
+}
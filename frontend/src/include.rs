@@ -0,0 +1,189 @@
+//! Multi-file programs: `INCLUDE path` and `REQUIRE path`
+//!
+//! Both directives splice the definitions and top-level code of another file
+//! into the program at the point where they appear. `REQUIRE` skips files
+//! that are already loaded; `INCLUDE` loads them again, but fails on a file
+//! that is still being parsed. Relative paths are resolved against the
+//! directory of the including file (the working directory for source text),
+//! then against each directory of the search path.
+
+use crate::ast::{Program, SourceLocation};
+use crate::error::{ForthError, Result};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Where included files are looked up, and which are loaded
+#[derive(Debug, Clone, Default)]
+pub struct IncludeContext {
+    /// Directories searched after the including file's own
+    search_path: Vec<PathBuf>,
+    /// Canonical paths of every file loaded so far
+    loaded: HashSet<PathBuf>,
+    /// Files being parsed, outermost first
+    active: Vec<PathBuf>,
+}
+
+impl IncludeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also look for included files in `dir`
+    pub fn add_search_path(&mut self, dir: impl Into<PathBuf>) {
+        self.search_path.push(dir.into());
+    }
+
+    /// Canonical paths of the files loaded so far
+    pub fn loaded(&self) -> impl Iterator<Item = &Path> {
+        self.loaded.iter().map(PathBuf::as_path)
+    }
+
+    /// Forget which files are loaded, keeping the search path
+    pub fn reset(&mut self) {
+        self.loaded.clear();
+        self.active.clear();
+    }
+
+    /// Parse the file at `path` and everything it includes
+    pub fn parse_file(&mut self, path: &Path) -> Result<Program> {
+        let error = |e: std::io::Error| ForthError::ParseError {
+            line: 0,
+            column: 0,
+            message: format!("Cannot read {}: {}", path.display(), e),
+        };
+        let file = path.canonicalize().map_err(error)?;
+        let source = std::fs::read_to_string(&file).map_err(error)?;
+
+        self.loaded.insert(file.clone());
+        self.active.push(file);
+        let program = self.parse_source(&source);
+        self.active.pop();
+        program
+    }
+
+    /// Parse `source`, resolving includes against the working directory
+    pub fn parse_source(&mut self, source: &str) -> Result<Program> {
+        let (tokens, locations) = Lexer::new(source).tokenize_with_locations()?;
+        let mut parser = Parser::new(tokens).with_locations(locations).with_includes(std::mem::take(self));
+        let program = parser.parse_program();
+        *self = parser.into_includes();
+        program
+    }
+
+    /// Resolve `path` as written in the file being parsed
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return path.canonicalize().ok();
+        }
+        let own_dir = match self.active.last() {
+            Some(file) => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => PathBuf::from("."),
+        };
+        std::iter::once(&own_dir)
+            .chain(&self.search_path)
+            .find_map(|dir| dir.join(path).canonicalize().ok())
+    }
+
+    /// Name of the file being parsed, for messages
+    fn current(&self) -> String {
+        self.active.last().map_or_else(|| "<source>".to_string(), |file| file.display().to_string())
+    }
+
+    /// Start loading the file `path` names for an `INCLUDE` (or, if `once`,
+    /// a `REQUIRE`) at `location` of the file being parsed
+    ///
+    /// Returns the file's source, to be parsed before calling
+    /// [`close`](Self::close), or `None` if it is required and already loaded.
+    pub(crate) fn open(&mut self, path: &str, once: bool, location: &SourceLocation) -> Result<Option<String>> {
+        let error = |message: String| ForthError::ParseError {
+            line: location.line,
+            column: location.column,
+            message,
+        };
+
+        let file = self
+            .resolve(path)
+            .ok_or_else(|| error(format!("Cannot find included file '{}' (included from {})", path, self.current())))?;
+        if once && self.loaded.contains(&file) {
+            return Ok(None);
+        }
+        if self.active.contains(&file) {
+            return Err(error(format!("Circular include of {} from {}", file.display(), self.current())));
+        }
+        let source = std::fs::read_to_string(&file).map_err(|e| {
+            error(format!("Cannot read {} (included from {}): {}", file.display(), self.current(), e))
+        })?;
+
+        self.loaded.insert(file.clone());
+        self.active.push(file);
+        Ok(Some(source))
+    }
+
+    /// Finish loading the innermost file started by [`open`](Self::open)
+    pub(crate) fn close(&mut self) {
+        self.active.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Word;
+    use std::fs;
+
+    /// Fresh directory for one test's files
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fastforth_include_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_include_splices_in_order() {
+        let dir = temp_dir("splice");
+        fs::write(dir.join("lib/square.fs"), ": square dup * ;\n3 constant three").unwrap();
+        fs::write(dir.join("lib/util.fs"), "include \"square.fs\"\n: cube dup square * ;").unwrap();
+        fs::write(dir.join("main.fs"), "REQUIRE lib/util.fs\nrequire lib/util.fs\n: x [ three ] literal cube ;").unwrap();
+
+        let mut includes = IncludeContext::new();
+        let program = includes.parse_file(&dir.join("main.fs")).unwrap();
+        let names: Vec<&str> = program.definitions.iter().map(|def| def.name.as_str()).collect();
+        assert_eq!(names, vec!["square", "cube", "x"]);
+        assert_eq!(program.definitions[2].body[0], Word::IntLiteral(3));
+        assert_eq!(includes.loaded().count(), 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let dir = temp_dir("errors");
+        fs::write(dir.join("a.fs"), "include b.fs").unwrap();
+        fs::write(dir.join("b.fs"), "1 2 +\ninclude a.fs").unwrap();
+        fs::write(dir.join("main.fs"), ": x 1 ;\n  include missing.fs").unwrap();
+
+        let err = IncludeContext::new().parse_file(&dir.join("a.fs")).unwrap_err();
+        assert!(err.to_string().contains("Circular include"), "{}", err);
+
+        match IncludeContext::new().parse_file(&dir.join("main.fs")).unwrap_err() {
+            ForthError::ParseError { line, column, message } => {
+                assert_eq!((line, column), (2, 3));
+                assert!(message.contains("'missing.fs'") && message.contains("main.fs"), "{}", message);
+            }
+            err => panic!("unexpected error {}", err),
+        }
+
+        // The search path is tried after the including file's directory
+        let mut includes = IncludeContext::new();
+        includes.add_search_path(&dir);
+        assert!(includes.parse_source("require b.fs").is_err());
+        let program = includes.parse_source(": y 2 ;").unwrap();
+        assert_eq!(program.definitions.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,103 @@
 //! Lexical analyzer for Forth source code
+//!
+//! Which tokens are numbers is decided by a [`NumberParser`], so dialects
+//! with other number syntaxes can plug in their own. [`StandardNumbers`] is
+//! the default; [`DigitSeparators`] adds `1_000_000` style separators to any
+//! strategy.
 
 use crate::ast::{SourceLocation, Token};
 use crate::error::{ForthError, Result};
+use std::collections::HashSet;
+
+/// Strategy for recognizing numeric literals
+///
+/// A dialect that reads a trailing `.` as a double-cell number rather than a
+/// float can return [`Token::Integer`] for it, since cells are 64 bits wide.
+pub trait NumberParser {
+    /// Parse `text`, a whole token, as a number in `base`
+    ///
+    /// Returns `None` if `text` is not a number, in which case it is lexed
+    /// as a word, or an error message if it is a malformed one.
+    fn parse(&self, text: &str, base: u32) -> Option<std::result::Result<Token, String>>;
+}
+
+/// Integers in the current base, and decimal floats with a `.` or exponent
+///
+/// Decimal integers must lie in `i64::MIN..=i64::MAX`. Integers in other
+/// bases may use all 64 bits of a cell and are reinterpreted as signed, so
+/// `HEX ffffffffffffffff` is -1. Integers out of range are errors rather
+/// than words.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardNumbers;
+
+impl NumberParser for StandardNumbers {
+    fn parse(&self, text: &str, base: u32) -> Option<std::result::Result<Token, String>> {
+        if base != 10 {
+            return parse_cell_bits(text, base);
+        }
+
+        // Words such as 2dup also start with a digit
+        let unsigned = text.strip_prefix('-').unwrap_or(text);
+        if !unsigned.starts_with(|ch: char| ch.is_ascii_digit()) {
+            return None;
+        }
+        if unsigned.chars().all(|ch| ch.is_ascii_digit()) {
+            return Some(text.parse().map(Token::Integer).map_err(|_| {
+                format!("Integer literal {} is out of range {}..={}", text, i64::MIN, i64::MAX)
+            }));
+        }
+        if unsigned.chars().all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E' | '+' | '-')) {
+            return text.parse().ok().map(|value| Ok(Token::Float(value)));
+        }
+        None
+    }
+}
+
+/// `text` as an integer in `base` whose digits fill at most the 64 bits of a cell
+fn parse_cell_bits(text: &str, base: u32) -> Option<std::result::Result<Token, String>> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(base)) {
+        return None;
+    }
+
+    Some(match u64::from_str_radix(digits, base) {
+        Ok(bits) if negative => Ok(Token::Integer((bits as i64).wrapping_neg())),
+        Ok(bits) => Ok(Token::Integer(bits as i64)),
+        Err(_) => Err(format!("Integer literal {} in base {} does not fit in 64 bits", text, base)),
+    })
+}
+
+/// The numbers of another strategy, with `_` allowed between digits
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DigitSeparators<P = StandardNumbers>(pub P);
+
+impl<P: NumberParser> NumberParser for DigitSeparators<P> {
+    fn parse(&self, text: &str, base: u32) -> Option<std::result::Result<Token, String>> {
+        if !text.contains('_') {
+            return self.0.parse(text, base);
+        }
+
+        // Names such as my_word stay words
+        let unsigned = text.strip_prefix('-').unwrap_or(text);
+        if !unsigned.starts_with(|ch: char| ch.is_digit(base)) {
+            return None;
+        }
+        let stripped: String = text.chars().filter(|&ch| ch != '_').collect();
+        let number = self.0.parse(&stripped, base)?;
+
+        let chars: Vec<char> = text.chars().collect();
+        let between_digits = |i: usize| {
+            i > 0 && chars[i - 1].is_digit(base) && chars.get(i + 1).is_some_and(|ch| ch.is_digit(base))
+        };
+        if (0..chars.len()).any(|i| chars[i] == '_' && !between_digits(i)) {
+            return Some(Err(format!("Digit separator not between digits: {}", text)));
+        }
+        Some(number)
+    }
+}
 
 /// Lexer state
 pub struct Lexer<'a> {
@@ -9,6 +105,20 @@ pub struct Lexer<'a> {
     position: usize,
     line: usize,
     column: usize,
+    /// Radix for integer literals, switched by `HEX` and `DECIMAL`
+    base: u32,
+    /// Where the token being read starts
+    token_start: SourceLocation,
+    /// Decides which tokens are numbers
+    numbers: Box<dyn NumberParser>,
+    /// Names defined so far, in lower case, which stay words even where
+    /// they read as numbers in the current base
+    defined: HashSet<String>,
+    /// Whether the next token, if a word, names a new definition or data word
+    naming: bool,
+    /// Whether a colon definition is open, in which `CREATE` and the like
+    /// take their name when run rather than from the source
+    compiling: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -18,9 +128,21 @@ impl<'a> Lexer<'a> {
             position: 0,
             line: 1,
             column: 1,
+            base: 10,
+            token_start: SourceLocation { line: 1, column: 1 },
+            numbers: Box::new(StandardNumbers),
+            defined: HashSet::new(),
+            naming: false,
+            compiling: false,
         }
     }
 
+    /// Recognize numbers with `numbers` instead of [`StandardNumbers`]
+    pub fn with_number_parser(mut self, numbers: impl NumberParser + 'static) -> Self {
+        self.numbers = Box::new(numbers);
+        self
+    }
+
     pub fn location(&self) -> SourceLocation {
         SourceLocation {
             line: self.line,
@@ -99,10 +221,7 @@ impl<'a> Lexer<'a> {
                     self.advance();
                 }
                 None => {
-                    return Err(ForthError::LexError {
-                        position: self.position,
-                        message: "Unclosed parenthesized comment".to_string(),
-                    });
+                    return Err(ForthError::incomplete_input("Unclosed parenthesized comment"));
                 }
             }
         }
@@ -124,6 +243,24 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Parse the text of `S" text"`, after the `S"` itself
+    ///
+    /// One space separates `S"` from the text and is not part of it; the
+    /// text runs to the next `"`, with no escapes.
+    fn parse_s_quote(&mut self) -> Result<Token> {
+        if self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+        let mut value = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(Token::String(value)),
+                Some(ch) => value.push(ch),
+                None => return Err(ForthError::incomplete_input("Unterminated S\" string")),
+            }
+        }
+    }
+
     /// Parse a string literal
     fn parse_string(&mut self) -> Result<Token> {
         self.advance(); // consume opening quote
@@ -157,57 +294,10 @@ impl<'a> Lexer<'a> {
                     self.advance();
                 }
                 None => {
-                    return Err(ForthError::LexError {
-                        position: self.position,
-                        message: "Unterminated string literal".to_string(),
-                    })
-                }
-            }
-        }
-    }
-
-    /// Parse a number (integer or float)
-    fn parse_number(&mut self, first_char: char) -> Result<Token> {
-        let mut num_str = String::new();
-        num_str.push(first_char);
-
-        let mut is_float = false;
-
-        while let Some(ch) = self.peek() {
-            if ch.is_ascii_digit() {
-                num_str.push(ch);
-                self.advance();
-            } else if ch == '.' && !is_float {
-                is_float = true;
-                num_str.push(ch);
-                self.advance();
-            } else if ch == 'e' || ch == 'E' {
-                is_float = true;
-                num_str.push(ch);
-                self.advance();
-                if let Some('+' | '-') = self.peek() {
-                    num_str.push(self.advance().unwrap());
+                    return Err(ForthError::incomplete_input("Unterminated string literal"))
                 }
-            } else {
-                break;
             }
         }
-
-        if is_float {
-            num_str.parse::<f64>()
-                .map(Token::Float)
-                .map_err(|_| ForthError::LexError {
-                    position: self.position,
-                    message: format!("Invalid float literal: {}", num_str),
-                })
-        } else {
-            num_str.parse::<i64>()
-                .map(Token::Integer)
-                .map_err(|_| ForthError::LexError {
-                    position: self.position,
-                    message: format!("Invalid integer literal: {}", num_str),
-                })
-        }
     }
 
     /// Parse a word/identifier
@@ -237,23 +327,37 @@ impl<'a> Lexer<'a> {
             "REPEAT" => Token::Repeat,
             "VARIABLE" => Token::Variable,
             "CONSTANT" => Token::Constant,
+            "VALUE" => Token::Value,
+            "TO" => Token::To,
+            "CREATE" => Token::Create,
+            "DOES>" => Token::Does,
             "IMMEDIATE" => Token::Immediate,
             _ => Token::Word(word),
         }
     }
 
     /// Get the next token
+    ///
+    /// `HEX` and `DECIMAL` take effect here, switching the base of the
+    /// integer literals that follow them, and produce no token themselves.
+    /// Every other token that is not punctuation, a string or a comment is a
+    /// word if it names a word defined earlier in the input; only otherwise
+    /// is it offered to the number strategy.
     pub fn next_token(&mut self) -> Result<Token> {
         self.skip_whitespace();
+        self.token_start = self.location();
 
         match self.peek() {
             None => Ok(Token::Eof),
             Some(':') => {
                 self.advance();
+                self.naming = true;
+                self.compiling = true;
                 Ok(Token::Colon)
             }
             Some(';') => {
                 self.advance();
+                self.compiling = false;
                 Ok(Token::Semicolon)
             }
             Some('(') => self.parse_paren_comment(),
@@ -266,63 +370,73 @@ impl<'a> Lexer<'a> {
                 self.skip_line_comment();
                 self.next_token()
             }
-            Some('-') => {
+            Some('-') if self.input[self.position..].starts_with("--") => {
                 self.advance();
-                if self.peek() == Some('-') {
-                    self.advance();
-                    Ok(Token::StackEffectSep)
-                } else if let Some(ch) = self.peek() {
-                    if ch.is_ascii_digit() {
-                        self.parse_number('-')
-                    } else {
-                        Ok(Token::Word("-".to_string()))
-                    }
-                } else {
-                    Ok(Token::Word("-".to_string()))
-                }
-            }
-            Some(ch) if ch.is_ascii_digit() => {
-                // Peek ahead to see if this is a number or a word starting with a digit
-                // (like 2dup, 2swap, etc.)
-                let saved_pos = self.position;
                 self.advance();
-
-                // Check if the next character would make this a word
-                let is_word = if let Some(next_ch) = self.peek() {
-                    !next_ch.is_ascii_digit() && !next_ch.is_whitespace() && next_ch != '(' && next_ch != ')' && next_ch != '.'
-                } else {
-                    false
-                };
-
-                if is_word {
-                    // Reset and parse as word
-                    self.position = saved_pos;
-                    self.advance();
-                    Ok(self.parse_word(ch))
-                } else {
-                    // Parse as number
-                    self.parse_number(ch)
-                }
+                Ok(Token::StackEffectSep)
             }
             Some(ch) => {
+                // Words are looked up first; the number strategy gets the rest
+                let input = self.input;
+                let rest = &input[self.position..];
+                let end = rest
+                    .find(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')')
+                    .unwrap_or(rest.len());
+                let text = &rest[..end];
+                let naming = std::mem::take(&mut self.naming);
+                let defined = self.defined.contains(&text.to_lowercase());
+                if let Some(number) = self.numbers.parse(text, self.base).filter(|_| !defined) {
+                    for _ in text.chars() {
+                        self.advance();
+                    }
+                    let start = &self.token_start;
+                    return number.map_err(|message| ForthError::parse_error(start.line, start.column, message));
+                }
+
                 self.advance();
-                Ok(self.parse_word(ch))
+                match self.parse_word(ch) {
+                    Token::Word(word) if naming => {
+                        self.defined.insert(word.to_lowercase());
+                        Ok(Token::Word(word))
+                    }
+                    token @ (Token::Variable | Token::Constant | Token::Value | Token::Create) => {
+                        self.naming = !self.compiling;
+                        Ok(token)
+                    }
+                    Token::Word(word) if word.eq_ignore_ascii_case("hex") => {
+                        self.base = 16;
+                        self.next_token()
+                    }
+                    Token::Word(word) if word.eq_ignore_ascii_case("decimal") => {
+                        self.base = 10;
+                        self.next_token()
+                    }
+                    Token::Word(word) if word.eq_ignore_ascii_case("s\"") => self.parse_s_quote(),
+                    token => Ok(token),
+                }
             }
         }
     }
 
     /// Tokenize the entire input
     pub fn tokenize(&mut self) -> Result<Vec<Token>> {
+        Ok(self.tokenize_with_locations()?.0)
+    }
+
+    /// Tokenize the entire input, along with where each token starts
+    pub fn tokenize_with_locations(&mut self) -> Result<(Vec<Token>, Vec<SourceLocation>)> {
         let mut tokens = Vec::new();
+        let mut locations = Vec::new();
         loop {
             let token = self.next_token()?;
+            locations.push(self.token_start.clone());
             if token == Token::Eof {
                 tokens.push(token);
                 break;
             }
             tokens.push(token);
         }
-        Ok(tokens)
+        Ok((tokens, locations))
     }
 }
 
@@ -369,4 +483,144 @@ mod tests {
             _ => panic!("Expected float token"),
         }
     }
+
+    #[test]
+    fn test_hex_and_decimal_switch_base() {
+        let mut lexer = Lexer::new("HEX ff -10 2dup DECIMAL 10");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Integer(255),
+                Token::Integer(-16),
+                Token::Word("2dup".to_string()),
+                Token::Integer(10),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_defined_words_are_not_numbers() {
+        let mut lexer = Lexer::new(": add + ; variable face HEX 1 2 ADD face BEEF");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(
+            &tokens[6..],
+            &[
+                Token::Integer(1),
+                Token::Integer(2),
+                Token::Word("ADD".to_string()),
+                Token::Word("face".to_string()),
+                Token::Integer(0xbeef),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_range() {
+        let source = "9223372036854775807 -9223372036854775808 -0009223372036854775808 007";
+        assert_eq!(
+            Lexer::new(source).tokenize().unwrap(),
+            vec![
+                Token::Integer(i64::MAX),
+                Token::Integer(i64::MIN),
+                Token::Integer(i64::MIN),
+                Token::Integer(7),
+                Token::Eof,
+            ]
+        );
+
+        let overflowing = [("1 99999999999999999999", 3), ("9223372036854775808", 1), ("-9223372036854775809", 1)];
+        for (source, column) in overflowing {
+            match Lexer::new(source).tokenize() {
+                Err(ForthError::ParseError { line: 1, column: found, message }) => {
+                    assert_eq!(found, column, "{}", source);
+                    assert!(message.contains(source.rsplit(' ').next().unwrap()), "{}", message);
+                }
+                other => panic!("{}: expected a parse error, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_decimal_literals_fill_the_cell() {
+        let source = "HEX ffffffffffffffff 7fffffffffffffff 0000ffffffffffffffff -8000000000000000 -a";
+        assert_eq!(
+            Lexer::new(source).tokenize().unwrap(),
+            vec![
+                Token::Integer(-1),
+                Token::Integer(i64::MAX),
+                Token::Integer(-1),
+                Token::Integer(i64::MIN),
+                Token::Integer(-10),
+                Token::Eof,
+            ]
+        );
+
+        for source in ["HEX 10000000000000000", "HEX 1 -10000000000000000"] {
+            assert!(
+                matches!(Lexer::new(source).tokenize(), Err(ForthError::ParseError { .. })),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_s_quote_string() {
+        let mut lexer = Lexer::new(r#"S" hi there" type s" " s"  (x)" dup"#);
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::String("hi there".to_string()),
+                Token::Word("type".to_string()),
+                Token::String(String::new()),
+                Token::String(" (x)".to_string()),
+                Token::Word("dup".to_string()),
+                Token::Eof,
+            ]
+        );
+
+        assert!(Lexer::new(r#"s" open"#).tokenize().is_err());
+    }
+
+    /// Dialect where a trailing `.` makes a (single-cell) double
+    struct TrailingDotDoubles;
+
+    impl NumberParser for TrailingDotDoubles {
+        fn parse(&self, text: &str, base: u32) -> Option<std::result::Result<Token, String>> {
+            match text.strip_suffix('.') {
+                Some(digits) if digits.parse::<i64>().is_ok() => digits.parse().ok().map(|value| Ok(Token::Integer(value))),
+                _ => StandardNumbers.parse(text, base),
+            }
+        }
+    }
+
+    #[test]
+    fn test_number_parser_strategies() {
+        let mut lexer = Lexer::new("1_000 -2_500 my_word 2dup 1.5").with_number_parser(DigitSeparators(StandardNumbers));
+        assert_eq!(
+            lexer.tokenize().unwrap(),
+            vec![
+                Token::Integer(1000),
+                Token::Integer(-2500),
+                Token::Word("my_word".to_string()),
+                Token::Word("2dup".to_string()),
+                Token::Float(1.5),
+                Token::Eof,
+            ]
+        );
+        for source in ["1_", "1__000", "-1_", "1_.5"] {
+            let mut lexer = Lexer::new(source).with_number_parser(DigitSeparators::<StandardNumbers>::default());
+            assert!(lexer.tokenize().is_err(), "{}", source);
+        }
+
+        // Without the strategy, separators make a word
+        assert_eq!(Lexer::new("1_000").tokenize().unwrap()[0], Token::Word("1_000".to_string()));
+
+        let mut lexer = Lexer::new("12. 1.5").with_number_parser(TrailingDotDoubles);
+        assert_eq!(lexer.tokenize().unwrap()[..2], [Token::Integer(12), Token::Float(1.5)]);
+    }
 }
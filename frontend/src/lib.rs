@@ -9,19 +9,29 @@
 
 pub mod error;
 pub mod ast;
+pub mod effect;
+pub mod builtins;
+pub mod const_eval;
 pub mod lexer;
+pub mod format;
 pub mod parser;
+pub mod include;
 pub mod stack_effects;
 pub mod type_inference;
 pub mod ssa;
 pub mod ssa_validator;
+pub mod licm;
 pub mod semantic;
+pub mod execute;
 
-pub use error::{ForthError, Result};
-pub use ast::{Program, Definition, Word, StackEffect};
-pub use parser::parse_program;
+pub use error::{ForthError, Result, MAX_REPORTED_ERRORS};
+pub use ast::{Program, Definition, Locals, Word, StackEffect};
+pub use parser::{parse_program, StreamingParser};
+pub use include::IncludeContext;
 pub use semantic::analyze;
-pub use ssa::{convert_to_ssa, SSAFunction};
+pub use execute::resolve_execute;
+pub use stack_effects::annotate_stack_effects;
+pub use ssa::{convert_to_ssa, convert_to_ssa_filtered, SSAFunction, MAIN_FUNCTION};
 pub use ssa_validator::SSAValidator;
 
 #[cfg(test)]
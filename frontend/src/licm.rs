@@ -0,0 +1,393 @@
+//! Loop-invariant code motion
+//!
+//! Finds the natural loops of an SSA function from its back edges (jumps to
+//! a block that dominates the jumping block) and moves instructions that
+//! compute the same value on every iteration into the loop's preheader, the
+//! block control enters the loop from. An instruction is invariant when it
+//! has no side effects and all its operands are defined outside the loop or
+//! by other invariant instructions. Two kinds stay in the loop anyway:
+//!
+//! - A `Load` the loop might overwrite the memory of, because it stores to
+//!   the same variable, through an address of unknown origin, or calls a
+//!   word that might store.
+//! - An instruction that can fault (division, a load through an address
+//!   that is not a variable's, or with checked arithmetic an operation that
+//!   can overflow) unless it runs whenever the loop is entered, so that
+//!   hoisting it never makes a fault happen that would not have.
+
+use crate::ssa::{BasicBlock, BinaryOperator, BlockId, Register, SSAFunction, SSAInstruction, UnaryOperator};
+use crate::ssa_validator::{destination_registers, used_registers};
+use std::collections::{HashMap, HashSet};
+
+/// Move the loop-invariant instructions of `function` to loop preheaders
+///
+/// Inner loops are processed first, so an invariant can move out of several
+/// nested loops. With `checked_arithmetic`, the operations that report
+/// overflow are treated as able to fault. Returns the number of
+/// instructions moved.
+pub fn hoist_loop_invariants(function: &mut SSAFunction, checked_arithmetic: bool) -> usize {
+    let mut loops = Cfg::new(function).loops();
+    loops.sort_by_key(|natural_loop| natural_loop.blocks.len());
+
+    let mut hoisted = 0;
+    for header in loops.into_iter().map(|natural_loop| natural_loop.header) {
+        // Preheaders added for inner loops become part of the enclosing ones
+        let cfg = Cfg::new(function);
+        if let Some(natural_loop) = cfg.loops().into_iter().find(|l| l.header == header) {
+            hoisted += hoist_from_loop(function, &cfg, &natural_loop, checked_arithmetic);
+        }
+    }
+    hoisted
+}
+
+/// A natural loop: the header and every block that can reach a back edge
+/// to it without passing through it
+struct Loop {
+    header: BlockId,
+    blocks: HashSet<BlockId>,
+}
+
+/// Control-flow graph of a function with its dominator sets
+struct Cfg {
+    successors: HashMap<BlockId, Vec<BlockId>>,
+    predecessors: HashMap<BlockId, Vec<BlockId>>,
+    /// Blocks dominating each reachable block, including itself
+    dominators: HashMap<BlockId, HashSet<BlockId>>,
+}
+
+impl Cfg {
+    fn new(function: &SSAFunction) -> Self {
+        let mut successors = HashMap::new();
+        let mut predecessors: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
+        for block in &function.blocks {
+            let targets = SSAFunction::successors(block);
+            for &target in &targets {
+                predecessors.entry(target).or_default().push(block.id);
+            }
+            successors.insert(block.id, targets);
+        }
+
+        // Iterate dom(b) = {b} ∪ ⋂ dom(p) over the predecessors p to a fixpoint
+        let all: HashSet<BlockId> = function.blocks.iter().map(|block| block.id).collect();
+        let mut dominators: HashMap<BlockId, HashSet<BlockId>> = all
+            .iter()
+            .map(|&id| (id, if id == function.entry_block { HashSet::from([id]) } else { all.clone() }))
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in function.blocks.iter().filter(|block| block.id != function.entry_block) {
+                let mut doms = predecessors
+                    .get(&block.id)
+                    .into_iter()
+                    .flatten()
+                    .map(|pred| &dominators[pred])
+                    .fold(None, |acc: Option<HashSet<BlockId>>, pred_doms| match acc {
+                        None => Some(pred_doms.clone()),
+                        Some(acc) => Some(acc.intersection(pred_doms).copied().collect()),
+                    })
+                    .unwrap_or_default();
+                doms.insert(block.id);
+                if doms != dominators[&block.id] {
+                    dominators.insert(block.id, doms);
+                    changed = true;
+                }
+            }
+        }
+
+        Self { successors, predecessors, dominators }
+    }
+
+    fn dominates(&self, dominator: BlockId, block: BlockId) -> bool {
+        self.dominators.get(&block).is_some_and(|doms| doms.contains(&dominator))
+    }
+
+    /// Natural loops, merging the bodies of back edges to the same header
+    fn loops(&self) -> Vec<Loop> {
+        let mut bodies: HashMap<BlockId, HashSet<BlockId>> = HashMap::new();
+        for (&from, targets) in &self.successors {
+            for &header in targets.iter().filter(|&&target| self.dominates(target, from)) {
+                let body = bodies.entry(header).or_insert_with(|| HashSet::from([header]));
+                let mut worklist = vec![from];
+                while let Some(block) = worklist.pop() {
+                    if body.insert(block) {
+                        worklist.extend(self.predecessors.get(&block).into_iter().flatten());
+                    }
+                }
+            }
+        }
+        bodies.into_iter().map(|(header, blocks)| Loop { header, blocks }).collect()
+    }
+}
+
+/// Memory an instruction may write: `Some(Some(name))` for a store to the
+/// variable `name`, `Some(None)` for unknown memory, `None` for none
+fn written_memory<'a>(inst: &SSAInstruction, variables: &HashMap<Register, &'a str>) -> Option<Option<&'a str>> {
+    match inst {
+        SSAInstruction::Store { address, .. } => Some(variables.get(address).copied()),
+        SSAInstruction::LoadInt { .. }
+        | SSAInstruction::LoadFloat { .. }
+        | SSAInstruction::LoadString { .. }
+        | SSAInstruction::BinaryOp { .. }
+        | SSAInstruction::UnaryOp { .. }
+        | SSAInstruction::Phi { .. }
+        | SSAInstruction::Load { .. }
+        | SSAInstruction::VariableAddr { .. }
+        | SSAInstruction::ExecutionToken { .. }
+        | SSAInstruction::Branch { .. }
+        | SSAInstruction::Jump { .. }
+        | SSAInstruction::Return { .. } => None,
+        _ => Some(None),
+    }
+}
+
+/// Hoist the invariant instructions of one loop, returning how many moved
+fn hoist_from_loop(function: &mut SSAFunction, cfg: &Cfg, natural_loop: &Loop, checked_arithmetic: bool) -> usize {
+    let in_loop = |block: &BasicBlock| natural_loop.blocks.contains(&block.id);
+
+    // Only loops with a single entry edge get a preheader
+    let entries: Vec<BlockId> = cfg.predecessors[&natural_loop.header]
+        .iter()
+        .copied()
+        .filter(|pred| !natural_loop.blocks.contains(pred))
+        .collect();
+    let [entry] = entries[..] else {
+        return 0;
+    };
+
+    let variables: HashMap<Register, &str> = function
+        .blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+        .filter_map(|inst| match inst {
+            SSAInstruction::VariableAddr { dest, name, .. } => Some((*dest, name.as_str())),
+            _ => None,
+        })
+        .collect();
+
+    let loop_instructions = || function.blocks.iter().filter(|block| in_loop(block)).flat_map(|block| &block.instructions);
+    let defined_in_loop: HashSet<Register> = loop_instructions().flat_map(destination_registers).collect();
+    let writes: Vec<Option<&str>> = loop_instructions().filter_map(|inst| written_memory(inst, &variables)).collect();
+    let exits: Vec<BlockId> = natural_loop
+        .blocks
+        .iter()
+        .copied()
+        .filter(|block| cfg.successors[block].iter().any(|succ| !natural_loop.blocks.contains(succ)))
+        .collect();
+
+    let hoistable = |inst: &SSAInstruction, block: BlockId| {
+        let may_fault = match inst {
+            SSAInstruction::LoadInt { .. }
+            | SSAInstruction::LoadFloat { .. }
+            | SSAInstruction::VariableAddr { .. }
+            | SSAInstruction::ExecutionToken { .. } => false,
+            SSAInstruction::UnaryOp { op, .. } => {
+                checked_arithmetic
+                    && matches!(
+                        op,
+                        UnaryOperator::Negate | UnaryOperator::Abs | UnaryOperator::IncOne | UnaryOperator::DecOne
+                    )
+            }
+            SSAInstruction::BinaryOp { op, .. } => match op {
+                BinaryOperator::Div | BinaryOperator::Mod => true,
+                BinaryOperator::Add | BinaryOperator::Sub | BinaryOperator::Mul => checked_arithmetic,
+                _ => false,
+            },
+            SSAInstruction::Load { address, .. } => {
+                let variable = variables.get(address).copied();
+                let aliased = writes.iter().any(|&write| write.is_none() || variable.is_none() || write == variable);
+                if aliased {
+                    return false;
+                }
+                variable.is_none()
+            }
+            _ => return false,
+        };
+        !may_fault || exits.iter().all(|&exit| cfg.dominates(block, exit))
+    };
+
+    // Grow the invariant set to a fixpoint, so instructions are recorded
+    // after the invariants they use
+    let mut invariant: HashSet<Register> = HashSet::new();
+    let mut moved: Vec<(BlockId, usize)> = Vec::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in function.blocks.iter().filter(|block| in_loop(block)) {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                if moved.contains(&(block.id, index)) || !hoistable(inst, block.id) {
+                    continue;
+                }
+                let operands_invariant = used_registers(inst)
+                    .iter()
+                    .all(|reg| !defined_in_loop.contains(reg) || invariant.contains(reg));
+                if operands_invariant {
+                    invariant.extend(destination_registers(inst));
+                    moved.push((block.id, index));
+                    changed = true;
+                }
+            }
+        }
+    }
+    if moved.is_empty() {
+        return 0;
+    }
+
+    let block_index = |function: &SSAFunction, id: BlockId| function.blocks.iter().position(|block| block.id == id);
+    let hoisted: Vec<SSAInstruction> = moved
+        .iter()
+        .map(|&(id, index)| function.blocks[block_index(function, id).unwrap()].instructions[index].clone())
+        .collect();
+    let mut removals = moved.clone();
+    removals.sort_unstable_by_key(|&(id, index)| std::cmp::Reverse((id.0, index)));
+    for (id, index) in removals {
+        let position = block_index(function, id).unwrap();
+        function.blocks[position].instructions.remove(index);
+    }
+
+    let entry_position = block_index(function, entry).unwrap();
+    if cfg.successors[&entry] == [natural_loop.header] {
+        // The entry block only jumps to the loop, so it is the preheader
+        let instructions = &mut function.blocks[entry_position].instructions;
+        let jump = instructions.len() - 1;
+        instructions.splice(jump..jump, hoisted);
+    } else {
+        // Split the entry edge with a new preheader block
+        let preheader = BlockId(function.blocks.iter().map(|block| block.id.0).max().unwrap_or(0) + 1);
+        for inst in &mut function.blocks[entry_position].instructions {
+            if let SSAInstruction::Branch { true_block, false_block, .. } = inst {
+                for target in [true_block, false_block] {
+                    if *target == natural_loop.header {
+                        *target = preheader;
+                    }
+                }
+            }
+        }
+        let header_position = block_index(function, natural_loop.header).unwrap();
+        for inst in &mut function.blocks[header_position].instructions {
+            if let SSAInstruction::Phi { incoming, .. } = inst {
+                for (pred, _) in incoming.iter_mut().filter(|(pred, _)| *pred == entry) {
+                    *pred = preheader;
+                }
+            }
+        }
+
+        let mut block = BasicBlock::new(preheader);
+        block.instructions = hoisted;
+        block.instructions.push(SSAInstruction::Jump { target: natural_loop.header });
+        function.blocks.push(block);
+        function.compute_predecessors();
+    }
+
+    moved.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_program;
+    use crate::ssa::convert_to_ssa;
+
+    fn convert(source: &str, name: &str) -> SSAFunction {
+        let program = parse_program(source).unwrap();
+        convert_to_ssa(&program).unwrap().into_iter().find(|func| func.name == name).unwrap()
+    }
+
+    /// Number of loads in the loop headed by `header`, and elsewhere
+    fn count_loads(function: &SSAFunction, header: BlockId) -> (usize, usize) {
+        let natural_loop = Cfg::new(function).loops().into_iter().find(|l| l.header == header).unwrap();
+        let mut counts = (0, 0);
+        for block in &function.blocks {
+            let loads = block.instructions.iter().filter(|inst| matches!(inst, SSAInstruction::Load { .. })).count();
+            if natural_loop.blocks.contains(&block.id) {
+                counts.0 += loads;
+            } else {
+                counts.1 += loads;
+            }
+        }
+        counts
+    }
+
+    fn loop_header(function: &SSAFunction) -> BlockId {
+        let loops = Cfg::new(function).loops();
+        assert_eq!(loops.len(), 1);
+        loops[0].header
+    }
+
+    #[test]
+    fn test_hoists_invariant_load() {
+        let mut function = convert("variable x : sum 0 begin x @ + dup 100 > until ;", "sum");
+        let header = loop_header(&function);
+        assert_eq!(count_loads(&function, header), (1, 0));
+
+        assert!(hoist_loop_invariants(&mut function, false) >= 2);
+        assert_eq!(count_loads(&function, header), (0, 1));
+        function.validate().unwrap();
+    }
+
+    #[test]
+    fn test_keeps_load_after_aliasing_store() {
+        let mut function = convert("variable x : f 0 begin x @ + dup x ! dup 100 > until ;", "f");
+        let header = loop_header(&function);
+        hoist_loop_invariants(&mut function, false);
+        assert_eq!(count_loads(&function, header), (1, 0));
+
+        // A store to another variable cannot change x
+        let mut function = convert("variable x variable y : g 0 begin x @ + dup y ! dup 100 > until ;", "g");
+        let header = loop_header(&function);
+        hoist_loop_invariants(&mut function, false);
+        assert_eq!(count_loads(&function, header), (0, 1));
+    }
+
+    #[test]
+    fn test_division_only_hoisted_when_always_run() {
+        let divisions = |function: &SSAFunction, header: BlockId| {
+            let natural_loop = Cfg::new(function).loops().into_iter().find(|l| l.header == header).unwrap();
+            function
+                .blocks
+                .iter()
+                .filter(|block| natural_loop.blocks.contains(&block.id))
+                .flat_map(|block| &block.instructions)
+                .filter(|inst| matches!(inst, SSAInstruction::BinaryOp { op: BinaryOperator::Div, .. }))
+                .count()
+        };
+
+        // The body of BEGIN ... UNTIL runs at least once
+        let mut function = convert(": f ( a b -- n ) begin over over / drop 1 until ;", "f");
+        let header = loop_header(&function);
+        hoist_loop_invariants(&mut function, false);
+        assert_eq!(divisions(&function, header), 0);
+
+        // The body of BEGIN ... WHILE ... REPEAT may never run
+        let mut function = convert(": g ( a b c -- n ) begin dup while over 2over drop / drop repeat ;", "g");
+        let header = loop_header(&function);
+        hoist_loop_invariants(&mut function, false);
+        assert_eq!(divisions(&function, header), 1);
+    }
+
+    #[test]
+    fn test_overflow_only_hoisted_when_always_run_if_checked() {
+        let additions = |function: &SSAFunction, header: BlockId| {
+            let natural_loop = Cfg::new(function).loops().into_iter().find(|l| l.header == header).unwrap();
+            function
+                .blocks
+                .iter()
+                .filter(|block| natural_loop.blocks.contains(&block.id))
+                .flat_map(|block| &block.instructions)
+                .filter(|inst| matches!(inst, SSAInstruction::BinaryOp { op: BinaryOperator::Add, .. }))
+                .count()
+        };
+        let source = ": f ( n -- n ) begin dup 0 > while 9223372036854775807 1 + drop 1 - repeat ;";
+
+        let mut function = convert(source, "f");
+        let header = loop_header(&function);
+        hoist_loop_invariants(&mut function, false);
+        assert_eq!(additions(&function, header), 0);
+
+        // The loop may never run, so an overflow must not move ahead of it
+        let mut function = convert(source, "f");
+        let header = loop_header(&function);
+        hoist_loop_invariants(&mut function, true);
+        assert_eq!(additions(&function, header), 1);
+    }
+}
@@ -1,23 +1,93 @@
 //! Parser for Forth source code
 
 use crate::ast::*;
-use crate::error::{ForthError, Result};
+use crate::const_eval::evaluate_word;
+use crate::error::{ForthError, Result, MAX_REPORTED_ERRORS};
+use crate::include::IncludeContext;
 use crate::lexer::Lexer;
+use std::collections::{HashMap, HashSet};
+
+/// Default limit on nested control structures, so that the recursive parser
+/// and SSA converter cannot exhaust the native stack
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 256;
 
 /// Parser state
 pub struct Parser {
     tokens: Vec<Token>,
+    /// Where each token starts, if known
+    locations: Vec<SourceLocation>,
     position: usize,
+    /// Number of control structures enclosing the current word
+    nesting: usize,
+    /// Deepest nesting accepted before parsing fails
+    max_depth: usize,
+    /// Definitions containing CREATE; at top level they consume the next name
+    defining_words: HashSet<String>,
+    /// Constants declared so far, usable in `[ ... ]`
+    constants: HashMap<String, i64>,
+    /// Files loaded by INCLUDE and REQUIRE, and where to find them
+    includes: IncludeContext,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
             tokens,
+            locations: Vec::new(),
             position: 0,
+            nesting: 0,
+            max_depth: DEFAULT_MAX_NESTING_DEPTH,
+            defining_words: HashSet::new(),
+            constants: HashMap::new(),
+            includes: IncludeContext::default(),
         }
     }
 
+    /// Limit how deeply IF, BEGIN and DO may nest, in any combination
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Record where each token starts, so word references carry their location
+    pub fn with_locations(mut self, locations: Vec<SourceLocation>) -> Self {
+        self.locations = locations;
+        self
+    }
+
+    /// Resolve INCLUDE and REQUIRE with `includes`; by default they are
+    /// resolved against the working directory
+    pub fn with_includes(mut self, includes: IncludeContext) -> Self {
+        self.includes = includes;
+        self
+    }
+
+    /// The include context, updated with the files loaded while parsing
+    pub fn into_includes(self) -> IncludeContext {
+        self.includes
+    }
+
+    /// Location of the current token, or the default if unknown
+    fn location(&self) -> SourceLocation {
+        self.locations.get(self.position).cloned().unwrap_or_default()
+    }
+
+    /// Parse error at the current token
+    fn error_here(&self, message: impl Into<String>) -> ForthError {
+        let location = self.location();
+        ForthError::parse_error(location.line, location.column, message)
+    }
+
+    /// Parse error at the token just consumed
+    fn error_at_previous(&self, message: impl Into<String>) -> ForthError {
+        let location = self.position
+            .checked_sub(1)
+            .and_then(|i| self.locations.get(i))
+            .cloned()
+            .unwrap_or_default();
+        ForthError::parse_error(location.line, location.column, message)
+    }
+
     /// Peek at current token
     fn peek(&self) -> &Token {
         self.tokens.get(self.position).unwrap_or(&Token::Eof)
@@ -36,114 +106,245 @@ impl Parser {
         if std::mem::discriminant(&token) == std::mem::discriminant(&expected) {
             Ok(())
         } else {
-            Err(ForthError::ParseError {
-                line: 0,
-                column: 0,
-                message: format!("Expected {:?}, found {:?}", expected, token),
-            })
+            Err(self.error_at_previous(format!("Expected {:?}, found {:?}", expected, token)))
         }
     }
 
     /// Parse the entire program
+    ///
+    /// Parsing carries on after an error: past the `;` of a bad definition,
+    /// or past the bad token in top-level code. Several errors are returned
+    /// as [`ForthError::Multiple`], up to [`MAX_REPORTED_ERRORS`].
     pub fn parse_program(&mut self) -> Result<Program> {
         let mut program = Program::new();
         let mut pending_value: Option<i64> = None;
+        let mut errors = Vec::new();
 
-        while !matches!(self.peek(), Token::Eof) {
-            match self.peek() {
-                Token::Colon => {
-                    // If we have a pending value, push it first
-                    if let Some(value) = pending_value.take() {
-                        program.top_level_code.push(Word::IntLiteral(value));
-                    }
-                    let def = self.parse_definition()?;
-                    program.definitions.push(def);
+        while !matches!(self.peek(), Token::Eof) && errors.len() < MAX_REPORTED_ERRORS {
+            let start = self.position;
+            let in_definition = matches!(self.peek(), Token::Colon);
+            if let Err(error) = self.parse_top_level(&mut program, &mut pending_value) {
+                // Nothing after the end of the input to recover at
+                let incomplete = error.is_incomplete();
+                errors.push(error);
+                if incomplete {
+                    break;
                 }
-                Token::Variable => {
-                    // If we have a pending value, push it first
-                    if let Some(value) = pending_value.take() {
-                        program.top_level_code.push(Word::IntLiteral(value));
-                    }
+                if in_definition {
+                    self.skip_definition();
+                } else if self.position == start {
                     self.advance();
+                }
+            }
+        }
+        if let Some(error) = ForthError::combine(errors) {
+            return Err(error);
+        }
+
+        // Push any remaining pending value
+        if let Some(value) = pending_value {
+            program.top_level_code.push(Word::IntLiteral(value));
+        }
+
+        Ok(program)
+    }
+
+    /// Parse one top-level item: a definition, a defining word with its
+    /// name, or a word of top-level code
+    fn parse_top_level(&mut self, program: &mut Program, pending_value: &mut Option<i64>) -> Result<()> {
+        match self.peek() {
+            Token::Colon => {
+                // If we have a pending value, push it first
+                if let Some(value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(value));
+                }
+                let def = self.parse_definition()?;
+                program.definitions.push(def);
+            }
+            Token::Variable => {
+                // If we have a pending value, push it first
+                if let Some(value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(value));
+                }
+                self.advance();
+                if let Token::Word(name) = self.advance() {
+                    program.top_level_code.push(Word::Variable { name });
+                } else {
+                    return Err(self.error_at_previous("Expected variable name"));
+                }
+            }
+            Token::Constant => {
+                self.advance();
+                // The value should have been parsed as the previous token
+                if let Some(value) = pending_value.take() {
                     if let Token::Word(name) = self.advance() {
-                        program.top_level_code.push(Word::Variable { name });
+                        self.constants.insert(name.clone(), value);
+                        program.top_level_code.push(Word::Constant { name, value });
                     } else {
-                        return Err(ForthError::ParseError {
-                            line: 0,
-                            column: 0,
-                            message: "Expected variable name".to_string(),
-                        });
+                        return Err(self.error_at_previous("Expected constant name"));
                     }
+                } else {
+                    return Err(self.error_at_previous("Expected constant value before CONSTANT"));
                 }
-                Token::Constant => {
-                    self.advance();
-                    // The value should have been parsed as the previous token
-                    if let Some(value) = pending_value.take() {
-                        if let Token::Word(name) = self.advance() {
-                            program.top_level_code.push(Word::Constant { name, value });
-                        } else {
-                            return Err(ForthError::ParseError {
-                                line: 0,
-                                column: 0,
-                                message: "Expected constant name".to_string(),
-                            });
-                        }
+            }
+            Token::Value => {
+                self.advance();
+                // Like CONSTANT, the initial value is the previous token
+                if let Some(value) = pending_value.take() {
+                    if let Token::Word(name) = self.advance() {
+                        program.top_level_code.push(Word::Value { name, value });
                     } else {
-                        return Err(ForthError::ParseError {
-                            line: 0,
-                            column: 0,
-                            message: "Expected constant value before CONSTANT".to_string(),
-                        });
+                        return Err(self.error_at_previous("Expected value name"));
                     }
+                } else {
+                    return Err(self.error_at_previous("Expected initial value before VALUE"));
                 }
-                Token::Integer(value) => {
-                    // If we have a pending value, push it first
-                    if let Some(prev_value) = pending_value.take() {
-                        program.top_level_code.push(Word::IntLiteral(prev_value));
+            }
+            Token::Create => {
+                if let Some(value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(value));
+                }
+                self.advance();
+                let name = self.expect_name("CREATE")?;
+                program.top_level_code.push(Word::Create { name: Some(name) });
+            }
+            Token::Word(name) if self.defining_words.contains(name) => {
+                if let Some(value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(value));
+                }
+                let defining_word = name.clone();
+                self.advance();
+                let name = self.expect_name(&defining_word)?;
+                program.top_level_code.push(Word::Instantiate { defining_word, name });
+            }
+            Token::Does => {
+                return Err(self.error_here("DOES> outside of a definition"));
+            }
+            Token::Integer(value) => {
+                // If we have a pending value, push it first
+                if let Some(prev_value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(prev_value));
+                }
+                // Save this value in case the next token is CONSTANT
+                *pending_value = Some(*value);
+                self.advance();
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("include") || word.eq_ignore_ascii_case("require") => {
+                if let Some(value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(value));
+                }
+                let once = word.eq_ignore_ascii_case("require");
+                let location = self.location();
+                self.advance();
+                let path = match self.advance() {
+                    Token::String(path) | Token::Word(path) => path,
+                    token => {
+                        return Err(ForthError::ParseError {
+                            line: location.line,
+                            column: location.column,
+                            message: format!("Expected a file name after INCLUDE or REQUIRE, found {:?}", token),
+                        })
                     }
-                    // Save this value in case the next token is CONSTANT
-                    pending_value = Some(*value);
+                };
+                self.include(&path, once, &location, program)?;
+            }
+            _ => {
+                // If we have a pending value, push it first
+                if let Some(value) = pending_value.take() {
+                    program.top_level_code.push(Word::IntLiteral(value));
+                }
+                let word = self.parse_word()?;
+                program.top_level_code.push(word);
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip the rest of a definition that failed to parse, up to and
+    /// including its `;`
+    ///
+    /// Stops early at a `:`, so a definition missing its `;` does not take
+    /// the next one down with it.
+    fn skip_definition(&mut self) {
+        if matches!(self.position.checked_sub(1).and_then(|i| self.tokens.get(i)), Some(Token::Semicolon)) {
+            return;
+        }
+        loop {
+            match self.peek() {
+                Token::Colon | Token::Eof => break,
+                Token::Semicolon => {
                     self.advance();
+                    break;
                 }
                 _ => {
-                    // If we have a pending value, push it first
-                    if let Some(value) = pending_value.take() {
-                        program.top_level_code.push(Word::IntLiteral(value));
-                    }
-                    let word = self.parse_word()?;
-                    program.top_level_code.push(word);
+                    self.advance();
                 }
             }
         }
+    }
 
-        // Push any remaining pending value
-        if let Some(value) = pending_value {
-            program.top_level_code.push(Word::IntLiteral(value));
-        }
+    /// Parse the file `path` names and append its definitions and top-level
+    /// code to `program`
+    ///
+    /// The included file sees the constants and defining words declared so
+    /// far, and the ones it declares remain visible afterwards.
+    fn include(&mut self, path: &str, once: bool, location: &SourceLocation, program: &mut Program) -> Result<()> {
+        let source = match self.includes.open(path, once, location)? {
+            Some(source) => source,
+            None => return Ok(()),
+        };
 
-        Ok(program)
+        let included = Lexer::new(&source).tokenize_with_locations().and_then(|(tokens, locations)| {
+            let mut parser = Parser::new(tokens)
+                .with_locations(locations)
+                .with_max_depth(self.max_depth)
+                .with_includes(std::mem::take(&mut self.includes));
+            parser.constants = std::mem::take(&mut self.constants);
+            parser.defining_words = std::mem::take(&mut self.defining_words);
+
+            let included = parser.parse_program();
+            self.constants = parser.constants;
+            self.defining_words = parser.defining_words;
+            self.includes = parser.includes;
+            included
+        });
+        self.includes.close();
+
+        // More input can't complete an included file
+        let included = included.map_err(|e| match e {
+            ForthError::IncompleteInput { message } => ForthError::ParseError {
+                line: location.line,
+                column: location.column,
+                message: format!("{} at end of included file '{}'", message, path),
+            },
+            e => e,
+        })?;
+        program.definitions.extend(included.definitions);
+        program.top_level_code.extend(included.top_level_code);
+        Ok(())
     }
 
     /// Parse a word definition (: name ... ;)
     fn parse_definition(&mut self) -> Result<Definition> {
+        let location = self.location();
         self.expect(Token::Colon)?;
 
         let name = match self.advance() {
             Token::Word(name) => name,
+            Token::Eof => return Err(ForthError::incomplete_input("Expected word name after :")),
             token => {
-                return Err(ForthError::ParseError {
-                    line: 0,
-                    column: 0,
-                    message: format!("Expected word name, found {:?}", token),
-                })
+                return Err(self.error_at_previous(format!("Expected word name, found {:?}", token)))
             }
         };
 
-        let location = SourceLocation::default();
-
         // Parse optional stack effect comment
-        let stack_effect = if matches!(self.peek(), Token::LeftParen) {
-            self.parse_stack_effect()?
+        let (stack_effect, stack_comment) = match self.parse_stack_effect()? {
+            Some((effect, comment)) => (Some(effect), Some(comment)),
+            None => (None, None),
+        };
+
+        let locals = if matches!(self.peek(), Token::Word(w) if w == "{") {
+            Some(self.parse_locals()?)
         } else {
             None
         };
@@ -159,11 +360,25 @@ impl Parser {
                     break;
                 }
                 Token::Eof => {
-                    return Err(ForthError::ParseError {
-                        line: 0,
-                        column: 0,
-                        message: format!("Unterminated definition: {}", name),
-                    })
+                    return Err(ForthError::incomplete_input(format!("Unterminated definition: {}", name)))
+                }
+                Token::Create => {
+                    self.advance();
+                    body.push(Word::Create { name: None });
+                }
+                Token::Does => {
+                    self.advance();
+                    if !body.iter().any(|word| matches!(word, Word::Create { .. })) {
+                        return Err(
+                            self.error_at_previous(format!("DOES> without CREATE in definition of {}", name))
+                        );
+                    }
+                    // Everything up to `;` is the runtime behavior
+                    let mut does_body = Vec::new();
+                    while !matches!(self.peek(), Token::Semicolon | Token::Eof) {
+                        does_body.push(self.parse_word()?);
+                    }
+                    body.push(Word::Does { body: does_body });
                 }
                 _ => {
                     let word = self.parse_word()?;
@@ -178,17 +393,72 @@ impl Parser {
             immediate = true;
         }
 
+        // Redefining a word replaces its dictionary entry either way
+        if body.iter().any(|word| matches!(word, Word::Create { .. })) {
+            self.defining_words.insert(name.clone());
+        } else {
+            self.defining_words.remove(&name);
+        }
+
         Ok(Definition {
             name,
             body,
             immediate,
             stack_effect,
+            stack_comment,
+            locals,
             location,
         })
     }
 
-    /// Parse a stack effect comment ( a b -- c )
-    fn parse_stack_effect(&mut self) -> Result<Option<StackEffect>> {
+    /// Parse a locals declaration { a b | temp -- outputs }
+    fn parse_locals(&mut self) -> Result<Locals> {
+        self.advance();
+
+        let mut locals = Locals::default();
+        let mut uninitialized = false;
+        let mut in_outputs = false;
+
+        loop {
+            match self.advance() {
+                Token::Word(w) if w == "}" => break,
+                // Names after `--` only document the outputs
+                _ if in_outputs => {}
+                Token::StackEffectSep => in_outputs = true,
+                Token::Word(w) if w == "|" => uninitialized = true,
+                Token::Word(name) => {
+                    if locals.names().any(|existing| *existing == name) {
+                        return Err(self.error_at_previous(format!("Duplicate local name: {}", name)));
+                    }
+                    if uninitialized {
+                        locals.uninitialized.push(name);
+                    } else {
+                        locals.params.push(name);
+                    }
+                }
+                Token::Eof => {
+                    return Err(ForthError::incomplete_input("Unterminated locals declaration"))
+                }
+                token => {
+                    return Err(self.error_at_previous(format!("Invalid local name: {}", token)))
+                }
+            }
+        }
+
+        Ok(locals)
+    }
+
+    /// Consume the name a defining word needs, e.g. after CREATE
+    fn expect_name(&mut self, after: &str) -> Result<String> {
+        match self.advance() {
+            Token::Word(name) => Ok(name),
+            token => Err(self.error_at_previous(format!("Expected name after {}, found {:?}", after, token))),
+        }
+    }
+
+    /// Parse a stack effect comment ( a b -- c ), returning the effect and
+    /// the comment's text with single spaces between its words
+    fn parse_stack_effect(&mut self) -> Result<Option<(StackEffect, String)>> {
         if !matches!(self.peek(), Token::LeftParen) {
             return Ok(None);
         }
@@ -197,8 +467,15 @@ impl Parser {
         let mut inputs = Vec::new();
         let mut outputs = Vec::new();
         let mut before_separator = true;
+        let mut comment = String::from("(");
 
         loop {
+            match self.peek() {
+                Token::Word(name) => comment = comment + " " + name,
+                Token::Eof => {}
+                // Keywords print in upper case
+                token => comment = comment + " " + &token.to_string().to_lowercase(),
+            }
             match self.peek() {
                 Token::RightParen => {
                     self.advance();
@@ -227,12 +504,17 @@ impl Parser {
                         outputs.push(stack_type);
                     }
                 }
+                // Names like `value` and `to` are common in stack comments
+                Token::Value | Token::To => {
+                    self.advance();
+                    if before_separator {
+                        inputs.push(StackType::Unknown);
+                    } else {
+                        outputs.push(StackType::Unknown);
+                    }
+                }
                 Token::Eof => {
-                    return Err(ForthError::ParseError {
-                        line: 0,
-                        column: 0,
-                        message: "Unterminated stack effect".to_string(),
-                    })
+                    return Err(ForthError::incomplete_input("Unterminated stack effect"))
                 }
                 _ => {
                     self.advance(); // Skip other tokens in comments
@@ -240,7 +522,7 @@ impl Parser {
             }
         }
 
-        Ok(Some(StackEffect::new(inputs, outputs)))
+        Ok(Some((StackEffect::new(inputs, outputs), comment)))
     }
 
     /// Parse a single word
@@ -260,31 +542,106 @@ impl Parser {
             }
             Token::If => {
                 self.advance();
-                self.parse_if()
+                self.parse_nested(Self::parse_if)
             }
             Token::Begin => {
                 self.advance();
-                self.parse_begin()
+                self.parse_nested(Self::parse_begin)
             }
             Token::Do => {
                 self.advance();
-                self.parse_do_loop()
+                self.parse_nested(Self::parse_do_loop)
+            }
+            Token::Word(name) if name == "[" => {
+                self.advance();
+                self.parse_compile_time_literal()
+            }
+            Token::Word(tick) if tick == "'" || tick == "[']" => {
+                self.advance();
+                let location = self.location();
+                match self.advance() {
+                    Token::Word(name) => Ok(Word::Tick { name, location }),
+                    token => {
+                        Err(self.error_at_previous(format!("Expected word name after {}, found {:?}", tick, token)))
+                    }
+                }
             }
             Token::Word(name) => {
+                let location = self.location();
                 self.advance();
-                Ok(Word::WordRef {
-                    name,
-                    location: SourceLocation::default(),
-                })
+                Ok(Word::WordRef { name, location })
             }
-            token => Err(ForthError::ParseError {
-                line: 0,
-                column: 0,
-                message: format!("Unexpected token: {:?}", token),
+            Token::To => {
+                self.advance();
+                match self.advance() {
+                    Token::Word(name) => Ok(Word::To { name }),
+                    token => {
+                        Err(self.error_at_previous(format!("Expected value name after TO, found {:?}", token)))
+                    }
+                }
+            }
+            token => Err(self.error_here(format!("Unexpected token: {:?}", token))),
+        }
+    }
+
+    /// Parse `[ ... ] LITERAL` (after the `[`), evaluating the bracketed code
+    /// now and embedding the single value it leaves as a literal
+    fn parse_compile_time_literal(&mut self) -> Result<Word> {
+        let mut stack = Vec::new();
+        loop {
+            let location = self.location();
+            let message = match self.advance() {
+                Token::Integer(value) => {
+                    stack.push(value);
+                    continue;
+                }
+                Token::Word(name) if name == "]" => break,
+                Token::Word(name) if name == "[" => "Nested [ inside [ ... ]".to_string(),
+                Token::Word(name) => match evaluate_word(&name, &self.constants, &mut stack) {
+                    Ok(()) => continue,
+                    Err(message) => message,
+                },
+                Token::Eof => return Err(ForthError::incomplete_input("Unterminated [ ... ]")),
+                token => format!("'{}' cannot be evaluated at compile time", token),
+            };
+            return Err(ForthError::ParseError {
+                line: location.line,
+                column: location.column,
+                message,
+            });
+        }
+
+        let location = self.location();
+        if !matches!(self.advance(), Token::Word(word) if word.eq_ignore_ascii_case("literal")) {
+            return Err(ForthError::ParseError {
+                line: location.line,
+                column: location.column,
+                message: "Expected LITERAL after [ ... ]".to_string(),
+            });
+        }
+        match stack.as_slice() {
+            [value] => Ok(Word::IntLiteral(*value)),
+            _ => Err(ForthError::ParseError {
+                line: location.line,
+                column: location.column,
+                message: format!("[ ... ] LITERAL needs exactly one value, found {}", stack.len()),
             }),
         }
     }
 
+    /// Parse the body of a control structure one nesting level deeper
+    fn parse_nested(&mut self, parse: fn(&mut Self) -> Result<Word>) -> Result<Word> {
+        if self.nesting >= self.max_depth {
+            let message = format!("Control structure nesting too deep (limit {})", self.max_depth);
+            return Err(self.error_at_previous(message));
+        }
+
+        self.nesting += 1;
+        let word = parse(self);
+        self.nesting -= 1;
+        word
+    }
+
     /// Parse IF...THEN or IF...ELSE...THEN
     fn parse_if(&mut self) -> Result<Word> {
         let mut then_branch = Vec::new();
@@ -310,11 +667,7 @@ impl Parser {
                                 });
                             }
                             Token::Eof => {
-                                return Err(ForthError::ParseError {
-                                    line: 0,
-                                    column: 0,
-                                    message: "Unterminated IF...ELSE".to_string(),
-                                })
+                                return Err(ForthError::incomplete_input("Unterminated IF...ELSE"))
                             }
                             _ => {
                                 let word = self.parse_word()?;
@@ -324,11 +677,7 @@ impl Parser {
                     }
                 }
                 Token::Eof => {
-                    return Err(ForthError::ParseError {
-                        line: 0,
-                        column: 0,
-                        message: "Unterminated IF".to_string(),
-                    })
+                    return Err(ForthError::incomplete_input("Unterminated IF"))
                 }
                 _ => {
                     let word = self.parse_word()?;
@@ -368,11 +717,7 @@ impl Parser {
                                 });
                             }
                             Token::Eof => {
-                                return Err(ForthError::ParseError {
-                                    line: 0,
-                                    column: 0,
-                                    message: "Unterminated BEGIN...WHILE".to_string(),
-                                })
+                                return Err(ForthError::incomplete_input("Unterminated BEGIN...WHILE"))
                             }
                             _ => {
                                 let word = self.parse_word()?;
@@ -382,11 +727,7 @@ impl Parser {
                     }
                 }
                 Token::Eof => {
-                    return Err(ForthError::ParseError {
-                        line: 0,
-                        column: 0,
-                        message: "Unterminated BEGIN".to_string(),
-                    })
+                    return Err(ForthError::incomplete_input("Unterminated BEGIN"))
                 }
                 _ => {
                     let word = self.parse_word()?;
@@ -412,11 +753,7 @@ impl Parser {
                     return Ok(Word::DoLoop { body, increment: 1 });
                 }
                 Token::Eof => {
-                    return Err(ForthError::ParseError {
-                        line: 0,
-                        column: 0,
-                        message: "Unterminated DO loop".to_string(),
-                    })
+                    return Err(ForthError::incomplete_input("Unterminated DO loop"))
                 }
                 _ => {
                     let word = self.parse_word()?;
@@ -430,11 +767,53 @@ impl Parser {
 /// Parse a Forth program from source code
 pub fn parse_program(source: &str) -> Result<Program> {
     let mut lexer = Lexer::new(source);
-    let tokens = lexer.tokenize()?;
-    let mut parser = Parser::new(tokens);
+    let (tokens, locations) = lexer.tokenize_with_locations()?;
+    let mut parser = Parser::new(tokens).with_locations(locations);
     parser.parse_program()
 }
 
+/// Parser for input arriving a line at a time, as in the REPL
+///
+/// Lines are buffered until together they parse. Input that stops inside a
+/// definition, control structure, comment or string waits for more lines
+/// instead of failing; any other error discards the buffered lines.
+#[derive(Debug, Clone, Default)]
+pub struct StreamingParser {
+    pending: String,
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a line of input
+    ///
+    /// Returns the program once the buffered lines form a complete one, or
+    /// `None` while more input is needed.
+    pub fn feed(&mut self, line: &str) -> Result<Option<Program>> {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        match parse_program(&self.pending) {
+            Err(e) if e.is_incomplete() => Ok(None),
+            result => {
+                self.pending.clear();
+                result.map(Some)
+            }
+        }
+    }
+
+    /// Whether lines of an incomplete entry are buffered
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Discard the lines of an incomplete entry, e.g. on Ctrl-C
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,6 +846,127 @@ mod tests {
         assert_eq!(program.definitions.len(), 1);
     }
 
+    #[test]
+    fn test_parse_value_and_to() {
+        let program = parse_program("42 VALUE speed : faster 10 to speed ;").unwrap();
+        assert_eq!(
+            program.top_level_code,
+            vec![Word::Value { name: "speed".to_string(), value: 42 }]
+        );
+        assert_eq!(program.definitions[0].body[1], Word::To { name: "speed".to_string() });
+
+        assert!(parse_program("VALUE speed").is_err());
+        assert!(parse_program("1 TO").is_err());
+    }
+
+    #[test]
+    fn test_parse_tick() {
+        let program = parse_program("' dup : f ['] dup ;").unwrap();
+        assert!(matches!(&program.top_level_code[0], Word::Tick { name, .. } if name == "dup"));
+        assert_eq!(program.definitions[0].body[0].to_source(), "' dup");
+
+        assert!(parse_program("'").is_err());
+        assert!(parse_program(": f ['] ;").is_err());
+    }
+
+    #[test]
+    fn test_parse_locals() {
+        let program = parse_program(": dist { x y | tmp -- d } x x * y y * + ;").unwrap();
+        let locals = program.definitions[0].locals.as_ref().unwrap();
+        assert_eq!(locals.params, vec!["x", "y"]);
+        assert_eq!(locals.uninitialized, vec!["tmp"]);
+        assert_eq!(program.definitions[0].body.len(), 7);
+
+        assert!(parse_program(": f 1 ;").unwrap().definitions[0].locals.is_none());
+        assert!(parse_program(": f { x x -- } x ;").is_err());
+        assert!(parse_program(": f { x ").is_err());
+    }
+
+    #[test]
+    fn test_parse_compile_time_literal() {
+        let program = parse_program(": x [ 2 3 + ] literal ;").unwrap();
+        assert_eq!(program.definitions[0].body, vec![Word::IntLiteral(5)]);
+
+        let program = parse_program("4 constant four : y 1 [ four dup * ] LITERAL + ;").unwrap();
+        assert_eq!(
+            program.definitions[0].body[..2],
+            [Word::IntLiteral(1), Word::IntLiteral(16)]
+        );
+
+        for source in [
+            ": x [ 1 . ] literal ;",
+            ": x [ 1 [ 2 ] ] literal ;",
+            ": x [ 1 0 / ] literal ;",
+            ": x [ 1 2 ] literal ;",
+            ": x [ 1 ] ;",
+            ": x [ 1",
+        ] {
+            assert!(parse_program(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_streaming_parser() {
+        let mut parser = StreamingParser::new();
+        assert_eq!(parser.feed(": foo 1").unwrap(), None);
+        assert!(parser.is_pending());
+        let program = parser.feed("+ ;").unwrap().unwrap();
+        assert_eq!(program.definitions.len(), 1);
+        assert_eq!(program.definitions[0].name, "foo");
+        assert_eq!(program.definitions[0].body.len(), 2);
+        assert!(!parser.is_pending());
+
+        // Nested structures stay open until every one is closed
+        for line in [": bar ( n -- n )", "BEGIN dup IF", "1 -", "THEN", "dup 0= UNTIL"] {
+            assert_eq!(parser.feed(line).unwrap(), None, "{}", line);
+        }
+        assert_eq!(parser.feed(";").unwrap().unwrap().definitions[0].name, "bar");
+
+        // Comments and strings may span lines too
+        assert_eq!(parser.feed("( a comment").unwrap(), None);
+        assert!(parser.feed("that ends ) 1 2 +").unwrap().is_some());
+
+        // Malformed input is an error, not a wait for more
+        assert!(parser.feed(": baz IF 1 ;").is_err());
+        assert!(!parser.is_pending());
+        assert!(parse_program(": baz IF 1").unwrap_err().is_incomplete());
+        assert!(!parse_program("1 ;").unwrap_err().is_incomplete());
+
+        parser.feed(": qux").unwrap();
+        parser.cancel();
+        assert!(!parser.is_pending());
+        assert_eq!(parser.feed("1").unwrap().unwrap().top_level_code, vec![Word::IntLiteral(1)]);
+    }
+
+    #[test]
+    fn test_parse_create_does() {
+        let program = parse_program(": const create , does> @ ; 42 const answer").unwrap();
+        assert_eq!(
+            program.definitions[0].body,
+            vec![
+                Word::Create { name: None },
+                Word::WordRef { name: ",".to_string(), location: SourceLocation { line: 1, column: 16 } },
+                Word::Does {
+                    body: vec![Word::WordRef { name: "@".to_string(), location: SourceLocation { line: 1, column: 24 } }],
+                },
+            ]
+        );
+        assert_eq!(
+            program.top_level_code,
+            vec![
+                Word::IntLiteral(42),
+                Word::Instantiate { defining_word: "const".to_string(), name: "answer".to_string() },
+            ]
+        );
+
+        let program = parse_program("create table 1 , 2 ,").unwrap();
+        assert_eq!(program.top_level_code[0], Word::Create { name: Some("table".to_string()) });
+
+        assert!(parse_program(": broken 1 does> @ ;").is_err());
+        assert!(parse_program("does> @").is_err());
+        assert!(parse_program("create").is_err());
+    }
+
     #[test]
     fn test_deeply_nested_definitions() {
         // Test 15+ levels of nested IF-THEN structures
@@ -508,6 +1008,30 @@ mod tests {
         assert_eq!(program.definitions[0].name, "deep-nest");
     }
 
+    #[test]
+    fn test_nesting_depth_limit() {
+        let nested = |depth: usize| format!(": deep {} 1 {} ;", "0 if ".repeat(depth), "then ".repeat(depth));
+
+        assert!(parse_program(&nested(DEFAULT_MAX_NESTING_DEPTH)).is_ok());
+        match parse_program(&nested(10_000)) {
+            Err(ForthError::ParseError { message, .. }) => assert!(message.contains("nesting too deep")),
+            other => panic!("expected a nesting error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_nesting_limit_counts_mixed_structures() {
+        let parse = |source: &str, max_depth: usize| {
+            let tokens = Lexer::new(source).tokenize().unwrap();
+            Parser::new(tokens).with_max_depth(max_depth).parse_program()
+        };
+        // IF inside BEGIN inside DO is three levels deep
+        let source = ": mixed 10 0 do begin 1 if 2 then 0 until loop ;";
+
+        assert!(parse(source, 3).is_ok());
+        assert!(matches!(parse(source, 2), Err(ForthError::ParseError { .. })));
+    }
+
     #[test]
     fn test_malformed_if_missing_then() {
         // IF without THEN should produce error
@@ -515,14 +1039,14 @@ mod tests {
         let result = parse_program(": broken-if 5 0 > IF 10");
         assert!(result.is_err());
         // The error will be either "Unterminated IF" or "Unterminated definition"
-        if let Err(ForthError::ParseError { message, .. }) = result {
+        if let Err(ForthError::IncompleteInput { message }) = result {
             assert!(
                 message.contains("Unterminated") || message.contains("IF"),
                 "Expected error about unterminated construct, got: {}",
                 message
             );
         } else {
-            panic!("Expected ParseError for unterminated IF");
+            panic!("Expected IncompleteInput for unterminated IF");
         }
     }
 
@@ -596,13 +1120,36 @@ mod tests {
         // Definition without semicolon should error
         let result = parse_program(": no-semicolon 42");
         assert!(result.is_err());
-        if let Err(ForthError::ParseError { message, .. }) = result {
+        if let Err(ForthError::IncompleteInput { message }) = result {
             assert!(message.contains("Unterminated definition"));
         } else {
-            panic!("Expected ParseError for unterminated definition");
+            panic!("Expected IncompleteInput for unterminated definition");
         }
     }
 
+    #[test]
+    fn test_parse_errors_are_located() {
+        // At the offending token, whether or not it was consumed
+        let err = parse_program("1 2\nvariable 5").unwrap_err();
+        assert_eq!(err.span(), Some(SourceLocation { line: 2, column: 10 }));
+        let err = parse_program("  does>").unwrap_err();
+        assert_eq!(err.span(), Some(SourceLocation { line: 1, column: 3 }));
+    }
+
+    #[test]
+    fn test_parse_recovers_after_bad_definition() {
+        let err = parse_program(": 5 ;\n: good 1 ;\nvariable 7\n: also ( a b ) + ;").unwrap_err();
+        let lines: Vec<usize> = err.errors().iter().filter_map(|e| e.span()).map(|span| span.line).collect();
+        assert_eq!(lines, vec![1, 3]);
+
+        // A definition missing its `;` stops at the next `:`
+        let err = parse_program(": f 1 variable\n: g to ;").unwrap_err();
+        assert_eq!(err.errors().len(), 2, "{}", err);
+
+        // Input that runs out still reads as incomplete on its own
+        assert!(parse_program(": f 1").unwrap_err().is_incomplete());
+    }
+
     #[test]
     fn test_multiple_definitions_with_comments() {
         // Multiple definitions with various comment styles
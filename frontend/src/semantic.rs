@@ -4,11 +4,13 @@
 //! - Undefined word detection
 //! - Stack underflow detection
 //! - Control structure validation
-//! - Redefinition checks
+//! - Redefinition checks, with a warning for definitions shadowing built-ins
 
 use crate::ast::*;
-use crate::error::{ForthError, Result};
-use crate::stack_effects::StackEffectInference;
+use crate::builtins;
+use crate::error::{ForthError, Result, MAX_REPORTED_ERRORS};
+use crate::execute::resolve_execute;
+use crate::stack_effects::{execute_effect, StackEffectInference};
 use rustc_hash::FxHashSet;
 use std::collections::HashMap;
 
@@ -16,14 +18,24 @@ use std::collections::HashMap;
 pub struct SemanticAnalyzer {
     /// Known word definitions
     defined_words: FxHashSet<String>,
+    /// Names defined by the program itself, which may shadow built-ins once
+    user_words: FxHashSet<String>,
     /// Stack effect inference engine
     stack_inference: StackEffectInference,
     /// Variables
     variables: FxHashSet<String>,
     /// Constants
     constants: HashMap<String, i64>,
+    /// Values (updatable with TO)
+    values: FxHashSet<String>,
+    /// Words made by CREATE, directly or through a defining word
+    created: FxHashSet<String>,
+    /// Locals of the definition being validated
+    locals: FxHashSet<String>,
     /// Errors collected during analysis
     errors: Vec<ForthError>,
+    /// Warnings collected during analysis
+    warnings: Vec<String>,
 }
 
 impl SemanticAnalyzer {
@@ -44,12 +56,13 @@ impl SemanticAnalyzer {
             "d=", "d<", "d0=", "d0<",
             // Logical
             "and", "or", "xor", "not", "invert", "true", "false",
+            "lshift", "rshift", "arshift",
             // Memory
-            "@", "!", "c@", "c!", "+!", "?",
+            "@", "!", "c@", "c!", "+!", "?", ",",
             "cell", "cells", "cell+", "char+", "chars", "align", "aligned",
             "move", "fill", "erase", "compare", "search", "count",
             // I/O
-            ".", "emit", "cr", "space", "spaces", "type",
+            ".", "u.", "emit", "cr", "space", "spaces", "type",
             ".\"", ".(", ".r", ".s",
             // Control (these are special but should be recognized)
             "if", "then", "else", "begin", "until", "while", "repeat",
@@ -58,7 +71,7 @@ impl SemanticAnalyzer {
             ">r", "r>", "r@",
             // File I/O (ANS Forth File Access word set)
             "create-file", "open-file", "close-file",
-            "read-file", "write-file", "delete-file",
+            "read-file", "write-file", "delete-file", "file-status",
             "file-size", "file-position", "reposition-file",
             "resize-file", "flush-file",
             "r/o", "w/o", "r/w",  // File access modes
@@ -75,16 +88,37 @@ impl SemanticAnalyzer {
 
         Self {
             defined_words,
+            user_words: FxHashSet::default(),
             stack_inference: StackEffectInference::new(),
             variables: FxHashSet::default(),
             constants: HashMap::new(),
+            values: FxHashSet::default(),
+            created: FxHashSet::default(),
+            locals: FxHashSet::default(),
             errors: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
     /// Add an error to the list
+    ///
+    /// An undefined word is reported where it is first used only, so one
+    /// misspelled helper does not bury everything else.
     fn error(&mut self, error: ForthError) {
-        self.errors.push(error);
+        if let ForthError::UndefinedWord { word, .. } = &error {
+            let reported = |e: &ForthError| matches!(e, ForthError::UndefinedWord { word: w, .. } if w == word);
+            if self.errors.iter().any(reported) {
+                return;
+            }
+        }
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+
+    /// Warnings collected by [`analyze`](Self::analyze)
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
     }
 
     /// Check if a word is defined
@@ -92,18 +126,28 @@ impl SemanticAnalyzer {
         self.defined_words.contains(word)
             || self.variables.contains(word)
             || self.constants.contains_key(word)
+            || self.values.contains(word)
+            || self.created.contains(word)
+            || self.locals.contains(word)
     }
 
     /// Analyze a complete program
     pub fn analyze(&mut self, program: &Program) -> Result<()> {
+        // Checked as compiled, with the EXECUTEs of known tokens as direct calls
+        if let Some(effect) = execute_effect(program) {
+            self.stack_inference.set_execute_effect(effect);
+        }
+        let program = &*resolve_execute(program);
+
         // First pass: collect all definitions
         for def in &program.definitions {
-            if self.defined_words.contains(&def.name) && !self.is_builtin(&def.name) {
+            if !self.user_words.insert(def.name.clone()) {
                 self.error(ForthError::RedefinitionError {
                     word: def.name.clone(),
                 });
             }
             self.defined_words.insert(def.name.clone());
+            self.warnings.extend(shadowing_warning(def));
 
             // Add to stack inference
             if let Err(e) = self.stack_inference.add_definition(def) {
@@ -120,53 +164,34 @@ impl SemanticAnalyzer {
                 Word::Constant { name, value } => {
                     self.constants.insert(name.clone(), *value);
                 }
+                Word::Value { name, .. } => {
+                    self.values.insert(name.clone());
+                }
+                Word::Create { name: Some(name) } | Word::Instantiate { name, .. } => {
+                    self.created.insert(name.clone());
+                }
                 _ => {}
             }
         }
 
-        // Second pass: validate definitions
+        // Second pass: validate definitions, carrying on past bad ones
         for def in &program.definitions {
-            self.validate_definition(def)?;
+            if let Err(e) = self.validate_definition(def) {
+                self.error(e);
+            }
         }
 
         // Validate top-level code
         for word in &program.top_level_code {
-            self.validate_word(word)?;
+            if let Err(e) = self.validate_word(word) {
+                self.error(e);
+            }
         }
 
-        // Return error if any were collected
-        if !self.errors.is_empty() {
-            return Err(self.errors[0].clone());
+        match ForthError::combine(self.errors.clone()) {
+            Some(error) => Err(error),
+            None => Ok(()),
         }
-
-        Ok(())
-    }
-
-    /// Check if a word is a builtin
-    fn is_builtin(&self, word: &str) -> bool {
-        matches!(
-            word,
-            // Arithmetic
-            "+" | "-" | "*" | "/" | "mod" | "/mod" | "negate" | "abs" | "min" | "max"
-            // Stack manipulation
-            | "dup" | "drop" | "swap" | "over" | "rot" | "2dup" | "2drop" | "2swap" | "2over"
-            | "pick" | "roll" | "depth"
-            // Comparison
-            | "<" | ">" | "=" | "<=" | ">=" | "<>" | "0<" | "0>" | "0="
-            // Logical
-            | "and" | "or" | "xor" | "not" | "invert"
-            // Memory
-            | "@" | "!" | "c@" | "c!" | "+!" | "?"
-            // I/O
-            | "." | "emit" | "cr" | "space" | "spaces" | "type"
-            // Control
-            | "if" | "then" | "else" | "begin" | "until" | "while" | "repeat"
-            | "do" | "loop" | "+loop" | "leave" | "exit"
-            // Return stack
-            | ">r" | "r>" | "r@"
-            // Other
-            | "here" | "allot" | "cells" | "cell+" | "i" | "j" | "execute" | "char"
-        )
     }
 
     /// Validate a definition
@@ -174,15 +199,17 @@ impl SemanticAnalyzer {
         // Check for control structure balance
         self.validate_control_structures(&def.body)?;
 
-        // Check for undefined words
-        for word in &def.body {
-            self.validate_word(word)?;
-        }
+        // Check for undefined words, with the definition's locals in scope
+        self.locals = def.locals.iter().flat_map(|locals| locals.names().cloned()).collect();
+        let validated = def.body.iter().try_for_each(|word| self.validate_word(word));
+        self.locals.clear();
+        validated?;
 
         // Validate stack effect if declared
-        // Skip validation for definitions with loops or return stack operations,
-        // as these are complex to analyze statically
-        let has_complex_control_flow = self.has_complex_control_flow(&def.body);
+        // Skip validation for definitions with loops, return stack operations,
+        // locals or EXECUTE of varying effect, as these are complex to analyze
+        // statically
+        let has_complex_control_flow = self.has_complex_control_flow(&def.body) || def.locals.is_some();
 
         if let Some(declared_effect) = &def.stack_effect {
             if !has_complex_control_flow {
@@ -193,12 +220,16 @@ impl SemanticAnalyzer {
                         if declared_effect.inputs.len() != inferred_effect.inputs.len()
                             || declared_effect.outputs.len() != inferred_effect.outputs.len()
                         {
-                            self.error(ForthError::InvalidStackEffect {
-                                declaration: format!(
-                                    "Declared {} but inferred ( {} -- {} )",
+                            let trace = self.stack_inference.trace_definition(def)?;
+                            self.error(ForthError::StackEffectMismatch {
+                                word: def.name.clone(),
+                                offending: trace.offending_step().map(|step| step.word.clone()),
+                                trace: format!(
+                                    "declared {} but inferred ( {} -- {} )\n{}",
                                     declared_effect,
                                     inferred_effect.inputs.len(),
-                                    inferred_effect.outputs.len()
+                                    inferred_effect.outputs.len(),
+                                    trace
                                 ),
                             });
                         }
@@ -216,12 +247,28 @@ impl SemanticAnalyzer {
     /// Validate a word
     fn validate_word(&mut self, word: &Word) -> Result<()> {
         match word {
-            Word::WordRef { name, .. } => {
-                if !self.is_defined(name) {
-                    self.error(ForthError::UndefinedWord {
-                        word: name.clone(),
-                        line: None,
-                    });
+            Word::WordRef { name, location } | Word::Tick { name, location } if !self.is_defined(name) => {
+                self.error(ForthError::UndefinedWord {
+                    word: name.clone(),
+                    location: Some(location.clone()),
+                });
+            }
+            Word::To { name } if !self.values.contains(name) => {
+                self.error(ForthError::TypeError {
+                    expected: "VALUE after TO".to_string(),
+                    found: name.clone(),
+                    location: None,
+                });
+            }
+            Word::Instantiate { defining_word, .. } if !self.defined_words.contains(defining_word) => {
+                self.error(ForthError::UndefinedWord {
+                    word: defining_word.clone(),
+                    location: None,
+                });
+            }
+            Word::Does { body } => {
+                for w in body {
+                    self.validate_word(w)?;
                 }
             }
             Word::If {
@@ -271,6 +318,10 @@ impl SemanticAnalyzer {
                 Word::WordRef { name, .. } if matches!(name.as_str(), ">r" | "r>" | "r@") => {
                     return true;
                 }
+                // EXECUTE of a token whose words have no one effect
+                Word::WordRef { name, .. } if name == "execute" && self.stack_inference.get_effect(name).is_none() => {
+                    return true;
+                }
                 Word::If { then_branch, else_branch } => {
                     if self.has_complex_control_flow(then_branch) {
                         return true;
@@ -337,11 +388,22 @@ impl Default for SemanticAnalyzer {
 }
 
 /// Convenience function to analyze a program
+///
+/// Every error found is reported, as [`ForthError::Multiple`] when there is
+/// more than one.
 pub fn analyze(program: &Program) -> Result<()> {
     let mut analyzer = SemanticAnalyzer::new();
     analyzer.analyze(program)
 }
 
+/// Warning for a definition that shadows a built-in word, if it does
+///
+/// The definition replaces the built-in everywhere in the program, including
+/// in definitions that come before it.
+pub fn shadowing_warning(def: &Definition) -> Option<String> {
+    builtins::lookup(&def.name).map(|_| format!("'{}' redefines a built-in word and shadows it", def.name))
+}
+
 /// Validation result with detailed information
 #[derive(Debug, Clone)]
 pub struct ValidationResult {
@@ -372,10 +434,12 @@ impl ValidationResult {
 pub fn validate_program(program: &Program) -> ValidationResult {
     let mut analyzer = SemanticAnalyzer::new();
 
-    match analyzer.analyze(program) {
+    let mut result = match analyzer.analyze(program) {
         Ok(_) => ValidationResult::success(),
         Err(_) => ValidationResult::with_errors(analyzer.errors.clone()),
-    }
+    };
+    result.warnings = analyzer.warnings;
+    result
 }
 
 #[cfg(test)]
@@ -401,6 +465,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_every_undefined_word_reported_once() {
+        let program = parse_program(": a frob ;\n: b frob twiddle ;\n: c 1 + ;").unwrap();
+        let err = analyze(&program).unwrap_err();
+        let words: Vec<&str> = err
+            .errors()
+            .iter()
+            .map(|e| match e {
+                ForthError::UndefinedWord { word, .. } => word.as_str(),
+                other => panic!("unexpected error: {}", other),
+            })
+            .collect();
+        assert_eq!(words, vec!["frob", "twiddle"]);
+        assert_eq!(err.span().map(|span| span.line), Some(1));
+        assert!(err.to_string().starts_with("2 errors:\n  Undefined word: frob at line 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_error_count_is_capped() {
+        let source: String = (0..MAX_REPORTED_ERRORS + 5).map(|i| format!(": w{} missing{} ;\n", i, i)).collect();
+        let err = analyze(&parse_program(&source).unwrap()).unwrap_err();
+        assert_eq!(err.errors().len(), MAX_REPORTED_ERRORS);
+    }
+
     #[test]
     fn test_stack_effect_mismatch() {
         // Declared ( n -- n n ) but actually ( n -- n )
@@ -410,6 +498,59 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_stack_effect_mismatch_trace() {
+        let program = parse_program(": sum3 ( n n n -- n ) + + + 1 ;").unwrap();
+        let Err(ForthError::StackEffectMismatch { offending, trace, .. }) = analyze(&program) else {
+            panic!("Expected StackEffectMismatch error");
+        };
+
+        // The third `+` runs out of items
+        assert_eq!(offending.as_deref(), Some("+"));
+        assert_eq!(
+            trace,
+            [
+                "declared ( n n n -- n ) but inferred ( 4 -- 2 )",
+                ": sum3 ( n n n -- n )",
+                "    entry  n n n",
+                "    +      n n",
+                "    +      n",
+                "  > +      n  <- needs 1 more item(s)",
+                "    1      n n",
+                "    ;      expected n",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_stack_effect_mismatch_branches() {
+        let program = parse_program(": pick1 ( f n -- n ) swap if dup then ;").unwrap();
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze(&program).ok();
+
+        let trace = analyzer.stack_inference.trace_definition(&program.definitions[0]).unwrap();
+        let step = trace.offending_step().unwrap();
+        assert_eq!(step.word, "then");
+        assert_eq!(step.stack, vec!["n", "n"]);
+        assert_eq!(step.alternative, Some(vec!["n".to_string()]));
+        assert!(trace.to_string().contains("n n | n  <- branches leave different stacks"));
+    }
+
+    #[test]
+    fn test_stack_trace_truncates_long_bodies() {
+        let body = "1 drop ".repeat(20);
+        let program = parse_program(&format!(": long ( -- n ) {} ;", body)).unwrap();
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze(&program).ok();
+
+        let trace = analyzer.stack_inference.trace_definition(&program.definitions[0]).unwrap();
+        let rendered = trace.to_string();
+        assert_eq!(trace.steps.len(), 40);
+        assert!(rendered.contains("... 24 words"));
+        assert_eq!(rendered.lines().count(), 2 + 1 + crate::stack_effects::MAX_TRACE_STEPS + 1);
+    }
+
     #[test]
     fn test_valid_control_structures() {
         let program = parse_program(": abs dup 0 < IF negate THEN ;").unwrap();
@@ -432,4 +573,18 @@ mod tests {
         assert!(result.passed);
         assert!(result.errors.is_empty());
     }
+
+    #[test]
+    fn test_shadowing_builtin_warns() {
+        let program = parse_program(": + - ; : square dup * ;").unwrap();
+        let result = validate_program(&program);
+        assert!(result.passed);
+        assert_eq!(result.warnings, vec!["'+' redefines a built-in word and shadows it".to_string()]);
+    }
+
+    #[test]
+    fn test_redefining_shadowed_builtin_is_an_error() {
+        let program = parse_program(": + - ; : + * ;").unwrap();
+        assert!(matches!(analyze(&program), Err(ForthError::RedefinitionError { word }) if word == "+"));
+    }
 }
@@ -4,8 +4,12 @@
 //! Each stack value gets a unique SSA variable, and all operations are explicit.
 
 use crate::ast::*;
+use crate::builtins;
 use crate::error::{ForthError, Result};
+use crate::execute::{resolve_execute, ticked_words};
+use crate::stack_effects::{definition_effects_with, execute_effect};
 use smallvec::SmallVec;
+use std::collections::HashSet;
 use std::fmt;
 
 /// SSA register/variable
@@ -92,6 +96,20 @@ pub enum SSAInstruction {
         ty: StackType,
     },
 
+    /// Address of the storage of a `VARIABLE`, `VALUE` or `CREATE`d word
+    VariableAddr {
+        dest: Register,
+        name: String,
+        /// Size of the storage in cells; the same for every reference to `name`
+        cells: usize,
+    },
+
+    /// Execution token of a definition (`' name`), the same wherever it is taken
+    ExecutionToken {
+        dest: Register,
+        word: String,
+    },
+
     /// Store to memory
     Store {
         address: Register,
@@ -152,6 +170,15 @@ pub enum SSAInstruction {
         path_len: Register,     // String length
     },
 
+    /// File status query (ANS Forth: file-status)
+    /// Stack effect: ( c-addr u -- x ior )
+    FileStatus {
+        dest_status: Register,  // Permission bits of the file
+        dest_ior: Register,     // I/O result (0 = success)
+        path_addr: Register,    // String address
+        path_len: Register,     // String length
+    },
+
     /// File create operation (ANS Forth: create-file)
     /// Stack effect: ( c-addr u fam -- fileid ior )
     FileCreate {
@@ -171,6 +198,16 @@ pub enum SSAInstruction {
     },
 }
 
+impl SSAInstruction {
+    /// Whether this instruction ends its block
+    pub fn is_terminator(&self) -> bool {
+        matches!(
+            self,
+            SSAInstruction::Branch { .. } | SSAInstruction::Jump { .. } | SSAInstruction::Return { .. }
+        )
+    }
+}
+
 /// Binary operators
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOperator {
@@ -179,14 +216,27 @@ pub enum BinaryOperator {
     Mul,
     Div,
     Mod,
+    Min,
+    Max,
     Lt,
     Gt,
     Le,
     Ge,
     Eq,
     Ne,
+    /// Unsigned less-than, treating both cells as `u64`
+    ULt,
+    /// Unsigned greater-than, treating both cells as `u64`
+    UGt,
     And,
     Or,
+    Xor,
+    /// Left shift; the count is taken modulo 64
+    Shl,
+    /// Logical (zero-filling) right shift; the count is taken modulo 64
+    Shr,
+    /// Arithmetic (sign-extending) right shift; the count is taken modulo 64
+    Sar,
 }
 
 impl fmt::Display for BinaryOperator {
@@ -197,14 +247,22 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::Mul => write!(f, "mul"),
             BinaryOperator::Div => write!(f, "div"),
             BinaryOperator::Mod => write!(f, "mod"),
+            BinaryOperator::Min => write!(f, "min"),
+            BinaryOperator::Max => write!(f, "max"),
             BinaryOperator::Lt => write!(f, "lt"),
             BinaryOperator::Gt => write!(f, "gt"),
             BinaryOperator::Le => write!(f, "le"),
             BinaryOperator::Ge => write!(f, "ge"),
             BinaryOperator::Eq => write!(f, "eq"),
             BinaryOperator::Ne => write!(f, "ne"),
+            BinaryOperator::ULt => write!(f, "ult"),
+            BinaryOperator::UGt => write!(f, "ugt"),
             BinaryOperator::And => write!(f, "and"),
             BinaryOperator::Or => write!(f, "or"),
+            BinaryOperator::Xor => write!(f, "xor"),
+            BinaryOperator::Shl => write!(f, "shl"),
+            BinaryOperator::Shr => write!(f, "shr"),
+            BinaryOperator::Sar => write!(f, "sar"),
         }
     }
 }
@@ -215,6 +273,16 @@ pub enum UnaryOperator {
     Negate,
     Not,
     Abs,
+    /// Comparison against zero: `0=`, `0<` and `0>`
+    ZeroEq,
+    ZeroLt,
+    ZeroGt,
+    /// `1+` and `1-`
+    IncOne,
+    DecOne,
+    /// `2*` and `2/`: shifts by one bit, `2/` keeping the sign
+    MulTwo,
+    DivTwo,
 }
 
 impl fmt::Display for UnaryOperator {
@@ -223,6 +291,13 @@ impl fmt::Display for UnaryOperator {
             UnaryOperator::Negate => write!(f, "neg"),
             UnaryOperator::Not => write!(f, "not"),
             UnaryOperator::Abs => write!(f, "abs"),
+            UnaryOperator::ZeroEq => write!(f, "0="),
+            UnaryOperator::ZeroLt => write!(f, "0<"),
+            UnaryOperator::ZeroGt => write!(f, "0>"),
+            UnaryOperator::IncOne => write!(f, "1+"),
+            UnaryOperator::DecOne => write!(f, "1-"),
+            UnaryOperator::MulTwo => write!(f, "2*"),
+            UnaryOperator::DivTwo => write!(f, "2/"),
         }
     }
 }
@@ -277,6 +352,73 @@ impl SSAFunction {
         }
     }
 
+    /// Blocks a block can transfer control to
+    pub(crate) fn successors(block: &BasicBlock) -> Vec<BlockId> {
+        let mut successors = Vec::new();
+        for inst in &block.instructions {
+            match inst {
+                SSAInstruction::Branch { true_block, false_block, .. } => {
+                    successors.push(*true_block);
+                    successors.push(*false_block);
+                }
+                SSAInstruction::Jump { target } => successors.push(*target),
+                _ => {}
+            }
+        }
+        successors
+    }
+
+    /// Delete blocks that cannot be reached from the entry block
+    ///
+    /// Phi inputs from deleted blocks are dropped and the `predecessors` of
+    /// the remaining blocks are recomputed. Returns the number of blocks removed.
+    pub fn remove_unreachable_blocks(&mut self) -> usize {
+        let mut reachable = std::collections::HashSet::new();
+        let mut worklist = vec![self.entry_block];
+        while let Some(id) = worklist.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+            if let Some(block) = self.blocks.iter().find(|b| b.id == id) {
+                worklist.extend(Self::successors(block));
+            }
+        }
+
+        let before = self.blocks.len();
+        self.blocks.retain(|block| reachable.contains(&block.id));
+
+        for block in &mut self.blocks {
+            for inst in &mut block.instructions {
+                if let SSAInstruction::Phi { incoming, .. } = inst {
+                    incoming.retain(|(pred, _)| reachable.contains(pred));
+                }
+            }
+        }
+
+        self.compute_predecessors();
+        before - self.blocks.len()
+    }
+
+    /// Fill in `predecessors` of every block from the control-flow edges
+    pub fn compute_predecessors(&mut self) {
+        let edges: Vec<(BlockId, BlockId)> = self
+            .blocks
+            .iter()
+            .flat_map(|block| Self::successors(block).into_iter().map(move |succ| (block.id, succ)))
+            .collect();
+
+        for block in &mut self.blocks {
+            block.predecessors.clear();
+        }
+        for (from, to) in edges {
+            if let Some(block) = self.blocks.iter_mut().find(|b| b.id == to) {
+                if !block.predecessors.contains(&from) {
+                    block.predecessors.push(from);
+                }
+            }
+        }
+    }
+
     /// Validate SSA form invariants
     ///
     /// This performs comprehensive validation including:
@@ -291,6 +433,18 @@ impl SSAFunction {
         let mut validator = SSAValidator::new(self);
         validator.validate()
     }
+
+    /// Check the function is well-formed enough to hand to code generation
+    ///
+    /// On top of `validate`, every block must end in a single branch, jump or
+    /// return, every branch must target an existing block, the entry block must
+    /// not be a branch target, and every register, Phi operands included, must
+    /// be defined on all paths to its use. Errors name the function, block and
+    /// register involved.
+    pub fn verify(&self) -> Result<()> {
+        use crate::ssa_validator::SSAValidator;
+        SSAValidator::new(self).verify()
+    }
 }
 
 /// SSA converter
@@ -301,6 +455,25 @@ pub struct SSAConverter {
     blocks: Vec<BasicBlock>,
     /// Map from function name to parameter count
     function_params: std::collections::HashMap<String, usize>,
+    /// Names declared with VARIABLE; references push the variable's address
+    variables: std::collections::HashSet<String>,
+    /// Names declared with VALUE; references push the current value
+    values: std::collections::HashSet<String>,
+    /// Names declared with CONSTANT and their values
+    constants: std::collections::HashMap<String, i64>,
+    /// Bodies of defining words (definitions containing CREATE)
+    defining_words: std::collections::HashMap<String, Vec<Word>>,
+    /// Size in cells of the data field of every word made by CREATE
+    data_fields: std::collections::HashMap<String, usize>,
+    /// DOES> code of words made by defining words
+    behaviors: std::collections::HashMap<String, Vec<Word>>,
+    /// Data field being filled by `,` and the next cell to fill
+    filling: Option<(String, usize)>,
+    /// Locals of the definition being converted, bound to their registers
+    locals: std::collections::HashMap<String, Register>,
+    /// Parameters taken and results left by the words called through
+    /// EXECUTE, or `None` when the ticked words disagree
+    indirect_effect: Option<(usize, usize)>,
 }
 
 impl SSAConverter {
@@ -311,6 +484,15 @@ impl SSAConverter {
             current_block: BlockId(0),
             blocks: Vec::new(),
             function_params: std::collections::HashMap::new(),
+            variables: std::collections::HashSet::new(),
+            values: std::collections::HashSet::new(),
+            constants: std::collections::HashMap::new(),
+            defining_words: std::collections::HashMap::new(),
+            data_fields: std::collections::HashMap::new(),
+            behaviors: std::collections::HashMap::new(),
+            filling: None,
+            locals: std::collections::HashMap::new(),
+            indirect_effect: Some((0, 1)),
         }
     }
 
@@ -328,30 +510,79 @@ impl SSAConverter {
 
     fn emit(&mut self, instruction: SSAInstruction) {
         if let Some(block) = self.blocks.iter_mut().find(|b| b.id == self.current_block) {
+            debug_assert!(
+                !block.instructions.last().is_some_and(SSAInstruction::is_terminator),
+                "Attempting to emit instruction after the terminator of block {:?}", self.current_block
+            );
             block.instructions.push(instruction);
         } else {
             debug_assert!(false, "Attempting to emit instruction to non-existent block {:?}", self.current_block);
         }
     }
 
+    /// Value of a register defined by an integer literal, if any
+    fn constant_value(&self, reg: Register) -> Option<i64> {
+        self.blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .find_map(|inst| match inst {
+                SSAInstruction::LoadInt { dest, value } if *dest == reg => Some(*value),
+                _ => None,
+            })
+    }
+
     fn create_block(&mut self) -> BlockId {
         let id = self.fresh_block();
         self.blocks.push(BasicBlock::new(id));
         id
     }
 
+    /// Whether a block already ends in a branch, jump or return
+    fn is_terminated(&self, id: BlockId) -> bool {
+        self.blocks
+            .iter()
+            .find(|b| b.id == id)
+            .and_then(|b| b.instructions.last())
+            .is_some_and(SSAInstruction::is_terminator)
+    }
+
+    /// Continue emitting into `id`; a block left without a terminator falls
+    /// through to it with an explicit jump
     fn set_current_block(&mut self, id: BlockId) {
+        let open = self.blocks.iter().any(|b| b.id == self.current_block)
+            && !self.is_terminated(self.current_block);
+        if id != self.current_block && open {
+            self.emit(SSAInstruction::Jump { target: id });
+        }
         self.current_block = id;
     }
 
     /// Convert a sequence of words to SSA
     pub fn convert_sequence(&mut self, words: &[Word], stack: &mut Vec<Register>) -> Result<()> {
-        for word in words {
-            self.convert_word(word, stack)?;
+        let mut index = 0;
+        while index < words.len() {
+            // `?dup if` is converted as one conditional, since `?dup` alone
+            // leaves a depth that depends on its input
+            if let (Word::WordRef { name, .. }, Some(Word::If { then_branch, else_branch })) =
+                (&words[index], words.get(index + 1))
+            {
+                if name == "?dup" && self.is_builtin_word(name) {
+                    self.convert_if(then_branch, else_branch.as_deref(), true, stack)?;
+                    index += 2;
+                    continue;
+                }
+            }
+            self.convert_word(&words[index], stack)?;
+            index += 1;
         }
         Ok(())
     }
 
+    /// Whether `name` means the built-in word, rather than a local or definition shadowing it
+    fn is_builtin_word(&self, name: &str) -> bool {
+        !self.locals.contains_key(name) && !self.function_params.contains_key(name)
+    }
+
     /// Convert a single word to SSA
     fn convert_word(&mut self, word: &Word, stack: &mut Vec<Register>) -> Result<()> {
         match word {
@@ -386,15 +617,15 @@ impl SSAConverter {
                 stack.push(dest_len);
             }
 
-            Word::WordRef { name, .. } => {
-                self.convert_word_call(name, stack)?;
+            Word::WordRef { name, location } => {
+                self.convert_word_call(name, location, stack)?;
             }
 
             Word::If {
                 then_branch,
                 else_branch,
             } => {
-                self.convert_if(then_branch, else_branch.as_deref(), stack)?;
+                self.convert_if(then_branch, else_branch.as_deref(), false, stack)?;
             }
 
             Word::BeginUntil { body } => {
@@ -409,20 +640,65 @@ impl SSAConverter {
                 self.convert_do_loop(body, stack)?;
             }
 
-            Word::Variable { name: _ } => {
-                // Variables push their address
-                let dest = self.fresh_register();
-                // Would need to implement variable address allocation
-                stack.push(dest);
+            Word::Variable { .. } => {
+                // Declaration only: storage is allocated by the backend and
+                // references to the name push its address
             }
 
-            Word::Constant { name: _, value } => {
-                let dest = self.fresh_register();
+            Word::Constant { .. } => {
+                // Declaration only: references to the name push its value
+            }
+
+            Word::Value { name, value } => {
+                let initial = self.fresh_register();
                 self.emit(SSAInstruction::LoadInt {
-                    dest,
+                    dest: initial,
                     value: *value,
                 });
-                stack.push(dest);
+                self.store_value(name, initial);
+            }
+
+            Word::To { name } => {
+                if !self.values.contains(name) {
+                    return Err(ForthError::SSAConversionError {
+                        message: format!("TO requires a VALUE, but '{}' is not one", name),
+                    });
+                }
+                let value = stack.pop().ok_or_else(|| ForthError::StackUnderflow {
+                    word: format!("TO {}", name),
+                    expected: 1,
+                    found: 0,
+                })?;
+                self.store_value(name, value);
+            }
+
+            Word::Tick { name, .. } => return self.convert_tick(name, stack),
+
+            Word::Create { name: Some(name) } => {
+                // Following `,`s fill the new data field
+                self.filling = Some((name.clone(), 0));
+            }
+
+            Word::Create { name: None } | Word::Does { .. } => {
+                return Err(ForthError::SSAConversionError {
+                    message: "CREATE and DOES> inside a definition must belong to a defining word".to_string(),
+                });
+            }
+
+            Word::Instantiate { defining_word, name } => {
+                let body = self.defining_words.get(defining_word).cloned().ok_or_else(|| {
+                    ForthError::SSAConversionError {
+                        message: format!("'{}' is not a defining word", defining_word),
+                    }
+                })?;
+                // Run the defining word up to DOES>, with CREATE naming the new word
+                for word in &body {
+                    match word {
+                        Word::Create { .. } => self.filling = Some((name.clone(), 0)),
+                        Word::Does { .. } => break,
+                        _ => self.convert_word(word, stack)?,
+                    }
+                }
             }
 
             Word::Comment(_) => {
@@ -433,8 +709,172 @@ impl SSAConverter {
         Ok(())
     }
 
+    /// Push the execution token of `name`
+    fn convert_tick(&mut self, name: &str, stack: &mut Vec<Register>) -> Result<()> {
+        // Ticks of built-ins only work where EXECUTE was resolved statically
+        if !self.function_params.contains_key(name) {
+            return Err(ForthError::SSAConversionError {
+                message: format!(
+                    "'{}' has no execution token at run time; only definitions can be executed indirectly",
+                    name
+                ),
+            });
+        }
+        let dest = self.fresh_register();
+        self.emit(SSAInstruction::ExecutionToken { dest, word: name.to_string() });
+        stack.push(dest);
+        Ok(())
+    }
+
+    /// Emit the address of the cell `offset` cells into a word's storage
+    fn field_addr(&mut self, name: &str, offset: usize) -> Register {
+        let base = self.fresh_register();
+        self.emit(SSAInstruction::VariableAddr {
+            dest: base,
+            name: name.to_string(),
+            cells: self.data_fields.get(name).copied().unwrap_or(1),
+        });
+        if offset == 0 {
+            return base;
+        }
+
+        let bytes = self.fresh_register();
+        self.emit(SSAInstruction::LoadInt {
+            dest: bytes,
+            value: (offset * 8) as i64,
+        });
+        let dest = self.fresh_register();
+        self.emit(SSAInstruction::BinaryOp {
+            dest,
+            op: BinaryOperator::Add,
+            left: base,
+            right: bytes,
+        });
+        dest
+    }
+
+    /// Write a register into the storage cell of a VALUE
+    fn store_value(&mut self, name: &str, value: Register) {
+        let address = self.field_addr(name, 0);
+        self.emit(SSAInstruction::Store {
+            address,
+            value,
+            ty: StackType::Int,
+        });
+    }
+
+    /// Append a cell to the data field being filled (`,`)
+    fn convert_comma(&mut self, stack: &mut Vec<Register>) -> Result<()> {
+        let value = stack.pop().ok_or_else(|| ForthError::StackUnderflow {
+            word: ",".to_string(),
+            expected: 1,
+            found: 0,
+        })?;
+        let (name, offset) = self.filling.clone().ok_or_else(|| ForthError::SSAConversionError {
+            message: "',' without a preceding CREATE".to_string(),
+        })?;
+        // Data fields are sized statically from the `,`s that follow CREATE
+        if offset >= self.data_fields.get(&name).copied().unwrap_or(0) {
+            return Err(ForthError::SSAConversionError {
+                message: format!("',' past the end of the data field of '{}'", name),
+            });
+        }
+
+        let address = self.field_addr(&name, offset);
+        self.emit(SSAInstruction::Store {
+            address,
+            value,
+            ty: StackType::Int,
+        });
+        self.filling = Some((name, offset + 1));
+        Ok(())
+    }
+
+    /// Convert `n ALLOT` after CREATE, reserving the next `n` bytes of the data field
+    ///
+    /// Data fields are sized before conversion, so the count must be a literal.
+    fn convert_allot(&mut self, stack: &mut Vec<Register>) -> Result<()> {
+        let count = stack.pop().ok_or_else(|| ForthError::StackUnderflow {
+            word: "allot".to_string(),
+            expected: 1,
+            found: 0,
+        })?;
+        let bytes = self.constant_value(count).filter(|bytes| *bytes >= 0).ok_or_else(|| {
+            ForthError::SSAConversionError {
+                message: "ALLOT needs a literal, non-negative byte count in compiled code".to_string(),
+            }
+        })?;
+        let (name, offset) = self.filling.clone().ok_or_else(|| ForthError::SSAConversionError {
+            message: "ALLOT without a preceding CREATE".to_string(),
+        })?;
+
+        let end = offset + cells_for_bytes(bytes);
+        if end > self.data_fields.get(&name).copied().unwrap_or(0) {
+            return Err(ForthError::SSAConversionError {
+                message: format!("ALLOT past the end of the data field of '{}'", name),
+            });
+        }
+        self.filling = Some((name, end));
+        Ok(())
+    }
+
     /// Convert a word call to SSA
-    fn convert_word_call(&mut self, name: &str, stack: &mut Vec<Register>) -> Result<()> {
+    fn convert_word_call(&mut self, name: &str, location: &SourceLocation, stack: &mut Vec<Register>) -> Result<()> {
+        // Locals shadow every other word, and can be read any number of times
+        if let Some(&register) = self.locals.get(name) {
+            stack.push(register);
+            return Ok(());
+        }
+
+        // A VALUE is re-read on every use, never folded to its initial value
+        if self.values.contains(name) {
+            let address = self.field_addr(name, 0);
+            let dest = self.fresh_register();
+            self.emit(SSAInstruction::Load {
+                dest,
+                address,
+                ty: StackType::Int,
+            });
+            stack.push(dest);
+            return Ok(());
+        }
+
+        if let Some(&value) = self.constants.get(name) {
+            let dest = self.fresh_register();
+            self.emit(SSAInstruction::LoadInt { dest, value });
+            stack.push(dest);
+            return Ok(());
+        }
+
+        if self.variables.contains(name) {
+            let dest = self.field_addr(name, 0);
+            stack.push(dest);
+            return Ok(());
+        }
+
+        // Words made by CREATE push their data field, then run any DOES> code;
+        // instances of one defining word share the code but not the data
+        if self.data_fields.contains_key(name) {
+            let address = self.field_addr(name, 0);
+            stack.push(address);
+            if let Some(behavior) = self.behaviors.get(name).cloned() {
+                self.convert_sequence(&behavior, stack)?;
+            }
+            return Ok(());
+        }
+
+        if self.defining_words.contains_key(name) {
+            return Err(ForthError::SSAConversionError {
+                message: format!("Defining word '{}' must be followed by a name at top level", name),
+            });
+        }
+
+        // Definitions shadow built-in words of the same name everywhere in
+        // the program, including in definitions that come before them
+        if let Some(&param_count) = self.function_params.get(name) {
+            return self.convert_definition_call(name, param_count, stack);
+        }
+
         match name {
             // Arithmetic operations
             "+" => self.convert_binary_op(BinaryOperator::Add, stack),
@@ -442,6 +882,8 @@ impl SSAConverter {
             "*" => self.convert_binary_op(BinaryOperator::Mul, stack),
             "/" => self.convert_binary_op(BinaryOperator::Div, stack),
             "mod" => self.convert_binary_op(BinaryOperator::Mod, stack),
+            "min" => self.convert_binary_op(BinaryOperator::Min, stack),
+            "max" => self.convert_binary_op(BinaryOperator::Max, stack),
 
             // Comparison operations
             "<" => self.convert_binary_op(BinaryOperator::Lt, stack),
@@ -450,15 +892,28 @@ impl SSAConverter {
             ">=" => self.convert_binary_op(BinaryOperator::Ge, stack),
             "=" => self.convert_binary_op(BinaryOperator::Eq, stack),
             "<>" => self.convert_binary_op(BinaryOperator::Ne, stack),
+            "u<" => self.convert_binary_op(BinaryOperator::ULt, stack),
+            "u>" => self.convert_binary_op(BinaryOperator::UGt, stack),
+            "0=" => self.convert_unary_op(UnaryOperator::ZeroEq, stack),
+            "0<" => self.convert_unary_op(UnaryOperator::ZeroLt, stack),
+            "0>" => self.convert_unary_op(UnaryOperator::ZeroGt, stack),
 
             // Logical operations
             "and" => self.convert_binary_op(BinaryOperator::And, stack),
             "or" => self.convert_binary_op(BinaryOperator::Or, stack),
+            "xor" => self.convert_binary_op(BinaryOperator::Xor, stack),
+            "lshift" => self.convert_binary_op(BinaryOperator::Shl, stack),
+            "rshift" => self.convert_binary_op(BinaryOperator::Shr, stack),
+            "arshift" => self.convert_binary_op(BinaryOperator::Sar, stack),
             "not" => self.convert_unary_op(UnaryOperator::Not, stack),
 
             // Unary operations
             "negate" => self.convert_unary_op(UnaryOperator::Negate, stack),
             "abs" => self.convert_unary_op(UnaryOperator::Abs, stack),
+            "1+" => self.convert_unary_op(UnaryOperator::IncOne, stack),
+            "1-" => self.convert_unary_op(UnaryOperator::DecOne, stack),
+            "2*" => self.convert_unary_op(UnaryOperator::MulTwo, stack),
+            "2/" => self.convert_unary_op(UnaryOperator::DivTwo, stack),
 
             // Stack manipulation
             "dup" => {
@@ -474,6 +929,28 @@ impl SSAConverter {
                 Ok(())
             }
 
+            // `?dup if` is handled by `convert_sequence`; on its own the
+            // result depth is only known for a literal input
+            "?dup" => {
+                let top = *stack.last().ok_or_else(|| ForthError::StackUnderflow {
+                    word: "?dup".to_string(),
+                    expected: 1,
+                    found: 0,
+                })?;
+                match self.constant_value(top) {
+                    Some(0) => Ok(()),
+                    Some(_) => {
+                        stack.push(top);
+                        Ok(())
+                    }
+                    None => Err(ForthError::SSAConversionError {
+                        message: "?DUP leaves one or two items depending on its input; \
+                                  follow it with IF"
+                            .to_string(),
+                    }),
+                }
+            }
+
             "drop" => {
                 if stack.pop().is_none() {
                     return Err(ForthError::StackUnderflow {
@@ -527,6 +1004,61 @@ impl SSAConverter {
                 Ok(())
             }
 
+            // Double-cell shuffles only rearrange registers
+            "2dup" | "2drop" | "2swap" | "2over" => {
+                let needed = if name == "2dup" || name == "2drop" { 2 } else { 4 };
+                if stack.len() < needed {
+                    return Err(ForthError::StackUnderflow {
+                        word: name.to_string(),
+                        expected: needed,
+                        found: stack.len(),
+                    });
+                }
+                let len = stack.len();
+                match name {
+                    "2dup" => stack.extend_from_within(len - 2..),
+                    "2drop" => stack.truncate(len - 2),
+                    "2swap" => stack[len - 4..].rotate_left(2),
+                    _ => stack.extend_from_within(len - 4..len - 2),
+                }
+                Ok(())
+            }
+
+            "pick" | "roll" => {
+                // The index must be a literal so the shuffle can be resolved statically
+                let index_reg = stack.pop().ok_or_else(|| ForthError::StackUnderflow {
+                    word: name.to_string(),
+                    expected: 1,
+                    found: 0,
+                })?;
+                let index = self.constant_value(index_reg).ok_or_else(|| ForthError::SSAConversionError {
+                    message: format!("{} requires a literal index", name),
+                })?;
+                if index < 0 {
+                    return Err(ForthError::SSAConversionError {
+                        message: format!("{} index must not be negative, got {}", name, index),
+                    });
+                }
+
+                let index = index as usize;
+                if stack.len() <= index {
+                    return Err(ForthError::StackUnderflow {
+                        word: name.to_string(),
+                        expected: index + 2,
+                        found: stack.len() + 1,
+                    });
+                }
+
+                let pos = stack.len() - 1 - index;
+                if name == "pick" {
+                    stack.push(stack[pos]);
+                } else {
+                    let reg = stack.remove(pos);
+                    stack.push(reg);
+                }
+                Ok(())
+            }
+
             // Memory operations
             "@" => {
                 if let Some(addr) = stack.pop() {
@@ -547,6 +1079,29 @@ impl SSAConverter {
                 Ok(())
             }
 
+            "," => self.convert_comma(stack),
+            "count" => {
+                // ( c-addr -- c-addr+1 u ): the length is the byte at c-addr
+                let addr = stack.pop().ok_or_else(|| ForthError::StackUnderflow {
+                    word: "count".to_string(),
+                    expected: 1,
+                    found: 0,
+                })?;
+                let text = self.fresh_register();
+                self.emit(SSAInstruction::UnaryOp { dest: text, op: UnaryOperator::IncOne, operand: addr });
+                let len = self.fresh_register();
+                self.emit(SSAInstruction::Load { dest: len, address: addr, ty: StackType::Char });
+                stack.push(text);
+                stack.push(len);
+                Ok(())
+            }
+            "allot" => self.convert_allot(stack),
+            "here" => Err(ForthError::SSAConversionError {
+                message: "HERE is only available in the interpreter; compiled code has \
+                          no data-space pointer"
+                    .to_string(),
+            }),
+
             "!" => {
                 if stack.len() < 2 {
                     return Err(ForthError::StackUnderflow {
@@ -600,22 +1155,28 @@ impl SSAConverter {
             }
 
             // I/O operations
-            "." | "emit" | "cr" => {
-                // Print operations - consume from stack
-                if let Some(val) = stack.pop() {
-                    self.emit(SSAInstruction::Call {
-                        dest: SmallVec::new(),
-                        name: name.to_string(),
-                        args: smallvec::smallvec![val],
-                    });
-                    Ok(())
-                } else {
-                    Err(ForthError::StackUnderflow {
+            "." | "u." | ".r" | "emit" | "cr" | "type" => {
+                // Print operations - consume their arguments, produce nothing
+                let arity = match name {
+                    "cr" => 0,
+                    ".r" | "type" => 2,
+                    _ => 1,
+                };
+                if stack.len() < arity {
+                    return Err(ForthError::StackUnderflow {
                         word: name.to_string(),
-                        expected: 1,
-                        found: 0,
-                    })
+                        expected: arity,
+                        found: stack.len(),
+                    });
                 }
+
+                let args = stack.split_off(stack.len() - arity).into_iter().collect();
+                self.emit(SSAInstruction::Call {
+                    dest: SmallVec::new(),
+                    name: name.to_string(),
+                    args,
+                });
+                Ok(())
             }
 
             // File mode constants (ANS Forth)
@@ -821,6 +1382,33 @@ impl SSAConverter {
                 Ok(())
             }
 
+            "file-status" => {
+                // Stack effect: ( c-addr u -- x ior )
+                if stack.len() < 2 {
+                    return Err(ForthError::StackUnderflow {
+                        word: "file-status".to_string(),
+                        expected: 2,
+                        found: stack.len(),
+                    });
+                }
+                let path_len = stack.pop().unwrap();
+                let path_addr = stack.pop().unwrap();
+
+                let dest_status = self.fresh_register();
+                let dest_ior = self.fresh_register();
+
+                self.emit(SSAInstruction::FileStatus {
+                    dest_status,
+                    dest_ior,
+                    path_addr,
+                    path_len,
+                });
+
+                stack.push(dest_status);
+                stack.push(dest_ior);
+                Ok(())
+            }
+
             // System call
             "system" => {
                 // Stack effect: ( c-addr u -- return-code )
@@ -859,15 +1447,23 @@ impl SSAConverter {
                 Ok(())
             }
 
-            // Other special words
-            "execute" | "char" => {
-                // For now, treat as generic calls
+            // A token not known at compile time: an indirect call taking the
+            // word's parameters and then the token
+            "execute" => {
+                let (params, _) = self.indirect_effect.ok_or_else(|| ForthError::SSAConversionError {
+                    message: "EXECUTE of a token not known at compile time needs every ticked word \
+                              to take the same number of parameters"
+                        .to_string(),
+                })?;
+                if stack.len() < params + 1 {
+                    return Err(ForthError::StackUnderflow {
+                        word: name.to_string(),
+                        expected: params + 1,
+                        found: stack.len(),
+                    });
+                }
+                let args = SmallVec::from_vec(stack.split_off(stack.len() - params - 1));
                 let dest = self.fresh_register();
-                let args = if name == "execute" && !stack.is_empty() {
-                    smallvec::smallvec![stack.pop().unwrap()]
-                } else {
-                    SmallVec::new()
-                };
                 self.emit(SSAInstruction::Call {
                     dest: smallvec::smallvec![dest],
                     name: name.to_string(),
@@ -877,42 +1473,59 @@ impl SSAConverter {
                 Ok(())
             }
 
-            // Generic word call
-            _ => {
-                // Look up the function to determine how many parameters it takes
-                let param_count = self.function_params.get(name).copied().unwrap_or(0);
-
-                // Pop arguments from stack
-                if stack.len() < param_count {
-                    return Err(ForthError::StackUnderflow {
-                        word: name.to_string(),
-                        expected: param_count,
-                        found: stack.len(),
-                    });
-                }
-
-                let mut args = SmallVec::new();
-                for _ in 0..param_count {
-                    // Pop from end and reverse to maintain order
-                    if let Some(arg) = stack.pop() {
-                        args.push(arg);
-                    }
-                }
-                // Reverse to get correct argument order
-                args.reverse();
-
+            // Other special words
+            "char" => {
+                // For now, treat as a generic call
                 let dest = self.fresh_register();
                 self.emit(SSAInstruction::Call {
                     dest: smallvec::smallvec![dest],
                     name: name.to_string(),
-                    args,
+                    args: SmallVec::new(),
                 });
                 stack.push(dest);
                 Ok(())
             }
+
+            // Every definition is registered before any is converted, so
+            // forward references and mutual recursion never get here
+            _ => Err(ForthError::UndefinedWord {
+                word: name.to_string(),
+                location: Some(location.clone()),
+            }),
         }
     }
 
+    /// Convert a call to a definition taking `param_count` parameters
+    fn convert_definition_call(&mut self, name: &str, param_count: usize, stack: &mut Vec<Register>) -> Result<()> {
+        // Pop arguments from stack
+        if stack.len() < param_count {
+            return Err(ForthError::StackUnderflow {
+                word: name.to_string(),
+                expected: param_count,
+                found: stack.len(),
+            });
+        }
+
+        let mut args = SmallVec::new();
+        for _ in 0..param_count {
+            // Pop from end and reverse to maintain order
+            if let Some(arg) = stack.pop() {
+                args.push(arg);
+            }
+        }
+        // Reverse to get correct argument order
+        args.reverse();
+
+        let dest = self.fresh_register();
+        self.emit(SSAInstruction::Call {
+            dest: smallvec::smallvec![dest],
+            name: name.to_string(),
+            args,
+        });
+        stack.push(dest);
+        Ok(())
+    }
+
     fn convert_binary_op(&mut self, op: BinaryOperator, stack: &mut Vec<Register>) -> Result<()> {
         if stack.len() < 2 {
             return Err(ForthError::StackUnderflow {
@@ -952,25 +1565,37 @@ impl SSAConverter {
         }
     }
 
+    /// Convert `if ... else ... then`, or `?dup if ... else ... then` when
+    /// `after_qdup` is set
+    ///
+    /// After `?dup` the tested value stays on the stack for the THEN branch
+    /// only: a nonzero value was duplicated and one copy consumed by IF,
+    /// while a zero was not duplicated and is consumed by IF.
     fn convert_if(
         &mut self,
         then_branch: &[Word],
         else_branch: Option<&[Word]>,
+        after_qdup: bool,
         stack: &mut Vec<Register>,
     ) -> Result<()> {
         let condition = stack.pop().ok_or_else(|| ForthError::StackUnderflow {
-            word: "IF".to_string(),
+            word: if after_qdup { "?dup" } else { "IF" }.to_string(),
             expected: 1,
             found: 0,
         })?;
 
+        let else_stack = stack.clone();
+        let mut then_stack = stack.clone();
+        if after_qdup {
+            then_stack.push(condition);
+        }
+
+        // Without ELSE the else block is empty and only jumps to the merge
+        // block, so every edge into the merge block is a jump carrying the
+        // Phi inputs
         let then_block = self.create_block();
         let merge_block = self.create_block();
-        let else_block = if else_branch.is_some() {
-            self.create_block()
-        } else {
-            merge_block
-        };
+        let else_block = self.create_block();
 
         // Emit branch
         self.emit(SSAInstruction::Branch {
@@ -979,12 +1604,8 @@ impl SSAConverter {
             false_block: else_block,
         });
 
-        //  Save original stack before branches
-        let original_stack = stack.clone();
-
         // Convert then branch
         self.set_current_block(then_block);
-        let mut then_stack = original_stack.clone();
         self.convert_sequence(then_branch, &mut then_stack)?;
         let then_final = then_stack.clone();
         // Track which block we're actually in after conversion (may differ from then_block if nested control flow)
@@ -993,20 +1614,14 @@ impl SSAConverter {
             target: merge_block,
         });
 
-        // Convert else branch if present, otherwise use original stack
-        let (else_final, actual_else_block) = if let Some(else_words) = else_branch {
-            self.set_current_block(else_block);
-            let mut else_stack = original_stack.clone();
-            self.convert_sequence(else_words, &mut else_stack)?;
-            let result = else_stack.clone();
-            let actual_block = self.current_block;
-            self.emit(SSAInstruction::Jump {
-                target: merge_block,
-            });
-            (result, actual_block)
-        } else {
-            (original_stack.clone(), else_block)
-        };
+        // Convert else branch, empty without ELSE
+        self.set_current_block(else_block);
+        let mut else_final = else_stack;
+        self.convert_sequence(else_branch.unwrap_or_default(), &mut else_final)?;
+        let actual_else_block = self.current_block;
+        self.emit(SSAInstruction::Jump {
+            target: merge_block,
+        });
 
         // Verify same stack depth from both branches
         if then_final.len() != else_final.len() {
@@ -1174,25 +1789,29 @@ impl SSAConverter {
         Ok(())
     }
 
-    /// Convert a definition to SSA function
-    pub fn convert_definition(&mut self, def: &Definition) -> Result<SSAFunction> {
-        // Reset converter state for new function
+    /// Reset all per-function state, so every function numbers its blocks
+    /// from `bb0` and its registers from the parameters `0..param_count`
+    fn begin_function(&mut self, param_count: usize) {
+        self.next_register = param_count;
         self.next_block = 0;
         self.blocks.clear();
         self.current_block = BlockId(0);
+        self.filling = None;
+        self.locals.clear();
+    }
 
+    /// Convert a definition to SSA function
+    pub fn convert_definition(&mut self, def: &Definition) -> Result<SSAFunction> {
         // Determine number of parameters from stack effect, or infer from body
         let param_count = if let Some(ref effect) = def.stack_effect {
             effect.inputs.len()
         } else {
             // Infer parameter count by simulating the stack
-            self.infer_parameter_count(&def.body)?
+            self.infer_parameter_count(def)?
         };
 
         let mut function = SSAFunction::new(def.name.clone(), param_count);
-
-        // Initialize register counter with parameters
-        self.next_register = param_count;
+        self.begin_function(param_count);
 
         // Create entry block (will now be BlockId(0))
         let entry = self.create_block();
@@ -1201,8 +1820,14 @@ impl SSAConverter {
         // Initialize stack with parameters
         let mut stack: Vec<Register> = function.parameters.clone();
 
+        if let Some(locals) = &def.locals {
+            self.bind_locals(&def.name, locals, &mut stack)?;
+        }
+
         // Convert function body
-        self.convert_sequence(&def.body, &mut stack)?;
+        let converted = self.convert_sequence(&def.body, &mut stack);
+        self.locals.clear();
+        converted?;
 
         // Emit return - ensure we always return at least one value (0 if stack is empty)
         // This matches Cranelift backend expectation that all Forth functions return i64
@@ -1225,23 +1850,80 @@ impl SSAConverter {
 
         // Move blocks to function
         function.blocks = std::mem::take(&mut self.blocks);
+        function.remove_unreachable_blocks();
+
+        if let Some(open) = function.blocks.iter()
+            .find(|block| !block.instructions.last().is_some_and(SSAInstruction::is_terminator))
+        {
+            return Err(ForthError::SSAConversionError {
+                message: format!("Block {} in '{}' was left without a terminator", open.id, def.name),
+            });
+        }
 
         Ok(function)
     }
 
+    /// Pop the initialized locals off the entry stack and zero the rest
+    fn bind_locals(&mut self, word: &str, locals: &Locals, stack: &mut Vec<Register>) -> Result<()> {
+        if stack.len() < locals.params.len() {
+            return Err(ForthError::StackUnderflow {
+                word: format!("{} locals", word),
+                expected: locals.params.len(),
+                found: stack.len(),
+            });
+        }
+
+        let values = stack.split_off(stack.len() - locals.params.len());
+        for (name, register) in locals.params.iter().zip(values) {
+            self.locals.insert(name.clone(), register);
+        }
+
+        for name in &locals.uninitialized {
+            let dest = self.fresh_register();
+            self.emit(SSAInstruction::LoadInt { dest, value: 0 });
+            self.locals.insert(name.clone(), dest);
+        }
+
+        Ok(())
+    }
+
     /// Infer the number of parameters needed by simulating stack depth
-    fn infer_parameter_count(&self, body: &[Word]) -> Result<usize> {
+    fn infer_parameter_count(&self, def: &Definition) -> Result<usize> {
+        let (min_depth, _) = self.simulate_depth(&def.body, def.locals.as_ref());
+
+        // Initialized locals are taken from the stack before the body runs
+        let locals = def.locals.as_ref().map_or(0, |locals| locals.params.len() as i32);
+
+        // The minimum depth below 0 tells us how many parameters we need
+        Ok((locals - min_depth).max(0) as usize)
+    }
+
+    /// Simulate the stack depth over a sequence, returning the lowest depth
+    /// reached and the final depth, both relative to the starting depth
+    fn simulate_depth(&self, body: &[Word], locals: Option<&Locals>) -> (i32, i32) {
         let mut min_depth: i32 = 0;
         let mut current_depth: i32 = 0;
 
-        for word in body {
+        for (i, word) in body.iter().enumerate() {
             match word {
-                Word::IntLiteral(_) | Word::FloatLiteral(_) | Word::StringLiteral(_) => {
+                Word::IntLiteral(_) | Word::FloatLiteral(_) => {
+                    current_depth += 1;
+                }
+                Word::StringLiteral(_) => {
+                    // Address and length
+                    current_depth += 2;
+                }
+                Word::Tick { .. } => {
                     current_depth += 1;
                 }
                 Word::WordRef { name, .. } => {
-                    // Get stack effect for this word
-                    let (consumes, produces) = self.get_word_stack_effect(name);
+                    // Get stack effect for this word; PICK and ROLL depend on their literal index
+                    let (consumes, produces) = match (name.as_str(), i.checked_sub(1).map(|j| &body[j])) {
+                        ("pick", Some(Word::IntLiteral(u))) if *u >= 0 => (*u as i32 + 2, *u as i32 + 2),
+                        ("roll", Some(Word::IntLiteral(u))) if *u >= 0 => (*u as i32 + 2, *u as i32 + 1),
+                        _ if locals.is_some_and(|locals| locals.names().any(|local| local == name)) => (0, 1),
+                        _ => self.get_word_stack_effect(name),
+                    };
                     current_depth -= consumes;
                     if current_depth < min_depth {
                         min_depth = current_depth;
@@ -1260,13 +1942,18 @@ impl SSAConverter {
                         min_depth = current_depth;
                     }
                 }
-                Word::Variable { .. } => {
-                    // Variable pushes its address
-                    current_depth += 1;
+                Word::To { .. } => {
+                    current_depth -= 1;
+                    if current_depth < min_depth {
+                        min_depth = current_depth;
+                    }
                 }
-                Word::Constant { .. } => {
-                    // Constant pushes its value
-                    current_depth += 1;
+                Word::Variable { .. } | Word::Constant { .. } | Word::Value { .. } => {
+                    // Declaration has no stack effect
+                }
+                Word::Create { .. } | Word::Does { .. } | Word::Instantiate { .. } => {
+                    // Only valid in defining words and top-level code, which
+                    // are never called with parameters
                 }
                 Word::Comment(_) => {
                     // Comments don't affect stack
@@ -1274,36 +1961,34 @@ impl SSAConverter {
             }
         }
 
-        // The minimum depth below 0 tells us how many parameters we need
-        Ok((-min_depth).max(0) as usize)
+        (min_depth, current_depth)
     }
 
     /// Get stack effect for a word (consumes, produces)
     fn get_word_stack_effect(&self, name: &str) -> (i32, i32) {
-        match name {
-            // Arithmetic (2 in, 1 out)
-            "+" | "-" | "*" | "/" | "mod" => (2, 1),
-            "<" | ">" | "<=" | ">=" | "=" | "<>" => (2, 1),
-            "and" | "or" => (2, 1),
-
-            // Unary (1 in, 1 out)
-            "negate" | "abs" | "not" => (1, 1),
-            "1+" | "1-" | "2*" | "2/" => (1, 1),
-
-            // Stack manipulation
-            "dup" => (1, 2),
-            "drop" => (1, 0),
-            "swap" => (2, 2),
-            "over" => (2, 3),
-            "rot" => (3, 3),
+        if self.variables.contains(name) || self.values.contains(name) || self.constants.contains_key(name) {
+            return (0, 1);
+        }
 
-            // Memory
-            "@" => (1, 1),
-            "!" => (2, 0),
+        if self.data_fields.contains_key(name) {
+            // The data field address, then the DOES> code
+            let (min_depth, final_depth) = self
+                .behaviors
+                .get(name)
+                .map_or((0, 0), |behavior| self.simulate_depth(behavior, None));
+            let consumes = (-(min_depth + 1)).max(0);
+            return (consumes, 1 + final_depth + consumes);
+        }
 
-            // Default: assume no stack effect for unknown words
-            _ => (0, 0),
+        if name == "execute" {
+            let (params, results) = self.indirect_effect.unwrap_or((0, 1));
+            return (params as i32 + 1, results as i32);
         }
+
+        // Default: assume no stack effect for unknown words
+        builtins::lookup(name)
+            .and_then(|builtin| builtin.effect)
+            .map_or((0, 0), |(inputs, outputs)| (inputs as i32, outputs as i32))
     }
 }
 
@@ -1313,46 +1998,197 @@ impl Default for SSAConverter {
     }
 }
 
+/// Name of the function synthesized from top-level code
+///
+/// The leading colon keeps it apart from any word a program can define.
+pub const MAIN_FUNCTION: &str = ":main";
+
+/// Cells `word` adds to a data field after CREATE: one for `,`, or enough
+/// to hold the bytes of `n ALLOT` when `previous` is the literal `n`
+fn allotted_cells(word: &Word, previous: Option<&Word>) -> usize {
+    match (word, previous) {
+        (Word::WordRef { name, .. }, _) if name == "," => 1,
+        (Word::WordRef { name, .. }, Some(Word::IntLiteral(bytes))) if name == "allot" => cells_for_bytes(*bytes),
+        _ => 0,
+    }
+}
+
+/// Whole 8-byte cells needed to hold `bytes`; none for a negative count
+fn cells_for_bytes(bytes: i64) -> usize {
+    usize::try_from(bytes).map_or(0, |bytes| bytes.div_ceil(8))
+}
+
 /// Convert a program to SSA form
+///
+/// Returns one function per definition, in definition order, followed by
+/// [`MAIN_FUNCTION`] holding the top-level code.
 pub fn convert_to_ssa(program: &Program) -> Result<Vec<SSAFunction>> {
+    convert_to_ssa_filtered(program, |_| true)
+}
+
+/// Convert a program to SSA form, skipping definitions for which `convert`
+/// returns false
+///
+/// Skipped definitions still count as callees, so the functions that are
+/// converted match what [`convert_to_ssa`] produces for them. Top-level code
+/// is always converted.
+pub fn convert_to_ssa_filtered(
+    program: &Program,
+    convert: impl Fn(&Definition) -> bool,
+) -> Result<Vec<SSAFunction>> {
+    // Taken from the source, which may tick words whose tokens get resolved
+    let execute = execute_effect(program);
+    let program = &*resolve_execute(program);
     let mut converter = SSAConverter::new();
     let mut functions = Vec::new();
 
-    // First pass: Build map of function names to parameter counts
+    // Defining words only run at top level, expanded where they make a new word
     for def in &program.definitions {
-        let param_count = if let Some(ref effect) = def.stack_effect {
+        if def.body.iter().any(|word| matches!(word, Word::Create { .. })) {
+            converter.defining_words.insert(def.name.clone(), def.body.clone());
+        }
+    }
+
+    // Variables, values and created words are visible to every definition,
+    // wherever they are declared
+    let mut filling: Option<&str> = None;
+    let mut previous: Option<&Word> = None;
+    for word in &program.top_level_code {
+        let allotted = allotted_cells(word, previous);
+        previous = Some(word);
+        match word {
+            Word::Variable { name } => {
+                converter.variables.insert(name.clone());
+            }
+            Word::Value { name, .. } => {
+                converter.values.insert(name.clone());
+            }
+            Word::Constant { name, value } => {
+                converter.constants.insert(name.clone(), *value);
+            }
+            Word::Create { name: Some(name) } => {
+                converter.data_fields.insert(name.clone(), 0);
+                filling = Some(name);
+            }
+            Word::Instantiate { defining_word, name } => {
+                let body = converter.defining_words.get(defining_word).ok_or_else(|| {
+                    ForthError::SSAConversionError {
+                        message: format!("'{}' is not a defining word", defining_word),
+                    }
+                })?;
+                // Cells stored or allotted between CREATE and DOES>
+                let field: Vec<&Word> = body
+                    .iter()
+                    .skip_while(|word| !matches!(word, Word::Create { .. }))
+                    .take_while(|word| !matches!(word, Word::Does { .. }))
+                    .collect();
+                let cells = field
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| allotted_cells(word, i.checked_sub(1).map(|prev| field[prev])))
+                    .sum();
+                let behavior = body.iter().find_map(|word| match word {
+                    Word::Does { body } => Some(body.clone()),
+                    _ => None,
+                });
+
+                converter.data_fields.insert(name.clone(), cells);
+                if let Some(behavior) = behavior {
+                    converter.behaviors.insert(name.clone(), behavior);
+                }
+                filling = Some(name);
+            }
+            _ if allotted > 0 => {
+                if let Some(cells) = filling.and_then(|name| converter.data_fields.get_mut(name)) {
+                    *cells += allotted;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Declared effects, plus those the inference engine can work out exactly
+    let effects = definition_effects_with(program, execute.clone());
+
+    // Words called through EXECUTE share one signature; where the inference
+    // engine cannot tell it, it is taken from the depths through the bodies
+    // of the words whose tokens are still taken
+    converter.indirect_effect = match execute {
+        Some(effect) => Some((effect.inputs.len() - 1, effect.outputs.len())),
+        None => {
+            let ticked: HashSet<&str> = program
+                .definitions
+                .iter()
+                .flat_map(|def| ticked_words(&def.body))
+                .chain(ticked_words(&program.top_level_code))
+                .collect();
+            let mut indirect_effects = HashSet::new();
+            for (def, effect) in program.definitions.iter().zip(&effects) {
+                if ticked.contains(def.name.as_str()) {
+                    indirect_effects.insert(match effect {
+                        Some(effect) => (effect.inputs.len(), effect.outputs.len()),
+                        None => {
+                            let params = converter.infer_parameter_count(def)?;
+                            let (_, final_depth) = converter.simulate_depth(&def.body, def.locals.as_ref());
+                            (params, (params as i32 + final_depth).max(0) as usize)
+                        }
+                    });
+                }
+            }
+            match indirect_effects.len() {
+                0 => Some((0, 1)),
+                1 => indirect_effects.into_iter().next(),
+                _ => None,
+            }
+        }
+    };
+
+    // First pass: Build map of function names to parameter counts
+    for (def, effect) in program.definitions.iter().zip(&effects) {
+        if converter.defining_words.contains_key(&def.name) {
+            continue;
+        }
+        let param_count = if let Some(effect) = effect {
             effect.inputs.len()
         } else {
             // Infer parameter count
-            converter.infer_parameter_count(&def.body)?
+            converter.infer_parameter_count(def)?
         };
         converter.function_params.insert(def.name.clone(), param_count);
     }
 
     // Second pass: Convert all word definitions
-    for def in &program.definitions {
-        let function = converter.convert_definition(def)?;
+    for (def, effect) in program.definitions.iter().zip(&effects) {
+        if converter.defining_words.contains_key(&def.name) || !convert(def) {
+            continue;
+        }
+        let function = match (&def.stack_effect, effect) {
+            (None, Some(effect)) => converter.convert_definition(&Definition {
+                stack_effect: Some(effect.clone()),
+                ..def.clone()
+            })?,
+            _ => converter.convert_definition(def)?,
+        };
         functions.push(function);
     }
 
-    // If there's top-level code, wrap it in an implicit :main function
-    if !program.top_level_code.is_empty() {
-        // Create a synthetic Definition for top-level code
-        // Top-level code has no parameters (it's the entry point)
-        let main_def = Definition {
-            name: "main".to_string(),
-            body: program.top_level_code.clone(),
-            immediate: false,
-            stack_effect: Some(StackEffect {
-                inputs: vec![],  // Top-level has no parameters
-                outputs: vec![StackType::Int],  // Returns top of stack
-            }),
-            location: SourceLocation::default(),
-        };
-
-        let main_function = converter.convert_definition(&main_def)?;
-        functions.push(main_function);
-    }
+    // Wrap top-level code in a :main function, always last and always
+    // present; with no top-level code it just returns 0
+    let main_def = Definition {
+        name: MAIN_FUNCTION.to_string(),
+        body: program.top_level_code.clone(),
+        immediate: false,
+        stack_effect: Some(StackEffect::new(
+            vec![],  // Top-level has no parameters
+            vec![StackType::Int],  // Returns top of stack
+        )),
+        stack_comment: None,
+        locals: None,
+        location: SourceLocation::default(),
+    };
+
+    let main_function = converter.convert_definition(&main_def)?;
+    functions.push(main_function);
 
     Ok(functions)
 }
@@ -1429,6 +2265,8 @@ fn format_instruction(inst: &SSAInstruction) -> String {
             format!("{} = phi {}", dest, incoming_str)
         }
         SSAInstruction::Load { dest, address, .. } => format!("{} = load {}", dest, address),
+        SSAInstruction::VariableAddr { dest, name, .. } => format!("{} = addr {}", dest, name),
+        SSAInstruction::ExecutionToken { dest, word } => format!("{} = xt {}", dest, word),
         SSAInstruction::Store { address, value, .. } => format!("store {}, {}", value, address),
 
         // FFI and File I/O formatting
@@ -1460,6 +2298,9 @@ fn format_instruction(inst: &SSAInstruction) -> String {
         SSAInstruction::FileDelete { dest_ior, path_addr, path_len } => {
             format!("{} = file_delete {}, {}", dest_ior, path_addr, path_len)
         }
+        SSAInstruction::FileStatus { dest_status, dest_ior, path_addr, path_len } => {
+            format!("{}, {} = file_status {}, {}", dest_status, dest_ior, path_addr, path_len)
+        }
         SSAInstruction::FileCreate { dest_fileid, dest_ior, path_addr, path_len, mode } => {
             format!("{}, {} = file_create {}, {}, {}", dest_fileid, dest_ior, path_addr, path_len, mode)
         }
@@ -1479,7 +2320,7 @@ mod tests {
         let program = parse_program(": double ( n -- n*2 ) 2 * ;").unwrap();
         let functions = convert_to_ssa(&program).unwrap();
 
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
         assert_eq!(functions[0].name, "double");
     }
 
@@ -1488,7 +2329,7 @@ mod tests {
         let program = parse_program(": square ( n -- n^2 ) dup * ;").unwrap();
         let functions = convert_to_ssa(&program).unwrap();
 
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
         let func = &functions[0];
         assert!(!func.blocks.is_empty());
     }
@@ -1529,7 +2370,7 @@ mod tests {
 
         let program = parse_program(&source).unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
         // Verify it generated all the loads
         let func = &functions[0];
         assert!(!func.blocks.is_empty());
@@ -1548,7 +2389,7 @@ mod tests {
             ;"
         ).unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         // Check that Phi nodes are generated for the merged value
         let func = &functions[0];
@@ -1573,7 +2414,7 @@ mod tests {
             ;"
         ).unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
         let func = &functions[0];
         assert!(func.blocks.len() > 1, "Nested loops should create multiple blocks");
     }
@@ -1584,7 +2425,7 @@ mod tests {
         // This Forth uses double quotes, not S"
         let program = parse_program(r#": test-string " Hello World " ;"#).unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         let func = &functions[0];
         let has_load_string = func.blocks[0].instructions.iter().any(|inst| {
@@ -1598,16 +2439,68 @@ mod tests {
         // Test complex stack manipulation (dup, swap, over, rot)
         let program = parse_program(": stack-ops ( a b c -- b c a b ) rot swap dup ;").unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
         assert_eq!(functions[0].parameters.len(), 3);
     }
 
+    #[test]
+    fn test_double_cell_shuffles_ssa() {
+        let program = parse_program(": t 2swap 2over 2dup 2drop ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+        let func = &functions[0];
+        assert_eq!(func.parameters.len(), 4);
+
+        // Pure register shuffles: nothing but the return is emitted
+        let instructions = &func.blocks[0].instructions;
+        assert_eq!(instructions.len(), 1);
+        match &instructions[0] {
+            SSAInstruction::Return { values } => {
+                let regs: Vec<usize> = values.iter().map(|r| r.0).collect();
+                assert_eq!(regs, vec![2, 3, 0, 1, 2, 3]);
+            }
+            other => panic!("expected return, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pick_roll_ssa() {
+        // 0 pick is dup, 1 roll is swap: both resolve to register shuffles
+        let program = parse_program(": p ( a b -- a b a ) 1 pick ; : r ( a b c -- b c a ) 2 roll ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        let returned = |func: &SSAFunction| {
+            func.blocks[0]
+                .instructions
+                .iter()
+                .find_map(|inst| match inst {
+                    SSAInstruction::Return { values } => Some(values.to_vec()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(returned(&functions[0]), vec![Register(0), Register(1), Register(0)]);
+        assert_eq!(returned(&functions[1]), vec![Register(1), Register(2), Register(0)]);
+
+        // Parameter counts are inferred from the literal index
+        let program = parse_program(": third 2 pick ;").unwrap();
+        assert_eq!(convert_to_ssa(&program).unwrap()[0].parameters.len(), 3);
+    }
+
+    #[test]
+    fn test_pick_requires_literal_index() {
+        let program = parse_program(": p ( a b n -- a b x ) pick ;").unwrap();
+        assert!(convert_to_ssa(&program).is_err());
+
+        let program = parse_program(": p ( a -- a x ) -1 pick ;").unwrap();
+        assert!(convert_to_ssa(&program).is_err());
+    }
+
     #[test]
     fn test_memory_operations_ssa() {
         // Test memory load and store operations
         let program = parse_program(": mem-test ( addr value -- ) swap ! ;").unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         let func = &functions[0];
         let has_store = func.blocks[0].instructions.iter().any(|inst| {
@@ -1616,6 +2509,27 @@ mod tests {
         assert!(has_store, "Expected Store instruction");
     }
 
+    #[test]
+    fn test_variable_references_ssa() {
+        let program = parse_program("variable a variable b : get-b b @ ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        // The word refers to its variable without taking a parameter
+        let get_b = &functions[0];
+        assert!(get_b.parameters.is_empty());
+        assert!(matches!(
+            &get_b.blocks[0].instructions[0],
+            SSAInstruction::VariableAddr { name, .. } if name == "b"
+        ));
+
+        // Declarations alone generate no code
+        let main = &functions[1];
+        assert!(!main.blocks[0]
+            .instructions
+            .iter()
+            .any(|inst| matches!(inst, SSAInstruction::VariableAddr { .. })));
+    }
+
     #[test]
     fn test_file_io_operations_ssa() {
         // Test file I/O operations generate correct SSA
@@ -1627,7 +2541,7 @@ mod tests {
             ;"#
         ).unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         let func = &functions[0];
         let has_file_ops = func.blocks[0].instructions.iter().any(|inst| {
@@ -1651,7 +2565,7 @@ mod tests {
             ;"
         ).unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         let func = &functions[0];
         assert!(func.blocks.len() >= 3, "WHILE-REPEAT should create multiple blocks");
@@ -1662,7 +2576,7 @@ mod tests {
         // Test function with empty body
         let program = parse_program(": noop ;").unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         // Empty function should still have return instruction
         let func = &functions[0];
@@ -1677,7 +2591,7 @@ mod tests {
         // Test that parameter count is correctly inferred from stack usage
         let program = parse_program(": inferred-params dup * + ;").unwrap();
         let functions = convert_to_ssa(&program).unwrap();
-        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.len(), 2);
 
         // dup requires 1, * requires 2, + requires 2
         // Stack analysis: start with n (1 param), dup -> n n (2), * -> n*n (1), + requires 2
@@ -1685,4 +2599,298 @@ mod tests {
         let func = &functions[0];
         assert!(func.parameters.len() >= 2, "Should infer at least 2 parameters");
     }
+
+    #[test]
+    fn test_convert_xor() {
+        let program = parse_program(": toggle ( a b -- a^b ) xor ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        let output = format!("{}", functions[0]);
+        assert!(output.contains("xor"));
+        assert!(functions[0].blocks[0].instructions.iter().any(|inst| matches!(
+            inst,
+            SSAInstruction::BinaryOp { op: BinaryOperator::Xor, .. }
+        )));
+    }
+
+    #[test]
+    fn test_convert_shifts() {
+        let program = parse_program(": bits ( a b c d -- r ) lshift rshift arshift ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        let ops: Vec<BinaryOperator> = functions[0].blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                SSAInstruction::BinaryOp { op, .. } => Some(*op),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ops, vec![BinaryOperator::Shl, BinaryOperator::Shr, BinaryOperator::Sar]);
+    }
+
+    #[test]
+    fn test_convert_zero_comparisons() {
+        let program = parse_program(": signs ( n -- a b c ) dup 0= over 0< rot 0> ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        let ops: Vec<UnaryOperator> = functions[0].blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                SSAInstruction::UnaryOp { op, .. } => Some(*op),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ops, vec![UnaryOperator::ZeroEq, UnaryOperator::ZeroLt, UnaryOperator::ZeroGt]);
+        assert!(format!("{}", functions[0]).contains("0="));
+    }
+
+    #[test]
+    fn test_convert_increment_and_shift_words() {
+        let program = parse_program(": step ( n -- n ) 1+ 1- 2* 2/ ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        let ops: Vec<UnaryOperator> = functions[0].blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                SSAInstruction::UnaryOp { op, .. } => Some(*op),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ops,
+            vec![UnaryOperator::IncOne, UnaryOperator::DecOne, UnaryOperator::MulTwo, UnaryOperator::DivTwo]
+        );
+        // Lowered directly, with no literal operand
+        assert!(!functions[0].blocks[0]
+            .instructions
+            .iter()
+            .any(|inst| matches!(inst, SSAInstruction::LoadInt { .. })));
+    }
+
+    #[test]
+    fn test_convert_string_words() {
+        let program = parse_program(": greet s\" hi\" type ; : len ( a -- a n ) count ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        let greet = &functions[0].blocks[0].instructions;
+        assert!(matches!(greet[0], SSAInstruction::LoadString { .. }));
+        assert!(matches!(&greet[1], SSAInstruction::Call { name, args, .. } if name == "type" && args.len() == 2));
+
+        // COUNT reads the length byte and steps past it
+        let len = &functions[1].blocks[0].instructions;
+        assert!(len.iter().any(|inst| matches!(inst, SSAInstruction::Load { ty: StackType::Char, .. })));
+        assert!(len
+            .iter()
+            .any(|inst| matches!(inst, SSAInstruction::UnaryOp { op: UnaryOperator::IncOne, .. })));
+    }
+
+    #[test]
+    fn test_if_without_else_has_no_dangling_block() {
+        let program = parse_program(": abs' dup 0 < if negate then ;").unwrap();
+        let func = &convert_to_ssa(&program).unwrap()[0];
+
+        // Entry, THEN, the empty ELSE jumping to the merge, and merge blocks only
+        assert_eq!(func.blocks.len(), 4);
+        for block in &func.blocks[1..] {
+            assert!(!block.predecessors.is_empty(), "{} has no predecessors", block.id);
+            for inst in &block.instructions {
+                if let SSAInstruction::Phi { incoming, .. } = inst {
+                    assert!(incoming.iter().all(|(pred, _)| block.predecessors.contains(pred)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_block_terminated() {
+        let program = parse_program(
+            ": last-if ( n -- n ) dup 0 < if drop 0 then ; \
+             : empty-then ( n -- n ) dup if then ; \
+             : empty-else ( n -- n ) dup if 1 + else then ; \
+             : nested ( n -- n ) dup 0 < if dup -10 < if drop -10 then else begin 1 - dup 5 < until then ; \
+             : loops ( n -- n ) begin dup 0 > while 1 - repeat 0 swap 0 do 1 + loop ;",
+        )
+        .unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        for function in &functions {
+            for block in &function.blocks {
+                assert_eq!(
+                    block.instructions.iter().position(SSAInstruction::is_terminator),
+                    Some(block.instructions.len() - 1),
+                    "{} {}",
+                    function.name,
+                    block.id
+                );
+            }
+            function.verify().unwrap();
+        }
+
+        // Every edge into a merge block with Phi nodes is a jump
+        let last_if = &functions[0];
+        let merge = last_if.blocks.iter()
+            .find(|block| matches!(block.instructions[0], SSAInstruction::Phi { .. }))
+            .unwrap();
+        for pred in &merge.predecessors {
+            let block = last_if.blocks.iter().find(|block| block.id == *pred).unwrap();
+            assert!(matches!(block.instructions.last(), Some(SSAInstruction::Jump { .. })));
+        }
+
+        // Dropping on one path only leaves unbalanced branches, which is
+        // reported rather than turned into a malformed function
+        let program = parse_program(": f dup 0 < if drop then ;").unwrap();
+        assert!(matches!(convert_to_ssa(&program), Err(ForthError::StackMismatch { .. })));
+    }
+
+    #[test]
+    fn test_each_function_numbered_from_zero() {
+        let program = parse_program(
+            ": clamp ( n -- n ) dup 0 < if drop 0 then ; \
+             : count ( n -- n ) 0 swap 0 do 1 + loop ; \
+             : pick3 ( a b c -- a ) drop drop ;",
+        )
+        .unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+
+        for function in &functions {
+            assert_eq!(function.entry_block, BlockId(0), "{}", function.name);
+            assert_eq!(function.blocks[0].id, BlockId(0), "{}", function.name);
+            assert_eq!(
+                function.parameters,
+                (0..function.parameters.len()).map(Register).collect::<Vec<_>>()
+            );
+
+            function.verify().unwrap();
+        }
+        assert_eq!(functions[2].parameters.len(), 3);
+    }
+
+    #[test]
+    fn test_main_function_always_last() {
+        // Definitions only: :main still exists and returns 0
+        let program = parse_program(": sq ( n -- n ) dup * ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+        let main = functions.last().unwrap();
+        assert_eq!(main.name, MAIN_FUNCTION);
+        assert!(main.parameters.is_empty());
+        assert!(main.blocks[0].instructions.iter().any(|inst| matches!(
+            inst,
+            SSAInstruction::LoadInt { value: 0, .. }
+        )));
+
+        // Top-level code interleaved with definitions is gathered into :main
+        let program = parse_program("3 : sq ( n -- n ) dup * ; sq").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+        assert_eq!(functions.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["sq", MAIN_FUNCTION]);
+        assert!(functions[1].blocks[0].instructions.iter().any(|inst| matches!(
+            inst,
+            SSAInstruction::Call { name, .. } if name == "sq"
+        )));
+    }
+
+    #[test]
+    fn test_qdup() {
+        let returned = |source: &str| {
+            let functions = convert_to_ssa(&parse_program(source).unwrap()).unwrap();
+            let main = functions.last().unwrap();
+            main.blocks
+                .iter()
+                .flat_map(|block| &block.instructions)
+                .find_map(|inst| match inst {
+                    SSAInstruction::Return { values } => Some(values.len()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(returned("0 ?dup"), 1);
+        assert_eq!(returned("5 ?dup"), 2);
+
+        // With IF, only the THEN branch sees the tested value
+        let program = parse_program(": f ( n -- n ) ?dup if 2 * else 7 then ;").unwrap();
+        let functions = convert_to_ssa(&program).unwrap();
+        crate::SSAValidator::new(&functions[0]).validate().unwrap();
+        assert!(functions[0].blocks.iter().flat_map(|block| &block.instructions).any(|inst| matches!(
+            inst,
+            SSAInstruction::Branch { condition: Register(0), .. }
+        )));
+
+        // The depth after a lone ?DUP of an unknown value is unknown
+        let program = parse_program(": g ( n -- ) ?dup drop ;").unwrap();
+        assert!(matches!(convert_to_ssa(&program), Err(ForthError::SSAConversionError { .. })));
+    }
+
+    #[test]
+    fn test_convert_maximally_nested_program() {
+        use crate::parser::DEFAULT_MAX_NESTING_DEPTH;
+
+        let depth = DEFAULT_MAX_NESTING_DEPTH;
+        let source = format!(": deep {} 1 {} ;", "0 if ".repeat(depth), "then ".repeat(depth));
+        let program = parse_program(&source).unwrap();
+        // Unbalanced: every IF without ELSE leaves the inner push on one path only
+        assert!(convert_to_ssa(&program).is_err());
+
+        let source = format!(": deep {} 1 {} ;", "begin ".repeat(depth), "0 until ".repeat(depth));
+        let program = parse_program(&source).unwrap();
+        assert!(convert_to_ssa(&program).is_ok());
+    }
+
+    #[test]
+    fn test_remove_unreachable_blocks() {
+        let mut func = SSAFunction::new("f".to_string(), 0);
+        func.blocks[0].instructions.push(SSAInstruction::Jump { target: BlockId(1) });
+
+        // A loop reached from the entry, then an unreachable cycle feeding a phi
+        let mut body = BasicBlock::new(BlockId(1));
+        body.instructions.push(SSAInstruction::Branch {
+            condition: Register(0),
+            true_block: BlockId(1),
+            false_block: BlockId(2),
+        });
+        let mut exit = BasicBlock::new(BlockId(2));
+        exit.instructions.push(SSAInstruction::Phi {
+            dest: Register(2),
+            incoming: vec![(BlockId(1), Register(0)), (BlockId(3), Register(1))],
+        });
+        exit.instructions.push(SSAInstruction::Return { values: smallvec::smallvec![Register(2)] });
+        let mut orphan = BasicBlock::new(BlockId(3));
+        orphan.instructions.push(SSAInstruction::Jump { target: BlockId(4) });
+        let mut orphan_loop = BasicBlock::new(BlockId(4));
+        orphan_loop.instructions.push(SSAInstruction::Jump { target: BlockId(3) });
+        func.blocks.extend([body, exit, orphan, orphan_loop]);
+
+        assert_eq!(func.remove_unreachable_blocks(), 2);
+
+        let ids: Vec<BlockId> = func.blocks.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![BlockId(0), BlockId(1), BlockId(2)]);
+        // The back edge keeps the loop body alive and counts as a predecessor
+        assert_eq!(func.blocks[1].predecessors, vec![BlockId(0), BlockId(1)]);
+        assert_eq!(func.blocks[2].predecessors, vec![BlockId(1)]);
+        assert!(matches!(
+            &func.blocks[2].instructions[0],
+            SSAInstruction::Phi { incoming, .. } if incoming == &vec![(BlockId(1), Register(0))]
+        ));
+    }
+
+    #[test]
+    fn test_undefined_word_is_reported() {
+        let program = parse_program(": foo\n  bar ;").unwrap();
+        let err = convert_to_ssa(&program).unwrap_err();
+        assert_eq!(
+            err,
+            ForthError::UndefinedWord { word: "bar".to_string(), location: Some(SourceLocation { line: 2, column: 3 }) }
+        );
+        assert_eq!(err.span(), Some(SourceLocation { line: 2, column: 3 }));
+        assert_eq!(err.to_string(), "Undefined word: bar at line 2");
+
+        // Forward references, mutual recursion and constants all resolve
+        let program = parse_program(
+            "10 constant ten : a ( n -- n ) dup if 1 - b then ; : b ( n -- n ) a ; : c ten d ; : d 1 + ; c",
+        )
+        .unwrap();
+        assert!(convert_to_ssa(&program).is_ok());
+    }
 }
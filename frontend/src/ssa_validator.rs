@@ -8,6 +8,10 @@
 //! - Use-before-def: all registers defined before use
 //! - Block connectivity: no unreachable blocks
 //! - Type consistency: stack depth matches at merge points
+//!
+//! `verify` adds the structural checks code generation relies on: every
+//! block ends in a terminator, every branch target exists, and every operand
+//! is defined on all paths to its use.
 
 use crate::error::{ForthError, Result};
 use crate::ssa::{SSAFunction, SSAInstruction, Register, BlockId};
@@ -127,6 +131,175 @@ impl<'a> SSAValidator<'a> {
         Ok(())
     }
 
+    /// Run the structural checks code generation relies on, then `validate`
+    ///
+    /// Errors name the function and the offending block and register.
+    pub fn verify(&mut self) -> Result<()> {
+        self.check_terminators()
+            .and_then(|_| self.check_branch_targets())
+            .and_then(|_| self.check_entry_block())
+            .and_then(|_| self.validate())
+            .and_then(|_| self.check_block_order())
+            .and_then(|_| self.check_phi_operands())
+            .map_err(|e| match e {
+                ForthError::SSAConversionError { message } => ForthError::SSAConversionError {
+                    message: format!("{} (in '{}')", message, self.function.name),
+                },
+                other => other,
+            })
+    }
+
+    /// Every block ends in exactly one branch, jump or return
+    fn check_terminators(&self) -> Result<()> {
+        for block in &self.function.blocks {
+            let Some(position) = block.instructions.iter().position(SSAInstruction::is_terminator) else {
+                return Err(ForthError::SSAConversionError {
+                    message: format!("Block {} does not end in a branch, jump or return", block.id),
+                });
+            };
+            if position + 1 != block.instructions.len() {
+                return Err(ForthError::SSAConversionError {
+                    message: format!(
+                        "Block {} has instructions after its terminator at instruction {}",
+                        block.id, position
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Block ids are unique and every branch or jump targets one of them
+    fn check_branch_targets(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for block in &self.function.blocks {
+            if !ids.insert(block.id) {
+                return Err(ForthError::SSAConversionError {
+                    message: format!("Block {} is defined more than once", block.id),
+                });
+            }
+        }
+
+        for block in &self.function.blocks {
+            for target in SSAFunction::successors(block) {
+                if !ids.contains(&target) {
+                    return Err(ForthError::SSAConversionError {
+                        message: format!("Block {} branches to nonexistent block {}", block.id, target),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The entry block exists, receives the parameters and nothing branches back to it
+    fn check_entry_block(&self) -> Result<()> {
+        let entry = self.function.entry_block;
+        let Some(entry_block) = self.function.blocks.iter().find(|b| b.id == entry) else {
+            return Err(ForthError::SSAConversionError {
+                message: format!("Entry block {} does not exist", entry),
+            });
+        };
+
+        if let Some(source) = self.function.blocks.iter()
+            .find(|block| SSAFunction::successors(block).contains(&entry))
+        {
+            return Err(ForthError::SSAConversionError {
+                message: format!("Entry block {} is the target of a branch in block {}", entry, source.id),
+            });
+        }
+
+        if entry_block.instructions.iter().any(|inst| matches!(inst, SSAInstruction::Phi { .. })) {
+            return Err(ForthError::SSAConversionError {
+                message: format!("Entry block {} contains a Phi node", entry),
+            });
+        }
+
+        for block in &self.function.blocks {
+            for inst in &block.instructions {
+                if let Some(param) = destination_registers(inst).into_iter()
+                    .find(|reg| self.function.parameters.contains(reg))
+                {
+                    return Err(ForthError::SSAConversionError {
+                        message: format!("Parameter {} is reassigned in block {}", param, block.id),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Within a block, a register is defined before the instructions that use it
+    ///
+    /// `check_dominance` covers uses in other blocks.
+    fn check_block_order(&self) -> Result<()> {
+        for block in &self.function.blocks {
+            let defined_later: HashSet<Register> = block.instructions.iter()
+                .flat_map(destination_registers)
+                .collect();
+            let mut defined = HashSet::new();
+
+            for (index, inst) in block.instructions.iter().enumerate() {
+                if !matches!(inst, SSAInstruction::Phi { .. }) {
+                    if let Some(reg) = used_registers(inst).into_iter()
+                        .find(|reg| defined_later.contains(reg) && !defined.contains(reg))
+                    {
+                        return Err(ForthError::SSAConversionError {
+                            message: format!(
+                                "Register {} used in block {} at instruction {} before its definition",
+                                reg, block.id, index
+                            ),
+                        });
+                    }
+                }
+                defined.extend(destination_registers(inst));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Each Phi operand is defined on every path reaching its incoming edge
+    fn check_phi_operands(&self) -> Result<()> {
+        for block in &self.function.blocks {
+            for inst in &block.instructions {
+                let SSAInstruction::Phi { dest, incoming } = inst else {
+                    continue;
+                };
+                for &(pred, reg) in incoming {
+                    if self.function.parameters.contains(&reg) {
+                        continue;
+                    }
+                    match self.find_definition_block(reg) {
+                        Some(def_block) if self.is_dominated_by(pred, def_block) => {}
+                        Some(def_block) => {
+                            return Err(ForthError::SSAConversionError {
+                                message: format!(
+                                    "Phi node for {} in block {} takes {} from {}, but {} is defined in {} \
+                                     which does not dominate {}",
+                                    dest, block.id, reg, pred, reg, def_block, pred
+                                ),
+                            });
+                        }
+                        None => {
+                            return Err(ForthError::SSAConversionError {
+                                message: format!(
+                                    "Phi node for {} in block {} takes {} from {}, but {} is never defined",
+                                    dest, block.id, reg, pred, reg
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Build control flow graph by analyzing branches and jumps
     fn build_cfg(&mut self) {
         // Initialize empty successor and predecessor lists
@@ -168,7 +341,7 @@ impl<'a> SSAValidator<'a> {
 
             for inst in &block.instructions {
                 // Collect all destination registers from this instruction
-                let dests = self.get_destination_registers(inst);
+                let dests = destination_registers(inst);
 
                 for dest in dests {
                     // Check if this register was already defined
@@ -206,7 +379,7 @@ impl<'a> SSAValidator<'a> {
 
             for inst in &block.instructions {
                 // First, check all uses in this instruction
-                let uses = self.get_used_registers(inst);
+                let uses = used_registers(inst);
 
                 for used_reg in &uses {
                     // Skip Phi nodes - they're special (values come from predecessors)
@@ -223,7 +396,7 @@ impl<'a> SSAValidator<'a> {
                 }
 
                 // Then, add any definitions from this instruction
-                let defs = self.get_destination_registers(inst);
+                let defs = destination_registers(inst);
                 for def in defs {
                     defined_in_block.insert(def);
                 }
@@ -371,7 +544,7 @@ impl<'a> SSAValidator<'a> {
                     continue;
                 }
 
-                let uses = self.get_used_registers(inst);
+                let uses = used_registers(inst);
 
                 for used_reg in uses {
                     // Find where this register is defined
@@ -521,70 +694,75 @@ impl<'a> SSAValidator<'a> {
             false
         }
     }
+}
 
-    /// Helper: Extract all destination registers from an instruction
-    fn get_destination_registers(&self, inst: &SSAInstruction) -> Vec<Register> {
-        match inst {
-            SSAInstruction::LoadInt { dest, .. } => vec![*dest],
-            SSAInstruction::LoadFloat { dest, .. } => vec![*dest],
-            SSAInstruction::LoadString { dest_addr, dest_len, .. } => vec![*dest_addr, *dest_len],
-            SSAInstruction::BinaryOp { dest, .. } => vec![*dest],
-            SSAInstruction::UnaryOp { dest, .. } => vec![*dest],
-            SSAInstruction::Call { dest, .. } => dest.to_vec(),
-            SSAInstruction::Phi { dest, .. } => vec![*dest],
-            SSAInstruction::Load { dest, .. } => vec![*dest],
-            SSAInstruction::FFICall { dest, .. } => dest.to_vec(),
-            SSAInstruction::FileOpen { dest_fileid, dest_ior, .. } => vec![*dest_fileid, *dest_ior],
-            SSAInstruction::FileRead { dest_bytes, dest_ior, .. } => vec![*dest_bytes, *dest_ior],
-            SSAInstruction::FileWrite { dest_ior, .. } => vec![*dest_ior],
-            SSAInstruction::FileClose { dest_ior, .. } => vec![*dest_ior],
-            SSAInstruction::FileDelete { dest_ior, .. } => vec![*dest_ior],
-            SSAInstruction::FileCreate { dest_fileid, dest_ior, .. } => vec![*dest_fileid, *dest_ior],
-            SSAInstruction::SystemCall { dest, .. } => vec![*dest],
-            SSAInstruction::Branch { .. } => vec![],
-            SSAInstruction::Jump { .. } => vec![],
-            SSAInstruction::Return { .. } => vec![],
-            SSAInstruction::Store { .. } => vec![],
-        }
-    }
-
-    /// Helper: Extract all used registers from an instruction
-    fn get_used_registers(&self, inst: &SSAInstruction) -> Vec<Register> {
-        match inst {
-            SSAInstruction::LoadInt { .. } => vec![],
-            SSAInstruction::LoadFloat { .. } => vec![],
-            SSAInstruction::LoadString { .. } => vec![],
-            SSAInstruction::BinaryOp { left, right, .. } => vec![*left, *right],
-            SSAInstruction::UnaryOp { operand, .. } => vec![*operand],
-            SSAInstruction::Call { args, .. } => args.to_vec(),
-            SSAInstruction::Branch { condition, .. } => vec![*condition],
-            SSAInstruction::Jump { .. } => vec![],
-            SSAInstruction::Return { values } => values.to_vec(),
-            SSAInstruction::Phi { incoming, .. } => {
-                incoming.iter().map(|(_, reg)| *reg).collect()
-            }
-            SSAInstruction::Load { address, .. } => vec![*address],
-            SSAInstruction::Store { address, value, .. } => vec![*address, *value],
-            SSAInstruction::FFICall { args, .. } => args.to_vec(),
-            SSAInstruction::FileOpen { path_addr, path_len, mode, .. } => {
-                vec![*path_addr, *path_len, *mode]
-            }
-            SSAInstruction::FileRead { buffer, count, fileid, .. } => {
-                vec![*buffer, *count, *fileid]
-            }
-            SSAInstruction::FileWrite { buffer, count, fileid, .. } => {
-                vec![*buffer, *count, *fileid]
-            }
-            SSAInstruction::FileClose { fileid, .. } => vec![*fileid],
-            SSAInstruction::FileDelete { path_addr, path_len, .. } => {
-                vec![*path_addr, *path_len]
-            }
-            SSAInstruction::FileCreate { path_addr, path_len, mode, .. } => {
-                vec![*path_addr, *path_len, *mode]
-            }
-            SSAInstruction::SystemCall { command_addr, command_len, .. } => {
-                vec![*command_addr, *command_len]
-            }
+/// Registers an instruction assigns
+pub fn destination_registers(inst: &SSAInstruction) -> Vec<Register> {
+    match inst {
+        SSAInstruction::LoadInt { dest, .. } => vec![*dest],
+        SSAInstruction::LoadFloat { dest, .. } => vec![*dest],
+        SSAInstruction::LoadString { dest_addr, dest_len, .. } => vec![*dest_addr, *dest_len],
+        SSAInstruction::BinaryOp { dest, .. } => vec![*dest],
+        SSAInstruction::UnaryOp { dest, .. } => vec![*dest],
+        SSAInstruction::Call { dest, .. } => dest.to_vec(),
+        SSAInstruction::Phi { dest, .. } => vec![*dest],
+        SSAInstruction::Load { dest, .. } => vec![*dest],
+        SSAInstruction::VariableAddr { dest, .. } => vec![*dest],
+        SSAInstruction::ExecutionToken { dest, .. } => vec![*dest],
+        SSAInstruction::FFICall { dest, .. } => dest.to_vec(),
+        SSAInstruction::FileOpen { dest_fileid, dest_ior, .. } => vec![*dest_fileid, *dest_ior],
+        SSAInstruction::FileRead { dest_bytes, dest_ior, .. } => vec![*dest_bytes, *dest_ior],
+        SSAInstruction::FileWrite { dest_ior, .. } => vec![*dest_ior],
+        SSAInstruction::FileClose { dest_ior, .. } => vec![*dest_ior],
+        SSAInstruction::FileDelete { dest_ior, .. } => vec![*dest_ior],
+        SSAInstruction::FileStatus { dest_status, dest_ior, .. } => vec![*dest_status, *dest_ior],
+        SSAInstruction::FileCreate { dest_fileid, dest_ior, .. } => vec![*dest_fileid, *dest_ior],
+        SSAInstruction::SystemCall { dest, .. } => vec![*dest],
+        SSAInstruction::Branch { .. } => vec![],
+        SSAInstruction::Jump { .. } => vec![],
+        SSAInstruction::Return { .. } => vec![],
+        SSAInstruction::Store { .. } => vec![],
+    }
+}
+
+/// Registers an instruction reads
+pub fn used_registers(inst: &SSAInstruction) -> Vec<Register> {
+    match inst {
+        SSAInstruction::LoadInt { .. } => vec![],
+        SSAInstruction::LoadFloat { .. } => vec![],
+        SSAInstruction::LoadString { .. } => vec![],
+        SSAInstruction::BinaryOp { left, right, .. } => vec![*left, *right],
+        SSAInstruction::UnaryOp { operand, .. } => vec![*operand],
+        SSAInstruction::Call { args, .. } => args.to_vec(),
+        SSAInstruction::Branch { condition, .. } => vec![*condition],
+        SSAInstruction::Jump { .. } => vec![],
+        SSAInstruction::Return { values } => values.to_vec(),
+        SSAInstruction::Phi { incoming, .. } => {
+            incoming.iter().map(|(_, reg)| *reg).collect()
+        }
+        SSAInstruction::Load { address, .. } => vec![*address],
+        SSAInstruction::VariableAddr { .. } | SSAInstruction::ExecutionToken { .. } => vec![],
+        SSAInstruction::Store { address, value, .. } => vec![*address, *value],
+        SSAInstruction::FFICall { args, .. } => args.to_vec(),
+        SSAInstruction::FileOpen { path_addr, path_len, mode, .. } => {
+            vec![*path_addr, *path_len, *mode]
+        }
+        SSAInstruction::FileRead { buffer, count, fileid, .. } => {
+            vec![*buffer, *count, *fileid]
+        }
+        SSAInstruction::FileWrite { buffer, count, fileid, .. } => {
+            vec![*buffer, *count, *fileid]
+        }
+        SSAInstruction::FileClose { fileid, .. } => vec![*fileid],
+        SSAInstruction::FileDelete { path_addr, path_len, .. }
+        | SSAInstruction::FileStatus { path_addr, path_len, .. } => {
+            vec![*path_addr, *path_len]
+        }
+        SSAInstruction::FileCreate { path_addr, path_len, mode, .. } => {
+            vec![*path_addr, *path_len, *mode]
+        }
+        SSAInstruction::SystemCall { command_addr, command_len, .. } => {
+            vec![*command_addr, *command_len]
         }
     }
 }
@@ -659,4 +837,158 @@ mod tests {
         let mut validator = SSAValidator::new(&func);
         assert!(validator.validate().is_err());
     }
+
+    /// Builds a function from `(block, instructions)` pairs, entry block first
+    fn function(params: usize, blocks: Vec<(usize, Vec<SSAInstruction>)>) -> SSAFunction {
+        let mut func = SSAFunction::new("f".to_string(), params);
+        func.blocks = blocks
+            .into_iter()
+            .map(|(id, instructions)| BasicBlock { id: BlockId(id), instructions, predecessors: Vec::new() })
+            .collect();
+        func.compute_predecessors();
+        func
+    }
+
+    fn ret(reg: usize) -> SSAInstruction {
+        SSAInstruction::Return { values: smallvec::smallvec![Register(reg)] }
+    }
+
+    fn jump(target: usize) -> SSAInstruction {
+        SSAInstruction::Jump { target: BlockId(target) }
+    }
+
+    fn branch(condition: usize, true_block: usize, false_block: usize) -> SSAInstruction {
+        SSAInstruction::Branch {
+            condition: Register(condition),
+            true_block: BlockId(true_block),
+            false_block: BlockId(false_block),
+        }
+    }
+
+    fn load(dest: usize, value: i64) -> SSAInstruction {
+        SSAInstruction::LoadInt { dest: Register(dest), value }
+    }
+
+    fn phi(dest: usize, incoming: &[(usize, usize)]) -> SSAInstruction {
+        SSAInstruction::Phi {
+            dest: Register(dest),
+            incoming: incoming.iter().map(|&(block, reg)| (BlockId(block), Register(reg))).collect(),
+        }
+    }
+
+    fn verify_error(func: &SSAFunction) -> String {
+        func.verify().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_verify_well_formed_diamond() {
+        // bb0: branch %0 -> bb1, bb2; both jump to bb3 which merges with a Phi
+        let func = function(1, vec![
+            (0, vec![branch(0, 1, 2)]),
+            (1, vec![load(1, 1), jump(3)]),
+            (2, vec![load(2, 2), jump(3)]),
+            (3, vec![phi(3, &[(1, 1), (2, 2)]), ret(3)]),
+        ]);
+        func.verify().unwrap();
+    }
+
+    #[test]
+    fn test_verify_missing_terminator() {
+        let func = function(0, vec![(0, vec![load(0, 1), jump(1)]), (1, vec![load(1, 2)])]);
+        let message = verify_error(&func);
+        assert!(message.contains("Block bb1 does not end in a branch, jump or return"), "{}", message);
+        assert!(message.contains("'f'"), "{}", message);
+
+        let func = function(0, vec![(0, vec![])]);
+        assert!(verify_error(&func).contains("Block bb0 does not end"));
+    }
+
+    #[test]
+    fn test_verify_instruction_after_terminator() {
+        let func = function(0, vec![(0, vec![load(0, 1), ret(0), load(1, 2)])]);
+        assert!(verify_error(&func).contains("instructions after its terminator at instruction 1"));
+    }
+
+    #[test]
+    fn test_verify_branch_to_nonexistent_block() {
+        let func = function(1, vec![(0, vec![branch(0, 1, 7)]), (1, vec![ret(0)])]);
+        assert!(verify_error(&func).contains("Block bb0 branches to nonexistent block bb7"));
+    }
+
+    #[test]
+    fn test_verify_use_before_def_in_block() {
+        let func = function(0, vec![(0, vec![
+            SSAInstruction::UnaryOp { dest: Register(1), op: UnaryOperator::Negate, operand: Register(0) },
+            load(0, 5),
+            ret(1),
+        ])]);
+        assert!(verify_error(&func).contains("Register %0 used in block bb0 at instruction 0 before its definition"));
+    }
+
+    #[test]
+    fn test_verify_use_not_defined_on_all_paths() {
+        // %1 is only defined on the bb1 path but used after the merge
+        let func = function(1, vec![
+            (0, vec![branch(0, 1, 2)]),
+            (1, vec![load(1, 1), jump(2)]),
+            (2, vec![ret(1)]),
+        ]);
+        assert!(verify_error(&func).contains("Register %1 used in block bb2 but defined in non-dominating block bb1"));
+    }
+
+    #[test]
+    fn test_verify_phi_predecessors() {
+        // The Phi names bb0, which does not jump to bb3
+        let func = function(1, vec![
+            (0, vec![branch(0, 1, 2)]),
+            (1, vec![load(1, 1), jump(3)]),
+            (2, vec![load(2, 2), jump(3)]),
+            (3, vec![phi(3, &[(0, 1), (2, 2)]), ret(3)]),
+        ]);
+        assert!(verify_error(&func).contains("missing incoming value from predecessor bb1"));
+
+        // The operand from bb2 is defined on the other branch
+        let func = function(1, vec![
+            (0, vec![branch(0, 1, 2)]),
+            (1, vec![load(1, 1), jump(3)]),
+            (2, vec![load(2, 2), jump(3)]),
+            (3, vec![phi(3, &[(1, 1), (2, 1)]), ret(3)]),
+        ]);
+        let message = verify_error(&func);
+        assert!(message.contains("takes %1 from bb2, but %1 is defined in bb1"), "{}", message);
+
+        // Operands may come straight from the parameters
+        let func = function(1, vec![
+            (0, vec![branch(0, 1, 2)]),
+            (1, vec![load(1, 1), jump(2)]),
+            (2, vec![phi(2, &[(0, 0), (1, 1)]), ret(2)]),
+        ]);
+        func.verify().unwrap();
+    }
+
+    #[test]
+    fn test_verify_unreachable_block() {
+        let func = function(0, vec![(0, vec![load(0, 1), ret(0)]), (1, vec![load(1, 2), ret(1)])]);
+        assert!(verify_error(&func).contains("Unreachable block bb1"));
+    }
+
+    #[test]
+    fn test_verify_entry_block() {
+        // Parameters are defined on entry
+        let func = function(2, vec![(0, vec![
+            SSAInstruction::BinaryOp { dest: Register(2), op: BinaryOperator::Add, left: Register(0), right: Register(1) },
+            ret(2),
+        ])]);
+        func.verify().unwrap();
+
+        let func = function(1, vec![(0, vec![load(0, 1), ret(0)])]);
+        assert!(verify_error(&func).contains("Parameter %0 is reassigned in block bb0"));
+
+        let func = function(1, vec![(0, vec![jump(1)]), (1, vec![branch(0, 0, 2)]), (2, vec![ret(0)])]);
+        assert!(verify_error(&func).contains("Entry block bb0 is the target of a branch in block bb1"));
+
+        let mut func = function(0, vec![(0, vec![load(0, 1), ret(0)])]);
+        func.entry_block = BlockId(4);
+        assert!(verify_error(&func).contains("Entry block bb4 does not exist"));
+    }
 }
@@ -2,11 +2,21 @@
 //!
 //! This module infers stack effects for Forth words based on their definitions.
 //! It tracks stack depth and computes the net effect of each operation.
+//!
+//! [`StackEffectInference::trace_definition`] also models the stack contents
+//! after every word of a body, for diagnostics that show where the body
+//! stops matching its declared effect.
 
 use crate::ast::*;
 use crate::error::Result;
+use crate::execute::{resolve_execute, ticked_words};
 use rustc_hash::FxHashMap;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Steps shown by a rendered [`StackTrace`] before it is cut around the offending word
+pub const MAX_TRACE_STEPS: usize = 16;
 
 /// Stack effect inference engine
 pub struct StackEffectInference {
@@ -135,6 +145,20 @@ impl StackEffectInference {
             "<>".to_string(),
             StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Bool]),
         );
+        builtins.insert(
+            "u<".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Bool]),
+        );
+        builtins.insert(
+            "u>".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Bool]),
+        );
+        for word in ["0=", "0<", "0>"] {
+            builtins.insert(
+                word.to_string(),
+                StackEffect::new(vec![StackType::Int], vec![StackType::Bool]),
+            );
+        }
 
         // Logical operations
         builtins.insert(
@@ -145,6 +169,10 @@ impl StackEffectInference {
             "or".to_string(),
             StackEffect::new(vec![StackType::Bool, StackType::Bool], vec![StackType::Bool]),
         );
+        builtins.insert(
+            "xor".to_string(),
+            StackEffect::new(vec![StackType::Bool, StackType::Bool], vec![StackType::Bool]),
+        );
         builtins.insert(
             "not".to_string(),
             StackEffect::new(vec![StackType::Bool], vec![StackType::Bool]),
@@ -153,12 +181,32 @@ impl StackEffectInference {
             "invert".to_string(),
             StackEffect::new(vec![StackType::Int], vec![StackType::Int]),
         );
+        builtins.insert(
+            "lshift".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Int]),
+        );
+        builtins.insert(
+            "rshift".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Int]),
+        );
+        builtins.insert(
+            "arshift".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Int]),
+        );
 
         // I/O operations
         builtins.insert(
             ".".to_string(),
             StackEffect::new(vec![StackType::Int], vec![]),
         );
+        builtins.insert(
+            "u.".to_string(),
+            StackEffect::new(vec![StackType::Int], vec![]),
+        );
+        builtins.insert(
+            ".r".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![]),
+        );
         builtins.insert(
             "emit".to_string(),
             StackEffect::new(vec![StackType::Char], vec![]),
@@ -167,6 +215,14 @@ impl StackEffectInference {
             "cr".to_string(),
             StackEffect::new(vec![], vec![]),
         );
+        builtins.insert(
+            "type".to_string(),
+            StackEffect::new(vec![StackType::Addr, StackType::Int], vec![]),
+        );
+        builtins.insert(
+            "count".to_string(),
+            StackEffect::new(vec![StackType::Addr], vec![StackType::Addr, StackType::Int]),
+        );
 
         // Memory operations
         builtins.insert(
@@ -177,6 +233,18 @@ impl StackEffectInference {
             "!".to_string(),
             StackEffect::new(vec![StackType::Int, StackType::Addr], vec![]),
         );
+        builtins.insert(
+            ",".to_string(),
+            StackEffect::new(vec![StackType::Int], vec![]),
+        );
+        builtins.insert(
+            "allot".to_string(),
+            StackEffect::new(vec![StackType::Int], vec![]),
+        );
+        builtins.insert(
+            "here".to_string(),
+            StackEffect::new(vec![], vec![StackType::Addr]),
+        );
         builtins.insert(
             "c@".to_string(),
             StackEffect::new(vec![StackType::Addr], vec![StackType::Char]),
@@ -195,6 +263,12 @@ impl StackEffectInference {
             "abs".to_string(),
             StackEffect::new(vec![StackType::Int], vec![StackType::Int]),
         );
+        for word in ["1+", "1-", "2*", "2/"] {
+            builtins.insert(
+                word.to_string(),
+                StackEffect::new(vec![StackType::Int], vec![StackType::Int]),
+            );
+        }
         builtins.insert(
             "min".to_string(),
             StackEffect::new(vec![StackType::Int, StackType::Int], vec![StackType::Int]),
@@ -215,7 +289,7 @@ impl StackEffectInference {
         let mut stack_depth = 0;
         let mut inputs_needed = 0;
 
-        for word in words {
+        for word in expand_qdup_if(words).iter() {
             let effect = self.infer_word_effect(word)?;
 
             // Check if we have enough items on the stack
@@ -241,10 +315,14 @@ impl StackEffectInference {
     /// Infer stack effect for a single word
     fn infer_word_effect(&self, word: &Word) -> Result<StackEffect> {
         match word {
-            Word::IntLiteral(_) | Word::FloatLiteral(_) | Word::StringLiteral(_) => {
+            Word::IntLiteral(_) | Word::FloatLiteral(_) => {
                 // Literals push one value
                 Ok(StackEffect::new(vec![], vec![StackType::Unknown]))
             }
+            Word::StringLiteral(_) => {
+                // String literals push their address and length
+                Ok(StackEffect::new(vec![], vec![StackType::Addr, StackType::Int]))
+            }
             Word::WordRef { name, .. } => {
                 // Look up word effect
                 if let Some(effect) = self.builtins.get(name) {
@@ -312,6 +390,20 @@ impl StackEffectInference {
                 // Variable/constant push address or value
                 Ok(StackEffect::new(vec![], vec![StackType::Addr]))
             }
+            Word::Value { .. } => {
+                // The initial value is part of the declaration
+                Ok(StackEffect::new(vec![], vec![]))
+            }
+            Word::To { .. } => Ok(StackEffect::new(vec![StackType::Int], vec![])),
+            Word::Tick { .. } => Ok(StackEffect::new(vec![], vec![StackType::Int])),
+            Word::Create { .. } | Word::Does { .. } => {
+                // DOES> code only runs when an instance is used
+                Ok(StackEffect::new(vec![], vec![]))
+            }
+            Word::Instantiate { defining_word, .. } => {
+                // Runs the defining word's code up to DOES>
+                Ok(self.user_words.get(defining_word).cloned().unwrap_or_else(|| StackEffect::new(vec![], vec![])))
+            }
             Word::Comment(_) => {
                 // Comments have no effect
                 Ok(StackEffect::new(vec![], vec![]))
@@ -319,6 +411,174 @@ impl StackEffectInference {
         }
     }
 
+    /// Exact stack effect of an unannotated definition, if it can be determined
+    ///
+    /// Unlike [`infer_sequence`](Self::infer_sequence), which assumes unknown
+    /// words have no effect, this gives up on anything it cannot account for:
+    /// calls to unknown or recursive words, branches that leave different
+    /// depths, loops that change the depth, locals and declarations. Every
+    /// slot is typed as a cell, as if written `( n -- n )`.
+    pub fn infer_definition(&self, def: &Definition) -> Option<StackEffect> {
+        if def.locals.is_some() {
+            return None;
+        }
+
+        let (inputs, outputs) = self.exact_sequence(&def.body, &def.name)?;
+        Some(StackEffect::new(vec![StackType::Int; inputs], vec![StackType::Int; outputs]))
+    }
+
+    /// Items consumed and produced by `words`, inside the definition `name`
+    fn exact_sequence(&self, words: &[Word], name: &str) -> Option<(usize, usize)> {
+        expand_qdup_if(words)
+            .iter()
+            .try_fold((0, 0), |effect, word| Some(compose(effect, self.exact_word(word, name)?)))
+    }
+
+    fn exact_word(&self, word: &Word, name: &str) -> Option<(usize, usize)> {
+        match word {
+            Word::IntLiteral(_) | Word::FloatLiteral(_) | Word::Tick { .. } => Some((0, 1)),
+            Word::StringLiteral(_) => Some((0, 2)),
+            Word::Comment(_) => Some((0, 0)),
+            Word::WordRef { name: callee, .. } if callee == name => None,
+            Word::WordRef { name: callee, .. } => self
+                .get_effect(callee)
+                .map(|effect| (effect.inputs.len(), effect.outputs.len())),
+            Word::If { then_branch, else_branch } => {
+                let then_effect = self.exact_sequence(then_branch, name)?;
+                let else_effect = match else_branch {
+                    Some(else_branch) => self.exact_sequence(else_branch, name)?,
+                    None => (0, 0),
+                };
+                // The flag is on top of whatever the branches consume
+                let (consumed, produced) = join(then_effect, else_effect)?;
+                Some((consumed + 1, produced))
+            }
+            Word::BeginUntil { body } => {
+                let (consumed, produced) = self.exact_sequence(body, name)?;
+                balanced((consumed, produced.checked_sub(1)?))
+            }
+            Word::BeginWhileRepeat { condition, body } => {
+                let (consumed, produced) = self.exact_sequence(condition, name)?;
+                let exit = (consumed, produced.checked_sub(1)?);
+                let iteration = balanced(compose(exit, self.exact_sequence(body, name)?))?;
+                // Leaving the loop runs the condition once more than the body
+                join(exit, compose(iteration, exit))
+            }
+            Word::DoLoop { body, .. } => {
+                let (consumed, produced) = balanced(self.exact_sequence(body, name)?)?;
+                Some((consumed + 2, produced))
+            }
+            Word::Variable { .. }
+            | Word::Constant { .. }
+            | Word::Value { .. }
+            | Word::To { .. }
+            | Word::Create { .. }
+            | Word::Does { .. }
+            | Word::Instantiate { .. } => None,
+        }
+    }
+
+    /// Model the stack through `def`, starting from its declared inputs
+    ///
+    /// Each step names the items on the stack after one word, bottom first.
+    /// Type variables are bound to the items they take, so `dup` on an `n`
+    /// leaves `n n`. An `IF` adds a step for each branch and a `then` step
+    /// that shows both resulting stacks when the branches disagree. Loops and
+    /// other compound words take one step, using their inferred effect.
+    pub fn trace_definition(&self, def: &Definition) -> Result<StackTrace> {
+        let declared = def.stack_effect.clone().unwrap_or_else(|| StackEffect::new(vec![], vec![]));
+        let entry: Vec<String> = declared.inputs.iter().map(ToString::to_string).collect();
+        let mut steps = Vec::new();
+        let stack = self.trace_sequence(&def.body, entry.clone(), 0, &mut steps)?;
+
+        let mut trace = StackTrace {
+            name: def.name.clone(),
+            declared,
+            entry,
+            steps,
+            offending: None,
+        };
+        trace.offending = trace.find_offending(&stack);
+        Ok(trace)
+    }
+
+    fn trace_sequence(
+        &self,
+        words: &[Word],
+        mut stack: Vec<String>,
+        indent: usize,
+        steps: &mut Vec<TraceStep>,
+    ) -> Result<Vec<String>> {
+        let mut words = words.iter().peekable();
+        while let Some(word) = words.next() {
+            stack = match (word, words.peek()) {
+                (Word::Comment(_), _) => continue,
+                (Word::WordRef { name, .. }, Some(Word::If { then_branch, else_branch }))
+                    if name == "?dup" =>
+                {
+                    words.next();
+                    // The THEN branch gets the tested value, the other path nothing
+                    let missing = match stack.last().cloned() {
+                        Some(top) => {
+                            stack.push(top);
+                            0
+                        }
+                        None => 1,
+                    };
+                    steps.push(TraceStep::new("?dup", indent, &stack, missing));
+                    stack.pop();
+                    let then_stack = stack.clone();
+                    stack.pop();
+                    self.trace_if(then_branch, else_branch.as_deref(), then_stack, stack, indent, steps)?
+                }
+                (Word::If { then_branch, else_branch }, _) => {
+                    let missing = usize::from(stack.pop().is_none());
+                    steps.push(TraceStep::new("if", indent, &stack, missing));
+                    self.trace_if(then_branch, else_branch.as_deref(), stack.clone(), stack, indent, steps)?
+                }
+                _ => {
+                    let effect = match word {
+                        Word::IntLiteral(_) => StackEffect::new(vec![], vec![StackType::Int]),
+                        Word::FloatLiteral(_) => StackEffect::new(vec![], vec![StackType::Float]),
+                        _ => self.infer_word_effect(word)?,
+                    };
+                    let missing = apply_traced(&effect, &mut stack);
+                    steps.push(TraceStep::new(&truncate_source(&word.to_source()), indent, &stack, missing));
+                    stack
+                }
+            };
+        }
+
+        Ok(stack)
+    }
+
+    /// Trace both branches of an `IF` and a `then` step joining them
+    fn trace_if(
+        &self,
+        then_branch: &[Word],
+        else_branch: Option<&[Word]>,
+        then_stack: Vec<String>,
+        else_stack: Vec<String>,
+        indent: usize,
+        steps: &mut Vec<TraceStep>,
+    ) -> Result<Vec<String>> {
+        let then_stack = self.trace_sequence(then_branch, then_stack, indent + 1, steps)?;
+        let else_stack = match else_branch {
+            Some(else_branch) => {
+                steps.push(TraceStep::new("else", indent, &else_stack, 0));
+                self.trace_sequence(else_branch, else_stack, indent + 1, steps)?
+            }
+            None => else_stack,
+        };
+
+        let mut step = TraceStep::new("then", indent, &then_stack, 0);
+        if else_stack.len() != then_stack.len() {
+            step.alternative = Some(else_stack);
+        }
+        steps.push(step);
+        Ok(then_stack)
+    }
+
     /// Add a user-defined word and infer its effect
     pub fn add_definition(&mut self, def: &Definition) -> Result<()> {
         let effect = if let Some(declared_effect) = &def.stack_effect {
@@ -333,6 +593,12 @@ impl StackEffectInference {
         Ok(())
     }
 
+    /// Use `effect` for EXECUTE of a token not known at compile time (see
+    /// [`execute_effect`])
+    pub fn set_execute_effect(&mut self, effect: StackEffect) {
+        self.builtins.insert("execute".to_string(), effect);
+    }
+
     /// Get the stack effect for a word
     pub fn get_effect(&self, name: &str) -> Option<&StackEffect> {
         self.builtins.get(name).or_else(|| self.user_words.get(name))
@@ -353,6 +619,311 @@ impl StackEffectInference {
     }
 }
 
+/// `words` with every `?dup if ... else ... then` written as the equivalent
+/// `dup if ... else drop ... then`, whose depth is the same on both paths
+///
+/// `?dup` on its own leaves one or two items depending on its input, so it
+/// has no fixed effect; only in front of `IF` does the depth come out even.
+fn expand_qdup_if(words: &[Word]) -> Cow<'_, [Word]> {
+    let is_qdup_if = |pair: &[Word]| {
+        matches!(pair, [Word::WordRef { name, .. }, Word::If { .. }] if name == "?dup")
+    };
+    if !words.windows(2).any(is_qdup_if) {
+        return Cow::Borrowed(words);
+    }
+
+    let mut expanded = Vec::with_capacity(words.len());
+    let mut words = words.iter().peekable();
+    while let Some(word) = words.next() {
+        match (word, words.peek()) {
+            (Word::WordRef { name, location }, Some(Word::If { then_branch, else_branch }))
+                if name == "?dup" =>
+            {
+                let word_ref = |name: &str| Word::WordRef {
+                    name: name.to_string(),
+                    location: location.clone(),
+                };
+                let mut else_words = vec![word_ref("drop")];
+                else_words.extend(else_branch.iter().flatten().cloned());
+                expanded.push(word_ref("dup"));
+                expanded.push(Word::If { then_branch: then_branch.clone(), else_branch: Some(else_words) });
+                words.next();
+            }
+            _ => expanded.push(word.clone()),
+        }
+    }
+    Cow::Owned(expanded)
+}
+
+/// Apply `effect` to a modeled stack, returning how many inputs were missing
+fn apply_traced(effect: &StackEffect, stack: &mut Vec<String>) -> usize {
+    let available = effect.inputs.len().min(stack.len());
+    let missing = effect.inputs.len() - available;
+    let taken = stack.split_off(stack.len() - available);
+
+    // Missing items are the deepest inputs
+    let mut bindings: HashMap<&TypeVar, String> = HashMap::new();
+    for (ty, item) in effect.inputs[missing..].iter().zip(taken) {
+        if let StackType::Var(var) = ty {
+            bindings.insert(var, item);
+        }
+    }
+
+    stack.extend(effect.outputs.iter().map(|ty| match ty {
+        StackType::Var(var) => bindings.get(var).cloned().unwrap_or_else(|| var.to_string()),
+        ty => ty.to_string(),
+    }));
+    missing
+}
+
+/// Source text of a word, shortened to fit a trace line
+fn truncate_source(source: &str) -> String {
+    const MAX_LEN: usize = 24;
+    if source.chars().count() <= MAX_LEN {
+        source.to_string()
+    } else {
+        format!("{}...", source.chars().take(MAX_LEN - 3).collect::<String>())
+    }
+}
+
+/// The modeled stack after one word of a [`StackTrace`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// Source text of the word (`if`, `else` and `then` for branches)
+    pub word: String,
+    /// Nesting inside `IF` branches
+    pub indent: usize,
+    /// Items on the stack afterwards, bottom first
+    pub stack: Vec<String>,
+    /// Inputs the word needed that were not on the stack
+    pub missing: usize,
+    /// Stack left by the `ELSE` branch, on a `then` step whose branches differ
+    pub alternative: Option<Vec<String>>,
+}
+
+impl TraceStep {
+    fn new(word: &str, indent: usize, stack: &[String], missing: usize) -> Self {
+        Self {
+            word: word.to_string(),
+            indent,
+            stack: stack.to_vec(),
+            missing,
+            alternative: None,
+        }
+    }
+}
+
+/// Per-word trace of the modeled stack through a definition, from
+/// [`StackEffectInference::trace_definition`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackTrace {
+    /// Name of the traced definition
+    pub name: String,
+    /// Declared effect the trace is checked against
+    pub declared: StackEffect,
+    /// Stack on entry: the declared inputs
+    pub entry: Vec<String>,
+    pub steps: Vec<TraceStep>,
+    /// Index into `steps` of the word where the body diverged from the declaration
+    pub offending: Option<usize>,
+}
+
+impl StackTrace {
+    /// The step where the body diverged from the declaration, if it did
+    pub fn offending_step(&self) -> Option<&TraceStep> {
+        self.offending.map(|index| &self.steps[index])
+    }
+
+    /// First underflow, else first pair of disagreeing branches, else the
+    /// word after which the depth never again matched the declared outputs
+    fn find_offending(&self, exit: &[String]) -> Option<usize> {
+        if let Some(index) = self.steps.iter().position(|step| step.missing > 0) {
+            return Some(index);
+        }
+        if let Some(index) = self.steps.iter().position(|step| step.alternative.is_some()) {
+            return Some(index);
+        }
+
+        let expected = self.declared.outputs.len();
+        if exit.len() == expected {
+            return None;
+        }
+        let top_level: Vec<usize> = (0..self.steps.len()).filter(|&i| self.steps[i].indent == 0).collect();
+        let on_track = top_level.iter().rposition(|&i| self.steps[i].stack.len() == expected);
+        match on_track {
+            Some(position) => top_level.get(position + 1).copied(),
+            None => top_level.first().copied(),
+        }
+    }
+}
+
+impl fmt::Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items = |stack: &[String]| {
+            if stack.is_empty() {
+                "(empty)".to_string()
+            } else {
+                stack.join(" ")
+            }
+        };
+        let width = self
+            .steps
+            .iter()
+            .map(|step| 2 * step.indent + step.word.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("entry".len());
+
+        // Long bodies show a window that ends shortly after the offending word
+        let (start, end) = if self.steps.len() <= MAX_TRACE_STEPS {
+            (0, self.steps.len())
+        } else {
+            let end = (self.offending.unwrap_or(self.steps.len() - 1) + 3).min(self.steps.len());
+            let end = end.max(MAX_TRACE_STEPS);
+            (end - MAX_TRACE_STEPS, end)
+        };
+
+        writeln!(f, ": {} {}", self.name, self.declared)?;
+        writeln!(f, "    {:width$}  {}", "entry", items(&self.entry), width = width)?;
+        if start > 0 {
+            writeln!(f, "    ... {} words", start)?;
+        }
+        for (index, step) in self.steps.iter().enumerate().take(end).skip(start) {
+            let marker = if self.offending == Some(index) { ">" } else { " " };
+            let word = format!("{}{}", "  ".repeat(step.indent), step.word);
+            let mut line = format!("  {} {:width$}  {}", marker, word, items(&step.stack), width = width);
+            if let Some(alternative) = &step.alternative {
+                line.push_str(&format!(" | {}  <- branches leave different stacks", items(alternative)));
+            }
+            if step.missing > 0 {
+                line.push_str(&format!("  <- needs {} more item(s)", step.missing));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        if end < self.steps.len() {
+            writeln!(f, "    ... {} words", self.steps.len() - end)?;
+        }
+        write!(
+            f,
+            "    {:width$}  expected {}",
+            ";",
+            items(&self.declared.outputs.iter().map(ToString::to_string).collect::<Vec<_>>()),
+            width = width
+        )
+    }
+}
+
+/// Effect of running `first` then `second`, as (consumed, produced) counts
+fn compose(first: (usize, usize), second: (usize, usize)) -> (usize, usize) {
+    let (consumed, produced) = first;
+    let (needed, pushed) = second;
+    (consumed + needed.saturating_sub(produced), produced.saturating_sub(needed) + pushed)
+}
+
+/// Common effect of two alternative paths, if they change the depth equally
+fn join(a: (usize, usize), b: (usize, usize)) -> Option<(usize, usize)> {
+    let consumed = a.0.max(b.0);
+    let produced = a.1 + (consumed - a.0);
+    (produced == b.1 + (consumed - b.0)).then_some((consumed, produced))
+}
+
+/// The effect itself, if it leaves the depth unchanged (a loop body)
+fn balanced(effect: (usize, usize)) -> Option<(usize, usize)> {
+    (effect.0 == effect.1).then_some(effect)
+}
+
+/// Stack effect of every definition, declared or exactly inferred (see
+/// [`StackEffectInference::infer_definition`]), in definition order
+///
+/// Definitions are inferred in order, so a word's effect is known to the
+/// words defined after it. `None` marks definitions whose effect is unknown.
+pub fn definition_effects(program: &Program) -> Vec<Option<StackEffect>> {
+    definition_effects_with(program, execute_effect(program))
+}
+
+/// [`definition_effects`], with `execute` as the effect of EXECUTE on a token
+/// not known at compile time
+pub fn definition_effects_with(program: &Program, execute: Option<StackEffect>) -> Vec<Option<StackEffect>> {
+    let mut inference = StackEffectInference::new();
+    if let Some(effect) = execute {
+        inference.set_execute_effect(effect);
+    }
+    effects_in_order(program, inference)
+}
+
+/// Effect of EXECUTE on a token not known at compile time, `( i*x xt -- j*x )`
+///
+/// The token may be that of any definition whose token is still taken once
+/// known tokens are resolved, so these must all share one effect
+/// `( i*x -- j*x )`. When resolution leaves no such token, EXECUTE can only
+/// trap, and the definitions ticked in the source decide; when none are,
+/// it is `( xt -- x )`. `None` when the effects disagree or one is unknown.
+pub fn execute_effect(program: &Program) -> Option<StackEffect> {
+    let resolved = resolve_execute(program);
+    let program = if ticked_definitions(&resolved).is_empty() { program } else { &resolved };
+    let ticked = ticked_definitions(program);
+    let effects = definition_effects_with(program, None);
+
+    let mut signatures = program
+        .definitions
+        .iter()
+        .zip(effects)
+        .filter(|(def, _)| ticked.contains(def.name.as_str()))
+        .map(|(_, effect)| effect.map(|effect| (effect.inputs.len(), effect.outputs.len())));
+    let (inputs, outputs) = match signatures.next() {
+        Some(signature) => signature?,
+        None => (0, 1),
+    };
+    if !signatures.all(|other| other == Some((inputs, outputs))) {
+        return None;
+    }
+
+    Some(StackEffect::new(vec![StackType::Int; inputs + 1], vec![StackType::Int; outputs]))
+}
+
+/// Names of the definitions whose token `program` takes
+fn ticked_definitions(program: &Program) -> HashSet<&str> {
+    let ticked: HashSet<&str> = program
+        .definitions
+        .iter()
+        .flat_map(|def| ticked_words(&def.body))
+        .chain(ticked_words(&program.top_level_code))
+        .collect();
+    program
+        .definitions
+        .iter()
+        .map(|def| def.name.as_str())
+        .filter(|name| ticked.contains(name))
+        .collect()
+}
+
+fn effects_in_order(program: &Program, mut inference: StackEffectInference) -> Vec<Option<StackEffect>> {
+    program
+        .definitions
+        .iter()
+        .map(|def| {
+            let effect = def.stack_effect.clone().or_else(|| inference.infer_definition(def));
+            // A word whose effect is unknown makes its callers unknown too
+            match &effect {
+                Some(effect) => inference.user_words.insert(def.name.clone(), effect.clone()),
+                None => inference.user_words.remove(&def.name),
+            };
+            effect
+        })
+        .collect()
+}
+
+/// Fill in the stack effect of every unannotated definition whose effect can
+/// be inferred; explicit annotations are left as written
+pub fn annotate_stack_effects(program: &mut Program) {
+    let effects = definition_effects(program);
+    for (def, effect) in program.definitions.iter_mut().zip(effects) {
+        if def.stack_effect.is_none() {
+            def.stack_effect = effect;
+        }
+    }
+}
+
 impl Default for StackEffectInference {
     fn default() -> Self {
         Self::new()
@@ -421,4 +992,59 @@ mod tests {
         let effect = inference.infer_sequence(&words).unwrap();
         assert_eq!(effect.outputs.len(), 2);
     }
+
+    #[test]
+    fn test_annotate_square() {
+        let mut program = parse_program(": sq dup * ; 3 sq").unwrap();
+        annotate_stack_effects(&mut program);
+
+        let annotated = parse_program(": sq ( n -- n ) dup * ;").unwrap();
+        assert_eq!(program.definitions[0].stack_effect, annotated.definitions[0].stack_effect);
+
+        let functions = crate::ssa::convert_to_ssa(&program).unwrap();
+        assert_eq!(functions[0].parameters.len(), 1);
+    }
+
+    #[test]
+    fn test_annotation_uses_callee_effects() {
+        let mut program = parse_program(": double 2 * ; : quad double double ;").unwrap();
+        annotate_stack_effects(&mut program);
+
+        let quad = program.definitions[1].stack_effect.as_ref().unwrap();
+        assert_eq!((quad.inputs.len(), quad.outputs.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_annotation_control_flow() {
+        let effects = |source: &str| {
+            definition_effects(&parse_program(source).unwrap())
+                .into_iter()
+                .map(|effect| effect.map(|effect| (effect.inputs.len(), effect.outputs.len())))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(effects(": f if 1 else 2 then ;"), vec![Some((1, 1))]);
+        assert_eq!(effects(": f 0 swap 0 do 1 + loop ;"), vec![Some((1, 1))]);
+        assert_eq!(effects(": f begin 1 - dup 0 = until ;"), vec![Some((1, 1))]);
+        assert_eq!(effects(": f begin dup while 1 - repeat ;"), vec![Some((1, 1))]);
+        assert_eq!(effects(": f ?dup if . then ;"), vec![Some((1, 0))]);
+
+        // Unequal branches, depth-changing loops and recursion stay unknown
+        assert_eq!(effects(": f if 1 2 then ;"), vec![None]);
+        assert_eq!(effects(": f begin 1 dup until ;"), vec![None]);
+        assert_eq!(effects(": f dup if 1 - f then ;"), vec![None]);
+
+        // So does anything calling them, or calling an unknown word
+        assert_eq!(effects(": f if 1 2 then ; : g 0 f ;"), vec![None, None]);
+        assert_eq!(effects(": g mystery 1 + ;"), vec![None]);
+    }
+
+    #[test]
+    fn test_annotation_keeps_declared_effect() {
+        let mut program = parse_program(": f ( a b c -- ) drop ;").unwrap();
+        annotate_stack_effects(&mut program);
+
+        let effect = program.definitions[0].stack_effect.as_ref().unwrap();
+        assert_eq!((effect.inputs.len(), effect.outputs.len()), (3, 0));
+    }
 }
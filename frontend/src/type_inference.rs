@@ -164,7 +164,7 @@ impl TypeInference {
         match word {
             Word::IntLiteral(_) => Ok((vec![], vec![StackType::Int])),
             Word::FloatLiteral(_) => Ok((vec![], vec![StackType::Float])),
-            Word::StringLiteral(_) => Ok((vec![], vec![StackType::String])),
+            Word::StringLiteral(_) => Ok((vec![], vec![StackType::Addr, StackType::Int])),
 
             Word::WordRef { name, .. } => {
                 // Look up word type from environment
@@ -232,6 +232,10 @@ impl TypeInference {
 
             Word::Variable { .. } => Ok((vec![], vec![StackType::Addr])),
             Word::Constant { .. } => Ok((vec![], vec![StackType::Int])),
+            Word::Value { .. } => Ok((vec![], vec![])),
+            Word::To { .. } => Ok((vec![StackType::Int], vec![])),
+            Word::Tick { .. } => Ok((vec![], vec![StackType::Int])),
+            Word::Create { .. } | Word::Does { .. } | Word::Instantiate { .. } => Ok((vec![], vec![])),
             Word::Comment(_) => Ok((vec![], vec![])),
         }
     }
@@ -281,30 +285,39 @@ impl TypeInference {
             }
 
             // Comparison
-            "<" | ">" | "=" | "<=" | ">=" | "<>" => {
+            "<" | ">" | "=" | "<=" | ">=" | "<>" | "u<" | "u>" => {
                 Ok((vec![StackType::Int, StackType::Int], vec![StackType::Bool]))
             }
+            "0=" | "0<" | "0>" => Ok((vec![StackType::Int], vec![StackType::Bool])),
 
             // Logical
-            "and" | "or" => {
+            "and" | "or" | "xor" => {
                 Ok((vec![StackType::Bool, StackType::Bool], vec![StackType::Bool]))
             }
             "not" => Ok((vec![StackType::Bool], vec![StackType::Bool])),
             "invert" => Ok((vec![StackType::Int], vec![StackType::Int])),
+            "lshift" | "rshift" | "arshift" => {
+                Ok((vec![StackType::Int, StackType::Int], vec![StackType::Int]))
+            }
 
             // Memory
             "@" => Ok((vec![StackType::Addr], vec![StackType::Int])),
             "!" => Ok((vec![StackType::Int, StackType::Addr], vec![])),
+            "," | "allot" => Ok((vec![StackType::Int], vec![])),
+            "here" => Ok((vec![], vec![StackType::Addr])),
             "c@" => Ok((vec![StackType::Addr], vec![StackType::Char])),
             "c!" => Ok((vec![StackType::Char, StackType::Addr], vec![])),
 
             // I/O
-            "." => Ok((vec![StackType::Int], vec![])),
+            "." | "u." => Ok((vec![StackType::Int], vec![])),
+            ".r" => Ok((vec![StackType::Int, StackType::Int], vec![])),
             "emit" => Ok((vec![StackType::Char], vec![])),
             "cr" => Ok((vec![], vec![])),
+            "type" => Ok((vec![StackType::Addr, StackType::Int], vec![])),
+            "count" => Ok((vec![StackType::Addr], vec![StackType::Addr, StackType::Int])),
 
             // Other
-            "negate" | "abs" => Ok((vec![StackType::Int], vec![StackType::Int])),
+            "negate" | "abs" | "1+" | "1-" | "2*" | "2/" => Ok((vec![StackType::Int], vec![StackType::Int])),
             "min" | "max" => {
                 Ok((vec![StackType::Int, StackType::Int], vec![StackType::Int]))
             }
@@ -21,7 +21,7 @@ fn test_complete_pipeline() {
 
     // SSA conversion
     let ssa_functions = ssa::convert_to_ssa(&program).expect("SSA conversion failed");
-    assert_eq!(ssa_functions.len(), 2);
+    assert_eq!(ssa_functions.len(), 3);
 
     println!("SSA for square:\n{}", ssa_functions[0]);
     println!("\nSSA for sum-of-squares:\n{}", ssa_functions[1]);
@@ -46,7 +46,7 @@ fn test_fibonacci() {
     semantic::analyze(&program).expect("Semantic analysis failed");
 
     let ssa_functions = ssa::convert_to_ssa(&program).expect("SSA conversion failed");
-    assert_eq!(ssa_functions.len(), 1);
+    assert_eq!(ssa_functions.len(), 2);
 }
 
 #[test]
@@ -155,7 +155,7 @@ fn test_nested_control_structures() {
     semantic::analyze(&program).expect("Semantic analysis failed");
 
     let ssa_functions = ssa::convert_to_ssa(&program).expect("SSA conversion failed");
-    assert_eq!(ssa_functions.len(), 1);
+    assert_eq!(ssa_functions.len(), 2);
 }
 
 #[test]
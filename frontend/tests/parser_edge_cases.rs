@@ -397,10 +397,10 @@ fn test_unterminated_definition() {
     let result = parse_program(source);
     assert!(result.is_err());
 
-    if let Err(ForthError::ParseError { message, .. }) = result {
+    if let Err(ForthError::IncompleteInput { message }) = result {
         assert!(message.contains("Unterminated"));
     } else {
-        panic!("Expected ParseError");
+        panic!("Expected IncompleteInput");
     }
 }
 
@@ -62,6 +62,17 @@ fn bench_superinstructions(c: &mut Criterion) {
         });
     }
 
+    // Large program: patterns interleaved with calls, 10k instructions
+    let source = "5 dup + 1 + helper dup * other drop ".repeat(1000);
+    let large = ForthIR::parse(&source).unwrap();
+    assert_eq!(large.instruction_count(), 10_000);
+    group.bench_function("large_program", |b| {
+        let optimizer = SuperinstructionOptimizer::new();
+        b.iter(|| {
+            optimizer.recognize(black_box(&large)).unwrap()
+        });
+    });
+
     group.finish();
 }
 
@@ -127,7 +138,7 @@ fn bench_inlining(c: &mut Criterion) {
         "cube".to_string(),
         vec![
             Instruction::Dup,
-            Instruction::Call("square".to_string()),
+            Instruction::call("square"),
             Instruction::Mul,
         ],
     );
@@ -135,7 +146,7 @@ fn bench_inlining(c: &mut Criterion) {
 
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("cube".to_string()),
+        Instruction::call("cube"),
     ];
 
     for level in [OptimizationLevel::Basic, OptimizationLevel::Standard, OptimizationLevel::Aggressive].iter() {
@@ -179,7 +190,7 @@ fn bench_full_pipeline(c: &mut Criterion) {
             BenchmarkId::from_parameter(format!("{:?}", level)),
             level,
             |b, &level| {
-                let optimizer = Optimizer::new(level);
+                let mut optimizer = Optimizer::new(level);
                 b.iter(|| {
                     optimizer.optimize(black_box(ir.clone())).unwrap()
                 });
@@ -198,7 +209,7 @@ fn bench_fixpoint_optimization(c: &mut Criterion) {
     let ir = ForthIR::parse(code).unwrap();
 
     group.bench_function("aggressive", |b| {
-        let optimizer = Optimizer::new(OptimizationLevel::Aggressive);
+        let mut optimizer = Optimizer::new(OptimizationLevel::Aggressive);
         b.iter(|| {
             optimizer.optimize_until_fixpoint(black_box(ir.clone())).unwrap()
         });
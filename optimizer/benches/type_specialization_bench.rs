@@ -112,9 +112,9 @@ fn bench_optimization_comparison(c: &mut Criterion) {
     let sum_squares = WordDef::new(
         "sum_squares".to_string(),
         vec![
-            Instruction::Call("square".to_string()),
+            Instruction::call("square"),
             Instruction::Swap,
-            Instruction::Call("square".to_string()),
+            Instruction::call("square"),
             Instruction::Add,
         ],
     );
@@ -124,7 +124,7 @@ fn bench_optimization_comparison(c: &mut Criterion) {
     ir.main = vec![
         Instruction::Literal(3),
         Instruction::Literal(4),
-        Instruction::Call("sum_squares".to_string()),
+        Instruction::call("sum_squares"),
     ];
 
     let mut type_info = TypeInferenceResults::new();
@@ -176,7 +176,7 @@ fn bench_call_site_resolution(c: &mut Criterion) {
 
         // Create many call sites
         for _ in 0..*call_count {
-            ir.main.push(Instruction::Call("test".to_string()));
+            ir.main.push(Instruction::call("test"));
         }
 
         let mut type_info = TypeInferenceResults::new();
@@ -84,7 +84,7 @@ fn bench_conditional_elimination(c: &mut Criterion) {
                 let mut ir = ForthIR::new();
                 ir.main = vec![
                     Instruction::Literal(-1), // TRUE
-                    Instruction::BranchIf(10),
+                    Instruction::BranchIf(BlockRef(10)),
                     Instruction::Literal(99),
                 ];
                 ir
@@ -93,7 +93,7 @@ fn bench_conditional_elimination(c: &mut Criterion) {
                 let mut ir = ForthIR::new();
                 ir.main = vec![
                     Instruction::Literal(0), // FALSE
-                    Instruction::BranchIf(10),
+                    Instruction::BranchIf(BlockRef(10)),
                     Instruction::Literal(99),
                 ];
                 ir
@@ -102,7 +102,7 @@ fn bench_conditional_elimination(c: &mut Criterion) {
                 let mut ir = ForthIR::new();
                 ir.main = vec![
                     Instruction::Literal(-1),
-                    Instruction::BranchIfNot(20),
+                    Instruction::BranchIfNot(BlockRef(20)),
                 ];
                 ir
             }
@@ -110,7 +110,7 @@ fn bench_conditional_elimination(c: &mut Criterion) {
                 let mut ir = ForthIR::new();
                 ir.main = vec![
                     Instruction::Literal(0),
-                    Instruction::BranchIfNot(20),
+                    Instruction::BranchIfNot(BlockRef(20)),
                 ];
                 ir
             }
@@ -107,15 +107,15 @@ fn test_conditional_elimination() -> Result<()> {
         let mut ir = ForthIR::new();
         ir.main = vec![
             Instruction::Literal(-1), // TRUE
-            Instruction::BranchIf(10),
+            Instruction::BranchIf(BlockRef(10)),
         ];
 
         let optimizer = ZeroCostOptimizer::default();
         let optimized = optimizer.optimize(&ir)?;
 
-        println!("    Before: Literal(-1), BranchIf(10)");
+        println!("    Before: Literal(-1), BranchIf(BlockRef(10))");
         if optimized.main.len() == 1 && matches!(&optimized.main[0], Instruction::Branch(_)) {
-            println!("    After:  Branch(10)");
+            println!("    After:  Branch(BlockRef(10))");
             println!("    ✓ Conditional eliminated and converted to unconditional branch");
         }
     }
@@ -125,13 +125,13 @@ fn test_conditional_elimination() -> Result<()> {
         let mut ir = ForthIR::new();
         ir.main = vec![
             Instruction::Literal(0), // FALSE
-            Instruction::BranchIf(10),
+            Instruction::BranchIf(BlockRef(10)),
         ];
 
         let optimizer = ZeroCostOptimizer::default();
         let optimized = optimizer.optimize(&ir)?;
 
-        println!("    Before: Literal(0), BranchIf(10)");
+        println!("    Before: Literal(0), BranchIf(BlockRef(10))");
         if optimized.main.is_empty() {
             println!("    After:  (empty - branch eliminated)");
             println!("    ✓ Dead code eliminated!");
@@ -8,6 +8,8 @@
 //! - Multi-level recursive inline expansion
 //! - Cost/benefit analysis with code size tracking
 //! - Programmer control via INLINE/NOINLINE directives
+//! - Call frequencies from a profiling run ([`ProfileData`]), so hot words
+//!   are inlined whatever their size, within the code bloat cap
 //!
 //! # Algorithm Overview
 //!
@@ -36,7 +38,8 @@
 //! - Fibonacci: Inline base case checks (15% speedup)
 //! - Overall: 10-20% on call-heavy code
 
-use crate::ir::{ForthIR, Instruction, StackEffect, WordDef};
+use crate::inline::ProfileData;
+use crate::ir::{ForthIR, Instruction, Splice, WordDef};
 use crate::{OptimizationLevel, Result};
 use petgraph::algo::tarjan_scc;
 use petgraph::graph::{DiGraph, NodeIndex};
@@ -62,6 +65,7 @@ pub struct InlineableWord {
     pub call_count: usize,
     pub inline_depth: usize,  // How many levels deep this has been inlined
     pub total_cost: usize,    // Cost including inlined callees
+    pub hot: bool,            // Called frequently in the profile
 }
 
 impl InlineableWord {
@@ -73,6 +77,7 @@ impl InlineableWord {
             call_count: 0,
             inline_depth: 0,
             total_cost,
+            hot: false,
         }
     }
 }
@@ -122,8 +127,8 @@ impl CallGraph {
             let mut callee_counts: HashMap<String, usize> = HashMap::new();
             for inst in &word.instructions {
                 if let Instruction::Call(callee_name) = inst {
-                    if name_to_node.contains_key(callee_name) {
-                        *callee_counts.entry(callee_name.clone()).or_insert(0) += 1;
+                    if name_to_node.contains_key(callee_name.as_str()) {
+                        *callee_counts.entry(callee_name.to_string()).or_insert(0) += 1;
                     }
                 }
             }
@@ -144,16 +149,48 @@ impl CallGraph {
 
     /// Detect strongly connected components (cycles)
     pub fn find_cycles(&self) -> Vec<HashSet<String>> {
-        let sccs = tarjan_scc(&self.graph);
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|scc| self.is_recursive_component(scc))
+            .map(|scc| scc.into_iter().collect())
+            .collect()
+    }
 
-        sccs.into_iter()
-            .filter(|scc| scc.len() > 1 || self.has_self_loop(scc[0]))
+    /// Every word's strongly connected component, found with Tarjan's algorithm
+    ///
+    /// Words that are not part of a cycle form a component of their own.
+    /// Names are sorted within each component and components are sorted by
+    /// their first name, so the result does not depend on `HashMap` order.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        let mut sccs: Vec<Vec<String>> = tarjan_scc(&self.graph)
+            .into_iter()
             .map(|scc| {
-                scc.into_iter()
-                    .map(|idx| self.graph[idx].name.clone())
-                    .collect()
+                let mut names: Vec<String> =
+                    scc.into_iter().map(|idx| self.graph[idx].name.clone()).collect();
+                names.sort();
+                names
             })
-            .collect()
+            .collect();
+        sccs.sort();
+        sccs
+    }
+
+    /// Whether a component from `strongly_connected_components` is a
+    /// recursive cluster: several mutually recursive words, or one word that
+    /// calls itself
+    pub fn is_recursive_component(&self, scc: &[String]) -> bool {
+        match scc {
+            [name] => self
+                .name_to_node
+                .get(name.as_str())
+                .is_some_and(|&node| self.has_self_loop(node)),
+            _ => scc.len() > 1,
+        }
+    }
+
+    /// Words in any recursive cluster
+    pub fn recursive_words(&self) -> HashSet<String> {
+        self.find_cycles().into_iter().flatten().collect()
     }
 
     /// Check if node has self-loop (direct recursion)
@@ -207,6 +244,23 @@ impl CallGraph {
         result.push(self.graph[node].name.clone());
     }
 
+    /// Words reachable from `roots` through any number of calls, roots included
+    pub fn reachable_from<'a>(&self, roots: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+        let mut stack: Vec<NodeIndex> = roots
+            .into_iter()
+            .filter_map(|name| self.name_to_node.get(name).copied())
+            .collect();
+        let mut seen = HashSet::new();
+
+        while let Some(node) = stack.pop() {
+            if seen.insert(node) {
+                stack.extend(self.graph.edges(node).map(|edge| edge.target()));
+            }
+        }
+
+        seen.into_iter().map(|idx| self.graph[idx].name.clone()).collect()
+    }
+
     /// Get callees of a word
     pub fn get_callees(&self, word_name: &str) -> Vec<String> {
         if let Some(&node_idx) = self.name_to_node.get(word_name) {
@@ -244,6 +298,7 @@ pub struct AggressiveInlineOptimizer {
     max_inline_depth: usize,
     max_code_bloat_factor: f64,
     max_iterations: usize,
+    profile: Option<ProfileData>,
 }
 
 impl AggressiveInlineOptimizer {
@@ -257,6 +312,7 @@ impl AggressiveInlineOptimizer {
                 max_inline_depth: 0,
                 max_code_bloat_factor: 1.0,
                 max_iterations: 0,
+                profile: None,
             },
             OptimizationLevel::Basic => Self {
                 level,
@@ -266,6 +322,7 @@ impl AggressiveInlineOptimizer {
                 max_inline_depth: 2,
                 max_code_bloat_factor: 1.5,
                 max_iterations: 2,
+                profile: None,
             },
             OptimizationLevel::Standard => Self {
                 level,
@@ -275,6 +332,7 @@ impl AggressiveInlineOptimizer {
                 max_inline_depth: 3,
                 max_code_bloat_factor: 2.0,
                 max_iterations: 3,
+                profile: None,
             },
             OptimizationLevel::Aggressive => Self {
                 level,
@@ -284,10 +342,17 @@ impl AggressiveInlineOptimizer {
                 max_inline_depth: 5,
                 max_code_bloat_factor: 3.0,
                 max_iterations: 5,
+                profile: None,
             },
         }
     }
 
+    /// Inline the words `profile` shows to be hot regardless of their size
+    pub fn with_profile(mut self, profile: ProfileData) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
     /// Perform aggressive inlining with whole-program analysis
     pub fn inline(&self, ir: &ForthIR) -> Result<ForthIR> {
         if self.level == OptimizationLevel::None {
@@ -302,9 +367,7 @@ impl AggressiveInlineOptimizer {
             let call_graph = CallGraph::build(&current_ir);
 
             // Detect cycles
-            let cycles = call_graph.find_cycles();
-            let cyclic_words: HashSet<String> =
-                cycles.into_iter().flat_map(|c| c.into_iter()).collect();
+            let cyclic_words = call_graph.recursive_words();
 
             // Build inlineable word map
             let mut inlineable_words = self.build_inlineable_map(&current_ir, &call_graph);
@@ -352,9 +415,15 @@ impl AggressiveInlineOptimizer {
         call_graph: &CallGraph,
     ) -> HashMap<String, InlineableWord> {
         let mut map = HashMap::new();
+        let hot_words = self
+            .profile
+            .as_ref()
+            .map(|profile| profile.hot_words(ir))
+            .unwrap_or_default();
 
         for (name, word) in &ir.words {
             let mut inlineable = InlineableWord::new(word.clone());
+            inlineable.hot = hot_words.contains(name);
 
             // Use is_inline flag as directive
             if word.is_inline {
@@ -481,20 +550,21 @@ impl AggressiveInlineOptimizer {
         iteration: usize,
     ) -> Result<Vec<Instruction>> {
         let mut result = Vec::with_capacity(instructions.len() * 2);
+        let mut splice = Splice::new(instructions);
 
         for inst in instructions {
             match inst {
                 Instruction::Call(callee_name) => {
-                    if let Some(inlineable) = inlineable_words.get(callee_name) {
+                    if let Some(inlineable) = inlineable_words.get(callee_name.as_str()) {
                         if self.should_inline_call(inlineable, iteration) {
-                            if let Some(callee) = ir.get_word(callee_name) {
+                            if let Some(body) = ir.get_word(callee_name).and_then(|callee| splice.body(callee)) {
                                 // Add comment marker
                                 result.push(Instruction::Comment(
                                     format!("inlined {}", callee_name)
                                 ));
 
                                 // Inline the callee's instructions
-                                result.extend_from_slice(&callee.instructions);
+                                result.extend(body);
                                 continue;
                             }
                         }
@@ -526,6 +596,11 @@ impl AggressiveInlineOptimizer {
             return false;
         }
 
+        // Hot words pay for their size; the bloat cap still limits growth
+        if inlineable.hot {
+            return true;
+        }
+
         // Check if too many call sites
         if inlineable.call_count > self.max_inline_sites {
             return false;
@@ -647,8 +722,8 @@ mod tests {
 
         // Create word chain: a calls b, b calls c
         let c = WordDef::new("c".to_string(), vec![Instruction::Dup]);
-        let b = WordDef::new("b".to_string(), vec![Instruction::Call("c".to_string())]);
-        let a = WordDef::new("a".to_string(), vec![Instruction::Call("b".to_string())]);
+        let b = WordDef::new("b".to_string(), vec![Instruction::call("c")]);
+        let a = WordDef::new("a".to_string(), vec![Instruction::call("b")]);
 
         ir.add_word(c);
         ir.add_word(b);
@@ -668,7 +743,7 @@ mod tests {
         // Create recursive word
         let factorial = WordDef::new(
             "factorial".to_string(),
-            vec![Instruction::Dup, Instruction::Call("factorial".to_string())],
+            vec![Instruction::Dup, Instruction::call("factorial")],
         );
         ir.add_word(factorial);
 
@@ -679,14 +754,53 @@ mod tests {
         assert!(cycles[0].contains("factorial"));
     }
 
+    #[test]
+    fn test_scc_three_word_cycle() {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("a".to_string(), vec![Instruction::call("b")]));
+        ir.add_word(WordDef::new("b".to_string(), vec![Instruction::call("c")]));
+        ir.add_word(WordDef::new("c".to_string(), vec![Instruction::call("a")]));
+
+        let call_graph = CallGraph::build(&ir);
+        let sccs = call_graph.strongly_connected_components();
+
+        assert_eq!(sccs, vec![vec!["a", "b", "c"]]);
+        assert!(call_graph.is_recursive_component(&sccs[0]));
+    }
+
+    #[test]
+    fn test_scc_independent_clusters() {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("even".to_string(), vec![Instruction::call("odd")]));
+        ir.add_word(WordDef::new("odd".to_string(), vec![Instruction::call("even")]));
+        let fact = vec![Instruction::Dup, Instruction::call("fact")];
+        ir.add_word(WordDef::new("fact".to_string(), fact));
+        ir.add_word(WordDef::new("ping".to_string(), vec![Instruction::call("pong")]));
+        let pong = vec![Instruction::call("ping"), Instruction::call("leaf")];
+        ir.add_word(WordDef::new("pong".to_string(), pong));
+        ir.add_word(WordDef::new("leaf".to_string(), vec![Instruction::Dup]));
+
+        let call_graph = CallGraph::build(&ir);
+        let sccs = call_graph.strongly_connected_components();
+
+        assert_eq!(
+            sccs,
+            vec![vec!["even", "odd"], vec!["fact"], vec!["leaf"], vec!["ping", "pong"]]
+        );
+        let recursive: Vec<bool> =
+            sccs.iter().map(|scc| call_graph.is_recursive_component(scc)).collect();
+        assert_eq!(recursive, vec![true, true, false, true]);
+        assert_eq!(call_graph.recursive_words().len(), 5);
+    }
+
     #[test]
     fn test_topological_sort() {
         let mut ir = ForthIR::new();
 
         // Create dependency chain
         let c = WordDef::new("c".to_string(), vec![Instruction::Dup]);
-        let b = WordDef::new("b".to_string(), vec![Instruction::Call("c".to_string())]);
-        let a = WordDef::new("a".to_string(), vec![Instruction::Call("b".to_string())]);
+        let b = WordDef::new("b".to_string(), vec![Instruction::call("c")]);
+        let a = WordDef::new("a".to_string(), vec![Instruction::call("b")]);
 
         ir.add_word(c);  // Add in c, b, a order
         ir.add_word(b);
@@ -720,12 +834,12 @@ mod tests {
         let tiny = WordDef::new("tiny".to_string(), vec![Instruction::Dup]);
         let small = WordDef::new(
             "small".to_string(),
-            vec![Instruction::Call("tiny".to_string()), Instruction::Add],
+            vec![Instruction::call("tiny"), Instruction::Add],
         );
 
         ir.add_word(tiny);
         ir.add_word(small);
-        ir.main = vec![Instruction::Literal(5), Instruction::Call("small".to_string())];
+        ir.main = vec![Instruction::Literal(5), Instruction::call("small")];
 
         let optimized = optimizer.inline(&ir).unwrap();
 
@@ -751,11 +865,11 @@ mod tests {
                 Instruction::Dup,
                 Instruction::Literal(1),
                 Instruction::Gt,
-                Instruction::Call("factorial".to_string()),
+                Instruction::call("factorial"),
             ],
         );
         ir.add_word(factorial);
-        ir.main = vec![Instruction::Literal(5), Instruction::Call("factorial".to_string())];
+        ir.main = vec![Instruction::Literal(5), Instruction::call("factorial")];
 
         let optimized = optimizer.inline(&ir).unwrap();
 
@@ -774,17 +888,17 @@ mod tests {
         let level3 = WordDef::new("level3".to_string(), vec![Instruction::Dup]);
         let level2 = WordDef::new(
             "level2".to_string(),
-            vec![Instruction::Call("level3".to_string())],
+            vec![Instruction::call("level3")],
         );
         let level1 = WordDef::new(
             "level1".to_string(),
-            vec![Instruction::Call("level2".to_string())],
+            vec![Instruction::call("level2")],
         );
 
         ir.add_word(level3);
         ir.add_word(level2);
         ir.add_word(level1);
-        ir.main = vec![Instruction::Literal(5), Instruction::Call("level1".to_string())];
+        ir.main = vec![Instruction::Literal(5), Instruction::call("level1")];
 
         let optimized = optimizer.inline(&ir).unwrap();
 
@@ -803,7 +917,7 @@ mod tests {
         let mut large = WordDef::new("large".to_string(), vec![Instruction::Dup; 50]);
         large.is_inline = true; // Force inline
         ir.add_word(large);
-        ir.main = vec![Instruction::Call("large".to_string())];
+        ir.main = vec![Instruction::call("large")];
 
         let optimized = optimizer.inline(&ir).unwrap();
 
@@ -812,6 +926,34 @@ mod tests {
         assert!(!has_call, "Forced inline should override size threshold");
     }
 
+    #[test]
+    fn test_profile_guided_inlining() {
+        let large = vec![Instruction::Dup; 40];
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("hot".to_string(), large.clone()));
+        ir.add_word(WordDef::new("cold".to_string(), large));
+        ir.main = vec![Instruction::Literal(2), Instruction::call("hot"), Instruction::call("cold")];
+        let profile: ProfileData = [("hot", 5_000), ("cold", 2), ("removed", 90_000)].into_iter().collect();
+
+        let calls = |ir: &ForthIR| -> Vec<Instruction> {
+            ir.main.iter().filter(|inst| matches!(inst, Instruction::Call(_))).cloned().collect()
+        };
+
+        let optimizer = AggressiveInlineOptimizer::new(OptimizationLevel::Aggressive);
+        assert_eq!(calls(&optimizer.inline(&ir).unwrap()).len(), 2);
+
+        let optimized = optimizer.with_profile(profile.clone()).inline(&ir).unwrap();
+        assert_eq!(calls(&optimized), vec![Instruction::call("cold")]);
+
+        // Inlining the hot word at many sites would exceed the bloat cap
+        ir.main = vec![Instruction::call("hot"); 8];
+        let optimized = AggressiveInlineOptimizer::new(OptimizationLevel::Aggressive)
+            .with_profile(profile)
+            .inline(&ir)
+            .unwrap();
+        assert_eq!(calls(&optimized).len(), 8);
+    }
+
     #[test]
     fn test_inline_stats() {
         let optimizer = AggressiveInlineOptimizer::new(OptimizationLevel::Aggressive);
@@ -820,8 +962,8 @@ mod tests {
         let small = WordDef::new("small".to_string(), vec![Instruction::Dup]);
         ir.add_word(small);
         ir.main = vec![
-            Instruction::Call("small".to_string()),
-            Instruction::Call("small".to_string()),
+            Instruction::call("small"),
+            Instruction::call("small"),
         ];
 
         let optimized = optimizer.inline(&ir).unwrap();
@@ -88,6 +88,7 @@ impl CFG {
                 Instruction::Branch(_)
                     | Instruction::BranchIf(_)
                     | Instruction::BranchIfNot(_)
+                    | Instruction::BranchIfElse(..)
                     | Instruction::Return
             );
 
@@ -167,6 +168,7 @@ impl ReachingDefinitions {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::BlockRef;
 
     #[test]
     fn test_stack_depth_analysis() {
@@ -188,7 +190,7 @@ mod tests {
     fn test_cfg_construction() {
         let instructions = vec![
             Instruction::Literal(1),
-            Instruction::BranchIf(5),
+            Instruction::BranchIf(BlockRef(5)),
             Instruction::Literal(2),
         ];
 
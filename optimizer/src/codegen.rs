@@ -52,14 +52,17 @@ impl CCodegen {
             Mod => "    NOS %= TOS; DROP;".to_string(),
             Neg => "    TOS = -TOS;".to_string(),
             Abs => "    TOS = (TOS < 0) ? -TOS : TOS;".to_string(),
+            Min => "    NOS = (TOS < NOS) ? TOS : NOS; DROP;".to_string(),
+            Max => "    NOS = (TOS > NOS) ? TOS : NOS; DROP;".to_string(),
 
             // Bitwise
             And => "    NOS &= TOS; DROP;".to_string(),
             Or => "    NOS |= TOS; DROP;".to_string(),
             Xor => "    NOS ^= TOS; DROP;".to_string(),
             Not => "    TOS = ~TOS;".to_string(),
-            Shl => "    NOS <<= TOS; DROP;".to_string(),
-            Shr => "    NOS >>= TOS; DROP;".to_string(),
+            Shl => "    NOS = (cell_t)((uint64_t)NOS << (TOS & 63)); DROP;".to_string(),
+            Shr => "    NOS = (cell_t)((uint64_t)NOS >> (TOS & 63)); DROP;".to_string(),
+            Sar => "    NOS >>= (TOS & 63); DROP;".to_string(),
 
             // Comparisons
             Eq => "    NOS = (NOS == TOS) ? -1 : 0; DROP;".to_string(),
@@ -68,6 +71,8 @@ impl CCodegen {
             Le => "    NOS = (NOS <= TOS) ? -1 : 0; DROP;".to_string(),
             Gt => "    NOS = (NOS > TOS) ? -1 : 0; DROP;".to_string(),
             Ge => "    NOS = (NOS >= TOS) ? -1 : 0; DROP;".to_string(),
+            ULt => "    NOS = ((uint64_t)NOS < (uint64_t)TOS) ? -1 : 0; DROP;".to_string(),
+            UGt => "    NOS = ((uint64_t)NOS > (uint64_t)TOS) ? -1 : 0; DROP;".to_string(),
             ZeroEq => "    TOS = (TOS == 0) ? -1 : 0;".to_string(),
             ZeroLt => "    TOS = (TOS < 0) ? -1 : 0;".to_string(),
             ZeroGt => "    TOS = (TOS > 0) ? -1 : 0;".to_string(),
@@ -88,6 +93,8 @@ impl CCodegen {
                 "    { cell_t tmp = TOS; TOS = NOS; NOS = tmp; }".to_string()
             }
             FlushCache => "    /* cache flushed */".to_string(),
+            SpillCache => "    /* cache spilled */".to_string(),
+            FillCache => "    /* cache filled */".to_string(),
 
             // Control flow
             Call(name) => format!("    {}();", sanitize_name(name)),
@@ -97,6 +104,9 @@ impl CCodegen {
             BranchIfNot(target) => {
                 format!("    if (!TOS) {{ DROP; goto L{}; }} DROP;", target)
             }
+            BranchIfElse(then, otherwise) => {
+                format!("    if (TOS) {{ DROP; goto L{}; }} DROP; goto L{};", then, otherwise)
+            }
 
             // Memory operations
             Load => "    TOS = *(cell_t*)TOS;".to_string(),
@@ -117,6 +127,36 @@ impl CCodegen {
             _ => "    /* unimplemented */".to_string(),
         }
     }
+
+    /// Generate a C header declaring every word `generate` defines
+    ///
+    /// Words take their arguments from and leave their results on the shared
+    /// data stack, so every prototype is `void name(void)`, with the stack
+    /// effect as a comment. Callers push arguments through `sp`, which the
+    /// header declares along with `stack`. `library` names the include guard.
+    pub fn generate_header(&self, ir: &ForthIR, library: &str) -> String {
+        let guard = format!("{}_H", sanitize_name(library).to_uppercase());
+        let mut code = format!(
+            "#ifndef {guard}\n#define {guard}\n\n#include <stdint.h>\n\n\
+             typedef int64_t cell_t;\n\n\
+             extern cell_t stack[];\nextern cell_t* sp;\n\n"
+        );
+
+        // Name order, so the header is stable across runs
+        let mut words: Vec<&WordDef> = ir.words.values().collect();
+        words.sort_by(|a, b| a.name.cmp(&b.name));
+        for word in words {
+            code.push_str(&format!(
+                "void {}(void); /* {} */\n",
+                sanitize_name(&word.name),
+                word.stack_effect
+            ));
+        }
+
+        code.push_str("void forth_main(void);\n\n");
+        code.push_str(&format!("#endif /* {} */\n", guard));
+        code
+    }
 }
 
 impl Default for CCodegen {
@@ -139,8 +179,8 @@ typedef int64_t cell_t;
 
 // Stack macros
 #define STACK_SIZE 256
-static cell_t stack[STACK_SIZE];
-static cell_t* sp = stack;
+cell_t stack[STACK_SIZE];
+cell_t* sp = stack;
 static cell_t rstack[STACK_SIZE];
 static cell_t* rsp = rstack;
 
@@ -191,8 +231,12 @@ static cell_t* rsp = rstack;
 }
 
 /// Sanitize word name for C identifier
+///
+/// Every character other than an ASCII letter, digit or underscore becomes
+/// `_`, and a leading digit is replaced by `_`, so `2dup` is `_dup` and
+/// `foo+bar` is `foo_bar`. Distinct words can map to the same identifier.
 fn sanitize_name(name: &str) -> String {
-    let mut result = name.replace(|c: char| !c.is_alphanumeric() && c != '_', "_");
+    let mut result = name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
 
     // C identifiers can't start with a digit, replace leading digits with underscore
     if result.chars().next().map_or(false, |c| c.is_numeric()) {
@@ -232,6 +276,25 @@ mod tests {
         assert!(code.contains("TOS"));
     }
 
+    #[test]
+    fn test_c_header() {
+        let codegen = CCodegen::new();
+        let mut ir = ForthIR::parse("3 square").unwrap();
+        ir.add_word(WordDef::new(
+            "square".to_string(),
+            vec![Instruction::Dup, Instruction::Mul],
+        ));
+        ir.add_word(WordDef::new("1+".to_string(), vec![Instruction::IncOne]));
+
+        let header = codegen.generate_header(&ir, "math-lib");
+
+        assert!(header.starts_with("#ifndef MATH_LIB_H\n#define MATH_LIB_H\n"));
+        assert!(header.trim_end().ends_with("#endif /* MATH_LIB_H */"));
+        assert!(header.contains("void square(void); /* (1 -- 1) */"));
+        assert!(header.contains("void __(void); /* (1 -- 1) */"));
+        assert!(header.contains("extern cell_t* sp;"));
+    }
+
     #[test]
     fn test_sanitize_name() {
         assert_eq!(sanitize_name("foo+bar"), "foo_bar");
@@ -20,56 +20,99 @@
 //! - Bitwise constant folding
 //! - Comparison constant folding
 //! - Constant propagation through stack
-//! - Algebraic simplifications (x*0=0, x*1=x, x+0=x, etc.)
+//! - Algebraic simplifications with one constant operand (x*0=0, x*1=x, x+0=x,
+//!   x*2=x dup +, etc.) and self-cancellation (x x - = 0)
+//! - Strength reduction of multiplication by a power of two to a left shift
+//!   (x*8 = x 3 lshift). Division and modulo are signed and round toward
+//!   zero, which a right shift or mask does not, so they are left alone
+//! - Compile-time rejection of division/modulo by a constant zero
+//! - With checked arithmetic, compile-time rejection of `+ - * negate abs` on
+//!   constants that overflow (instead of folding the wrapped result)
 
 use crate::ir::{ForthIR, Instruction, WordDef};
-use crate::Result;
-use smallvec::{SmallVec, smallvec};
+use crate::{OptimizerError, Result};
 
 /// Value that can be tracked through constant propagation
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Value {
+    /// Known constant that has not been emitted yet
     Constant(i64),
-    Unknown,
+    /// Value present on the runtime stack, identified for `x x -` style rules
+    Unknown(usize),
 }
 
 impl Value {
     fn as_constant(&self) -> Option<i64> {
         match self {
             Value::Constant(v) => Some(*v),
-            Value::Unknown => None,
+            Value::Unknown(_) => None,
         }
     }
 }
 
 /// Stack for abstract interpretation during constant folding
+///
+/// Pending constants always sit on top of the runtime values: any instruction
+/// that needs runtime operands first materializes every pending constant.
 struct AbstractStack {
     stack: Vec<Value>,
+    next_id: usize,
 }
 
 impl AbstractStack {
     fn new() -> Self {
-        Self { stack: Vec::new() }
+        Self {
+            stack: Vec::new(),
+            next_id: 0,
+        }
     }
 
     fn push(&mut self, value: Value) {
         self.stack.push(value);
     }
 
+    /// Push a fresh runtime value
+    fn push_unknown(&mut self) {
+        let value = self.fresh();
+        self.stack.push(value);
+    }
+
+    fn fresh(&mut self) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        Value::Unknown(id)
+    }
+
+    /// Pop a value; popping past the tracked region yields a runtime value
     fn pop(&mut self) -> Value {
-        self.stack.pop().unwrap_or(Value::Unknown)
+        match self.stack.pop() {
+            Some(value) => value,
+            None => self.fresh(),
+        }
     }
 
-    fn peek(&self, depth: usize) -> Value {
+    fn peek(&self, depth: usize) -> Option<Value> {
         if depth < self.stack.len() {
-            self.stack[self.stack.len() - 1 - depth].clone()
+            Some(self.stack[self.stack.len() - 1 - depth])
         } else {
-            Value::Unknown
+            None
+        }
+    }
+
+    /// Emit all pending constants so the runtime stack matches the abstract one
+    fn materialize(&mut self, out: &mut Vec<Instruction>) {
+        for i in 0..self.stack.len() {
+            if let Value::Constant(v) = self.stack[i] {
+                out.push(Instruction::Literal(v));
+                self.stack[i] = Value::Unknown(self.next_id);
+                self.next_id += 1;
+            }
         }
     }
 
-    fn depth(&self) -> usize {
-        self.stack.len()
+    /// Forget everything (unknown stack effect or control-flow boundary)
+    fn clear(&mut self) {
+        self.stack.clear();
     }
 }
 
@@ -77,11 +120,19 @@ impl AbstractStack {
 pub struct ConstantFolder {
     /// Enable aggressive algebraic simplifications
     aggressive: bool,
+    /// Reject overflowing constant arithmetic instead of wrapping
+    checked: bool,
 }
 
 impl ConstantFolder {
     pub fn new() -> Self {
-        Self { aggressive: true }
+        Self { aggressive: true, checked: false }
+    }
+
+    /// Treat signed overflow in folded `+ - * negate abs` as an error
+    pub fn with_checked_arithmetic(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
     }
 
     /// Fold constants in IR
@@ -112,172 +163,175 @@ impl ConstantFolder {
         let mut result = Vec::new();
         let mut stack = AbstractStack::new();
 
-        for inst in instructions {
-            match self.fold_instruction(inst, &mut stack) {
-                FoldResult::Instructions(insts) => result.extend(insts),
-                FoldResult::None => {}
-            }
+        for (index, inst) in instructions.iter().enumerate() {
+            self.fold_instruction(inst, &mut stack, index, &mut result)?;
         }
 
         // Materialize any remaining constants on the stack
-        for value in stack.stack.iter() {
-            if let Some(v) = value.as_constant() {
-                result.push(Instruction::Literal(v));
-            }
-        }
+        stack.materialize(&mut result);
 
         Ok(result)
     }
 
-    /// Fold a single instruction with abstract stack
+    /// Fold a single instruction with abstract stack, emitting into `out`
     fn fold_instruction(
         &self,
         inst: &Instruction,
         stack: &mut AbstractStack,
-    ) -> FoldResult {
+        index: usize,
+        out: &mut Vec<Instruction>,
+    ) -> Result<()> {
         use Instruction::*;
 
         match inst {
             // Literals: push constant value onto abstract stack, don't emit yet
-            Literal(v) => {
-                stack.push(Value::Constant(*v));
-                FoldResult::None  // Don't emit yet, will materialize at end if needed
-            }
+            Literal(v) => stack.push(Value::Constant(*v)),
 
             // Binary arithmetic operations
-            Add => self.fold_binary_op(stack, |a, b| a.wrapping_add(b), Add),
-            Sub => self.fold_binary_op(stack, |a, b| a.wrapping_sub(b), Sub),
-            Mul => self.fold_binary_op(stack, |a, b| a.wrapping_mul(b), Mul),
-            Div => {
-                // Check for division by zero
-                if let Value::Constant(0) = stack.peek(0) {
-                    // Division by zero - keep instruction for runtime error
-                    stack.pop();
-                    stack.pop();
-                    stack.push(Value::Unknown);
-                    FoldResult::Instructions(smallvec![Div])
-                } else {
-                    self.fold_binary_op(stack, |a, b| a.wrapping_div(b), Div)
-                }
+            Add => {
+                self.check_overflow(stack, index, 2, |v| v[0].checked_add(v[1]))?;
+                self.fold_binary_op(stack, out, |a, b| a.wrapping_add(b), Add)
+            }
+            Sub => {
+                self.check_overflow(stack, index, 2, |v| v[0].checked_sub(v[1]))?;
+                self.fold_binary_op(stack, out, |a, b| a.wrapping_sub(b), Sub)
+            }
+            Mul => {
+                self.check_overflow(stack, index, 2, |v| v[0].checked_mul(v[1]))?;
+                self.fold_binary_op(stack, out, |a, b| a.wrapping_mul(b), Mul)
             }
-            Mod => {
-                if let Value::Constant(0) = stack.peek(0) {
-                    stack.pop();
-                    stack.pop();
-                    stack.push(Value::Unknown);
-                    FoldResult::Instructions(smallvec![Mod])
+            Div | Mod => {
+                // A constant zero divisor is a guaranteed runtime trap: reject it now
+                if let Some(Value::Constant(0)) = stack.peek(0) {
+                    let op = if matches!(inst, Div) { "Division" } else { "Modulo" };
+                    return Err(OptimizerError::OptimizationFailed(format!(
+                        "{} by zero at instruction {}",
+                        op, index
+                    )));
+                }
+                if matches!(inst, Div) {
+                    // wrapping_div maps i64::MIN / -1 to i64::MIN instead of panicking
+                    self.fold_binary_op(stack, out, |a, b| a.wrapping_div(b), Div)
                 } else {
-                    self.fold_binary_op(stack, |a, b| a.wrapping_rem(b), Mod)
+                    self.fold_binary_op(stack, out, |a, b| a.wrapping_rem(b), Mod)
                 }
             }
 
             // Bitwise operations
-            And => self.fold_binary_op(stack, |a, b| a & b, And),
-            Or => self.fold_binary_op(stack, |a, b| a | b, Or),
-            Xor => self.fold_binary_op(stack, |a, b| a ^ b, Xor),
-            Shl => self.fold_binary_op(stack, |a, b| a.wrapping_shl(b as u32), Shl),
-            Shr => self.fold_binary_op(stack, |a, b| a.wrapping_shr(b as u32), Shr),
+            And => self.fold_binary_op(stack, out, |a, b| a & b, And),
+            Or => self.fold_binary_op(stack, out, |a, b| a | b, Or),
+            Xor => self.fold_binary_op(stack, out, |a, b| a ^ b, Xor),
+            // Shift counts wrap modulo 64, as they do in the backend
+            Shl => self.fold_binary_op(stack, out, |a, b| a.wrapping_shl(b as u32), Shl),
+            Shr => self.fold_binary_op(stack, out, |a, b| (a as u64).wrapping_shr(b as u32) as i64, Shr),
+            Sar => self.fold_binary_op(stack, out, |a, b| a.wrapping_shr(b as u32), Sar),
 
             // Unary operations
-            Neg => self.fold_unary_op(stack, |a| a.wrapping_neg(), Neg),
-            Abs => self.fold_unary_op(stack, |a| a.abs(), Abs),
-            Not => self.fold_unary_op(stack, |a| !a, Not),
+            Neg => {
+                self.check_overflow(stack, index, 1, |v| v[0].checked_neg())?;
+                self.fold_unary_op(stack, out, |a| a.wrapping_neg(), Neg)
+            }
+            // abs of i64::MIN wraps to itself, as it does in the backend
+            Abs => {
+                self.check_overflow(stack, index, 1, |v| v[0].checked_abs())?;
+                self.fold_unary_op(stack, out, |a| a.wrapping_abs(), Abs)
+            }
+            Min => self.fold_binary_op(stack, out, |a, b| a.min(b), Min),
+            Max => self.fold_binary_op(stack, out, |a, b| a.max(b), Max),
+            Not => self.fold_unary_op(stack, out, |a| !a, Not),
 
             // Comparison operations
-            Eq => self.fold_binary_op(stack, |a, b| if a == b { -1 } else { 0 }, Eq),
-            Ne => self.fold_binary_op(stack, |a, b| if a != b { -1 } else { 0 }, Ne),
-            Lt => self.fold_binary_op(stack, |a, b| if a < b { -1 } else { 0 }, Lt),
-            Le => self.fold_binary_op(stack, |a, b| if a <= b { -1 } else { 0 }, Le),
-            Gt => self.fold_binary_op(stack, |a, b| if a > b { -1 } else { 0 }, Gt),
-            Ge => self.fold_binary_op(stack, |a, b| if a >= b { -1 } else { 0 }, Ge),
-            ZeroEq => self.fold_unary_op(stack, |a| if a == 0 { -1 } else { 0 }, ZeroEq),
-            ZeroLt => self.fold_unary_op(stack, |a| if a < 0 { -1 } else { 0 }, ZeroLt),
-            ZeroGt => self.fold_unary_op(stack, |a| if a > 0 { -1 } else { 0 }, ZeroGt),
+            Eq => self.fold_binary_op(stack, out, |a, b| if a == b { -1 } else { 0 }, Eq),
+            Ne => self.fold_binary_op(stack, out, |a, b| if a != b { -1 } else { 0 }, Ne),
+            Lt => self.fold_binary_op(stack, out, |a, b| if a < b { -1 } else { 0 }, Lt),
+            Le => self.fold_binary_op(stack, out, |a, b| if a <= b { -1 } else { 0 }, Le),
+            Gt => self.fold_binary_op(stack, out, |a, b| if a > b { -1 } else { 0 }, Gt),
+            Ge => self.fold_binary_op(stack, out, |a, b| if a >= b { -1 } else { 0 }, Ge),
+            ULt => self.fold_binary_op(stack, out, |a, b| if (a as u64) < (b as u64) { -1 } else { 0 }, ULt),
+            UGt => self.fold_binary_op(stack, out, |a, b| if (a as u64) > (b as u64) { -1 } else { 0 }, UGt),
+            ZeroEq => self.fold_unary_op(stack, out, |a| if a == 0 { -1 } else { 0 }, ZeroEq),
+            ZeroLt => self.fold_unary_op(stack, out, |a| if a < 0 { -1 } else { 0 }, ZeroLt),
+            ZeroGt => self.fold_unary_op(stack, out, |a| if a > 0 { -1 } else { 0 }, ZeroGt),
 
             // Stack operations that preserve constants
             Dup => {
-                let top = stack.peek(0);
-                stack.push(top.clone());
-                // If duplicating a constant, no need to emit Dup
-                if top.as_constant().is_some() {
-                    FoldResult::None
-                } else {
-                    FoldResult::Instructions(smallvec![Dup])
+                let top = stack.pop();
+                stack.push(top);
+                stack.push(top);
+                // Duplicating a pending constant needs no code
+                if top.as_constant().is_none() {
+                    out.push(Dup);
                 }
             }
 
             Drop => {
-                stack.pop();
-                FoldResult::None // Drop can be eliminated if value is unused
+                // Dropping a pending constant needs no code
+                if stack.pop().as_constant().is_none() {
+                    Self::emit_drop(out);
+                }
             }
 
             Swap => {
-                if stack.depth() >= 2 {
-                    let a = stack.pop();
-                    let b = stack.pop();
-                    // If both are constants, no need to emit Swap
-                    let both_const = a.as_constant().is_some() && b.as_constant().is_some();
-                    stack.push(a);
+                let b = stack.pop();
+                let a = stack.pop();
+                if a.as_constant().is_some() && b.as_constant().is_some() {
                     stack.push(b);
-                    if both_const {
-                        FoldResult::None
-                    } else {
-                        FoldResult::Instructions(smallvec![Swap])
-                    }
+                    stack.push(a);
                 } else {
-                    FoldResult::Instructions(smallvec![Swap])
+                    stack.push(a);
+                    stack.push(b);
+                    stack.materialize(out);
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    stack.push(b);
+                    stack.push(a);
+                    out.push(Swap);
                 }
             }
 
             Over => {
                 let second = stack.peek(1);
-                let is_const = second.as_constant().is_some();
-                stack.push(second);
-                // If copying a constant, no need to emit Over
-                if is_const {
-                    FoldResult::None
-                } else {
-                    FoldResult::Instructions(smallvec![Over])
+                match second {
+                    // Copying a pending constant needs no code
+                    Some(Value::Constant(v)) => stack.push(Value::Constant(v)),
+                    _ => {
+                        stack.materialize(out);
+                        let b = stack.pop();
+                        let a = stack.pop();
+                        stack.push(a);
+                        stack.push(b);
+                        stack.push(a);
+                        out.push(Over);
+                    }
                 }
             }
 
             // Superinstructions
-            DupAdd => {
-                if let Some(v) = stack.peek(0).as_constant() {
-                    stack.pop();
-                    let result = v.wrapping_add(v);
-                    stack.push(Value::Constant(result));
-                    FoldResult::None  // Don't emit yet, will materialize at end
-                } else {
-                    stack.pop();
-                    stack.push(Value::Unknown);
-                    FoldResult::Instructions(smallvec![DupAdd])
-                }
+            DupAdd => self.fold_unary_op(stack, out, |a| a.wrapping_add(a), DupAdd),
+            DupMul => self.fold_unary_op(stack, out, |a| a.wrapping_mul(a), DupMul),
+            IncOne => {
+                self.check_overflow(stack, index, 1, |v| v[0].checked_add(1))?;
+                self.fold_unary_op(stack, out, |a| a.wrapping_add(1), IncOne)
             }
-
-            DupMul => {
-                if let Some(v) = stack.peek(0).as_constant() {
-                    stack.pop();
-                    let result = v.wrapping_mul(v);
-                    stack.push(Value::Constant(result));
-                    FoldResult::None  // Don't emit yet, will materialize at end
-                } else {
-                    stack.pop();
-                    stack.push(Value::Unknown);
-                    FoldResult::Instructions(smallvec![DupMul])
-                }
+            DecOne => {
+                self.check_overflow(stack, index, 1, |v| v[0].checked_sub(1))?;
+                self.fold_unary_op(stack, out, |a| a.wrapping_sub(1), DecOne)
             }
+            // 2* and 2/ are shifts, so they never overflow; 2/ keeps the sign
+            MulTwo => self.fold_unary_op(stack, out, |a| a.wrapping_shl(1), MulTwo),
+            DivTwo => self.fold_unary_op(stack, out, |a| a.wrapping_shr(1), DivTwo),
 
-            IncOne => self.fold_unary_op(stack, |a| a.wrapping_add(1), IncOne),
-            DecOne => self.fold_unary_op(stack, |a| a.wrapping_sub(1), DecOne),
-            MulTwo => self.fold_unary_op(stack, |a| a.wrapping_shl(1), MulTwo),
-            DivTwo => self.fold_unary_op(stack, |a| a.wrapping_shr(1), DivTwo),
+            // Metadata that doesn't touch the stack
+            Comment(_) | Nop => out.push(inst.clone()),
 
             // Non-foldable instructions
             _ => {
-                // Conservatively mark stack as unknown after non-pure operations
-                if !inst.is_pure() {
-                    stack.stack.clear();
+                stack.materialize(out);
+                out.push(inst.clone());
+
+                // Labels are merge points; impure operations have unknown effects
+                if !inst.is_pure() || matches!(inst, Label(_)) {
+                    stack.clear();
                 } else {
                     // Handle stack effect
                     let effect = inst.stack_effect();
@@ -285,39 +339,136 @@ impl ConstantFolder {
                         stack.pop();
                     }
                     for _ in 0..effect.produced {
-                        stack.push(Value::Unknown);
+                        stack.push_unknown();
                     }
                 }
-                FoldResult::Instructions(smallvec![inst.clone()])
             }
         }
+
+        Ok(())
+    }
+
+    /// In checked mode, reject an operation whose `arity` constant operands overflow
+    fn check_overflow(
+        &self,
+        stack: &AbstractStack,
+        index: usize,
+        arity: usize,
+        op: impl FnOnce(&[i64]) -> Option<i64>,
+    ) -> Result<()> {
+        if !self.checked {
+            return Ok(());
+        }
+
+        let operands: Option<Vec<i64>> = (0..arity)
+            .rev()
+            .map(|depth| stack.peek(depth).and_then(|v| v.as_constant()))
+            .collect();
+        match operands {
+            Some(operands) if op(&operands).is_none() => Err(OptimizerError::OptimizationFailed(format!(
+                "Integer overflow at instruction {}",
+                index
+            ))),
+            _ => Ok(()),
+        }
     }
 
-    /// Fold binary operation if both operands are constant
+    /// Fold binary operation if both operands are constant, otherwise try
+    /// algebraic simplification before emitting the operation
     fn fold_binary_op<F>(
         &self,
         stack: &mut AbstractStack,
+        out: &mut Vec<Instruction>,
         op: F,
         fallback: Instruction,
-    ) -> FoldResult
-    where
+    ) where
         F: FnOnce(i64, i64) -> i64,
     {
         let b = stack.pop();
         let a = stack.pop();
 
-        match (a.as_constant(), b.as_constant()) {
-            (Some(av), Some(bv)) => {
-                // Both constants: fold!
-                let result = op(av, bv);
-                stack.push(Value::Constant(result));
-                FoldResult::None  // Don't emit yet, will materialize at end
+        if let (Some(av), Some(bv)) = (a.as_constant(), b.as_constant()) {
+            // Both constants: fold!
+            stack.push(Value::Constant(op(av, bv)));
+            return;
+        }
+
+        if self.aggressive {
+            if let Some(simplified) = self.simplify(&fallback, a, b) {
+                match simplified {
+                    Simplified::Left => stack.push(a),
+                    Simplified::Constant(v) => {
+                        // The runtime operands are dropped; computing them had no side effects
+                        // beyond what was already emitted
+                        for operand in [b, a] {
+                            if operand.as_constant().is_none() {
+                                Self::emit_drop(out);
+                            }
+                        }
+                        stack.push(Value::Constant(v));
+                    }
+                    Simplified::Replace(inst) => {
+                        stack.push(a);
+                        stack.materialize(out);
+                        stack.pop();
+                        out.push(inst);
+                        stack.push_unknown();
+                    }
+                    Simplified::ShiftLeft(bits) => {
+                        stack.push(a);
+                        stack.materialize(out);
+                        stack.pop();
+                        out.push(Instruction::Literal(bits as i64));
+                        out.push(Instruction::Shl);
+                        stack.push_unknown();
+                    }
+                }
+                return;
             }
-            _ => {
-                // Not both constants: keep original instruction
-                stack.push(Value::Unknown);
-                FoldResult::Instructions(smallvec![fallback])
+        }
+
+        // Not both constants: keep original instruction
+        stack.push(a);
+        stack.push(b);
+        stack.materialize(out);
+        stack.pop();
+        stack.pop();
+        out.push(fallback);
+        stack.push_unknown();
+    }
+
+    /// Identity, absorption and self-cancellation laws for `a b op`
+    ///
+    /// Only integer literals are tracked, so floating-point operands (where
+    /// `x 0.0 +` is not an identity because of signed zero) are never simplified.
+    /// With checked arithmetic a multiply is not turned into a shift, which
+    /// would wrap instead of reporting the overflow.
+    fn simplify(&self, op: &Instruction, a: Value, b: Value) -> Option<Simplified> {
+        use Instruction::*;
+
+        // Same runtime value on both sides: x x -
+        if let (Value::Unknown(x), Value::Unknown(y)) = (a, b) {
+            if x == y {
+                return match op {
+                    Sub | Xor | Ne => Some(Simplified::Constant(0)),
+                    Eq => Some(Simplified::Constant(-1)),
+                    _ => None,
+                };
             }
+            return None;
+        }
+
+        // Constant on the right: x c op
+        match (op, b.as_constant()?) {
+            (Add | Sub | Or | Xor | Shl | Shr | Sar, 0) => Some(Simplified::Left),
+            (Mul | Div, 1) => Some(Simplified::Left),
+            (And, -1) => Some(Simplified::Left),
+            (Mul | And, 0) => Some(Simplified::Constant(0)),
+            (Mod, 1) => Some(Simplified::Constant(0)),
+            (Or, -1) => Some(Simplified::Constant(-1)),
+            (Mul, 2) => Some(Simplified::Replace(DupAdd)),
+            (Mul, c) if !self.checked && c > 2 && (c as u64).is_power_of_two() => Some(Simplified::ShiftLeft(c.trailing_zeros())),
+            _ => None,
         }
     }
 
@@ -325,28 +476,37 @@ impl ConstantFolder {
     fn fold_unary_op<F>(
         &self,
         stack: &mut AbstractStack,
+        out: &mut Vec<Instruction>,
         op: F,
         fallback: Instruction,
-    ) -> FoldResult
-    where
+    ) where
         F: FnOnce(i64) -> i64,
     {
         let a = stack.pop();
 
         match a.as_constant() {
-            Some(av) => {
-                // Constant: fold!
-                let result = op(av);
-                stack.push(Value::Constant(result));
-                FoldResult::None  // Don't emit yet, will materialize at end
-            }
+            // Constant: fold!
+            Some(av) => stack.push(Value::Constant(op(av))),
             None => {
                 // Not constant: keep original instruction
-                stack.push(Value::Unknown);
-                FoldResult::Instructions(smallvec![fallback])
+                stack.push(a);
+                stack.materialize(out);
+                stack.pop();
+                out.push(fallback);
+                stack.push_unknown();
             }
         }
     }
+
+    /// Emit a drop, cancelling it against an immediately preceding copy
+    fn emit_drop(out: &mut Vec<Instruction>) {
+        match out.last() {
+            Some(Instruction::Dup) | Some(Instruction::Over) => {
+                out.pop();
+            }
+            _ => out.push(Instruction::Drop),
+        }
+    }
 }
 
 impl Default for ConstantFolder {
@@ -355,9 +515,16 @@ impl Default for ConstantFolder {
     }
 }
 
-enum FoldResult {
-    Instructions(SmallVec<[Instruction; 4]>),
-    None, // Instruction eliminated
+/// Outcome of an algebraic simplification
+enum Simplified {
+    /// Result is the left operand unchanged (`x 0 +`)
+    Left,
+    /// Result is a constant; runtime operands are dropped (`x 0 *`)
+    Constant(i64),
+    /// Replace `x c op` with a cheaper unary instruction (`x 2 *` -> `x dup +`)
+    Replace(Instruction),
+    /// Replace `x c op` with a left shift by a constant (`x 8 *` -> `x 3 lshift`)
+    ShiftLeft(u32),
 }
 
 #[cfg(test)]
@@ -415,7 +582,7 @@ mod tests {
         let mut ir = ForthIR::new();
         // foo is a call (produces unknown value)
         ir.main = vec![
-            Instruction::Call("foo".to_string()),
+            Instruction::call("foo"),
             Instruction::Literal(5),
             Instruction::Add,
         ];
@@ -437,4 +604,243 @@ mod tests {
         assert_eq!(folded.main.len(), 1);
         assert!(matches!(folded.main[0], Instruction::Literal(3)));
     }
+
+    #[test]
+    fn test_division_by_constant_zero_rejected() {
+        let folder = ConstantFolder::new();
+        let ir = ForthIR::parse("10 0 /").unwrap();
+
+        match folder.fold(&ir) {
+            Err(OptimizerError::OptimizationFailed(msg)) => {
+                assert!(msg.contains("Division by zero"), "unexpected message: {}", msg);
+                assert!(msg.contains("instruction 2"), "unexpected message: {}", msg);
+            }
+            other => panic!("Expected OptimizationFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_modulo_by_constant_zero_rejected() {
+        let folder = ConstantFolder::new();
+        let mut ir = ForthIR::new();
+        // Dividend is unknown, divisor is a literal zero
+        ir.main = vec![
+            Instruction::call("foo"),
+            Instruction::Literal(0),
+            Instruction::Mod,
+        ];
+
+        assert!(matches!(
+            folder.fold(&ir),
+            Err(OptimizerError::OptimizationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_error_for_unknown_divisor() {
+        let folder = ConstantFolder::new();
+        let mut ir = ForthIR::new();
+        ir.main = vec![
+            Instruction::Literal(10),
+            Instruction::call("foo"),
+            Instruction::Div,
+        ];
+
+        let folded = folder.fold(&ir).unwrap();
+        assert!(folded.main.iter().any(|i| matches!(i, Instruction::Div)));
+    }
+
+    #[test]
+    fn test_fold_min_div_neg_one_wraps() {
+        let folder = ConstantFolder::new();
+        let mut ir = ForthIR::new();
+        ir.main = vec![
+            Instruction::Literal(i64::MIN),
+            Instruction::Literal(-1),
+            Instruction::Div,
+        ];
+
+        let folded = folder.fold(&ir).unwrap();
+        assert_eq!(folded.main, vec![Instruction::Literal(i64::MIN)]);
+    }
+
+    fn fold_main(main: Vec<Instruction>) -> Vec<Instruction> {
+        let mut ir = ForthIR::new();
+        ir.main = main;
+        ConstantFolder::new().fold(&ir).unwrap().main
+    }
+
+    fn foo() -> Instruction {
+        Instruction::call("foo")
+    }
+
+    #[test]
+    fn test_unknown_operand_keeps_literal() {
+        let folded = fold_main(vec![foo(), Instruction::Literal(5), Instruction::Add]);
+        assert_eq!(folded, vec![foo(), Instruction::Literal(5), Instruction::Add]);
+    }
+
+    #[test]
+    fn test_drop_of_unknown_is_kept() {
+        let folded = fold_main(vec![foo(), Instruction::Drop]);
+        assert_eq!(folded, vec![foo(), Instruction::Drop]);
+    }
+
+    #[test]
+    fn test_simplify_add_zero() {
+        let folded = fold_main(vec![foo(), Instruction::Literal(0), Instruction::Add]);
+        assert_eq!(folded, vec![foo()]);
+    }
+
+    #[test]
+    fn test_simplify_mul_one() {
+        let folded = fold_main(vec![foo(), Instruction::Literal(1), Instruction::Mul]);
+        assert_eq!(folded, vec![foo()]);
+    }
+
+    #[test]
+    fn test_simplify_mul_zero_drops_operand() {
+        let folded = fold_main(vec![foo(), Instruction::Literal(0), Instruction::Mul]);
+        assert_eq!(folded, vec![foo(), Instruction::Drop, Instruction::Literal(0)]);
+    }
+
+    #[test]
+    fn test_simplify_self_subtraction() {
+        let folded = fold_main(vec![foo(), Instruction::Dup, Instruction::Sub]);
+        assert_eq!(folded, vec![foo(), Instruction::Drop, Instruction::Literal(0)]);
+    }
+
+    #[test]
+    fn test_fold_shifts() {
+        let folded = fold_main(vec![Instruction::Literal(1), Instruction::Literal(4), Instruction::Shl]);
+        assert_eq!(folded, vec![Instruction::Literal(16)]);
+
+        // RSHIFT fills with zeros, ARSHIFT with the sign bit
+        let folded = fold_main(vec![Instruction::Literal(-16), Instruction::Literal(60), Instruction::Shr]);
+        assert_eq!(folded, vec![Instruction::Literal(15)]);
+        let folded = fold_main(vec![Instruction::Literal(-16), Instruction::Literal(2), Instruction::Sar]);
+        assert_eq!(folded, vec![Instruction::Literal(-4)]);
+
+        // Counts wrap modulo 64
+        let folded = fold_main(vec![Instruction::Literal(1), Instruction::Literal(65), Instruction::Shl]);
+        assert_eq!(folded, vec![Instruction::Literal(2)]);
+    }
+
+    #[test]
+    fn test_fold_increment_and_shift_words() {
+        assert_eq!(fold_main(vec![Instruction::Literal(5), Instruction::IncOne]), vec![Instruction::Literal(6)]);
+        assert_eq!(fold_main(vec![Instruction::Literal(5), Instruction::DecOne]), vec![Instruction::Literal(4)]);
+        assert_eq!(fold_main(vec![Instruction::Literal(-3), Instruction::MulTwo]), vec![Instruction::Literal(-6)]);
+        // 2/ shifts in the sign bit, rounding towards negative infinity
+        assert_eq!(fold_main(vec![Instruction::Literal(-3), Instruction::DivTwo]), vec![Instruction::Literal(-2)]);
+        assert_eq!(
+            fold_main(vec![Instruction::Literal(i64::MAX), Instruction::IncOne]),
+            vec![Instruction::Literal(i64::MIN)]
+        );
+
+        let mut ir = ForthIR::new();
+        ir.main = vec![Instruction::Literal(i64::MAX), Instruction::IncOne];
+        let folder = ConstantFolder::new().with_checked_arithmetic(true);
+        assert!(folder.fold(&ir).is_err());
+        ir.main = vec![Instruction::Literal(i64::MIN), Instruction::DecOne];
+        assert!(folder.fold(&ir).is_err());
+    }
+
+    #[test]
+    fn test_checked_overflow_not_folded() {
+        let mut ir = ForthIR::new();
+        ir.main = vec![Instruction::Literal(i64::MAX), Instruction::Literal(1), Instruction::Add];
+        let folder = ConstantFolder::new().with_checked_arithmetic(true);
+        assert!(matches!(folder.fold(&ir), Err(OptimizerError::OptimizationFailed(_))));
+
+        // Wrapping stays the default
+        assert_eq!(fold_main(ir.main.clone()), vec![Instruction::Literal(i64::MIN)]);
+
+        ir.main = vec![Instruction::Literal(i64::MIN), Instruction::Neg];
+        assert!(folder.fold(&ir).is_err());
+        ir.main = vec![Instruction::Literal(-5), Instruction::Neg];
+        assert_eq!(folder.fold(&ir).unwrap().main, vec![Instruction::Literal(5)]);
+    }
+
+    #[test]
+    fn test_fold_min_max() {
+        for (a, b) in [(3, 7), (7, 3)] {
+            let folded = fold_main(vec![Instruction::Literal(a), Instruction::Literal(b), Instruction::Max]);
+            assert_eq!(folded, vec![Instruction::Literal(7)]);
+            let folded = fold_main(vec![Instruction::Literal(a), Instruction::Literal(b), Instruction::Min]);
+            assert_eq!(folded, vec![Instruction::Literal(3)]);
+        }
+
+        let folded = fold_main(vec![Instruction::Literal(5), Instruction::Literal(5), Instruction::Min]);
+        assert_eq!(folded, vec![Instruction::Literal(5)]);
+        let folded = fold_main(vec![Instruction::Literal(i64::MIN), Instruction::Abs]);
+        assert_eq!(folded, vec![Instruction::Literal(i64::MIN)]);
+    }
+
+    #[test]
+    fn test_fold_unsigned_comparisons() {
+        // -1 is the largest unsigned cell
+        let folded = fold_main(vec![Instruction::Literal(-1), Instruction::Literal(1), Instruction::ULt]);
+        assert_eq!(folded, vec![Instruction::Literal(0)]);
+        let folded = fold_main(vec![Instruction::Literal(-1), Instruction::Literal(1), Instruction::UGt]);
+        assert_eq!(folded, vec![Instruction::Literal(-1)]);
+
+        let folded = fold_main(vec![Instruction::Literal(-1), Instruction::Literal(1), Instruction::Lt]);
+        assert_eq!(folded, vec![Instruction::Literal(-1)]);
+    }
+
+    #[test]
+    fn test_simplify_self_xor() {
+        let folded = fold_main(vec![foo(), Instruction::Dup, Instruction::Xor]);
+        assert_eq!(folded, vec![foo(), Instruction::Drop, Instruction::Literal(0)]);
+    }
+
+    #[test]
+    fn test_simplify_mul_two_to_dup_add() {
+        let folded = fold_main(vec![foo(), Instruction::Literal(2), Instruction::Mul]);
+        assert_eq!(folded, vec![foo(), Instruction::DupAdd]);
+    }
+
+    #[test]
+    fn test_strength_reduce_mul_by_power_of_two() {
+        let folded = fold_main(vec![foo(), Instruction::Literal(16), Instruction::Mul]);
+        assert_eq!(folded, vec![foo(), Instruction::Literal(4), Instruction::Shl]);
+
+        for constant in [3, 12, -8, i64::MIN] {
+            let main = vec![foo(), Instruction::Literal(constant), Instruction::Mul];
+            assert_eq!(fold_main(main.clone()), main);
+        }
+
+        // -7 8 / is 0 but -7 3 arshift is -1, so signed division keeps its divide
+        for op in [Instruction::Div, Instruction::Mod] {
+            let main = vec![foo(), Instruction::Literal(8), op];
+            assert_eq!(fold_main(main.clone()), main);
+        }
+
+        // A shift would wrap where checked multiplication reports the overflow
+        let mut ir = ForthIR::new();
+        ir.main = vec![foo(), Instruction::Literal(16), Instruction::Mul];
+        let folder = ConstantFolder::new().with_checked_arithmetic(true);
+        assert_eq!(folder.fold(&ir).unwrap().main, ir.main);
+    }
+
+    #[test]
+    fn test_division_by_zero_not_simplified() {
+        let mut ir = ForthIR::new();
+        ir.main = vec![foo(), Instruction::Literal(0), Instruction::Div];
+        assert!(ConstantFolder::new().fold(&ir).is_err());
+    }
+
+    #[test]
+    fn test_float_zero_add_not_simplified() {
+        // x 0.0 + is not an identity for x = -0.0
+        let main = vec![foo(), Instruction::FloatLiteral(0.0), Instruction::Add];
+        assert_eq!(fold_main(main.clone()), main);
+    }
+
+    #[test]
+    fn test_different_operands_not_cancelled() {
+        let main = vec![foo(), foo(), Instruction::Sub];
+        assert_eq!(fold_main(main.clone()), main);
+    }
 }
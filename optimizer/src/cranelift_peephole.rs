@@ -9,6 +9,8 @@ use crate::{OptimizerError, Result};
 /// Peephole optimizer for Cranelift backend
 pub struct CraneliftPeephole {
     stats: PeepholeStats,
+    /// Leave overflowing constant arithmetic unfolded instead of wrapping
+    checked: bool,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -24,9 +26,16 @@ impl CraneliftPeephole {
     pub fn new() -> Self {
         Self {
             stats: PeepholeStats::default(),
+            checked: false,
         }
     }
 
+    /// Keep `+ - *` on constants that would overflow, so they can be reported
+    pub fn with_checked_arithmetic(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
     /// Get optimization statistics
     pub fn stats(&self) -> &PeepholeStats {
         &self.stats
@@ -81,7 +90,9 @@ impl CraneliftPeephole {
     /// Examples:
     /// - MUL x, 2 → SHL x, 1
     /// - MUL x, 4 → SHL x, 2
-    /// - DIV x, 2 → SHR x, 1
+    ///
+    /// Division is left alone: `/` truncates, while a shift rounds towards
+    /// negative infinity.
     fn strength_reduction(&mut self, instructions: &mut Vec<Instruction>) -> Result<bool> {
         let mut changed = false;
         let mut i = 0;
@@ -97,15 +108,6 @@ impl CraneliftPeephole {
                     changed = true;
                 }
 
-                // Pattern: Literal(power_of_2), Div → Literal(log2), Shr
-                (Instruction::Literal(n), Instruction::Div) if is_power_of_2(*n) && *n > 0 => {
-                    let shift_amount = (*n as u64).trailing_zeros() as i64;
-                    instructions[i] = Instruction::Literal(shift_amount);
-                    instructions[i + 1] = Instruction::Shr;
-                    self.stats.strength_reductions += 1;
-                    changed = true;
-                }
-
                 // Pattern: Literal(2), Mul → MulTwo (superinstruction)
                 (Instruction::Literal(2), Instruction::Mul) => {
                     instructions.splice(i..=i+1, vec![Instruction::MulTwo]);
@@ -114,14 +116,6 @@ impl CraneliftPeephole {
                     continue; // Don't increment i since we removed an instruction
                 }
 
-                // Pattern: Literal(2), Div → DivTwo (superinstruction)
-                (Instruction::Literal(2), Instruction::Div) => {
-                    instructions.splice(i..=i+1, vec![Instruction::DivTwo]);
-                    self.stats.strength_reductions += 1;
-                    changed = true;
-                    continue;
-                }
-
                 // Pattern: Literal(1), Add → IncOne
                 (Instruction::Literal(1), Instruction::Add) => {
                     instructions.splice(i..=i+1, vec![Instruction::IncOne]);
@@ -156,10 +150,15 @@ impl CraneliftPeephole {
         let mut changed = false;
         let mut i = 0;
 
-        while i < instructions.len().saturating_sub(2) {
-            match (&instructions[i], &instructions[i + 1], &instructions[i + 2]) {
+        // Run up to the last pair, so a trailing `Literal, unary op` folds too;
+        // past the end a Nop stands in for the third instruction
+        while i < instructions.len().saturating_sub(1) {
+            let third = instructions.get(i + 2).unwrap_or(&Instruction::Nop);
+            match (&instructions[i], &instructions[i + 1], third) {
                 // Binary arithmetic operations
-                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Add) => {
+                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Add)
+                    if !self.checked || a.checked_add(*b).is_some() =>
+                {
                     let result = a.wrapping_add(*b);
                     instructions.splice(i..=i+2, vec![Instruction::Literal(result)]);
                     self.stats.constant_folds += 1;
@@ -167,7 +166,9 @@ impl CraneliftPeephole {
                     continue;
                 }
 
-                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Sub) => {
+                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Sub)
+                    if !self.checked || a.checked_sub(*b).is_some() =>
+                {
                     let result = a.wrapping_sub(*b);
                     instructions.splice(i..=i+2, vec![Instruction::Literal(result)]);
                     self.stats.constant_folds += 1;
@@ -175,7 +176,9 @@ impl CraneliftPeephole {
                     continue;
                 }
 
-                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Mul) => {
+                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Mul)
+                    if !self.checked || a.checked_mul(*b).is_some() =>
+                {
                     let result = a.wrapping_mul(*b);
                     instructions.splice(i..=i+2, vec![Instruction::Literal(result)]);
                     self.stats.constant_folds += 1;
@@ -223,6 +226,14 @@ impl CraneliftPeephole {
                 }
 
                 (Instruction::Literal(a), Instruction::Literal(b), Instruction::Shr) if *b >= 0 && *b < 64 => {
+                    let result = (*a as u64 >> *b) as i64;
+                    instructions.splice(i..=i+2, vec![Instruction::Literal(result)]);
+                    self.stats.constant_folds += 1;
+                    changed = true;
+                    continue;
+                }
+
+                (Instruction::Literal(a), Instruction::Literal(b), Instruction::Sar) if *b >= 0 && *b < 64 => {
                     let result = a.wrapping_shr(*b as u32);
                     instructions.splice(i..=i+2, vec![Instruction::Literal(result)]);
                     self.stats.constant_folds += 1;
@@ -257,6 +268,51 @@ impl CraneliftPeephole {
                         continue;
                     }
 
+                    (Instruction::Literal(a), Instruction::IncOne)
+                        if !self.checked || a.checked_add(1).is_some() =>
+                    {
+                        instructions.splice(i..=i+1, vec![Instruction::Literal(a.wrapping_add(1))]);
+                        self.stats.constant_folds += 1;
+                        changed = true;
+                        continue;
+                    }
+
+                    (Instruction::Literal(a), Instruction::DecOne)
+                        if !self.checked || a.checked_sub(1).is_some() =>
+                    {
+                        instructions.splice(i..=i+1, vec![Instruction::Literal(a.wrapping_sub(1))]);
+                        self.stats.constant_folds += 1;
+                        changed = true;
+                        continue;
+                    }
+
+                    (Instruction::Literal(a), Instruction::MulTwo) => {
+                        instructions.splice(i..=i+1, vec![Instruction::Literal(a.wrapping_shl(1))]);
+                        self.stats.constant_folds += 1;
+                        changed = true;
+                        continue;
+                    }
+
+                    (Instruction::Literal(a), Instruction::DivTwo) => {
+                        instructions.splice(i..=i+1, vec![Instruction::Literal(a >> 1)]);
+                        self.stats.constant_folds += 1;
+                        changed = true;
+                        continue;
+                    }
+
+                    // Comparisons against zero give -1 for true, like ConstantFolder
+                    (Instruction::Literal(a), op @ (Instruction::ZeroEq | Instruction::ZeroLt | Instruction::ZeroGt)) => {
+                        let holds = match op {
+                            Instruction::ZeroEq => *a == 0,
+                            Instruction::ZeroLt => *a < 0,
+                            _ => *a > 0,
+                        };
+                        instructions.splice(i..=i+1, vec![Instruction::Literal(-(holds as i64))]);
+                        self.stats.constant_folds += 1;
+                        changed = true;
+                        continue;
+                    }
+
                     _ => {}
                 }
             }
@@ -370,10 +426,17 @@ mod tests {
 
         peephole.optimize_word(&mut word).unwrap();
 
-        assert_eq!(word.instructions.len(), 2);
-        assert_eq!(word.instructions[0], Instruction::Literal(2));
-        assert_eq!(word.instructions[1], Instruction::Shr);
-        assert_eq!(peephole.stats.strength_reductions, 1);
+        // Division truncates, which no shift does for negative dividends
+        assert_eq!(word.instructions, vec![Instruction::Literal(4), Instruction::Div]);
+        assert_eq!(peephole.stats.strength_reductions, 0);
+
+        let mut word = create_test_word(vec![
+            Instruction::Literal(-7),
+            Instruction::Literal(2),
+            Instruction::Div,
+        ]);
+        peephole.optimize_word(&mut word).unwrap();
+        assert_eq!(word.instructions, vec![Instruction::Literal(-3)]);
     }
 
     #[test]
@@ -407,6 +470,51 @@ mod tests {
         assert_eq!(word.instructions[0], Instruction::Literal(42));
     }
 
+    #[test]
+    fn test_constant_folding_increment_and_shift_words() {
+        let cases = [
+            (5, Instruction::IncOne, 6),
+            (5, Instruction::DecOne, 4),
+            (-3, Instruction::MulTwo, -6),
+            (-3, Instruction::DivTwo, -2),
+            (i64::MAX, Instruction::IncOne, i64::MIN),
+        ];
+        for (value, op, expected) in cases {
+            let mut peephole = CraneliftPeephole::new();
+            let mut word = create_test_word(vec![Instruction::Literal(value), op.clone()]);
+
+            peephole.optimize_word(&mut word).unwrap();
+
+            assert_eq!(word.instructions, vec![Instruction::Literal(expected)], "{} {}", value, op);
+        }
+
+        // Checked arithmetic leaves the overflow for the runtime to report
+        let mut peephole = CraneliftPeephole::new().with_checked_arithmetic(true);
+        let mut word = create_test_word(vec![Instruction::Literal(i64::MAX), Instruction::IncOne]);
+        peephole.optimize_word(&mut word).unwrap();
+        assert_eq!(word.instructions, vec![Instruction::Literal(i64::MAX), Instruction::IncOne]);
+    }
+
+    #[test]
+    fn test_constant_folding_zero_comparisons() {
+        let cases = [
+            (0, Instruction::ZeroEq, -1),
+            (7, Instruction::ZeroEq, 0),
+            (i64::MIN, Instruction::ZeroLt, -1),
+            (0, Instruction::ZeroLt, 0),
+            (1, Instruction::ZeroGt, -1),
+            (-1, Instruction::ZeroGt, 0),
+        ];
+        for (value, op, expected) in cases {
+            let mut peephole = CraneliftPeephole::new();
+            let mut word = create_test_word(vec![Instruction::Literal(value), op.clone()]);
+
+            peephole.optimize_word(&mut word).unwrap();
+
+            assert_eq!(word.instructions, vec![Instruction::Literal(expected)], "{} {}", value, op);
+        }
+    }
+
     #[test]
     fn test_dead_store_elimination_dup_drop() {
         let mut peephole = CraneliftPeephole::new();
@@ -0,0 +1,377 @@
+//! Common Subexpression Elimination
+//!
+//! Uses local value numbering over the abstract stack to find pure operations
+//! that recompute a value already on the stack. The redundant operation is
+//! replaced by dropping its operands and copying the earlier result.
+//!
+//! # Example
+//!
+//! Before:
+//! ```forth
+//! dup dup * swap dup *    \ computes x*x twice
+//! ```
+//!
+//! After:
+//! ```forth
+//! dup dup * swap drop dup
+//! ```
+//!
+//! Only pure arithmetic, bitwise and comparison operations are eligible.
+//! `Load` results are always treated as fresh values, so expressions on either
+//! side of a `Store` are never merged through memory. Analysis is local to a
+//! basic block: calls, branches, labels and any instruction with an unmodeled
+//! stack effect end the block and forget all available expressions.
+
+use crate::ir::{ForthIR, Instruction, WordDef};
+use crate::Result;
+use smallvec::{smallvec, SmallVec};
+use std::collections::HashMap;
+use std::mem::Discriminant;
+
+/// Value number assigned to a stack item
+type ValueId = usize;
+
+/// Key identifying a pure computation: operation and operand value numbers
+type ExprKey = (Discriminant<Instruction>, SmallVec<[ValueId; 2]>);
+
+/// Abstract stack of value numbers for a single basic block
+struct ValueStack {
+    /// Known items, top of stack last
+    items: Vec<ValueId>,
+    /// Next fresh value number
+    next_value: ValueId,
+}
+
+impl ValueStack {
+    fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            next_value: 0,
+        }
+    }
+
+    fn fresh(&mut self) -> ValueId {
+        let id = self.next_value;
+        self.next_value += 1;
+        id
+    }
+
+    /// Make sure at least `n` items are modeled, inventing values for block inputs
+    fn ensure(&mut self, n: usize) {
+        while self.items.len() < n {
+            let id = self.fresh();
+            self.items.insert(0, id);
+        }
+    }
+
+    fn push(&mut self, id: ValueId) {
+        self.items.push(id);
+    }
+
+    fn pop(&mut self) -> ValueId {
+        self.ensure(1);
+        self.items.pop().unwrap()
+    }
+
+    /// Value at `depth` (0 = top of stack)
+    fn peek(&mut self, depth: usize) -> ValueId {
+        self.ensure(depth + 1);
+        self.items[self.items.len() - 1 - depth]
+    }
+
+    /// Depth of the topmost occurrence of `id`, if any
+    fn depth_of(&self, id: ValueId) -> Option<usize> {
+        self.items.iter().rev().position(|&item| item == id)
+    }
+
+    /// Forget everything (block boundary)
+    fn reset(&mut self) {
+        self.items.clear();
+    }
+}
+
+/// Common subexpression eliminator
+pub struct CommonSubexpressionEliminator;
+
+impl CommonSubexpressionEliminator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Eliminate common subexpressions in IR
+    pub fn eliminate(&self, ir: &ForthIR) -> Result<ForthIR> {
+        let mut optimized = ir.clone();
+
+        optimized.main = self.eliminate_sequence(&ir.main);
+
+        for (name, word) in ir.words.iter() {
+            optimized.words.insert(name.clone(), self.eliminate_word(word));
+        }
+
+        Ok(optimized)
+    }
+
+    /// Eliminate common subexpressions in a word definition
+    fn eliminate_word(&self, word: &WordDef) -> WordDef {
+        let mut optimized = word.clone();
+        optimized.instructions = self.eliminate_sequence(&word.instructions);
+        optimized.update();
+        optimized
+    }
+
+    /// Eliminate common subexpressions in an instruction sequence
+    fn eliminate_sequence(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        use Instruction::*;
+
+        let mut result = Vec::with_capacity(instructions.len());
+        let mut stack = ValueStack::new();
+        let mut literals: HashMap<i64, ValueId> = HashMap::new();
+        let mut available: HashMap<ExprKey, ValueId> = HashMap::new();
+
+        for inst in instructions {
+            match inst {
+                Literal(v) => {
+                    let id = match literals.get(v) {
+                        Some(&id) => id,
+                        None => {
+                            let id = stack.fresh();
+                            literals.insert(*v, id);
+                            id
+                        }
+                    };
+                    stack.push(id);
+                    result.push(inst.clone());
+                }
+
+                Dup => {
+                    let a = stack.peek(0);
+                    stack.push(a);
+                    result.push(inst.clone());
+                }
+                Drop => {
+                    stack.pop();
+                    result.push(inst.clone());
+                }
+                Swap => {
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    stack.push(b);
+                    stack.push(a);
+                    result.push(inst.clone());
+                }
+                Over => {
+                    let a = stack.peek(1);
+                    stack.push(a);
+                    result.push(inst.clone());
+                }
+                Rot => {
+                    let c = stack.pop();
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    stack.push(b);
+                    stack.push(c);
+                    stack.push(a);
+                    result.push(inst.clone());
+                }
+                Nip => {
+                    let b = stack.pop();
+                    stack.pop();
+                    stack.push(b);
+                    result.push(inst.clone());
+                }
+                Tuck => {
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    stack.push(b);
+                    stack.push(a);
+                    stack.push(b);
+                    result.push(inst.clone());
+                }
+
+                Add | Sub | Mul | Div | Mod | Min | Max | And | Or | Xor | Shl | Shr | Sar | Eq | Ne
+                | Lt | Le | Gt | Ge | ULt | UGt => {
+                    let b = stack.pop();
+                    let a = stack.pop();
+                    let operands: SmallVec<[ValueId; 2]> = if Self::is_commutative(inst) && b < a {
+                        smallvec![b, a]
+                    } else {
+                        smallvec![a, b]
+                    };
+                    self.emit_pure(inst, operands, &mut stack, &mut available, &mut result);
+                }
+
+                Neg | Abs | Not | ZeroEq | ZeroLt | ZeroGt => {
+                    let a = stack.pop();
+                    self.emit_pure(inst, smallvec![a], &mut stack, &mut available, &mut result);
+                }
+
+                // Loads may observe different memory each time: always a fresh value
+                Load | Load8 => {
+                    stack.pop();
+                    let id = stack.fresh();
+                    stack.push(id);
+                    result.push(inst.clone());
+                }
+                Store | Store8 => {
+                    stack.pop();
+                    stack.pop();
+                    result.push(inst.clone());
+                }
+
+                Comment(_) | Nop => result.push(inst.clone()),
+
+                // Block boundaries and unmodeled stack effects
+                _ => {
+                    stack.reset();
+                    available.clear();
+                    result.push(inst.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Emit a pure operation whose operands have already been popped
+    ///
+    /// If the same computation is still on the stack within reach of `dup` or
+    /// `over`, the operation is replaced by dropping its operands and copying
+    /// the earlier result.
+    fn emit_pure(
+        &self,
+        inst: &Instruction,
+        operands: SmallVec<[ValueId; 2]>,
+        stack: &mut ValueStack,
+        available: &mut HashMap<ExprKey, ValueId>,
+        result: &mut Vec<Instruction>,
+    ) {
+        let key = (std::mem::discriminant(inst), operands);
+        let arity = key.1.len();
+
+        if let Some(&existing) = available.get(&key) {
+            let copy = match stack.depth_of(existing) {
+                Some(0) => Some(Instruction::Dup),
+                Some(1) => Some(Instruction::Over),
+                _ => None,
+            };
+
+            if let Some(copy) = copy {
+                for _ in 0..arity {
+                    Self::emit_drop(result);
+                }
+                result.push(copy);
+                stack.push(existing);
+                return;
+            }
+        }
+
+        let id = stack.fresh();
+        available.insert(key, id);
+        stack.push(id);
+        result.push(inst.clone());
+    }
+
+    /// Emit a drop, cancelling it against an immediately preceding pure push
+    fn emit_drop(result: &mut Vec<Instruction>) {
+        match result.last() {
+            Some(Instruction::Dup) | Some(Instruction::Over) | Some(Instruction::Literal(_)) => {
+                result.pop();
+            }
+            _ => result.push(Instruction::Drop),
+        }
+    }
+
+    fn is_commutative(inst: &Instruction) -> bool {
+        matches!(
+            inst,
+            Instruction::Add
+                | Instruction::Mul
+                | Instruction::Min
+                | Instruction::Max
+                | Instruction::And
+                | Instruction::Or
+                | Instruction::Xor
+                | Instruction::Eq
+                | Instruction::Ne
+        )
+    }
+}
+
+impl Default for CommonSubexpressionEliminator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_mul(instructions: &[Instruction]) -> usize {
+        instructions
+            .iter()
+            .filter(|i| matches!(i, Instruction::Mul))
+            .count()
+    }
+
+    #[test]
+    fn test_eliminate_repeated_square() {
+        let cse = CommonSubexpressionEliminator::new();
+        let ir = ForthIR::parse("dup dup * swap dup *").unwrap();
+        assert_eq!(count_mul(&ir.main), 2);
+
+        let optimized = cse.eliminate(&ir).unwrap();
+        assert_eq!(count_mul(&optimized.main), 1);
+        assert_eq!(
+            optimized.main,
+            vec![
+                Instruction::Dup,
+                Instruction::Dup,
+                Instruction::Mul,
+                Instruction::Swap,
+                Instruction::Drop,
+                Instruction::Dup,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commutative_operands() {
+        let cse = CommonSubexpressionEliminator::new();
+        // a b + then b a + is the same sum
+        let ir = ForthIR::parse("over over + rot rot swap +").unwrap();
+        let optimized = cse.eliminate(&ir).unwrap();
+
+        let adds = optimized.main.iter().filter(|i| matches!(i, Instruction::Add)).count();
+        assert_eq!(adds, 1);
+    }
+
+    #[test]
+    fn test_loads_not_merged_across_store() {
+        let cse = CommonSubexpressionEliminator::new();
+        let ir = ForthIR::parse("100 @ dup * 5 100 ! 100 @ dup *").unwrap();
+        let optimized = cse.eliminate(&ir).unwrap();
+
+        assert_eq!(count_mul(&optimized.main), 2);
+        assert_eq!(optimized.main, ir.main);
+    }
+
+    #[test]
+    fn test_calls_end_basic_block() {
+        let cse = CommonSubexpressionEliminator::new();
+        let ir = ForthIR::parse("dup dup * foo dup dup *").unwrap();
+        let optimized = cse.eliminate(&ir).unwrap();
+
+        assert_eq!(count_mul(&optimized.main), 2);
+    }
+
+    #[test]
+    fn test_result_out_of_reach_kept() {
+        let cse = CommonSubexpressionEliminator::new();
+        // The first product is buried three items deep when recomputed
+        let ir = ForthIR::parse("dup dup * swap 1 2 rot dup *").unwrap();
+        let optimized = cse.eliminate(&ir).unwrap();
+
+        assert_eq!(optimized.main, ir.main);
+    }
+}
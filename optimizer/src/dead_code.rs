@@ -23,9 +23,23 @@
 //! ```forth
 //! (empty - identity operation)
 //! ```
-
-use crate::ir::{ForthIR, Instruction, WordDef};
-use crate::Result;
+//!
+//! Stores to a variable slot that are overwritten before anything can read
+//! the slot are dead too:
+//!
+//! ```forth
+//! variable x  3 x !  5 x !  x @    \ -> 5 x !  x @
+//! ```
+//!
+//! Only named variables (`ForthIR::variables`) are tracked, since their
+//! storage is never aliased by another name. Stores through any other
+//! address, including memory-mapped I/O, are always kept. A slot is assumed
+//! to be read by calls, by loads from untracked addresses, by any use of its
+//! address other than an immediate `@` or `!`, and at control flow.
+
+use crate::explain::{self, Notes, OptimizationNote};
+use crate::ir::{ForthIR, Instruction, StackEffect, WordDef};
+use crate::{PassKind, Result};
 use std::collections::HashSet;
 
 /// Liveness analysis result
@@ -48,14 +62,29 @@ impl DeadCodeEliminator {
 
     /// Eliminate dead code in IR
     pub fn eliminate(&self, ir: &ForthIR) -> Result<ForthIR> {
+        self.eliminate_noting(ir, &mut Notes::new(PassKind::DeadCode, false))
+    }
+
+    /// Eliminate dead code in IR, noting every instruction removed and why
+    pub fn eliminate_explained(&self, ir: &ForthIR) -> Result<(ForthIR, Vec<OptimizationNote>)> {
+        let mut notes = Notes::new(PassKind::DeadCode, true);
+        let optimized = self.eliminate_noting(ir, &mut notes)?;
+        Ok((optimized, notes.into_vec()))
+    }
+
+    fn eliminate_noting(&self, ir: &ForthIR, notes: &mut Notes) -> Result<ForthIR> {
         let mut optimized = ir.clone();
 
         // Eliminate in main sequence
-        optimized.main = self.eliminate_sequence(&ir.main)?;
-
-        // Eliminate in each word
-        for (name, word) in ir.words.iter() {
-            let optimized_word = self.eliminate_word(word)?;
+        notes.set_word(None);
+        optimized.main = self.eliminate_sequence(&ir.main, &ir.variables, notes)?;
+
+        // Eliminate in each word, in name order so notes come out the same way every time
+        let mut names: Vec<&String> = ir.words.keys().collect();
+        names.sort();
+        for name in names {
+            notes.set_word(Some(name));
+            let optimized_word = self.eliminate_word(&ir.words[name], &ir.variables, notes)?;
             optimized.words.insert(name.clone(), optimized_word);
         }
 
@@ -63,34 +92,129 @@ impl DeadCodeEliminator {
     }
 
     /// Eliminate dead code in a word definition
-    fn eliminate_word(&self, word: &WordDef) -> Result<WordDef> {
+    fn eliminate_word(&self, word: &WordDef, variables: &HashSet<String>, notes: &mut Notes) -> Result<WordDef> {
         let mut optimized = word.clone();
-        optimized.instructions = self.eliminate_sequence(&word.instructions)?;
+        optimized.instructions = self.eliminate_sequence(&word.instructions, variables, notes)?;
         optimized.update();
         Ok(optimized)
     }
 
     /// Eliminate dead code in an instruction sequence
-    fn eliminate_sequence(&self, instructions: &[Instruction]) -> Result<Vec<Instruction>> {
+    ///
+    /// Each step also returns, for every instruction it keeps, the index in
+    /// `instructions` it came from, so notes refer to the code as it was.
+    fn eliminate_sequence(
+        &self,
+        instructions: &[Instruction],
+        variables: &HashSet<String>,
+        notes: &mut Notes,
+    ) -> Result<Vec<Instruction>> {
+        // Dead stores leave their value behind as a `drop` for the passes below
+        let (instructions, origins) = self.eliminate_dead_stores(instructions, variables, notes);
+
         // First pass: remove trivial operations
-        let mut result = self.remove_trivial_ops(instructions);
+        let (result, origins) = self.remove_trivial_ops(&instructions, &origins, notes);
 
         // Perform liveness analysis on simplified code
-        let liveness = self.analyze_liveness(&result);
+        let liveness = self.analyze_liveness(&result, variables);
 
         // Remove dead instructions
         let mut filtered = Vec::new();
         for (i, inst) in result.iter().enumerate() {
             if self.should_keep(inst, i, &liveness) {
                 filtered.push(inst.clone());
+            } else {
+                notes.add(Some(origins[i]), || format!("`{}` eliminated: result unused", inst));
             }
         }
 
         Ok(filtered)
     }
 
+    /// Replace stores to variable slots that are overwritten before any read
+    /// with a `drop` of the stored value
+    fn eliminate_dead_stores(
+        &self,
+        instructions: &[Instruction],
+        variables: &HashSet<String>,
+        notes: &mut Notes,
+    ) -> (Vec<Instruction>, Vec<usize>) {
+        let mut result = Vec::with_capacity(instructions.len());
+        let mut origins = Vec::with_capacity(instructions.len());
+        let mut i = 0;
+
+        while i < instructions.len() {
+            origins.push(i);
+            match Self::slot_access(instructions, i, variables) {
+                Some((slot, Instruction::Store)) if self.is_overwritten(instructions, i + 2, slot, variables) => {
+                    notes.add(Some(i), || format!("store to `{}` eliminated: overwritten before it is read", slot));
+                    result.push(Instruction::Drop);
+                    i += 2;
+                }
+                _ => {
+                    result.push(instructions[i].clone());
+                    i += 1;
+                }
+            }
+        }
+
+        (result, origins)
+    }
+
+    /// Check whether `slot` is stored to again, starting at `start`, before
+    /// anything might read it
+    fn is_overwritten(&self, instructions: &[Instruction], start: usize, slot: &str, variables: &HashSet<String>) -> bool {
+        use Instruction::*;
+
+        let mut i = start;
+        while i < instructions.len() {
+            if let Some((accessed, op)) = Self::slot_access(instructions, i, variables) {
+                match op {
+                    Store if accessed == slot => return true,
+                    Load | Load8 | Store8 if accessed != slot => {}
+                    Store => {}
+                    _ => return false,
+                }
+                i += 2;
+                continue;
+            }
+
+            match &instructions[i] {
+                // Any other use of the slot's address may read through it
+                Call(name) if name == slot => return false,
+                Call(name) if variables.contains(name.as_str()) => {}
+                Load | Load8 | Label(_) | Branch(_) | BranchIf(_) | BranchIfNot(_) | BranchIfElse(..) | Return => {
+                    return false
+                }
+                Store | Store8 | FlushCache | SpillCache | FillCache => {}
+                inst if inst.is_pure() => {}
+                _ => return false,
+            }
+            i += 1;
+        }
+
+        // The value outlives the sequence
+        false
+    }
+
+    /// Variable slot and memory operation when `index` starts `Call(slot) op`
+    fn slot_access<'a>(
+        instructions: &'a [Instruction],
+        index: usize,
+        variables: &HashSet<String>,
+    ) -> Option<(&'a str, &'a Instruction)> {
+        match instructions.get(index..index + 2)? {
+            [Instruction::Call(slot), op @ (Instruction::Load | Instruction::Load8 | Instruction::Store | Instruction::Store8)]
+                if variables.contains(slot.as_str()) =>
+            {
+                Some((slot.as_str(), op))
+            }
+            _ => None,
+        }
+    }
+
     /// Analyze which instructions are live (their results are used)
-    fn analyze_liveness(&self, instructions: &[Instruction]) -> LivenessInfo {
+    fn analyze_liveness(&self, instructions: &[Instruction], variables: &HashSet<String>) -> LivenessInfo {
         let mut live = HashSet::new();
 
         // Simple backward analysis: mark instructions as live if they have side effects
@@ -105,7 +229,7 @@ impl DeadCodeEliminator {
         // Calculate final stack depth to mark final values as live
         let mut final_stack_depth = 0i32;
         for inst in instructions.iter() {
-            let effect = inst.stack_effect();
+            let effect = Self::stack_effect(inst, variables);
             final_stack_depth += effect.produced as i32 - effect.consumed as i32;
         }
 
@@ -116,7 +240,13 @@ impl DeadCodeEliminator {
             let mut stack_depth = final_stack_depth; // Start with final depth
 
             for (i, inst) in instructions.iter().enumerate().rev() {
-                let effect = inst.stack_effect();
+                // Values left at the end of a block flow into another block or
+                // out of the word, so they are all live
+                if matches!(inst, Instruction::Label(_) | Instruction::Return) || !inst.branch_targets().is_empty() {
+                    stack_depth = i32::MAX / 2;
+                }
+
+                let effect = Self::stack_effect(inst, variables);
 
                 // If this instruction produces values and stack is needed, it's live
                 if effect.produced > 0 && stack_depth > 0 {
@@ -138,6 +268,14 @@ impl DeadCodeEliminator {
         }
     }
 
+    /// Stack effect of an instruction, knowing that variable slots push their address
+    fn stack_effect(inst: &Instruction, variables: &HashSet<String>) -> StackEffect {
+        match inst {
+            Instruction::Call(name) if variables.contains(name.as_str()) => StackEffect::new(0, 1),
+            _ => inst.stack_effect(),
+        }
+    }
+
     /// Check if instruction should be kept
     fn should_keep(&self, inst: &Instruction, index: usize, liveness: &LivenessInfo) -> bool {
         use Instruction::*;
@@ -152,9 +290,10 @@ impl DeadCodeEliminator {
             return true;
         }
 
-        // Keep control flow and metadata
+        // Keep control flow and metadata, and drops not paired with their
+        // value by `remove_trivial_ops`
         match inst {
-            Branch(_) | BranchIf(_) | BranchIfNot(_) | Return | Label(_) | Comment(_) => true,
+            Branch(_) | BranchIf(_) | BranchIfNot(_) | BranchIfElse(..) | Return | Label(_) | Comment(_) | Drop => true,
 
             // Remove dead pure instructions
             _ if inst.is_pure() => false,
@@ -164,54 +303,59 @@ impl DeadCodeEliminator {
     }
 
     /// Remove trivial operations (second pass)
-    fn remove_trivial_ops(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+    ///
+    /// `origins` holds the original index of each instruction and is returned
+    /// for the instructions kept.
+    fn remove_trivial_ops(
+        &self,
+        instructions: &[Instruction],
+        origins: &[usize],
+        notes: &mut Notes,
+    ) -> (Vec<Instruction>, Vec<usize>) {
         let mut result = Vec::new();
+        let mut kept = Vec::new();
         let mut i = 0;
 
         while i < instructions.len() {
-            match &instructions[i..] {
+            let removed = match &instructions[i..] {
                 // dup drop -> (remove both)
-                [Instruction::Dup, Instruction::Drop, ..] => {
-                    i += 2; // Skip both
-                }
+                [Instruction::Dup, Instruction::Drop, ..] => 2,
 
                 // swap swap -> (remove both)
-                [Instruction::Swap, Instruction::Swap, ..] => {
-                    i += 2;
-                }
+                [Instruction::Swap, Instruction::Swap, ..] => 2,
 
                 // over drop drop -> (remove all)
-                [Instruction::Over, Instruction::Drop, Instruction::Drop, ..] => {
-                    i += 3;
-                }
+                [Instruction::Over, Instruction::Drop, Instruction::Drop, ..] => 3,
 
                 // Nop -> (remove)
-                [Instruction::Nop, ..] => {
-                    i += 1;
-                }
+                [Instruction::Nop, ..] => 1,
 
                 // literal drop -> (remove both if no side effects)
-                [Instruction::Literal(_), Instruction::Drop, ..] => {
-                    i += 2;
-                }
+                [Instruction::Literal(_), Instruction::Drop, ..] => 2,
 
                 // Binary operation followed by drop -> remove all 3 (if literals)
                 [Instruction::Literal(_), Instruction::Literal(_), op, Instruction::Drop, ..]
                     if matches!(op, Instruction::Add | Instruction::Sub | Instruction::Mul
                                   | Instruction::Div | Instruction::Mod | Instruction::And
-                                  | Instruction::Or | Instruction::Xor) => {
-                    i += 4; // Skip all 4
-                }
+                                  | Instruction::Or | Instruction::Xor) => 4,
 
                 // Keep instruction
-                _ => {
-                    result.push(instructions[i].clone());
-                    i += 1;
-                }
+                _ => 0,
+            };
+
+            if removed == 0 {
+                result.push(instructions[i].clone());
+                kept.push(origins[i]);
+                i += 1;
+            } else {
+                notes.add(Some(origins[i]), || {
+                    format!("`{}` eliminated: no effect", explain::source(&instructions[i..i + removed]))
+                });
+                i += removed;
             }
         }
 
-        result
+        (result, kept)
     }
 
     /// Get statistics about dead code elimination
@@ -362,4 +506,82 @@ mod tests {
         let stats = eliminator.get_stats(&ir, &optimized);
         assert!(stats.instructions_eliminated > 0);
     }
+
+    fn call(name: &str) -> Instruction {
+        Instruction::call(name)
+    }
+
+    #[test]
+    fn test_overwritten_store_removed() {
+        let eliminator = DeadCodeEliminator::new();
+        let ir = ForthIR::parse("variable x 3 x ! 5 x ! x @").unwrap();
+        let optimized = eliminator.eliminate(&ir).unwrap();
+
+        assert_eq!(
+            optimized.main,
+            vec![Instruction::Literal(5), call("x"), Instruction::Store, call("x"), Instruction::Load]
+        );
+    }
+
+    #[test]
+    fn test_store_read_before_overwrite_kept() {
+        let eliminator = DeadCodeEliminator::new();
+
+        // Read through the slot itself
+        let ir = ForthIR::parse("variable x 3 x ! x @ 5 x ! x @ +").unwrap();
+        assert_eq!(eliminator.eliminate(&ir).unwrap().main, ir.main);
+
+        // The address escapes into a call, which may read it
+        let ir = ForthIR::parse("variable x 3 x ! x show 5 x !").unwrap();
+        assert_eq!(eliminator.eliminate(&ir).unwrap().main, ir.main);
+
+        // Any call may read the slot
+        let ir = ForthIR::parse("variable x 3 x ! show 5 x !").unwrap();
+        assert_eq!(eliminator.eliminate(&ir).unwrap().main, ir.main);
+
+        // The last store is visible after the sequence
+        let ir = ForthIR::parse("variable x 3 x !").unwrap();
+        assert_eq!(eliminator.eliminate(&ir).unwrap().main, ir.main);
+    }
+
+    #[test]
+    fn test_untracked_addresses_kept() {
+        let eliminator = DeadCodeEliminator::new();
+
+        // Raw addresses may be memory-mapped I/O
+        let ir = ForthIR::parse("3 4096 ! 5 4096 !").unwrap();
+        assert_eq!(eliminator.eliminate(&ir).unwrap().main, ir.main);
+    }
+
+    #[test]
+    fn test_other_slots_do_not_block() {
+        let eliminator = DeadCodeEliminator::new();
+        let ir = ForthIR::parse("variable x variable y 3 x ! y @ drop 1 y ! 5 x !").unwrap();
+        let optimized = eliminator.eliminate(&ir).unwrap();
+
+        let stores_to_x = optimized
+            .main
+            .windows(2)
+            .filter(|w| w[0] == call("x") && w[1] == Instruction::Store)
+            .count();
+        assert_eq!(stores_to_x, 1);
+    }
+
+    #[test]
+    fn test_explained_notes_use_original_indices() {
+        let eliminator = DeadCodeEliminator::new();
+        let ir = ForthIR::parse("variable x 3 x ! 5 x ! dup drop x @").unwrap();
+        let (optimized, notes) = eliminator.eliminate_explained(&ir).unwrap();
+        assert_eq!(optimized, eliminator.eliminate(&ir).unwrap());
+
+        let notes: Vec<String> = notes.iter().map(|note| note.to_string()).collect();
+        assert_eq!(
+            notes,
+            [
+                "dead-code: main@1: store to `x` eliminated: overwritten before it is read",
+                "dead-code: main@0: `3 drop` eliminated: no effect",
+                "dead-code: main@6: `dup drop` eliminated: no effect",
+            ]
+        );
+    }
 }
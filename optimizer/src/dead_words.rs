@@ -0,0 +1,126 @@
+//! Whole-Program Dead Word Elimination
+//!
+//! Drops word definitions that can never run: words not called from `main`,
+//! directly or through other words. Reachability is computed over the
+//! [`CallGraph`], so helpers called only by dead words and recursive clusters
+//! that nothing outside the cluster calls are dropped too.
+//!
+//! # Example
+//!
+//! Before:
+//! ```forth
+//! : helper 1 + ;
+//! : unused 2 * ;
+//! : trace unused trace ;
+//! 5 helper
+//! ```
+//!
+//! After:
+//! ```forth
+//! : helper 1 + ;
+//! 5 helper
+//! ```
+//!
+//! Words with [`is_exported`](crate::ir::WordDef::is_exported) set are
+//! roots like `main`, so a word meant to be called from outside the program
+//! (a library entry point, a word the REPL will call later) is kept along
+//! with everything it calls.
+
+use crate::aggressive_inline::CallGraph;
+use crate::ir::{ForthIR, Instruction};
+use crate::Result;
+
+/// Whole-program dead word eliminator
+pub struct DeadWordEliminator;
+
+impl DeadWordEliminator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Remove every word not reachable from `main` or an exported word
+    pub fn eliminate(&self, ir: &ForthIR) -> Result<ForthIR> {
+        let roots = ir
+            .main
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::Call(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .chain(ir.words.values().filter(|word| word.is_exported).map(|word| word.name.as_str()));
+        let live = CallGraph::build(ir).reachable_from(roots);
+
+        let mut optimized = ir.clone();
+        optimized.words.retain(|name, _| live.contains(name));
+        Ok(optimized)
+    }
+
+    /// Names of the words `eliminate` would remove, sorted
+    pub fn dead_words(&self, ir: &ForthIR) -> Result<Vec<String>> {
+        let live = self.eliminate(ir)?;
+        let mut dead: Vec<String> =
+            ir.words.keys().filter(|name| !live.words.contains_key(*name)).cloned().collect();
+        dead.sort();
+        Ok(dead)
+    }
+}
+
+impl Default for DeadWordEliminator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::WordDef;
+
+    fn word(name: &str, instructions: Vec<Instruction>) -> WordDef {
+        WordDef::new(name.to_string(), instructions)
+    }
+
+    #[test]
+    fn test_unused_helper_removed() {
+        let mut ir = ForthIR::new();
+        ir.add_word(word("helper", vec![Instruction::Literal(1), Instruction::Add]));
+        ir.add_word(word("unused", vec![Instruction::Literal(2), Instruction::Mul]));
+        ir.main = vec![Instruction::Literal(5), Instruction::call("helper")];
+
+        let optimized = DeadWordEliminator::new().eliminate(&ir).unwrap();
+
+        assert!(optimized.words.contains_key("helper"));
+        assert!(!optimized.words.contains_key("unused"));
+        assert_eq!(optimized.main, ir.main);
+    }
+
+    #[test]
+    fn test_dead_callers_and_cycles_removed() {
+        let mut ir = ForthIR::new();
+        ir.add_word(word("leaf", vec![Instruction::Dup]));
+        ir.add_word(word("dead", vec![Instruction::call("leaf")]));
+        ir.add_word(word("ping", vec![Instruction::call("pong")]));
+        ir.add_word(word("pong", vec![Instruction::call("ping")]));
+        ir.add_word(word("live", vec![Instruction::call("leaf")]));
+        ir.main = vec![Instruction::Literal(1), Instruction::call("live")];
+
+        let dead = DeadWordEliminator::new().dead_words(&ir).unwrap();
+        assert_eq!(dead, vec!["dead", "ping", "pong"]);
+    }
+
+    #[test]
+    fn test_exported_words_kept() {
+        let mut ir = ForthIR::new();
+        let mut api = word("api", vec![Instruction::call("impl")]);
+        api.is_exported = true;
+        ir.add_word(api);
+        ir.add_word(word("impl", vec![Instruction::Dup]));
+        ir.add_word(word("scratch", vec![Instruction::Drop]));
+
+        let optimized = DeadWordEliminator::new().eliminate(&ir).unwrap();
+
+        let mut kept: Vec<&String> = optimized.words.keys().collect();
+        kept.sort();
+        assert_eq!(kept, vec!["api", "impl"]);
+    }
+}
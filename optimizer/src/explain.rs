@@ -0,0 +1,116 @@
+//! Explanations of optimization decisions
+//!
+//! In explain mode the optimizer records why it did or did not transform the
+//! code, one [`OptimizationNote`] per decision:
+//!
+//! ```text
+//! inline: cube: not inlined: size 14 > threshold 10
+//! superinstructions: square@0: pattern `dup *` fused into `dup*`
+//! dead-code: main@7: `3` eliminated: result unused
+//! ```
+//!
+//! Inlining, superinstruction recognition and dead code elimination explain
+//! their decisions; the other passes record nothing. Recording notes never
+//! changes what a pass does, and with explain mode off no note is formatted.
+
+use crate::{Instruction, PassKind};
+use std::fmt;
+
+/// A decision made by an optimization pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimizationNote {
+    /// Pass that made the decision
+    pub pass: PassKind,
+    /// Word the decision is about (`None` for the main sequence)
+    pub word: Option<String>,
+    /// Index of the instruction the decision is about, in the word as the
+    /// pass received it
+    ///
+    /// Indices are stable for a given input: rerunning the passes before
+    /// this one (see `Optimizer::with_passes`) reproduces the code they refer to.
+    pub index: Option<usize>,
+    /// What was decided and why
+    pub message: String,
+}
+
+impl fmt::Display for OptimizationNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pass.name(), self.word.as_deref().unwrap_or("main"))?;
+        if let Some(index) = self.index {
+            write!(f, "@{}", index)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Notes of one pass, collected only when explaining
+pub(crate) struct Notes {
+    pass: PassKind,
+    enabled: bool,
+    /// Word being optimized (`None` for the main sequence)
+    word: Option<String>,
+    notes: Vec<OptimizationNote>,
+}
+
+impl Notes {
+    pub(crate) fn new(pass: PassKind, enabled: bool) -> Self {
+        Self {
+            pass,
+            enabled,
+            word: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Attribute the following notes to `word` (`None` for the main sequence)
+    pub(crate) fn set_word(&mut self, word: Option<&str>) {
+        if self.enabled {
+            self.word = word.map(str::to_string);
+        }
+    }
+
+    /// Record a decision about the instruction at `index` of the current word;
+    /// `message` is only called when explaining
+    pub(crate) fn add(&mut self, index: Option<usize>, message: impl FnOnce() -> String) {
+        if self.enabled {
+            self.notes.push(OptimizationNote {
+                pass: self.pass,
+                word: self.word.clone(),
+                index,
+                message: message(),
+            });
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<OptimizationNote> {
+        self.notes
+    }
+}
+
+/// `instructions` as Forth source, e.g. `dup *`
+pub(crate) fn source<'a>(instructions: impl IntoIterator<Item = &'a Instruction>) -> String {
+    instructions.into_iter().map(|inst| inst.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_display() {
+        let note = OptimizationNote {
+            pass: PassKind::DeadCode,
+            word: None,
+            index: Some(7),
+            message: "`3` eliminated: result unused".to_string(),
+        };
+        assert_eq!(note.to_string(), "dead-code: main@7: `3` eliminated: result unused");
+    }
+
+    #[test]
+    fn test_disabled_notes_are_not_formatted() {
+        let mut notes = Notes::new(PassKind::Inline, false);
+        notes.add(None, || unreachable!("message formatted while not explaining"));
+        assert!(notes.into_vec().is_empty());
+    }
+}
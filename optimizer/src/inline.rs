@@ -5,11 +5,24 @@
 //!
 //! # Inlining Heuristics
 //!
-//! A word is inlined if:
+//! Words in a recursive cluster of the call graph (see
+//! [`CallGraph::strongly_connected_components`]) are never inlined.
+//! Otherwise a word is inlined if:
 //! 1. It's marked as inline, OR
-//! 2. It's small (d threshold instructions), AND
-//! 3. It has compatible stack effects, AND
-//! 4. Inlining won't cause code bloat (called d max_inline_sites times)
+//! 2. It's called from exactly one place and
+//!    [`InlineConfig::always_inline_single_use`] is set, OR
+//! 3. It's small (<= `max_inline_size` instructions), AND
+//! 4. It has compatible stack effects, AND
+//! 5. Inlining won't cause code bloat (called <= `max_inline_sites` times)
+//!
+//! With [`ProfileData`] from a profiling run, hot words are inlined whatever
+//! their size; words without a profile entry fall back to the rules above.
+//!
+//! Every inlined call site grows the program by the callee's size less the
+//! call. Once the growth would pass `max_total_bloat`, no further words are
+//! inlined by rules 3-5 or the profile. Words marked inline are inlined
+//! anyway, and single-use words add no bloat since their definition is left
+//! unused.
 //!
 //! # Example
 //!
@@ -25,8 +38,10 @@
 //! 5 dup * dup *
 //! ```
 
-use crate::ir::{ForthIR, Instruction, StackEffect, WordDef};
-use crate::{OptimizationLevel, Result};
+use crate::aggressive_inline::CallGraph;
+use crate::explain::{Notes, OptimizationNote};
+use crate::ir::{ForthIR, Instruction, Splice, WordDef};
+use crate::{OptimizationLevel, PassKind, Result};
 use std::collections::{HashMap, HashSet};
 
 /// Inline cost threshold by optimization level
@@ -38,11 +53,140 @@ const INLINE_THRESHOLD_AGGRESSIVE: usize = 25;
 const MAX_INLINE_SITES_STANDARD: usize = 5;
 const MAX_INLINE_SITES_AGGRESSIVE: usize = 20;
 
+/// Instructions inlining may add to the whole program, by optimization level
+const MAX_TOTAL_BLOAT_BASIC: usize = 64;
+const MAX_TOTAL_BLOAT_STANDARD: usize = 256;
+const MAX_TOTAL_BLOAT_AGGRESSIVE: usize = 1024;
+
+/// Cost model deciding which calls are inlined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineConfig {
+    /// Largest word, in instructions, inlined on size alone
+    pub max_inline_size: usize,
+    /// Most call sites a word may have and still be inlined on size alone
+    pub max_inline_sites: usize,
+    /// Instructions inlining may add to the program before it stops
+    pub max_total_bloat: usize,
+    /// Inline every word with a single call site, whatever its size
+    pub always_inline_single_use: bool,
+}
+
+impl InlineConfig {
+    /// Defaults for `level`: favor size at Basic, speed at Aggressive
+    pub fn for_level(level: OptimizationLevel) -> Self {
+        let (max_inline_size, max_inline_sites, max_total_bloat) = match level {
+            OptimizationLevel::None => (0, 0, 0),
+            OptimizationLevel::Basic => {
+                (INLINE_THRESHOLD_BASIC, MAX_INLINE_SITES_STANDARD, MAX_TOTAL_BLOAT_BASIC)
+            }
+            OptimizationLevel::Standard => {
+                (INLINE_THRESHOLD_STANDARD, MAX_INLINE_SITES_STANDARD, MAX_TOTAL_BLOAT_STANDARD)
+            }
+            OptimizationLevel::Aggressive => (
+                INLINE_THRESHOLD_AGGRESSIVE,
+                MAX_INLINE_SITES_AGGRESSIVE,
+                MAX_TOTAL_BLOAT_AGGRESSIVE,
+            ),
+        };
+
+        Self {
+            max_inline_size,
+            max_inline_sites,
+            max_total_bloat,
+            always_inline_single_use: level == OptimizationLevel::Aggressive,
+        }
+    }
+
+    pub fn with_max_inline_size(mut self, size: usize) -> Self {
+        self.max_inline_size = size;
+        self
+    }
+
+    pub fn with_max_inline_sites(mut self, sites: usize) -> Self {
+        self.max_inline_sites = sites;
+        self
+    }
+
+    pub fn with_max_total_bloat(mut self, bloat: usize) -> Self {
+        self.max_total_bloat = bloat;
+        self
+    }
+
+    pub fn with_always_inline_single_use(mut self, enabled: bool) -> Self {
+        self.always_inline_single_use = enabled;
+        self
+    }
+}
+
+impl Default for InlineConfig {
+    fn default() -> Self {
+        Self::for_level(OptimizationLevel::Standard)
+    }
+}
+
+/// A word is hot if it was called at least this fraction as often as the
+/// most frequently called word of the program
+const HOT_CALL_FRACTION: f64 = 0.1;
+
+/// Dynamic call counts per word, collected from a profiling run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileData {
+    calls: HashMap<String, u64>,
+}
+
+impl ProfileData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `calls` calls of `word`
+    pub fn record(&mut self, word: impl Into<String>, calls: u64) {
+        *self.calls.entry(word.into()).or_insert(0) += calls;
+    }
+
+    /// Number of times `word` was called, or `None` if it was not profiled
+    pub fn call_count(&self, word: &str) -> Option<u64> {
+        self.calls.get(word).copied()
+    }
+
+    /// Words of `ir` that are hot in this profile
+    ///
+    /// Hotness is relative to the most frequently called word that still
+    /// exists in `ir`, so entries for removed words are ignored.
+    pub fn hot_words(&self, ir: &ForthIR) -> HashSet<String> {
+        let profiled = || {
+            ir.words
+                .keys()
+                .filter_map(|name| self.call_count(name).map(|calls| (name, calls)))
+        };
+        let hottest = profiled().map(|(_, calls)| calls).max().unwrap_or(0);
+
+        profiled()
+            .filter(|&(_, calls)| calls > 0 && calls as f64 >= hottest as f64 * HOT_CALL_FRACTION)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, u64)> for ProfileData {
+    fn from_iter<I: IntoIterator<Item = (S, u64)>>(iter: I) -> Self {
+        let mut profile = Self::new();
+        for (word, calls) in iter {
+            profile.record(word, calls);
+        }
+        profile
+    }
+}
+
 /// Inlining decision for a word
 #[derive(Debug, Clone, PartialEq)]
 enum InlineDecision {
+    /// Marked inline: inlined whatever the bloat
+    Forced,
+    /// Called once: inlined without counting towards the bloat
+    SingleUse,
+    /// Inlined while the bloat budget lasts
     Inline,
-    NoInline,
     TooLarge,
     TooManyCalls,
     Recursive,
@@ -51,32 +195,47 @@ enum InlineDecision {
 /// Inlining optimizer
 pub struct InlineOptimizer {
     level: OptimizationLevel,
-    inline_threshold: usize,
-    max_inline_sites: usize,
+    config: InlineConfig,
+    profile: Option<ProfileData>,
 }
 
 impl InlineOptimizer {
     pub fn new(level: OptimizationLevel) -> Self {
-        let (inline_threshold, max_inline_sites) = match level {
-            OptimizationLevel::None => (0, 0),
-            OptimizationLevel::Basic => (INLINE_THRESHOLD_BASIC, MAX_INLINE_SITES_STANDARD),
-            OptimizationLevel::Standard => {
-                (INLINE_THRESHOLD_STANDARD, MAX_INLINE_SITES_STANDARD)
-            }
-            OptimizationLevel::Aggressive => {
-                (INLINE_THRESHOLD_AGGRESSIVE, MAX_INLINE_SITES_AGGRESSIVE)
-            }
-        };
+        Self::with_config(level, InlineConfig::for_level(level))
+    }
 
+    /// Inline at `level` with an explicit cost model
+    pub fn with_config(level: OptimizationLevel, config: InlineConfig) -> Self {
         Self {
             level,
-            inline_threshold,
-            max_inline_sites,
+            config,
+            profile: None,
         }
     }
 
+    pub fn config(&self) -> &InlineConfig {
+        &self.config
+    }
+
+    /// Inline the words `profile` shows to be hot regardless of their size
+    pub fn with_profile(mut self, profile: ProfileData) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
     /// Inline small words in IR
     pub fn inline(&self, ir: &ForthIR) -> Result<ForthIR> {
+        self.inline_noting(ir, &mut Notes::new(PassKind::Inline, false))
+    }
+
+    /// Inline small words in IR, explaining why each called word was or was not inlined
+    pub fn inline_explained(&self, ir: &ForthIR) -> Result<(ForthIR, Vec<OptimizationNote>)> {
+        let mut notes = Notes::new(PassKind::Inline, true);
+        let optimized = self.inline_noting(ir, &mut notes)?;
+        Ok((optimized, notes.into_vec()))
+    }
+
+    fn inline_noting(&self, ir: &ForthIR, notes: &mut Notes) -> Result<ForthIR> {
         if self.level == OptimizationLevel::None {
             return Ok(ir.clone());
         }
@@ -87,16 +246,29 @@ impl InlineOptimizer {
         let call_counts = self.count_calls(ir);
 
         // Decide which words to inline
-        let inline_decisions = self.make_inline_decisions(ir, &call_counts);
+        let inline_decisions = self.make_inline_decisions(ir, &call_counts, notes);
+        let mut budget = BloatBudget {
+            remaining: self.config.max_total_bloat,
+            exhausted: false,
+        };
 
         // Inline in main sequence
-        optimized.main = self.inline_sequence(&ir.main, ir, &inline_decisions)?;
-
-        // Inline in each word
-        for (name, word) in ir.words.iter() {
-            let mut optimized_word = word.clone();
-            optimized_word.instructions =
-                self.inline_sequence(&word.instructions, ir, &inline_decisions)?;
+        notes.set_word(None);
+        optimized.main = self.inline_sequence(&ir.main, ir, &inline_decisions, &mut budget, notes)?;
+
+        // Inline in each word, in name order so the budget runs out in the same place every time
+        let mut names: Vec<&String> = ir.words.keys().collect();
+        names.sort();
+        for name in names {
+            let mut optimized_word = ir.words[name].clone();
+            notes.set_word(Some(name));
+            optimized_word.instructions = self.inline_sequence(
+                &optimized_word.instructions,
+                ir,
+                &inline_decisions,
+                &mut budget,
+                notes,
+            )?;
             optimized_word.update();
             optimized.words.insert(name.clone(), optimized_word);
         }
@@ -111,7 +283,7 @@ impl InlineOptimizer {
         // Count in main
         for inst in &ir.main {
             if let Instruction::Call(name) = inst {
-                *counts.entry(name.clone()).or_insert(0) += 1;
+                *counts.entry(name.to_string()).or_insert(0) += 1;
             }
         }
 
@@ -119,7 +291,7 @@ impl InlineOptimizer {
         for word in ir.words.values() {
             for inst in &word.instructions {
                 if let Instruction::Call(name) = inst {
-                    *counts.entry(name.clone()).or_insert(0) += 1;
+                    *counts.entry(name.to_string()).or_insert(0) += 1;
                 }
             }
         }
@@ -127,41 +299,89 @@ impl InlineOptimizer {
         counts
     }
 
-    /// Decide which words should be inlined
+    /// Decide which words should be inlined, noting the decision for every called word
     fn make_inline_decisions(
         &self,
         ir: &ForthIR,
         call_counts: &HashMap<String, usize>,
+        notes: &mut Notes,
     ) -> HashMap<String, InlineDecision> {
         let mut decisions = HashMap::new();
+        let hot_words = self
+            .profile
+            .as_ref()
+            .map(|profile| profile.hot_words(ir))
+            .unwrap_or_default();
+        let recursive = CallGraph::build(ir).recursive_words();
+
+        // In name order, so notes come out the same way every time
+        let mut names: Vec<&String> = ir.words.keys().collect();
+        names.sort();
+        for name in names {
+            let word = &ir.words[name];
+            let call_count = call_counts.get(name).copied().unwrap_or(0);
+            let decision = self.should_inline(word, call_count, recursive.contains(name));
+            let hot = decision == InlineDecision::TooLarge && hot_words.contains(name);
+
+            if call_count > 0 {
+                notes.set_word(Some(name));
+                notes.add(None, || match (&decision, hot) {
+                    (_, true) => format!(
+                        "inlined: hot in profile (size {} > threshold {})",
+                        word.cost, self.config.max_inline_size
+                    ),
+                    (InlineDecision::Forced, _) => "inlined: marked inline".to_string(),
+                    (InlineDecision::SingleUse, _) => "inlined: single call site".to_string(),
+                    (InlineDecision::Inline, _) => format!(
+                        "inlined: size {} <= threshold {}, {} call site{}",
+                        word.cost,
+                        self.config.max_inline_size,
+                        call_count,
+                        if call_count == 1 { "" } else { "s" }
+                    ),
+                    (InlineDecision::TooLarge, _) => format!(
+                        "not inlined: size {} > threshold {}",
+                        word.cost, self.config.max_inline_size
+                    ),
+                    (InlineDecision::TooManyCalls, _) => format!(
+                        "not inlined: {} call sites > limit {}",
+                        call_count, self.config.max_inline_sites
+                    ),
+                    (InlineDecision::Recursive, _) => "not inlined: recursive".to_string(),
+                });
+            }
 
-        for (name, word) in &ir.words {
-            let decision = self.should_inline(word, call_counts.get(name).copied().unwrap_or(0));
-            decisions.insert(name.clone(), decision);
+            decisions.insert(name.clone(), if hot { InlineDecision::Inline } else { decision });
         }
 
         decisions
     }
 
     /// Determine if a word should be inlined
-    fn should_inline(&self, word: &WordDef, call_count: usize) -> InlineDecision {
+    ///
+    /// `recursive` is set for words in a recursive cluster of the call graph
+    fn should_inline(&self, word: &WordDef, call_count: usize, recursive: bool) -> InlineDecision {
+        // Inlining any word of a recursive cluster would never end
+        if recursive {
+            return InlineDecision::Recursive;
+        }
+
         // Explicitly marked inline
         if word.is_inline {
-            return InlineDecision::Inline;
+            return InlineDecision::Forced;
         }
 
-        // Check for recursion
-        if self.is_recursive(word) {
-            return InlineDecision::Recursive;
+        if call_count == 1 && self.config.always_inline_single_use {
+            return InlineDecision::SingleUse;
         }
 
         // Too large?
-        if word.cost > self.inline_threshold {
+        if word.cost > self.config.max_inline_size {
             return InlineDecision::TooLarge;
         }
 
         // Too many call sites?
-        if call_count > self.max_inline_sites {
+        if call_count > self.config.max_inline_sites {
             return InlineDecision::TooManyCalls;
         }
 
@@ -169,31 +389,39 @@ impl InlineOptimizer {
         InlineDecision::Inline
     }
 
-    /// Check if word is recursive
-    fn is_recursive(&self, word: &WordDef) -> bool {
-        word.instructions
-            .iter()
-            .any(|inst| matches!(inst, Instruction::Call(name) if name == &word.name))
-    }
-
     /// Inline calls in an instruction sequence
     fn inline_sequence(
         &self,
         instructions: &[Instruction],
         ir: &ForthIR,
         decisions: &HashMap<String, InlineDecision>,
+        budget: &mut BloatBudget,
+        notes: &mut Notes,
     ) -> Result<Vec<Instruction>> {
         let mut result = Vec::with_capacity(instructions.len());
+        let mut splice = Splice::new(instructions);
 
-        for inst in instructions {
+        for (index, inst) in instructions.iter().enumerate() {
             match inst {
                 Instruction::Call(name) => {
                     // Check if we should inline this call
-                    if let Some(InlineDecision::Inline) = decisions.get(name) {
-                        if let Some(word) = ir.get_word(name) {
-                            // Inline the word's instructions
-                            result.extend_from_slice(&word.instructions);
-                            continue;
+                    let callee = (decisions.get(name.as_str()), ir.get_word(name));
+                    if let (Some(decision), Some(word)) = callee {
+                        if let Some(body) = splice.body(word) {
+                            let exhausted = budget.exhausted;
+                            if budget.allows(decision, body.len().saturating_sub(1)) {
+                                // Inline the word's instructions
+                                result.extend(body);
+                                continue;
+                            }
+                            if budget.exhausted && !exhausted {
+                                notes.add(Some(index), || {
+                                    format!(
+                                        "call of `{}` not inlined: bloat budget of {} instructions used up",
+                                        name, self.config.max_total_bloat
+                                    )
+                                });
+                            }
                         }
                     }
 
@@ -244,6 +472,36 @@ impl InlineOptimizer {
     }
 }
 
+/// Growth inlining may still add to the program
+struct BloatBudget {
+    remaining: usize,
+    /// Set once a call did not fit; no optional inlining happens after that
+    exhausted: bool,
+}
+
+impl BloatBudget {
+    /// Whether a call with `decision` is inlined, growing the program by
+    /// `growth`; charges the growth if so
+    fn allows(&mut self, decision: &InlineDecision, growth: usize) -> bool {
+        match decision {
+            InlineDecision::Forced => {
+                self.remaining = self.remaining.saturating_sub(growth);
+                true
+            }
+            InlineDecision::SingleUse => true,
+            InlineDecision::Inline if !self.exhausted && growth <= self.remaining => {
+                self.remaining -= growth;
+                true
+            }
+            InlineDecision::Inline => {
+                self.exhausted = true;
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InlineStats {
     pub calls_before: usize,
@@ -288,7 +546,7 @@ mod tests {
         );
         ir.add_word(square);
 
-        ir.main = vec![Instruction::Literal(5), Instruction::Call("square".to_string())];
+        ir.main = vec![Instruction::Literal(5), Instruction::call("square")];
 
         let optimized = optimizer.inline(&ir).unwrap();
 
@@ -314,7 +572,7 @@ mod tests {
         let large = WordDef::new("large".to_string(), large_instructions);
         ir.add_word(large);
 
-        ir.main = vec![Instruction::Call("large".to_string())];
+        ir.main = vec![Instruction::call("large")];
 
         let optimized = optimizer.inline(&ir).unwrap();
 
@@ -338,14 +596,14 @@ mod tests {
                 Instruction::Dup,
                 Instruction::Literal(1),
                 Instruction::Gt,
-                Instruction::Call("factorial".to_string()),
+                Instruction::call("factorial"),
             ],
         );
         ir.add_word(recursive);
 
         ir.main = vec![
             Instruction::Literal(5),
-            Instruction::Call("factorial".to_string()),
+            Instruction::call("factorial"),
         ];
 
         let optimized = optimizer.inline(&ir).unwrap();
@@ -358,6 +616,24 @@ mod tests {
         assert!(has_call);
     }
 
+    #[test]
+    fn test_dont_inline_mutual_recursion() {
+        let optimizer = InlineOptimizer::new(OptimizationLevel::Aggressive);
+
+        let mut ir = ForthIR::new();
+        let mut even = WordDef::new("even".to_string(), vec![Instruction::call("odd")]);
+        even.is_inline = true;
+        ir.add_word(even);
+        ir.add_word(WordDef::new("odd".to_string(), vec![Instruction::call("even")]));
+        ir.main = vec![Instruction::Literal(4), Instruction::call("even")];
+
+        let optimized = optimizer.inline(&ir).unwrap();
+
+        // Neither word of the cluster is inlined, even when marked inline
+        assert_eq!(optimized.main, ir.main);
+        assert_eq!(optimized.words["odd"].instructions, vec![Instruction::call("even")]);
+    }
+
     #[test]
     fn test_inline_forced() {
         let optimizer = InlineOptimizer::new(OptimizationLevel::Standard);
@@ -369,7 +645,7 @@ mod tests {
 
         ir.main = vec![
             Instruction::Literal(5),
-            Instruction::Call("tiny".to_string()),
+            Instruction::call("tiny"),
         ];
 
         let optimized = optimizer.inline(&ir).unwrap();
@@ -382,6 +658,92 @@ mod tests {
         assert!(!has_call);
     }
 
+    #[test]
+    fn test_inline_hot_words() {
+        let body = vec![Instruction::Dup; 30];
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("hot".to_string(), body.clone()));
+        ir.add_word(WordDef::new("cold".to_string(), body));
+        ir.add_word(WordDef::new("tiny".to_string(), vec![Instruction::Dup]));
+        ir.main = vec![
+            Instruction::Literal(1),
+            Instruction::call("hot"),
+            Instruction::call("cold"),
+            Instruction::call("tiny"),
+        ];
+
+        let profile: ProfileData = [("hot", 10_000), ("cold", 3), ("gone", 1_000_000)].into_iter().collect();
+        assert_eq!(profile.hot_words(&ir), HashSet::from(["hot".to_string()]));
+
+        let optimized = InlineOptimizer::new(OptimizationLevel::Standard)
+            .with_profile(profile)
+            .inline(&ir)
+            .unwrap();
+        let calls: Vec<&Instruction> =
+            optimized.main.iter().filter(|inst| matches!(inst, Instruction::Call(_))).collect();
+        // Unprofiled `tiny` is still inlined by size
+        assert_eq!(calls, vec![&Instruction::call("cold")]);
+    }
+
+    #[test]
+    fn test_inline_single_use() {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("once".to_string(), vec![Instruction::Dup; 30]));
+        ir.add_word(WordDef::new("twice".to_string(), vec![Instruction::Dup; 30]));
+        ir.main = vec![
+            Instruction::Literal(1),
+            Instruction::call("once"),
+            Instruction::call("twice"),
+            Instruction::call("twice"),
+        ];
+
+        let config =
+            InlineConfig::for_level(OptimizationLevel::Basic).with_always_inline_single_use(true);
+        let optimized = InlineOptimizer::with_config(OptimizationLevel::Basic, config)
+            .inline(&ir)
+            .unwrap();
+        let calls: Vec<&Instruction> =
+            optimized.main.iter().filter(|inst| matches!(inst, Instruction::Call(_))).collect();
+        assert_eq!(calls, vec![&Instruction::call("twice"), &Instruction::call("twice")]);
+
+        // Off by default below Aggressive
+        let optimized = InlineOptimizer::new(OptimizationLevel::Basic).inline(&ir).unwrap();
+        assert!(optimized.main.contains(&Instruction::call("once")));
+    }
+
+    #[test]
+    fn test_bloat_cap_stops_inlining() {
+        let mut ir = ForthIR::new();
+        let pair = vec![Instruction::Dup, Instruction::Dup, Instruction::Drop];
+        ir.add_word(WordDef::new("pair".to_string(), pair));
+        let mut forced =
+            WordDef::new("forced".to_string(), vec![Instruction::Dup, Instruction::Drop]);
+        forced.is_inline = true;
+        ir.add_word(forced);
+        ir.main = vec![
+            Instruction::Literal(1),
+            Instruction::call("pair"),
+            Instruction::call("pair"),
+            Instruction::call("forced"),
+            Instruction::call("pair"),
+        ];
+
+        // Each `pair` site adds two instructions; the budget covers only the first
+        let config = InlineConfig::for_level(OptimizationLevel::Standard).with_max_total_bloat(3);
+        let optimized = InlineOptimizer::with_config(OptimizationLevel::Standard, config)
+            .inline(&ir)
+            .unwrap();
+        let calls: Vec<&Instruction> =
+            optimized.main.iter().filter(|inst| matches!(inst, Instruction::Call(_))).collect();
+        // Forced inlining ignores the cap; `pair` stays a call once the cap is hit
+        assert_eq!(calls, vec![&Instruction::call("pair"), &Instruction::call("pair")]);
+        assert_eq!(optimized.main.len(), 8);
+
+        // With no cap every site is inlined
+        let optimized = InlineOptimizer::new(OptimizationLevel::Standard).inline(&ir).unwrap();
+        assert!(!optimized.main.iter().any(|inst| matches!(inst, Instruction::Call(_))));
+    }
+
     #[test]
     fn test_inline_stats() {
         let optimizer = InlineOptimizer::new(OptimizationLevel::Aggressive);
@@ -395,8 +757,8 @@ mod tests {
 
         ir.main = vec![
             Instruction::Literal(5),
-            Instruction::Call("square".to_string()),
-            Instruction::Call("square".to_string()),
+            Instruction::call("square"),
+            Instruction::call("square"),
         ];
 
         let optimized = optimizer.inline(&ir).unwrap();
@@ -3,13 +3,30 @@
 //! This module defines the IR used throughout the optimization pipeline.
 
 use crate::{OptimizerError, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smallvec::SmallVec;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
+use std::ops::Deref;
+use std::cell::RefCell;
+use std::sync::{Arc, Weak};
+
+/// Deepest data stack `ForthIR::verify` accepts
+pub const DEFAULT_MAX_STACK_DEPTH: usize = 255;
+
+/// Version of the JSON format written by [`ForthIR::to_json`]
+///
+/// Bump it when a change to the IR types would make older dumps read
+/// differently, and teach [`ForthIR::from_json`] to upgrade them.
+pub const IR_JSON_VERSION: u32 = 1;
 
 /// Stack effect notation: (before -- after)
 /// Example: (a b -- c) means: takes 2 items, produces 1 item
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// Only the number of cells is tracked, which is all the optimizer's passes
+/// need. For typed, polymorphic effects such as `( a b -- b a )`, use
+/// `fastforth::inference::StackEffect`, which offers the same operations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StackEffect {
     /// Number of items consumed from stack
     pub consumed: u8,
@@ -22,16 +39,48 @@ impl StackEffect {
         Self { consumed, produced }
     }
 
+    /// Effect that leaves the stack untouched, `( -- )`
+    pub fn identity() -> Self {
+        Self::new(0, 0)
+    }
+
     /// Net stack change (negative = shrinks, positive = grows)
     pub fn net_change(&self) -> i32 {
+        self.depth_delta()
+    }
+
+    /// Stack depth change, `produced - consumed`
+    pub fn depth_delta(&self) -> i32 {
         self.produced as i32 - self.consumed as i32
     }
 
-    /// Compose two stack effects
-    pub fn compose(&self, other: &StackEffect) -> Self {
-        Self {
-            consumed: self.consumed + other.consumed.saturating_sub(self.produced),
-            produced: self.produced.saturating_sub(other.consumed) + other.produced,
+    /// Whether the stack is as deep afterwards as before
+    pub fn is_balanced(&self) -> bool {
+        self.consumed == self.produced
+    }
+
+    /// Whether `other` can stand in for this effect: both take and leave
+    /// the same number of cells
+    pub fn compatible_with(&self, other: &StackEffect) -> bool {
+        self == other
+    }
+
+    /// Effect of running `self` and then `other`
+    ///
+    /// Inputs of `other` not supplied by `self` come from below `self`'s own
+    /// inputs. Cells carry no types here, so composition only fails when a
+    /// count no longer fits in a `u8`.
+    pub fn compose(&self, other: &StackEffect) -> Result<StackEffect> {
+        let shortfall = other.consumed.saturating_sub(self.produced);
+        let kept = self.produced.saturating_sub(other.consumed);
+        match (self.consumed.checked_add(shortfall), kept.checked_add(other.produced)) {
+            (Some(consumed), Some(produced)) => Ok(Self::new(consumed, produced)),
+            _ => Err(OptimizerError::InvalidStackEffect(format!(
+                "composing {} with {} needs more than {} cells",
+                self,
+                other,
+                u8::MAX
+            ))),
         }
     }
 }
@@ -42,12 +91,200 @@ impl fmt::Display for StackEffect {
     }
 }
 
+/// Interned word or label name
+///
+/// Names are interned in a table per thread rather than one per `ForthIR`,
+/// because instructions are built, compared and printed outside any IR (in
+/// `WordDef`s and in sequences the passes assemble). A table per thread also
+/// lets the workers of a parallel optimization intern without waiting on each
+/// other. Every symbol a thread makes for a name shares one allocation, so
+/// cloning a symbol never copies the string and comparing two symbols for
+/// the same name only compares pointers; symbols that come from different
+/// threads compare by name.
+///
+/// The table holds its names weakly: a name is freed with its last symbol,
+/// so a long-running REPL does not keep every name it has ever seen.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+/// Names interned so far; entries whose symbols are all gone are swept out
+/// once the table has doubled since the last sweep
+struct SymbolTable {
+    names: HashMap<Box<str>, Weak<str>>,
+    sweep_at: usize,
+}
+
+/// Smallest table size that triggers a sweep
+const SYMBOL_SWEEP_MIN: usize = 64;
+
+thread_local! {
+    static SYMBOLS: RefCell<SymbolTable> =
+        RefCell::new(SymbolTable { names: HashMap::new(), sweep_at: SYMBOL_SWEEP_MIN });
+}
+
+impl Symbol {
+    /// Symbol for `name`, sharing the name with the symbols this thread
+    /// already made for it
+    pub fn intern(name: &str) -> Self {
+        SYMBOLS.with(|table| {
+            let mut table = table.borrow_mut();
+            if let Some(shared) = table.names.get(name).and_then(Weak::upgrade) {
+                return Symbol(shared);
+            }
+
+            if table.names.len() >= table.sweep_at {
+                table.names.retain(|_, shared| shared.strong_count() > 0);
+                table.sweep_at = (table.names.len() * 2).max(SYMBOL_SWEEP_MIN);
+            }
+            let shared: Arc<str> = Arc::from(name);
+            table.names.insert(name.into(), Arc::downgrade(&shared));
+            Symbol(shared)
+        })
+    }
+
+    /// The interned name
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Symbols for a name share one allocation unless they were interned on
+/// different threads, or the name was swept out and interned again in
+/// between, so the names are compared as a fallback
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl std::hash::Hash for Symbol {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::intern(&name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Symbols are written as their names, since indices differ between processes
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Symbol::intern(&name))
+    }
+}
+
+/// JSON numbers cannot be NaN or infinite, so those floats are written as
+/// the strings `"NaN"`, `"inf"` and `"-inf"`
+mod json_float {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        match *value {
+            v if v.is_nan() => serializer.serialize_str("NaN"),
+            f64::INFINITY => serializer.serialize_str("inf"),
+            f64::NEG_INFINITY => serializer.serialize_str("-inf"),
+            v => serializer.serialize_f64(v),
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Float {
+        Number(f64),
+        Special(String),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match Float::deserialize(deserializer)? {
+            Float::Number(value) => Ok(value),
+            Float::Special(name) => match name.as_str() {
+                "NaN" => Ok(f64::NAN),
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                _ => Err(serde::de::Error::custom(format!("invalid float '{}'", name))),
+            },
+        }
+    }
+}
+
+/// Write words in name order, so JSON dumps are stable across runs
+fn serialize_sorted_map<S: Serializer>(map: &HashMap<String, WordDef>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Write variables in name order
+fn serialize_sorted_set<S: Serializer>(set: &HashSet<String>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    set.iter().collect::<BTreeSet<_>>().serialize(serializer)
+}
+
 /// Forth instruction in IR form
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Instruction {
     // Literals
     Literal(i64),
-    FloatLiteral(f64),
+    FloatLiteral(#[serde(with = "json_float")] f64),
 
     // Stack operations
     Dup,       // ( a -- a a )
@@ -57,8 +294,8 @@ pub enum Instruction {
     Rot,       // ( a b c -- b c a )
     Nip,       // ( a b -- b )
     Tuck,      // ( a b -- b a b )
-    Pick(u8),  // ( ... n -- ... n-th )
-    Roll(u8),  // ( ... n -- ... rotate n items )
+    Pick(u8),  // ( xn ... x0 -- xn ... x0 xn )
+    Roll(u8),  // ( xn ... x0 -- xn-1 ... x0 xn )
 
     // Arithmetic
     Add,       // ( a b -- a+b )
@@ -68,6 +305,8 @@ pub enum Instruction {
     Mod,       // ( a b -- a%b )
     Neg,       // ( a -- -a )
     Abs,       // ( a -- |a| )
+    Min,       // ( a b -- min(a,b) )
+    Max,       // ( a b -- max(a,b) )
 
     // Bitwise
     And,       // ( a b -- a&b )
@@ -75,7 +314,8 @@ pub enum Instruction {
     Xor,       // ( a b -- a^b )
     Not,       // ( a -- ~a )
     Shl,       // ( a n -- a<<n )
-    Shr,       // ( a n -- a>>n )
+    Shr,       // ( a n -- a>>n ), logical
+    Sar,       // ( a n -- a>>n ), arithmetic
 
     // Comparison
     Eq,        // ( a b -- a==b )
@@ -84,16 +324,19 @@ pub enum Instruction {
     Le,        // ( a b -- a<=b )
     Gt,        // ( a b -- a>b )
     Ge,        // ( a b -- a>=b )
+    ULt,       // ( u1 u2 -- u1<u2 ), unsigned
+    UGt,       // ( u1 u2 -- u1>u2 ), unsigned
     ZeroEq,    // ( a -- a==0 )
     ZeroLt,    // ( a -- a<0 )
     ZeroGt,    // ( a -- a>0 )
 
     // Control flow
-    Call(String),              // Call word by name
+    Call(Symbol),              // Call word by name
     Return,                    // Return from word
-    Branch(usize),             // Unconditional branch to instruction
-    BranchIf(usize),          // Branch if TOS is true
-    BranchIfNot(usize),       // Branch if TOS is false
+    Branch(BlockRef),              // Unconditional branch to a block
+    BranchIf(BlockRef),            // Branch if TOS is true
+    BranchIfNot(BlockRef),         // Branch if TOS is false
+    BranchIfElse(BlockRef, BlockRef), // Branch to the first block if TOS is true, else the second
 
     // Memory operations
     Load,      // ( addr -- value )
@@ -123,6 +366,8 @@ pub enum Instruction {
     CachedSwap { depth: u8 },     // Swap with known stack depth
     CachedOver { depth: u8 },     // Over with known stack depth
     FlushCache,                    // Force stack cache to memory
+    SpillCache,                    // Move the deepest cached item to memory
+    FillCache,                     // Reload the item below the cache into a register
 
     // Concurrency primitives (NEW)
     Spawn,         // ( xt -- thread-id ) Create OS thread
@@ -135,11 +380,21 @@ pub enum Instruction {
 
     // Metadata
     Comment(String),
-    Label(String),
+    Label(Symbol),
     Nop,
 }
 
 impl Instruction {
+    /// Call the word `name`
+    pub fn call(name: impl Into<Symbol>) -> Self {
+        Instruction::Call(name.into())
+    }
+
+    /// Branch target `name`
+    pub fn label(name: impl Into<Symbol>) -> Self {
+        Instruction::Label(name.into())
+    }
+
     /// Get the stack effect of this instruction
     pub fn stack_effect(&self) -> StackEffect {
         use Instruction::*;
@@ -148,15 +403,18 @@ impl Instruction {
 
             Dup => StackEffect::new(1, 2),
             Drop => StackEffect::new(1, 0),
-            Swap | Rot | Nip | Tuck => StackEffect::new(2, 2),
+            Swap => StackEffect::new(2, 2),
+            Rot => StackEffect::new(3, 3),
+            Nip => StackEffect::new(2, 1),
+            Tuck => StackEffect::new(2, 3),
             Over => StackEffect::new(2, 3),
-            Pick(_) => StackEffect::new(1, 1), // Simplified
-            Roll(_) => StackEffect::new(1, 0),
+            Pick(n) => StackEffect::new(n.saturating_add(1), n.saturating_add(2)),
+            Roll(n) => StackEffect::new(n.saturating_add(1), n.saturating_add(1)),
 
-            Add | Sub | Mul | Div | Mod => StackEffect::new(2, 1),
+            Add | Sub | Mul | Div | Mod | Min | Max => StackEffect::new(2, 1),
             And | Or | Xor => StackEffect::new(2, 1),
-            Eq | Ne | Lt | Le | Gt | Ge => StackEffect::new(2, 1),
-            Shl | Shr => StackEffect::new(2, 1),
+            Eq | Ne | Lt | Le | Gt | Ge | ULt | UGt => StackEffect::new(2, 1),
+            Shl | Shr | Sar => StackEffect::new(2, 1),
 
             Neg | Abs | Not => StackEffect::new(1, 1),
             ZeroEq | ZeroLt | ZeroGt => StackEffect::new(1, 1),
@@ -180,9 +438,10 @@ impl Instruction {
             // Stack caching
             CachedDup { .. } => StackEffect::new(1, 2),
             CachedSwap { .. } | CachedOver { .. } => StackEffect::new(2, 2),
-            FlushCache => StackEffect::new(0, 0),
+            FlushCache | SpillCache | FillCache => StackEffect::new(0, 0),
 
-            Return | Branch(_) | BranchIf(_) | BranchIfNot(_) => StackEffect::new(0, 0),
+            Return | Branch(_) => StackEffect::new(0, 0),
+            BranchIf(_) | BranchIfNot(_) | BranchIfElse(..) => StackEffect::new(1, 0), // Pops the flag
             Call(_) => StackEffect::new(0, 0), // Depends on called word
 
             // Concurrency primitives
@@ -204,7 +463,7 @@ impl Instruction {
         !matches!(
             self,
             Store | Store8 | ToR | Call(_) | Return | Branch(_) |
-            BranchIf(_) | BranchIfNot(_) | FlushCache |
+            BranchIf(_) | BranchIfNot(_) | BranchIfElse(..) | FlushCache | SpillCache | FillCache |
             // Concurrency primitives are NOT pure (side effects)
             Spawn | Join | Channel(_) | Send | Recv | CloseChannel | DestroyChannel
         )
@@ -217,15 +476,271 @@ impl Instruction {
             _ => None,
         }
     }
+
+    /// Blocks a branch leads to
+    pub fn branch_targets(&self) -> SmallVec<[BlockRef; 2]> {
+        use Instruction::*;
+        match self {
+            Branch(t) | BranchIf(t) | BranchIfNot(t) => smallvec::smallvec![*t],
+            BranchIfElse(t, e) => smallvec::smallvec![*t, *e],
+            _ => SmallVec::new(),
+        }
+    }
+
+    /// Mutable operands of a branch, for passes that renumber or move blocks
+    pub fn branch_targets_mut(&mut self) -> SmallVec<[&mut BlockRef; 2]> {
+        use Instruction::*;
+        match self {
+            Branch(t) | BranchIf(t) | BranchIfNot(t) => smallvec::smallvec![t],
+            BranchIfElse(t, e) => smallvec::smallvec![t, e],
+            _ => SmallVec::new(),
+        }
+    }
+}
+
+/// Block a branch leads to, started by the label [`block_label`] gives it
+///
+/// Branches never name instruction indices, so passes can insert, remove and
+/// move instructions freely as long as they keep the labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BlockRef(pub usize);
+
+impl fmt::Display for BlockRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+/// Label starting `block`, as emitted by SSA lowering
+pub fn block_label(block: BlockRef) -> Symbol {
+    Symbol::from(block.to_string())
+}
+
+/// Block a label starts, if it is a [`block_label`]
+pub fn label_block(label: &str) -> Option<BlockRef> {
+    label.strip_prefix("bb")?.parse().ok().map(BlockRef)
+}
+
+/// Where the branches of an instruction sequence lead
+#[derive(Debug, Clone)]
+pub struct BranchTargets {
+    /// Position of each block's label
+    blocks: HashMap<BlockRef, usize>,
+}
+
+impl BranchTargets {
+    pub fn of(instructions: &[Instruction]) -> Self {
+        let blocks = instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, inst)| match inst {
+                Instruction::Label(name) => label_block(name).map(|block| (block, i)),
+                _ => None,
+            })
+            .collect();
+        Self { blocks }
+    }
+
+    /// Index of the label starting `block`
+    pub fn resolve(&self, block: BlockRef) -> Option<usize> {
+        self.blocks.get(&block).copied()
+    }
+
+    /// Indices some branch in `instructions` leads to
+    pub fn landing_sites(&self, instructions: &[Instruction]) -> HashSet<usize> {
+        instructions
+            .iter()
+            .flat_map(Instruction::branch_targets)
+            .filter_map(|target| self.resolve(target))
+            .collect()
+    }
+
+    /// Check that block labels are unique and every branch resolves
+    pub fn check(&self, instructions: &[Instruction]) -> Result<()> {
+        let mut seen = HashSet::new();
+        for (i, inst) in instructions.iter().enumerate() {
+            if let Instruction::Label(name) = inst {
+                if label_block(name).is_some() && !seen.insert(name.as_str()) {
+                    return Err(OptimizerError::InvalidBranchTarget(format!(
+                        "Label '{}' at instruction {} is defined more than once", name, i
+                    )));
+                }
+            }
+            for target in inst.branch_targets() {
+                if self.resolve(target).is_none() {
+                    return Err(OptimizerError::InvalidBranchTarget(format!(
+                        "Branch at instruction {} leads to missing block {}", i, target
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rebuilds a sequence with calls replaced by inlined bodies, giving each
+/// body blocks the sequence doesn't use yet
+pub(crate) struct Splice {
+    next_block: usize,
+}
+
+impl Splice {
+    pub(crate) fn new(instructions: &[Instruction]) -> Self {
+        Self {
+            next_block: max_block(instructions).map_or(0, |last| last.0 + 1),
+        }
+    }
+
+    /// Body to splice in place of a call to `word`, if it can be
+    pub(crate) fn body(&mut self, word: &WordDef) -> Option<Vec<Instruction>> {
+        word.inline_body(&mut self.next_block)
+    }
+}
+
+/// Largest block labelled in `instructions`
+pub fn max_block(instructions: &[Instruction]) -> Option<BlockRef> {
+    instructions
+        .iter()
+        .filter_map(|inst| match inst {
+            Instruction::Label(name) => label_block(name),
+            _ => None,
+        })
+        .max()
+}
+
+/// Renumber every block of a labelled sequence by `offset`, labels and
+/// branch operands alike, so it can be spliced into another sequence
+pub fn offset_blocks(instructions: &mut [Instruction], offset: usize) {
+    for inst in instructions {
+        if let Instruction::Label(name) = inst {
+            if let Some(block) = label_block(name) {
+                *name = block_label(BlockRef(block.0 + offset));
+            }
+        }
+        for target in inst.branch_targets_mut() {
+            target.0 += offset;
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Instruction::*;
+        match self {
+            Literal(v) => write!(f, "{}", v),
+            FloatLiteral(v) => write!(f, "{:?}e", v),
+
+            Dup => write!(f, "dup"),
+            Drop => write!(f, "drop"),
+            Swap => write!(f, "swap"),
+            Over => write!(f, "over"),
+            Rot => write!(f, "rot"),
+            Nip => write!(f, "nip"),
+            Tuck => write!(f, "tuck"),
+            Pick(n) => write!(f, "pick {}", n),
+            Roll(n) => write!(f, "roll {}", n),
+
+            Add => write!(f, "+"),
+            Sub => write!(f, "-"),
+            Mul => write!(f, "*"),
+            Div => write!(f, "/"),
+            Mod => write!(f, "mod"),
+            Neg => write!(f, "negate"),
+            Abs => write!(f, "abs"),
+            Min => write!(f, "min"),
+            Max => write!(f, "max"),
+
+            And => write!(f, "and"),
+            Or => write!(f, "or"),
+            Xor => write!(f, "xor"),
+            Not => write!(f, "invert"),
+            Shl => write!(f, "lshift"),
+            Shr => write!(f, "rshift"),
+            Sar => write!(f, "arshift"),
+
+            Eq => write!(f, "="),
+            Ne => write!(f, "<>"),
+            Lt => write!(f, "<"),
+            Le => write!(f, "<="),
+            Gt => write!(f, ">"),
+            Ge => write!(f, ">="),
+            ULt => write!(f, "u<"),
+            UGt => write!(f, "u>"),
+            ZeroEq => write!(f, "0="),
+            ZeroLt => write!(f, "0<"),
+            ZeroGt => write!(f, "0>"),
+
+            Call(name) => write!(f, "call {}", name),
+            Return => write!(f, "exit"),
+            Branch(t) => write!(f, "branch {}", t),
+            BranchIf(t) => write!(f, "branch-if {}", t),
+            BranchIfNot(t) => write!(f, "0branch {}", t),
+            BranchIfElse(t, e) => write!(f, "branch-if {} else {}", t, e),
+
+            Load => write!(f, "@"),
+            Store => write!(f, "!"),
+            Load8 => write!(f, "c@"),
+            Store8 => write!(f, "c!"),
+
+            ToR => write!(f, ">r"),
+            FromR => write!(f, "r>"),
+            RFetch => write!(f, "r@"),
+
+            DupAdd => write!(f, "dup+"),
+            DupMul => write!(f, "dup*"),
+            OverAdd => write!(f, "over+"),
+            SwapSub => write!(f, "swap-"),
+            LiteralAdd(v) => write!(f, "lit+ {}", v),
+            LiteralMul(v) => write!(f, "lit* {}", v),
+            IncOne => write!(f, "1+"),
+            DecOne => write!(f, "1-"),
+            MulTwo => write!(f, "2*"),
+            DivTwo => write!(f, "2/"),
+
+            CachedDup { depth } => write!(f, "dup.cached {}", depth),
+            CachedSwap { depth } => write!(f, "swap.cached {}", depth),
+            CachedOver { depth } => write!(f, "over.cached {}", depth),
+            FlushCache => write!(f, "flush-cache"),
+            SpillCache => write!(f, "spill-cache"),
+            FillCache => write!(f, "fill-cache"),
+
+            Spawn => write!(f, "spawn"),
+            Join => write!(f, "join"),
+            Channel(size) => write!(f, "channel {}", size),
+            Send => write!(f, "send"),
+            Recv => write!(f, "recv"),
+            CloseChannel => write!(f, "close-channel"),
+            DestroyChannel => write!(f, "destroy-channel"),
+
+            Comment(text) => write!(f, "\\ {}", text),
+            Label(name) => write!(f, "{}:", name),
+            Nop => write!(f, "nop"),
+        }
+    }
+}
+
+/// Write one instruction per line; labels are outdented
+fn fmt_instructions(f: &mut fmt::Formatter<'_>, instructions: &[Instruction]) -> fmt::Result {
+    for inst in instructions {
+        match inst {
+            Instruction::Label(_) => writeln!(f, "{}", inst)?,
+            _ => writeln!(f, "    {}", inst)?,
+        }
+    }
+    Ok(())
 }
 
 /// Word definition (like a function)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WordDef {
     pub name: String,
     pub instructions: Vec<Instruction>,
     pub stack_effect: StackEffect,
+    #[serde(default)]
     pub is_inline: bool,
+    /// Kept by whole-program dead word elimination even if nothing calls it
+    #[serde(default)]
+    pub is_exported: bool,
     pub cost: usize, // Instruction count for inlining decisions
 }
 
@@ -238,29 +753,84 @@ impl WordDef {
             instructions,
             stack_effect,
             is_inline: false,
+            is_exported: false,
             cost,
         }
     }
 
+    /// Composes the instruction effects, counting in `usize` so that
+    /// sequences deeper than a `u8` still balance; the totals saturate
     fn calculate_stack_effect(instructions: &[Instruction]) -> StackEffect {
-        instructions
-            .iter()
-            .map(|i| i.stack_effect())
-            .fold(StackEffect::new(0, 0), |acc, e| acc.compose(&e))
+        let (mut consumed, mut depth) = (0usize, 0usize);
+        for effect in instructions.iter().map(|i| i.stack_effect()) {
+            let needed = effect.consumed as usize;
+            consumed += needed.saturating_sub(depth);
+            depth = depth.saturating_sub(needed) + effect.produced as usize;
+        }
+
+        let clamp = |n: usize| u8::try_from(n).unwrap_or(u8::MAX);
+        StackEffect::new(clamp(consumed), clamp(depth))
     }
 
     /// Update computed properties after modification
+    ///
+    /// The stack effect is kept: a rewrite must not change what the word does
+    /// to the stack, and the estimate `new` makes can't see variables or
+    /// branches, so recomputing it could lose an exact effect.
     pub fn update(&mut self) {
-        self.stack_effect = Self::calculate_stack_effect(&self.instructions);
         self.cost = self.instructions.len();
     }
+
+    /// Instructions to splice in place of a call to this word
+    ///
+    /// A trailing `Return` is dropped. The body's blocks are renumbered from
+    /// `next_block`, which moves past them, and any other `Return` branches
+    /// to a new block after the body so control carries on in the caller.
+    /// Bodies with branches to blocks they don't label can't be spliced and
+    /// give `None`.
+    pub fn inline_body(&self, next_block: &mut usize) -> Option<Vec<Instruction>> {
+        let mut body = self.instructions.clone();
+        if body.last() == Some(&Instruction::Return) {
+            body.pop();
+        }
+        BranchTargets::of(&body).check(&body).ok()?;
+
+        let exits = body.iter().any(|inst| matches!(inst, Instruction::Return));
+        if !exits && max_block(&body).is_none() {
+            return Some(body);
+        }
+
+        offset_blocks(&mut body, *next_block);
+        let exit = max_block(&body).map_or(*next_block, |last| last.0 + 1);
+        if exits {
+            for inst in body.iter_mut().filter(|inst| matches!(inst, Instruction::Return)) {
+                *inst = Instruction::Branch(BlockRef(exit));
+            }
+            body.push(Instruction::Label(block_label(BlockRef(exit))));
+        }
+        *next_block = exit + 1;
+        Some(body)
+    }
+}
+
+impl fmt::Display for WordDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, ": {} {}", self.name, self.stack_effect)?;
+        fmt_instructions(f, &self.instructions)?;
+        write!(f, ";")
+    }
 }
 
 /// Complete Forth IR with all word definitions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ForthIR {
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub words: HashMap<String, WordDef>,
     pub main: Vec<Instruction>,
+    /// Names of variable slots; `Call(name)` on one of these pushes the
+    /// address of its storage, which no other name aliases
+    #[serde(default, serialize_with = "serialize_sorted_set")]
+    pub variables: HashSet<String>,
 }
 
 impl ForthIR {
@@ -268,6 +838,7 @@ impl ForthIR {
         Self {
             words: HashMap::new(),
             main: Vec::new(),
+            variables: HashSet::new(),
         }
     }
 
@@ -276,11 +847,18 @@ impl ForthIR {
         // Very basic parser for demonstration
         // A real implementation would use a proper parser (nom, pest, etc.)
         let mut ir = Self::new();
-        let tokens: Vec<&str> = source.split_whitespace().collect();
+        let mut tokens = source.split_whitespace();
         let mut instructions = Vec::new();
 
-        for token in tokens {
+        while let Some(token) = tokens.next() {
             let inst = match token {
+                "variable" => {
+                    if let Some(name) = tokens.next() {
+                        ir.variables.insert(name.to_string());
+                    }
+                    continue;
+                }
+
                 // Stack operations
                 "dup" => Instruction::Dup,
                 "drop" => Instruction::Drop,
@@ -298,6 +876,8 @@ impl ForthIR {
                 "mod" => Instruction::Mod,
                 "negate" => Instruction::Neg,
                 "abs" => Instruction::Abs,
+                "min" => Instruction::Min,
+                "max" => Instruction::Max,
 
                 // Bitwise
                 "&" | "and" => Instruction::And,
@@ -306,6 +886,7 @@ impl ForthIR {
                 "~" | "not" | "invert" => Instruction::Not,
                 "<<" | "lshift" => Instruction::Shl,
                 ">>" | "rshift" => Instruction::Shr,
+                "arshift" => Instruction::Sar,
 
                 // Comparison
                 "=" => Instruction::Eq,
@@ -314,6 +895,8 @@ impl ForthIR {
                 "<=" => Instruction::Le,
                 ">" => Instruction::Gt,
                 ">=" => Instruction::Ge,
+                "u<" => Instruction::ULt,
+                "u>" => Instruction::UGt,
                 "0=" => Instruction::ZeroEq,
                 "0<" => Instruction::ZeroLt,
                 "0>" => Instruction::ZeroGt,
@@ -331,7 +914,7 @@ impl ForthIR {
                     } else if token.starts_with(':') || token.starts_with(';') {
                         continue; // Skip word definition markers
                     } else {
-                        Instruction::Call(token.to_string())
+                        Instruction::call(token)
                     }
                 }
             };
@@ -359,38 +942,139 @@ impl ForthIR {
 
     /// Verify stack effects are valid
     pub fn verify(&self) -> Result<()> {
+        self.verify_with_limit(DEFAULT_MAX_STACK_DEPTH)
+    }
+
+    /// Verify stack effects, rejecting code that can grow the stack past `limit`
+    pub fn verify_with_limit(&self, limit: usize) -> Result<()> {
+        let mut peaks = HashMap::new();
+
         // Check main sequence
-        self.verify_sequence(&self.main)?;
+        self.verify_sequence(&self.main, 0, limit, &mut peaks)?;
 
-        // Check each word
-        for (name, word) in &self.words {
-            self.verify_sequence(&word.instructions).map_err(|e| {
-                OptimizerError::InvalidStackEffect(format!("In word '{}': {}", name, e))
-            })?;
+        // Check each word, starting from the inputs it takes
+        for name in self.words.keys() {
+            self.word_peak(name, limit, &mut peaks)?;
         }
 
         Ok(())
     }
 
-    fn verify_sequence(&self, instructions: &[Instruction]) -> Result<()> {
-        let mut depth = 0i32;
+    /// Deepest the data stack gets while running the main sequence
+    ///
+    /// Calls count the depth reached inside the called word. A recursive call
+    /// only counts the values it leaves behind, since its depth depends on
+    /// how deep the recursion goes.
+    pub fn max_stack_depth(&self) -> Result<usize> {
+        let peak = self.verify_sequence(&self.main, 0, usize::MAX, &mut HashMap::new())?;
+        Ok(peak as usize)
+    }
 
-        for (i, inst) in instructions.iter().enumerate() {
-            let effect = inst.stack_effect();
-            depth -= effect.consumed as i32;
+    /// Deepest the stack gets inside `name`, counting its inputs, or `None`
+    /// while `name` is still being checked (a recursive call)
+    fn word_peak(&self, name: &str, limit: usize, peaks: &mut HashMap<String, Option<i32>>) -> Result<Option<i32>> {
+        if let Some(&peak) = peaks.get(name) {
+            return Ok(peak);
+        }
+        let Some(word) = self.words.get(name) else {
+            return Ok(None);
+        };
+
+        peaks.insert(name.to_string(), None);
+        let peak = self
+            .verify_sequence(&word.instructions, word.stack_effect.consumed as i32, limit, peaks)
+            .map_err(|e| OptimizerError::InvalidStackEffect(format!("In word '{}': {}", name, e)))?;
+        peaks.insert(name.to_string(), Some(peak));
+        Ok(Some(peak))
+    }
 
-            if depth < 0 {
-                return Err(OptimizerError::StackUnderflow(i));
-            }
+    /// Simulate stack depths along every path through `instructions`,
+    /// returning the deepest point reached
+    ///
+    /// Each instruction must be reached with the same depth on every path, so
+    /// branches that merge with different depths and loops that grow or shrink
+    /// the stack are rejected. A call to a word whose effect is unknown ends
+    /// checking along that path.
+    fn verify_sequence(
+        &self,
+        instructions: &[Instruction],
+        inputs: i32,
+        limit: usize,
+        peaks: &mut HashMap<String, Option<i32>>,
+    ) -> Result<i32> {
+        let limit = limit.min(i32::MAX as usize) as i32;
+        let targets = BranchTargets::of(instructions);
+        targets.check(instructions)?;
+        let resolve = |target: BlockRef| targets.resolve(target);
+
+        let mut seen: Vec<Option<i32>> = vec![None; instructions.len()];
+        let mut pending = vec![(0, inputs)];
+        let mut peak = inputs;
+
+        while let Some((start, mut depth)) = pending.pop() {
+            let mut i = start;
+
+            while i < instructions.len() {
+                match seen[i] {
+                    Some(expected) if expected == depth => break,
+                    Some(expected) => {
+                        return Err(OptimizerError::InvalidStackEffect(format!(
+                            "Stack depth mismatch at instruction {}: expected {}, found {}",
+                            i, expected, depth
+                        )));
+                    }
+                    None => seen[i] = Some(depth),
+                }
 
-            depth += effect.produced as i32;
+                let inst = &instructions[i];
+                let effect = match inst {
+                    Instruction::Call(name) if self.variables.contains(name.as_str()) => StackEffect::new(0, 1),
+                    Instruction::Call(name) => match self.words.get(name.as_str()) {
+                        Some(word) => word.stack_effect.clone(),
+                        None => break,
+                    },
+                    _ => inst.stack_effect(),
+                };
+
+                depth -= effect.consumed as i32;
+                if depth < 0 {
+                    return Err(OptimizerError::StackUnderflow(i));
+                }
+
+                // A called word may go deeper than it ends up
+                let inner = match inst {
+                    Instruction::Call(name) => self.word_peak(name, limit as usize, peaks)?,
+                    _ => None,
+                };
+                let deepest = depth + inner.map_or(0, |p| p - effect.consumed as i32).max(effect.produced as i32);
+
+                depth += effect.produced as i32;
+                if deepest > limit {
+                    return Err(OptimizerError::StackOverflow(i));
+                }
+                peak = peak.max(deepest);
 
-            if depth > 255 {
-                return Err(OptimizerError::StackOverflow(i));
+                match inst {
+                    Instruction::Branch(target) => {
+                        pending.extend(resolve(*target).map(|t| (t, depth)));
+                        break;
+                    }
+                    Instruction::BranchIf(target) | Instruction::BranchIfNot(target) => {
+                        pending.extend(resolve(*target).map(|t| (t, depth)));
+                    }
+                    Instruction::BranchIfElse(then, otherwise) => {
+                        pending.extend(resolve(*then).map(|t| (t, depth)));
+                        pending.extend(resolve(*otherwise).map(|t| (t, depth)));
+                        break;
+                    }
+                    Instruction::Return => break,
+                    _ => {}
+                }
+                i += 1;
             }
         }
 
-        Ok(())
+        Ok(peak)
     }
 
     /// Count total instructions
@@ -398,6 +1082,60 @@ impl ForthIR {
         self.main.len()
             + self.words.values().map(|w| w.instructions.len()).sum::<usize>()
     }
+
+    /// Export as JSON for external tools
+    ///
+    /// The document is `{"version": IR_JSON_VERSION, "ir": ...}`, with words
+    /// and variables in name order so dumps of the same IR are identical.
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Document<'a> {
+            version: u32,
+            ir: &'a ForthIR,
+        }
+
+        serde_json::to_string_pretty(&Document { version: IR_JSON_VERSION, ir: self })
+            .expect("IR always serializes to JSON")
+    }
+
+    /// Import IR exported by [`to_json`](Self::to_json), by this or an
+    /// earlier version
+    pub fn from_json(json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Document {
+            version: u32,
+            ir: serde_json::Value,
+        }
+
+        let error = |e: serde_json::Error| OptimizerError::ParseError(format!("Invalid IR JSON: {}", e));
+        let document: Document = serde_json::from_str(json).map_err(error)?;
+        if document.version > IR_JSON_VERSION {
+            return Err(OptimizerError::ParseError(format!(
+                "IR JSON version {} is newer than the supported version {}",
+                document.version, IR_JSON_VERSION
+            )));
+        }
+        serde_json::from_value(document.ir).map_err(error)
+    }
+}
+
+impl fmt::Display for ForthIR {
+    /// Words are printed in name order so dumps are stable across runs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.words.keys().collect();
+        names.sort();
+
+        for name in names {
+            writeln!(f, "{}", self.words[name])?;
+        }
+
+        if !self.main.is_empty() {
+            writeln!(f, "main:")?;
+            fmt_instructions(f, &self.main)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for ForthIR {
@@ -414,10 +1152,22 @@ mod tests {
     fn test_stack_effect_composition() {
         let dup = StackEffect::new(1, 2); // ( a -- a a )
         let add = StackEffect::new(2, 1); // ( a b -- c )
-        let composed = dup.compose(&add);  // Should be ( a -- c )
+        let composed = dup.compose(&add).unwrap();  // Should be ( a -- c )
 
         assert_eq!(composed.consumed, 1);
         assert_eq!(composed.produced, 1);
+        assert!(composed.is_balanced());
+        assert_eq!(composed.depth_delta(), 0);
+        assert!(composed.compatible_with(&StackEffect::new(1, 1)));
+        assert!(!composed.compatible_with(&add));
+
+        // Inputs dup doesn't supply come from below its own
+        let drop2 = StackEffect::new(2, 0);
+        assert_eq!(StackEffect::identity().compose(&drop2).unwrap(), drop2);
+        assert_eq!(add.compose(&drop2).unwrap(), StackEffect::new(3, 0));
+
+        let deep = StackEffect::new(0, 200);
+        assert!(deep.compose(&deep).is_err());
     }
 
     #[test]
@@ -458,4 +1208,253 @@ mod tests {
         ir.main = vec![Instruction::Add]; // Requires 2 items but stack is empty
         assert!(matches!(ir.verify(), Err(OptimizerError::StackUnderflow(_))));
     }
+
+    #[test]
+    fn test_verify_reports_failing_index() {
+        let mut ir = ForthIR::new();
+        ir.main = vec![Instruction::Literal(1), Instruction::Literal(2), Instruction::Add, Instruction::Add];
+        assert!(matches!(ir.verify(), Err(OptimizerError::StackUnderflow(3))));
+
+        ir.main = vec![Instruction::Literal(1); 300];
+        assert!(matches!(ir.verify(), Err(OptimizerError::StackOverflow(255))));
+    }
+
+    #[test]
+    fn test_stack_depth_counts_called_words() {
+        let mut ir = ForthIR::new();
+        // Pushes four values before leaving one
+        ir.add_word(WordDef::new(
+            "sum4".to_string(),
+            vec![
+                Instruction::Literal(1),
+                Instruction::Literal(2),
+                Instruction::Literal(3),
+                Instruction::Literal(4),
+                Instruction::Add,
+                Instruction::Add,
+                Instruction::Add,
+            ],
+        ));
+        ir.main = vec![Instruction::Literal(0), Instruction::call("sum4"), Instruction::Add];
+        assert_eq!(ir.max_stack_depth().unwrap(), 5);
+
+        assert!(ir.verify_with_limit(5).is_ok());
+        assert!(matches!(ir.verify_with_limit(4), Err(OptimizerError::StackOverflow(1))));
+
+        // Past the u8 range of a stack effect
+        ir.main = vec![Instruction::Literal(1); 300];
+        assert_eq!(ir.max_stack_depth().unwrap(), 300);
+        assert!(ir.verify_with_limit(300).is_ok());
+    }
+
+    #[test]
+    fn test_verify_branch_merge_depths() {
+        let mut ir = ForthIR::new();
+        // The taken branch skips the push, so the paths meet at 3 with depths 1 and 0
+        ir.main = vec![
+            Instruction::Literal(-1),
+            Instruction::BranchIfNot(BlockRef(1)),
+            Instruction::Literal(7),
+            Instruction::label("bb1"),
+            Instruction::Return,
+        ];
+        match ir.verify() {
+            Err(OptimizerError::InvalidStackEffect(msg)) => {
+                assert_eq!(msg, "Stack depth mismatch at instruction 3: expected 1, found 0");
+            }
+            other => panic!("expected a depth mismatch, got {:?}", other),
+        }
+
+        // Both arms push one value
+        ir.main = vec![
+            Instruction::Literal(-1),
+            Instruction::BranchIfNot(BlockRef(1)),
+            Instruction::Branch(BlockRef(2)),
+            Instruction::label("bb1"),
+            Instruction::Literal(1),
+            Instruction::Branch(BlockRef(3)),
+            Instruction::label("bb2"),
+            Instruction::Literal(2),
+            Instruction::Branch(BlockRef(3)),
+            Instruction::label("bb3"),
+            Instruction::Return,
+        ];
+        assert!(ir.verify().is_ok());
+    }
+
+    #[test]
+    fn test_verify_loops_and_calls() {
+        let mut ir = ForthIR::new();
+        // Each trip around the loop leaves one more value behind
+        ir.main = vec![
+            Instruction::label("bb0"),
+            Instruction::Literal(1),
+            Instruction::Branch(BlockRef(0)),
+        ];
+        assert!(matches!(ir.verify(), Err(OptimizerError::InvalidStackEffect(_))));
+
+        // Known words use their effect; unknown ones stop the check on that path
+        ir.add_word(WordDef::new(
+            "pair".to_string(),
+            vec![Instruction::Literal(1), Instruction::Literal(2)],
+        ));
+        ir.main = vec![Instruction::call("pair"), Instruction::Add];
+        assert!(ir.verify().is_ok());
+        ir.main = vec![Instruction::call("pair"), Instruction::Add, Instruction::Add];
+        assert!(matches!(ir.verify(), Err(OptimizerError::StackUnderflow(2))));
+        ir.main = vec![Instruction::call("mystery"), Instruction::Add];
+        assert!(ir.verify().is_ok());
+    }
+
+    #[test]
+    fn test_instruction_display() {
+        assert_eq!(Instruction::Literal(-3).to_string(), "-3");
+        assert_eq!(Instruction::Add.to_string(), "+");
+        assert_eq!(Instruction::call("foo").to_string(), "call foo");
+        assert_eq!(Instruction::BranchIfNot(BlockRef(2)).to_string(), "0branch bb2");
+        assert_eq!(Instruction::BranchIfElse(BlockRef(1), BlockRef(2)).to_string(), "branch-if bb1 else bb2");
+        assert_eq!(Instruction::label("bb1").to_string(), "bb1:");
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut ir = ForthIR::parse(": square dup * ; variable x 5 square x !").unwrap();
+        let mut word = WordDef::new("hot".to_string(), vec![Instruction::CachedDup { depth: 2 }, Instruction::label("l1")]);
+        word.is_inline = true;
+        ir.add_word(word);
+        ir.main.extend([
+            Instruction::FloatLiteral(0.1),
+            Instruction::FloatLiteral(3.0e-310),
+            Instruction::FloatLiteral(-0.0),
+            Instruction::FloatLiteral(f64::INFINITY),
+            Instruction::FloatLiteral(1.0 / 3.0),
+            Instruction::Comment("keep \"quotes\"".to_string()),
+        ]);
+
+        let json = ir.to_json();
+        assert_eq!(ForthIR::from_json(&json).unwrap(), ir);
+        assert_eq!(json, ForthIR::from_json(&json).unwrap().to_json());
+
+        let floats: Vec<u64> = ForthIR::from_json(&json)
+            .unwrap()
+            .main
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::FloatLiteral(v) => Some(v.to_bits()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(floats, [0.1, 3.0e-310, -0.0, f64::INFINITY, 1.0 / 3.0].map(f64::to_bits));
+
+        // Older documents without optional fields still load; newer ones are refused
+        let old = r#"{"version": 1, "ir": {"words": {}, "main": ["Dup", {"Literal": 7}, {"Call": "square"}]}}"#;
+        assert_eq!(
+            ForthIR::from_json(old).unwrap().main,
+            vec![Instruction::Dup, Instruction::Literal(7), Instruction::call("square")]
+        );
+        let newer = json.replacen(&format!("\"version\": {}", IR_JSON_VERSION), "\"version\": 99", 1);
+        assert!(ForthIR::from_json(&newer).unwrap_err().to_string().contains("newer"));
+        assert!(ForthIR::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_symbols_interned() {
+        let square = Symbol::intern("square");
+        assert_eq!(square, Symbol::from("square".to_string()));
+        assert_ne!(square, Symbol::intern("cube"));
+        assert_eq!(square.as_str(), "square");
+        assert_eq!(square, "square");
+
+        // Separately built instructions compare equal by name
+        assert_eq!(Instruction::call("square"), Instruction::Call(square));
+        assert_eq!(format!("{:?}", Instruction::call("square")), "Call(\"square\")");
+    }
+
+    #[test]
+    fn test_symbols_interned_per_thread() {
+        let here = Symbol::intern("square");
+        let there = std::thread::spawn(|| Symbol::intern("square")).join().unwrap();
+
+        // Another thread's table made its own copy, which still compares and hashes the same
+        assert!(!Arc::ptr_eq(&here.0, &there.0));
+        assert_eq!(here, there);
+        assert!([here].into_iter().collect::<HashSet<_>>().contains(&there));
+    }
+
+    #[test]
+    fn test_symbols_freed_with_last_use() {
+        let transient = Symbol::intern("test_symbols_freed_with_last_use");
+        assert!(Arc::ptr_eq(&transient.0, &Symbol::intern("test_symbols_freed_with_last_use").0));
+
+        // The table does not keep the name alive
+        let name = Arc::downgrade(&transient.0);
+        drop(transient);
+        assert!(name.upgrade().is_none());
+        assert_eq!(Symbol::intern("test_symbols_freed_with_last_use"), "test_symbols_freed_with_last_use");
+    }
+
+    #[test]
+    fn test_ir_display() {
+        let mut ir = ForthIR::parse("5 square").unwrap();
+        ir.add_word(WordDef::new(
+            "square".to_string(),
+            vec![Instruction::Dup, Instruction::Mul],
+        ));
+
+        assert_eq!(
+            ir.to_string(),
+            ": square (1 -- 1)\n    dup\n    *\n;\nmain:\n    5\n    call square\n"
+        );
+    }
+
+    #[test]
+    fn test_branch_targets_check() {
+        use Instruction::*;
+        let label = |id| Label(block_label(BlockRef(id)));
+
+        let blocks = vec![label(0), Literal(1), BranchIfElse(BlockRef(1), BlockRef(2)), label(1), Return, label(2), Return];
+        assert!(BranchTargets::of(&blocks).check(&blocks).is_ok());
+        assert_eq!(BranchTargets::of(&blocks).landing_sites(&blocks), [3, 5].into_iter().collect());
+
+        // A deleted block that is still branched to
+        let deleted = vec![label(0), Literal(1), BranchIfElse(BlockRef(1), BlockRef(2)), label(1), Return];
+        assert!(matches!(
+            BranchTargets::of(&deleted).check(&deleted),
+            Err(OptimizerError::InvalidBranchTarget(_))
+        ));
+
+        let duplicated = vec![label(0), Branch(BlockRef(0)), label(0), Return];
+        assert!(BranchTargets::of(&duplicated).check(&duplicated).is_err());
+
+        // Operands name blocks even in a sequence without labels
+        let unlabelled = vec![Literal(1), BranchIf(BlockRef(3)), Literal(2), Return];
+        assert!(BranchTargets::of(&unlabelled).check(&unlabelled).is_err());
+    }
+
+    #[test]
+    fn test_inline_body_renumbers_blocks() {
+        use Instruction::*;
+        let label = |id| Label(block_label(BlockRef(id)));
+        let word = WordDef::new(
+            "sign".to_string(),
+            vec![label(0), BranchIf(BlockRef(1)), label(1), Return, label(2), Literal(0), Return],
+        );
+
+        let mut next_block = 4;
+        let body = word.inline_body(&mut next_block).unwrap();
+        assert_eq!(
+            body,
+            vec![label(4), BranchIf(BlockRef(5)), label(5), Branch(BlockRef(7)), label(6), Literal(0), label(7)]
+        );
+        assert_eq!(next_block, 8);
+
+        // An early return gets an exit block even when the body has no labels
+        let early = WordDef::new("early".to_string(), vec![Dup, Return, Drop, Return]);
+        assert_eq!(early.inline_body(&mut next_block).unwrap(), vec![Dup, Branch(BlockRef(8)), Drop, label(8)]);
+        assert_eq!(next_block, 9);
+
+        // Branches to blocks the body doesn't label can't be moved into another sequence
+        let dangling = WordDef::new("skip".to_string(), vec![BranchIf(BlockRef(2)), Drop, Return]);
+        assert_eq!(dangling.inline_body(&mut next_block), None);
+    }
 }
@@ -14,8 +14,11 @@
 //! - **Stack Caching**: Keep TOS/NOS/3OS in registers (2-3x speedup)
 //! - **Superinstructions**: Fuse common patterns (20-30% code size reduction)
 //! - **Constant Folding**: Compile-time evaluation of constants
+//! - **Common Subexpression Elimination**: Reuse values already on the stack
 //! - **Dead Code Elimination**: Remove unused stack operations
+//! - **Peephole**: Remove cancelling stack shuffles (`swap swap`, `dup drop`, `0 +`, ...)
 //! - **Inlining**: Expand small words with stack effect analysis
+//! - **Tail Call Optimization**: Turn self-recursive tail calls into loops
 //! - **Memory Optimization**: Alias analysis, load/store reordering, prefetching (5-15% speedup)
 //!
 //! # Example
@@ -39,6 +42,7 @@ pub mod superinstructions;
 pub mod pgo_superinstructions;
 pub mod constant_fold;
 pub mod dead_code;
+pub mod dead_words;
 pub mod inline;
 pub mod aggressive_inline;
 pub mod analysis;
@@ -48,21 +52,35 @@ pub mod memory_opt;
 pub mod whole_program;
 pub mod zero_cost;
 pub mod cranelift_peephole;
+pub mod tail_call;
+pub mod cse;
+pub mod peephole;
+pub mod explain;
 
-pub use ir::{ForthIR, Instruction, StackEffect, WordDef};
-pub use stack_cache::StackCacheOptimizer;
+pub use ir::{
+    block_label, BlockRef, BranchTargets, ForthIR, Instruction, StackEffect, Symbol, WordDef, DEFAULT_MAX_STACK_DEPTH,
+    IR_JSON_VERSION,
+};
+pub use stack_cache::{StackCacheOptimizer, MAX_CACHE_DEPTH};
 pub use superinstructions::SuperinstructionOptimizer;
 pub use pgo_superinstructions::{PGOOptimizer, PatternDatabase, PGOStats, PGOConfig};
 pub use constant_fold::ConstantFolder;
 pub use dead_code::DeadCodeEliminator;
-pub use inline::InlineOptimizer;
+pub use dead_words::DeadWordEliminator;
+pub use inline::{InlineConfig, InlineOptimizer, ProfileData};
 pub use aggressive_inline::{AggressiveInlineOptimizer, CallGraph, AggressiveInlineStats, InlineDirective};
 pub use type_specialization::{TypeSpecializer, TypeInferenceResults, ConcreteType, TypeSignature, SpecializationStats};
 pub use memory_opt::{MemoryOptimizer, OptimizationStats as MemoryOptimizationStats};
 pub use whole_program::{WholeProgramOptimizer, WPOStats};
 pub use zero_cost::{ZeroCostOptimizer, ZeroCostConfig, ZeroCostStats};
 pub use cranelift_peephole::{CraneliftPeephole, PeepholeStats};
+pub use tail_call::TailCallOptimizer;
+pub use cse::CommonSubexpressionEliminator;
+pub use peephole::PeepholeOptimizer;
+pub use explain::OptimizationNote;
 
+use rayon::prelude::*;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -81,6 +99,9 @@ pub enum OptimizerError {
 
     #[error("Parse error: {0}")]
     ParseError(String),
+
+    #[error("Invalid branch target: {0}")]
+    InvalidBranchTarget(String),
 }
 
 pub type Result<T> = std::result::Result<T, OptimizerError>;
@@ -97,6 +118,330 @@ pub enum OptimizationLevel {
     Aggressive,
 }
 
+/// Individual optimization pass, for running passes selectively
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PassKind {
+    /// Zero-cost abstraction elimination
+    ZeroCost,
+    /// Constant folding and algebraic simplification
+    ConstantFold,
+    /// Common subexpression elimination
+    CommonSubexpression,
+    /// Stack-shuffle peephole rewrites
+    Peephole,
+    /// Cranelift-specific peephole (strength reduction, etc.)
+    CraneliftPeephole,
+    /// Inlining of small definitions
+    Inline,
+    /// Self tail-call optimization
+    TailCall,
+    /// Superinstruction recognition
+    Superinstructions,
+    /// Dead code elimination
+    DeadCode,
+    /// Removal of word definitions unreachable from `main`
+    DeadWords,
+    /// Memory access optimization
+    MemoryOpt,
+    /// Stack caching
+    StackCache,
+}
+
+/// Upper bound on pipeline rounds in `Optimizer::optimize_until_fixpoint`
+pub const MAX_FIXPOINT_ITERATIONS: usize = 10;
+
+/// Canonical pass order with the minimum level at which each pass runs
+///
+/// Constant folding runs again after inlining, since an inlined body often
+/// leaves literals next to the caller's constants (`5 double` -> `5 2 *`).
+const PIPELINE: [(PassKind, OptimizationLevel); 13] = [
+    (PassKind::ZeroCost, OptimizationLevel::Aggressive),
+    (PassKind::ConstantFold, OptimizationLevel::Basic),
+    (PassKind::CommonSubexpression, OptimizationLevel::Standard),
+    (PassKind::Peephole, OptimizationLevel::Basic),
+    (PassKind::CraneliftPeephole, OptimizationLevel::Basic),
+    (PassKind::Inline, OptimizationLevel::Standard),
+    (PassKind::TailCall, OptimizationLevel::Standard),
+    (PassKind::ConstantFold, OptimizationLevel::Standard),
+    (PassKind::Superinstructions, OptimizationLevel::Basic),
+    (PassKind::DeadCode, OptimizationLevel::Basic),
+    (PassKind::DeadWords, OptimizationLevel::Aggressive),
+    (PassKind::MemoryOpt, OptimizationLevel::Standard),
+    (PassKind::StackCache, OptimizationLevel::Standard),
+];
+
+impl PassKind {
+    /// All passes in canonical order, each listed once
+    pub fn all() -> Vec<PassKind> {
+        let mut passes: Vec<PassKind> = Vec::new();
+        for (pass, _) in PIPELINE.iter() {
+            if !passes.contains(pass) {
+                passes.push(*pass);
+            }
+        }
+        passes
+    }
+
+    /// Passes run by `Optimizer::optimize` at the given level, in order
+    pub fn pipeline(level: OptimizationLevel) -> Vec<PassKind> {
+        PIPELINE
+            .iter()
+            .filter(|(_, min_level)| level >= *min_level)
+            .map(|(pass, _)| *pass)
+            .collect()
+    }
+
+    /// Human-readable pass name
+    pub fn name(&self) -> &'static str {
+        match self {
+            PassKind::ZeroCost => "zero-cost",
+            PassKind::ConstantFold => "constant-fold",
+            PassKind::CommonSubexpression => "cse",
+            PassKind::Peephole => "peephole",
+            PassKind::CraneliftPeephole => "cranelift-peephole",
+            PassKind::Inline => "inline",
+            PassKind::TailCall => "tail-call",
+            PassKind::Superinstructions => "superinstructions",
+            PassKind::DeadCode => "dead-code",
+            PassKind::DeadWords => "dead-words",
+            PassKind::MemoryOpt => "memory-opt",
+            PassKind::StackCache => "stack-cache",
+        }
+    }
+}
+
+impl std::str::FromStr for PassKind {
+    type Err = OptimizerError;
+
+    /// Parse a pass from its `name`
+    fn from_str(s: &str) -> Result<Self> {
+        PassKind::all().into_iter().find(|pass| pass.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = PassKind::all().iter().map(|pass| pass.name()).collect();
+            OptimizerError::ParseError(format!("Unknown pass '{}', expected one of: {}", s, names.join(", ")))
+        })
+    }
+}
+
+/// Instruction counts and time for a single optimization pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassRecord {
+    pub pass: PassKind,
+    /// Number of times the pass ran (0 means it was skipped)
+    pub runs: usize,
+    /// Instruction count before the first run
+    pub instructions_before: usize,
+    /// Instruction count after the last run
+    pub instructions_after: usize,
+    /// Instructions removed, summed over runs
+    pub removed: usize,
+    /// Instructions added, summed over runs
+    pub added: usize,
+    /// Time spent in the pass, summed over runs
+    pub duration: Duration,
+}
+
+impl PassRecord {
+    fn skipped(pass: PassKind) -> Self {
+        Self {
+            pass,
+            runs: 0,
+            instructions_before: 0,
+            instructions_after: 0,
+            removed: 0,
+            added: 0,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Whether the pass did not run (e.g. because of the optimization level)
+    pub fn is_skipped(&self) -> bool {
+        self.runs == 0
+    }
+
+    /// Net change in instruction count
+    pub fn net_change(&self) -> i64 {
+        self.added as i64 - self.removed as i64
+    }
+}
+
+/// Per-pass optimization statistics
+///
+/// The net changes of all passes sum to `instructions_after - instructions_before`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassStats {
+    /// One record per pass, in canonical pass order
+    pub passes: Vec<PassRecord>,
+    /// Instruction count before optimization
+    pub instructions_before: usize,
+    /// Instruction count after optimization
+    pub instructions_after: usize,
+}
+
+impl PassStats {
+    fn new(instructions: usize) -> Self {
+        Self {
+            passes: PassKind::all().into_iter().map(PassRecord::skipped).collect(),
+            instructions_before: instructions,
+            instructions_after: instructions,
+        }
+    }
+
+    fn record(&mut self, pass: PassKind, before: usize, after: usize, duration: Duration) {
+        if let Some(record) = self.passes.iter_mut().find(|r| r.pass == pass) {
+            if record.runs == 0 {
+                record.instructions_before = before;
+            }
+            record.runs += 1;
+            record.instructions_after = after;
+            record.removed += before.saturating_sub(after);
+            record.added += after.saturating_sub(before);
+            record.duration += duration;
+        }
+        self.instructions_after = after;
+    }
+
+    /// Add the statistics of a separately optimized part of the program
+    fn absorb(&mut self, part: &PassStats) {
+        for other in &part.passes {
+            if let Some(record) = self.passes.iter_mut().find(|r| r.pass == other.pass) {
+                record.runs = record.runs.max(other.runs);
+                record.instructions_before += other.instructions_before;
+                record.instructions_after += other.instructions_after;
+                record.removed += other.removed;
+                record.added += other.added;
+                record.duration += other.duration;
+            }
+        }
+        self.instructions_before += part.instructions_before;
+        self.instructions_after += part.instructions_after;
+    }
+
+    /// Statistics for a single pass
+    pub fn get(&self, pass: PassKind) -> Option<&PassRecord> {
+        self.passes.iter().find(|r| r.pass == pass)
+    }
+
+    /// Accumulate statistics from a later optimization run (e.g. fixpoint iterations)
+    pub fn merge(&mut self, later: &PassStats) {
+        if self.passes.is_empty() {
+            *self = later.clone();
+            return;
+        }
+
+        for other in &later.passes {
+            if other.is_skipped() {
+                continue;
+            }
+            if let Some(record) = self.passes.iter_mut().find(|r| r.pass == other.pass) {
+                if record.runs == 0 {
+                    record.instructions_before = other.instructions_before;
+                }
+                record.runs += other.runs;
+                record.instructions_after = other.instructions_after;
+                record.removed += other.removed;
+                record.added += other.added;
+                record.duration += other.duration;
+            }
+        }
+        self.instructions_after = later.instructions_after;
+    }
+}
+
+/// Configuration for the optimizer
+#[derive(Debug, Clone)]
+pub struct OptimizerConfig {
+    /// Optimization level
+    pub level: OptimizationLevel,
+    /// Number of stack items cached in registers (0 disables stack caching,
+    /// depths above [`MAX_CACHE_DEPTH`] are clamped to it)
+    pub stack_cache_depth: u8,
+    /// Treat signed overflow in `+ - *` as an error instead of wrapping
+    pub checked_arithmetic: bool,
+    /// Verify stack balance after every pass, naming the pass that broke it
+    pub verify_each_pass: bool,
+    /// Optimize groups of words that never call each other on separate threads
+    pub parallel: bool,
+    /// Deepest data stack the optimized program may reach (see `ForthIR::verify_with_limit`)
+    pub max_stack_depth: usize,
+    /// Call counts from a profiling run, used to inline hot words
+    pub profile: Option<ProfileData>,
+    /// Cost model for inlining (defaults to `InlineConfig::for_level(level)`)
+    pub inline: InlineConfig,
+    /// Record why passes did or did not transform the code (see [`explain`])
+    pub explain: bool,
+}
+
+impl OptimizerConfig {
+    /// Default configuration for the given level (TOS, NOS, 3OS cached)
+    pub fn new(level: OptimizationLevel) -> Self {
+        Self {
+            level,
+            stack_cache_depth: 3,
+            checked_arithmetic: false,
+            verify_each_pass: false,
+            parallel: false,
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
+            profile: None,
+            inline: InlineConfig::for_level(level),
+            explain: false,
+        }
+    }
+
+    /// Set the stack cache depth (0-8); larger depths are clamped to [`MAX_CACHE_DEPTH`]
+    pub fn with_stack_cache_depth(mut self, depth: u8) -> Self {
+        self.stack_cache_depth = depth.min(MAX_CACHE_DEPTH);
+        self
+    }
+
+    /// Refuse to fold constant expressions that overflow
+    pub fn with_checked_arithmetic(mut self, checked: bool) -> Self {
+        self.checked_arithmetic = checked;
+        self
+    }
+
+    /// Verify the IR after each pass instead of only at the end (for debugging passes)
+    pub fn with_verify_each_pass(mut self, verify: bool) -> Self {
+        self.verify_each_pass = verify;
+        self
+    }
+
+    /// Split the program along the call graph and optimize the parts in parallel
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Reject programs that can grow the data stack deeper than `depth`
+    pub fn with_max_stack_depth(mut self, depth: usize) -> Self {
+        self.max_stack_depth = depth;
+        self
+    }
+
+    /// Guide inlining by the call counts of a profiling run
+    pub fn with_profile(mut self, profile: ProfileData) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Replace the inlining cost model
+    pub fn with_inline_config(mut self, inline: InlineConfig) -> Self {
+        self.inline = inline;
+        self
+    }
+
+    /// Record optimization notes, available from `Optimizer::notes` after a run
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        Self::new(OptimizationLevel::Standard)
+    }
+}
+
 /// Main optimizer that coordinates all optimization passes
 pub struct Optimizer {
     level: OptimizationLevel,
@@ -106,30 +451,132 @@ pub struct Optimizer {
     pgo: PGOOptimizer,
     constant_fold: ConstantFolder,
     dead_code: DeadCodeEliminator,
+    dead_words: DeadWordEliminator,
     inline: InlineOptimizer,
     type_specializer: TypeSpecializer,
     memory_opt: MemoryOptimizer,
     cranelift_peephole: CraneliftPeephole,
+    tail_call: TailCallOptimizer,
+    cse: CommonSubexpressionEliminator,
+    peephole: PeepholeOptimizer,
     // whole_program: WholeProgramOptimizer, // Temporarily disabled
     pgo_enabled: bool,
+    /// Verify after every pass rather than once at the end
+    verify_each_pass: bool,
+    /// Explicit pass ordering (overrides the level-based pipeline)
+    passes: Option<Vec<PassKind>>,
+    /// Statistics from the most recent optimization run
+    pass_stats: PassStats,
+    /// Notes from the most recent optimization run, in explain mode
+    notes: Vec<OptimizationNote>,
+    /// Configuration the optimizer was built from, for optimizing parts in parallel
+    config: OptimizerConfig,
 }
 
 impl Optimizer {
     pub fn new(level: OptimizationLevel) -> Self {
+        Self::with_config(OptimizerConfig::new(level))
+    }
+
+    /// Create an optimizer from an explicit configuration
+    pub fn with_config(config: OptimizerConfig) -> Self {
+        let level = config.level;
         Self {
             level,
-            zero_cost: ZeroCostOptimizer::default(),
-            stack_cache: StackCacheOptimizer::new(3), // TOS, NOS, 3OS
+            zero_cost: ZeroCostOptimizer::new(ZeroCostConfig {
+                checked_arithmetic: config.checked_arithmetic,
+                ..ZeroCostConfig::default()
+            }),
+            stack_cache: StackCacheOptimizer::new(config.stack_cache_depth.min(MAX_CACHE_DEPTH)),
             superinstructions: SuperinstructionOptimizer::new(),
             pgo: PGOOptimizer::new(),
-            constant_fold: ConstantFolder::new(),
+            constant_fold: ConstantFolder::new().with_checked_arithmetic(config.checked_arithmetic),
             dead_code: DeadCodeEliminator::new(),
-            inline: InlineOptimizer::new(level),
+            dead_words: DeadWordEliminator::new(),
+            inline: match &config.profile {
+                Some(profile) => InlineOptimizer::with_config(level, config.inline.clone())
+                    .with_profile(profile.clone()),
+                None => InlineOptimizer::with_config(level, config.inline.clone()),
+            },
             type_specializer: TypeSpecializer::new(),
             memory_opt: MemoryOptimizer::new(),
-            cranelift_peephole: CraneliftPeephole::new(),
+            cranelift_peephole: CraneliftPeephole::new().with_checked_arithmetic(config.checked_arithmetic),
+            tail_call: TailCallOptimizer::new(),
+            cse: CommonSubexpressionEliminator::new(),
+            peephole: PeepholeOptimizer::new(),
             // whole_program: WholeProgramOptimizer::new(level), // Temporarily disabled
             pgo_enabled: false,
+            verify_each_pass: config.verify_each_pass,
+            passes: None,
+            pass_stats: PassStats::default(),
+            notes: Vec::new(),
+            config,
+        }
+    }
+
+    /// Run exactly the given passes, in order, instead of the level-based pipeline
+    pub fn with_passes(mut self, passes: Vec<PassKind>) -> Result<Self> {
+        if passes.is_empty() {
+            return Err(OptimizerError::OptimizationFailed(
+                "Pass list must contain at least one pass".to_string(),
+            ));
+        }
+        self.passes = Some(passes);
+        Ok(self)
+    }
+
+    /// Passes that `optimize` will run, in order
+    pub fn passes(&self) -> Vec<PassKind> {
+        match &self.passes {
+            Some(passes) => passes.clone(),
+            None => PassKind::pipeline(self.level),
+        }
+    }
+
+    /// Run a single pass and verify the result
+    ///
+    /// Takes `&mut self` as `optimize` does: the Cranelift peephole pass adds
+    /// to [`peephole_stats`](Self::peephole_stats), and in explain mode every
+    /// pass adds to [`notes`](Self::notes).
+    pub fn run_pass(&mut self, ir: ForthIR, pass: PassKind) -> Result<ForthIR> {
+        let ir = self.apply_pass(ir, pass)?;
+        self.verify(&ir)?;
+        Ok(ir)
+    }
+
+    /// Check stack effects against the configured depth limit
+    fn verify(&self, ir: &ForthIR) -> Result<()> {
+        ir.verify_with_limit(self.config.max_stack_depth)
+    }
+
+    /// Apply a single pass without verification
+    fn apply_pass(&mut self, ir: ForthIR, pass: PassKind) -> Result<ForthIR> {
+        if self.config.explain {
+            let explained = match pass {
+                PassKind::Inline => Some(self.inline.inline_explained(&ir)?),
+                PassKind::Superinstructions => Some(self.superinstructions.recognize_explained(&ir)?),
+                PassKind::DeadCode => Some(self.dead_code.eliminate_explained(&ir)?),
+                _ => None,
+            };
+            if let Some((ir, notes)) = explained {
+                self.notes.extend(notes);
+                return Ok(ir);
+            }
+        }
+
+        match pass {
+            PassKind::ZeroCost => self.zero_cost.optimize(&ir),
+            PassKind::ConstantFold => self.constant_fold.fold(&ir),
+            PassKind::CommonSubexpression => self.cse.eliminate(&ir),
+            PassKind::Peephole => self.peephole.optimize(&ir),
+            PassKind::CraneliftPeephole => self.cranelift_peephole.optimize(&ir),
+            PassKind::Inline => self.inline.inline(&ir),
+            PassKind::TailCall => self.tail_call.optimize(&ir),
+            PassKind::Superinstructions => self.superinstructions.recognize(&ir),
+            PassKind::DeadCode => self.dead_code.eliminate(&ir),
+            PassKind::DeadWords => self.dead_words.eliminate(&ir),
+            PassKind::MemoryOpt => self.memory_opt.optimize(&ir),
+            PassKind::StackCache => self.stack_cache.optimize(&ir),
         }
     }
 
@@ -174,53 +621,148 @@ impl Optimizer {
     }
 
     /// Run all optimization passes in the optimal order
-    pub fn optimize(&mut self, mut ir: ForthIR) -> Result<ForthIR> {
-        if self.level == OptimizationLevel::None {
-            return Ok(ir);
-        }
+    ///
+    /// Uses the explicit pass list from `with_passes` if one was given,
+    /// otherwise the pipeline for the optimization level (see `PassKind::pipeline`).
+    pub fn optimize(&mut self, ir: ForthIR) -> Result<ForthIR> {
+        self.optimize_with_stats(ir).map(|(ir, _)| ir)
+    }
 
-        // Pass 0: Zero-cost abstractions (aggressive inlining, constant folding, algebraic simplification)
-        // This early aggressive pass eliminates abstraction overhead
-        if self.level >= OptimizationLevel::Aggressive {
-            ir = self.zero_cost.optimize(&ir)?;
-        }
+    /// Run all optimization passes in explain mode, also returning the notes
+    /// the passes recorded, in the order they were made
+    ///
+    /// The optimized IR is the same as that of `optimize`.
+    pub fn optimize_with_notes(&mut self, ir: ForthIR) -> Result<(ForthIR, Vec<OptimizationNote>)> {
+        let explain = std::mem::replace(&mut self.config.explain, true);
+        let result = self.optimize_with_stats(ir);
+        self.config.explain = explain;
+        Ok((result?.0, self.notes.clone()))
+    }
 
-        // Pass 1: Constant folding (enables other optimizations)
-        ir = self.constant_fold.fold(&ir)?;
+    /// Run all optimization passes, also returning per-pass statistics
+    ///
+    /// With [`OptimizerConfig::parallel`] set, groups of words that never call
+    /// each other (see [`whole_program::CallGraph::components`]) are optimized
+    /// on the rayon thread pool and merged back in a fixed order, so the
+    /// result is the same as optimizing the whole program at once.
+    pub fn optimize_with_stats(&mut self, ir: ForthIR) -> Result<(ForthIR, PassStats)> {
+        self.notes.clear();
+        let (ir, stats) = match self.partition(&ir) {
+            Some(parts) => self.optimize_parts(ir, parts)?,
+            None => self.run_pipeline(ir)?,
+        };
 
-        // Pass 1.5: Cranelift-specific peephole optimizations (strength reduction, etc.)
-        // Run after constant folding for maximum effectiveness
-        if self.level >= OptimizationLevel::Basic {
-            ir = self.cranelift_peephole.optimize(&ir)?;
-        }
+        self.pass_stats = stats.clone();
+        Ok((ir, stats))
+    }
 
-        // Pass 2: Inlining (expands small definitions)
-        if self.level >= OptimizationLevel::Standard {
-            ir = self.inline.inline(&ir)?;
+    /// Split `ir` into independently optimizable parts, if parallel
+    /// optimization is enabled and there is more than one
+    fn partition(&self, ir: &ForthIR) -> Option<Vec<ForthIR>> {
+        if !self.config.parallel || (self.passes.is_none() && self.level == OptimizationLevel::None) {
+            return None;
         }
 
-        // Pass 3: Superinstruction recognition (after inlining)
-        if self.level >= OptimizationLevel::Basic {
-            ir = self.superinstructions.recognize(&ir)?;
+        let components = whole_program::CallGraph::build(ir).components();
+        if components.len() < 2 {
+            return None;
         }
 
-        // Pass 4: Dead code elimination
-        ir = self.dead_code.eliminate(&ir)?;
+        let parts = components
+            .into_iter()
+            .map(|names| {
+                let mut part = ForthIR::new();
+                part.variables = ir.variables.clone();
+                for name in names {
+                    match ir.words.get(&name) {
+                        Some(word) => {
+                            part.words.insert(name, word.clone());
+                        }
+                        None => part.main = ir.main.clone(),
+                    }
+                }
+                part
+            })
+            .collect();
+        Some(parts)
+    }
 
-        // Pass 5: Memory optimization (before stack caching)
-        if self.level >= OptimizationLevel::Standard {
-            ir = self.memory_opt.optimize(&ir)?;
+    /// Optimize each part on its own optimizer and merge the results
+    fn optimize_parts(&mut self, ir: ForthIR, parts: Vec<ForthIR>) -> Result<(ForthIR, PassStats)> {
+        let config = self.config.clone().with_parallel(false);
+        let results: Vec<Result<(ForthIR, PassStats, Vec<OptimizationNote>)>> = parts
+            .into_par_iter()
+            .map(|part| {
+                let mut optimizer = Optimizer::with_config(config.clone());
+                optimizer.passes = self.passes.clone();
+                let (part, stats) = optimizer.run_pipeline(part)?;
+                Ok((part, stats, optimizer.notes))
+            })
+            .collect();
+
+        // Merge in component order, reporting the first error in that order
+        let mut merged = ForthIR::new();
+        merged.variables = ir.variables;
+        let mut stats = PassStats::new(0);
+        for result in results {
+            let (part, part_stats, notes) = result?;
+            self.notes.extend(notes);
+            merged.words.extend(part.words);
+            if !part.main.is_empty() {
+                merged.main = part.main;
+            }
+            stats.absorb(&part_stats);
         }
 
-        // Pass 6: Stack caching (final pass before codegen)
-        if self.level >= OptimizationLevel::Standard {
-            ir = self.stack_cache.optimize(&ir)?;
+        Ok((merged, stats))
+    }
+
+    /// Run the pass pipeline over the whole of `ir`
+    fn run_pipeline(&mut self, mut ir: ForthIR) -> Result<(ForthIR, PassStats)> {
+        let mut stats = PassStats::new(ir.instruction_count());
+
+        if self.passes.is_some() || self.level != OptimizationLevel::None {
+            if self.verify_each_pass {
+                self.verify(&ir).map_err(|err| {
+                    OptimizerError::OptimizationFailed(format!("Input IR is unbalanced: {}", err))
+                })?;
+            }
+
+            for pass in self.passes() {
+                let before = ir.instruction_count();
+                let start = Instant::now();
+                ir = self.apply_pass(ir, pass)?;
+                stats.record(pass, before, ir.instruction_count(), start.elapsed());
+
+                if self.verify_each_pass {
+                    self.verify(&ir).map_err(|err| {
+                        OptimizerError::OptimizationFailed(format!(
+                            "Pass '{}' broke stack balance: {}",
+                            pass.name(),
+                            err
+                        ))
+                    })?;
+                }
+            }
+
+            // Verify stack effects are still valid
+            self.verify(&ir)?;
         }
 
-        // Verify stack effects are still valid
-        ir.verify()?;
+        Ok((ir, stats))
+    }
 
-        Ok(ir)
+    /// Statistics from the most recent `optimize` or `optimize_until_fixpoint` call
+    pub fn pass_stats(&self) -> &PassStats {
+        &self.pass_stats
+    }
+
+    /// Notes from the most recent optimization run, if the optimizer was
+    /// configured with [`OptimizerConfig::with_explain`]
+    ///
+    /// After `optimize_until_fixpoint`, holds the notes of every round.
+    pub fn notes(&self) -> &[OptimizationNote] {
+        &self.notes
     }
 
     /// Run optimization with type specialization
@@ -242,7 +784,17 @@ impl Optimizer {
         // Pass 2: Constant folding (enables other optimizations)
         ir = self.constant_fold.fold(&ir)?;
 
-        // Pass 2.5: Cranelift-specific peephole optimizations
+        // Pass 2.25: Common subexpression elimination
+        if self.level >= OptimizationLevel::Standard {
+            ir = self.cse.eliminate(&ir)?;
+        }
+
+        // Pass 2.5: Stack-shuffle peephole (before strength reduction rewrites `1 *`)
+        if self.level >= OptimizationLevel::Basic {
+            ir = self.peephole.optimize(&ir)?;
+        }
+
+        // Pass 2.75: Cranelift-specific peephole optimizations
         if self.level >= OptimizationLevel::Basic {
             ir = self.cranelift_peephole.optimize(&ir)?;
         }
@@ -252,6 +804,16 @@ impl Optimizer {
             ir = self.inline.inline(&ir)?;
         }
 
+        // Pass 3.5: Tail call optimization (after inlining, which skips recursive words)
+        if self.level >= OptimizationLevel::Standard {
+            ir = self.tail_call.optimize(&ir)?;
+        }
+
+        // Pass 3.75: Fold constants exposed by inlining
+        if self.level >= OptimizationLevel::Standard {
+            ir = self.constant_fold.fold(&ir)?;
+        }
+
         // Pass 4: Superinstruction recognition (after inlining)
         if self.level >= OptimizationLevel::Basic {
             ir = self.superinstructions.recognize(&ir)?;
@@ -271,7 +833,7 @@ impl Optimizer {
         }
 
         // Verify stack effects are still valid
-        ir.verify()?;
+        self.verify(&ir)?;
 
         Ok(ir)
     }
@@ -297,21 +859,31 @@ impl Optimizer {
     // }
 
     /// Run optimization passes in a loop until fixpoint
+    ///
+    /// Each round runs the full pipeline, so code exposed by one round (e.g.
+    /// inlining a word that was itself only just simplified) is optimized by
+    /// the next. Stops once a round changes nothing, or after
+    /// [`MAX_FIXPOINT_ITERATIONS`] rounds.
     pub fn optimize_until_fixpoint(&mut self, ir: ForthIR) -> Result<ForthIR> {
         let mut current = ir;
-        let mut iterations = 0;
-        const MAX_ITERATIONS: usize = 10;
+        let mut total_stats = PassStats::default();
+        let mut notes = Vec::new();
 
-        loop {
-            let optimized = self.optimize(current.clone())?;
-
-            if optimized == current || iterations >= MAX_ITERATIONS {
-                return Ok(optimized);
-            }
+        for _ in 0..MAX_FIXPOINT_ITERATIONS {
+            let (optimized, stats) = self.optimize_with_stats(current.clone())?;
+            total_stats.merge(&stats);
+            notes.append(&mut self.notes);
 
+            let converged = optimized == current;
             current = optimized;
-            iterations += 1;
+            if converged {
+                break;
+            }
         }
+
+        self.pass_stats = total_stats;
+        self.notes = notes;
+        Ok(current)
     }
 }
 
@@ -345,4 +917,330 @@ mod tests {
         // Memory optimizer should be initialized
         assert!(std::ptr::addr_of!(*mem_opt) as usize != 0);
     }
+
+    #[test]
+    fn test_optimizer_config_stack_cache_depth() {
+        let config = OptimizerConfig::new(OptimizationLevel::Standard).with_stack_cache_depth(5);
+        let opt = Optimizer::with_config(config);
+        assert_eq!(opt.stack_cache.cache_size(), 5);
+
+        let mut disabled = Optimizer::with_config(
+            OptimizerConfig::new(OptimizationLevel::Standard).with_stack_cache_depth(0),
+        );
+        let ir = ForthIR::parse("1 2 3 4").unwrap();
+        let optimized = disabled.optimize(ir).unwrap();
+        assert!(!optimized.main.iter().any(|i| matches!(i, Instruction::FlushCache)));
+    }
+
+    #[test]
+    fn test_stack_cache_depth_above_maximum_is_clamped() {
+        let config = OptimizerConfig::new(OptimizationLevel::Standard).with_stack_cache_depth(9);
+        assert_eq!(config.stack_cache_depth, MAX_CACHE_DEPTH);
+
+        // Setting the field directly must not panic either
+        let mut config = OptimizerConfig::new(OptimizationLevel::Standard);
+        config.stack_cache_depth = 200;
+        let mut opt = Optimizer::with_config(config);
+        assert_eq!(opt.stack_cache.cache_size(), MAX_CACHE_DEPTH);
+        assert!(opt.optimize(ForthIR::parse("1 2 3 + +").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_each_pass() {
+        let mut ir = ForthIR::new();
+        ir.main = vec![Instruction::Literal(1), Instruction::Add];
+
+        let mut optimizer = Optimizer::with_config(
+            OptimizerConfig::new(OptimizationLevel::Basic).with_verify_each_pass(true),
+        );
+        match optimizer.optimize(ir) {
+            Err(OptimizerError::OptimizationFailed(msg)) => {
+                assert_eq!(msg, "Input IR is unbalanced: Stack underflow at instruction 1");
+            }
+            other => panic!("expected the input to be rejected, got {:?}", other),
+        }
+
+        let ir = ForthIR::parse("1 2 + dup *").unwrap();
+        assert!(optimizer.optimize(ir).is_ok());
+    }
+
+    #[test]
+    fn test_if_keeps_valid_branch_targets() {
+        use Instruction::*;
+        let label = |id| Label(block_label(BlockRef(id)));
+
+        // : clamp ( n -- n ) dup 0 < if drop 0 then ;  -5 clamp 1 +
+        let clamp = vec![
+            label(0), Dup, Literal(0), Lt, BranchIfElse(BlockRef(1), BlockRef(2)),
+            label(1), Drop, Literal(0), Branch(BlockRef(3)),
+            label(2), Branch(BlockRef(3)),
+            label(3), Return,
+        ];
+        let main = vec![label(0), Literal(-5), Call("clamp".into()), Literal(1), Add, Return];
+
+        let levels = [OptimizationLevel::Basic, OptimizationLevel::Standard, OptimizationLevel::Aggressive];
+        for level in levels {
+            let mut ir = ForthIR::new();
+            ir.add_word(WordDef::new("clamp".to_string(), clamp.clone()));
+            ir.main = main.clone();
+
+            let mut optimizer =
+                Optimizer::with_config(OptimizerConfig::new(level).with_verify_each_pass(true));
+            let optimized = optimizer.optimize(ir).unwrap();
+
+            let words = optimized.words.values().map(|w| &w.instructions);
+            for sequence in std::iter::once(&optimized.main).chain(words) {
+                BranchTargets::of(sequence).check(sequence).unwrap();
+                // Only the caller's own exit remains once clamp's body is spliced in
+                let returns = sequence.iter().filter(|inst| matches!(inst, Return)).count();
+                assert!(returns <= 1, "{:?}: {:?}", level, sequence);
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimizer_config_checked_arithmetic() {
+        let source = format!("{} 1 +", i64::MAX);
+        for level in [OptimizationLevel::Basic, OptimizationLevel::Aggressive] {
+            let mut checked = Optimizer::with_config(OptimizerConfig::new(level).with_checked_arithmetic(true));
+            let result = checked.optimize(ForthIR::parse(&source).unwrap());
+            assert!(matches!(result, Err(OptimizerError::OptimizationFailed(_))));
+
+            let mut wrapping = Optimizer::new(level);
+            assert!(wrapping.optimize(ForthIR::parse(&source).unwrap()).is_ok());
+        }
+
+        // Words are never folded by the constant folder, so the peephole pass
+        // must leave the overflowing expression for the backend to check
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new(
+            "big".to_string(),
+            vec![Instruction::Literal(i64::MAX), Instruction::Literal(1), Instruction::Add],
+        ));
+        let mut checked = Optimizer::with_config(
+            OptimizerConfig::new(OptimizationLevel::Basic).with_checked_arithmetic(true),
+        );
+        let optimized = checked.optimize(ir).unwrap();
+        assert!(!optimized.words["big"].instructions.contains(&Instruction::Literal(i64::MIN)));
+    }
+
+    fn call_program() -> ForthIR {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new(
+            "forty-nine".to_string(),
+            vec![Instruction::Literal(7), Instruction::DupMul],
+        ));
+        ir.main = vec![
+            Instruction::Literal(2),
+            Instruction::Literal(3),
+            Instruction::Add,
+            Instruction::call("forty-nine"),
+        ];
+        ir
+    }
+
+    #[test]
+    fn test_run_single_pass_constant_fold() {
+        let mut opt = Optimizer::new(OptimizationLevel::Aggressive);
+        let optimized = opt.run_pass(call_program(), PassKind::ConstantFold).unwrap();
+
+        // Folded, but not inlined
+        assert_eq!(optimized.main[0], Instruction::Literal(5));
+        assert!(optimized.main.contains(&Instruction::call("forty-nine")));
+    }
+
+    #[test]
+    fn test_with_passes_runs_only_listed_passes() {
+        let mut opt = Optimizer::new(OptimizationLevel::Aggressive)
+            .with_passes(vec![PassKind::ConstantFold])
+            .unwrap();
+        let optimized = opt.optimize(call_program()).unwrap();
+
+        assert!(optimized.main.contains(&Instruction::call("forty-nine")));
+        assert!(!optimized.main.iter().any(|i| matches!(i, Instruction::FlushCache)));
+    }
+
+    #[test]
+    fn test_stack_cache_without_constant_fold() {
+        let mut opt = Optimizer::new(OptimizationLevel::None);
+        let ir = ForthIR::parse("1 2 + 3 *").unwrap();
+        let optimized = opt.run_pass(ir, PassKind::StackCache).unwrap();
+
+        assert!(optimized.main.iter().any(|i| matches!(i, Instruction::FlushCache)));
+    }
+
+    #[test]
+    fn test_empty_pass_list_rejected() {
+        let result = Optimizer::new(OptimizationLevel::Standard).with_passes(Vec::new());
+        assert!(matches!(result, Err(OptimizerError::OptimizationFailed(_))));
+    }
+
+    #[test]
+    fn test_default_pipeline_by_level() {
+        assert!(PassKind::pipeline(OptimizationLevel::None).is_empty());
+        assert!(!PassKind::pipeline(OptimizationLevel::Basic).contains(&PassKind::Inline));
+        assert!(PassKind::pipeline(OptimizationLevel::Standard).contains(&PassKind::StackCache));
+        assert_eq!(PassKind::pipeline(OptimizationLevel::Aggressive)[0], PassKind::ZeroCost);
+    }
+
+    #[test]
+    fn test_pass_stats_dead_code_removal() {
+        let mut opt = Optimizer::new(OptimizationLevel::Standard)
+            .with_passes(vec![PassKind::DeadCode])
+            .unwrap();
+        let ir = ForthIR::parse("1 2 drop 3 4 drop drop").unwrap();
+        let (_, stats) = opt.optimize_with_stats(ir).unwrap();
+
+        let dce = stats.get(PassKind::DeadCode).unwrap();
+        assert_eq!(dce.runs, 1);
+        assert!(dce.removed > 0);
+    }
+
+    #[test]
+    fn test_pass_stats_skipped_and_consistent() {
+        let mut opt = Optimizer::new(OptimizationLevel::Basic);
+        let ir = ForthIR::parse("2 3 + dup drop 4 *").unwrap();
+        let (_, stats) = opt.optimize_with_stats(ir).unwrap();
+
+        assert!(stats.get(PassKind::Inline).unwrap().is_skipped());
+        assert!(stats.get(PassKind::StackCache).unwrap().is_skipped());
+        assert!(!stats.get(PassKind::ConstantFold).unwrap().is_skipped());
+
+        let net: i64 = stats.passes.iter().map(|r| r.net_change()).sum();
+        assert_eq!(net, stats.instructions_after as i64 - stats.instructions_before as i64);
+    }
+
+    #[test]
+    fn test_pass_stats_accumulate_over_fixpoint() {
+        let mut opt = Optimizer::new(OptimizationLevel::Basic);
+        let ir = ForthIR::parse("2 3 + 4 *").unwrap();
+        opt.optimize_until_fixpoint(ir).unwrap();
+
+        let stats = opt.pass_stats();
+        assert!(stats.get(PassKind::ConstantFold).unwrap().runs >= 2);
+        let net: i64 = stats.passes.iter().map(|r| r.net_change()).sum();
+        assert_eq!(net, stats.instructions_after as i64 - stats.instructions_before as i64);
+    }
+
+    fn double_program() -> ForthIR {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new(
+            "double".to_string(),
+            vec![Instruction::Literal(2), Instruction::Mul],
+        ));
+        ir.main = vec![Instruction::Literal(5), Instruction::call("double")];
+        ir
+    }
+
+    #[test]
+    fn test_constants_fold_across_inlined_word() {
+        // Folding `double` alone turns `2 *` into `dup +`; after inlining the
+        // constant 5 flows into the inlined `dup` and folds away
+        let mut opt = Optimizer::new(OptimizationLevel::Standard);
+        let optimized = opt.optimize(double_program()).unwrap();
+        assert_eq!(optimized.main, vec![Instruction::Literal(10), Instruction::FlushCache]);
+
+        let stats = opt.pass_stats();
+        assert_eq!(stats.get(PassKind::ConstantFold).unwrap().runs, 2);
+    }
+
+    #[test]
+    fn test_fixpoint_terminates() {
+        let mut opt = Optimizer::new(OptimizationLevel::Aggressive);
+        let optimized = opt.optimize_until_fixpoint(double_program()).unwrap();
+        // Stack caching must not add another flush every round
+        assert_eq!(optimized.main, vec![Instruction::Literal(10), Instruction::FlushCache]);
+
+        // Two folds per round, and the loop is bounded even if it never converges
+        let runs = opt.pass_stats().get(PassKind::ConstantFold).unwrap().runs;
+        assert!(runs <= 2 * MAX_FIXPOINT_ITERATIONS);
+        assert!(runs < 2 * MAX_FIXPOINT_ITERATIONS, "fixpoint did not converge");
+    }
+
+    /// A program of many small groups of words that never call each other
+    fn independent_groups_program() -> ForthIR {
+        let mut ir = ForthIR::new();
+        for group in 0..8 {
+            let leaf = format!("leaf{}", group);
+            let caller = format!("caller{}", group);
+            ir.add_word(WordDef::new(
+                leaf.clone(),
+                vec![Instruction::Literal(group), Instruction::Add, Instruction::Dup, Instruction::Drop],
+            ));
+            ir.add_word(WordDef::new(
+                caller,
+                vec![Instruction::Literal(3), Instruction::Mul, Instruction::call(leaf)],
+            ));
+        }
+        ir.main = vec![Instruction::Literal(5), Instruction::call("caller0")];
+        ir
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        for level in [OptimizationLevel::Basic, OptimizationLevel::Standard, OptimizationLevel::Aggressive] {
+            let mut serial = Optimizer::new(level);
+            let mut parallel = Optimizer::with_config(OptimizerConfig::new(level).with_parallel(true));
+
+            let (expected, serial_stats) = serial.optimize_with_stats(independent_groups_program()).unwrap();
+            let (actual, parallel_stats) = parallel.optimize_with_stats(independent_groups_program()).unwrap();
+            assert_eq!(actual.to_string(), expected.to_string());
+            assert_eq!(parallel_stats.instructions_before, serial_stats.instructions_before);
+            assert_eq!(parallel_stats.instructions_after, serial_stats.instructions_after);
+
+            let expected = serial.optimize_until_fixpoint(independent_groups_program()).unwrap();
+            let actual = parallel.optimize_until_fixpoint(independent_groups_program()).unwrap();
+            assert_eq!(actual.to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_explain_word_over_inline_threshold() {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("large".to_string(), vec![Instruction::Dup; 20]));
+        ir.main = vec![Instruction::Literal(1), Instruction::call("large")];
+        let cost = ir.words["large"].cost;
+
+        let mut optimizer = Optimizer::new(OptimizationLevel::Standard);
+        let (_, notes) = optimizer.optimize_with_notes(ir).unwrap();
+
+        let note = notes
+            .iter()
+            .find(|note| note.pass == PassKind::Inline && note.word.as_deref() == Some("large"))
+            .expect("no inlining note for `large`");
+        assert_eq!(note.message, format!("not inlined: size {} > threshold 10", cost));
+        assert_eq!(note.to_string(), format!("inline: large: not inlined: size {} > threshold 10", cost));
+    }
+
+    #[test]
+    fn test_explain_does_not_change_optimization() {
+        for level in [OptimizationLevel::Basic, OptimizationLevel::Standard, OptimizationLevel::Aggressive] {
+            let mut optimizer = Optimizer::new(level);
+            let expected = optimizer.optimize(independent_groups_program()).unwrap();
+            assert!(optimizer.notes().is_empty());
+
+            let (actual, notes) = optimizer.optimize_with_notes(independent_groups_program()).unwrap();
+            assert_eq!(actual, expected);
+            assert!(!notes.is_empty());
+            assert_eq!(optimizer.notes(), notes.as_slice());
+
+            // Parts optimized in parallel explain the same decisions
+            let config = OptimizerConfig::new(level).with_parallel(true).with_explain(true);
+            let mut parallel = Optimizer::with_config(config);
+            parallel.optimize(independent_groups_program()).unwrap();
+            let mut sorted = notes.clone();
+            let mut parallel_notes = parallel.notes().to_vec();
+            sorted.sort_by_key(|note| note.to_string());
+            parallel_notes.sort_by_key(|note| note.to_string());
+            assert_eq!(parallel_notes, sorted);
+        }
+    }
+
+    #[test]
+    fn test_pass_kind_from_name() {
+        for pass in PassKind::all() {
+            assert_eq!(pass.name().parse::<PassKind>().unwrap(), pass);
+        }
+        assert!("inlining".parse::<PassKind>().is_err());
+    }
 }
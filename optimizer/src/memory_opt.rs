@@ -14,7 +14,7 @@
 //! - 1-3% from cache line alignment
 //! - 1-2% from stack discipline optimization
 
-use crate::ir::{ForthIR, Instruction, WordDef};
+use crate::ir::{BranchTargets, ForthIR, Instruction};
 use crate::analysis::StackDepthAnalysis;
 use crate::{Result, OptimizerError};
 use std::collections::{HashMap, HashSet, VecDeque};
@@ -388,10 +388,10 @@ impl MemoryOptimizer {
                     pts.stack_locs.insert(format!("stack_{}", i));
                 }
                 Instruction::Call(name) if name.contains("alloc") || name.contains("malloc") => {
-                    pts.heap_locs.insert(name.clone());
+                    pts.heap_locs.insert(name.to_string());
                 }
                 Instruction::Call(name) if name.contains("rstack") => {
-                    pts.rstack_locs.insert(name.clone());
+                    pts.rstack_locs.insert(name.to_string());
                 }
                 _ => {}
             }
@@ -566,14 +566,16 @@ impl MemoryOptimizer {
     fn detect_loops_advanced(&self, instructions: &[Instruction]) -> Vec<LoopInfo> {
         let mut loops = Vec::new();
         let mut loop_count = 0;
+        let targets = BranchTargets::of(instructions);
 
         for (i, inst) in instructions.iter().enumerate() {
             match inst {
-                Instruction::Branch(target) if *target < i => {
-                    let pattern = self.analyze_loop_pattern_advanced(instructions, *target, i);
+                Instruction::Branch(target) if targets.resolve(*target).is_some_and(|start| start < i) => {
+                    let start = targets.resolve(*target).unwrap_or_default();
+                    let pattern = self.analyze_loop_pattern_advanced(instructions, start, i);
                     loop_count += 1;
                     loops.push(LoopInfo {
-                        start: *target,
+                        start,
                         end: i,
                         pattern,
                         base_addr: None,
@@ -673,7 +675,7 @@ impl MemoryOptimizer {
 
         for inst in instructions {
             if let Instruction::Call(name) = inst {
-                *access_counts.entry(name.clone()).or_insert(0) += 1;
+                *access_counts.entry(name.to_string()).or_insert(0) += 1;
             }
         }
 
@@ -799,6 +801,7 @@ impl OptimizationStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::BlockRef;
 
     #[test]
     fn test_memory_optimizer_creation() {
@@ -836,16 +839,17 @@ mod tests {
     fn test_loop_detection_advanced() {
         let opt = MemoryOptimizer::new();
         let instructions = vec![
+            Instruction::label("bb0"),    // Loop start
             Instruction::Literal(0),      // Loop counter
             Instruction::Load,            // Body
             Instruction::Add,             // Body
-            Instruction::Branch(0),       // Back to start
+            Instruction::Branch(BlockRef(0)), // Back to start
         ];
 
         let loops = opt.detect_loops_advanced(&instructions);
         assert_eq!(loops.len(), 1);
         assert_eq!(loops[0].start, 0);
-        assert_eq!(loops[0].end, 3);
+        assert_eq!(loops[0].end, 4);
     }
 
     #[test]
@@ -865,9 +869,10 @@ mod tests {
     fn test_prefetch_insertion_advanced() {
         let opt = MemoryOptimizer::new();
         let instructions = vec![
+            Instruction::label("bb0"),
             Instruction::Literal(0),
             Instruction::Load,
-            Instruction::Branch(0),
+            Instruction::Branch(BlockRef(0)),
         ];
 
         let prefetched = opt.insert_prefetches_advanced(&instructions).unwrap();
@@ -887,7 +892,7 @@ mod tests {
     fn test_cache_optimization_usage() {
         let opt = MemoryOptimizer::new();
         let instructions = vec![
-            Instruction::Call("data_array".to_string()),
+            Instruction::call("data_array"),
             Instruction::Load,
         ];
 
@@ -940,11 +945,12 @@ mod tests {
         ir.main = vec![
             Instruction::Literal(10),     // Array size
             Instruction::Literal(0),      // Counter
-            Instruction::Dup,             // Loop start
+            Instruction::label("bb0"),    // Loop start
+            Instruction::Dup,
             Instruction::Load,            // Load from array
             Instruction::Literal(1),
             Instruction::Add,             // Increment
-            Instruction::BranchIfNot(2),  // Loop end
+            Instruction::BranchIfNot(BlockRef(0)), // Loop end
         ];
 
         let optimized = opt.optimize(&ir).unwrap();
@@ -972,10 +978,11 @@ mod tests {
     fn test_advanced_loop_detection() {
         let opt = MemoryOptimizer::new();
         let instructions = vec![
+            Instruction::label("bb0"),    // Loop start
             Instruction::Literal(0),      // Loop counter
             Instruction::Load,            // Body
             Instruction::Add,             // Body
-            Instruction::Branch(0),       // Back to start
+            Instruction::Branch(BlockRef(0)), // Back to start
         ];
 
         let loops = opt.detect_loops_advanced(&instructions);
@@ -987,11 +994,11 @@ mod tests {
     fn test_cache_line_optimization() {
         let opt = MemoryOptimizer::new();
         let instructions = vec![
-            Instruction::Call("data_array".to_string()),
+            Instruction::call("data_array"),
             Instruction::Load,
-            Instruction::Call("data_array".to_string()),
+            Instruction::call("data_array"),
             Instruction::Load,
-            Instruction::Call("data_array".to_string()),
+            Instruction::call("data_array"),
             Instruction::Load,
         ];
 
@@ -0,0 +1,201 @@
+//! Stack-Shuffle Peephole Optimization
+//!
+//! Scans the instruction stream for short sequences that cancel out or have a
+//! cheaper equivalent, using a fixed rewrite table. Rewrites are repeated until
+//! a fixpoint since one rewrite can expose another.
+//!
+//! # Examples
+//!
+//! ```forth
+//! swap swap   \ -> (nothing)
+//! dup drop    \ -> (nothing)
+//! 0 +         \ -> (nothing)
+//! 1 *         \ -> (nothing)
+//! 0 *         \ -> drop 0
+//! dup 0 *     \ -> dup drop 0 -> 0
+//! ```
+//!
+//! Patterns only match contiguous instructions, so any intervening instruction
+//! blocks the rewrite. Every branch leads to a `Label`, so a pattern never
+//! spans a branch target either.
+
+use crate::ir::{ForthIR, Instruction, WordDef};
+use crate::Result;
+
+/// Rewrite table: (pattern, replacement)
+const RULES: &[(&[Instruction], &[Instruction])] = &[
+    (&[Instruction::Swap, Instruction::Swap], &[]),
+    (&[Instruction::Dup, Instruction::Drop], &[]),
+    (&[Instruction::Over, Instruction::Drop], &[]),
+    (&[Instruction::Literal(0), Instruction::Add], &[]),
+    (&[Instruction::Literal(0), Instruction::Sub], &[]),
+    (&[Instruction::Literal(1), Instruction::Mul], &[]),
+    (&[Instruction::Literal(1), Instruction::Div], &[]),
+    (&[Instruction::Literal(0), Instruction::Mul], &[Instruction::Drop, Instruction::Literal(0)]),
+];
+
+/// Upper bound on rewrite rounds per sequence
+const MAX_ITERATIONS: usize = 16;
+
+/// Peephole optimizer for redundant stack shuffles
+pub struct PeepholeOptimizer;
+
+impl PeepholeOptimizer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Apply peephole rewrites to the whole IR
+    pub fn optimize(&self, ir: &ForthIR) -> Result<ForthIR> {
+        let mut optimized = ir.clone();
+
+        self.optimize_sequence(&mut optimized.main);
+
+        for word in optimized.words.values_mut() {
+            self.optimize_word(word);
+        }
+
+        Ok(optimized)
+    }
+
+    /// Apply peephole rewrites to a word definition
+    fn optimize_word(&self, word: &mut WordDef) {
+        self.optimize_sequence(&mut word.instructions);
+        word.update();
+    }
+
+    /// Rewrite a sequence until no rule applies
+    fn optimize_sequence(&self, instructions: &mut Vec<Instruction>) {
+        for _ in 0..MAX_ITERATIONS {
+            if !self.rewrite_once(instructions) {
+                break;
+            }
+        }
+    }
+
+    /// Perform one left-to-right rewriting sweep; returns whether anything changed
+    fn rewrite_once(&self, instructions: &mut Vec<Instruction>) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+
+        while i < instructions.len() {
+            let rule = RULES.iter().find(|(pattern, _)| instructions[i..].starts_with(pattern));
+
+            match rule {
+                Some((pattern, replacement)) => {
+                    instructions.splice(i..i + pattern.len(), replacement.iter().cloned());
+                    changed = true;
+                    // Step back so the rewrite can combine with the preceding instruction
+                    i = i.saturating_sub(1);
+                }
+                None => i += 1,
+            }
+        }
+
+        changed
+    }
+}
+
+impl Default for PeepholeOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::BlockRef;
+
+    fn optimize(source: &str) -> Vec<Instruction> {
+        let ir = ForthIR::parse(source).unwrap();
+        PeepholeOptimizer::new().optimize(&ir).unwrap().main
+    }
+
+    #[test]
+    fn test_swap_swap_eliminated() {
+        assert_eq!(optimize("1 2 swap swap +"), optimize("1 2 +"));
+    }
+
+    #[test]
+    fn test_dup_drop_eliminated() {
+        assert_eq!(optimize("5 dup drop negate"), vec![Instruction::Literal(5), Instruction::Neg]);
+    }
+
+    #[test]
+    fn test_add_zero_eliminated() {
+        assert_eq!(optimize("foo 0 +"), vec![Instruction::call("foo")]);
+    }
+
+    #[test]
+    fn test_mul_one_eliminated() {
+        assert_eq!(optimize("foo 1 *"), vec![Instruction::call("foo")]);
+    }
+
+    #[test]
+    fn test_mul_zero_becomes_drop_zero() {
+        assert_eq!(
+            optimize("foo 0 *"),
+            vec![Instruction::call("foo"), Instruction::Drop, Instruction::Literal(0)]
+        );
+    }
+
+    #[test]
+    fn test_rewrites_reach_fixpoint() {
+        // dup 0 * -> dup drop 0 -> 0; swap dup drop swap -> swap swap -> (nothing)
+        assert_eq!(optimize("foo dup 0 *"), vec![Instruction::call("foo"), Instruction::Literal(0)]);
+        assert_eq!(optimize("foo swap dup drop swap"), vec![Instruction::call("foo")]);
+    }
+
+    #[test]
+    fn test_label_blocks_fusion() {
+        let mut ir = ForthIR::new();
+        ir.main = vec![
+            Instruction::Swap,
+            Instruction::label("bb1"),
+            Instruction::Swap,
+        ];
+
+        let optimized = PeepholeOptimizer::new().optimize(&ir).unwrap();
+        assert_eq!(optimized.main, ir.main);
+    }
+
+    #[test]
+    fn test_branch_target_blocks_fusion() {
+        let mut ir = ForthIR::new();
+        // Block 1 is a branch target, so `dup` and `drop` must not fuse
+        ir.main = vec![
+            Instruction::BranchIfNot(BlockRef(1)),
+            Instruction::Dup,
+            Instruction::label("bb1"),
+            Instruction::Drop,
+        ];
+
+        let optimized = PeepholeOptimizer::new().optimize(&ir).unwrap();
+        assert_eq!(optimized.main, ir.main);
+    }
+
+    #[test]
+    fn test_block_targets_survive_rewrites() {
+        let mut ir = ForthIR::new();
+        ir.main = vec![
+            Instruction::BranchIfNot(BlockRef(1)),
+            Instruction::Swap,
+            Instruction::Swap,
+            Instruction::label("bb1"),
+            Instruction::Literal(1),
+            Instruction::Literal(2),
+        ];
+
+        let optimized = PeepholeOptimizer::new().optimize(&ir).unwrap();
+        assert_eq!(
+            optimized.main,
+            vec![
+                Instruction::BranchIfNot(BlockRef(1)),
+                Instruction::label("bb1"),
+                Instruction::Literal(1),
+                Instruction::Literal(2),
+            ]
+        );
+    }
+}
@@ -495,10 +495,6 @@ impl FusionGenerator {
             return Some(Instruction::MulTwo);
         }
 
-        if pattern_str.contains("Literal(2)") && pattern_str.contains("Div") {
-            return Some(Instruction::DivTwo);
-        }
-
         if pattern_str.contains("Over") && pattern_str.contains("Add") {
             return Some(Instruction::OverAdd);
         }
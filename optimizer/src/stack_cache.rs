@@ -9,7 +9,8 @@
 //! 1. Track stack depth at each instruction
 //! 2. Allocate registers for top N items (typically 3: TOS, NOS, 3OS)
 //! 3. Transform instructions to use cached registers
-//! 4. Insert flush/reload instructions at call boundaries
+//! 4. Spill the deepest cached item when the cache is full, fill it back on use
+//! 5. Insert flush/reload instructions at call boundaries
 //!
 //! # Register Allocation
 //!
@@ -33,6 +34,15 @@
 //! mov r1, r0       ; dup: r0 -> r1
 //! mul r0, r1       ; * consumes r0,r1 -> r0
 //! ```
+//!
+//! # Backends
+//!
+//! The cached instructions are consumed by backends that work from this IR,
+//! such as the C generator. The Cranelift JIT compiles the frontend's SSA
+//! instead, where every stack item is already a virtual register for
+//! Cranelift's allocator, so it neither uses nor needs this pass. The fixed
+//! r12-r14 assignment described in the backend's `calling_convention` module
+//! belongs to the LLVM backend.
 
 use crate::ir::{ForthIR, Instruction, WordDef};
 use crate::{OptimizerError, Result};
@@ -56,6 +66,8 @@ pub struct CacheState {
     pub cached_depth: u8,
     /// Total stack depth (including non-cached items)
     pub total_depth: i32,
+    /// Items spilled to memory that have not been filled back yet
+    pub spilled: u8,
 }
 
 impl CacheState {
@@ -63,6 +75,7 @@ impl CacheState {
         Self {
             cached_depth: 0,
             total_depth: 0,
+            spilled: 0,
         }
     }
 
@@ -81,7 +94,14 @@ impl CacheState {
     }
 }
 
+/// Maximum number of stack items that can be cached in registers
+pub const MAX_CACHE_DEPTH: u8 = 8;
+
 /// Stack caching optimizer
+///
+/// When the modeled stack grows past `cache_size`, the deepest cached item is
+/// spilled to memory; it is filled back lazily once the cached items above it
+/// have been consumed. A `cache_size` of 0 disables caching entirely.
 pub struct StackCacheOptimizer {
     /// Number of stack items to cache in registers
     cache_size: u8,
@@ -89,10 +109,15 @@ pub struct StackCacheOptimizer {
 
 impl StackCacheOptimizer {
     pub fn new(cache_size: u8) -> Self {
-        assert!(cache_size > 0 && cache_size <= 8, "Cache size must be 1-8");
+        assert!(cache_size <= MAX_CACHE_DEPTH, "Cache size must be 0-8");
         Self { cache_size }
     }
 
+    /// Number of stack items cached in registers
+    pub fn cache_size(&self) -> u8 {
+        self.cache_size
+    }
+
     /// Optimize IR with stack caching
     pub fn optimize(&self, ir: &ForthIR) -> Result<ForthIR> {
         let mut optimized = ir.clone();
@@ -118,11 +143,22 @@ impl StackCacheOptimizer {
     }
 
     /// Optimize a sequence of instructions
+    ///
+    /// Branches always leave the cache empty, so it is flushed before every
+    /// label as well, and all paths into a block agree.
     fn optimize_sequence(&self, instructions: &[Instruction]) -> Result<Vec<Instruction>> {
         let mut result = Vec::with_capacity(instructions.len());
         let mut state = CacheState::new(self.cache_size);
 
         for inst in instructions {
+            if matches!(inst, Instruction::Label(_)) {
+                if state.cached_depth > 0 {
+                    result.push(Instruction::FlushCache);
+                    state.cached_depth = 0;
+                }
+                state.spilled = 0;
+            }
+
             // Apply stack caching transformation
             let transformed = self.transform_instruction(inst, &mut state)?;
             result.extend(transformed);
@@ -149,29 +185,33 @@ impl StackCacheOptimizer {
         match inst {
             // Literals: push to cache
             Literal(v) => {
+                self.push_cache(&mut result, state);
                 result.push(Literal(*v));
-                self.push_cache(state);
             }
 
             FloatLiteral(v) => {
+                self.push_cache(&mut result, state);
                 result.push(FloatLiteral(*v));
-                self.push_cache(state);
             }
 
-            // Stack operations with caching
+            // Stack operations with caching; an operand is never the item spilled
+            // to make room for the result, so DUP needs two registers and OVER three
             Dup => {
-                if state.cached_depth >= 1 {
+                self.fill_cache(1, &mut result, state);
+                if state.cached_depth >= 1 && self.cache_size > 1 {
+                    self.push_cache(&mut result, state);
                     result.push(CachedDup {
-                        depth: state.cached_depth,
+                        depth: state.cached_depth - 1,
                     });
-                    self.push_cache(state);
                 } else {
+                    self.flush_cache(&mut result, state);
                     result.push(Dup);
                     state.total_depth += 1;
                 }
             }
 
             Drop => {
+                self.fill_cache(1, &mut result, state);
                 if state.cached_depth >= 1 {
                     self.pop_cache(state);
                 } else {
@@ -181,6 +221,7 @@ impl StackCacheOptimizer {
             }
 
             Swap => {
+                self.fill_cache(2, &mut result, state);
                 if state.cached_depth >= 2 {
                     result.push(CachedSwap {
                         depth: state.cached_depth,
@@ -194,11 +235,12 @@ impl StackCacheOptimizer {
             }
 
             Over => {
-                if state.cached_depth >= 2 {
+                self.fill_cache(2, &mut result, state);
+                if state.cached_depth >= 2 && self.cache_size > 2 {
+                    self.push_cache(&mut result, state);
                     result.push(CachedOver {
-                        depth: state.cached_depth,
+                        depth: state.cached_depth - 1,
                     });
-                    self.push_cache(state);
                 } else {
                     self.flush_cache(&mut result, state);
                     result.push(Over);
@@ -207,8 +249,9 @@ impl StackCacheOptimizer {
             }
 
             // Arithmetic: operate on cached values
-            Add | Sub | Mul | Div | Mod | And | Or | Xor | Eq | Ne | Lt | Le | Gt | Ge | Shl
-            | Shr => {
+            Add | Sub | Mul | Div | Mod | Min | Max | And | Or | Xor | Eq | Ne | Lt | Le | Gt | Ge
+            | Shl | Shr | Sar | ULt | UGt => {
+                self.fill_cache(2, &mut result, state);
                 if state.cached_depth >= 2 {
                     result.push(inst.clone());
                     self.pop_cache(state); // Binary op: consume 2, produce 1
@@ -221,6 +264,7 @@ impl StackCacheOptimizer {
 
             // Unary operations
             Neg | Abs | Not | ZeroEq | ZeroLt | ZeroGt => {
+                self.fill_cache(1, &mut result, state);
                 if state.cached_depth >= 1 {
                     result.push(inst.clone());
                     // Depth unchanged
@@ -232,6 +276,7 @@ impl StackCacheOptimizer {
 
             // Superinstructions
             DupAdd | DupMul => {
+                self.fill_cache(1, &mut result, state);
                 if state.cached_depth >= 1 {
                     result.push(inst.clone());
                     // Net effect: consume 1, produce 1 (depth unchanged)
@@ -242,7 +287,7 @@ impl StackCacheOptimizer {
             }
 
             // Control flow: flush cache
-            Call(_) | Return | Branch(_) | BranchIf(_) | BranchIfNot(_) => {
+            Call(_) | Return | Branch(_) | BranchIf(_) | BranchIfNot(_) | BranchIfElse(..) => {
                 self.flush_cache(&mut result, state);
                 result.push(inst.clone());
             }
@@ -265,12 +310,19 @@ impl StackCacheOptimizer {
             }
 
             // Metadata: pass through
-            Comment(_) | Label(_) | Nop | FlushCache => {
+            Comment(_) | Label(_) | Nop => {
+                result.push(inst.clone());
+            }
+
+            // An existing flush (from an earlier run) already empties the cache
+            FlushCache => {
                 result.push(inst.clone());
+                state.cached_depth = 0;
+                state.spilled = 0;
             }
 
             // Already cached instructions: pass through
-            CachedDup { .. } | CachedSwap { .. } | CachedOver { .. } => {
+            CachedDup { .. } | CachedSwap { .. } | CachedOver { .. } | SpillCache | FillCache => {
                 result.push(inst.clone());
             }
 
@@ -286,15 +338,20 @@ impl StackCacheOptimizer {
         Ok(result)
     }
 
-    /// Push an item to the cache
-    fn push_cache(&self, state: &mut CacheState) {
+    /// Make room for a pushed item, emitting a spill of the deepest cached item when full
+    fn push_cache(&self, result: &mut SmallVec<[Instruction; 4]>, state: &mut CacheState) {
+        if self.cache_size > 0 && state.cached_depth == self.cache_size {
+            result.push(Instruction::SpillCache);
+            state.cached_depth -= 1;
+            state.spilled += 1;
+        }
         if state.cached_depth < self.cache_size {
             state.cached_depth += 1;
         }
         state.total_depth += 1;
     }
 
-    /// Pop an item from the cache
+    /// Pop an item from the cache (see `fill_cache` for spilled items)
     fn pop_cache(&self, state: &mut CacheState) {
         if state.cached_depth > 0 {
             state.cached_depth -= 1;
@@ -302,12 +359,22 @@ impl StackCacheOptimizer {
         state.total_depth -= 1;
     }
 
+    /// Emit fills until `needed` items are cached or nothing is left spilled
+    fn fill_cache(&self, needed: u8, result: &mut SmallVec<[Instruction; 4]>, state: &mut CacheState) {
+        while state.cached_depth < needed && state.spilled > 0 {
+            result.push(Instruction::FillCache);
+            state.cached_depth += 1;
+            state.spilled -= 1;
+        }
+    }
+
     /// Flush cache to memory
     fn flush_cache(&self, result: &mut SmallVec<[Instruction; 4]>, state: &mut CacheState) {
         if state.cached_depth > 0 {
             result.push(Instruction::FlushCache);
             state.cached_depth = 0;
         }
+        state.spilled = 0;
     }
 }
 
@@ -322,13 +389,62 @@ mod tests {
         assert_eq!(state.total_depth, 0);
 
         let optimizer = StackCacheOptimizer::new(3);
-        optimizer.push_cache(&mut state);
+        let mut emitted = SmallVec::new();
+        optimizer.push_cache(&mut emitted, &mut state);
         assert_eq!(state.cached_depth, 1);
         assert_eq!(state.total_depth, 1);
 
         optimizer.pop_cache(&mut state);
         assert_eq!(state.cached_depth, 0);
         assert_eq!(state.total_depth, 0);
+        assert!(emitted.is_empty());
+    }
+
+    #[test]
+    fn test_full_cache_spills_and_fills() {
+        let mut state = CacheState::new(2);
+        let optimizer = StackCacheOptimizer::new(2);
+        let mut emitted = SmallVec::new();
+        for _ in 0..3 {
+            optimizer.push_cache(&mut emitted, &mut state);
+        }
+        assert_eq!(emitted.as_slice(), &[Instruction::SpillCache]);
+        assert_eq!((state.cached_depth, state.spilled, state.total_depth), (2, 1, 3));
+
+        optimizer.pop_cache(&mut state);
+        optimizer.pop_cache(&mut state);
+        optimizer.fill_cache(2, &mut emitted, &mut state);
+        assert_eq!(emitted.as_slice(), &[Instruction::SpillCache, Instruction::FillCache]);
+        assert_eq!((state.cached_depth, state.spilled), (1, 0));
+    }
+
+    #[test]
+    fn test_emitted_spill_fill_sequence() {
+        use Instruction::*;
+        let optimizer = StackCacheOptimizer::new(2);
+        let ir = ForthIR::parse("1 2 3 + +").unwrap();
+        let optimized = optimizer.optimize(&ir).unwrap();
+
+        assert_eq!(
+            optimized.main,
+            vec![Literal(1), Literal(2), SpillCache, Literal(3), Add, FillCache, Add, FlushCache]
+        );
+        assert!(optimized.verify().is_ok());
+
+        // DUP and OVER keep their operands cached while making room for the result
+        let ir = ForthIR::parse("1 2 dup over").unwrap();
+        let optimized = optimizer.optimize(&ir).unwrap();
+        assert_eq!(
+            optimized.main,
+            vec![
+                Literal(1),
+                Literal(2),
+                SpillCache,
+                CachedDup { depth: 1 },
+                FlushCache,
+                Over,
+            ]
+        );
     }
 
     #[test]
@@ -369,4 +485,60 @@ mod tests {
             .any(|i| matches!(i, Instruction::FlushCache));
         assert!(has_flush);
     }
+
+    fn count_flushes(ir: &ForthIR) -> usize {
+        ir.main
+            .iter()
+            .filter(|i| matches!(i, Instruction::FlushCache))
+            .count()
+    }
+
+    fn count_memory_ops(ir: &ForthIR) -> usize {
+        ir.main
+            .iter()
+            .filter(|i| matches!(i, Instruction::FlushCache | Instruction::SpillCache | Instruction::FillCache))
+            .count()
+    }
+
+    #[test]
+    fn test_zero_depth_disables_caching() {
+        let optimizer = StackCacheOptimizer::new(0);
+        let ir = ForthIR::parse("5 dup + 1 2 swap").unwrap();
+        let optimized = optimizer.optimize(&ir).unwrap();
+
+        assert_eq!(optimized.main, ir.main);
+    }
+
+    #[test]
+    fn test_depth_exceeding_stack_needs_no_spills() {
+        let optimizer = StackCacheOptimizer::new(MAX_CACHE_DEPTH);
+        let ir = ForthIR::parse("1 2 3 + +").unwrap();
+        let optimized = optimizer.optimize(&ir).unwrap();
+
+        // Only the final flush at the end of the sequence
+        assert_eq!(count_flushes(&optimized), 1);
+        assert_eq!(count_memory_ops(&optimized), 1);
+        assert!(optimized.verify().is_ok());
+    }
+
+    #[test]
+    fn test_deeper_cache_eliminates_memory_ops() {
+        let ir = ForthIR::parse("1 2 3 4 5 + + + + 6 + 7 +").unwrap();
+
+        let shallow = StackCacheOptimizer::new(3).optimize(&ir).unwrap();
+        let deep = StackCacheOptimizer::new(5).optimize(&ir).unwrap();
+
+        assert_eq!(count_memory_ops(&deep), 1);
+        assert!(count_memory_ops(&deep) < count_memory_ops(&shallow));
+        assert!(shallow.main.contains(&Instruction::SpillCache));
+        assert!(shallow.main.contains(&Instruction::FillCache));
+        assert!(shallow.verify().is_ok());
+        assert!(deep.verify().is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_depth_above_maximum_rejected() {
+        StackCacheOptimizer::new(MAX_CACHE_DEPTH + 1);
+    }
 }
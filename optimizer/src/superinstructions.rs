@@ -12,7 +12,6 @@
 //! - `1 +` -> `IncOne` (increment)
 //! - `1 -` -> `DecOne` (decrement)
 //! - `2 *` -> `MulTwo` (shift left)
-//! - `2 /` -> `DivTwo` (shift right)
 //! - `over +` -> `OverAdd`
 //! - `swap -` -> `SwapSub`
 //!
@@ -37,8 +36,9 @@
 //! : square dup_mul ;  # Single superinstruction
 //! ```
 
+use crate::explain::{self, Notes, OptimizationNote};
 use crate::ir::{ForthIR, Instruction, WordDef};
-use crate::Result;
+use crate::{PassKind, Result};
 
 /// Pattern matcher for instruction sequences
 #[derive(Debug, Clone)]
@@ -75,6 +75,28 @@ impl Pattern {
         true
     }
 
+    /// Label that splits a sequence at `pos` which would match without it
+    fn split_at_label(&self, instructions: &[Instruction], pos: usize) -> Option<usize> {
+        if !instructions.get(pos).is_some_and(|inst| self.instruction_matches(&self.sequence[0], inst)) {
+            return None;
+        }
+
+        let mut joined = Vec::with_capacity(self.sequence.len());
+        let mut label = None;
+        for (i, inst) in instructions.iter().enumerate().skip(pos) {
+            if joined.len() == self.sequence.len() {
+                break;
+            }
+            match inst {
+                Instruction::Label(_) => {
+                    label.get_or_insert(i);
+                }
+                _ => joined.push(inst.clone()),
+            }
+        }
+        label.filter(|_| self.matches(&joined, 0))
+    }
+
     /// Check if two instructions match (with wildcard support)
     fn instruction_matches(&self, pattern: &Instruction, inst: &Instruction) -> bool {
         use Instruction::*;
@@ -151,12 +173,6 @@ impl SuperinstructionOptimizer {
                 vec![Literal(2), Mul],
                 vec![MulTwo],
             ),
-            // 2 / -> shift right (faster than divide)
-            Pattern::new(
-                "div_two",
-                vec![Literal(2), Div],
-                vec![DivTwo],
-            ),
             // over + -> over_add
             Pattern::new(
                 "over_add",
@@ -267,30 +283,48 @@ impl SuperinstructionOptimizer {
 
     /// Recognize and fuse superinstructions in IR
     pub fn recognize(&self, ir: &ForthIR) -> Result<ForthIR> {
+        Ok(self.recognize_noting(ir, &mut Notes::new(PassKind::Superinstructions, false)))
+    }
+
+    /// Recognize and fuse superinstructions, noting every pattern fused or left alone
+    pub fn recognize_explained(&self, ir: &ForthIR) -> Result<(ForthIR, Vec<OptimizationNote>)> {
+        let mut notes = Notes::new(PassKind::Superinstructions, true);
+        let optimized = self.recognize_noting(ir, &mut notes);
+        Ok((optimized, notes.into_vec()))
+    }
+
+    fn recognize_noting(&self, ir: &ForthIR, notes: &mut Notes) -> ForthIR {
         let mut optimized = ir.clone();
 
         // Optimize main sequence
-        optimized.main = self.recognize_sequence(&ir.main);
-
-        // Optimize each word
-        for (name, word) in ir.words.iter() {
-            let optimized_word = self.recognize_word(word);
+        notes.set_word(None);
+        optimized.main = self.recognize_sequence(&ir.main, notes);
+
+        // Optimize each word, in name order so notes come out the same way every time
+        let mut names: Vec<&String> = ir.words.keys().collect();
+        names.sort();
+        for name in names {
+            notes.set_word(Some(name));
+            let optimized_word = self.recognize_word(&ir.words[name], notes);
             optimized.words.insert(name.clone(), optimized_word);
         }
 
-        Ok(optimized)
+        optimized
     }
 
     /// Recognize patterns in a word definition
-    fn recognize_word(&self, word: &WordDef) -> WordDef {
+    fn recognize_word(&self, word: &WordDef, notes: &mut Notes) -> WordDef {
         let mut optimized = word.clone();
-        optimized.instructions = self.recognize_sequence(&word.instructions);
+        optimized.instructions = self.recognize_sequence(&word.instructions, notes);
         optimized.update();
         optimized
     }
 
     /// Recognize patterns in an instruction sequence
-    fn recognize_sequence(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+    ///
+    /// Every branch leads to a label and a pattern never spans one, so control
+    /// flow entering in the middle of a fused sequence cannot happen.
+    fn recognize_sequence(&self, instructions: &[Instruction], notes: &mut Notes) -> Vec<Instruction> {
         let mut result = Vec::with_capacity(instructions.len());
         let mut pos = 0;
 
@@ -299,10 +333,29 @@ impl SuperinstructionOptimizer {
 
             // Try each pattern
             for pattern in &self.patterns {
-                if pattern.matches(instructions, pos) {
+                let len = pattern.sequence.len();
+                if !pattern.matches(instructions, pos) {
+                    if let Some(label) = pattern.split_at_label(instructions, pos) {
+                        notes.add(Some(pos), || {
+                            format!(
+                                "pattern `{}` not fused: instruction {} starts a block",
+                                explain::source(&pattern.sequence),
+                                label
+                            )
+                        });
+                    }
+                } else {
                     // Pattern matched! Apply replacement
+                    notes.add(Some(pos), || match pattern.replacement.as_slice() {
+                        [] => format!("pattern `{}` removed", explain::source(&pattern.sequence)),
+                        replacement => format!(
+                            "pattern `{}` fused into `{}`",
+                            explain::source(&pattern.sequence),
+                            explain::source(replacement)
+                        ),
+                    });
                     result.extend_from_slice(&pattern.replacement);
-                    pos += pattern.sequence.len();
+                    pos += len;
                     matched = true;
                     break;
                 }
@@ -369,6 +422,7 @@ impl std::fmt::Display for OptimizationStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::BlockRef;
 
     #[test]
     fn test_dup_add_pattern() {
@@ -474,4 +528,25 @@ mod tests {
 
         assert!(superinst_count >= 2); // Should find at least 2 patterns
     }
+
+    #[test]
+    fn test_explained_notes() {
+        let optimizer = SuperinstructionOptimizer::new();
+        let mut ir = ForthIR::parse("3 dup * 1 +").unwrap();
+        // A block starting at the `*` keeps `dup *` apart
+        ir.main.insert(2, Instruction::label("bb1"));
+        ir.main.push(Instruction::Branch(BlockRef(1)));
+
+        let (optimized, notes) = optimizer.recognize_explained(&ir).unwrap();
+        assert_eq!(optimized, optimizer.recognize(&ir).unwrap());
+
+        let notes: Vec<String> = notes.iter().map(|note| note.to_string()).collect();
+        assert_eq!(
+            notes,
+            [
+                "superinstructions: main@1: pattern `dup *` not fused: instruction 2 starts a block",
+                "superinstructions: main@4: pattern `1 +` fused into `1+`",
+            ]
+        );
+    }
 }
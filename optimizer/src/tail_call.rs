@@ -0,0 +1,212 @@
+//! Tail Call Optimization
+//!
+//! Rewrites self-recursive calls in tail position into a branch back to the
+//! start of the word, so tail-recursive definitions run in constant stack space.
+//! Only self tail calls are handled; calls to other words are left untouched.
+//!
+//! # Example
+//!
+//! Before:
+//! ```forth
+//! : countdown dup 0 > if 1 - countdown then ;
+//! ```
+//!
+//! After (conceptually):
+//! ```forth
+//! : countdown
+//!   begin dup 0 > while 1 - repeat ;
+//! ```
+//!
+//! A call is in tail position when nothing but a `Return` follows it, either
+//! directly, at the end of the word, or after a `Branch` to a block that
+//! immediately returns. The rewritten call branches to the block labelled at
+//! the start of the word, which is added when the word has none.
+
+use crate::ir::{block_label, label_block, max_block, BlockRef, BranchTargets, ForthIR, Instruction, WordDef};
+use crate::Result;
+
+/// Tail call optimizer
+pub struct TailCallOptimizer;
+
+impl TailCallOptimizer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Rewrite self tail calls in every word definition
+    pub fn optimize(&self, ir: &ForthIR) -> Result<ForthIR> {
+        let mut optimized = ir.clone();
+
+        for (name, word) in ir.words.iter() {
+            optimized.words.insert(name.clone(), self.optimize_word(word));
+        }
+
+        Ok(optimized)
+    }
+
+    /// Rewrite self tail calls in a single word
+    fn optimize_word(&self, word: &WordDef) -> WordDef {
+        let mut optimized = word.clone();
+        let entry = match word.instructions.first() {
+            Some(Instruction::Label(name)) => label_block(name),
+            _ => None,
+        };
+        let entry_block = entry.unwrap_or_else(|| BlockRef(max_block(&word.instructions).map_or(0, |last| last.0 + 1)));
+
+        let mut rewritten = false;
+        for i in 0..word.instructions.len() {
+            if matches!(&word.instructions[i], Instruction::Call(name) if name == &word.name)
+                && self.is_tail_position(&word.instructions, i + 1)
+            {
+                optimized.instructions[i] = Instruction::Branch(entry_block);
+                rewritten = true;
+            }
+        }
+        if rewritten && entry.is_none() {
+            optimized.instructions.insert(0, Instruction::Label(block_label(entry_block)));
+        }
+
+        optimized.update();
+        optimized
+    }
+
+    /// Check whether execution starting at `start` returns without doing any work
+    fn is_tail_position(&self, instructions: &[Instruction], start: usize) -> bool {
+        match self.next_significant(instructions, start) {
+            None | Some(Instruction::Return) => true,
+            Some(Instruction::Branch(target)) => {
+                BranchTargets::of(instructions)
+                    .resolve(*target)
+                    .is_some_and(|pos| {
+                        matches!(
+                            self.next_significant(instructions, pos),
+                            None | Some(Instruction::Return)
+                        )
+                    })
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Next instruction at or after `start` that is not metadata
+    fn next_significant<'a>(&self, instructions: &'a [Instruction], start: usize) -> Option<&'a Instruction> {
+        instructions
+            .get(start..)?
+            .iter()
+            .find(|inst| !matches!(inst, Instruction::Comment(_) | Instruction::Label(_) | Instruction::Nop))
+    }
+}
+
+impl Default for TailCallOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn countdown(body_tail: Vec<Instruction>) -> ForthIR {
+        let mut instructions = vec![
+            Instruction::Dup,
+            Instruction::Literal(0),
+            Instruction::Gt,
+            Instruction::BranchIfNot(BlockRef(1)),
+            Instruction::Literal(1),
+            Instruction::Sub,
+        ];
+        instructions.extend(body_tail);
+        instructions.extend([Instruction::label("bb1"), Instruction::Return]);
+
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new("countdown".to_string(), instructions));
+        ir
+    }
+
+    #[test]
+    fn test_self_tail_call_becomes_branch() {
+        let ir = countdown(vec![Instruction::call("countdown"), Instruction::Return]);
+        let optimized = TailCallOptimizer::new().optimize(&ir).unwrap();
+
+        // The word had no entry label, so one is added for the branch
+        let word = optimized.get_word("countdown").unwrap();
+        assert_eq!(word.instructions[0], Instruction::label("bb2"));
+        assert_eq!(word.instructions[7], Instruction::Branch(BlockRef(2)));
+        assert!(BranchTargets::of(&word.instructions).check(&word.instructions).is_ok());
+        assert!(!word.instructions.iter().any(|i| matches!(i, Instruction::Call(_))));
+    }
+
+    #[test]
+    fn test_tail_call_at_end_of_word() {
+        let ir = countdown(vec![Instruction::call("countdown")]);
+        let optimized = TailCallOptimizer::new().optimize(&ir).unwrap();
+
+        assert_eq!(optimized.get_word("countdown").unwrap().instructions[7], Instruction::Branch(BlockRef(2)));
+    }
+
+    #[test]
+    fn test_tail_call_through_merge_block() {
+        // Pipeline-style IF: the call jumps to a merge block that only returns
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new(
+            "countdown".to_string(),
+            vec![
+                Instruction::label("bb0"),
+                Instruction::Dup,
+                Instruction::BranchIfNot(BlockRef(1)),
+                Instruction::Branch(BlockRef(3)),
+                Instruction::label("bb1"),
+                Instruction::Literal(1),
+                Instruction::Sub,
+                Instruction::call("countdown"),
+                Instruction::Branch(BlockRef(2)),
+                Instruction::label("bb3"),
+                Instruction::Literal(1),
+                Instruction::Add,
+                Instruction::call("countdown"),
+                Instruction::Branch(BlockRef(2)),
+                Instruction::label("bb2"),
+                Instruction::Return,
+            ],
+        ));
+
+        let optimized = TailCallOptimizer::new().optimize(&ir).unwrap();
+        let word = optimized.get_word("countdown").unwrap();
+
+        // Both branches of the IF are rewritten
+        assert_eq!(word.instructions[7], Instruction::Branch(BlockRef(0)));
+        assert_eq!(word.instructions[12], Instruction::Branch(BlockRef(0)));
+    }
+
+    #[test]
+    fn test_non_tail_self_call_kept() {
+        // factorial: dup 1 - factorial * -- the multiply follows the call
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new(
+            "factorial".to_string(),
+            vec![
+                Instruction::Dup,
+                Instruction::Literal(1),
+                Instruction::Sub,
+                Instruction::call("factorial"),
+                Instruction::Mul,
+            ],
+        ));
+
+        let optimized = TailCallOptimizer::new().optimize(&ir).unwrap();
+        assert_eq!(optimized, ir);
+    }
+
+    #[test]
+    fn test_tail_call_to_other_word_kept() {
+        let mut ir = ForthIR::new();
+        ir.add_word(WordDef::new(
+            "caller".to_string(),
+            vec![Instruction::Literal(1), Instruction::call("other")],
+        ));
+
+        let optimized = TailCallOptimizer::new().optimize(&ir).unwrap();
+        assert_eq!(optimized, ir);
+    }
+}
@@ -332,8 +332,8 @@ impl TypeSpecializer {
             if let Instruction::Call(name) = inst {
                 if let Some(sig) = type_info.call_site_signatures.get(&idx) {
                     let profile = self.profiles
-                        .entry(name.clone())
-                        .or_insert_with(|| UsageProfile::new(name.clone()));
+                        .entry(name.to_string())
+                        .or_insert_with(|| UsageProfile::new(name.to_string()));
 
                     profile.add_signature(sig.clone());
                     self.call_site_types.insert(idx, sig.clone());
@@ -412,6 +412,7 @@ impl TypeSpecializer {
             instructions: specialized_instructions,
             stack_effect: word.stack_effect.clone(),
             is_inline: word.is_inline,
+            is_exported: word.is_exported,
             cost: word.cost,
         })
     }
@@ -464,6 +465,11 @@ impl TypeSpecializer {
                 Ok(Instruction::Abs)
             }
 
+            Instruction::Min | Instruction::Max => {
+                // Lowered to a compare and select
+                Ok(inst.clone())
+            }
+
             // === Stack Operations ===
             // These are type-agnostic but benefit from stack cache integration
             Instruction::Dup | Instruction::Drop | Instruction::Swap | Instruction::Over | Instruction::Rot => {
@@ -474,7 +480,7 @@ impl TypeSpecializer {
 
             // === Bitwise Operations ===
             // These are integer-only and don't need specialization
-            Instruction::And | Instruction::Or | Instruction::Xor | Instruction::Not | Instruction::Shl | Instruction::Shr => {
+            Instruction::And | Instruction::Or | Instruction::Xor | Instruction::Not | Instruction::Shl | Instruction::Shr | Instruction::Sar => {
                 Ok(inst.clone())
             }
 
@@ -484,6 +490,11 @@ impl TypeSpecializer {
                 self.specialize_comparison(inst, &primary_type)
             }
 
+            Instruction::ULt | Instruction::UGt => {
+                // Unsigned comparisons only apply to integer cells
+                Ok(inst.clone())
+            }
+
             Instruction::ZeroEq | Instruction::ZeroLt | Instruction::ZeroGt => {
                 // Zero comparisons don't need type specialization
                 Ok(inst.clone())
@@ -687,12 +698,12 @@ impl TypeSpecializer {
         for (idx, inst) in instructions.iter_mut().enumerate() {
             if let Instruction::Call(name) = inst {
                 if let Some(signature) = self.call_site_types.get(&idx) {
-                    if let Some(specializations) = self.specializations.get(name) {
+                    if let Some(specializations) = self.specializations.get(name.as_str()) {
                         // Find matching specialization
                         for (sig, specialized) in specializations {
                             if sig == signature {
                                 let new_name = specialized.name.clone();
-                                *inst = Instruction::Call(new_name);
+                                *inst = Instruction::call(new_name);
                                 self.stats.call_sites_rewritten += 1;
                                 break;
                             }
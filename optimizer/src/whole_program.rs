@@ -96,7 +96,7 @@ impl CallGraph {
         // Add edges for calls in main sequence
         for inst in &ir.main {
             if let Instruction::Call(callee) = inst {
-                if let Some(&callee_node) = name_to_node.get(callee) {
+                if let Some(&callee_node) = name_to_node.get(callee.as_str()) {
                     graph.add_edge(main_node, callee_node, CallEdge::Direct);
                 }
             }
@@ -109,7 +109,7 @@ impl CallGraph {
 
             for (i, inst) in instructions.iter().enumerate() {
                 if let Instruction::Call(callee_name) = inst {
-                    if let Some(&callee_node) = name_to_node.get(callee_name) {
+                    if let Some(&callee_node) = name_to_node.get(callee_name.as_str()) {
                         // Determine edge type
                         let edge_type = if callee_name == caller_name {
                             CallEdge::Recursive
@@ -166,6 +166,41 @@ impl CallGraph {
         reachable
     }
 
+    /// Group words that are connected by calls in either direction
+    ///
+    /// Words in different groups never call each other, so they can be
+    /// optimized independently. Each group is sorted by name; the group
+    /// holding `__main__` comes first and the rest are ordered by their first
+    /// name, so the result does not depend on hash map ordering.
+    pub fn components(&self) -> Vec<Vec<String>> {
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+
+        for &start in self.entry_points.iter().chain(self.name_to_node.values()) {
+            if !seen.insert(start) {
+                continue;
+            }
+
+            let mut component = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                component.push(self.graph[node].name.clone());
+                for neighbor in self.graph.neighbors_undirected(node) {
+                    if seen.insert(neighbor) {
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            component.sort();
+            components.push(component);
+        }
+
+        let main_first = |component: &Vec<String>| !component.iter().any(|name| name == "__main__");
+        components.sort_by(|a, b| main_first(a).cmp(&main_first(b)).then_with(|| a.cmp(b)));
+        components
+    }
+
     /// Find unreachable words (dead code)
     pub fn find_unreachable(&self) -> Vec<String> {
         let reachable = self.find_reachable();
@@ -236,7 +271,7 @@ impl CallGraph {
             let effects = word.instructions.iter().any(|inst| {
                 match inst {
                     Instruction::Store | Instruction::Store8 | Instruction::ToR => true,
-                    Instruction::Call(c) => has_side_effects.get(c).map_or(true, |&e| e),
+                    Instruction::Call(c) => has_side_effects.get(c.as_str()).map_or(true, |&e| e),
                     _ => false,
                 }
             });
@@ -558,7 +593,7 @@ impl WholeProgramOptimizer {
                 }
                 Instruction::Call(name) => {
                     // Check if the called word's output is constant
-                    if let Some(const_args) = constant_info.get(name) {
+                    if let Some(const_args) = constant_info.get(name.as_str()) {
                         // Invalidate stack for now (conservative)
                         stack.clear();
                     } else {
@@ -895,7 +930,7 @@ impl WholeProgramOptimizer {
                 {
                     if *n == constant && name == original_name {
                         // Replace with specialized call (without literal)
-                        result.push(Instruction::Call(specialized_name.to_string()));
+                        result.push(Instruction::call(specialized_name));
                         i += 2;
                         continue;
                     }
@@ -999,7 +1034,7 @@ mod tests {
         // Main uses only helper
         ir.main = vec![
             Instruction::Literal(10),
-            Instruction::Call("helper".to_string()),
+            Instruction::call("helper"),
         ];
 
         ir
@@ -1029,6 +1064,20 @@ mod tests {
         assert!(!unreachable.contains(&"helper".to_string()));
     }
 
+    #[test]
+    fn test_call_graph_components() {
+        let ir = create_test_ir_with_dead_code();
+        let call_graph = CallGraph::build(&ir);
+
+        assert_eq!(
+            call_graph.components(),
+            vec![
+                vec!["__main__".to_string(), "helper".to_string()],
+                vec!["unused".to_string()],
+            ]
+        );
+    }
+
     #[test]
     fn test_eliminate_dead_words() {
         // Create a simpler test without the optimizer pipeline
@@ -1052,7 +1101,7 @@ mod tests {
                 Instruction::Dup,
                 Instruction::Literal(1),
                 Instruction::Le,
-                Instruction::Call("factorial".to_string()),
+                Instruction::call("factorial"),
             ],
         );
         ir.add_word(factorial);
@@ -1108,11 +1157,11 @@ mod tests {
 
         let a = WordDef::new(
             "a".to_string(),
-            vec![Instruction::Call("b".to_string())],
+            vec![Instruction::call("b")],
         );
         let b = WordDef::new(
             "b".to_string(),
-            vec![Instruction::Call("c".to_string())],
+            vec![Instruction::call("c")],
         );
         let c = WordDef::new("c".to_string(), vec![Instruction::Literal(1)]);
 
@@ -1151,7 +1200,7 @@ mod tests {
         // Call helper with a value on the stack
         ir.main = vec![
             Instruction::Literal(10),
-            Instruction::Call("helper".to_string()),
+            Instruction::call("helper"),
         ];
 
         let optimized = optimizer.optimize(&ir).unwrap();
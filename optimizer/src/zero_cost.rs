@@ -38,7 +38,7 @@
 //! ;
 //! ```
 
-use crate::ir::{ForthIR, Instruction, StackEffect, WordDef};
+use crate::ir::{ForthIR, Instruction, Splice};
 use crate::{ConstantFolder, InlineOptimizer, OptimizationLevel, Result, OptimizerError};
 use smallvec::{SmallVec, smallvec};
 use std::collections::HashMap;
@@ -58,6 +58,8 @@ pub struct ZeroCostConfig {
     pub conditional_elimination: bool,
     /// Enable algebraic simplifications
     pub algebraic_simplification: bool,
+    /// Reject overflowing constant arithmetic instead of folding the wrapped result
+    pub checked_arithmetic: bool,
 }
 
 impl Default for ZeroCostConfig {
@@ -69,6 +71,7 @@ impl Default for ZeroCostConfig {
             constant_folding: true,
             conditional_elimination: true,
             algebraic_simplification: true,
+            checked_arithmetic: false,
         }
     }
 }
@@ -83,8 +86,8 @@ pub struct ZeroCostOptimizer {
 impl ZeroCostOptimizer {
     pub fn new(config: ZeroCostConfig) -> Self {
         Self {
+            constant_folder: ConstantFolder::new().with_checked_arithmetic(config.checked_arithmetic),
             config,
-            constant_folder: ConstantFolder::new(),
             inline_optimizer: InlineOptimizer::new(OptimizationLevel::Aggressive),
         }
     }
@@ -164,15 +167,20 @@ impl ZeroCostOptimizer {
         candidates: &HashMap<String, bool>,
     ) -> Result<Vec<Instruction>> {
         let mut result = Vec::new();
+        let mut splice = Splice::new(instructions);
 
         for inst in instructions {
             if let Instruction::Call(name) = inst {
-                if candidates.get(name).copied().unwrap_or(false) {
+                if candidates.get(name.as_str()).copied().unwrap_or(false) {
                     if let Some(word) = ir.get_word(name) {
                         // Recursively inline
-                        let inlined = self.inline_sequence(&word.instructions, ir, candidates)?;
-                        result.extend(inlined);
-                        continue;
+                        let mut inlined = word.clone();
+                        inlined.instructions =
+                            self.inline_sequence(&word.instructions, ir, candidates)?;
+                        if let Some(body) = splice.body(&inlined) {
+                            result.extend(body);
+                            continue;
+                        }
                     }
                 }
             }
@@ -244,18 +252,21 @@ impl ZeroCostOptimizer {
             // Look for patterns: operation following DUP or other stack ops
             if i + 2 < instructions.len() {
                 match (&instructions[i], &instructions[i + 1], &instructions[i + 2]) {
-                    // DUP followed by comparison to zero
+                    // DUP followed by comparison to zero keeps the duplicated value
                     (Instruction::Dup, Instruction::Literal(0), Instruction::Eq) => {
+                        result.push(Instruction::Dup);
                         result.push(Instruction::ZeroEq);
                         i += 3;
                         continue;
                     }
                     (Instruction::Dup, Instruction::Literal(0), Instruction::Lt) => {
+                        result.push(Instruction::Dup);
                         result.push(Instruction::ZeroLt);
                         i += 3;
                         continue;
                     }
                     (Instruction::Dup, Instruction::Literal(0), Instruction::Gt) => {
+                        result.push(Instruction::Dup);
                         result.push(Instruction::ZeroGt);
                         i += 3;
                         continue;
@@ -360,7 +371,7 @@ impl ZeroCostOptimizer {
         let mut i = 0;
 
         while i < instructions.len() {
-            // Look for pattern: Literal(n) BranchIf/BranchIfNot
+            // Look for pattern: Literal(n) BranchIf/BranchIfNot/BranchIfElse
             if i + 1 < instructions.len() {
                 match (&instructions[i], &instructions[i + 1]) {
                     // TRUE (non-zero) followed by BranchIf -> always take branch
@@ -387,6 +398,12 @@ impl ZeroCostOptimizer {
                         i += 2;
                         continue;
                     }
+                    // A constant flag picks one side of a two-way branch
+                    (Instruction::Literal(n), Instruction::BranchIfElse(then, otherwise)) => {
+                        result.push(Instruction::Branch(if *n != 0 { *then } else { *otherwise }));
+                        i += 2;
+                        continue;
+                    }
                     _ => {}
                 }
             }
@@ -434,7 +451,12 @@ impl ZeroCostOptimizer {
                     // Check if this looks like a loop (has backward branch)
                     let loop_body_end = instructions[i + 2..]
                         .iter()
-                        .position(|inst| matches!(inst, Instruction::BranchIfNot(_) | Instruction::Branch(_)));
+                        .position(|inst| {
+                            matches!(
+                                inst,
+                                Instruction::BranchIfNot(_) | Instruction::BranchIfElse(..) | Instruction::Branch(_)
+                            )
+                        });
 
                     if let Some(body_len) = loop_body_end {
                         let iterations = (end_val - start_val).abs();
@@ -492,10 +514,10 @@ impl ZeroCostOptimizer {
         let literals_after = count_type(after, |i| matches!(i, Instruction::Literal(_)));
 
         let branches_before = count_type(before, |i| {
-            matches!(i, Instruction::BranchIf(_) | Instruction::BranchIfNot(_))
+            matches!(i, Instruction::BranchIf(_) | Instruction::BranchIfNot(_) | Instruction::BranchIfElse(..))
         });
         let branches_after = count_type(after, |i| {
-            matches!(i, Instruction::BranchIf(_) | Instruction::BranchIfNot(_))
+            matches!(i, Instruction::BranchIf(_) | Instruction::BranchIfNot(_) | Instruction::BranchIfElse(..))
         });
 
         ZeroCostStats {
@@ -577,6 +599,7 @@ impl std::fmt::Display for ZeroCostStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::{BlockRef, WordDef};
 
     #[test]
     fn test_unconditional_inline_tiny_words() {
@@ -592,8 +615,8 @@ mod tests {
         ir.add_word(three);
 
         ir.main = vec![
-            Instruction::Call("three".to_string()),
-            Instruction::Call("three".to_string()),
+            Instruction::call("three"),
+            Instruction::call("three"),
             Instruction::Add,
         ];
 
@@ -702,14 +725,14 @@ mod tests {
         let mut ir = ForthIR::new();
         ir.main = vec![
             Instruction::Literal(-1), // TRUE in Forth
-            Instruction::BranchIf(10),
+            Instruction::BranchIf(BlockRef(10)),
         ];
 
         let optimized = optimizer.eliminate_conditional_sequence(&ir.main).unwrap();
 
         // Should become unconditional branch
         assert_eq!(optimized.len(), 1);
-        assert!(matches!(optimized[0], Instruction::Branch(10)));
+        assert!(matches!(optimized[0], Instruction::Branch(BlockRef(10))));
     }
 
     #[test]
@@ -719,7 +742,7 @@ mod tests {
         let mut ir = ForthIR::new();
         ir.main = vec![
             Instruction::Literal(0), // FALSE in Forth
-            Instruction::BranchIf(10),
+            Instruction::BranchIf(BlockRef(10)),
         ];
 
         let optimized = optimizer.eliminate_conditional_sequence(&ir.main).unwrap();
@@ -762,7 +785,7 @@ mod tests {
             Instruction::Literal(3),
             Instruction::Literal(0),
             Instruction::Dup,
-            Instruction::BranchIfNot(2),
+            Instruction::BranchIfNot(BlockRef(2)),
         ];
 
         let optimized = optimizer.unroll_loops(&ir).unwrap();
@@ -816,8 +839,8 @@ mod tests {
 
         // Main: sum5 sum5 +  -> should become 6
         ir.main = vec![
-            Instruction::Call("sum5".to_string()),
-            Instruction::Call("sum5".to_string()),
+            Instruction::call("sum5"),
+            Instruction::call("sum5"),
             Instruction::Add,
         ];
 
@@ -851,8 +874,8 @@ mod tests {
         let four = WordDef::new(
             "four".to_string(),
             vec![
-                Instruction::Call("two".to_string()),
-                Instruction::Call("two".to_string()),
+                Instruction::call("two"),
+                Instruction::call("two"),
                 Instruction::Add,
             ],
         );
@@ -860,7 +883,7 @@ mod tests {
 
         // Main: four -> should become 4
         ir.main = vec![
-            Instruction::Call("four".to_string()),
+            Instruction::call("four"),
         ];
 
         let optimized = optimizer.optimize(&ir).unwrap();
@@ -112,7 +112,7 @@ fn test_strength_reduction_div_two() {
 
     // Should constant fold to 5 or use DivTwo/shift
     let has_5 = optimized.main.iter().any(|i| matches!(i, Instruction::Literal(5)));
-    let has_optimization = optimized.main.iter().any(|i| matches!(i, Instruction::DivTwo | Instruction::Shr));
+    let has_optimization = optimized.main.iter().any(|i| matches!(i, Instruction::DivTwo | Instruction::Sar));
     assert!(has_5 || has_optimization || optimized.main.len() < 3, "Should optimize division by 2");
 }
 
@@ -177,7 +177,7 @@ fn test_stack_effect_composition() {
     let effect1 = StackEffect::new(2, 1); // ( a b -- c )
     let effect2 = StackEffect::new(1, 2); // ( c -- d e )
 
-    let composed = effect1.compose(&effect2);
+    let composed = effect1.compose(&effect2).unwrap();
 
     // ( a b -- d e ) net: consumes 2, produces 2
     assert_eq!(composed.consumed, 2);
@@ -255,9 +255,9 @@ fn test_call_site_rewriting() {
     // Create main sequence with calls
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("square".to_string()),
+        Instruction::call("square"),
         Instruction::Literal(3),
-        Instruction::Call("square".to_string()),
+        Instruction::call("square"),
     ];
 
     let square = WordDef::new(
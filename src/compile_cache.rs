@@ -0,0 +1,370 @@
+//! Incremental compilation cache
+//!
+//! A [`CompileCache`] remembers the SSA form of every definition it lowers,
+//! keyed by a hash of the definition together with the hashes of the words it
+//! calls and the program's top-level declarations. Recompiling a program on
+//! the same cache only converts definitions whose key changed, so editing a
+//! word also recompiles every word that (transitively) calls it.
+//!
+//! Entries keep their normalized definition, so a hash collision is detected
+//! by comparing definitions and treated as a miss. The cache holds at most
+//! [`CompileCache::capacity`] entries and evicts the least recently used one
+//! when full. Native code is not cached: the backend still compiles every
+//! function into a fresh JIT module.
+
+use crate::error::Result;
+use crate::pipeline::lower_program_with;
+use fastforth_frontend::ast::SourceLocation;
+use fastforth_frontend::{Definition, Program, SSAFunction, Word};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Default number of cached definitions
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// A cached definition and its SSA form
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    /// The definition the function was lowered from, with its location cleared
+    definition: Definition,
+    function: SSAFunction,
+    /// Value of the cache clock when the entry was last used
+    last_used: u64,
+}
+
+/// Cache of lowered definitions, shared across compilations
+#[derive(Debug, Clone)]
+pub struct CompileCache {
+    entries: HashMap<u64, CacheEntry>,
+    capacity: usize,
+    /// Advanced once per lowering, for least-recently-used eviction
+    clock: u64,
+    hits: usize,
+    misses: usize,
+}
+
+impl CompileCache {
+    /// Create an empty cache holding up to [`DEFAULT_CACHE_CAPACITY`] definitions
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Create an empty cache holding up to `capacity` definitions
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Maximum number of cached definitions
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of cached definitions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Definitions reused by the most recent [`lower`](Self::lower)
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Definitions converted by the most recent [`lower`](Self::lower)
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Forget every cached definition
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Lower `program` to SSA, reusing cached functions for unchanged definitions
+    ///
+    /// Returns the same functions, in the same order, as a full lowering.
+    pub fn lower(&mut self, program: &Program) -> Result<Vec<SSAFunction>> {
+        self.clock += 1;
+
+        let keys = Self::definition_keys(program);
+        // Take cached functions out up front, so entries evicted while
+        // inserting new ones are still available to this lowering
+        let mut cached: HashMap<u64, SSAFunction> = HashMap::new();
+        for (def, key) in program.definitions.iter().zip(&keys) {
+            if let Some(entry) = self.entries.get_mut(key) {
+                if entry.definition == normalize(def) {
+                    entry.last_used = self.clock;
+                    cached.insert(*key, entry.function.clone());
+                }
+            }
+        }
+        let skipped: HashSet<*const Definition> = program
+            .definitions
+            .iter()
+            .zip(&keys)
+            .filter(|(_, key)| cached.contains_key(key))
+            .map(|(def, _)| def as *const Definition)
+            .collect();
+
+        let mut converted =
+            lower_program_with(program, |def| !skipped.contains(&(def as *const Definition)))?.into_iter();
+
+        // Reassemble in definition order; defining words produce no function
+        let defining_words = defining_words(program);
+        let mut functions = Vec::new();
+        self.hits = 0;
+        self.misses = 0;
+        for (def, key) in program.definitions.iter().zip(&keys) {
+            if defining_words.contains(def.name.as_str()) {
+                continue;
+            }
+
+            if let Some(function) = cached.get(key) {
+                functions.push(function.clone());
+                self.hits += 1;
+            } else if let Some(function) = converted.next() {
+                self.insert(*key, normalize(def), function.clone());
+                functions.push(function);
+                self.misses += 1;
+            }
+        }
+        // Whatever remains is the function made from top-level code
+        functions.extend(converted);
+
+        Ok(functions)
+    }
+
+    fn insert(&mut self, key: u64, definition: Definition, function: SSAFunction) {
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        if self.capacity > 0 {
+            self.entries.insert(key, CacheEntry { definition, function, last_used: self.clock });
+        }
+    }
+
+    /// Cache key of every definition, in definition order
+    fn definition_keys(program: &Program) -> Vec<u64> {
+        let context = declarations_hash(program);
+        let mut memo = HashMap::new();
+
+        (0..program.definitions.len())
+            .map(|index| Self::definition_key(program, index, context, &mut memo, &mut HashSet::new()))
+            .collect()
+    }
+
+    /// Hash of a definition, the declarations it can see, and the keys of the
+    /// words it calls
+    fn definition_key(
+        program: &Program,
+        index: usize,
+        context: u64,
+        memo: &mut HashMap<usize, u64>,
+        visiting: &mut HashSet<usize>,
+    ) -> u64 {
+        if let Some(&key) = memo.get(&index) {
+            return key;
+        }
+
+        let def = &program.definitions[index];
+        let mut hasher = DefaultHasher::new();
+        context.hash(&mut hasher);
+        format!("{:?}", normalize(def)).hash(&mut hasher);
+
+        visiting.insert(index);
+        let mut names = Vec::new();
+        collect_calls(&def.body, &mut names);
+        for name in names {
+            // The latest definition of a name before this one is the one called
+            let callee = program.definitions[..index]
+                .iter()
+                .rposition(|candidate| candidate.name == name);
+            if let Some(callee) = callee.filter(|callee| !visiting.contains(callee)) {
+                Self::definition_key(program, callee, context, memo, visiting).hash(&mut hasher);
+            }
+        }
+        visiting.remove(&index);
+
+        let key = hasher.finish();
+        memo.insert(index, key);
+        key
+    }
+}
+
+impl Default for CompileCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A definition with its source location cleared, so moving it does not
+/// invalidate its entry
+fn normalize(def: &Definition) -> Definition {
+    Definition {
+        body: strip_locations(&def.body),
+        location: SourceLocation::default(),
+        ..def.clone()
+    }
+}
+
+/// Copy of `words` with every word reference's location cleared
+fn strip_locations(words: &[Word]) -> Vec<Word> {
+    words
+        .iter()
+        .map(|word| match word {
+            Word::WordRef { name, .. } => Word::WordRef {
+                name: name.clone(),
+                location: SourceLocation::default(),
+            },
+            Word::If { then_branch, else_branch } => Word::If {
+                then_branch: strip_locations(then_branch),
+                else_branch: else_branch.as_deref().map(strip_locations),
+            },
+            Word::BeginUntil { body } => Word::BeginUntil { body: strip_locations(body) },
+            Word::BeginWhileRepeat { condition, body } => Word::BeginWhileRepeat {
+                condition: strip_locations(condition),
+                body: strip_locations(body),
+            },
+            Word::DoLoop { body, increment } => Word::DoLoop {
+                body: strip_locations(body),
+                increment: *increment,
+            },
+            Word::Does { body } => Word::Does { body: strip_locations(body) },
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// Names of definitions containing `CREATE`, which are expanded where used
+fn defining_words(program: &Program) -> HashSet<&str> {
+    program
+        .definitions
+        .iter()
+        .filter(|def| def.body.iter().any(|word| matches!(word, Word::Create { .. })))
+        .map(|def| def.name.as_str())
+        .collect()
+}
+
+/// Hash of everything outside a definition that changes how it is lowered:
+/// top-level declarations and the bodies of defining words
+fn declarations_hash(program: &Program) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for word in &program.top_level_code {
+        let declares = match word {
+            Word::Variable { .. }
+            | Word::Constant { .. }
+            | Word::Value { .. }
+            | Word::Create { .. }
+            | Word::Instantiate { .. } => true,
+            Word::WordRef { name, .. } => name == ",",
+            _ => false,
+        };
+        if declares {
+            format!("{:?}", strip_locations(std::slice::from_ref(word))).hash(&mut hasher);
+        }
+    }
+    let defining_words = defining_words(program);
+    for def in program.definitions.iter().filter(|def| defining_words.contains(def.name.as_str())) {
+        format!("{:?}", normalize(def)).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Names referenced anywhere in `words`, in order of first appearance
+fn collect_calls<'a>(words: &'a [Word], names: &mut Vec<&'a str>) {
+    for word in words {
+        match word {
+            Word::WordRef { name, .. } if !names.contains(&name.as_str()) => names.push(name),
+            Word::If { then_branch, else_branch } => {
+                collect_calls(then_branch, names);
+                if let Some(else_branch) = else_branch {
+                    collect_calls(else_branch, names);
+                }
+            }
+            Word::BeginWhileRepeat { condition, body } => {
+                collect_calls(condition, names);
+                collect_calls(body, names);
+            }
+            Word::BeginUntil { body } | Word::DoLoop { body, .. } | Word::Does { body } => {
+                collect_calls(body, names);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastforth_frontend::parse_program;
+
+    fn lower(cache: &mut CompileCache, source: &str) -> Vec<SSAFunction> {
+        cache.lower(&parse_program(source).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_unchanged_definitions_hit() {
+        let mut cache = CompileCache::new();
+        let source = ": double ( n -- n ) 2 * ; : quad ( n -- n ) double double ; 3 quad";
+
+        let first = lower(&mut cache, source);
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+
+        let second = lower(&mut cache, source);
+        assert_eq!((cache.hits(), cache.misses()), (2, 0));
+
+        let names = |functions: &[SSAFunction]| functions.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&first), names(&second));
+        assert_eq!(names(&second), vec!["double", "quad", ":main"]);
+    }
+
+    #[test]
+    fn test_changed_callee_invalidates_callers() {
+        let mut cache = CompileCache::new();
+        lower(&mut cache, ": double ( n -- n ) 2 * ; : quad ( n -- n ) double double ; : one 1 ; 3 quad");
+
+        // `double` changed, so `quad` must be relowered; `one` is untouched
+        lower(&mut cache, ": double ( n -- n ) dup + ; : quad ( n -- n ) double double ; : one 1 ; 5 quad");
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[test]
+    fn test_declarations_invalidate_definitions() {
+        let mut cache = CompileCache::new();
+        lower(&mut cache, "variable x : get x @ ; get");
+        lower(&mut cache, "variable x variable y : get x @ ; get");
+        assert_eq!(cache.hits(), 0);
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted() {
+        let mut cache = CompileCache::with_capacity(2);
+        lower(&mut cache, ": a 1 ; : b 2 ;");
+        lower(&mut cache, ": a 1 ;");
+        lower(&mut cache, ": c 3 ;");
+        assert_eq!(cache.len(), 2);
+
+        // `b` was the least recently used, so it was the one dropped
+        lower(&mut cache, ": a 1 ; : b 2 ; : c 3 ;");
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+    }
+}
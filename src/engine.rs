@@ -1,11 +1,61 @@
 //! Simple Forth engine for testing and REPL
 //!
 //! Provides a simple interface for executing Forth code and inspecting the stack
+//!
+//! Colon definitions are stored as token lists and can be referenced by
+//! execution token (`'`), run with `EXECUTE`, and guarded with `CATCH`.
+//! `THROW` unwinds to the innermost `CATCH`, which restores the data and
+//! return stack depths it saw on entry.
+//!
+//! Numbers are read and printed in the radix held by the `BASE` variable,
+//! which `DECIMAL`, `HEX`, `BINARY` and `OCTAL` set. A token that names a
+//! word is never read as a number.
+//!
+//! Data space is a fixed region starting at `DATA_SPACE_START`. `HERE`
+//! points at its next free byte, `ALLOT` moves that pointer, `,` stores a
+//! cell there, and `CREATE` names the aligned address it ends up at.
+//! Variables are allotted from the same region.
 
+use crate::error::CompileError;
+use fastforth_frontend::ForthError;
 use crate::{Compiler, CompilationMode, OptimizationLevel, Result};
 use std::collections::HashMap;
 use std::fmt;
 
+/// Address of the `BASE` variable, just below data space
+const BASE_ADDR: i64 = 0x0ff8;
+
+/// Size of a cell in bytes
+const CELL: i64 = 8;
+
+/// First address of data space
+const DATA_SPACE_START: i64 = 0x1000;
+
+/// Bytes of data space available to `ALLOT`, `,` and variables
+const DATA_SPACE_SIZE: i64 = 0x10000;
+
+/// Cells of return stack, one per colon definition being run plus those
+/// moved there with `>R`
+const RETURN_STACK_SIZE: usize = 256;
+
+/// Names of the built-in words, which `'` gives execution tokens too
+const BUILTIN_WORDS: &[&str] = &[
+    "+", "-", "*", "/", "MOD", "/MOD", "DUP", "DROP", "SWAP", "OVER", "ROT", "-ROT", "NIP", "TUCK",
+    "2DUP", "2DROP", "2SWAP", "2OVER", "DEPTH", "PICK", "ROLL", "=", "<>", "<", ">", "<=", ">=",
+    "0=", "0<", "0>", "AND", "OR", "XOR", "INVERT", "NEGATE", "ABS", "MIN", "MAX", ".", "U.", "!",
+    "@", "+!", "HERE", "ALLOT", ",", "ALIGN", "ALIGNED", "CELLS", "CELL+", "CREATE", ">R", "R>",
+    "R@", "2>R", "2R>", "2R@", ":", "'", "EXECUTE", "CATCH", "THROW", "BASE", "DECIMAL", "HEX",
+    "BINARY", "OCTAL",
+];
+
+/// ANS Forth exception codes of the errors `CATCH` can handle
+const STACK_UNDERFLOW: i64 = -4;
+const RETURN_STACK_OVERFLOW: i64 = -5;
+const RETURN_STACK_UNDERFLOW: i64 = -6;
+const DICTIONARY_OVERFLOW: i64 = -8;
+const DIVISION_BY_ZERO: i64 = -10;
+const UNDEFINED_WORD: i64 = -13;
+
 /// Simple Forth execution engine for testing
 pub struct ForthEngine {
     compiler: Compiler,
@@ -15,8 +65,14 @@ pub struct ForthEngine {
     variables: HashMap<String, i64>,
     constants: HashMap<String, i64>,
     values: HashMap<String, i64>,
-    next_addr: i64,
-    base: i64,
+    /// Bodies of colon definitions and ticked words, indexed by execution token
+    definitions: Vec<Vec<String>>,
+    /// Execution token of each colon definition
+    words: HashMap<String, i64>,
+    /// Colon definitions being run, each holding a return stack cell
+    calls: usize,
+    /// Data-space pointer returned by `HERE`
+    here: i64,
     output: String,
 }
 
@@ -27,12 +83,14 @@ impl ForthEngine {
             compiler: Compiler::new(OptimizationLevel::Standard),
             stack: Vec::new(),
             return_stack: Vec::new(),
-            memory: HashMap::new(),
+            memory: HashMap::from([(BASE_ADDR, 10)]),
             variables: HashMap::new(),
             constants: HashMap::new(),
             values: HashMap::new(),
-            next_addr: 0x1000, // Start memory addresses at 0x1000
-            base: 10,
+            definitions: Vec::new(),
+            words: HashMap::new(),
+            calls: 0,
+            here: DATA_SPACE_START,
             output: String::new(),
         }
     }
@@ -42,302 +100,40 @@ impl ForthEngine {
         // Parse simple stack operations for testing
         // This is a minimal interpreter for differential testing
         let tokens: Vec<&str> = code.split_whitespace().collect();
+        self.interpret(&tokens)
+    }
 
-        for token in tokens {
-            match token.to_uppercase().as_str() {
-                // Numbers
-                s if s.parse::<i64>().is_ok() => {
-                    self.stack.push(s.parse().unwrap());
-                }
-                // Arithmetic
-                "+" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a + b);
-                }
-                "-" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a - b);
-                }
-                "*" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a * b);
-                }
-                "/" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    if b == 0 {
-                        return Err(crate::error::CompileError::RuntimeError("Division by zero".to_string()));
-                    }
-                    self.stack.push(a / b);
-                }
-                "MOD" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    if b == 0 {
-                        return Err(crate::error::CompileError::RuntimeError("Modulo by zero".to_string()));
-                    }
-                    self.stack.push(a % b);
-                }
-                "/MOD" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    if b == 0 {
-                        return Err(crate::error::CompileError::RuntimeError("Division by zero".to_string()));
-                    }
-                    self.stack.push(a % b);  // remainder
-                    self.stack.push(a / b);  // quotient
-                }
-                // Stack manipulation
-                "DUP" => {
-                    let a = self.peek()?;
-                    self.stack.push(a);
-                }
-                "DROP" => {
-                    self.pop()?;
-                }
-                "SWAP" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(b);
-                    self.stack.push(a);
-                }
-                "OVER" => {
-                    // ( a b -- a b a )
-                    if self.stack.len() < 2 {
-                        return Err(crate::error::CompileError::RuntimeError("Stack underflow".to_string()));
-                    }
-                    let a = self.stack[self.stack.len() - 2];
-                    self.stack.push(a);
-                }
-                "ROT" => {
-                    // ( a b c -- b c a )
-                    if self.stack.len() < 3 {
-                        return Err(crate::error::CompileError::RuntimeError("Stack underflow".to_string()));
-                    }
-                    let c = self.pop()?;
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(b);
-                    self.stack.push(c);
-                    self.stack.push(a);
-                }
-                "NIP" => {
-                    // ( a b -- b )
-                    let b = self.pop()?;
-                    self.pop()?;
-                    self.stack.push(b);
-                }
-                "TUCK" => {
-                    // ( a b -- b a b )
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(b);
-                    self.stack.push(a);
-                    self.stack.push(b);
-                }
-                "2DUP" => {
-                    // ( a b -- a b a b )
-                    if self.stack.len() < 2 {
-                        return Err(crate::error::CompileError::RuntimeError("Stack underflow".to_string()));
-                    }
-                    let b = self.stack[self.stack.len() - 1];
-                    let a = self.stack[self.stack.len() - 2];
-                    self.stack.push(a);
-                    self.stack.push(b);
-                }
-                "2DROP" => {
-                    self.pop()?;
-                    self.pop()?;
-                }
-                "2SWAP" => {
-                    // ( a b c d -- c d a b )
-                    if self.stack.len() < 4 {
-                        return Err(crate::error::CompileError::RuntimeError("Stack underflow".to_string()));
-                    }
-                    let d = self.pop()?;
-                    let c = self.pop()?;
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(c);
-                    self.stack.push(d);
-                    self.stack.push(a);
-                    self.stack.push(b);
-                }
-                // Comparison
-                "=" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(if a == b { -1 } else { 0 });
-                }
-                "<" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(if a < b { -1 } else { 0 });
-                }
-                ">" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(if a > b { -1 } else { 0 });
-                }
-                "<=" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(if a <= b { -1 } else { 0 });
-                }
-                ">=" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(if a >= b { -1 } else { 0 });
-                }
-                "0=" => {
-                    let a = self.pop()?;
-                    self.stack.push(if a == 0 { -1 } else { 0 });
-                }
-                "0<" => {
-                    let a = self.pop()?;
-                    self.stack.push(if a < 0 { -1 } else { 0 });
-                }
-                "0>" => {
-                    let a = self.pop()?;
-                    self.stack.push(if a > 0 { -1 } else { 0 });
-                }
-                // Logical
-                "AND" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a & b);
-                }
-                "OR" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a | b);
-                }
-                "XOR" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a ^ b);
-                }
-                "INVERT" => {
-                    let a = self.pop()?;
-                    self.stack.push(!a);
-                }
-                "NEGATE" => {
-                    let a = self.pop()?;
-                    self.stack.push(-a);
-                }
-                "ABS" => {
-                    let a = self.pop()?;
-                    self.stack.push(a.abs());
-                }
-                "MIN" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a.min(b));
-                }
-                "MAX" => {
-                    let b = self.pop()?;
-                    let a = self.pop()?;
-                    self.stack.push(a.max(b));
-                }
-                "." => {
-                    // Print and drop (for GForth compatibility)
-                    let val = self.pop()?;
-                    self.output.push_str(&format!("{} ", val));
-                }
-
-                // PRIORITY 1: Memory Operations
-                "!" => {
-                    // ( val addr -- ) Store value at address
-                    let addr = self.pop()?;
-                    let val = self.pop()?;
-                    self.memory.insert(addr, val);
-                }
-                "@" => {
-                    // ( addr -- val ) Fetch value from address
-                    let addr = self.pop()?;
-                    let val = *self.memory.get(&addr).unwrap_or(&0);
-                    self.stack.push(val);
-                }
-                "+!" => {
-                    // ( n addr -- ) Add n to value at addr
-                    let addr = self.pop()?;
-                    let n = self.pop()?;
-                    let current = *self.memory.get(&addr).unwrap_or(&0);
-                    self.memory.insert(addr, current + n);
-                }
+    /// Run a sequence of tokens
+    fn interpret(&mut self, tokens: &[&str]) -> Result<()> {
+        let mut rest = tokens.iter();
 
-                // PRIORITY 2: Advanced Stack Operations (Return Stack)
-                ">R" => {
-                    // ( n -- ) Move from data stack to return stack
-                    let val = self.pop()?;
-                    self.return_stack.push(val);
-                }
-                "R>" => {
-                    // ( -- n ) Move from return stack to data stack
-                    let val = self.return_stack.pop().ok_or_else(|| {
-                        crate::error::CompileError::RuntimeError("Return stack underflow".to_string())
-                    })?;
-                    self.stack.push(val);
+        while let Some(&token) = rest.next() {
+            // Words are looked up before numbers, so one spelled with digits
+            // of the current base still runs. Words that run other words are
+            // kept out of `builtin`, so deep recursion does not stack up its
+            // large frames
+            let upper_token = token.to_uppercase();
+            match upper_token.as_str() {
+                "EXECUTE" => {
+                    // ( i*x xt -- j*x )
+                    let xt = self.pop()?;
+                    self.execute(xt)?;
                 }
-                "R@" => {
-                    // ( -- n ) Copy from return stack to data stack
-                    let val = self.return_stack.last().copied().ok_or_else(|| {
-                        crate::error::CompileError::RuntimeError("Return stack underflow".to_string())
-                    })?;
-                    self.stack.push(val);
-                }
-                "2>R" => {
-                    // ( n1 n2 -- ) Move two cells to return stack
-                    let n2 = self.pop()?;
-                    let n1 = self.pop()?;
-                    self.return_stack.push(n1);
-                    self.return_stack.push(n2);
-                }
-                "2R>" => {
-                    // ( -- n1 n2 ) Move two cells from return stack
-                    let n2 = self.return_stack.pop().ok_or_else(|| {
-                        crate::error::CompileError::RuntimeError("Return stack underflow".to_string())
-                    })?;
-                    let n1 = self.return_stack.pop().ok_or_else(|| {
-                        crate::error::CompileError::RuntimeError("Return stack underflow".to_string())
-                    })?;
-                    self.stack.push(n1);
-                    self.stack.push(n2);
-                }
-                "2R@" => {
-                    // ( -- n1 n2 ) Copy two cells from return stack
-                    if self.return_stack.len() < 2 {
-                        return Err(crate::error::CompileError::RuntimeError("Return stack underflow".to_string()));
-                    }
-                    let len = self.return_stack.len();
-                    let n1 = self.return_stack[len - 2];
-                    let n2 = self.return_stack[len - 1];
-                    self.stack.push(n1);
-                    self.stack.push(n2);
-                }
-
-                // PRIORITY 4: Base Conversion
-                "DECIMAL" => {
-                    self.base = 10;
-                }
-                "HEX" => {
-                    self.base = 16;
-                }
-                "BINARY" => {
-                    self.base = 2;
-                }
-                "OCTAL" => {
-                    self.base = 8;
+                "CATCH" => {
+                    // ( i*x xt -- j*x 0 | i*x n )
+                    let xt = self.pop()?;
+                    self.catch(xt)?;
                 }
+                _ if self.builtin(&upper_token, &mut rest)? => {}
 
                 // Handle variable/constant/value references
                 _ => {
-                    let upper_token = token.to_uppercase();
+                    // Check if it's a colon definition
+                    if let Some(&xt) = self.words.get(&upper_token) {
+                        self.execute(xt)?;
+                    }
                     // Check if it's a variable reference
-                    if let Some(&addr) = self.variables.get(&upper_token) {
+                    else if let Some(&addr) = self.variables.get(&upper_token) {
                         self.stack.push(addr);
                     }
                     // Check if it's a constant reference
@@ -348,6 +144,10 @@ impl ForthEngine {
                     else if let Some(&val) = self.values.get(&upper_token) {
                         self.stack.push(val);
                     }
+                    // Otherwise it should be a number
+                    else if let Some(n) = self.parse_number(token)? {
+                        self.stack.push(n);
+                    }
                     // Ignore unknown words for now
                     // In a real implementation, this would error
                 }
@@ -357,6 +157,595 @@ impl ForthEngine {
         Ok(())
     }
 
+    /// Run the built-in word `token`, given in upper case, taking any name it
+    /// parses from `rest`; false if there is no such word
+    fn builtin(&mut self, token: &str, rest: &mut std::slice::Iter<'_, &str>) -> Result<bool> {
+        match token {
+            // Arithmetic
+            "+" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a + b);
+            }
+            "-" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a - b);
+            }
+            "*" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a * b);
+            }
+            "/" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                if b == 0 {
+                    return Err(exception(DIVISION_BY_ZERO, "Division by zero"));
+                }
+                self.stack.push(a / b);
+            }
+            "MOD" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                if b == 0 {
+                    return Err(exception(DIVISION_BY_ZERO, "Modulo by zero"));
+                }
+                self.stack.push(a % b);
+            }
+            "/MOD" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                if b == 0 {
+                    return Err(exception(DIVISION_BY_ZERO, "Division by zero"));
+                }
+                self.stack.push(a % b);  // remainder
+                self.stack.push(a / b);  // quotient
+            }
+            // Stack manipulation
+            "DUP" => {
+                let a = self.peek()?;
+                self.stack.push(a);
+            }
+            "DROP" => {
+                self.pop()?;
+            }
+            "SWAP" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(b);
+                self.stack.push(a);
+            }
+            "OVER" => {
+                // ( a b -- a b a )
+                self.require("OVER", 2)?;
+                let a = self.stack[self.stack.len() - 2];
+                self.stack.push(a);
+            }
+            "ROT" => {
+                // ( a b c -- b c a )
+                self.require("ROT", 3)?;
+                let c = self.pop()?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(b);
+                self.stack.push(c);
+                self.stack.push(a);
+            }
+            "-ROT" => {
+                // ( a b c -- c a b )
+                self.require("-ROT", 3)?;
+                let c = self.pop()?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(c);
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            "NIP" => {
+                // ( a b -- b )
+                self.require("NIP", 2)?;
+                let b = self.pop()?;
+                self.pop()?;
+                self.stack.push(b);
+            }
+            "TUCK" => {
+                // ( a b -- b a b )
+                self.require("TUCK", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(b);
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            "2DUP" => {
+                // ( a b -- a b a b )
+                self.require("2DUP", 2)?;
+                let b = self.stack[self.stack.len() - 1];
+                let a = self.stack[self.stack.len() - 2];
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            "2DROP" => {
+                // ( a b -- )
+                self.require("2DROP", 2)?;
+                self.pop()?;
+                self.pop()?;
+            }
+            "2SWAP" => {
+                // ( a b c d -- c d a b )
+                self.require("2SWAP", 4)?;
+                let d = self.pop()?;
+                let c = self.pop()?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(c);
+                self.stack.push(d);
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            "2OVER" => {
+                // ( a b c d -- a b c d a b )
+                self.require("2OVER", 4)?;
+                let b = self.stack[self.stack.len() - 3];
+                let a = self.stack[self.stack.len() - 4];
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            "DEPTH" => {
+                // ( -- n )
+                self.stack.push(self.stack.len() as i64);
+            }
+            "PICK" | "ROLL" => {
+                // PICK ( xu ... x0 u -- xu ... x0 xu )
+                // ROLL ( xu xu-1 ... x0 u -- xu-1 ... x0 xu )
+                self.require(token, 1)?;
+                let u = self.stack[self.stack.len() - 1];
+                if u < 0 || u as usize + 1 >= self.stack.len() {
+                    return Err(crate::error::CompileError::RuntimeError(format!(
+                        "{} index {} out of range for stack depth {}",
+                        token,
+                        u,
+                        self.stack.len() - 1
+                    )));
+                }
+                self.pop()?;
+                let pos = self.stack.len() - 1 - u as usize;
+                if token == "PICK" {
+                    self.stack.push(self.stack[pos]);
+                } else {
+                    let x = self.stack.remove(pos);
+                    self.stack.push(x);
+                }
+            }
+            // Comparison
+            "=" => {
+                self.require("=", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(if a == b { -1 } else { 0 });
+            }
+            "<>" => {
+                self.require("<>", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(if a != b { -1 } else { 0 });
+            }
+            "<" => {
+                self.require("<", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(if a < b { -1 } else { 0 });
+            }
+            ">" => {
+                self.require(">", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(if a > b { -1 } else { 0 });
+            }
+            "<=" => {
+                self.require("<=", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(if a <= b { -1 } else { 0 });
+            }
+            ">=" => {
+                self.require(">=", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(if a >= b { -1 } else { 0 });
+            }
+            "0=" => {
+                let a = self.pop()?;
+                self.stack.push(if a == 0 { -1 } else { 0 });
+            }
+            "0<" => {
+                let a = self.pop()?;
+                self.stack.push(if a < 0 { -1 } else { 0 });
+            }
+            "0>" => {
+                let a = self.pop()?;
+                self.stack.push(if a > 0 { -1 } else { 0 });
+            }
+            // Logical
+            "AND" => {
+                self.require("AND", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a & b);
+            }
+            "OR" => {
+                self.require("OR", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a | b);
+            }
+            "XOR" => {
+                self.require("XOR", 2)?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a ^ b);
+            }
+            "INVERT" => {
+                self.require("INVERT", 1)?;
+                let a = self.pop()?;
+                self.stack.push(!a);
+            }
+            "NEGATE" => {
+                let a = self.pop()?;
+                self.stack.push(-a);
+            }
+            "ABS" => {
+                let a = self.pop()?;
+                self.stack.push(a.abs());
+            }
+            "MIN" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a.min(b));
+            }
+            "MAX" => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.stack.push(a.max(b));
+            }
+            "." => {
+                // Print and drop (for GForth compatibility)
+                let val = self.pop()?;
+                let digits = format_radix(val.unsigned_abs(), self.radix()?);
+                let sign = if val < 0 { "-" } else { "" };
+                self.output.push_str(&format!("{}{} ", sign, digits));
+            }
+            "U." => {
+                // ( u -- ) Print as unsigned
+                let val = self.pop()?;
+                let digits = format_radix(val as u64, self.radix()?);
+                self.output.push_str(&format!("{} ", digits));
+            }
+
+            // PRIORITY 1: Memory Operations
+            "!" => {
+                // ( val addr -- ) Store value at address
+                let addr = self.pop()?;
+                let val = self.pop()?;
+                self.memory.insert(addr, val);
+            }
+            "@" => {
+                // ( addr -- val ) Fetch value from address
+                let addr = self.pop()?;
+                let val = *self.memory.get(&addr).unwrap_or(&0);
+                self.stack.push(val);
+            }
+            "+!" => {
+                // ( n addr -- ) Add n to value at addr
+                let addr = self.pop()?;
+                let n = self.pop()?;
+                let current = *self.memory.get(&addr).unwrap_or(&0);
+                self.memory.insert(addr, current + n);
+            }
+
+            // Data space
+            "HERE" => {
+                // ( -- addr )
+                self.stack.push(self.here);
+            }
+            "ALLOT" => {
+                // ( n -- ) A negative count gives space back
+                let n = self.pop()?;
+                self.allot(n)?;
+            }
+            "," => {
+                // ( x -- ) Store a cell at the aligned HERE
+                let x = self.pop()?;
+                self.align();
+                let addr = self.here;
+                self.allot(CELL)?;
+                self.memory.insert(addr, x);
+            }
+            "ALIGN" => {
+                self.align();
+            }
+            "ALIGNED" => {
+                // ( addr -- a-addr )
+                let addr = self.pop()?;
+                self.stack.push(aligned(addr));
+            }
+            "CELLS" => {
+                // ( n -- n*cell )
+                let n = self.pop()?;
+                self.stack.push(n.wrapping_mul(CELL));
+            }
+            "CELL+" => {
+                // ( addr -- addr+cell )
+                let addr = self.pop()?;
+                self.stack.push(addr.wrapping_add(CELL));
+            }
+            "CREATE" => {
+                // ( -- ) The new word pushes the aligned HERE
+                let name = rest.next().ok_or_else(|| {
+                    CompileError::RuntimeError("Missing name after CREATE".to_string())
+                })?;
+                self.align();
+                self.variables.insert(name.to_uppercase(), self.here);
+            }
+
+            // PRIORITY 2: Advanced Stack Operations (Return Stack)
+            ">R" => {
+                // ( n -- ) Move from data stack to return stack
+                let val = self.pop()?;
+                self.push_return(val)?;
+            }
+            "R>" => {
+                // ( -- n ) Move from return stack to data stack
+                let val = self.return_stack.pop().ok_or_else(|| {
+                    exception(RETURN_STACK_UNDERFLOW, "Return stack underflow")
+                })?;
+                self.stack.push(val);
+            }
+            "R@" => {
+                // ( -- n ) Copy from return stack to data stack
+                let val = self.return_stack.last().copied().ok_or_else(|| {
+                    exception(RETURN_STACK_UNDERFLOW, "Return stack underflow")
+                })?;
+                self.stack.push(val);
+            }
+            "2>R" => {
+                // ( n1 n2 -- ) Move two cells to return stack
+                let n2 = self.pop()?;
+                let n1 = self.pop()?;
+                self.push_return(n1)?;
+                self.push_return(n2)?;
+            }
+            "2R>" => {
+                // ( -- n1 n2 ) Move two cells from return stack
+                let n2 = self.return_stack.pop().ok_or_else(|| {
+                    exception(RETURN_STACK_UNDERFLOW, "Return stack underflow")
+                })?;
+                let n1 = self.return_stack.pop().ok_or_else(|| {
+                    exception(RETURN_STACK_UNDERFLOW, "Return stack underflow")
+                })?;
+                self.stack.push(n1);
+                self.stack.push(n2);
+            }
+            "2R@" => {
+                // ( -- n1 n2 ) Copy two cells from return stack
+                if self.return_stack.len() < 2 {
+                    return Err(exception(RETURN_STACK_UNDERFLOW, "Return stack underflow"));
+                }
+                let len = self.return_stack.len();
+                let n1 = self.return_stack[len - 2];
+                let n2 = self.return_stack[len - 1];
+                self.stack.push(n1);
+                self.stack.push(n2);
+            }
+
+            // Definitions and execution tokens
+            ":" => {
+                let name = rest.next().ok_or_else(|| {
+                    CompileError::RuntimeError("Missing name after :".to_string())
+                })?;
+                let body: Vec<String> = rest
+                    .by_ref()
+                    .take_while(|t| **t != ";")
+                    .map(|t| t.to_string())
+                    .collect();
+                let xt = self.definitions.len() as i64;
+                self.definitions.push(body);
+                self.words.insert(name.to_uppercase(), xt);
+            }
+            "'" => {
+                // ( -- xt )
+                let name = rest.next().ok_or_else(|| {
+                    CompileError::RuntimeError("Missing name after '".to_string())
+                })?;
+                let xt = self.tick(name)?;
+                self.stack.push(xt);
+            }
+            "THROW" => {
+                // ( k*x n -- k*x | i*x n ); THROW 0 does nothing
+                let code = self.pop()?;
+                if code != 0 {
+                    return Err(CompileError::Throw(code));
+                }
+            }
+
+            // PRIORITY 4: Base Conversion
+            "BASE" => {
+                // ( -- addr )
+                self.stack.push(BASE_ADDR);
+            }
+            "DECIMAL" => {
+                self.memory.insert(BASE_ADDR, 10);
+            }
+            "HEX" => {
+                self.memory.insert(BASE_ADDR, 16);
+            }
+            "BINARY" => {
+                self.memory.insert(BASE_ADDR, 2);
+            }
+            "OCTAL" => {
+                self.memory.insert(BASE_ADDR, 8);
+            }
+
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Current value of `BASE`, which must be a usable radix
+    fn radix(&self) -> Result<u32> {
+        let base = self.get_memory(BASE_ADDR);
+        u32::try_from(base)
+            .ok()
+            .filter(|radix| (2..=36).contains(radix))
+            .ok_or_else(|| CompileError::RuntimeError(format!("Invalid BASE {}", base)))
+    }
+
+    /// Read `token` as a number in the current base
+    ///
+    /// A token made only of decimal digits that is invalid in the current base
+    /// is a parse error; anything else that fails to parse is a word.
+    fn parse_number(&self, token: &str) -> Result<Option<i64>> {
+        let radix = self.radix()?;
+        if let Ok(n) = i64::from_str_radix(token, radix) {
+            return Ok(Some(n));
+        }
+
+        let digits = token.strip_prefix('-').unwrap_or(token);
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(CompileError::ParseError(ForthError::InvalidNumber {
+                token: token.to_string(),
+                radix,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Move HERE by `n` bytes, failing if it would leave data space
+    fn allot(&mut self, n: i64) -> Result<()> {
+        let end = DATA_SPACE_START + DATA_SPACE_SIZE;
+        match self.here.checked_add(n) {
+            Some(here) if (DATA_SPACE_START..=end).contains(&here) => {
+                self.here = here;
+                Ok(())
+            }
+            _ => Err(exception(
+                DICTIONARY_OVERFLOW,
+                format!(
+                    "Data space overflow: cannot allot {} bytes at HERE {}, {} bytes free",
+                    n,
+                    self.here,
+                    end - self.here
+                ),
+            )),
+        }
+    }
+
+    /// Round HERE up to a cell boundary
+    fn align(&mut self) {
+        self.here = aligned(self.here);
+    }
+
+    /// Execution token for `name`, allocating one for words without a colon
+    /// definition; an undefined word is an exception
+    fn tick(&mut self, name: &str) -> Result<i64> {
+        let upper = name.to_uppercase();
+        if let Some(&xt) = self.words.get(&upper) {
+            return Ok(xt);
+        }
+        let defined = BUILTIN_WORDS.contains(&upper.as_str())
+            || self.variables.contains_key(&upper)
+            || self.constants.contains_key(&upper)
+            || self.values.contains_key(&upper);
+        if !defined {
+            return Err(exception(UNDEFINED_WORD, format!("Undefined word: {}", name)));
+        }
+        self.definitions.push(vec![name.to_string()]);
+        Ok(self.definitions.len() as i64 - 1)
+    }
+
+    /// Run the word behind an execution token
+    fn execute(&mut self, xt: i64) -> Result<()> {
+        let body = usize::try_from(xt)
+            .ok()
+            .and_then(|index| self.definitions.get(index))
+            .cloned()
+            .ok_or_else(|| CompileError::RuntimeError(format!("Invalid execution token {}", xt)))?;
+        let tokens: Vec<&str> = body.iter().map(String::as_str).collect();
+
+        self.check_return_space()?;
+        self.calls += 1;
+        let result = self.interpret(&tokens);
+        self.calls -= 1;
+        result
+    }
+
+    /// Move `value` to the return stack
+    fn push_return(&mut self, value: i64) -> Result<()> {
+        self.check_return_space()?;
+        self.return_stack.push(value);
+        Ok(())
+    }
+
+    /// Fail with a return stack overflow unless there is room for another cell,
+    /// so runaway recursion is an exception rather than a crash
+    fn check_return_space(&self) -> Result<()> {
+        if self.calls + self.return_stack.len() >= RETURN_STACK_SIZE {
+            return Err(exception(RETURN_STACK_OVERFLOW, "Return stack overflow"));
+        }
+        Ok(())
+    }
+
+    /// Run `xt`, pushing 0 on success or the exception code after restoring
+    /// the stack depths seen on entry
+    fn catch(&mut self, xt: i64) -> Result<()> {
+        let depth = self.stack.len();
+        let return_depth = self.return_stack.len();
+
+        match self.execute(xt) {
+            Ok(()) => self.stack.push(0),
+            Err(err) => {
+                let code = Self::throw_code(&err).ok_or(err)?;
+                // The contents of restored cells are unspecified; missing ones read as 0
+                self.stack.resize(depth, 0);
+                self.return_stack.truncate(return_depth);
+                self.stack.push(code);
+            }
+        }
+        Ok(())
+    }
+
+    /// ANS Forth exception code for an error that `CATCH` can handle
+    fn throw_code(err: &CompileError) -> Option<i64> {
+        match err {
+            CompileError::Throw(code) | CompileError::Exception { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Evaluate Forth code and pop the value it leaves on top of the stack
+    ///
+    /// Returns `None` if the stack is empty afterwards. Only the top item is
+    /// removed; anything beneath it stays for later evaluations.
+    ///
+    /// ```
+    /// use fastforth::ForthEngine;
+    ///
+    /// let mut engine = ForthEngine::new();
+    /// assert_eq!(engine.eval_and_pop("2 3 +").unwrap(), Some(5));
+    /// assert_eq!(engine.eval_and_pop("").unwrap(), None);
+    /// ```
+    pub fn eval_and_pop(&mut self, code: &str) -> Result<Option<i64>> {
+        self.eval(code)?;
+
+        if self.stack.len() > 1 {
+            tracing::warn!("eval_and_pop leaves {} items on the stack", self.stack.len() - 1);
+        }
+
+        Ok(self.stack.pop())
+    }
+
     /// Get the current stack
     pub fn stack(&self) -> &[i64] {
         &self.stack
@@ -384,9 +773,13 @@ impl ForthEngine {
 
     /// Define a variable
     /// Returns the address of the variable
+    ///
+    /// The cell is taken from data space like `CREATE name 0 ,`, without
+    /// checking for overflow.
     pub fn define_variable(&mut self, name: &str) -> i64 {
-        let addr = self.next_addr;
-        self.next_addr += 8; // Increment by cell size
+        self.align();
+        let addr = self.here;
+        self.here += CELL;
         self.variables.insert(name.to_uppercase(), addr);
         self.memory.insert(addr, 0); // Initialize to zero
         addr
@@ -436,17 +829,55 @@ impl ForthEngine {
 
     fn pop(&mut self) -> Result<i64> {
         self.stack.pop().ok_or_else(|| {
-            crate::error::CompileError::RuntimeError("Stack underflow".to_string())
+            exception(STACK_UNDERFLOW, "Stack underflow")
         })
     }
 
+    /// Fail with a descriptive underflow error unless `word` has `needed` items available
+    fn require(&self, word: &str, needed: usize) -> Result<()> {
+        if self.stack.len() < needed {
+            return Err(exception(
+                STACK_UNDERFLOW,
+                format!("Stack underflow in {}: expected {} items, found {}", word, needed, self.stack.len()),
+            ));
+        }
+        Ok(())
+    }
+
     fn peek(&self) -> Result<i64> {
         self.stack.last().copied().ok_or_else(|| {
-            crate::error::CompileError::RuntimeError("Stack underflow".to_string())
+            exception(STACK_UNDERFLOW, "Stack underflow")
         })
     }
 }
 
+/// Error for a condition that `CATCH` handles with the exception `code`
+fn exception(code: i64, message: impl Into<String>) -> CompileError {
+    CompileError::Exception {
+        code,
+        message: message.into(),
+    }
+}
+
+/// `addr` rounded up to a cell boundary
+fn aligned(addr: i64) -> i64 {
+    addr.wrapping_add(CELL - 1) & !(CELL - 1)
+}
+
+/// Digits of `value` in `radix`, most significant first
+fn format_radix(mut value: u64, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        let digit = (value % radix as u64) as u32;
+        digits.push(std::char::from_digit(digit, radix).unwrap().to_ascii_uppercase());
+        value /= radix as u64;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
 impl Default for ForthEngine {
     fn default() -> Self {
         Self::new()
@@ -485,4 +916,322 @@ mod tests {
         engine.eval("5 DUP").unwrap();
         assert_eq!(engine.stack(), &[5, 5]);
     }
+
+    #[test]
+    fn test_over_and_rot() {
+        let mut engine = ForthEngine::new();
+        engine.eval("5 10 OVER").unwrap();
+        assert_eq!(engine.stack(), &[5, 10, 5]);
+
+        engine.clear_stack();
+        engine.eval("1 2 3 ROT").unwrap();
+        assert_eq!(engine.stack(), &[2, 3, 1]);
+
+        engine.clear_stack();
+        engine.eval("1 2 3 -rot").unwrap();
+        assert_eq!(engine.stack(), &[3, 1, 2]);
+    }
+
+    #[test]
+    fn test_nip_and_tuck() {
+        let mut engine = ForthEngine::new();
+        engine.eval("5 10 NIP").unwrap();
+        assert_eq!(engine.stack(), &[10]);
+
+        engine.clear_stack();
+        engine.eval("5 10 TUCK").unwrap();
+        assert_eq!(engine.stack(), &[10, 5, 10]);
+    }
+
+    #[test]
+    fn test_shuffle_underflow_reports_word() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("1 ROT").unwrap_err().to_string();
+        assert!(err.contains("ROT: expected 3 items, found 1"), "{}", err);
+
+        // Nothing is consumed when the check fails
+        assert_eq!(engine.stack(), &[1]);
+        assert!(engine.eval("TUCK").unwrap_err().to_string().contains("TUCK: expected 2 items"));
+    }
+
+    #[test]
+    fn test_comparisons_push_forth_flags() {
+        let mut engine = ForthEngine::new();
+        engine.eval("5 5 = 5 6 = 5 6 <> -1 0 < 1 -1 > 3 3 <= 2 3 >=").unwrap();
+        assert_eq!(engine.stack(), &[-1, 0, -1, -1, -1, -1, 0]);
+    }
+
+    #[test]
+    fn test_logical_words_are_bitwise() {
+        let mut engine = ForthEngine::new();
+        engine.eval("12 10 AND 12 10 OR 12 10 XOR 0 INVERT 5 INVERT").unwrap();
+        assert_eq!(engine.stack(), &[8, 14, 6, -1, -6]);
+    }
+
+    #[test]
+    fn test_comparison_underflow() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("1 <>").unwrap_err().to_string();
+        assert!(err.contains("<>: expected 2 items, found 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_depth_pick_roll() {
+        let mut engine = ForthEngine::new();
+        engine.eval("DEPTH 10 20 DEPTH").unwrap();
+        assert_eq!(engine.stack(), &[0, 10, 20, 3]);
+
+        engine.clear_stack();
+        engine.eval("10 20 30 2 PICK").unwrap();
+        assert_eq!(engine.stack(), &[10, 20, 30, 10]);
+
+        engine.clear_stack();
+        engine.eval("10 20 30 2 ROLL").unwrap();
+        assert_eq!(engine.stack(), &[20, 30, 10]);
+    }
+
+    #[test]
+    fn test_pick_roll_degenerate_cases() {
+        let mut engine = ForthEngine::new();
+        engine.eval("1 2 0 PICK 1 2 1 ROLL").unwrap();
+        assert_eq!(engine.stack(), &[1, 2, 2, 2, 1]);
+
+        // 0 ROLL is a no-op
+        engine.clear_stack();
+        engine.eval("7 0 ROLL").unwrap();
+        assert_eq!(engine.stack(), &[7]);
+    }
+
+    #[test]
+    fn test_pick_roll_out_of_range() {
+        let mut engine = ForthEngine::new();
+        assert!(engine.eval("1 2 2 PICK").is_err());
+        assert_eq!(engine.stack(), &[1, 2, 2]);
+
+        engine.clear_stack();
+        assert!(engine.eval("1 2 -1 ROLL").is_err());
+        assert!(engine.eval("PICK").is_err());
+    }
+
+    #[test]
+    fn test_double_cell_shuffles() {
+        let mut engine = ForthEngine::new();
+        engine.eval("1 2 2DUP").unwrap();
+        assert_eq!(engine.stack(), &[1, 2, 1, 2]);
+
+        engine.eval("2DROP 3 4 2SWAP").unwrap();
+        assert_eq!(engine.stack(), &[3, 4, 1, 2]);
+
+        engine.eval("2OVER").unwrap();
+        assert_eq!(engine.stack(), &[3, 4, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_double_cell_underflow() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("1 2 3 2OVER").unwrap_err().to_string();
+        assert!(err.contains("2OVER: expected 4 items, found 3"), "{}", err);
+
+        // A failed 2DROP leaves the stack untouched
+        engine.clear_stack();
+        assert!(engine.eval("1 2DROP").is_err());
+        assert_eq!(engine.stack(), &[1]);
+    }
+
+    #[test]
+    fn test_eval_and_pop_keeps_rest_of_stack() {
+        let mut engine = ForthEngine::new();
+        assert_eq!(engine.eval_and_pop("1 2 3").unwrap(), Some(3));
+        assert_eq!(engine.stack(), &[1, 2]);
+
+        assert_eq!(engine.eval_and_pop("+").unwrap(), Some(3));
+        assert_eq!(engine.eval_and_pop("").unwrap(), None);
+    }
+
+    #[test]
+    fn test_colon_definition_and_execute() {
+        let mut engine = ForthEngine::new();
+        engine.eval(": SQUARE DUP * ; 4 SQUARE").unwrap();
+        assert_eq!(engine.stack(), &[16]);
+
+        engine.eval("' SQUARE EXECUTE ' NEGATE EXECUTE").unwrap();
+        assert_eq!(engine.stack(), &[-256]);
+    }
+
+    #[test]
+    fn test_nested_catch() {
+        let mut engine = ForthEngine::new();
+        engine.eval(": INNER 1 2 -3 THROW ; : OUTER 10 ' INNER CATCH 20 -7 THROW ;").unwrap();
+        engine.eval("' OUTER CATCH").unwrap();
+
+        // The inner CATCH handled -3; the outer one unwinds past it with -7
+        assert_eq!(engine.stack(), &[-7]);
+    }
+
+    #[test]
+    fn test_catch_restores_return_stack() {
+        let mut engine = ForthEngine::new();
+        engine.eval("1 >R : LEAKY 5 >R 6 >R -1 THROW ; ' LEAKY CATCH").unwrap();
+        assert_eq!(engine.stack(), &[-1]);
+        assert_eq!(engine.return_stack(), &[1]);
+    }
+
+    #[test]
+    fn test_uncaught_throw() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("42 THROW").unwrap_err();
+        assert!(matches!(err, CompileError::Throw(42)));
+
+        // Underflow inside CATCH is reported with its ANS code
+        engine.clear_stack();
+        engine.eval(": BAD DROP ; ' BAD CATCH").unwrap();
+        assert_eq!(engine.stack(), &[-4]);
+    }
+
+    #[test]
+    fn test_runaway_recursion_is_caught() {
+        let mut engine = ForthEngine::new();
+        engine.eval(": F F ; ' F CATCH").unwrap();
+        assert_eq!(engine.stack(), &[-5]);
+
+        // Cells moved with >R share the same space
+        engine.clear_stack();
+        engine.eval(": H 1 >R H ; ' H CATCH").unwrap();
+        assert_eq!(engine.stack(), &[-5]);
+        assert!(engine.return_stack().is_empty());
+
+        let err = engine.eval(": G G ; G").unwrap_err();
+        assert!(matches!(err, CompileError::Exception { code: -5, .. }));
+    }
+
+    #[test]
+    fn test_tick_undefined_word() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("' NOSUCHWORD").unwrap_err();
+        assert!(matches!(&err, CompileError::Exception { code: -13, message } if message.contains("NOSUCHWORD")));
+
+        // Which CATCH handles like any other exception
+        engine.eval(": T ' NOSUCHWORD ; ' T CATCH").unwrap();
+        assert_eq!(engine.stack(), &[-13]);
+
+        // Built-ins still have tokens
+        engine.clear_stack();
+        engine.eval("3 4 ' + EXECUTE").unwrap();
+        assert_eq!(engine.stack(), &[7]);
+    }
+
+    #[test]
+    fn test_builtin_words_are_recognized() {
+        for &name in BUILTIN_WORDS.iter().filter(|&&name| !matches!(name, "EXECUTE" | "CATCH")) {
+            let mut engine = ForthEngine::new();
+            let recognized = engine.builtin(name, &mut [].iter());
+            assert!(!matches!(recognized, Ok(false)), "{} is not a built-in", name);
+        }
+    }
+
+    #[test]
+    fn test_here_and_allot() {
+        let mut engine = ForthEngine::new();
+        engine.eval("HERE 100 ALLOT HERE SWAP -").unwrap();
+        assert_eq!(engine.stack(), &[100]);
+
+        // A negative count gives the space back
+        engine.clear_stack();
+        engine.eval("HERE -100 ALLOT HERE -").unwrap();
+        assert_eq!(engine.stack(), &[100]);
+    }
+
+    #[test]
+    fn test_comma_builds_table() {
+        let mut engine = ForthEngine::new();
+        engine.eval("CREATE PRIMES 2 , 3 , 5 , 7 ,").unwrap();
+        engine.eval("PRIMES @ PRIMES CELL+ @ PRIMES 3 CELLS + @").unwrap();
+        assert_eq!(engine.stack(), &[2, 3, 7]);
+
+        engine.clear_stack();
+        engine.eval("HERE PRIMES -").unwrap();
+        assert_eq!(engine.stack(), &[32]);
+    }
+
+    #[test]
+    fn test_comma_and_create_align() {
+        let mut engine = ForthEngine::new();
+        engine.eval("3 ALLOT HERE ALIGNED HERE - 42 , HERE").unwrap();
+        let here = engine.stack()[1];
+        assert_eq!(engine.stack()[0], 5);
+        assert_eq!(here % 8, 0);
+        assert_eq!(engine.get_memory(here - 8), 42);
+
+        engine.clear_stack();
+        engine.eval("1 ALLOT CREATE BUF BUF 8 MOD").unwrap();
+        assert_eq!(engine.stack(), &[0]);
+    }
+
+    #[test]
+    fn test_data_space_overflow() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("1000000 ALLOT").unwrap_err().to_string();
+        assert!(err.contains("Data space overflow"), "{}", err);
+        assert!(engine.eval("-8 ALLOT").is_err());
+
+        // ANS code -8 is dictionary overflow
+        engine.clear_stack();
+        engine.eval(": GROW 1000000 ALLOT ; ' GROW CATCH").unwrap();
+        assert_eq!(engine.stack(), &[-8]);
+    }
+
+    #[test]
+    fn test_base_switching() {
+        let mut engine = ForthEngine::new();
+        engine.eval("BASE @ HEX ff -10 DECIMAL 10").unwrap();
+        assert_eq!(engine.stack(), &[10, 255, -16, 10]);
+
+        engine.clear_stack();
+        engine.eval("3 BASE ! 21 DECIMAL").unwrap();
+        assert_eq!(engine.stack(), &[7]);
+    }
+
+    #[test]
+    fn test_numeric_output_uses_base() {
+        let mut engine = ForthEngine::new();
+        engine.eval("HEX ff . -ff . -1 U. DECIMAL").unwrap();
+        assert_eq!(engine.take_output(), "FF -FF FFFFFFFFFFFFFFFF ");
+
+        let min = i64::MIN.to_string();
+        engine.eval(&format!("{} . BINARY 101 .", min)).unwrap();
+        assert_eq!(engine.take_output(), format!("{} 101 ", min));
+    }
+
+    #[test]
+    fn test_word_restores_base() {
+        let mut engine = ForthEngine::new();
+        engine.eval(": H. BASE @ SWAP HEX . BASE ! ; 255 H. 255 .").unwrap();
+        assert_eq!(engine.take_output(), "FF 255 ");
+    }
+
+    #[test]
+    fn test_words_shadow_numbers() {
+        let mut engine = ForthEngine::new();
+        engine.eval(": ADD + ; HEX 1 2 ADD 10 ADD DECIMAL").unwrap();
+        assert_eq!(engine.stack(), &[0x13]);
+
+        engine.clear_stack();
+        engine.define_constant("BEEF", 5);
+        engine.eval("HEX BEEF DECIMAL").unwrap();
+        assert_eq!(engine.stack(), &[5]);
+    }
+
+    #[test]
+    fn test_invalid_digit_for_base() {
+        let mut engine = ForthEngine::new();
+        let err = engine.eval("OCTAL 19").unwrap_err();
+        assert!(matches!(err, CompileError::ParseError(_)));
+
+        // Words that start with a digit are still words
+        engine.eval("DECIMAL 1 2 2DUP").unwrap();
+        assert_eq!(engine.stack(), &[1, 2, 1, 2]);
+
+        let err = engine.eval("1 BASE ! 1").unwrap_err();
+        assert!(matches!(err, CompileError::RuntimeError(_)));
+    }
 }
@@ -1,5 +1,7 @@
 //! Error types for the Fast Forth compiler
 
+use fastforth_frontend::ast::SourceLocation;
+use fastforth_frontend::ForthError;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -11,11 +13,11 @@ pub type Result<T> = std::result::Result<T, CompileError>;
 pub enum CompileError {
     /// Frontend parsing error
     #[error("Parse error: {0}")]
-    ParseError(String),
+    ParseError(ForthError),
 
     /// Semantic analysis error
     #[error("Semantic error: {0}")]
-    SemanticError(String),
+    SemanticError(ForthError),
 
     /// Type inference error
     #[error("Type error: {0}")]
@@ -23,7 +25,7 @@ pub enum CompileError {
 
     /// SSA conversion error
     #[error("SSA conversion error: {0}")]
-    SSAError(String),
+    SSAError(ForthError),
 
     /// Optimization error
     #[error("Optimization error: {0}")]
@@ -49,14 +51,35 @@ pub enum CompileError {
     #[error("Runtime error: {0}")]
     RuntimeError(String),
 
+    /// Runtime error with the ANS Forth exception code `CATCH` handles it with
+    #[error("Runtime error: {message}")]
+    Exception { code: i64, message: String },
+
+    /// Exception raised by `THROW` that no `CATCH` handled
+    #[error("Uncaught exception {0}")]
+    Throw(i64),
+
     /// Internal compiler error
     #[error("Internal compiler error: {0}")]
     InternalError(String),
 }
 
-impl From<fastforth_frontend::ForthError> for CompileError {
-    fn from(err: fastforth_frontend::ForthError) -> Self {
-        CompileError::ParseError(err.to_string())
+impl CompileError {
+    /// Where in the source the error was found
+    ///
+    /// `None` for errors from later stages, such as the backend, that no
+    /// longer know which source text they came from.
+    pub fn span(&self) -> Option<SourceLocation> {
+        match self {
+            CompileError::ParseError(err) | CompileError::SemanticError(err) | CompileError::SSAError(err) => err.span(),
+            _ => None,
+        }
+    }
+}
+
+impl From<ForthError> for CompileError {
+    fn from(err: ForthError) -> Self {
+        CompileError::ParseError(err)
     }
 }
 
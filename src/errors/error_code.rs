@@ -7,6 +7,7 @@
 //! - E3000-E3999: Control flow errors
 //! - E4000-E4999: Optimization errors
 //! - E5000-E5999: Code generation errors
+//! - E6000-E6999: Runtime errors
 //! - E9000-E9999: Internal compiler errors
 
 use serde::{Serialize, Deserialize};
@@ -61,6 +62,9 @@ pub enum ErrorCode {
     LLVMError = 5001,
     LinkingError = 5002,
 
+    // Runtime Errors (E6000-E6999)
+    UncaughtException = 6000,
+
     // Internal Errors (E9000-E9999)
     InternalCompilerError = 9000,
     SSAConversionError = 9001,
@@ -83,6 +87,7 @@ impl ErrorCode {
             3000..=3999 => "Control Flow",
             4000..=4999 => "Optimization",
             5000..=5999 => "Code Generation",
+            6000..=6999 => "Runtime",
             9000..=9999 => "Internal",
             _ => "Unknown",
         }
@@ -131,6 +136,8 @@ impl ErrorCode {
             ErrorCode::LLVMError => "LLVM backend error",
             ErrorCode::LinkingError => "Linking error",
 
+            ErrorCode::UncaughtException => "Exception that no CATCH handled",
+
             ErrorCode::InternalCompilerError => "Internal compiler error",
             ErrorCode::SSAConversionError => "SSA conversion error",
             ErrorCode::UnexpectedState => "Unexpected compiler state",
@@ -208,6 +215,9 @@ impl ErrorCodeRegistry {
             ErrorCode::LLVMError,
             ErrorCode::LinkingError,
 
+            // Runtime
+            ErrorCode::UncaughtException,
+
             // Internal
             ErrorCode::InternalCompilerError,
             ErrorCode::SSAConversionError,
@@ -233,6 +243,7 @@ mod tests {
     fn test_error_category() {
         assert_eq!(ErrorCode::StackDepthMismatch.category(), "Stack Effects");
         assert_eq!(ErrorCode::UndefinedWord.category(), "Semantic");
+        assert_eq!(ErrorCode::UncaughtException.category(), "Runtime");
     }
 
     #[test]
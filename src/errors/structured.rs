@@ -191,20 +191,26 @@ pub fn convert_to_structured(
     suggest_fixes: bool,
 ) -> StructuredError {
     use crate::error::CompileError;
+    use fastforth_frontend::ForthError;
+
+    let span = error.span().map(|span| Location::new(span.line, span.column));
 
     match error {
-        CompileError::ParseError(msg) => {
-            StructuredError::new(ErrorCode::UnexpectedToken, msg)
-                .with_location(Location::new(0, 0))
+        CompileError::ParseError(err) => {
+            StructuredError::new(ErrorCode::UnexpectedToken, err.to_string())
+                .with_location(span.unwrap_or_else(|| Location::new(0, 0)))
         }
 
-        CompileError::SemanticError(msg) => {
-            if msg.contains("undefined") {
-                StructuredError::new(ErrorCode::UndefinedWord, msg)
-            } else if msg.contains("redefined") {
-                StructuredError::new(ErrorCode::RedefinedWord, msg)
-            } else {
-                StructuredError::new(ErrorCode::InternalCompilerError, msg)
+        CompileError::SemanticError(err) => {
+            let code = match err {
+                ForthError::UndefinedWord { .. } => ErrorCode::UndefinedWord,
+                ForthError::RedefinitionError { .. } => ErrorCode::RedefinedWord,
+                _ => ErrorCode::InternalCompilerError,
+            };
+            let structured = StructuredError::new(code, err.to_string());
+            match span {
+                Some(location) => structured.with_location(location),
+                None => structured,
             }
         }
 
@@ -230,8 +236,8 @@ pub fn convert_to_structured(
             err
         }
 
-        CompileError::SSAError(msg) => {
-            StructuredError::new(ErrorCode::SSAConversionError, msg)
+        CompileError::SSAError(err) => {
+            StructuredError::new(ErrorCode::SSAConversionError, err.to_string())
         }
 
         CompileError::OptimizationError(msg) => {
@@ -257,6 +263,14 @@ pub fn convert_to_structured(
             StructuredError::new(ErrorCode::InternalCompilerError, msg)
         }
 
+        CompileError::Exception { message, .. } => {
+            StructuredError::new(ErrorCode::UncaughtException, message)
+        }
+
+        CompileError::Throw(code) => {
+            StructuredError::new(ErrorCode::UncaughtException, format!("Uncaught exception {}", code))
+        }
+
         CompileError::InternalError(msg) => {
             StructuredError::new(ErrorCode::InternalCompilerError, msg)
         }
@@ -302,4 +316,11 @@ mod tests {
         assert_eq!(suggestion.confidence, 0.9);
         assert_eq!(suggestion.pattern, Some("DROP_EXCESS_001".to_string()));
     }
+
+    #[test]
+    fn test_uncaught_exception_is_runtime_error() {
+        let error = convert_to_structured(&crate::error::CompileError::Throw(42), false);
+        assert_eq!(error.code, "E6000");
+        assert_eq!(error.error, "Uncaught exception 42");
+    }
 }
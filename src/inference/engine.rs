@@ -1,6 +1,7 @@
 //! Core inference engine for stack effect analysis
 
 use super::types::{StackEffect, StackType};
+use fastforth_frontend::ast::Word;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
@@ -52,69 +53,34 @@ impl InferenceEngine {
             ),
         );
 
-        // Stack manipulation
-        builtins.insert(
-            "dup".to_string(),
-            StackEffect::new(
-                vec![StackType::Unknown],
-                vec![StackType::Unknown, StackType::Unknown],
-            ),
-        );
-        builtins.insert(
-            "drop".to_string(),
-            StackEffect::new(vec![StackType::Unknown], vec![]),
-        );
-        builtins.insert(
-            "swap".to_string(),
-            StackEffect::new(
-                vec![StackType::Unknown, StackType::Unknown],
-                vec![StackType::Unknown, StackType::Unknown],
-            ),
-        );
-        builtins.insert(
-            "over".to_string(),
-            StackEffect::new(
-                vec![StackType::Unknown, StackType::Unknown],
-                vec![
-                    StackType::Unknown,
-                    StackType::Unknown,
-                    StackType::Unknown,
-                ],
-            ),
-        );
-        builtins.insert(
-            "rot".to_string(),
-            StackEffect::new(
-                vec![StackType::Unknown, StackType::Unknown, StackType::Unknown],
-                vec![
-                    StackType::Unknown,
-                    StackType::Unknown,
-                    StackType::Unknown,
-                ],
-            ),
-        );
-        builtins.insert(
-            "2dup".to_string(),
-            StackEffect::new(
-                vec![StackType::Unknown, StackType::Unknown],
-                vec![
-                    StackType::Unknown,
-                    StackType::Unknown,
-                    StackType::Unknown,
-                    StackType::Unknown,
-                ],
-            ),
-        );
-        builtins.insert(
-            "2drop".to_string(),
-            StackEffect::new(
-                vec![StackType::Unknown, StackType::Unknown],
-                vec![],
-            ),
-        );
+        // Stack manipulation (polymorphic over any cell type)
+        let shuffles: &[(&str, &[&str], &[&str])] = &[
+            ("dup", &["a"], &["a", "a"]),
+            ("drop", &["a"], &[]),
+            ("swap", &["a", "b"], &["b", "a"]),
+            ("over", &["a", "b"], &["a", "b", "a"]),
+            ("rot", &["a", "b", "c"], &["b", "c", "a"]),
+            ("-rot", &["a", "b", "c"], &["c", "a", "b"]),
+            ("nip", &["a", "b"], &["b"]),
+            ("tuck", &["a", "b"], &["b", "a", "b"]),
+            ("2dup", &["a", "b"], &["a", "b", "a", "b"]),
+            ("2drop", &["a", "b"], &[]),
+            ("2swap", &["a", "b", "c", "d"], &["c", "d", "a", "b"]),
+            ("2over", &["a", "b", "c", "d"], &["a", "b", "c", "d", "a", "b"]),
+        ];
+        let vars = |names: &[&str]| names.iter().map(|n| StackType::var(*n)).collect();
+        for (name, inputs, outputs) in shuffles {
+            builtins.insert(name.to_string(), StackEffect::new(vars(inputs), vars(outputs)));
+        }
+
+        // Return stack transfers
+        let a = || vec![StackType::var("a")];
+        builtins.insert(">r".to_string(), StackEffect::new(a(), vec![]).with_return(vec![], a()));
+        builtins.insert("r>".to_string(), StackEffect::new(vec![], a()).with_return(a(), vec![]));
+        builtins.insert("r@".to_string(), StackEffect::new(vec![], a()).with_return(a(), a()));
 
         // Comparison operations
-        for op in &["<", ">", "=", "<=", ">=", "<>"] {
+        for op in &["<", ">", "=", "<=", ">=", "<>", "u<", "u>"] {
             builtins.insert(
                 op.to_string(),
                 StackEffect::new(
@@ -125,7 +91,7 @@ impl InferenceEngine {
         }
 
         // Logical operations
-        for op in &["and", "or"] {
+        for op in &["and", "or", "xor"] {
             builtins.insert(
                 op.to_string(),
                 StackEffect::new(
@@ -143,6 +109,17 @@ impl InferenceEngine {
             StackEffect::new(vec![StackType::Int], vec![StackType::Int]),
         );
 
+        // Shifts
+        for op in &["lshift", "rshift", "arshift"] {
+            builtins.insert(
+                op.to_string(),
+                StackEffect::new(
+                    vec![StackType::Int, StackType::Int],
+                    vec![StackType::Int],
+                ),
+            );
+        }
+
         // Unary operations
         for op in &["negate", "abs", "1+", "1-", "2*", "2/"] {
             builtins.insert(
@@ -167,11 +144,27 @@ impl InferenceEngine {
             ".".to_string(),
             StackEffect::new(vec![StackType::Int], vec![]),
         );
+        builtins.insert(
+            "u.".to_string(),
+            StackEffect::new(vec![StackType::Int], vec![]),
+        );
+        builtins.insert(
+            ".r".to_string(),
+            StackEffect::new(vec![StackType::Int, StackType::Int], vec![]),
+        );
         builtins.insert(
             "emit".to_string(),
             StackEffect::new(vec![StackType::Char], vec![]),
         );
         builtins.insert("cr".to_string(), StackEffect::new(vec![], vec![]));
+        builtins.insert(
+            "type".to_string(),
+            StackEffect::new(vec![StackType::Addr, StackType::Int], vec![]),
+        );
+        builtins.insert(
+            "count".to_string(),
+            StackEffect::new(vec![StackType::Addr], vec![StackType::Addr, StackType::Int]),
+        );
 
         // Memory operations
         builtins.insert(
@@ -182,6 +175,10 @@ impl InferenceEngine {
             "!".to_string(),
             StackEffect::new(vec![StackType::Int, StackType::Addr], vec![]),
         );
+        builtins.insert(
+            ",".to_string(),
+            StackEffect::new(vec![StackType::Int], vec![]),
+        );
         builtins.insert(
             "c@".to_string(),
             StackEffect::new(vec![StackType::Addr], vec![StackType::Char]),
@@ -195,15 +192,53 @@ impl InferenceEngine {
     }
 
     /// Infer stack effect from code string
+    ///
+    /// Colon definitions in the code are checked on their own and may be
+    /// used by the code that follows them. A definition must leave the return
+    /// stack balanced.
     pub fn infer(&self, code: &str) -> Result<InferResult, String> {
         let words = self.tokenize(code);
         let mut operations = Vec::new();
         let mut total_effect = StackEffect::identity();
+        let mut definitions: FxHashMap<String, StackEffect> = FxHashMap::default();
+        let mut current: Option<(String, StackEffect)> = None;
+
+        let mut words = words.into_iter();
+        while let Some(word) = words.next() {
+            match word.as_str() {
+                ":" => {
+                    if let Some((name, _)) = &current {
+                        return Err(format!("Nested definition inside '{}'", name));
+                    }
+                    let name = words.next().ok_or("Missing name after ':'")?;
+                    current = Some((name, StackEffect::identity()));
+                }
+                ";" => {
+                    let (name, body) = current.take().ok_or("';' without matching ':'")?;
+                    if !body.return_balanced() {
+                        return Err(format!(
+                            "Unbalanced return stack in definition of '{}': {}",
+                            name, body
+                        ));
+                    }
+                    definitions.insert(name, body);
+                }
+                _ => {
+                    let effect = match definitions.get(&word) {
+                        Some(effect) => effect.clone(),
+                        None => self.infer_word(&word)?,
+                    };
+                    match &mut current {
+                        Some((_, body)) => *body = body.compose(&effect)?,
+                        None => total_effect = total_effect.compose(&effect)?,
+                    }
+                    operations.push(word);
+                }
+            }
+        }
 
-        for word in words {
-            let effect = self.infer_word(&word)?;
-            total_effect = total_effect.compose(&effect)?;
-            operations.push(word);
+        if let Some((name, _)) = current {
+            return Err(format!("Unterminated definition of '{}'", name));
         }
 
         Ok(InferResult {
@@ -213,24 +248,116 @@ impl InferenceEngine {
         })
     }
 
-    /// Parse a stack effect string like "( n -- n² )"
+    /// Infer the stack effect of a parsed definition body
+    ///
+    /// Both branches of an `IF` must have the same effect, and loop bodies
+    /// must leave the stack depth unchanged from one iteration to the next.
+    pub fn infer_body(&self, words: &[Word]) -> Result<StackEffect, String> {
+        let flag = || StackEffect::new(vec![StackType::Bool], vec![]);
+        let mut total = StackEffect::identity();
+
+        for word in words {
+            let effect = match word {
+                Word::IntLiteral(_) => StackEffect::new(vec![], vec![StackType::Int]),
+                Word::FloatLiteral(_) => StackEffect::new(vec![], vec![StackType::Float]),
+                Word::StringLiteral(_) => {
+                    StackEffect::new(vec![], vec![StackType::Addr, StackType::Int])
+                }
+                Word::WordRef { name, .. } => self.infer_word(name)?,
+                Word::If { then_branch, else_branch } => {
+                    let then_effect = self.infer_body(then_branch)?;
+                    let else_effect = match else_branch {
+                        Some(branch) => self.infer_body(branch)?,
+                        None => StackEffect::identity(),
+                    };
+                    if !then_effect.compatible_with(&else_effect) {
+                        return Err(format!(
+                            "IF branches disagree: {} vs {}",
+                            then_effect, else_effect
+                        ));
+                    }
+                    flag().compose(&then_effect)?
+                }
+                Word::BeginUntil { body } => {
+                    let effect = self.infer_body(body)?.compose(&flag())?;
+                    Self::check_loop_balanced("BEGIN...UNTIL", &effect)?;
+                    effect
+                }
+                Word::BeginWhileRepeat { condition, body } => {
+                    let exit = self.infer_body(condition)?.compose(&flag())?;
+                    let iteration = exit.compose(&self.infer_body(body)?)?;
+                    Self::check_loop_balanced("BEGIN...WHILE...REPEAT", &iteration)?;
+                    exit
+                }
+                Word::DoLoop { body, .. } => {
+                    let effect = self.infer_body(body)?;
+                    Self::check_loop_balanced("DO...LOOP", &effect)?;
+                    StackEffect::new(vec![StackType::Int, StackType::Int], vec![]).compose(&effect)?
+                }
+                Word::To { .. } => StackEffect::new(vec![StackType::Int], vec![]),
+                Word::Tick { .. } => StackEffect::new(vec![], vec![StackType::Int]),
+                // DOES> code runs when an instance is used, not here
+                Word::Variable { .. }
+                | Word::Constant { .. }
+                | Word::Value { .. }
+                | Word::Create { .. }
+                | Word::Does { .. }
+                | Word::Instantiate { .. }
+                | Word::Comment(_) => StackEffect::identity(),
+            };
+            total = total.compose(&effect)?;
+        }
+
+        Ok(total)
+    }
+
+    fn check_loop_balanced(construct: &str, iteration: &StackEffect) -> Result<(), String> {
+        if iteration.depth_delta() != 0 {
+            return Err(format!(
+                "{} body changes the stack depth by {} per iteration: {}",
+                construct,
+                iteration.depth_delta(),
+                iteration
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse a stack effect string like "( n -- n² )", optionally followed
+    /// by a return-stack effect as in "( n -- )( R: -- n )"
     pub fn parse_effect(&self, effect_str: &str) -> Result<StackEffect, String> {
         let trimmed = effect_str.trim();
         if !trimmed.starts_with('(') || !trimmed.ends_with(')') {
             return Err("Stack effect must be in format: ( inputs -- outputs )".to_string());
         }
 
-        let inner = &trimmed[1..trimmed.len() - 1];
+        let data_end = trimmed.find(')').unwrap_or(trimmed.len() - 1);
+        let (inputs, outputs) = self.parse_stack_part(&trimmed[1..data_end])?;
+        let effect = StackEffect::new(inputs, outputs);
+
+        let rest = trimmed[data_end + 1..].trim();
+        if rest.is_empty() {
+            return Ok(effect);
+        }
+
+        let return_part = rest
+            .strip_prefix('(')
+            .and_then(|r| r.strip_suffix(')'))
+            .and_then(|r| r.trim_start().strip_prefix("R:"))
+            .ok_or("Return stack effect must be in format: ( R: inputs -- outputs )")?;
+        let (return_inputs, return_outputs) = self.parse_stack_part(return_part)?;
+
+        Ok(effect.with_return(return_inputs, return_outputs))
+    }
+
+    fn parse_stack_part(&self, inner: &str) -> Result<(Vec<StackType>, Vec<StackType>), String> {
         let parts: Vec<&str> = inner.split("--").collect();
 
         if parts.len() != 2 {
             return Err("Stack effect must contain '--' separator".to_string());
         }
 
-        let inputs = self.parse_type_list(parts[0])?;
-        let outputs = self.parse_type_list(parts[1])?;
-
-        Ok(StackEffect::new(inputs, outputs))
+        Ok((self.parse_type_list(parts[0])?, self.parse_type_list(parts[1])?))
     }
 
     fn parse_type_list(&self, s: &str) -> Result<Vec<StackType>, String> {
@@ -246,7 +373,7 @@ impl InferenceEngine {
                 "a" | "addr" => StackType::Addr,
                 "x" => StackType::Unknown,
                 _ if token.contains('²') || token.contains('³') => StackType::Int,
-                _ => StackType::Var(token.to_string()),
+                _ => StackType::var(token),
             };
             types.push(ty);
         }
@@ -262,9 +389,12 @@ impl InferenceEngine {
 
     fn infer_word(&self, word: &str) -> Result<StackEffect, String> {
         // Check if it's a number
-        if word.parse::<i64>().is_ok() || word.parse::<f64>().is_ok() {
+        if word.parse::<i64>().is_ok() {
             return Ok(StackEffect::new(vec![], vec![StackType::Int]));
         }
+        if word.parse::<f64>().is_ok() {
+            return Ok(StackEffect::new(vec![], vec![StackType::Float]));
+        }
 
         // Check builtins
         if let Some(effect) = self.builtins.get(word) {
@@ -320,4 +450,77 @@ mod tests {
         assert_eq!(result.effect.outputs.len(), 2);
         assert_eq!(result.stack_depth_delta, 0);
     }
+
+    #[test]
+    fn test_infer_polymorphic_shuffles() {
+        let engine = InferenceEngine::new();
+        assert_eq!(engine.infer("dup").unwrap().effect.to_string(), "( a -- a a )");
+        assert_eq!(engine.infer("over").unwrap().effect.to_string(), "( a b -- a b a )");
+        assert_eq!(engine.infer("dup 1+").unwrap().effect.to_string(), "( n -- n n )");
+    }
+
+    #[test]
+    fn test_infer_float_into_int_word() {
+        let engine = InferenceEngine::new();
+        assert!(engine.infer("3.5 1+").is_err());
+        assert_eq!(engine.infer("3.5 dup").unwrap().effect.to_string(), "( -- f f )");
+    }
+
+    #[test]
+    fn test_infer_return_stack_round_trip() {
+        let engine = InferenceEngine::new();
+        let result = engine.infer(">r r>").unwrap();
+        assert_eq!(result.effect.to_string(), "( a -- a )");
+        assert_eq!(result.stack_depth_delta, 0);
+
+        // r@ copies without popping the return stack
+        let result = engine.infer(">r r@ r>").unwrap();
+        assert_eq!(result.effect.to_string(), "( a -- a a )");
+        assert_eq!(engine.infer("r@").unwrap().effect.to_string(), "( -- a )( R: a -- a )");
+    }
+
+    #[test]
+    fn test_unbalanced_return_stack_in_definition() {
+        let engine = InferenceEngine::new();
+        let err = engine.infer(": leak >r ;").err().unwrap();
+        assert!(err.contains("Unbalanced return stack"), "{}", err);
+
+        let result = engine.infer(": keep >r 1 r> ; 5 keep").unwrap();
+        assert_eq!(result.effect.to_string(), "( -- n n )");
+    }
+
+    #[test]
+    fn test_parse_dual_notation_round_trip() {
+        let engine = InferenceEngine::new();
+        let effect = engine.parse_effect("( n -- )( R: -- n )").unwrap();
+        assert_eq!(effect.return_outputs, vec![StackType::Int]);
+        assert_eq!(effect.to_string(), "( n -- )( R: -- n )");
+        assert_eq!(engine.parse_effect(&effect.to_string()).unwrap(), effect);
+
+        assert!(engine.parse_effect("( n -- )( -- n )").is_err());
+    }
+
+    fn body(source: &str) -> Vec<Word> {
+        let program = fastforth_frontend::parse_program(source).unwrap();
+        program.definitions.into_iter().next().unwrap().body
+    }
+
+    #[test]
+    fn test_infer_body_if_branches() {
+        let engine = InferenceEngine::new();
+        let effect = engine.infer_body(&body(": f dup 0 < if negate else 1+ then ;")).unwrap();
+        assert_eq!(effect.to_string(), "( n -- n )");
+
+        let err = engine.infer_body(&body(": g if 1 then ;")).unwrap_err();
+        assert!(err.contains("IF branches disagree"), "{}", err);
+    }
+
+    #[test]
+    fn test_infer_body_loops() {
+        let engine = InferenceEngine::new();
+        let effect = engine.infer_body(&body(": f begin 1- dup 0 = until ;")).unwrap();
+        assert_eq!(effect.depth_delta(), 0);
+
+        assert!(engine.infer_body(&body(": g begin 1 dup 0 = until ;")).is_err());
+    }
 }
@@ -9,6 +9,7 @@ pub mod types;
 pub use engine::{InferenceEngine, InferenceResult};
 pub use types::{StackEffect, StackType, OperationInfo};
 
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
@@ -52,6 +53,93 @@ impl InferenceAPI {
         })
     }
 
+    /// Infer stack effect from Forth code as a JSON document
+    ///
+    /// Inference failures are reported in the document itself, with `valid`
+    /// set to `false` and the message in `error`, so tools only have to
+    /// handle one shape of output. The `Err` case is reserved for
+    /// serialization failures.
+    pub fn infer_json(&self, code: &str) -> Result<String, String> {
+        let start = Instant::now();
+        let result = self.infer(code).unwrap_or_else(|e| InferenceResult {
+            valid: false,
+            inferred_effect: String::new(),
+            stack_depth_delta: 0,
+            operations: Vec::new(),
+            latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+            error: Some(e),
+        });
+
+        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+    }
+
+    /// Infer the stack effect of a `: name ... ;` definition and check it
+    /// against the definition's stack comment, if it has one
+    ///
+    /// # Example
+    /// ```
+    /// use fastforth::inference::InferenceAPI;
+    ///
+    /// let api = InferenceAPI::new();
+    /// let result = api.infer_definition(": square ( n -- n ) dup * ;").unwrap();
+    /// assert!(result.matches);
+    /// ```
+    pub fn infer_definition(&self, def_source: &str) -> Result<DefinitionResult, String> {
+        let start = Instant::now();
+        let program = fastforth_frontend::parse_program(def_source).map_err(|e| e.to_string())?;
+
+        let definition = match program.definitions.as_slice() {
+            [definition] if program.top_level_code.is_empty() => definition,
+            _ => return Err("Expected a single ': name ... ;' definition".to_string()),
+        };
+
+        let inferred = self.engine.infer_body(&definition.body)?;
+        let declared = definition.stack_effect.clone();
+        let matches = declared
+            .as_ref()
+            .is_none_or(|declared| inferred.compatible_with(declared));
+
+        Ok(DefinitionResult {
+            name: definition.name.clone(),
+            inferred_effect: inferred.to_string(),
+            declared_effect: declared.map(|effect| effect.to_string()),
+            matches,
+            latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+
+    /// Infer stack effects for many snippets at once
+    ///
+    /// Results are returned in input order, and a failing snippet does not
+    /// affect the others. Identical snippets are only inferred once.
+    pub fn infer_batch(&self, snippets: &[&str]) -> Vec<Result<InferenceResult, String>> {
+        self.infer_batch_timed(snippets).results
+    }
+
+    /// Like [`infer_batch`](Self::infer_batch), also reporting the latency of
+    /// the whole batch
+    pub fn infer_batch_timed(&self, snippets: &[&str]) -> BatchInferenceResult {
+        let start = Instant::now();
+        let mut seen: FxHashMap<&str, usize> = FxHashMap::default();
+        let mut results: Vec<Result<InferenceResult, String>> = Vec::with_capacity(snippets.len());
+
+        for (index, &snippet) in snippets.iter().enumerate() {
+            let result = match seen.get(snippet) {
+                Some(&first) => results[first].clone(),
+                None => {
+                    seen.insert(snippet, index);
+                    self.infer(snippet)
+                }
+            };
+            results.push(result);
+        }
+
+        BatchInferenceResult {
+            results,
+            total_latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        }
+    }
+
     /// Verify that code matches expected stack effect
     pub fn verify_effect(&self, code: &str, expected_effect: &str) -> Result<VerifyResult, String> {
         let start = Instant::now();
@@ -114,6 +202,27 @@ pub struct VerifyResult {
     pub message: String,
 }
 
+/// Result of inferring a whole definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionResult {
+    pub name: String,
+    pub inferred_effect: String,
+    /// Stack comment from the source, if present
+    pub declared_effect: Option<String>,
+    /// Whether the inferred effect agrees with the stack comment; always
+    /// `true` when there is none
+    pub matches: bool,
+    pub latency_ms: f64,
+}
+
+/// Result of batch inference
+#[derive(Debug, Clone)]
+pub struct BatchInferenceResult {
+    /// Per-snippet results, in input order
+    pub results: Vec<Result<InferenceResult, String>>,
+    pub total_latency_ms: f64,
+}
+
 /// Result of composition verification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompositionResult {
@@ -143,6 +252,18 @@ mod tests {
         assert!(result.latency_ms < 10.0);
     }
 
+    #[test]
+    fn test_verify_return_stack_effect() {
+        let api = InferenceAPI::new();
+        let result = api.verify_effect(">r", "( n -- )( R: -- n )").unwrap();
+        assert!(result.valid);
+        assert_eq!(result.inferred, "( a -- )( R: -- a )");
+
+        // The data stack alone does not describe >r
+        let result = api.verify_effect(">r", "( n -- )").unwrap();
+        assert!(!result.valid);
+    }
+
     #[test]
     fn test_compose() {
         let api = InferenceAPI::new();
@@ -151,6 +272,92 @@ mod tests {
         assert!(result.latency_ms < 10.0);
     }
 
+    #[test]
+    fn test_infer_definition() {
+        let api = InferenceAPI::new();
+        let result = api.infer_definition(": square ( n -- n ) dup * ;").unwrap();
+        assert!(result.matches);
+        assert_eq!(result.name, "square");
+
+        // Declared output count does not match the body
+        let result = api.infer_definition(": square ( n -- n n ) dup * ;").unwrap();
+        assert!(!result.matches);
+        assert_eq!(result.inferred_effect, "( n -- n )");
+        assert_eq!(result.declared_effect.as_deref(), Some("( n -- n n )"));
+
+        // No annotation: only the inferred effect is reported
+        let result = api.infer_definition(": inc 1 + ;").unwrap();
+        assert!(result.matches);
+        assert!(result.declared_effect.is_none());
+
+        // Unknown type names still count towards the shape
+        assert!(api.infer_definition(": pair ( thing -- thing thing ) dup ;").unwrap().matches);
+
+        assert!(api.infer_definition("1 2 +").is_err());
+    }
+
+    #[test]
+    fn test_declared_effect_flows_to_inference() {
+        let program = fastforth_frontend::parse_program(": square ( n -- n ) dup * ;").unwrap();
+        let definition = &program.definitions[0];
+        let declared: &StackEffect = definition.stack_effect.as_ref().unwrap();
+
+        let inferred = InferenceEngine::new().infer_body(&definition.body).unwrap();
+        assert_eq!(&inferred, declared);
+
+        // The optimizer counts the same cells
+        use fastforth_optimizer::Instruction;
+        let counted = Instruction::Dup.stack_effect().compose(&Instruction::Mul.stack_effect()).unwrap();
+        assert_eq!(counted.depth_delta(), declared.depth_delta());
+        assert_eq!(counted.consumed as usize, declared.inputs.len());
+    }
+
+    #[test]
+    fn test_infer_json_round_trip() {
+        let api = InferenceAPI::new();
+        let json = api.infer_json("cr").unwrap();
+        let result: InferenceResult = serde_json::from_str(&json).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.inferred_effect, "( -- )");
+        assert!(result.error.is_none());
+        assert!(json.contains("\"latency_ms\""));
+
+        let json = api.infer_json(": bad >r ;").unwrap();
+        let result: InferenceResult = serde_json::from_str(&json).unwrap();
+        assert!(!result.valid);
+        assert!(result.error.unwrap().contains("Unbalanced"));
+    }
+
+    #[test]
+    fn test_infer_batch() {
+        let api = InferenceAPI::new();
+        let snippets: Vec<String> = (0..100)
+            .map(|i| match i % 4 {
+                0 => format!("{} dup *", i),
+                1 => "swap over +".to_string(),
+                2 => format!("{} {} + 2*", i, i + 1),
+                _ => ": bad >r ;".to_string(),
+            })
+            .collect();
+        let refs: Vec<&str> = snippets.iter().map(String::as_str).collect();
+
+        let batch = api.infer_batch_timed(&refs);
+        assert_eq!(batch.results.len(), 100);
+        assert!(batch.total_latency_ms < 100.0, "Batch took {}ms", batch.total_latency_ms);
+
+        // Order is preserved and failures stay local to their snippet
+        for (i, result) in batch.results.iter().enumerate() {
+            match i % 4 {
+                3 => assert!(result.is_err()),
+                _ => {
+                    let result = result.as_ref().unwrap();
+                    assert!(result.latency_ms < 1.0);
+                    assert_eq!(result.inferred_effect, api.infer(refs[i]).unwrap().inferred_effect);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_subsecond_performance() {
         let api = InferenceAPI::new();
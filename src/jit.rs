@@ -0,0 +1,261 @@
+//! Compiled programs callable from Rust
+//!
+//! A [`JitProgram`] holds the JIT-compiled code for a Forth program and runs
+//! its top-level code or any of its words on a stack given as a slice,
+//! returning the stack they leave.
+//!
+//! Compiled code takes its inputs as parameters and returns only the top of
+//! its stack, so every word and the top-level code are compiled together
+//! with a wrapper that loads the inputs from an input buffer and stores the
+//! values left behind into an output buffer. Both buffers are owned by the
+//! program, which keeps every wrapper a plain zero-argument function
+//! regardless of the word's arity.
+
+use crate::error::{CompileError, Result};
+use crate::pipeline::{jit_compile, lower_program, result_depth};
+use backend::cranelift::{take_runtime_fault, CellWidth, CraneliftBackend, EntryPoint};
+use fastforth_frontend::ast::{SourceLocation, StackEffect, StackType};
+use fastforth_frontend::stack_effects::definition_effects;
+use fastforth_frontend::{convert_to_ssa, parse_program, Definition, Program, Word, MAIN_FUNCTION};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// Wrapper of a word (or of the top-level code) and its stack effect
+struct Entry {
+    run: EntryPoint,
+    inputs: usize,
+    outputs: usize,
+}
+
+/// A Forth program compiled with the JIT, ready to be run any number of times
+///
+/// ```
+/// use fastforth::Compiler;
+///
+/// let program = Compiler::default().jit_compile(": add + ;")?;
+/// assert_eq!(program.call("add", &[2, 3])?, vec![5]);
+/// assert_eq!(program.call("add", &[1, 2, 3])?, vec![1, 5]);
+/// # Ok::<(), fastforth::CompileError>(())
+/// ```
+///
+/// The compiled code lives as long as the `JitProgram`. Calls share its
+/// buffers, so a program is neither `Send` nor `Sync`; compile one per
+/// thread to run code in parallel. Output words write to the calling
+/// thread's output sink.
+pub struct JitProgram {
+    /// Always `Some` until dropped
+    backend: Option<CraneliftBackend>,
+    entries: HashMap<String, Entry>,
+    /// Inputs of the current call, read by the wrappers
+    input: Box<[Cell<i64>]>,
+    /// Outputs of the current call, written by the wrappers
+    output: Box<[Cell<i64>]>,
+}
+
+impl JitProgram {
+    /// Compile every word and the top-level code of `source`
+    pub(crate) fn compile(source: &str) -> Result<Self> {
+        let parsed = parse_program(source)
+            .map_err(CompileError::ParseError)?;
+
+        // Lower once to learn each target's stack effect. Top-level code is
+        // lowered as a definition so it can take inputs as parameters; it
+        // still returns a 0 if it empties the stack, so it gets one extra
+        // value pushed, discounted afterwards.
+        let mut main_probe = parsed.top_level_code.clone();
+        main_probe.push(Word::IntLiteral(0));
+        let probe = Program {
+            definitions: parsed.definitions.iter().cloned()
+                .chain(std::iter::once(definition(wrapper_name(MAIN_FUNCTION), main_probe, None)))
+                .collect(),
+            top_level_code: Vec::new(),
+        };
+        let lowered = lower_program(&probe)?;
+        let effects: HashMap<String, StackEffect> = parsed.definitions.iter()
+            .zip(definition_effects(&parsed))
+            .filter_map(|(def, effect)| Some((def.name.clone(), effect?)))
+            .collect();
+
+        let mut targets: Vec<(String, Vec<Word>, usize, usize)> = Vec::new();
+        for func in &lowered {
+            if func.name == wrapper_name(MAIN_FUNCTION) {
+                let outputs = result_depth(func).saturating_sub(1);
+                targets.push((MAIN_FUNCTION.to_string(), parsed.top_level_code.clone(), func.parameters.len(), outputs));
+            } else if func.name != MAIN_FUNCTION {
+                // A call leaves one value: the word's top of stack, or a 0
+                // standing in for it when the word leaves nothing, which
+                // only the word's stack effect tells apart from a real 0
+                let outputs = effects.get(&func.name)
+                    .map_or_else(|| result_depth(func), |effect| effect.outputs.len());
+                let mut body = vec![word_ref(&func.name)];
+                if outputs == 0 {
+                    body.push(word_ref("drop"));
+                }
+                targets.push((func.name.clone(), body, func.parameters.len(), outputs));
+            }
+        }
+
+        let max_inputs = targets.iter().map(|target| target.2).max().unwrap_or(0);
+        let max_outputs = targets.iter().map(|target| target.3).max().unwrap_or(0);
+        let input: Box<[Cell<i64>]> = (0..max_inputs).map(|_| Cell::new(0)).collect();
+        let output: Box<[Cell<i64>]> = (0..max_outputs).map(|_| Cell::new(0)).collect();
+        let input_base = input.as_ptr() as i64;
+        let output_base = output.as_ptr() as i64;
+
+        // Wrappers take no parameters: they load the inputs, run the body,
+        // spill the outputs and return how many there were
+        let mut program = Program { definitions: parsed.definitions, top_level_code: Vec::new() };
+        let wrapper_effect = StackEffect::new(Vec::new(), vec![StackType::Int]);
+        for (name, body, inputs, outputs) in &targets {
+            let mut code = Vec::new();
+            for slot in 0..*inputs {
+                code.push(Word::IntLiteral(input_base + (slot * 8) as i64));
+                code.push(word_ref("@"));
+            }
+            code.extend(body.iter().cloned());
+            for slot in (0..*outputs).rev() {
+                code.push(Word::IntLiteral(output_base + (slot * 8) as i64));
+                code.push(word_ref("!"));
+            }
+            code.push(Word::IntLiteral(*outputs as i64));
+            program.definitions.push(definition(wrapper_name(name), code, Some(wrapper_effect.clone())));
+        }
+
+        // The user's code was analyzed with the probe; the wrappers' declared
+        // effects only pin their parameter count, so skip checking them
+        let functions = convert_to_ssa(&program)
+            .map_err(CompileError::SSAError)?;
+        for function in &functions {
+            function.verify()
+                .map_err(|e| CompileError::InternalError(format!("SSA verification failed: {}", e)))?;
+        }
+        let backend = jit_compile(&functions, false, false, false, CellWidth::Bits64)?;
+
+        let mut entries = HashMap::new();
+        for (name, _, inputs, outputs) in targets {
+            let run = backend.get_entry_point(&wrapper_name(&name))
+                .ok_or_else(|| CompileError::BackendError(format!("Failed to get compiled function '{}'", name)))?;
+            entries.insert(name, Entry { run, inputs, outputs });
+        }
+
+        Ok(Self { backend: Some(backend), entries, input, output })
+    }
+
+    /// Run the top-level code on a stack holding `inputs` (bottom first) and
+    /// return the stack it leaves
+    pub fn run(&self, inputs: &[i64]) -> Result<Vec<i64>> {
+        self.call(MAIN_FUNCTION, inputs)
+    }
+
+    /// Run the word `name` on a stack holding `inputs` (bottom first) and
+    /// return the stack it leaves
+    ///
+    /// Items below the ones the word consumes are passed through unchanged.
+    pub fn call(&self, name: &str, inputs: &[i64]) -> Result<Vec<i64>> {
+        let entry = self.entries.get(name)
+            .ok_or_else(|| CompileError::RuntimeError(format!("Undefined word: {}", name)))?;
+        if inputs.len() < entry.inputs {
+            return Err(CompileError::RuntimeError(format!(
+                "Stack underflow in '{}': expected {} items, found {}",
+                name, entry.inputs, inputs.len()
+            )));
+        }
+
+        let (kept, consumed) = inputs.split_at(inputs.len() - entry.inputs);
+        for (cell, &value) in self.input.iter().zip(consumed) {
+            cell.set(value);
+        }
+
+        take_runtime_fault();
+        // SAFETY: the wrapper only touches the buffers, which outlive the call
+        let returned = unsafe { (entry.run)() };
+        if let Some(fault) = take_runtime_fault() {
+            return Err(CompileError::RuntimeError(fault.to_string()));
+        }
+        if returned != entry.outputs as i64 {
+            return Err(CompileError::RuntimeError(format!(
+                "Expected {} stack items from '{}', got {}",
+                entry.outputs, name, returned
+            )));
+        }
+
+        let mut stack = kept.to_vec();
+        stack.extend(self.output[..entry.outputs].iter().map(Cell::get));
+        Ok(stack)
+    }
+
+    /// Names of the words that can be passed to [`call`](Self::call)
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str).filter(|name| *name != MAIN_FUNCTION)
+    }
+}
+
+impl Drop for JitProgram {
+    fn drop(&mut self) {
+        if let Some(backend) = self.backend.take() {
+            // SAFETY: entry points never leave the program
+            unsafe { backend.free_memory() };
+        }
+    }
+}
+
+/// Name of the wrapper compiled for a target; the space keeps it apart from
+/// any word a program can define
+fn wrapper_name(target: &str) -> String {
+    format!("{} run", target)
+}
+
+fn word_ref(name: &str) -> Word {
+    Word::WordRef {
+        name: name.to_string(),
+        location: SourceLocation::default(),
+    }
+}
+
+fn definition(name: String, body: Vec<Word>, stack_effect: Option<StackEffect>) -> Definition {
+    Definition {
+        name,
+        body,
+        immediate: false,
+        stack_effect,
+        stack_comment: None,
+        locals: None,
+        location: SourceLocation::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_top_level_code_with_inputs() {
+        let program = JitProgram::compile(": sq dup * ; sq 1 +").unwrap();
+        assert_eq!(program.run(&[3]).unwrap(), vec![10]);
+        assert_eq!(program.run(&[7, 4]).unwrap(), vec![7, 17]);
+        assert!(program.run(&[]).is_err());
+    }
+
+    #[test]
+    fn test_call_words_repeatedly() {
+        let program = JitProgram::compile(": inc 1 + ; : answer 42 ;").unwrap();
+        for n in 0..3 {
+            assert_eq!(program.call("inc", &[9, n]).unwrap(), vec![9, n + 1]);
+        }
+        assert_eq!(program.call("answer", &[]).unwrap(), vec![42]);
+        assert!(program.call("missing", &[]).is_err());
+        assert!(program.call("inc", &[]).is_err());
+
+        let mut words: Vec<&str> = program.words().collect();
+        words.sort();
+        assert_eq!(words, vec!["answer", "inc"]);
+    }
+
+    #[test]
+    fn test_call_word_leaving_nothing() {
+        let program = JitProgram::compile(": noop ; : forget drop ;").unwrap();
+        assert_eq!(program.call("noop", &[1, 2]).unwrap(), vec![1, 2]);
+        assert_eq!(program.call("noop", &[]).unwrap(), Vec::<i64>::new());
+        assert_eq!(program.call("forget", &[1, 2]).unwrap(), vec![1]);
+    }
+}
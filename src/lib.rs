@@ -19,9 +19,12 @@
 pub mod error;
 pub mod compiler;
 pub mod pipeline;
+pub mod compile_cache;
 pub mod backend;
 pub mod patterns;
 pub mod engine;
+pub mod session;
+pub mod jit;
 pub mod runtime_ffi;
 
 // Machine-readable specifications
@@ -51,8 +54,11 @@ pub mod inference;
 pub mod server;
 
 pub use error::{CompileError, Result};
-pub use pipeline::{CompilationPipeline, CompilationMode, CompilationResult};
+pub use pipeline::{CompilationPipeline, CompilationMode, CompilationResult, IrStage};
+pub use compile_cache::CompileCache;
 pub use engine::ForthEngine;
+pub use session::Session;
+pub use jit::JitProgram;
 
 // Re-export pattern system
 pub use patterns::{
@@ -88,8 +94,9 @@ pub use fastforth_frontend::{
     parse_program, analyze, convert_to_ssa,
 };
 pub use fastforth_optimizer::{
-    ForthIR, Instruction, StackEffect, Optimizer, OptimizationLevel,
+    ForthIR, Instruction, StackEffect, Optimizer, OptimizationLevel, OptimizationNote, PassKind,
 };
+pub use ::backend::cranelift::CellWidth;
 
 use std::path::Path;
 
@@ -99,6 +106,7 @@ use std::path::Path;
 pub struct Compiler {
     optimization_level: OptimizationLevel,
     optimizer: Optimizer,
+    cell_width: CellWidth,
 }
 
 impl Compiler {
@@ -107,20 +115,56 @@ impl Compiler {
         Self {
             optimization_level,
             optimizer: Optimizer::new(optimization_level),
+            cell_width: CellWidth::Bits64,
         }
     }
 
     /// Compile Forth source code from a string
     pub fn compile_string(&self, source: &str, mode: CompilationMode) -> Result<CompilationResult> {
         let mut pipeline = CompilationPipeline::new(self.optimization_level);
+        pipeline.set_cell_width(self.cell_width);
         pipeline.compile(source, mode)
     }
 
+    /// Dump the IR of a pipeline stage for Forth source code, without generating code
+    pub fn emit_ir_string(&self, source: &str, stage: IrStage) -> Result<String> {
+        let mut pipeline = CompilationPipeline::new(self.optimization_level);
+        pipeline.set_emit_ir(Some(stage));
+        let result = pipeline.compile(source, CompilationMode::AOT)?;
+        Ok(result.ir_dump.unwrap_or_default())
+    }
+
+    /// Optimize Forth source code without generating code, returning the
+    /// optimizer's notes on what it inlined, fused and eliminated, and why
+    pub fn explain_string(&self, source: &str) -> Result<Vec<OptimizationNote>> {
+        let mut pipeline = CompilationPipeline::new(self.optimization_level);
+        pipeline.set_explain(true);
+        pipeline.optimized_ir(source)?;
+        Ok(pipeline.optimization_notes().to_vec())
+    }
+
+    /// Compile Forth source code with JIT and return the disassembly of every word
+    pub fn disassemble_string(&self, source: &str) -> Result<String> {
+        let mut pipeline = CompilationPipeline::new(self.optimization_level);
+        pipeline.set_disassemble(true);
+        pipeline.set_cell_width(self.cell_width);
+        let result = pipeline.compile(source, CompilationMode::JIT)?;
+        Ok(result.disassembly.unwrap_or_default())
+    }
+
+    /// Compile Forth source code with JIT into a program that can be run
+    /// repeatedly from Rust
+    pub fn jit_compile(&self, source: &str) -> Result<JitProgram> {
+        JitProgram::compile(source)
+    }
+
     /// Compile Forth source code from a file
+    ///
+    /// INCLUDE and REQUIRE in it are resolved relative to the including file.
     pub fn compile_file(&self, path: &Path, mode: CompilationMode) -> Result<CompilationResult> {
-        let source = std::fs::read_to_string(path)
-            .map_err(|e| CompileError::IoError(path.to_path_buf(), e))?;
-        self.compile_string(&source, mode)
+        let mut pipeline = CompilationPipeline::new(self.optimization_level);
+        pipeline.set_cell_width(self.cell_width);
+        pipeline.compile_file(path, mode)
     }
 
     /// Get the optimization level
@@ -133,6 +177,19 @@ impl Compiler {
         self.optimization_level = level;
         self.optimizer = Optimizer::new(level);
     }
+
+    /// Get the cell width of JIT-compiled code
+    pub fn cell_width(&self) -> CellWidth {
+        self.cell_width
+    }
+
+    /// Set the cell width of JIT-compiled code
+    ///
+    /// [`jit_compile`](Self::jit_compile) always uses 64-bit cells, since
+    /// its programs exchange host addresses with compiled code.
+    pub fn set_cell_width(&mut self, width: CellWidth) {
+        self.cell_width = width;
+    }
 }
 
 impl Default for Compiler {
@@ -2,13 +2,14 @@
 //!
 //! A high-performance Forth compiler with LLVM backend
 
-use fastforth::{Compiler, CompilationMode, OptimizationLevel};
+use fastforth::{Compiler, CompilationMode, IrStage, OptimizationLevel, PassKind, Session};
 #[cfg(feature = "inference")]
 use fastforth::inference::InferenceAPI;
 #[cfg(feature = "server")]
 use fastforth::server::{VerificationServer, ServerConfig};
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use fastforth_frontend::{Program, StreamingParser};
 use rustyline::DefaultEditor;
 use std::path::PathBuf;
 use std::process;
@@ -60,6 +61,25 @@ enum Commands {
         /// Include auto-fix suggestions in errors
         #[arg(long)]
         suggest_fixes: bool,
+
+        /// Dump IR instead of generating code (lowered, optimized, both, ssa);
+        /// written to --output if given, otherwise stdout
+        #[arg(long, value_name = "STAGE", num_args = 0..=1, default_missing_value = "both")]
+        emit_ir: Option<String>,
+
+        /// Print the generated Cranelift IR and assembly for every word instead of running
+        #[arg(long)]
+        disasm: bool,
+
+        /// Also print the time spent in each optimization pass
+        #[arg(long)]
+        timings: bool,
+
+        /// Print why the optimizer did or did not inline, fuse and eliminate code
+        /// instead of generating code; optionally only for the given comma-separated
+        /// passes (inline, superinstructions, dead-code)
+        #[arg(long, value_name = "PASSES", num_args = 0..=1, default_missing_value = "all")]
+        explain: Option<String>,
     },
 
     /// Run Forth code in JIT mode
@@ -216,6 +236,21 @@ enum Commands {
         #[arg(long, default_value = "human")]
         format: String,
     },
+
+    /// Reformat Forth source files in place
+    Fmt {
+        /// Forth source files to format
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Write nothing; fail if any file is not already formatted
+        #[arg(long)]
+        check: bool,
+
+        /// Print the formatted source instead of rewriting the files
+        #[arg(long, conflicts_with = "check")]
+        stdout: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -267,6 +302,10 @@ fn main() {
             agent_mode,
             verify_only,
             suggest_fixes,
+            emit_ir,
+            disasm,
+            timings,
+            explain,
         }) => {
             let compilation_mode = match mode.as_str() {
                 "aot" => CompilationMode::AOT,
@@ -277,6 +316,85 @@ fn main() {
                 }
             };
 
+            if let Some(stage) = emit_ir {
+                let stage: IrStage = match stage.parse() {
+                    Ok(stage) => stage,
+                    Err(e) => {
+                        eprintln!("{}: {}", "Error".red(), e);
+                        process::exit(1);
+                    }
+                };
+
+                let dump = std::fs::read_to_string(input)
+                    .map_err(|e| fastforth::CompileError::IoError(input.clone(), e))
+                    .and_then(|source| compiler.emit_ir_string(&source, stage));
+
+                match dump {
+                    Ok(dump) => match output {
+                        Some(path) => {
+                            if let Err(e) = std::fs::write(path, &dump) {
+                                eprintln!("{}: {}", "Failed to write IR".red().bold(), e);
+                                process::exit(1);
+                            }
+                        }
+                        None => print!("{}", dump),
+                    },
+                    Err(e) => {
+                        eprintln!("{}: {}", "Compilation failed".red().bold(), e);
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+
+            if *disasm {
+                let disassembly = std::fs::read_to_string(input)
+                    .map_err(|e| fastforth::CompileError::IoError(input.clone(), e))
+                    .and_then(|source| compiler.disassemble_string(&source));
+
+                match disassembly {
+                    Ok(text) => print!("{}", text),
+                    Err(e) => {
+                        eprintln!("{}: {}", "Compilation failed".red().bold(), e);
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+
+            if let Some(passes) = explain {
+                let passes: Option<Vec<PassKind>> = match passes.as_str() {
+                    "all" => None,
+                    passes => match passes.split(',').map(|pass| pass.trim().parse()).collect() {
+                        Ok(passes) => Some(passes),
+                        Err(e) => {
+                            eprintln!("{}: {}", "Error".red(), e);
+                            process::exit(1);
+                        }
+                    },
+                };
+
+                let notes = std::fs::read_to_string(input)
+                    .map_err(|e| fastforth::CompileError::IoError(input.clone(), e))
+                    .and_then(|source| compiler.explain_string(&source));
+
+                match notes {
+                    Ok(notes) => {
+                        let shown = notes
+                            .iter()
+                            .filter(|note| passes.as_ref().is_none_or(|passes| passes.contains(&note.pass)));
+                        for note in shown {
+                            println!("{}", note);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{}: {}", "Compilation failed".red().bold(), e);
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+
             // For verify-only mode, we only type-check
             if *verify_only {
                 // TODO: Implement type-check only mode
@@ -291,21 +409,82 @@ fn main() {
                             "status": "success",
                             "mode": format!("{:?}", result.mode),
                             "compile_time_ms": result.compile_time_ms,
+                            "frontend_time_ms": result.stats.frontend_time_ms,
+                            "optimization_time_ms": result.stats.optimization_time_ms,
+                            "backend_time_ms": result.stats.backend_time_ms,
                             "definitions_count": result.stats.definitions_count,
                             "optimization_savings": result.stats.optimization_savings(),
+                            "passes": result.stats.pass_stats.passes.iter().map(|record| {
+                                if record.is_skipped() {
+                                    serde_json::json!({
+                                        "pass": record.pass.name(),
+                                        "skipped": true,
+                                    })
+                                } else {
+                                    serde_json::json!({
+                                        "pass": record.pass.name(),
+                                        "skipped": false,
+                                        "instructions_before": record.instructions_before,
+                                        "instructions_after": record.instructions_after,
+                                        "removed": record.removed,
+                                        "added": record.added,
+                                        "time_us": record.duration.as_micros() as u64,
+                                    })
+                                }
+                            }).collect::<Vec<_>>(),
                             "output_path": result.output_path,
+                            "warnings": result.warnings,
                         });
                         println!("{}", serde_json::to_string(&json_output).unwrap());
                     } else {
+                        print_warnings(&result.warnings);
                         println!("{}", "✓ Compilation successful".green().bold());
                         println!("  Mode: {:?}", result.mode);
-                        println!("  Time: {}ms", result.compile_time_ms);
+                        println!("  Time: {}", format_ms(result.compile_time_ms as u128));
+                        println!("    {:<20} {}", "frontend", format_ms(result.stats.frontend_time_ms as u128));
+                        // JIT mode does not run the optimizer
+                        if result.mode == CompilationMode::AOT {
+                            println!(
+                                "    {:<20} {}",
+                                "optimization",
+                                format_ms(result.stats.optimization_time_ms as u128)
+                            );
+                        }
+                        println!("    {:<20} {}", "backend", format_ms(result.stats.backend_time_ms as u128));
                         println!("  Definitions: {}", result.stats.definitions_count);
                         println!(
                             "  Optimization: {:.1}% reduction",
                             result.stats.optimization_savings() * 100.0
                         );
 
+                        if !result.stats.pass_stats.passes.is_empty() {
+                            println!("  Passes:");
+                            for record in &result.stats.pass_stats.passes {
+                                if record.is_skipped() {
+                                    println!("    {:<20} {}", record.pass.name(), "skipped".dimmed());
+                                } else if *timings {
+                                    println!(
+                                        "    {:<20} {} -> {} (-{} +{}) {}",
+                                        record.pass.name(),
+                                        record.instructions_before,
+                                        record.instructions_after,
+                                        record.removed,
+                                        record.added,
+                                        format_ms(record.duration.as_millis())
+                                    );
+                                } else {
+                                    println!(
+                                        "    {:<20} {} -> {} (-{} +{})",
+                                        record.pass.name(),
+                                        record.instructions_before,
+                                        record.instructions_after,
+                                        record.removed,
+                                        record.added
+                                    );
+                                }
+                            }
+                        }
+
                         if let Some(output_path) = &result.output_path {
                             println!("  Output: {}", output_path);
                         }
@@ -329,6 +508,7 @@ fn main() {
         Some(Commands::Run { input }) => {
             match compiler.compile_file(input, CompilationMode::JIT) {
                 Ok(result) => {
+                    print_warnings(&result.warnings);
                     println!("{}", "✓ Execution complete".green().bold());
                     println!("  Time: {}ms", result.compile_time_ms);
                     if let Some(jit_result) = result.jit_result {
@@ -345,6 +525,7 @@ fn main() {
         Some(Commands::Execute { code }) => {
             match compiler.compile_string(code, CompilationMode::JIT) {
                 Ok(result) => {
+                    print_warnings(&result.warnings);
                     if let Some(jit_result) = result.jit_result {
                         println!("{}", jit_result);
                     }
@@ -367,17 +548,25 @@ fn main() {
         #[cfg(feature = "inference")]
         Some(Commands::Infer { code, json }) => {
             let api = InferenceAPI::new();
+            if *json {
+                // Failures are reported inside the document, not via the exit code
+                match api.infer_json(code) {
+                    Ok(document) => println!("{}", document),
+                    Err(e) => {
+                        eprintln!("{}: {}", "Serialization failed".red().bold(), e);
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+
             match api.infer(code) {
                 Ok(result) => {
-                    if *json {
-                        println!("{}", serde_json::to_string_pretty(&result).unwrap());
-                    } else {
-                        println!("{}", "✓ Stack Effect Inference".green().bold());
-                        println!("  Effect: {}", result.inferred_effect);
-                        println!("  Depth Delta: {}", result.stack_depth_delta);
-                        println!("  Operations: {}", result.operations.join(" "));
-                        println!("  Latency: {:.3}ms", result.latency_ms);
-                    }
+                    println!("{}", "✓ Stack Effect Inference".green().bold());
+                    println!("  Effect: {}", result.inferred_effect);
+                    println!("  Depth Delta: {}", result.stack_depth_delta);
+                    println!("  Operations: {}", result.operations.join(" "));
+                    println!("  Latency: {:.3}ms", result.latency_ms);
                 }
                 Err(e) => {
                     eprintln!("{}: {}", "Inference failed".red().bold(), e);
@@ -462,6 +651,10 @@ fn main() {
             handle_diff_command(old, new, *semantic, format);
         }
 
+        Some(Commands::Fmt { files, check, stdout }) => {
+            handle_fmt_command(files, *check, *stdout);
+        }
+
         None => {
             // Default: start REPL
             run_repl(compiler);
@@ -469,6 +662,15 @@ fn main() {
     }
 }
 
+/// Format a phase time, showing sub-millisecond phases as `<1ms`
+fn format_ms(ms: u128) -> String {
+    if ms == 0 {
+        "<1ms".to_string()
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
 fn handle_spec_command(command: &SpecCommands) {
     use fastforth::{Specification, SpecValidator};
 
@@ -632,11 +834,25 @@ fn run_repl(compiler: Compiler) {
     println!("Type {} to exit\n", "'.quit'".yellow());
 
     let mut rl = DefaultEditor::new().unwrap();
+    let mut session = Session::new();
+    let mut parser = StreamingParser::new();
     let mut line_number = 1;
 
     loop {
-        let prompt = format!("{}> ", line_number.to_string().cyan());
+        // An unfinished definition or control structure continues on the next line
+        let prompt = if parser.is_pending() {
+            format!("{} ", "...".cyan())
+        } else {
+            format!("{}> ", line_number.to_string().cyan())
+        };
         match rl.readline(&prompt) {
+            Ok(line) if parser.is_pending() => {
+                let _ = rl.add_history_entry(&line);
+                eval_repl_entry(&mut session, parser.feed(line.trim()));
+                if !parser.is_pending() {
+                    line_number += 1;
+                }
+            }
             Ok(line) => {
                 let trimmed = line.trim();
 
@@ -653,10 +869,29 @@ fn run_repl(compiler: Compiler) {
                     continue;
                 }
 
+                if trimmed == ".stack" || trimmed == ".s" {
+                    println!("{}", session.format_stack());
+                    continue;
+                }
+
+                if trimmed == ".words" {
+                    print_words(&session);
+                    continue;
+                }
+
+                if trimmed == ".clear" {
+                    session.clear_stack();
+                    println!("{}", "ok".green());
+                    continue;
+                }
+
                 if trimmed.starts_with(".load ") {
                     let path = trimmed.trim_start_matches(".load ").trim();
-                    match compiler.compile_file(&PathBuf::from(path), CompilationMode::JIT) {
-                        Ok(_) => println!("{}", "✓ File loaded".green()),
+                    match std::fs::read_to_string(path) {
+                        Ok(source) => match session.eval(&source) {
+                            Ok(()) => println!("{}", "✓ File loaded".green()),
+                            Err(e) => eprintln!("{}: {}", "Error".red(), e),
+                        },
                         Err(e) => eprintln!("{}: {}", "Error".red(), e),
                     }
                     continue;
@@ -665,29 +900,15 @@ fn run_repl(compiler: Compiler) {
                 // Add to history
                 let _ = rl.add_history_entry(&line);
 
-                // Try to compile and execute
-                match compiler.compile_string(trimmed, CompilationMode::JIT) {
-                    Ok(result) => {
-                        if let Some(jit_result) = result.jit_result {
-                            println!("{} {}", "=>".green(), jit_result);
-                        } else {
-                            println!("{}", "ok".green());
-                        }
-
-                        if result.stats.definitions_count > 0 {
-                            println!(
-                                "{} {} definitions",
-                                "✓".green(),
-                                result.stats.definitions_count
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{}: {}", "Error".red(), e);
-                    }
+                eval_repl_entry(&mut session, parser.feed(trimmed));
+                if !parser.is_pending() {
+                    line_number += 1;
                 }
-
-                line_number += 1;
+            }
+            Err(rustyline::error::ReadlineError::Interrupted) if parser.is_pending() => {
+                // Ctrl-C abandons a multi-line entry but keeps the REPL running
+                parser.cancel();
+                println!("^C");
             }
             Err(rustyline::error::ReadlineError::Interrupted) => {
                 println!("^C");
@@ -707,11 +928,37 @@ fn run_repl(compiler: Compiler) {
     println!("\n{}", "Goodbye!".cyan());
 }
 
+/// Compile and execute a complete REPL entry against the persistent session
+fn eval_repl_entry(session: &mut Session, parsed: fastforth_frontend::Result<Option<Program>>) {
+    let result = match parsed {
+        Ok(Some(program)) => {
+            let result = session.eval_program(program);
+            print_warnings(session.warnings());
+            result
+        }
+        // Wait for the rest of the entry
+        Ok(None) => return,
+        Err(e) => Err(e.into()),
+    };
+    match result {
+        Ok(()) => match session.stack().last() {
+            Some(top) => println!("{} {}", "=>".green(), top),
+            None => println!("{}", "ok".green()),
+        },
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+        }
+    }
+}
+
 fn print_repl_help() {
     println!("\n{}", "REPL Commands:".cyan().bold());
     println!("  {}        - Show this help", ".help".yellow());
     println!("  {}        - Quit the REPL", ".quit".yellow());
     println!("  {} <file> - Load and execute a Forth file", ".load".yellow());
+    println!("  {}       - Show the data stack, bottom to top (alias {})", ".stack".yellow(), ".s".yellow());
+    println!("  {}       - Empty the data stack", ".clear".yellow());
+    println!("  {}       - List defined and built-in words", ".words".yellow());
     println!("\n{}", "Forth Basics:".cyan().bold());
     println!("  {}       - Push 42 on stack", "42".yellow());
     println!("  {}        - Duplicate top of stack", "dup".yellow());
@@ -719,9 +966,49 @@ fn print_repl_help() {
     println!("  {}       - Swap top two items", "swap".yellow());
     println!("  {}    - Add, subtract, multiply, divide", "+ - * /".yellow());
     println!("  {}    - Define a new word", ": double 2 * ;".yellow());
+    println!("\n{}", "Example:".cyan().bold());
+    println!("  {}          - Prints {}", "1 2 3 .s".yellow(), "<3> 1 2 3".yellow());
     println!();
 }
 
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("{}: {}", "Warning".yellow().bold(), warning);
+    }
+}
+
+fn print_words(session: &Session) {
+    let (builtins, definitions): (Vec<_>, Vec<_>) =
+        session.dictionary().into_iter().partition(|entry| entry.builtin);
+
+    if !definitions.is_empty() {
+        println!("{}", "Defined words:".cyan().bold());
+        for entry in &definitions {
+            match &entry.stack_effect {
+                Some(effect) => println!("  {} {}", entry.name.yellow(), effect),
+                None => println!("  {}", entry.name.yellow()),
+            }
+        }
+    }
+
+    // Built-ins are many, so list only their names, wrapped
+    println!("{}", "Built-in words:".cyan().bold());
+    let mut line = String::new();
+    for entry in &builtins {
+        if !line.is_empty() && line.len() + entry.name.len() >= 78 {
+            println!("  {}", line);
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&entry.name);
+    }
+    if !line.is_empty() {
+        println!("  {}", line);
+    }
+}
+
 fn print_info(compiler: &Compiler) {
     println!("\n{}", "Fast Forth Compiler".cyan().bold());
     println!("{}", "=".repeat(50));
@@ -976,3 +1263,39 @@ fn handle_diff_command(old_path: &PathBuf, new_path: &PathBuf, _semantic: bool,
         process::exit(1);
     }
 }
+
+fn handle_fmt_command(files: &[PathBuf], check: bool, stdout: bool) {
+    let mut unformatted = 0;
+
+    for path in files {
+        let formatted = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                let formatted = fastforth_frontend::format::format_source(&source).map_err(|e| e.to_string())?;
+                Ok((source, formatted))
+            });
+        let (source, formatted) = match formatted {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}: {}: {}", "Format failed".red().bold(), path.display(), e);
+                process::exit(1);
+            }
+        };
+
+        if stdout {
+            print!("{}", formatted);
+        } else if formatted != source {
+            if check {
+                println!("{} would be reformatted", path.display());
+                unformatted += 1;
+            } else if let Err(e) = std::fs::write(path, &formatted) {
+                eprintln!("{}: {}: {}", "Failed to write".red().bold(), path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if unformatted > 0 {
+        process::exit(1);
+    }
+}
@@ -210,7 +210,8 @@ impl PerformanceModel {
 
                     // Branch operations
                     Instruction::Branch(_) |
-                    Instruction::BranchIfNot(_) => {
+                    Instruction::BranchIfNot(_) |
+                    Instruction::BranchIfElse(..) => {
                         breakdown.branch_ops += 1;
                     }
 
@@ -6,10 +6,21 @@
 //! 3. Backend: LLVM IR generation → Native code
 //! 4. Execution: JIT or AOT
 
+use crate::compile_cache::CompileCache;
 use crate::error::{CompileError, Result};
-use fastforth_frontend::{parse_program, analyze, convert_to_ssa, Program, SSAFunction};
-use fastforth_optimizer::{ForthIR, Optimizer, OptimizationLevel, Instruction};
+use fastforth_frontend::semantic::shadowing_warning;
+use fastforth_frontend::licm::hoist_loop_invariants;
+use fastforth_frontend::{analyze, convert_to_ssa_filtered, Definition, IncludeContext, Program, SSAFunction, MAIN_FUNCTION};
+use fastforth_frontend::ssa::{BasicBlock, BlockId, Register, SSAInstruction};
+use fastforth_frontend::ssa_validator::{destination_registers, used_registers};
+use fastforth_optimizer::{
+    block_label, BlockRef, ForthIR, Optimizer, OptimizerConfig, OptimizationLevel, OptimizationNote, Instruction, PassStats,
+    StackEffect, DEFAULT_MAX_STACK_DEPTH,
+};
+use backend::cranelift::{CellWidth, CraneliftBackend, CraneliftSettings, take_runtime_fault};
 use tracing::{debug, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 /// Compilation mode
@@ -21,6 +32,42 @@ pub enum CompilationMode {
     JIT,
 }
 
+/// Pipeline stage whose IR is dumped instead of generating code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrStage {
+    /// Frontend SSA form
+    Ssa,
+    /// Optimizer IR straight after lowering from SSA
+    Lowered,
+    /// Optimizer IR after all optimization passes
+    Optimized,
+    /// Optimizer IR both before and after optimization
+    Both,
+}
+
+impl std::str::FromStr for IrStage {
+    type Err = CompileError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ssa" => Ok(IrStage::Ssa),
+            "lowered" => Ok(IrStage::Lowered),
+            "optimized" => Ok(IrStage::Optimized),
+            "both" => Ok(IrStage::Both),
+            _ => Err(CompileError::InternalError(format!(
+                "Unknown IR stage '{}', use 'ssa', 'lowered', 'optimized' or 'both'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Where the source to compile comes from
+enum Source<'a> {
+    Text(&'a str),
+    File(&'a Path),
+}
+
 /// Result of compilation
 #[derive(Debug)]
 pub struct CompilationResult {
@@ -34,6 +81,12 @@ pub struct CompilationResult {
     pub output_path: Option<String>,
     /// JIT execution result (for JIT mode)
     pub jit_result: Option<i64>,
+    /// Textual IR dump (when an IR stage was requested; no code is generated)
+    pub ir_dump: Option<String>,
+    /// Backend disassembly of every compiled function (JIT mode, when requested)
+    pub disassembly: Option<String>,
+    /// Warnings about the program that did not stop compilation
+    pub warnings: Vec<String>,
     /// Optimization statistics
     pub stats: CompilationStats,
 }
@@ -51,17 +104,23 @@ pub struct CompilationStats {
     pub frontend_time_ms: u64,
     /// Optimization time in milliseconds
     pub optimization_time_ms: u64,
-    /// Backend time in milliseconds
+    /// Backend time in milliseconds, excluding optimization
     pub backend_time_ms: u64,
+    /// Per-pass optimizer statistics (AOT mode only)
+    pub pass_stats: PassStats,
+    /// Definitions reused from the incremental cache
+    pub cache_hits: usize,
+    /// Definitions lowered afresh while the incremental cache is enabled
+    pub cache_misses: usize,
 }
 
 impl CompilationStats {
-    /// Calculate optimization savings
+    /// Calculate optimization savings (negative if optimization grew the code)
     pub fn optimization_savings(&self) -> f64 {
         if self.instructions_before == 0 {
             0.0
         } else {
-            (self.instructions_before - self.instructions_after) as f64 / self.instructions_before as f64
+            (self.instructions_before as f64 - self.instructions_after as f64) / self.instructions_before as f64
         }
     }
 }
@@ -70,6 +129,22 @@ impl CompilationStats {
 pub struct CompilationPipeline {
     optimization_level: OptimizationLevel,
     optimizer: Optimizer,
+    emit_ir: Option<IrStage>,
+    disassemble: bool,
+    checked_arithmetic: bool,
+    /// Whether JIT programs may run shell commands with `system`
+    allow_system: bool,
+    /// Width of a cell in JIT-compiled code
+    cell_width: CellWidth,
+    /// Deepest data stack an AOT-compiled program may reach
+    max_stack_depth: usize,
+    /// Whether the optimizer records notes on its decisions
+    explain: bool,
+    /// Lowered definitions kept across `compile` calls, when enabled
+    cache: Option<CompileCache>,
+    /// Search path for INCLUDE and REQUIRE, and the files loaded by the
+    /// current compilation
+    includes: IncludeContext,
 }
 
 impl CompilationPipeline {
@@ -78,11 +153,123 @@ impl CompilationPipeline {
         Self {
             optimization_level,
             optimizer: Optimizer::new(optimization_level),
+            emit_ir: None,
+            disassemble: false,
+            checked_arithmetic: false,
+            allow_system: false,
+            cell_width: CellWidth::Bits64,
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
+            explain: false,
+            cache: None,
+            includes: IncludeContext::new(),
         }
     }
 
+    /// Dump the IR of `stage` instead of running the backend
+    pub fn set_emit_ir(&mut self, stage: Option<IrStage>) {
+        self.emit_ir = stage;
+    }
+
+    /// Produce backend disassembly instead of executing in JIT mode
+    pub fn set_disassemble(&mut self, enabled: bool) {
+        self.disassemble = enabled;
+    }
+
+    /// Report signed overflow in `+ - * negate abs` as an error instead of wrapping
+    ///
+    /// Overflowing constant expressions are rejected by the optimizer; overflow
+    /// at runtime makes JIT execution return a runtime error.
+    pub fn set_checked_arithmetic(&mut self, enabled: bool) {
+        self.checked_arithmetic = enabled;
+        self.rebuild_optimizer();
+    }
+
+    /// Let JIT programs run shell commands with `system ( c-addr u -- status )`
+    ///
+    /// Off by default: compiling a program that uses `system` fails with a
+    /// backend error until this is enabled.
+    pub fn set_allow_system(&mut self, enabled: bool) {
+        self.allow_system = enabled;
+    }
+
+    /// Compile JIT programs with `width`-sized cells
+    ///
+    /// Integer literals outside the cell range are rejected. So are programs
+    /// that take an address (variables, string literals) when the host's
+    /// pointers are wider than a cell.
+    pub fn set_cell_width(&mut self, width: CellWidth) {
+        self.cell_width = width;
+    }
+
+    /// Reject AOT programs that can push more than `depth` items on the data stack
+    ///
+    /// The limit covers the depth reached inside called words as well. JIT
+    /// code keeps stack items in registers and spill slots, so it has no
+    /// fixed-size stack to overflow and is not checked.
+    pub fn set_max_stack_depth(&mut self, depth: usize) {
+        self.max_stack_depth = depth;
+        self.rebuild_optimizer();
+    }
+
+    /// Record why the optimizer did or did not inline, fuse and eliminate
+    /// code; see [`optimization_notes`](Self::optimization_notes)
+    pub fn set_explain(&mut self, enabled: bool) {
+        self.explain = enabled;
+        self.rebuild_optimizer();
+    }
+
+    /// Notes from the most recent optimizer run, when explaining
+    pub fn optimization_notes(&self) -> &[OptimizationNote] {
+        self.optimizer.notes()
+    }
+
+    fn rebuild_optimizer(&mut self) {
+        let config = OptimizerConfig::new(self.optimization_level)
+            .with_checked_arithmetic(self.checked_arithmetic)
+            .with_max_stack_depth(self.max_stack_depth)
+            .with_explain(self.explain);
+        self.optimizer = Optimizer::with_config(config);
+    }
+
+    /// Also look for files named by INCLUDE and REQUIRE in `dir`, after the
+    /// including file's directory
+    pub fn add_include_path(&mut self, dir: impl Into<PathBuf>) {
+        self.includes.add_search_path(dir);
+    }
+
+    /// Keep lowered definitions across `compile` calls so unchanged words are
+    /// not converted again; disabling drops the cache
+    pub fn set_incremental(&mut self, enabled: bool) {
+        self.cache = enabled.then(|| self.cache.take().unwrap_or_default());
+    }
+
+    /// The incremental compilation cache, if enabled
+    pub fn cache(&self) -> Option<&CompileCache> {
+        self.cache.as_ref()
+    }
+
     /// Compile Forth source code
+    ///
+    /// Files it includes are resolved against the working directory.
     pub fn compile(&mut self, source: &str, mode: CompilationMode) -> Result<CompilationResult> {
+        self.compile_source(Source::Text(source), mode)
+    }
+
+    /// Compile the Forth file at `path`, along with the files it includes
+    pub fn compile_file(&mut self, path: &Path, mode: CompilationMode) -> Result<CompilationResult> {
+        std::fs::metadata(path).map_err(|e| CompileError::IoError(path.to_path_buf(), e))?;
+        self.compile_source(Source::File(path), mode)
+    }
+
+    /// Lower and optimize `source`, returning the optimizer IR without
+    /// generating code
+    pub fn optimized_ir(&mut self, source: &str) -> Result<ForthIR> {
+        let (_, ssa_functions) = self.run_frontend(Source::Text(source))?;
+        let ir = self.convert_to_ir(&ssa_functions)?;
+        self.run_optimizer(ir, &mut CompilationStats::default())
+    }
+
+    fn compile_source(&mut self, source: Source, mode: CompilationMode) -> Result<CompilationResult> {
         let start_time = Instant::now();
         let mut stats = CompilationStats::default();
 
@@ -91,16 +278,44 @@ impl CompilationPipeline {
         // Phase 1: Frontend (Parsing, Semantic Analysis, Type Inference, SSA)
         let frontend_start = Instant::now();
         let (program, ssa_functions) = self.run_frontend(source)?;
+        let warnings: Vec<String> = program.definitions.iter().filter_map(shadowing_warning).collect();
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
         stats.frontend_time_ms = frontend_start.elapsed().as_millis() as u64;
         stats.definitions_count = program.definitions.len();
+        if let Some(cache) = &self.cache {
+            stats.cache_hits = cache.hits();
+            stats.cache_misses = cache.misses();
+        }
 
         debug!("Frontend complete: {} definitions", stats.definitions_count);
 
+        if let Some(stage) = self.emit_ir {
+            let ir_dump = self.dump_ir(stage, &ssa_functions, &mut stats)?;
+            return Ok(CompilationResult {
+                mode,
+                compile_time_ms: start_time.elapsed().as_millis() as u64,
+                code_size: None,
+                output_path: None,
+                jit_result: None,
+                ir_dump: Some(ir_dump),
+                disassembly: None,
+                warnings,
+                stats,
+            });
+        }
+
         // Phase 2-4: Backend code generation
         // JIT mode: Skip optimization for faster compilation
         // AOT mode: Use full optimization pipeline
         let backend_start = Instant::now();
+        let mut disassembly = None;
         let result = match mode {
+            CompilationMode::JIT if self.disassemble => {
+                disassembly = Some(self.disassemble_jit(&ssa_functions)?);
+                (None, None, None)
+            }
             CompilationMode::JIT => {
                 debug!("JIT mode: Skipping optimization for fast compilation");
                 self.compile_jit(&ssa_functions, &mut stats)?
@@ -112,7 +327,7 @@ impl CompilationPipeline {
 
                 // Phase 3: Optimization
                 let optimization_start = Instant::now();
-                let optimized_ir = self.run_optimizer(ir)?;
+                let optimized_ir = self.run_optimizer(ir, &mut stats)?;
                 stats.optimization_time_ms = optimization_start.elapsed().as_millis() as u64;
                 stats.instructions_after = self.count_instructions(&optimized_ir);
 
@@ -125,7 +340,8 @@ impl CompilationPipeline {
                 self.compile_aot(&optimized_ir, &mut stats)?
             }
         };
-        stats.backend_time_ms = backend_start.elapsed().as_millis() as u64;
+        stats.backend_time_ms = (backend_start.elapsed().as_millis() as u64)
+            .saturating_sub(stats.optimization_time_ms);
 
         let compile_time_ms = start_time.elapsed().as_millis() as u64;
 
@@ -143,40 +359,69 @@ impl CompilationPipeline {
             code_size: result.0,
             output_path: result.1,
             jit_result: result.2,
+            ir_dump: None,
+            disassembly,
+            warnings,
             stats,
         })
     }
 
     /// Run the frontend pipeline
-    fn run_frontend(&self, source: &str) -> Result<(Program, Vec<SSAFunction>)> {
-        // Step 1: Parse
+    fn run_frontend(&mut self, source: Source) -> Result<(Program, Vec<SSAFunction>)> {
+        // Step 1: Parse, splicing in included files
         debug!("Parsing source code...");
-        let program = parse_program(source)
-            .map_err(|e| CompileError::ParseError(format!("{}", e)))?;
-
-        // Step 2: Semantic analysis
-        debug!("Running semantic analysis...");
-        analyze(&program)
-            .map_err(|e| CompileError::SemanticError(format!("{}", e)))?;
-
-        // Step 3: Type inference happens inside convert_to_ssa
-
-        // Step 4: Convert to SSA
-        debug!("Converting to SSA...");
-        let ssa_functions = convert_to_ssa(&program)
-            .map_err(|e| CompileError::SSAError(format!("{}", e)))?;
-
-        // Step 5: Validate SSA form
-        debug!("Validating SSA invariants...");
-        for func in &ssa_functions {
-            func.validate()
-                .map_err(|e| CompileError::SSAError(format!("SSA validation failed for {}: {}", func.name, e)))?;
+        self.includes.reset();
+        let program = match source {
+            Source::Text(text) => self.includes.parse_source(text),
+            Source::File(path) => self.includes.parse_file(path),
+        }
+        .map_err(CompileError::ParseError)?;
+
+        let mut ssa_functions = match &mut self.cache {
+            Some(cache) => cache.lower(&program)?,
+            None => lower_program(&program)?,
+        };
+
+        if self.optimization_level >= OptimizationLevel::Standard {
+            let hoisted: usize = ssa_functions
+                .iter_mut()
+                .map(|function| hoist_loop_invariants(function, self.checked_arithmetic))
+                .sum();
+            debug!("Hoisted {} loop-invariant instructions", hoisted);
         }
-        debug!("SSA validation passed for {} functions", ssa_functions.len());
 
         Ok((program, ssa_functions))
     }
 
+    /// Render the IR of the requested stage without generating code
+    fn dump_ir(&mut self, stage: IrStage, ssa_functions: &[SSAFunction], stats: &mut CompilationStats) -> Result<String> {
+        use std::fmt::Write;
+
+        let mut dump = String::new();
+
+        if stage == IrStage::Ssa {
+            for func in ssa_functions {
+                let _ = writeln!(dump, "{}", func);
+            }
+            return Ok(dump);
+        }
+
+        let ir = self.convert_to_ir(ssa_functions)?;
+        stats.instructions_before = self.count_instructions(&ir);
+
+        if matches!(stage, IrStage::Lowered | IrStage::Both) {
+            let _ = writeln!(dump, "\\ IR after lowering\n{}", ir);
+        }
+
+        if matches!(stage, IrStage::Optimized | IrStage::Both) {
+            let optimized = self.run_optimizer(ir, stats)?;
+            stats.instructions_after = self.count_instructions(&optimized);
+            let _ = writeln!(dump, "\\ IR after optimization\n{}", optimized);
+        }
+
+        Ok(dump)
+    }
+
     /// Convert frontend SSA to optimizer IR
     fn convert_to_ir(&self, ssa_functions: &[SSAFunction]) -> Result<ForthIR> {
         debug!("Converting SSA to optimizer IR...");
@@ -190,8 +435,29 @@ impl CompilationPipeline {
 
             // Create a word definition for this function
             use fastforth_optimizer::ir::WordDef;
-            let word_def = WordDef::new(func.name.clone(), instructions);
+            let mut word_def = WordDef::new(func.name.clone(), instructions);
+            // Top-level code is the program's entry point, so dead word
+            // elimination must treat it as a root
+            word_def.is_exported = func.name == MAIN_FUNCTION;
+            // The SSA signature is exact, unlike an estimate from the
+            // instructions once the word branches or reads variables
+            let results = func.blocks.iter()
+                .flat_map(|block| &block.instructions)
+                .find_map(|inst| match inst {
+                    SSAInstruction::Return { values } => Some(values.len()),
+                    _ => None,
+                })
+                .unwrap_or(0);
+            let clamp = |n: usize| u8::try_from(n).unwrap_or(u8::MAX);
+            word_def.stack_effect = StackEffect::new(clamp(func.parameters.len()), clamp(results));
             ir.add_word(word_def);
+
+            // Variable addresses are lowered to calls; record which ones are slots
+            for inst in func.blocks.iter().flat_map(|block| &block.instructions) {
+                if let SSAInstruction::VariableAddr { name, .. } = inst {
+                    ir.variables.insert(name.clone());
+                }
+            }
         }
 
         Ok(ir)
@@ -199,93 +465,26 @@ impl CompilationPipeline {
 
     /// Convert a single SSA function to IR instructions
     fn ssa_to_instructions(&self, func: &SSAFunction) -> Result<Vec<Instruction>> {
-        use fastforth_frontend::ssa::{SSAInstruction, BinaryOperator, UnaryOperator};
-
-        let mut instructions = Vec::new();
-
-        // Process each basic block
-        for block in &func.blocks {
-            // Add a label for this block
-            instructions.push(Instruction::Label(format!("bb{}", block.id.0)));
-
-            // Convert each SSA instruction
-            for ssa_inst in &block.instructions {
-                match ssa_inst {
-                    SSAInstruction::LoadInt { value, .. } => {
-                        instructions.push(Instruction::Literal(*value));
-                    }
-                    SSAInstruction::LoadFloat { value, .. } => {
-                        instructions.push(Instruction::FloatLiteral(*value));
-                    }
-                    SSAInstruction::BinaryOp { op, .. } => {
-                        let inst = match op {
-                            BinaryOperator::Add => Instruction::Add,
-                            BinaryOperator::Sub => Instruction::Sub,
-                            BinaryOperator::Mul => Instruction::Mul,
-                            BinaryOperator::Div => Instruction::Div,
-                            BinaryOperator::Mod => Instruction::Mod,
-                            BinaryOperator::Lt => Instruction::Lt,
-                            BinaryOperator::Gt => Instruction::Gt,
-                            BinaryOperator::Le => Instruction::Le,
-                            BinaryOperator::Ge => Instruction::Ge,
-                            BinaryOperator::Eq => Instruction::Eq,
-                            BinaryOperator::Ne => Instruction::Ne,
-                            BinaryOperator::And => Instruction::And,
-                            BinaryOperator::Or => Instruction::Or,
-                        };
-                        instructions.push(inst);
-                    }
-                    SSAInstruction::UnaryOp { op, .. } => {
-                        let inst = match op {
-                            UnaryOperator::Negate => Instruction::Neg,
-                            UnaryOperator::Not => Instruction::Not,
-                            UnaryOperator::Abs => Instruction::Abs,
-                        };
-                        instructions.push(inst);
-                    }
-                    SSAInstruction::Call { name, .. } => {
-                        instructions.push(Instruction::Call(name.clone()));
-                    }
-                    SSAInstruction::Return { .. } => {
-                        instructions.push(Instruction::Return);
-                    }
-                    SSAInstruction::Branch { true_block, false_block, .. } => {
-                        // Conditional branch: branch if not, then fall through or jump
-                        instructions.push(Instruction::BranchIfNot(true_block.0));
-                        instructions.push(Instruction::Branch(false_block.0));
-                    }
-                    SSAInstruction::Jump { target } => {
-                        instructions.push(Instruction::Branch(target.0));
-                    }
-                    SSAInstruction::Load { .. } => {
-                        instructions.push(Instruction::Load);
-                    }
-                    SSAInstruction::Store { .. } => {
-                        instructions.push(Instruction::Store);
-                    }
-                    SSAInstruction::Phi { .. } => {
-                        // Phi nodes are handled by SSA construction and don't need runtime code
-                        // They're just for data flow analysis
-                        continue;
-                    }
-                    _ => {
-                        warn!("Unhandled SSA instruction: {:?}", ssa_inst);
-                        continue;
-                    }
-                }
-            }
-        }
-
-        Ok(instructions)
+        StackLowering::new(func).lower()
     }
 
     /// Run the optimizer
-    fn run_optimizer(&mut self, ir: ForthIR) -> Result<ForthIR> {
+    fn run_optimizer(&mut self, ir: ForthIR, stats: &mut CompilationStats) -> Result<ForthIR> {
         debug!("Running optimizer with level {:?}...", self.optimization_level);
 
-        let optimized = self.optimizer.optimize(ir)
+        let (optimized, pass_stats) = self.optimizer.optimize_with_stats(ir)
             .map_err(|e| CompileError::OptimizationError(format!("{}", e)))?;
 
+        for record in pass_stats.passes.iter().filter(|r| !r.is_skipped()) {
+            debug!(
+                "  {}: {} -> {} instructions",
+                record.pass.name(),
+                record.instructions_before,
+                record.instructions_after
+            );
+        }
+        stats.pass_stats = pass_stats;
+
         Ok(optimized)
     }
 
@@ -304,64 +503,603 @@ impl CompilationPipeline {
     fn compile_jit(&self, ssa_functions: &[SSAFunction], stats: &mut CompilationStats) -> Result<(Option<usize>, Option<String>, Option<i64>)> {
         debug!("Compiling and executing (JIT)...");
 
-        // Use the backend crate's Cranelift compiler
-        use backend::cranelift::{CraneliftBackend, CraneliftSettings};
+        let backend = jit_compile(ssa_functions, false, self.checked_arithmetic, self.allow_system, self.cell_width)?;
+        let result = call_jit(&backend, MAIN_FUNCTION)?;
+
+        Ok((None, None, Some(result)))
+    }
+
+    /// Compile with JIT and collect the disassembly of every function without executing
+    fn disassemble_jit(&self, ssa_functions: &[SSAFunction]) -> Result<String> {
+        let backend = jit_compile(ssa_functions, true, self.checked_arithmetic, self.allow_system, self.cell_width)?;
 
-        if ssa_functions.is_empty() {
-            return Ok((None, None, Some(0)));
+        let mut text = String::new();
+        for func in ssa_functions {
+            let body = backend.disassemble(&func.name)
+                .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+            text.push_str(&format!("function {}:\n{}\n", func.name, body));
         }
 
-        // Create Cranelift backend
-        let settings = CraneliftSettings {
-            opt_level: 1,
-            debug_info: false,
-            target_triple: None,
-            enable_verification: cfg!(debug_assertions),
+        // SAFETY: no compiled function was called
+        unsafe { backend.free_memory() };
+        Ok(text)
+    }
+
+    /// Count total instructions in IR
+    fn count_instructions(&self, ir: &ForthIR) -> usize {
+        ir.instruction_count()
+    }
+}
+
+/// Run semantic analysis and SSA conversion on a parsed program
+pub(crate) fn lower_program(program: &Program) -> Result<Vec<SSAFunction>> {
+    lower_program_with(program, |_| true)
+}
+
+/// Lower a parsed program, converting only the definitions selected by
+/// `convert` (top-level code is always converted)
+pub(crate) fn lower_program_with(
+    program: &Program,
+    convert: impl Fn(&Definition) -> bool,
+) -> Result<Vec<SSAFunction>> {
+    // Step 2: Semantic analysis
+    debug!("Running semantic analysis...");
+    analyze(program)
+        .map_err(CompileError::SemanticError)?;
+
+    // Step 3: Type inference happens inside convert_to_ssa
+
+    // Step 4: Convert to SSA
+    debug!("Converting to SSA...");
+    let ssa_functions = convert_to_ssa_filtered(program, convert)
+        .map_err(CompileError::SSAError)?;
+
+    // Step 5: Verify SSA form before it reaches codegen
+    debug!("Verifying SSA invariants...");
+    for func in &ssa_functions {
+        func.verify()
+            .map_err(|e| CompileError::InternalError(format!("SSA verification failed: {}", e)))?;
+    }
+    debug!("SSA verification passed for {} functions", ssa_functions.len());
+
+    Ok(ssa_functions)
+}
+
+/// Lowers one SSA function to stack code. SSA operands are registers, so
+/// the stack shuffles the source performed (`dup`, `swap`, ...) are gone by
+/// now; this tracks which register sits in each stack slot and emits the
+/// shuffles that bring operands to the top in the order they are consumed.
+struct StackLowering<'a> {
+    func: &'a SSAFunction,
+    /// Registers live at the end of each block, phi operands included
+    live_out: HashMap<BlockId, HashSet<Register>>,
+    /// Registers live on entry to each block, phi results excluded
+    live_in: HashMap<BlockId, HashSet<Register>>,
+    /// Stack on entry to each block, bottom first
+    layouts: HashMap<BlockId, Vec<Register>>,
+    /// Blocks that reshape the stack along a conditional edge
+    edge_blocks: Vec<Vec<Instruction>>,
+    next_block: usize,
+}
+
+impl<'a> StackLowering<'a> {
+    fn new(func: &'a SSAFunction) -> Self {
+        let next_block = func.blocks.iter().map(|block| block.id.0 + 1).max().unwrap_or(0);
+        let mut lowering = Self {
+            func,
+            live_out: HashMap::new(),
+            live_in: HashMap::new(),
+            layouts: HashMap::new(),
+            edge_blocks: Vec::new(),
+            next_block,
         };
+        lowering.compute_liveness();
+        lowering
+    }
 
-        let mut backend = CraneliftBackend::new(settings)
-            .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+    fn block(&self, id: BlockId) -> Result<&'a BasicBlock> {
+        self.func.blocks.iter().find(|block| block.id == id).ok_or_else(|| {
+            CompileError::InternalError(format!("Block {} of '{}' does not exist", id, self.func.name))
+        })
+    }
+
+    fn successors(block: &BasicBlock) -> Vec<BlockId> {
+        match block.instructions.last() {
+            Some(SSAInstruction::Branch { true_block, false_block, .. }) => vec![*true_block, *false_block],
+            Some(SSAInstruction::Jump { target }) => vec![*target],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Phi results of `to` paired with the register each takes along the edge from `from`
+    fn phis(&self, from: BlockId, to: &BasicBlock) -> Vec<(Register, Option<Register>)> {
+        to.instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                SSAInstruction::Phi { dest, incoming } => {
+                    Some((*dest, incoming.iter().find(|(pred, _)| *pred == from).map(|(_, reg)| *reg)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn compute_liveness(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in self.func.blocks.iter().rev() {
+                let mut live: HashSet<Register> = HashSet::new();
+                for succ in Self::successors(block) {
+                    if let Some(succ) = self.func.blocks.iter().find(|b| b.id == succ) {
+                        live.extend(self.live_in.get(&succ.id).into_iter().flatten());
+                        live.extend(self.phis(block.id, succ).into_iter().filter_map(|(_, reg)| reg));
+                    }
+                }
+                self.live_out.insert(block.id, live.clone());
+
+                for inst in block.instructions.iter().rev() {
+                    for reg in destination_registers(inst) {
+                        live.remove(&reg);
+                    }
+                    if !matches!(inst, SSAInstruction::Phi { .. }) {
+                        live.extend(used_registers(inst));
+                    }
+                }
+                if self.live_in.get(&block.id) != Some(&live) {
+                    self.live_in.insert(block.id, live);
+                    changed = true;
+                }
+            }
+        }
+    }
 
-        // Prepare (name, function) pairs
-        let functions_with_names: Vec<(String, &SSAFunction)> = ssa_functions
+    /// Blocks in reverse postorder, so every block but a loop header is
+    /// reached after one of its predecessors
+    fn reverse_postorder(&self) -> Result<Vec<&'a BasicBlock>> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        let mut work = vec![(self.block(self.func.entry_block)?, false)];
+        while let Some((block, done)) = work.pop() {
+            if done {
+                order.push(block);
+                continue;
+            }
+            if !visited.insert(block.id) {
+                continue;
+            }
+            work.push((block, true));
+            for succ in Self::successors(block).into_iter().rev() {
+                if !visited.contains(&succ) {
+                    work.push((self.block(succ)?, false));
+                }
+            }
+        }
+        order.reverse();
+        Ok(order)
+    }
+
+    fn lower(mut self) -> Result<Vec<Instruction>> {
+        self.layouts.insert(self.func.entry_block, self.func.parameters.clone());
+
+        let mut lowered = HashMap::new();
+        for block in self.reverse_postorder()? {
+            let code = self.lower_block(block)?;
+            lowered.insert(block.id, code);
+        }
+
+        // Keep the source's block order, then the edge blocks
+        let mut instructions = Vec::new();
+        for block in &self.func.blocks {
+            instructions.extend(lowered.remove(&block.id).into_iter().flatten());
+        }
+        instructions.extend(self.edge_blocks.into_iter().flatten());
+        Ok(instructions)
+    }
+
+    fn lower_block(&mut self, block: &'a BasicBlock) -> Result<Vec<Instruction>> {
+        let layout = self.layouts.get(&block.id).cloned().ok_or_else(|| {
+            CompileError::InternalError(format!(
+                "Block {} of '{}' is reached before any predecessor",
+                block.id, self.func.name
+            ))
+        })?;
+        let mut frame = StackFrame::new(layout);
+        frame.code.push(Instruction::Label(block_label(BlockRef(block.id.0))));
+
+        let body: Vec<&SSAInstruction> = block
+            .instructions
             .iter()
-            .map(|func| (func.name.clone(), func))
+            .filter(|inst| !matches!(inst, SSAInstruction::Phi { .. }))
             .collect();
+        let mut last_use = HashMap::new();
+        for (i, inst) in body.iter().enumerate() {
+            for reg in used_registers(inst) {
+                last_use.insert(reg, i);
+            }
+        }
+        let live_out = self.live_out[&block.id].clone();
 
-        // Two-pass compilation
-        backend.declare_all_functions(&functions_with_names)
-            .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+        for (i, inst) in body.iter().enumerate() {
+            let needed = |reg: Register| {
+                live_out.contains(&reg) || last_use.get(&reg).is_some_and(|&last| last > i)
+            };
 
-        for (name, func) in &functions_with_names {
-            backend.compile_function(func, name)
-                .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+            match inst {
+                SSAInstruction::Return { values } => {
+                    frame.arrange(values)?;
+                    frame.code.push(Instruction::Return);
+                }
+                SSAInstruction::Jump { target } => {
+                    let arrival = self.arrival(block.id, *target, &frame.stack, false)?;
+                    frame.arrange(&arrival)?;
+                    frame.code.push(Instruction::Branch(BlockRef(target.0)));
+                }
+                SSAInstruction::Branch { condition, true_block, false_block } => {
+                    frame.bring(&[*condition], needed)?;
+                    frame.stack.pop();
+                    let then_target = self.edge_target(block.id, *true_block, &frame.stack)?;
+                    let else_target = self.edge_target(block.id, *false_block, &frame.stack)?;
+                    // The condition was brought to the top of the stack
+                    frame.code.push(Instruction::BranchIfElse(then_target, else_target));
+                }
+                _ => {
+                    let (operands, results, lowered) = Self::lower_instruction(inst);
+                    frame.bring(&operands, needed)?;
+                    frame.stack.truncate(frame.stack.len() - operands.len());
+                    frame.stack.extend(&results);
+                    frame.code.push(lowered);
+
+                    // Results nothing reads are dropped straight away
+                    while let Some(&top) = frame.stack.last() {
+                        if !results.contains(&top) || needed(top) {
+                            break;
+                        }
+                        frame.drop_at(0)?;
+                    }
+                }
+            }
         }
 
-        backend.finalize_all()
-            .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+        Ok(frame.code)
+    }
+
+    /// Target of the edge `from -> to` for a conditional branch leaving `stack`:
+    /// `to` itself, or a new block that reshapes the stack before jumping there
+    fn edge_target(&mut self, from: BlockId, to: BlockId, stack: &[Register]) -> Result<BlockRef> {
+        let arrival = self.arrival(from, to, stack, true)?;
+        if arrival == stack {
+            return Ok(BlockRef(to.0));
+        }
 
-        // Execute the last function (usually :main)
-        let func_name = &ssa_functions.last().unwrap().name;
-        let main_func_ptr = backend.get_function(func_name)
-            .ok_or_else(|| CompileError::BackendError("Failed to get compiled function".to_string()))?;
+        let id = BlockRef(self.next_block);
+        self.next_block += 1;
+        let mut frame = StackFrame::new(stack.to_vec());
+        frame.code.push(Instruction::Label(block_label(id)));
+        frame.arrange(&arrival)?;
+        frame.code.push(Instruction::Branch(BlockRef(to.0)));
+        self.edge_blocks.push(frame.code);
+        Ok(id)
+    }
 
-        // Call function (all Forth functions return i64)
-        type ForthFn = unsafe extern "C" fn() -> i64;
-        let forth_fn: ForthFn = unsafe { std::mem::transmute(main_func_ptr) };
-        let result = unsafe { forth_fn() };
+    /// Stack the edge `from -> to` must carry, bottom first, with each phi
+    /// result standing for the register it takes along this edge. The first
+    /// edge lowered into a block decides its layout.
+    fn arrival(
+        &mut self,
+        from: BlockId,
+        to: BlockId,
+        stack: &[Register],
+        conditional: bool,
+    ) -> Result<Vec<Register>> {
+        let target = self.block(to)?;
+        let phis = self.phis(from, target);
+        let incoming = |reg: Register| -> Result<Register> {
+            match phis.iter().find(|(dest, _)| *dest == reg) {
+                Some((_, Some(source))) => Ok(*source),
+                Some((_, None)) => Err(CompileError::InternalError(format!(
+                    "Phi {} in {} has no operand for {}",
+                    reg, to, from
+                ))),
+                None => Ok(reg),
+            }
+        };
 
-        Ok((None, None, Some(result)))
+        if !self.layouts.contains_key(&to) {
+            let layout = if phis.is_empty() && conditional {
+                // The other successor sees the same stack, so leave it be
+                stack.to_vec()
+            } else {
+                // Order what `to` needs by where its sources sit now
+                let mut entries: Vec<(Option<usize>, Register)> = self.live_in[&to]
+                    .iter()
+                    .copied()
+                    .chain(phis.iter().map(|(dest, _)| *dest))
+                    .map(|reg| {
+                        let source = incoming(reg)?;
+                        Ok((stack.iter().rposition(|r| *r == source), reg))
+                    })
+                    .collect::<Result<_>>()?;
+                entries.sort_by_key(|&(position, reg)| (position, reg.0));
+                entries.into_iter().map(|(_, reg)| reg).collect()
+            };
+            self.layouts.insert(to, layout);
+        }
+
+        self.layouts[&to].iter().map(|reg| incoming(*reg)).collect()
     }
 
-    /// Count total instructions in IR
-    fn count_instructions(&self, ir: &ForthIR) -> usize {
-        ir.instruction_count()
+    /// IR for a non-terminator, with the registers it consumes and produces in stack order
+    fn lower_instruction(inst: &SSAInstruction) -> (Vec<Register>, Vec<Register>, Instruction) {
+        use fastforth_frontend::ssa::{BinaryOperator, UnaryOperator};
+
+        let lowered = match inst {
+            SSAInstruction::LoadInt { value, .. } => Instruction::Literal(*value),
+            SSAInstruction::LoadFloat { value, .. } => Instruction::FloatLiteral(*value),
+            SSAInstruction::BinaryOp { op, .. } => match op {
+                BinaryOperator::Add => Instruction::Add,
+                BinaryOperator::Sub => Instruction::Sub,
+                BinaryOperator::Mul => Instruction::Mul,
+                BinaryOperator::Div => Instruction::Div,
+                BinaryOperator::Mod => Instruction::Mod,
+                BinaryOperator::Min => Instruction::Min,
+                BinaryOperator::Max => Instruction::Max,
+                BinaryOperator::Lt => Instruction::Lt,
+                BinaryOperator::Gt => Instruction::Gt,
+                BinaryOperator::Le => Instruction::Le,
+                BinaryOperator::Ge => Instruction::Ge,
+                BinaryOperator::Eq => Instruction::Eq,
+                BinaryOperator::Ne => Instruction::Ne,
+                BinaryOperator::ULt => Instruction::ULt,
+                BinaryOperator::UGt => Instruction::UGt,
+                BinaryOperator::And => Instruction::And,
+                BinaryOperator::Or => Instruction::Or,
+                BinaryOperator::Xor => Instruction::Xor,
+                BinaryOperator::Shl => Instruction::Shl,
+                BinaryOperator::Shr => Instruction::Shr,
+                BinaryOperator::Sar => Instruction::Sar,
+            },
+            SSAInstruction::UnaryOp { op, .. } => match op {
+                UnaryOperator::Negate => Instruction::Neg,
+                UnaryOperator::Not => Instruction::Not,
+                UnaryOperator::Abs => Instruction::Abs,
+                UnaryOperator::ZeroEq => Instruction::ZeroEq,
+                UnaryOperator::ZeroLt => Instruction::ZeroLt,
+                UnaryOperator::ZeroGt => Instruction::ZeroGt,
+                UnaryOperator::IncOne => Instruction::IncOne,
+                UnaryOperator::DecOne => Instruction::DecOne,
+                UnaryOperator::MulTwo => Instruction::MulTwo,
+                UnaryOperator::DivTwo => Instruction::DivTwo,
+            },
+            SSAInstruction::Call { name, .. } => Instruction::call(name),
+            SSAInstruction::VariableAddr { name, .. } => Instruction::call(name),
+            SSAInstruction::Load { .. } => Instruction::Load,
+            SSAInstruction::Store { address, value, .. } => {
+                // ( x addr -- ): the address is on top
+                return (vec![*value, *address], Vec::new(), Instruction::Store);
+            }
+            // The IR has no instructions for these, so they call the runtime words
+            SSAInstruction::LoadString { .. } => Instruction::call("s\""),
+            SSAInstruction::ExecutionToken { word, .. } => Instruction::call(format!("['] {}", word)),
+            SSAInstruction::FFICall { function, .. } => Instruction::call(function),
+            SSAInstruction::FileOpen { .. } => Instruction::call("open-file"),
+            SSAInstruction::FileRead { .. } => Instruction::call("read-file"),
+            SSAInstruction::FileWrite { .. } => Instruction::call("write-file"),
+            SSAInstruction::FileClose { .. } => Instruction::call("close-file"),
+            SSAInstruction::FileDelete { .. } => Instruction::call("delete-file"),
+            SSAInstruction::FileStatus { .. } => Instruction::call("file-status"),
+            SSAInstruction::FileCreate { .. } => Instruction::call("create-file"),
+            SSAInstruction::SystemCall { .. } => Instruction::call("system"),
+            SSAInstruction::Phi { .. }
+            | SSAInstruction::Branch { .. }
+            | SSAInstruction::Jump { .. }
+            | SSAInstruction::Return { .. } => unreachable!("lowered by the block"),
+        };
+        (used_registers(inst), destination_registers(inst), lowered)
     }
 }
 
+/// Registers on the data stack while lowering a block, and the code so far
+struct StackFrame {
+    stack: Vec<Register>,
+    code: Vec<Instruction>,
+}
+
+impl StackFrame {
+    fn new(stack: Vec<Register>) -> Self {
+        Self { stack, code: Vec::new() }
+    }
+
+    fn depth_operand(depth: usize) -> Result<u8> {
+        u8::try_from(depth)
+            .map_err(|_| CompileError::InternalError(format!("Stack shuffle {} items deep", depth)))
+    }
+
+    /// Copy the item `depth` below the top onto the top
+    fn pick(&mut self, depth: usize) -> Result<()> {
+        self.code.push(match depth {
+            0 => Instruction::Dup,
+            1 => Instruction::Over,
+            _ => Instruction::Pick(Self::depth_operand(depth)?),
+        });
+        self.stack.push(self.stack[self.stack.len() - 1 - depth]);
+        Ok(())
+    }
+
+    /// Move the item `depth` below the top to the top
+    fn roll(&mut self, depth: usize) -> Result<()> {
+        let position = self.stack.len() - 1 - depth;
+        let reg = self.stack[position];
+        // Moving a value past copies of itself changes nothing
+        if self.stack[position..].iter().all(|r| *r == reg) {
+            return Ok(());
+        }
+        self.code.push(match depth {
+            1 => Instruction::Swap,
+            2 => Instruction::Rot,
+            _ => Instruction::Roll(Self::depth_operand(depth)?),
+        });
+        self.stack.remove(position);
+        self.stack.push(reg);
+        Ok(())
+    }
+
+    /// Discard the item `depth` below the top
+    fn drop_at(&mut self, depth: usize) -> Result<()> {
+        match depth {
+            0 => self.code.push(Instruction::Drop),
+            1 => self.code.push(Instruction::Nip),
+            _ => {
+                self.roll(depth)?;
+                self.code.push(Instruction::Drop);
+                self.stack.pop();
+                return Ok(());
+            }
+        }
+        self.stack.remove(self.stack.len() - 1 - depth);
+        Ok(())
+    }
+
+    /// Put `operands` on top of the stack in order, copying those that are
+    /// `needed` afterwards and moving the rest
+    fn bring(&mut self, operands: &[Register], needed: impl Fn(Register) -> bool) -> Result<()> {
+        if self.stack.ends_with(operands) && !operands.iter().any(|reg| needed(*reg)) {
+            return Ok(());
+        }
+        for (k, &operand) in operands.iter().enumerate() {
+            // Skip the operands already brought up
+            let below = self.stack.len() - k;
+            let position = self.stack[..below].iter().rposition(|r| *r == operand).ok_or_else(|| {
+                CompileError::InternalError(format!("Register {} is not on the stack", operand))
+            })?;
+            let depth = self.stack.len() - 1 - position;
+            if operands[k + 1..].contains(&operand) || needed(operand) {
+                self.pick(depth)?;
+            } else {
+                self.roll(depth)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reshape the stack into exactly `target`, bottom first
+    fn arrange(&mut self, target: &[Register]) -> Result<()> {
+        let count = |regs: &[Register], reg: Register| regs.iter().filter(|r| **r == reg).count();
+
+        // Drop whatever the target has no use for, top first
+        for position in (0..self.stack.len()).rev() {
+            let reg = self.stack[position];
+            if count(&self.stack, reg) > count(target, reg) {
+                self.drop_at(self.stack.len() - 1 - position)?;
+            }
+        }
+
+        // Keep the common bottom of the stack, and build the rest on top of
+        // the remaining original items, moving each at its last use
+        let kept = self.stack.iter().zip(target).take_while(|(a, b)| a == b).count();
+        let mut original = self.stack.len();
+        for (i, &reg) in target.iter().enumerate().skip(kept) {
+            let remaining = count(&target[i + 1..], reg);
+            let position = match self.stack[kept..original].iter().rposition(|r| *r == reg) {
+                Some(offset) if count(&self.stack[kept..original], reg) > remaining => {
+                    original -= 1;
+                    let depth = self.stack.len() - 1 - (kept + offset);
+                    self.roll(depth)?;
+                    continue;
+                }
+                _ => self.stack[..original].iter().rposition(|r| *r == reg),
+            };
+            let position = position.ok_or_else(|| {
+                CompileError::InternalError(format!("Register {} is not on the stack", reg))
+            })?;
+            self.pick(self.stack.len() - 1 - position)?;
+        }
+
+        debug_assert_eq!(self.stack, target);
+        Ok(())
+    }
+}
+
+/// Compile SSA functions into a finalized JIT module, optionally recording disassembly
+pub(crate) fn jit_compile(
+    ssa_functions: &[SSAFunction],
+    disassemble: bool,
+    checked_arithmetic: bool,
+    allow_system: bool,
+    cell_width: CellWidth,
+) -> Result<CraneliftBackend> {
+    // Create Cranelift backend
+    let settings = CraneliftSettings {
+        opt_level: 1,
+        debug_info: false,
+        target_triple: None,
+        enable_verification: cfg!(debug_assertions),
+        checked_arithmetic,
+        allow_system,
+        cell_width,
+    };
+
+    let mut backend = CraneliftBackend::new(settings)
+        .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+    backend.set_keep_disassembly(disassemble);
+
+    // Prepare (name, function) pairs
+    let functions_with_names: Vec<(String, &SSAFunction)> = ssa_functions
+        .iter()
+        .map(|func| (func.name.clone(), func))
+        .collect();
+
+    // Two-pass compilation
+    backend.declare_all_functions(&functions_with_names)
+        .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+
+    for (name, func) in &functions_with_names {
+        backend.compile_function(func, name)
+            .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+    }
+
+    backend.finalize_all()
+        .map_err(|e| CompileError::BackendError(format!("{}", e)))?;
+
+    Ok(backend)
+}
+
+/// Number of values a lowered function leaves on the stack
+pub(crate) fn result_depth(func: &SSAFunction) -> usize {
+    func.blocks
+        .iter()
+        .flat_map(|block| block.instructions.iter())
+        .filter_map(|inst| match inst {
+            fastforth_frontend::ssa::SSAInstruction::Return { values } => Some(values.len()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Call a compiled zero-argument word and return its result
+pub(crate) fn call_jit(backend: &CraneliftBackend, name: &str) -> Result<i64> {
+    let func_ptr = backend.get_function(name)
+        .ok_or_else(|| CompileError::BackendError("Failed to get compiled function".to_string()))?;
+
+    // Call function (all Forth functions return i64)
+    type ForthFn = unsafe extern "C" fn() -> i64;
+    let forth_fn: ForthFn = unsafe { std::mem::transmute(func_ptr) };
+    take_runtime_fault();
+    let result = unsafe { forth_fn() };
+
+    // Guarded operations (e.g. division by zero) report faults out-of-band
+    if let Some(fault) = take_runtime_fault() {
+        return Err(CompileError::RuntimeError(fault.to_string()));
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fastforth_frontend::ForthError;
 
     #[test]
     fn test_pipeline_creation() {
@@ -379,4 +1117,114 @@ mod tests {
         // We expect this to fail for now, but it should be a compilation error, not a panic
         assert!(result.is_ok() || result.is_err());
     }
+
+    #[test]
+    fn test_lowering_restores_stack_shuffles() {
+        let source = ": square dup * ; : under ( a b -- b a a ) swap dup ; \
+                      : clamp ( n -- n ) dup 0 < if drop 0 then ; 1 2 under square clamp";
+        let program = fastforth_frontend::parse_program(source).unwrap();
+        let functions = lower_program(&program).unwrap();
+        let ir = CompilationPipeline::new(OptimizationLevel::None).convert_to_ir(&functions).unwrap();
+        ir.verify().unwrap();
+
+        // SSA reads registers, so the shuffles have to be rebuilt
+        let square = ir.get_word("square").unwrap();
+        assert_eq!(
+            square.instructions,
+            [Instruction::Label(block_label(BlockRef(0))), Instruction::Dup, Instruction::Mul, Instruction::Return]
+        );
+        assert_eq!(ir.get_word("under").unwrap().stack_effect, StackEffect::new(2, 3));
+
+        // Both arms of the IF leave one value for the merge
+        let clamp = ir.get_word("clamp").unwrap();
+        assert_eq!(clamp.stack_effect, StackEffect::new(1, 1));
+        assert!(clamp.instructions.iter().any(|inst| matches!(inst, Instruction::BranchIfElse(..))));
+    }
+
+    #[test]
+    fn test_if_lowers_to_one_two_way_branch() {
+        use fastforth_frontend::ssa::SSAInstruction;
+
+        let program = fastforth_frontend::parse_program(": f ( n -- n ) dup 0 < if drop 0 then ;").unwrap();
+        let functions = lower_program(&program).unwrap();
+        let (then_block, else_block) = functions[0].blocks.iter()
+            .flat_map(|block| &block.instructions)
+            .find_map(|inst| match inst {
+                SSAInstruction::Branch { true_block, false_block, .. } => Some((BlockRef(true_block.0), BlockRef(false_block.0))),
+                _ => None,
+            })
+            .unwrap();
+
+        let pipeline = CompilationPipeline::new(OptimizationLevel::Standard);
+        let ir = pipeline.convert_to_ir(&functions).unwrap();
+        let ir = ForthIR::from_json(&ir.to_json()).unwrap();
+
+        let conditional = |ir: &ForthIR| -> Vec<Instruction> {
+            ir.get_word("f").unwrap().instructions.iter()
+                .filter(|inst| matches!(
+                    inst,
+                    Instruction::BranchIf(_) | Instruction::BranchIfNot(_) | Instruction::BranchIfElse(..)
+                ))
+                .cloned()
+                .collect()
+        };
+        assert_eq!(conditional(&ir), [Instruction::BranchIfElse(then_block, else_block)]);
+
+        // The branch and its targets survive optimization
+        let mut stats = CompilationStats::default();
+        let mut pipeline = pipeline;
+        let optimized = pipeline.run_optimizer(ir, &mut stats).unwrap();
+        let word = optimized.get_word("f").unwrap();
+        assert_eq!(conditional(&optimized), [Instruction::BranchIfElse(then_block, else_block)]);
+        for target in [then_block, else_block] {
+            let label = block_label(target);
+            assert!(word.instructions.iter().any(|inst| matches!(inst, Instruction::Label(l) if *l == label)), "{}", label);
+        }
+        optimized.verify().unwrap();
+    }
+
+    #[test]
+    fn test_deep_stack_handled_safely() {
+        let source = format!("{}{}", "1 ".repeat(300), "+ ".repeat(299));
+
+        let mut pipeline = CompilationPipeline::new(OptimizationLevel::Basic);
+        let result = pipeline.compile(&source, CompilationMode::JIT).unwrap();
+        assert_eq!(result.jit_result, Some(300));
+
+        // The AOT stack has a fixed size, so the optimizer rejects the program
+        let err = pipeline.compile(&source, CompilationMode::AOT).unwrap_err();
+        assert!(err.to_string().contains("Stack overflow"), "{}", err);
+
+        pipeline.set_max_stack_depth(300);
+        pipeline.compile(&source, CompilationMode::AOT).unwrap();
+    }
+
+    #[test]
+    fn test_stack_underflow_rejected_before_execution() {
+        // JIT code has no stack pointer to run below its base: every
+        // underflow is caught while converting to SSA
+        for source in ["drop", ": f ( a -- ) drop ; f", ": g + ; 1 g"] {
+            let mut pipeline = CompilationPipeline::new(OptimizationLevel::Basic);
+            match pipeline.compile(source, CompilationMode::JIT) {
+                Err(CompileError::SSAError(err)) => {
+                    assert!(matches!(err, ForthError::StackUnderflow { .. }), "{}", err)
+                }
+                other => panic!("{}: expected an underflow error, got {:?}", source, other.map(|r| r.jit_result)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_recompile_hits_cache() {
+        let mut pipeline = CompilationPipeline::new(OptimizationLevel::Basic);
+        pipeline.set_incremental(true);
+        let source = ": double ( n -- n ) 2 * ; : quad ( n -- n ) double double ; 3 quad";
+
+        let first = pipeline.compile(source, CompilationMode::JIT).unwrap();
+        assert_eq!((first.stats.cache_hits, first.stats.cache_misses), (0, 2));
+
+        let second = pipeline.compile(source, CompilationMode::JIT).unwrap();
+        assert_eq!((second.stats.cache_hits, second.stats.cache_misses), (2, 0));
+        assert_eq!(first.jit_result, second.jit_result);
+    }
 }
@@ -0,0 +1,371 @@
+//! Interactive session state
+//!
+//! A [`Session`] keeps the dictionary and the data stack alive between
+//! evaluations, so a word defined on one REPL line can be used on the next.
+//!
+//! Each evaluation recompiles the accumulated dictionary together with the new
+//! line, with the current stack pushed as literals in front of the line's
+//! top-level code. The session state is only replaced once the line has
+//! compiled and run successfully, so a failing line leaves it untouched.
+
+use crate::error::{CompileError, Result};
+use crate::pipeline::{call_jit, jit_compile, lower_program, result_depth};
+use backend::cranelift::{replace_output_sink, CellWidth, CraneliftBackend, OutputSink};
+use fastforth_frontend::ast::SourceLocation;
+use fastforth_frontend::semantic::shadowing_warning;
+use fastforth_frontend::stack_effects::definition_effects;
+use fastforth_frontend::{builtins, parse_program, Definition, Program, StackEffect, Word, MAIN_FUNCTION};
+
+/// Number of items shown by [`Session::format_stack`], matching GForth's `maxdepth-.s`
+pub const MAX_DISPLAYED_DEPTH: usize = 9;
+
+/// A word listed by [`Session::dictionary`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryEntry {
+    pub name: String,
+    /// Declared or inferred stack effect, if known
+    pub stack_effect: Option<StackEffect>,
+    /// Whether the word is built into the compiler rather than defined in the session
+    pub builtin: bool,
+}
+
+/// Persistent compilation and execution state for the REPL
+pub struct Session {
+    /// Accumulated word definitions, in definition order
+    definitions: Vec<Definition>,
+    /// Live data stack, bottom first
+    stack: Vec<i64>,
+    /// JIT module holding the code for the current dictionary
+    backend: Option<CraneliftBackend>,
+    /// Where output words write while a line runs, or `None` for stdout
+    output: Option<Box<dyn OutputSink>>,
+    /// Warnings about the last evaluated line
+    warnings: Vec<String>,
+}
+
+impl Session {
+    /// Create an empty session
+    pub fn new() -> Self {
+        Self {
+            definitions: Vec::new(),
+            stack: Vec::new(),
+            backend: None,
+            output: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Send the output of `.`, `emit`, `cr` and friends to `sink` instead of stdout
+    ///
+    /// Returns the previously installed sink, if any.
+    pub fn set_output_sink(&mut self, sink: Box<dyn OutputSink>) -> Option<Box<dyn OutputSink>> {
+        self.output.replace(sink)
+    }
+
+    /// Evaluate a line of Forth source
+    ///
+    /// Definitions are added to the dictionary, replacing any earlier word of
+    /// the same name, and top-level code runs against the live stack. A
+    /// definition named like a built-in shadows it, with a warning, until
+    /// [`reset`](Self::reset); words defined earlier use the new meaning too.
+    pub fn eval(&mut self, source: &str) -> Result<()> {
+        let parsed = parse_program(source)
+            .map_err(CompileError::ParseError)?;
+        self.eval_program(parsed)
+    }
+
+    /// Evaluate an already parsed program, as [`eval`](Self::eval) does its source
+    pub fn eval_program(&mut self, parsed: Program) -> Result<()> {
+        self.warnings = parsed.definitions.iter().filter_map(shadowing_warning).collect();
+
+        let mut definitions = self.definitions.clone();
+        for def in parsed.definitions {
+            match definitions.iter_mut().find(|existing| existing.name == def.name) {
+                Some(existing) => *existing = def,
+                None => definitions.push(def),
+            }
+        }
+
+        let mut top_level_code: Vec<Word> = self.stack.iter().map(|&v| Word::IntLiteral(v)).collect();
+        top_level_code.extend(parsed.top_level_code);
+
+        let mut program = Program { definitions, top_level_code };
+
+        // Lower once to learn how many values the line leaves behind. A
+        // :main that empties the stack still returns a 0, so lower it with
+        // one extra value pushed and discount that instead.
+        let mut probe = program.clone();
+        probe.top_level_code.push(Word::IntLiteral(0));
+        let depth = lower_program(&probe)?
+            .iter()
+            .find(|func| func.name == MAIN_FUNCTION)
+            .map_or(0, |main| result_depth(main).saturating_sub(1));
+
+        // The JIT returns a single value, so spill the whole stack into a
+        // buffer and return the depth instead
+        let mut results = vec![0i64; depth];
+        if !program.top_level_code.is_empty() {
+            let base = results.as_mut_ptr() as i64;
+            for slot in (0..depth).rev() {
+                program.top_level_code.push(Word::IntLiteral(base + (slot * 8) as i64));
+                program.top_level_code.push(Word::WordRef {
+                    name: "!".to_string(),
+                    location: SourceLocation::default(),
+                });
+            }
+            program.top_level_code.push(Word::IntLiteral(depth as i64));
+        }
+
+        let functions = lower_program(&program)?;
+        let backend = jit_compile(&functions, false, false, false, CellWidth::Bits64)?;
+
+        if !program.top_level_code.is_empty() {
+            let previous = replace_output_sink(self.output.take());
+            let returned = call_jit(&backend, MAIN_FUNCTION);
+            self.output = replace_output_sink(previous);
+            if !matches!(returned, Ok(n) if n == depth as i64) {
+                // SAFETY: nothing else holds pointers into the discarded module
+                unsafe { backend.free_memory() };
+                return Err(returned.err().unwrap_or_else(|| {
+                    CompileError::RuntimeError("Unexpected stack depth after evaluation".to_string())
+                }));
+            }
+        }
+
+        self.replace_backend(Some(backend));
+        self.definitions = program.definitions;
+        self.stack = results;
+
+        Ok(())
+    }
+
+    /// Warnings about the last line passed to [`eval`](Self::eval), kept
+    /// even if it failed
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Current data stack, bottom first
+    pub fn stack(&self) -> &[i64] {
+        &self.stack
+    }
+
+    /// Render the stack the way GForth's `.s` does: `<depth> ` followed by
+    /// each item, bottom to top
+    ///
+    /// Only the topmost [`MAX_DISPLAYED_DEPTH`] items are shown; deeper items
+    /// are elided with `...`.
+    pub fn format_stack(&self) -> String {
+        let mut out = format!("<{}> ", self.stack.len());
+        let shown = self.stack.len().saturating_sub(MAX_DISPLAYED_DEPTH);
+        if shown > 0 {
+            out.push_str("... ");
+        }
+        for value in &self.stack[shown..] {
+            out.push_str(&value.to_string());
+            out.push(' ');
+        }
+        out
+    }
+
+    /// Empty the data stack, keeping all definitions
+    pub fn clear_stack(&mut self) {
+        self.stack.clear();
+    }
+
+    /// Names of all defined words, in definition order
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.definitions.iter().map(|def| def.name.as_str())
+    }
+
+    /// Every word usable on the next line: the session's definitions in
+    /// definition order, then the built-in words in alphabetical order
+    ///
+    /// A definition reusing a built-in's name is listed once, as the definition.
+    pub fn dictionary(&self) -> Vec<DictionaryEntry> {
+        let program = Program {
+            definitions: self.definitions.clone(),
+            top_level_code: Vec::new(),
+        };
+        let mut entries: Vec<DictionaryEntry> = self
+            .definitions
+            .iter()
+            .zip(definition_effects(&program))
+            .map(|(def, stack_effect)| DictionaryEntry {
+                name: def.name.clone(),
+                stack_effect,
+                builtin: false,
+            })
+            .collect();
+
+        for name in builtins::names() {
+            if self.definitions.iter().any(|def| def.name == name) {
+                continue;
+            }
+            entries.push(DictionaryEntry {
+                name: name.to_string(),
+                stack_effect: builtins::lookup(name).and_then(builtins::Builtin::stack_effect),
+                builtin: true,
+            });
+        }
+        entries
+    }
+
+    /// Release compiled code and forget all definitions and stack contents
+    pub fn reset(&mut self) {
+        self.replace_backend(None);
+        self.definitions.clear();
+        self.stack.clear();
+        self.warnings.clear();
+    }
+
+    fn replace_backend(&mut self, backend: Option<CraneliftBackend>) {
+        if let Some(old) = std::mem::replace(&mut self.backend, backend) {
+            // SAFETY: function pointers are only used during `eval`
+            unsafe { old.free_memory() };
+        }
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.replace_backend(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_stack_persists_between_lines() {
+        let mut session = Session::new();
+        session.eval("1 2").unwrap();
+        session.eval("3").unwrap();
+        assert_eq!(session.stack(), &[1, 2, 3]);
+
+        session.eval("+ +").unwrap();
+        assert_eq!(session.stack(), &[6]);
+    }
+
+    #[test]
+    fn test_output_sink_captures_output() {
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        let mut session = Session::new();
+        session.set_output_sink(Box::new(buffer.clone()));
+
+        session.eval("72 emit 105 emit").unwrap();
+        session.eval(": answer 42 . ; answer cr").unwrap();
+
+        assert_eq!(String::from_utf8(buffer.take()).unwrap(), "Hi42 \n");
+    }
+
+    #[test]
+    fn test_file_status_and_delete_file() {
+        let path = std::env::temp_dir().join(format!("fastforth_session_{}.txt", std::process::id()));
+        std::fs::write(&path, b"scratch").unwrap();
+        let name = path.to_str().unwrap();
+
+        let mut session = Session::new();
+        session.eval(&format!("\"{}\" file-status swap drop", name)).unwrap();
+        session.eval(&format!("\"{}\" delete-file", name)).unwrap();
+        assert_eq!(session.stack(), &[0, 0]);
+        assert!(!path.exists());
+
+        // Both report a nonzero ior once the file is gone
+        session.eval(&format!("\"{}\" file-status", name)).unwrap();
+        session.eval(&format!("\"{}\" delete-file", name)).unwrap();
+        let stack = session.stack();
+        assert_eq!(stack[2], 0);
+        assert_ne!(stack[3], 0);
+        assert_ne!(stack[4], 0);
+    }
+
+    #[test]
+    fn test_redefinition_replaces_word() {
+        let mut session = Session::new();
+        session.eval(": answer 41 ;").unwrap();
+        session.eval(": answer 42 ;").unwrap();
+        session.eval("answer").unwrap();
+
+        assert_eq!(session.stack(), &[42]);
+        assert_eq!(session.words().collect::<Vec<_>>(), vec!["answer"]);
+    }
+
+    #[test]
+    fn test_dictionary_lists_definitions_then_builtins() {
+        let mut session = Session::new();
+        session.eval(": square ( n -- n ) dup * ; : dup 1 ;").unwrap();
+
+        let dictionary = session.dictionary();
+        let names: Vec<&str> = dictionary.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(&names[..2], &["square", "dup"]);
+        assert_eq!(names.iter().filter(|name| **name == "dup").count(), 1);
+        assert!(names.contains(&"+") && names.contains(&"swap"));
+
+        assert!(!dictionary[0].builtin);
+        assert_eq!(dictionary[0].stack_effect.as_ref().unwrap().to_string(), "( n -- n )");
+        let plus = dictionary.iter().find(|entry| entry.name == "+").unwrap();
+        assert!(plus.builtin);
+        assert_eq!(plus.stack_effect.as_ref().unwrap().to_string(), "( n n -- n )");
+    }
+
+    #[test]
+    fn test_definition_shadows_builtin() {
+        let mut session = Session::new();
+        session.eval(": sum + ;").unwrap();
+        session.eval(": + - ;").unwrap();
+        assert_eq!(session.warnings(), &["'+' redefines a built-in word and shadows it".to_string()]);
+
+        session.eval("5 3 + 10 4 sum").unwrap();
+        assert!(session.warnings().is_empty());
+        assert_eq!(session.stack(), &[2, 6]);
+
+        session.eval(": emit drop 7 ; 65 emit").unwrap();
+        assert_eq!(session.stack(), &[2, 6, 7]);
+
+        session.reset();
+        session.eval("5 3 +").unwrap();
+        assert_eq!(session.stack(), &[8]);
+    }
+
+    #[test]
+    fn test_failed_line_keeps_state() {
+        let mut session = Session::new();
+        session.eval(": double 2 * ;").unwrap();
+        session.eval("7").unwrap();
+
+        assert!(session.eval("undefined-word").is_err());
+        assert!(session.eval("1 0 /").is_err());
+
+        assert_eq!(session.stack(), &[7]);
+        assert_eq!(session.words().collect::<Vec<_>>(), vec!["double"]);
+    }
+
+    #[test]
+    fn test_format_stack() {
+        let mut session = Session::new();
+        assert_eq!(session.format_stack(), "<0> ");
+
+        session.eval("1 -2 3").unwrap();
+        assert_eq!(session.format_stack(), "<3> 1 -2 3 ");
+
+        session.clear_stack();
+        assert!(session.stack().is_empty());
+    }
+
+    #[test]
+    fn test_format_deep_stack_truncated() {
+        let mut session = Session::new();
+        session.eval("1 2 3 4 5 6 7 8 9 10 11").unwrap();
+        assert_eq!(session.format_stack(), "<11> ... 3 4 5 6 7 8 9 10 11 ");
+    }
+}
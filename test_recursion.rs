@@ -1,11 +1,11 @@
 // Test recursion compilation
 use anyhow::Result;
 use backend::cranelift::{CraneliftBackend, CraneliftSettings};
-use fastforth_frontend::{parse_program, convert_to_ssa};
+use fastforth_frontend::{parse_program, convert_to_ssa, MAIN_FUNCTION};
 
 fn main() -> Result<()> {
     // Test simple recursion
-    let source = ": double 2 * ; : quad 2 double ; 5 quad";
+    let source = ": double 2 * ; : quad double double ; 5 quad";
 
     println!("Testing: {}", source);
 
@@ -42,25 +42,14 @@ fn main() -> Result<()> {
     println!("✓ Pass 3: Finalized all functions");
 
     // Get function pointer
-    let main_name = &functions.last().unwrap().name;
-    let func_ptr = backend.get_function(main_name)
+    let func_ptr = backend.get_function(MAIN_FUNCTION)
         .ok_or_else(|| anyhow::anyhow!("Failed to get function pointer"))?;
-    println!("✓ Got function pointer for '{}'", main_name);
+    println!("✓ Got function pointer for '{}'", MAIN_FUNCTION);
 
-    // Execute
-    let mut stack = vec![0i64; 256];
-    type ForthFn = unsafe extern "C" fn(*mut i64) -> *mut i64;
+    // Execute: compiled words return the top of the stack
+    type ForthFn = unsafe extern "C" fn() -> i64;
     let forth_fn: ForthFn = unsafe { std::mem::transmute(func_ptr) };
-
-    let stack_ptr = stack.as_mut_ptr();
-    let result_ptr = unsafe { forth_fn(stack_ptr) };
-
-    let stack_depth = unsafe { result_ptr.offset_from(stack_ptr) };
-    let result = if stack_depth > 0 {
-        unsafe { *result_ptr.offset(-1) }
-    } else {
-        0
-    };
+    let result = unsafe { forth_fn() };
 
     println!("✓ Execution complete");
     println!("Result: {} (expected: 20)", result);
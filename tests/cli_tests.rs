@@ -260,6 +260,20 @@ fn test_cli_error_reporting() {
     }
 }
 
+#[test]
+fn test_cli_reports_every_error() {
+    let (_temp, file_path) = create_temp_forth_file(": first frob ;\n: second 1 twiddle ;\nfirst second");
+
+    let compiler = Compiler::new(OptimizationLevel::Standard);
+    let error = compiler.compile_file(&file_path, CompilationMode::JIT).unwrap_err();
+
+    // The CLI prints the error's Display, which lists both
+    let message = error.to_string();
+    assert!(message.contains("Undefined word: frob at line 1"), "{}", message);
+    assert!(message.contains("Undefined word: twiddle at line 2"), "{}", message);
+    assert_eq!(error.span().map(|span| span.line), Some(1));
+}
+
 #[test]
 fn test_cli_benchmark_mode() {
     // Test 13: Test benchmark mode
@@ -333,6 +347,50 @@ fn test_cli_batch_execution() {
     }
 }
 
+#[cfg(feature = "inference")]
+#[test]
+fn test_cli_infer_json_reports_errors_in_document() {
+    let output = Command::new(get_binary_path())
+        .args(["infer", "--json", ": bad >r ;"])
+        .output();
+
+    if let Ok(result) = output {
+        assert!(result.status.success(), "infer --json should not fail the process");
+        let json: serde_json::Value = serde_json::from_slice(&result.stdout).unwrap();
+        assert_eq!(json["valid"], false);
+        assert!(json["error"].as_str().unwrap().contains("Unbalanced"));
+        assert!(json["latency_ms"].is_number());
+    } else {
+        eprintln!("Binary not found, skipping CLI test");
+    }
+}
+
+#[test]
+fn test_cli_fmt_check_and_rewrite() {
+    let (_temp, file_path) = create_temp_forth_file(":   double   2 * ;\n\n\n21 double");
+
+    let check = |path: &PathBuf| {
+        Command::new(get_binary_path())
+            .args(["fmt", "--check"])
+            .arg(path)
+            .output()
+    };
+
+    if let Ok(result) = check(&file_path) {
+        assert!(!result.status.success(), "--check should fail on unformatted source");
+        assert!(String::from_utf8_lossy(&result.stdout).contains("would be reformatted"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), ":   double   2 * ;\n\n\n21 double");
+
+        let result = Command::new(get_binary_path()).arg("fmt").arg(&file_path).output().unwrap();
+        assert!(result.status.success());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), ": double 2 * ;\n\n21 double\n");
+
+        assert!(check(&file_path).unwrap().status.success());
+    } else {
+        eprintln!("Binary not found, skipping CLI test");
+    }
+}
+
 // ============================================================================
 // Server Tests (5 tests)
 // ============================================================================
@@ -629,7 +629,7 @@ fn test_output_dot() {
     let mut engine = ForthEngine::new();
     engine.eval("42 .").unwrap();
     assert_eq!(engine.output().trim(), "42", ". should output number");
-    assert_eq!(engine.stack(), &[], ". should consume the number");
+    assert_eq!(engine.stack(), &[] as &[i64], ". should consume the number");
 }
 
 #[test]
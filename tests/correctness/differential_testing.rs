@@ -6,6 +6,7 @@
 use std::process::{Command, Stdio};
 use std::io::Write;
 use fastforth::ForthEngine;
+use proptest::prelude::*;
 
 /// Check if GForth is installed
 pub fn gforth_available() -> bool {
@@ -17,44 +18,57 @@ pub fn gforth_available() -> bool {
         .is_ok()
 }
 
-/// Parse GForth stack output
-/// GForth outputs like: ".s bye <1> 15  ok" when we use .s command
-/// The <N> indicates stack depth, and numbers follow
-fn parse_gforth_stack(output: &str) -> Vec<i64> {
-    // Find lines that contain ".s" followed by the stack depth indicator
-    // This avoids confusion with < and > comparison operators in the code
-    for line in output.lines() {
-        // First find ".s" to locate where the stack output starts
-        if let Some(ds_pos) = line.find(".s") {
-            // Now look for " <" after the ".s" position
-            let after_ds = &line[ds_pos..];
-            if let Some(bracket_pos) = after_ds.find(" <") {
-                // Check if this is followed by a number and >
-                let after_space_bracket = &after_ds[bracket_pos+2..]; // Skip " <"
-                if let Some(end_pos) = after_space_bracket.find('>') {
-                    let between = &after_space_bracket[..end_pos];
-                    // Verify this is a stack depth marker (should be a number)
-                    if !between.is_empty() && between.chars().all(|c| c.is_ascii_digit()) {
-                        // Extract the part after the > for this marker
-                        let after_marker = &after_space_bracket[end_pos+1..];
-                        // Parse all numbers from this part (before " ok")
-                        let before_ok = after_marker.split(" ok").next().unwrap_or(after_marker);
-
-                        let mut result = Vec::new();
-                        for part in before_ok.split_whitespace() {
-                            if let Ok(num) = part.parse::<i64>() {
-                                result.push(num);
-                            }
-                        }
-
-                        return result;
-                    }
-                }
-            }
+/// Deepest stack GForth's `.s` prints in full (its default `maxdepth-.s`)
+pub const GFORTH_MAX_DISPLAYED_DEPTH: usize = 9;
+
+/// Message of the first GForth error report (`:1: Undefined word`) in `output`
+fn gforth_error(output: &str) -> Option<&str> {
+    output.lines().find_map(|line| {
+        let rest = line.trim_start().strip_prefix(':')?;
+        let (position, message) = rest.split_once(':')?;
+        if position.is_empty() || !position.chars().all(|c| c.is_ascii_digit()) {
+            return None;
         }
-    }
+        Some(message.trim())
+    })
+}
 
-    Vec::new()
+/// Parse GForth stack output
+///
+/// GForth prints `.s` as `<N> a b c  ok`, possibly after an echo of the input,
+/// so the last `<N>` marker is taken to start the stack dump. Exactly `N`
+/// numbers must follow it; deeper stacks are elided by GForth and rejected.
+/// An error anywhere in the output aborts the line before `.s` runs, so it is
+/// reported instead of any stack.
+pub fn parse_gforth_stack(output: &str) -> Result<Vec<i64>, String> {
+    if let Some(message) = gforth_error(output) {
+        return Err(format!("GForth error: {}", message));
+    }
+
+    let tokens: Vec<&str> = output.split_whitespace().collect();
+    let (marker, depth) = tokens
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(i, token)| {
+            let depth = token.strip_prefix('<')?.strip_suffix('>')?.parse::<usize>().ok()?;
+            Some((i, depth))
+        })
+        .ok_or_else(|| format!("No stack dump in GForth output: {:?}", output))?;
+
+    if depth > GFORTH_MAX_DISPLAYED_DEPTH {
+        return Err(format!("GForth elides stacks deeper than {}: got {}", GFORTH_MAX_DISPLAYED_DEPTH, depth));
+    }
+
+    let stack: Vec<i64> = tokens[marker + 1..]
+        .iter()
+        .map_while(|token| token.parse::<i64>().ok())
+        .collect();
+
+    if stack.len() < depth {
+        return Err(format!("GForth reported depth {} but printed {:?}", depth, stack));
+    }
+    Ok(stack[..depth].to_vec())
 }
 
 /// Execute Forth code in GForth and capture stack state
@@ -81,10 +95,7 @@ pub fn run_gforth(code: &str) -> Result<Vec<i64>, String> {
 
     if output.status.success() {
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let stack = parse_gforth_stack(&stdout);
-
-
-        Ok(stack)
+        parse_gforth_stack(&stdout)
     } else {
         Err(String::from_utf8_lossy(&output.stderr).to_string())
     }
@@ -106,21 +117,171 @@ pub fn differential_test(code: &str) -> Result<(), String> {
 
     let fast_forth_stack = engine.stack();
 
-    // Compare stacks
-    if gforth_stack == fast_forth_stack {
-        Ok(())
-    } else {
-        Err(format!(
-            "Stack mismatch for code: {}\nGForth:     {:?}\nFast Forth: {:?}",
-            code, gforth_stack, fast_forth_stack
-        ))
+    // Compare stacks element by element, bottom first
+    let depth = gforth_stack.len().max(fast_forth_stack.len());
+    match (0..depth).find(|&i| gforth_stack.get(i) != fast_forth_stack.get(i)) {
+        None => Ok(()),
+        Some(i) => Err(format!(
+            "Stack mismatch for code: {}\nFirst difference at position {} from the bottom\nGForth:     {:?}\nFast Forth: {:?}",
+            code, i, gforth_stack, fast_forth_stack
+        )),
     }
 }
 
+/// One step of a generated program
+#[derive(Debug, Clone)]
+pub enum Op {
+    Lit(i64),
+    Add,
+    Sub,
+    Mul,
+    Dup,
+    Swap,
+    Drop,
+}
+
+/// Arbitrary program steps, weighted towards literals so programs have data to work on
+pub fn arb_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (-1000i64..1000).prop_map(Op::Lit),
+        1 => Just(Op::Add),
+        1 => Just(Op::Sub),
+        1 => Just(Op::Mul),
+        1 => Just(Op::Dup),
+        1 => Just(Op::Swap),
+        1 => Just(Op::Drop),
+    ]
+}
+
+/// Turn arbitrary steps into a valid program, returning its source and the
+/// stack it must leave
+///
+/// Steps that would underflow, overflow a cell, or grow the stack past what
+/// GForth's `.s` prints are skipped. Since the mapping is deterministic,
+/// shrinking the steps shrinks the program.
+pub fn balanced_program(ops: &[Op]) -> (String, Vec<i64>) {
+    let mut words = Vec::new();
+    let mut stack: Vec<i64> = Vec::new();
+
+    for op in ops {
+        let len = stack.len();
+        let word = match *op {
+            Op::Lit(n) if len < GFORTH_MAX_DISPLAYED_DEPTH => {
+                stack.push(n);
+                n.to_string()
+            }
+            Op::Dup if (1..GFORTH_MAX_DISPLAYED_DEPTH).contains(&len) => {
+                stack.push(stack[len - 1]);
+                "DUP".to_string()
+            }
+            Op::Swap if len >= 2 => {
+                stack.swap(len - 1, len - 2);
+                "SWAP".to_string()
+            }
+            Op::Drop if len >= 1 => {
+                stack.pop();
+                "DROP".to_string()
+            }
+            Op::Add | Op::Sub | Op::Mul if len >= 2 => {
+                let (a, b) = (stack[len - 2], stack[len - 1]);
+                let (result, word) = match op {
+                    Op::Add => (a.checked_add(b), "+"),
+                    Op::Sub => (a.checked_sub(b), "-"),
+                    _ => (a.checked_mul(b), "*"),
+                };
+                let Some(result) = result else { continue };
+                stack.truncate(len - 2);
+                stack.push(result);
+                word.to_string()
+            }
+            _ => continue,
+        };
+        words.push(word);
+    }
+
+    (words.join(" "), stack)
+}
+
+/// Random valid programs of `+ - * DUP SWAP DROP` and literals
+pub fn arb_balanced_program() -> impl Strategy<Value = (String, Vec<i64>)> {
+    prop::collection::vec(arb_op(), 1..32).prop_map(|ops| balanced_program(&ops))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        /// The engine agrees with the model the programs were generated from
+        #[test]
+        fn property_test_engine_matches_model((program, expected) in arb_balanced_program()) {
+            let mut engine = ForthEngine::new();
+            engine.eval(&program).map_err(|e| TestCaseError::fail(format!("{}: {}", program, e)))?;
+            prop_assert_eq!(engine.stack(), expected.as_slice(), "program: {}", program);
+        }
+
+        /// The engine agrees with GForth on random stack-balanced programs
+        #[test]
+        fn property_test_arithmetic((program, _) in arb_balanced_program()) {
+            if !gforth_available() {
+                return Ok(());
+            }
+
+            let gforth_stack = run_gforth(&program).map_err(TestCaseError::fail)?;
+            let mut engine = ForthEngine::new();
+            engine.eval(&program).map_err(|e| TestCaseError::fail(format!("{}: {}", program, e)))?;
+            prop_assert_eq!(engine.stack(), gforth_stack.as_slice(), "program: {}", program);
+        }
+    }
+
+    #[test]
+    fn test_parse_gforth_stack_formats() {
+        assert_eq!(parse_gforth_stack("<3> 1 -2 3  ok\n"), Ok(vec![1, -2, 3]));
+        assert_eq!(parse_gforth_stack("5 10 + .s <1> 15  ok\r\n"), Ok(vec![15]));
+        assert_eq!(parse_gforth_stack("<0>  ok"), Ok(vec![]));
+        // Only the last dump counts
+        assert_eq!(parse_gforth_stack("<1> 7  ok\n<2> 7 8  ok"), Ok(vec![7, 8]));
+
+        assert!(parse_gforth_stack("redefined foo  ok").is_err());
+        assert!(parse_gforth_stack("<2> 1  ok").is_err());
+        assert!(parse_gforth_stack("<12> ... 4 5 6").is_err());
+    }
+
+    #[test]
+    fn test_parse_gforth_stack_samples() {
+        // Output as GForth 0.7 prints it when reading the program on stdin
+        let empty = "Gforth 0.7.3, Copyright (C) 1995-2008 Free Software Foundation, Inc.\n\
+                     Gforth comes with ABSOLUTELY NO WARRANTY; for details type `license'\n\
+                     Type `bye' to exit\n\
+                     <0>  ok\n";
+        assert_eq!(parse_gforth_stack(empty), Ok(vec![]));
+
+        let negative = "<2> -5 10  ok\n";
+        assert_eq!(parse_gforth_stack(negative), Ok(vec![-5, 10]));
+
+        let large = "<2> 9223372036854775807 -9223372036854775808  ok\n";
+        assert_eq!(parse_gforth_stack(large), Ok(vec![i64::MAX, i64::MIN]));
+
+        let error = "in file included from *OS command line*:-1\n\
+                     :1: Undefined word\n\
+                     5 >>>foo<<<  .s\n\
+                     Backtrace:\n\
+                     $7F2C4A1B8A00 throw\n";
+        assert_eq!(parse_gforth_stack(error), Err("GForth error: Undefined word".to_string()));
+
+        let underflow = ":1: Stack underflow\n>>>+<<< .s\n<0>  ok\n";
+        assert_eq!(parse_gforth_stack(underflow), Err("GForth error: Stack underflow".to_string()));
+    }
+
+    #[test]
+    fn test_balanced_program_skips_invalid_steps() {
+        let ops = [Op::Add, Op::Lit(3), Op::Dup, Op::Mul, Op::Swap, Op::Lit(i64::MAX), Op::Mul, Op::Drop, Op::Drop];
+        // `+` and `SWAP` underflow, the second `*` overflows, the last `DROP` underflows
+        assert_eq!(balanced_program(&ops), ("3 DUP * 9223372036854775807 DROP DROP".to_string(), vec![]));
+    }
+
     #[test]
     fn test_gforth_availability() {
         let available = gforth_available();
@@ -9,10 +9,11 @@
 use fastforth::{
     CompileError, ForthEngine,
 };
+use fastforth_frontend::ForthError;
 
 #[test]
 fn test_error_display_formatting_parse() {
-    let error = CompileError::ParseError("Test parse error at line 42".to_string());
+    let error = CompileError::ParseError(ForthError::parse_error(42, 1, "Test parse error"));
 
     let error_string = format!("{}", error);
 
@@ -60,5 +61,5 @@ fn test_division_by_zero_error() {
 
     // Should return a runtime error
     assert!(result.is_err());
-    assert!(matches!(result.unwrap_err(), CompileError::RuntimeError(_)));
+    assert!(matches!(result.unwrap_err(), CompileError::Exception { code: -10, .. }));
 }
@@ -1,24 +1,29 @@
-/// Fuzzing target for SSA construction and validation
+/// Fuzzing target for SSA conversion
 ///
-/// Focuses on SSA-specific edge cases:
-/// - Phi node placement
-/// - Variable renaming
-/// - Control flow merges
-/// - Loop headers
+/// Parses random input and, when parsing succeeds, converts the program to
+/// SSA. Malformed-but-parseable programs (unbalanced stacks, stray control
+/// words, undefined words) must be rejected with a `ForthError`, never a panic:
+/// - Register and block bookkeeping in `convert_if`, `convert_begin_until`, ...
+/// - Deeply nested control structures (bounded by the parser's nesting limit)
+/// - Huge integer literals (rejected by the lexer)
 
 #![no_main]
 use libfuzzer_sys::fuzz_target;
-use fastforth_frontend::parse_program;
-use fastforth_optimizer::IRBuilder;
+use fastforth_frontend::{convert_to_ssa, parse_program, SSAValidator};
 
 fuzz_target!(|data: &[u8]| {
     if let Ok(code) = std::str::from_utf8(data) {
-        if let Ok(ast) = parse_program(code) {
-            let mut builder = IRBuilder::new();
-            if let Ok(ir) = builder.build_from_ast(&ast) {
-                // Validate SSA properties
-                if let Err(_) = ir.validate_ssa() {
-                    // SSA validation failed - this is OK, we just shouldn't crash
+        if let Ok(program) = parse_program(code) {
+            match convert_to_ssa(&program) {
+                Ok(functions) => {
+                    for function in &functions {
+                        assert!(!function.blocks.is_empty(), "'{}' has no entry block", function.name);
+                        // Validation may reject the function, but must not crash
+                        let _ = SSAValidator::new(function).validate();
+                    }
+                }
+                Err(err) => {
+                    assert!(!err.to_string().is_empty(), "error without a message: {:?}", err);
                 }
             }
         }
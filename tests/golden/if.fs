@@ -0,0 +1,2 @@
+: abs2 ( n -- n ) dup 0 < if negate then ;
+-5 abs2
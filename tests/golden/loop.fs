@@ -0,0 +1,3 @@
+variable total
+: tally ( n -- ) 0 do total @ 1 + total ! loop ;
+0 total ! 10 tally total @
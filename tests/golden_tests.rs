@@ -0,0 +1,154 @@
+//! Golden IR snapshots
+//!
+//! Every `tests/golden/NAME.fs` is lowered and optimized at the Standard
+//! level, and the optimized IR, exported as JSON, must match
+//! `tests/golden/NAME.json` exactly. A mismatch fails with a line diff and
+//! the instruction counts of both versions; fewer instructions suggest an
+//! improvement rather than a regression, but the diff needs reviewing either
+//! way. Once a change is intended, regenerate the snapshots with
+//!
+//! ```text
+//! UPDATE_SNAPSHOTS=1 cargo test --test golden_tests
+//! ```
+//!
+//! and commit the updated `.json` files with it.
+
+use fastforth::{CompilationPipeline, OptimizationLevel};
+use fastforth_optimizer::ForthIR;
+use std::path::{Path, PathBuf};
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn updating() -> bool {
+    std::env::var_os("UPDATE_SNAPSHOTS").is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Line diff of `expected` against `actual`, from their longest common
+/// subsequence, with two lines of context around each change
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // lcs[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    const CONTEXT: usize = 2;
+    let changed: Vec<usize> = (0..lines.len()).filter(|&k| lines[k].0 != ' ').collect();
+    let mut out = String::new();
+    let mut last_shown = None;
+    for (k, (tag, line)) in lines.iter().enumerate() {
+        let near_change = changed.iter().any(|&c| k + CONTEXT >= c && k <= c + CONTEXT);
+        if !near_change {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 != k) {
+            out.push_str("   ...\n");
+        }
+        out.push_str(&format!("{} {}\n", tag, line));
+        last_shown = Some(k);
+    }
+    out
+}
+
+/// Compare the optimized IR of `NAME.fs` with `NAME.json`, or rewrite the
+/// snapshot when updating. Returns a description of the mismatch, if any.
+fn check(source_path: &Path) -> Option<String> {
+    let name = source_path.file_stem().unwrap().to_string_lossy().into_owned();
+    let source = std::fs::read_to_string(source_path).unwrap();
+    let ir = CompilationPipeline::new(OptimizationLevel::Standard)
+        .optimized_ir(&source)
+        .unwrap_or_else(|e| panic!("{}.fs failed to compile: {}", name, e));
+    let actual = ir.to_json() + "\n";
+
+    let snapshot_path = source_path.with_extension("json");
+    if updating() {
+        std::fs::write(&snapshot_path, &actual).unwrap();
+        return None;
+    }
+
+    let expected = match std::fs::read_to_string(&snapshot_path) {
+        Ok(expected) => expected,
+        Err(_) => return Some(format!("{}: no snapshot {}", name, snapshot_path.display())),
+    };
+    if expected == actual {
+        return None;
+    }
+
+    let golden_count = ForthIR::from_json(&expected).map(|golden| golden.instruction_count());
+    let verdict = match golden_count {
+        Ok(golden) if ir.instruction_count() < golden => {
+            format!("{} -> {} instructions (fewer: likely an improvement)", golden, ir.instruction_count())
+        }
+        Ok(golden) if ir.instruction_count() > golden => {
+            format!("{} -> {} instructions (more: likely a regression)", golden, ir.instruction_count())
+        }
+        Ok(golden) => format!("{} instructions either way", golden),
+        Err(e) => format!("snapshot no longer loads: {}", e),
+    };
+    Some(format!("{}: optimized IR changed, {}\n{}", name, verdict, diff(&expected, &actual)))
+}
+
+#[test]
+fn test_golden_snapshots() {
+    let mut sources: Vec<PathBuf> = std::fs::read_dir(golden_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "fs"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "no programs in {}", golden_dir().display());
+
+    let failures: Vec<String> = sources.iter().filter_map(|path| check(path)).collect();
+    assert!(
+        failures.is_empty(),
+        "{}\nIf these changes are intended, rerun with UPDATE_SNAPSHOTS=1",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_snapshots_are_deterministic() {
+    for name in ["square", "loop", "if"] {
+        let source = std::fs::read_to_string(golden_dir().join(format!("{}.fs", name))).unwrap();
+        let dump = || {
+            CompilationPipeline::new(OptimizationLevel::Standard)
+                .optimized_ir(&source)
+                .unwrap()
+                .to_json()
+        };
+        assert_eq!(dump(), dump(), "{}", name);
+    }
+}
+
+#[test]
+fn test_diff_marks_changed_lines() {
+    let diff = diff("a\nb\nc\nd\ne\nf\ng\n", "a\nb\nC\nd\ne\nf\ng\n");
+    assert_eq!(diff, "  a\n  b\n- c\n+ C\n  d\n  e\n");
+}
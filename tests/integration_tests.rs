@@ -2,7 +2,7 @@
 //!
 //! These tests verify the end-to-end compilation pipeline.
 
-use fastforth::{Compiler, CompilationMode, OptimizationLevel};
+use fastforth::{Compiler, CompilationMode, OptimizationLevel, Session};
 
 #[test]
 fn test_compiler_creation() {
@@ -139,3 +139,17 @@ fn test_compilation_modes() {
     // (may fail at backend, but should fail consistently)
     assert_eq!(result_aot.is_ok(), result_jit.is_ok());
 }
+
+#[test]
+fn test_session_definitions_accumulate() {
+    let mut session = Session::new();
+
+    // A word defined on one line is callable from the next
+    session.eval(": double 2 * ;").unwrap();
+    session.eval("5 double").unwrap();
+    assert_eq!(session.stack(), &[10]);
+
+    // The stack carries over as well
+    session.eval("double 1 +").unwrap();
+    assert_eq!(session.stack(), &[21]);
+}
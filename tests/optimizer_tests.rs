@@ -5,7 +5,7 @@
 //! and advanced optimizations.
 
 use fastforth_optimizer::{
-    ConstantFolder, DeadCodeEliminator, ForthIR, InlineOptimizer, Instruction, OptimizationLevel,
+    BlockRef, ConstantFolder, DeadCodeEliminator, ForthIR, InlineOptimizer, Instruction, OptimizationLevel,
     Optimizer, WordDef, ZeroCostOptimizer,
 };
 
@@ -274,7 +274,7 @@ fn test_dead_code_unused_definitions() {
     ir.add_word(used_word);
 
     ir.main = vec![
-        Instruction::Call("used".to_string()),
+        Instruction::call("used"),
     ];
 
     let optimized = eliminator.eliminate(&ir).expect("Elimination failed");
@@ -349,7 +349,7 @@ fn test_inline_small_words() {
 
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("double".to_string()),
+        Instruction::call("double"),
     ];
 
     let optimized = inliner.inline(&ir).expect("Inlining failed");
@@ -378,12 +378,13 @@ fn test_no_inline_recursive() {
             Instruction::Dup,
             Instruction::Literal(1),
             Instruction::Gt,
-            Instruction::BranchIfNot(8),
+            Instruction::BranchIfNot(BlockRef(1)),
             Instruction::Dup,
             Instruction::Literal(1),
             Instruction::Sub,
-            Instruction::Call("factorial".to_string()),  // Recursive call
+            Instruction::call("factorial"),  // Recursive call
             Instruction::Mul,
+            Instruction::label("bb1"),
         ],
     );
     ir.add_word(factorial);
@@ -415,7 +416,7 @@ fn test_inline_threshold() {
 
     ir.main = vec![
         Instruction::Literal(5),
-        Instruction::Call("large".to_string()),
+        Instruction::call("large"),
     ];
 
     let optimized = inliner.inline(&ir).expect("Inlining failed");
@@ -587,9 +588,9 @@ fn test_loop_invariant_hoisting() {
 
     // Simulate calling it multiple times (as in a loop)
     ir.main = vec![
-        Instruction::Call("loop_body".to_string()),
-        Instruction::Call("loop_body".to_string()),
-        Instruction::Call("loop_body".to_string()),
+        Instruction::call("loop_body"),
+        Instruction::call("loop_body"),
+        Instruction::call("loop_body"),
     ];
 
     let optimized = optimizer.optimize(ir).expect("Optimization failed");
@@ -630,8 +631,8 @@ fn test_nested_loop_optimization() {
 
     // Simulate nested calls and use results
     ir.main = vec![
-        Instruction::Call("inner_compute".to_string()),  // Produces 6
-        Instruction::Call("inner_compute".to_string()),  // Produces 6
+        Instruction::call("inner_compute"),  // Produces 6
+        Instruction::call("inner_compute"),  // Produces 6
         Instruction::Add,                                // 6 + 6 = 12
     ];
 
@@ -708,12 +709,12 @@ fn test_loop_fusion() {
 
     // Two sequential calls simulating loop bodies
     ir.main = vec![
-        Instruction::Call("loop1_body".to_string()),
-        Instruction::Call("loop1_body".to_string()),
-        Instruction::Call("loop1_body".to_string()),
-        Instruction::Call("loop2_body".to_string()),
-        Instruction::Call("loop2_body".to_string()),
-        Instruction::Call("loop2_body".to_string()),
+        Instruction::call("loop1_body"),
+        Instruction::call("loop1_body"),
+        Instruction::call("loop1_body"),
+        Instruction::call("loop2_body"),
+        Instruction::call("loop2_body"),
+        Instruction::call("loop2_body"),
     ];
 
     let optimized = optimizer.optimize(ir).expect("Optimization failed");
@@ -912,7 +913,7 @@ fn test_interprocedural_constant_prop() {
 
     // Main: just call helper (should inline and fold to 25)
     ir.main = vec![
-        Instruction::Call("helper".to_string()),
+        Instruction::call("helper"),
     ];
 
     let optimized = optimizer.optimize(ir).expect("Optimization failed");
@@ -951,11 +952,11 @@ fn test_whole_program_dead_code() {
 
     // Only call word0, word5, and word9
     ir.main = vec![
-        Instruction::Call("word0".to_string()),
+        Instruction::call("word0"),
         Instruction::Drop,
-        Instruction::Call("word5".to_string()),
+        Instruction::call("word5"),
         Instruction::Drop,
-        Instruction::Call("word9".to_string()),
+        Instruction::call("word9"),
     ];
 
     let optimized = dce.eliminate(&ir).expect("Optimization failed");
@@ -5,9 +5,13 @@
 //! - Debug vs release pipeline differences
 //! - Error propagation through pipeline stages
 
+use backend::cranelift::capture_output;
 use fastforth::{
-    CompilationPipeline, CompilationMode, OptimizationLevel,
+    CellWidth, CompilationPipeline, CompilationMode, CompileError, IrStage, OptimizationLevel,
 };
+use fastforth_frontend::ast::SourceLocation;
+use fastforth_frontend::ForthError;
+use fastforth_optimizer::Instruction;
 
 #[test]
 fn test_pipeline_basic_jit_compilation() {
@@ -67,3 +71,638 @@ fn test_pipeline_invalid_syntax() {
     // Should return an error for invalid syntax
     assert!(result.is_err());
 }
+
+#[test]
+fn test_pipeline_jit_division() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(": div / ; 20 4 div", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(5));
+
+    let result = pipeline.compile(": rem mod ; 20 6 rem", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(2));
+}
+
+#[test]
+fn test_pipeline_jit_qdup_if() {
+    let source = ": f ( n -- n ) ?dup if 2 * else 7 then ;";
+    for level in [OptimizationLevel::None, OptimizationLevel::Standard] {
+        let mut pipeline = CompilationPipeline::new(level);
+        let result = pipeline.compile(&format!("{} 5 f", source), CompilationMode::JIT).unwrap();
+        assert_eq!(result.jit_result, Some(10));
+        let result = pipeline.compile(&format!("{} 0 f", source), CompilationMode::JIT).unwrap();
+        assert_eq!(result.jit_result, Some(7));
+    }
+
+    // A zero is consumed, leaving only what was below it for ELSE
+    let source = ": g ( a n -- a ) ?dup if + else 1 + then ;";
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(&format!("{} 4 3 g", source), CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(7));
+    let result = pipeline.compile(&format!("{} 4 0 g", source), CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(5));
+}
+
+#[test]
+fn test_pipeline_jit_xor() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(": flip xor ; -1 -1 flip", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(0));
+
+    let result = pipeline.compile(": flip xor ; 12 10 flip", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(6));
+
+    // Folded away entirely when optimizing
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Aggressive);
+    let result = pipeline.compile("-1 0 xor", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(-1));
+}
+
+#[test]
+fn test_pipeline_dead_words_keep_top_level() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Aggressive);
+    let ir = pipeline.optimized_ir(": used dup * ; : unused 1 + ; 3 used").unwrap();
+    assert!(ir.words.contains_key(fastforth_frontend::MAIN_FUNCTION));
+    assert!(!ir.words.contains_key("unused"));
+}
+
+#[test]
+fn test_pipeline_jit_shifts() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(": shl lshift ; 1 4 shl", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(16));
+
+    // RSHIFT is logical, ARSHIFT keeps the sign
+    let result = pipeline.compile(": shr rshift ; -1 60 shr", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(15));
+    let result = pipeline.compile(": sar arshift ; -16 2 sar", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(-4));
+
+    // Shift counts are taken modulo 64
+    let result = pipeline.compile(": shl lshift ; 1 65 shl", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(2));
+    let result = pipeline.compile(": shr rshift ; 256 64 shr", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(256));
+}
+
+#[test]
+fn test_pipeline_jit_min_max_abs() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(": bigger max ; 3 7 bigger", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(7));
+    let result = pipeline.compile(": smaller min ; 3 7 smaller", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(3));
+    let result = pipeline.compile(": smaller min ; -4 -4 smaller", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(-4));
+
+    let result = pipeline.compile(": magnitude abs ; -9 magnitude", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(9));
+    // Negating the most negative cell wraps around
+    let source = format!(": magnitude abs ; {} magnitude", i64::MIN);
+    let result = pipeline.compile(&source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(i64::MIN));
+}
+
+#[test]
+fn test_pipeline_jit_numeric_output() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let mut run = |source: &str| {
+        let (result, output) = capture_output(|| pipeline.compile(source, CompilationMode::JIT));
+        result.unwrap();
+        output
+    };
+
+    assert_eq!(run("42 ."), "42 ");
+    assert_eq!(run(&format!("{} .", i64::MIN)), format!("{} ", i64::MIN));
+    assert_eq!(run("-1 u."), "18446744073709551615 ");
+    assert_eq!(run("42 5 .r -7 1 .r"), "   42-7");
+    assert_eq!(run(": greet 72 emit 105 emit cr ; greet"), "Hi\n");
+}
+
+#[test]
+fn test_pipeline_jit_type_and_count() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let mut run = |source: &str| {
+        let (result, output) = capture_output(|| pipeline.compile(source, CompilationMode::JIT));
+        result.unwrap();
+        output
+    };
+
+    assert_eq!(run("s\" hi\" type"), "hi");
+    assert_eq!(run(": greet S\" Hello, World!\" type ; greet"), "Hello, World!");
+    assert_eq!(run("s\" \" type"), "");
+    assert_eq!(run("s\" h\u{e9}llo\" type"), "h\u{e9}llo");
+    assert_eq!(run("0 0 type s\" hi\" -1 type"), "");
+
+    // A counted string: length byte 2, then "hi" (little-endian cell)
+    assert_eq!(run("create cs 6907906 , cs count type"), "hi");
+    assert_eq!(run("create cs 6907906 , cs count swap drop"), "");
+}
+
+#[test]
+fn test_pipeline_jit_unsigned_comparison() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    // -1 is the largest unsigned cell
+    let result = pipeline.compile(": below u< ; -1 1 below", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(0));
+    let result = pipeline.compile(": above u> ; -1 1 above", CompilationMode::JIT).unwrap();
+    assert_ne!(result.jit_result, Some(0));
+
+    // Signed comparison is unchanged
+    let result = pipeline.compile(": less < ; -1 1 less", CompilationMode::JIT).unwrap();
+    assert_ne!(result.jit_result, Some(0));
+}
+
+#[test]
+fn test_pipeline_jit_zero_comparisons() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let cases = [
+        (": z 0= ; 0 z", 1),
+        (": z 0= ; 5 z", 0),
+        // 0= inverts a flag
+        (": z 0= ; 3 3 = z", 0),
+        (": n 0< ; -3 n", 1),
+        (": n 0< ; -9223372036854775808 n", 1),
+        (": n 0< ; 0 n", 0),
+        (": p 0> ; 3 p", 1),
+        (": p 0> ; 0 p", 0),
+        (": p 0> ; -1 p", 0),
+    ];
+    for (source, expected) in cases {
+        let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+        assert_eq!(result.jit_result, Some(expected), "{}", source);
+    }
+}
+
+#[test]
+fn test_pipeline_jit_increment_and_shift_words() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let cases = [
+        (": f 1+ ; 41 f", 42),
+        (": f 1- ; 43 f", 42),
+        (": f 2* ; 21 f", 42),
+        (": f 2* ; -21 f", -42),
+        (": f 2/ ; 85 f", 42),
+        // Arithmetic shift: the sign is kept and odd values round down
+        (": f 2/ ; -3 f", -2),
+        (": f 2/ ; -1 f", -1),
+    ];
+    for (source, expected) in cases {
+        let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+        assert_eq!(result.jit_result, Some(expected), "{}", source);
+    }
+
+    // 1+ wraps by default and traps under checked arithmetic
+    let source = format!(": f 1+ ; {} f", i64::MAX);
+    let result = pipeline.compile(&source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(i64::MIN));
+    pipeline.set_checked_arithmetic(true);
+    let result = pipeline.compile(&source, CompilationMode::JIT);
+    assert!(result.unwrap_err().to_string().contains("Integer overflow"));
+    let source = format!(": f 1- ; {} f", i64::MIN);
+    assert!(pipeline.compile(&source, CompilationMode::JIT).is_err());
+}
+
+#[test]
+fn test_pipeline_jit_32_bit_cells() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    pipeline.set_cell_width(CellWidth::Bits32);
+    let mut run = |source: &str| pipeline.compile(source, CompilationMode::JIT).map(|result| result.jit_result);
+
+    // Arithmetic wraps at 32 bits
+    assert_eq!(run(": inc 1 + ; 2147483647 inc").unwrap(), Some(i32::MIN as i64));
+    assert_eq!(run("65536 65536 *").unwrap(), Some(0));
+    assert_eq!(run("-2147483648 -1 /").unwrap(), Some(i32::MIN as i64));
+    assert_eq!(run("-2147483648 negate").unwrap(), Some(i32::MIN as i64));
+    assert_eq!(run("-1 1 rshift").unwrap(), Some(i32::MAX as i64));
+
+    // Literals must fit in a cell
+    assert_eq!(run("2147483647 -2147483648 +").unwrap(), Some(-1));
+    for literal in ["2147483648", "-2147483649", "4294967296"] {
+        let error = run(literal).unwrap_err().to_string();
+        assert!(error.contains("does not fit in a 32-bit cell"), "{}", error);
+    }
+
+    // Host addresses are wider than a 32-bit cell
+    let error = run("variable x 5 x ! x @").unwrap_err().to_string();
+    assert!(error.contains("address of 'x'"), "{}", error);
+
+    pipeline.set_checked_arithmetic(true);
+    let result = pipeline.compile(": f 1+ ; 2147483647 f", CompilationMode::JIT);
+    assert!(result.unwrap_err().to_string().contains("Integer overflow"));
+    let result = pipeline.compile("65536 65536 *", CompilationMode::JIT);
+    assert!(result.unwrap_err().to_string().contains("Integer overflow"));
+}
+
+#[test]
+fn test_pipeline_error_spans() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    let error = pipeline.compile("1 2 +\n  3 then", CompilationMode::JIT).unwrap_err();
+    assert!(matches!(error, CompileError::ParseError(_)), "{:?}", error);
+    assert_eq!(error.span(), Some(SourceLocation { line: 2, column: 5 }));
+
+    let error = pipeline.compile(": f\n  1 frob ;", CompilationMode::JIT).unwrap_err();
+    assert!(matches!(error, CompileError::SemanticError(ForthError::UndefinedWord { .. })), "{:?}", error);
+    assert_eq!(error.span(), Some(SourceLocation { line: 2, column: 5 }));
+    assert_eq!(error.to_string(), "Semantic error: Undefined word: frob at line 2");
+
+    // Backend errors no longer know their source
+    pipeline.set_cell_width(CellWidth::Bits32);
+    let error = pipeline.compile("4294967296", CompilationMode::JIT).unwrap_err();
+    assert_eq!(error.span(), None);
+}
+
+#[test]
+fn test_pipeline_increment_folds() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Standard);
+    let ir = pipeline.optimized_ir(": k 5 1+ ;").unwrap();
+    assert!(ir.words["k"].instructions.contains(&Instruction::Literal(6)));
+    assert!(!ir.words["k"].instructions.contains(&Instruction::IncOne));
+}
+
+#[test]
+fn test_pipeline_zero_comparisons_fold() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Standard);
+    for (code, expected) in [("0 0=", -1), ("7 0=", 0), ("-2 0<", -1), ("2 0<", 0), ("2 0>", -1)] {
+        let ir = pipeline.optimized_ir(&format!(": k {} ;", code)).unwrap();
+        let body: Vec<&Instruction> = ir.words["k"]
+            .instructions
+            .iter()
+            .filter(|inst| !matches!(inst, Instruction::Label(_) | Instruction::FlushCache | Instruction::Return))
+            .collect();
+        assert_eq!(body, vec![&Instruction::Literal(expected)], "{}", code);
+    }
+}
+
+#[test]
+fn test_pipeline_jit_variable_round_trip() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile("variable counter  5 counter !  counter @", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(5));
+
+    // Words share the variable's storage with top-level code
+    let source = "variable total : bump total @ 1 + dup total ! ; bump drop bump";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(2));
+}
+
+#[test]
+fn test_pipeline_jit_value_and_to() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile("42 value speed  speed  100 to speed  speed +", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(142));
+
+    // Reads inside a word must see the updated value, even when optimizing
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Aggressive);
+    let source = "42 value speed : current speed ; 7 to speed current";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(7));
+}
+
+#[test]
+fn test_pipeline_to_errors() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    // TO only applies to values
+    assert!(pipeline.compile("5 constant five 1 to five", CompilationMode::JIT).is_err());
+    assert!(pipeline.compile("variable v 1 to v", CompilationMode::JIT).is_err());
+
+    // Nothing on the stack to store
+    assert!(pipeline.compile("0 value v to v", CompilationMode::JIT).is_err());
+    assert!(pipeline.compile("to", CompilationMode::JIT).is_err());
+}
+
+#[test]
+fn test_pipeline_jit_create_does() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let source = ": const create , does> @ ; 42 const answer 7 const week answer week +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(49));
+
+    // Instances share the DOES> code but each has its own data field
+    let source = ": counter create 0 , does> dup @ 1 + dup rot ! ; \
+                  counter a counter b a drop a drop b drop a";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(3));
+
+    // Plain CREATE pushes the data field address
+    let source = "create table 10 , 20 , 30 , table 16 + @";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(30));
+}
+
+#[test]
+fn test_pipeline_jit_allot() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    // 20 bytes round up to three cells; the `,` after them fills the fourth
+    let source = "create buf 20 allot 99 , 7 buf 16 + ! buf 16 + @ buf 24 + @ +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(106));
+
+    let source = ": buffer create 16 allot does> 8 + ; buffer b 5 b ! b @";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(5));
+
+    // Compiled code sizes data fields statically
+    for source in ["create buf 4 2 * allot", "8 allot", ": f here ; f"] {
+        assert!(pipeline.compile(source, CompilationMode::JIT).is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn test_pipeline_create_does_errors() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    assert!(pipeline.compile(": broken 1 does> @ ; 5", CompilationMode::JIT).is_err());
+    // A defining word needs a name to create
+    assert!(pipeline.compile(": const create , does> @ ; : make 1 const ; make", CompilationMode::JIT).is_err());
+    assert!(pipeline.compile("1 ,", CompilationMode::JIT).is_err());
+}
+
+#[test]
+fn test_pipeline_jit_execute() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    // A known token is called directly, through words that execute it too
+    let result = pipeline.compile(": apply execute ; 5 ' dup apply +", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(10));
+    let result = pipeline.compile(": twice ( n -- n ) ['] 2* execute ; 4 twice", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(8));
+
+    // Otherwise EXECUTE calls through the token
+    let source = ": sq dup * ; : cube dup dup * * ; variable op : run op @ execute ; \
+                  ' sq op ! 3 run ' cube op ! 3 run +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(36));
+
+    // EXECUTE takes the token's inputs as well as the token, even when which
+    // token it is depends on a branch
+    let source = ": sq dup * ; : cube dup dup * * ; : pk if ['] sq else ['] cube then execute ; \
+                  3 1 pk 3 0 pk +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(36));
+    let source = ": sq dup * ; : cube dup dup * * ; : run ( n xt -- n ) execute ; \
+                  : pk if ['] sq else ['] cube then run ; 3 1 pk 3 0 pk +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(36));
+    let source = ": sq dup * ; : run ( n xt -- n ) execute ; 3 ' sq run";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(9));
+
+    // A word has one token, wherever it is taken
+    let source = ": sq dup * ; : cube dup dup * * ; : token ['] sq ; token ' sq = token ' cube = +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(1));
+}
+
+#[test]
+fn test_pipeline_execute_errors() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    // Executing something that is not a token traps
+    let result = pipeline.compile(": run execute ; 12345 run", CompilationMode::JIT);
+    assert!(result.unwrap_err().to_string().contains("Invalid execution token"));
+
+    for source in [
+        // Ticking an undefined word
+        "' nosuch drop",
+        "'",
+        // Built-ins can only be executed where the token is known
+        "variable v ' dup v ! 1 v @ execute",
+        // Words called through a token must share a signature
+        ": inc 1+ ; : add + ; variable v ' inc v ! ' add v ! 1 2 v @ execute",
+    ] {
+        assert!(pipeline.compile(source, CompilationMode::JIT).is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn test_pipeline_jit_locals() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(": dist { x y -- } x x * y y * + ; 3 4 dist", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(25));
+
+    // Locals shadow existing words
+    let result = pipeline.compile(": twice { dup -- } dup dup + ; 5 twice", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(10));
+
+    // Locals after `|` start out as zero
+    let result = pipeline.compile(": plus0 { a | t -- } t a + ; 7 plus0", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(7));
+
+    let source = ": clamp { x -- n } x 10 > if 10 else x then ; 15 clamp 4 clamp +";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(14));
+}
+
+#[test]
+fn test_pipeline_jit_runtime_division_by_zero() {
+    // Divisor only becomes zero at runtime; the JIT must not raise a hardware trap
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    let result = pipeline.compile(": div / ; 10 0 div", CompilationMode::JIT);
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("Division by zero"));
+
+    let result = pipeline.compile(": rem mod ; 10 0 rem", CompilationMode::JIT);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_pipeline_jit_fault_in_callee_stops_callers() {
+    // The zero divisor is two calls deep; nothing after the faulting call runs
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let source = ": inner 0 / ; : middle inner 42 emit ; : outer 10 middle 43 emit ; outer 1 +";
+    let (result, output) = capture_output(|| pipeline.compile(source, CompilationMode::JIT));
+    assert!(result.unwrap_err().to_string().contains("Division by zero"));
+    assert_eq!(output, "");
+}
+
+#[test]
+fn test_pipeline_checked_arithmetic_runtime_overflow() {
+    let source = format!(": inc 1 + ; {} inc", i64::MAX);
+
+    // Wrapping by default
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile(&source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(i64::MIN));
+
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    pipeline.set_checked_arithmetic(true);
+    let result = pipeline.compile(&source, CompilationMode::JIT);
+    assert!(result.unwrap_err().to_string().contains("Integer overflow"));
+
+    let source = format!(": neg negate ; {} neg", i64::MIN);
+    assert!(pipeline.compile(&source, CompilationMode::JIT).is_err());
+    let source = format!(": sq dup * ; {} sq", 1i64 << 32);
+    assert!(pipeline.compile(&source, CompilationMode::JIT).is_err());
+
+    // The fault is reported once; later runs are unaffected
+    let result = pipeline.compile(": inc 1 + ; 41 inc", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(42));
+}
+
+#[test]
+fn test_pipeline_checked_overflow_in_loop_never_run() {
+    let source = ": f ( n -- n ) begin dup 0 > while 9223372036854775807 1 + drop 1 - repeat ; 0 f";
+    for level in [OptimizationLevel::None, OptimizationLevel::Standard, OptimizationLevel::Aggressive] {
+        let mut pipeline = CompilationPipeline::new(level);
+        pipeline.set_checked_arithmetic(true);
+        let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+        assert_eq!(result.jit_result, Some(0), "{:?}", level);
+    }
+}
+
+#[test]
+fn test_pipeline_jit_min_div_neg_one() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let source = format!(": div / ; {} -1 div", i64::MIN);
+    let result = pipeline.compile(&source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(i64::MIN));
+}
+
+#[test]
+fn test_pipeline_jit_tail_recursion_constant_stack() {
+    // Ten million frames would overflow the native stack without tail-call optimization
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let source = ": countdown dup 0 > if 1 - countdown else 1 + then ; 10000000 countdown";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(1));
+}
+
+#[test]
+fn test_pipeline_jit_tail_recursive_accumulator() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let source = ": fact-acc over 0 > if over * swap 1 - swap fact-acc else swap drop then ; 10 1 fact-acc";
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(3628800));
+}
+
+#[test]
+fn test_pipeline_aot_pass_stats() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Basic);
+    let result = pipeline.compile(": five 2 3 + ; five", CompilationMode::AOT).unwrap();
+    let stats = &result.stats;
+
+    assert!(!stats.pass_stats.passes.is_empty());
+    assert_eq!(stats.pass_stats.instructions_before, stats.instructions_before);
+    assert_eq!(stats.pass_stats.instructions_after, stats.instructions_after);
+
+    let net: i64 = stats.pass_stats.passes.iter().map(|r| r.net_change()).sum();
+    assert_eq!(net, stats.instructions_after as i64 - stats.instructions_before as i64);
+}
+
+#[test]
+fn test_pipeline_explain() {
+    let source = ": big 1 + 2 + 3 + 4 + 5 + 6 + 7 + ; : sq dup * ; 3 big sq";
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Standard);
+    let optimized = pipeline.optimized_ir(source).unwrap();
+    assert!(pipeline.optimization_notes().is_empty());
+
+    pipeline.set_explain(true);
+    assert_eq!(pipeline.optimized_ir(source).unwrap(), optimized);
+    let notes: Vec<String> = pipeline.optimization_notes().iter().map(|note| note.to_string()).collect();
+    assert!(notes.iter().any(|note| note.starts_with("inline: big: not inlined: size")), "{:?}", notes);
+    assert!(notes.iter().any(|note| note.starts_with("inline: sq: inlined")), "{:?}", notes);
+}
+
+#[test]
+fn test_pipeline_timing_breakdown() {
+    let source = ": square dup * ; : cube dup square * ; 3 cube";
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Standard);
+
+    let result = pipeline.compile(source, CompilationMode::AOT).unwrap();
+    let stats = &result.stats;
+    // The phases are timed separately, so together they fit in the total
+    assert!(stats.frontend_time_ms + stats.optimization_time_ms + stats.backend_time_ms <= result.compile_time_ms);
+    for record in &stats.pass_stats.passes {
+        assert_eq!(record.is_skipped(), record.duration.is_zero(), "{}", record.pass.name());
+    }
+    assert!(stats.pass_stats.passes.iter().any(|r| !r.is_skipped()));
+
+    // JIT mode has no optimization phase
+    let result = pipeline.compile(source, CompilationMode::JIT).unwrap();
+    assert_eq!(result.stats.optimization_time_ms, 0);
+    assert!(result.stats.pass_stats.passes.is_empty());
+}
+
+#[test]
+fn test_pipeline_emit_ir_dump() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Basic);
+    pipeline.set_emit_ir(Some(IrStage::Both));
+    let result = pipeline.compile(": inc 1 + ; 5 inc", CompilationMode::JIT).unwrap();
+
+    // The backend never runs when dumping IR
+    assert!(result.jit_result.is_none());
+
+    let dump = result.ir_dump.unwrap();
+    assert!(dump.contains("\\ IR after lowering"));
+    assert!(dump.contains("\\ IR after optimization"));
+    assert!(dump.contains(": inc"));
+    assert!(dump.contains("call inc"));
+    assert!(dump.contains("exit"));
+}
+
+#[test]
+fn test_pipeline_emit_ssa_dump() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::Basic);
+    pipeline.set_emit_ir(Some(IrStage::Ssa));
+    let result = pipeline.compile(": inc 1 + ;", CompilationMode::AOT).unwrap();
+
+    let dump = result.ir_dump.unwrap();
+    assert!(dump.contains("define inc"));
+    assert!(dump.contains("add"));
+}
+
+#[test]
+fn test_pipeline_jit_disassembly() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    pipeline.set_disassemble(true);
+    let result = pipeline.compile(": inc 1 + ; : twice 2 * ;", CompilationMode::JIT).unwrap();
+
+    let text = result.disassembly.unwrap();
+    assert!(text.contains("function inc:"));
+    assert!(text.contains("function twice:"));
+    assert!(text.contains("iadd"));
+    assert!(result.jit_result.is_none());
+}
+
+#[test]
+fn test_pipeline_jit_system() {
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+
+    // Shelling out is refused until explicitly allowed
+    let err = pipeline.compile("\"echo hello\" system", CompilationMode::JIT).unwrap_err();
+    assert!(err.to_string().contains("allow_system"), "{}", err);
+
+    pipeline.set_allow_system(true);
+    let (result, output) = capture_output(|| pipeline.compile("\"echo hello\" system", CompilationMode::JIT));
+    assert_eq!(result.unwrap().jit_result, Some(0));
+    assert_eq!(output, "hello\n");
+
+    let result = pipeline.compile("\"exit 3\" system", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(3));
+}
+
+#[test]
+fn test_pipeline_compile_file_with_include() {
+    let dir = tempfile::tempdir().unwrap();
+    let lib = dir.path().join("lib");
+    std::fs::create_dir(&lib).unwrap();
+    std::fs::write(lib.join("square.fs"), ": square dup * ;").unwrap();
+    std::fs::write(dir.path().join("main.fs"), "include lib/square.fs\nrequire lib/square.fs\n7 square").unwrap();
+
+    let mut pipeline = CompilationPipeline::new(OptimizationLevel::None);
+    let result = pipeline.compile_file(&dir.path().join("main.fs"), CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(49));
+
+    // Files on the search path can be included from source text
+    pipeline.add_include_path(&lib);
+    let result = pipeline.compile("REQUIRE \"square.fs\" 5 square", CompilationMode::JIT).unwrap();
+    assert_eq!(result.jit_result, Some(25));
+
+    let err = pipeline.compile("include nowhere.fs", CompilationMode::JIT).unwrap_err();
+    assert!(err.to_string().contains("Cannot find included file 'nowhere.fs'"), "{}", err);
+}
@@ -67,7 +67,7 @@ fn test_deeply_nested_if_statements() {
     assert!(ssa_result.is_ok(), "Failed to convert to SSA");
 
     let functions = ssa_result.unwrap();
-    assert_eq!(functions.len(), 1);
+    assert_eq!(functions.len(), 2);
     assert_eq!(functions[0].name, "deeply-nested");
 
     // Should have many basic blocks (one for each branch)
@@ -123,7 +123,7 @@ fn test_deeply_nested_word_calls() {
     assert!(ssa_result.is_ok(), "Failed to convert nested words to SSA");
 
     let functions = ssa_result.unwrap();
-    assert_eq!(functions.len(), 11);
+    assert_eq!(functions.len(), 12);
 }
 
 #[test]
@@ -155,7 +155,7 @@ fn test_complex_control_flow_graph() {
     assert!(ssa_result.is_ok(), "Failed to convert complex CFG to SSA");
 
     let functions = ssa_result.unwrap();
-    assert_eq!(functions.len(), 1);
+    assert_eq!(functions.len(), 2);
 
     // Complex CFG should have multiple blocks
     assert!(functions[0].blocks.len() >= 4);
@@ -182,7 +182,7 @@ fn test_long_computation_chain() {
     assert!(ssa_result.is_ok(), "Failed to convert long chain to SSA");
 
     let functions = ssa_result.unwrap();
-    assert_eq!(functions.len(), 1);
+    assert_eq!(functions.len(), 2);
 
     // Should have many SSA instructions
     let total_instructions: usize = functions[0]
@@ -443,7 +443,7 @@ fn test_phi_nodes_in_complex_cfg() {
     assert!(ssa_result.is_ok(), "Failed to convert to SSA with phi nodes");
 
     let functions = ssa_result.unwrap();
-    assert_eq!(functions.len(), 1);
+    assert_eq!(functions.len(), 2);
 
     // Check that we have phi nodes
     let has_phi = functions[0].blocks.iter().any(|block| {
@@ -293,6 +293,21 @@ impl ForthEngine {
             "DEPTH" => {
                 self.stack.push(self.stack.len() as i64);
             }
+            "PICK" | "ROLL" => {
+                // PICK ( xu ... x0 u -- xu ... x0 xu )
+                // ROLL ( xu xu-1 ... x0 u -- xu-1 ... x0 xu )
+                let u = self.pop()?;
+                if u < 0 || u as usize >= self.stack.len() {
+                    return Err(format!("{} index {} out of range", token, u));
+                }
+                let pos = self.stack.len() - 1 - u as usize;
+                if token == "PICK" {
+                    self.stack.push(self.stack[pos]);
+                } else {
+                    let x = self.stack.remove(pos);
+                    self.stack.push(x);
+                }
+            }
 
             // Output operations (simplified)
             "." => {